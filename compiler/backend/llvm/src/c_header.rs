@@ -0,0 +1,1111 @@
+//! Reml の公開関数（`@export`）と `extern` 宣言から C ヘッダを生成する。
+//!
+//! 型の対応は LLVM 生成と同じ `RemlType` を基準にし、値渡しの構造体は
+//! `TypeMappingContext::layout_of` から求めたレイアウトを静的アサートとして埋め込む。
+//! 文字列・スライスは `reml_ffi_bridge.h` の `reml_string_t` / `reml_span_t` を使う。
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::codegen::MirFunction;
+use crate::monomorphize::{has_type_vars, is_export_attribute};
+use crate::type_mapping::{RemlType, TypeMappingContext};
+
+/// 型変換表で表せない型を含む宣言の診断コード（reml-bindgen と共通）。
+pub const UNKNOWN_TYPE_CODE: &str = "ffi.bindgen.unknown_type";
+/// 型以外の理由で出力しなかった宣言の診断コード（reml-bindgen と共通）。
+pub const SKIPPED_CODE: &str = "ffi.bindgen.skipped";
+
+/// ヘッダ生成の設定。
+#[derive(Clone, Debug, Default)]
+pub struct CHeaderOptions {
+    /// インクルードガードのマクロ名。省略時はモジュール名から作る。
+    pub guard: Option<String>,
+    /// 先頭コメントとタプル構造体名の接頭辞に使うモジュール名。
+    pub module: Option<String>,
+}
+
+/// C 側で定義する関数（Reml の `extern "C"` 宣言）。
+#[derive(Clone, Debug, Default)]
+pub struct CHeaderExtern {
+    pub name: String,
+    /// リンク時のシンボル名。省略時は `name`。
+    pub symbol: Option<String>,
+    pub abi: Option<String>,
+    /// 引数の型トークン（MIR の `params`）。
+    pub params: Vec<String>,
+    /// 引数の名前（MIR の `param_names`）。足りない分は `arg0` などで補う。
+    pub param_names: Vec<String>,
+    pub return_type: Option<String>,
+    pub varargs: bool,
+}
+
+/// C 型と Reml 型トークンの対応（manifest の `types` 相当）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CHeaderType {
+    pub c: String,
+    pub reml: String,
+    pub qualifiers: Vec<String>,
+}
+
+/// 構造体メンバのレイアウト。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CHeaderField {
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+    pub reml: String,
+}
+
+/// ヘッダで参照する構造体のレイアウト（C の `sizeof` / `offsetof` と一致する値）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CHeaderLayout {
+    pub name: String,
+    pub size: u64,
+    pub align: u64,
+    pub fields: Vec<CHeaderField>,
+}
+
+/// ヘッダに出力しなかった宣言の診断。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CHeaderDiagnostic {
+    pub code: String,
+    pub symbol: String,
+    pub c_type: Option<String>,
+    pub reason: String,
+    pub hint: Option<String>,
+}
+
+/// 生成したヘッダと manifest 用の付随情報。
+#[derive(Clone, Debug, Default)]
+pub struct CHeader {
+    pub text: String,
+    /// ヘッダに出力した公開関数のシンボル。
+    pub exports: Vec<String>,
+    /// ヘッダに出力した `extern` 関数のシンボル。
+    pub imports: Vec<String>,
+    pub types: Vec<CHeaderType>,
+    pub layouts: Vec<CHeaderLayout>,
+    pub diagnostics: Vec<CHeaderDiagnostic>,
+}
+
+/// 公開関数と `extern` 宣言から C ヘッダを生成する。
+///
+/// `@export`（`no_mangle` を含む）属性を持つ関数だけを対象とし、型変数を含む関数や
+/// C で値渡しできない型を含む関数は診断に回して出力しない。
+pub fn generate_c_header(
+    functions: &[MirFunction],
+    externs: &[CHeaderExtern],
+    type_mapping: &TypeMappingContext,
+    options: &CHeaderOptions,
+) -> CHeader {
+    let module = options.module.as_deref().unwrap_or("reml");
+    let mut builder = HeaderBuilder::new(type_mapping, module);
+
+    let mut exports = Vec::new();
+    for function in functions {
+        if !function
+            .attributes
+            .iter()
+            .any(|attr| is_export_attribute(attr))
+        {
+            continue;
+        }
+        if let Some(decl) = builder.export_decl(function) {
+            exports.push(decl);
+        }
+    }
+    let mut imports = Vec::new();
+    for extern_decl in externs {
+        if let Some(decl) = builder.extern_decl(extern_decl) {
+            imports.push(decl);
+        }
+    }
+
+    let guard = options
+        .guard
+        .clone()
+        .unwrap_or_else(|| format!("{}_H", c_identifier(module).to_ascii_uppercase()));
+    let text = builder.render(module, &guard, &exports, &imports);
+    CHeader {
+        text,
+        exports: exports.iter().map(|decl| decl.symbol.clone()).collect(),
+        imports: imports.iter().map(|decl| decl.symbol.clone()).collect(),
+        types: builder.types,
+        layouts: builder.layouts,
+        diagnostics: builder.diagnostics,
+    }
+}
+
+/// 出力する関数宣言 1 件分。
+struct FunctionDecl {
+    symbol: String,
+    /// 元の Reml シグネチャ（コメント用）。
+    signature: String,
+    prototype: String,
+    /// 引数・戻り値ごとの所有権の注記。
+    ownership: Vec<String>,
+}
+
+/// C 型へ写した結果。
+struct CMapped {
+    c: String,
+    /// 非スカラー値の所有権（`borrowed` / `transferred`）。
+    ownership: Option<Ownership>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Ownership {
+    Borrowed,
+    BorrowedMut,
+    Transferred,
+}
+
+impl Ownership {
+    fn describe(self) -> &'static str {
+        match self {
+            Ownership::Borrowed => "borrowed（呼び出しの間だけ参照する。所有権は渡した側に残る）",
+            Ownership::BorrowedMut => {
+                "borrowed mut（呼び出しの間だけ書き換える。所有権は渡した側に残る）"
+            }
+            Ownership::Transferred => {
+                "transferred（所有権は受け取った側へ移る。参照カウント付きの値は reml_ffi_release_transferred で手放す）"
+            }
+        }
+    }
+}
+
+/// 型の出現位置。公開関数の戻り値だけは所有権が C 側へ移り、
+/// C 関数の戻り値は仕様どおり Reml 側の借用として扱う。
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Position {
+    Param,
+    ExportReturn,
+    ImportReturn,
+}
+
+/// 型の対応から外れた理由。
+struct Unsupported {
+    reason: &'static str,
+}
+
+struct HeaderBuilder<'a> {
+    type_mapping: &'a TypeMappingContext,
+    prefix: String,
+    uses_bridge: bool,
+    /// 値渡しの構造体定義（出力順）。
+    structs: Vec<StructDef>,
+    types: Vec<CHeaderType>,
+    layouts: Vec<CHeaderLayout>,
+    diagnostics: Vec<CHeaderDiagnostic>,
+    symbols: BTreeSet<String>,
+}
+
+struct StructDef {
+    name: String,
+    ty: RemlType,
+    /// 元の Reml 型トークン。レコードでは重複判定と定義前のコメントに使う。
+    reml: Option<String>,
+    /// メンバの宣言（`int64_t _0` など）。
+    members: Vec<String>,
+}
+
+impl<'a> HeaderBuilder<'a> {
+    fn new(type_mapping: &'a TypeMappingContext, module: &str) -> Self {
+        Self {
+            type_mapping,
+            prefix: c_identifier(module),
+            uses_bridge: false,
+            structs: Vec::new(),
+            types: Vec::new(),
+            layouts: Vec::new(),
+            diagnostics: Vec::new(),
+            symbols: BTreeSet::new(),
+        }
+    }
+
+    fn export_decl(&mut self, function: &MirFunction) -> Option<FunctionDecl> {
+        let symbol = function.name.clone();
+        if !is_c_identifier(&symbol) {
+            self.diagnose(SKIPPED_CODE, &symbol, None, "invalid_c_identifier", None);
+            return None;
+        }
+        if function
+            .param_type_tokens
+            .iter()
+            .chain(function.return_type_token.iter())
+            .any(|token| has_type_vars(token))
+        {
+            self.diagnose(
+                SKIPPED_CODE,
+                &symbol,
+                None,
+                "generic_function",
+                Some("型引数を具体化したラッパー関数を @export する"),
+            );
+            return None;
+        }
+        if function.boxed_abi || function.closure_env.is_some() {
+            self.diagnose(SKIPPED_CODE, &symbol, None, "internal_abi", None);
+            return None;
+        }
+
+        let tokens: Vec<String> = (0..function.params.len())
+            .map(|index| {
+                function
+                    .param_type_tokens
+                    .get(index)
+                    .cloned()
+                    .unwrap_or_else(|| format!("{:?}", function.params[index]))
+            })
+            .collect();
+        let names: Vec<String> = (0..function.params.len())
+            .map(|index| {
+                function
+                    .param_names
+                    .get(index)
+                    .filter(|name| !name.is_empty())
+                    .cloned()
+                    .unwrap_or_else(|| format!("arg{index}"))
+            })
+            .collect();
+        let ret_token = function.return_type_token.clone();
+        let ret = match (&function.ret, ret_token.as_deref()) {
+            (_, Some(token)) if is_unit_token(token) => None,
+            (Some(RemlType::Unit), _) => None,
+            (ret, _) => ret.clone(),
+        };
+        let signature = format!(
+            "fn {}({}) -> {}",
+            symbol,
+            names
+                .iter()
+                .zip(&tokens)
+                .map(|(name, token)| format!("{name}: {token}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            ret_token.as_deref().unwrap_or("()")
+        );
+        let decl = self.function_decl(
+            &symbol,
+            signature,
+            &function.params,
+            &tokens,
+            &names,
+            ret.as_ref(),
+            ret_token.as_deref(),
+            Position::ExportReturn,
+            false,
+        )?;
+        self.symbols.insert(symbol);
+        Some(decl)
+    }
+
+    fn extern_decl(&mut self, extern_decl: &CHeaderExtern) -> Option<FunctionDecl> {
+        let symbol = extern_decl
+            .symbol
+            .clone()
+            .unwrap_or_else(|| extern_decl.name.clone());
+        if let Some(abi) = extern_decl.abi.as_deref() {
+            if !abi.eq_ignore_ascii_case("c") {
+                self.diagnose(
+                    SKIPPED_CODE,
+                    &symbol,
+                    None,
+                    "unsupported_abi",
+                    Some("extern \"C\" のみ出力する"),
+                );
+                return None;
+            }
+        }
+        if !is_c_identifier(&symbol) {
+            self.diagnose(SKIPPED_CODE, &symbol, None, "invalid_c_identifier", None);
+            return None;
+        }
+        if self.symbols.contains(&symbol) {
+            self.diagnose(SKIPPED_CODE, &symbol, None, "duplicate_symbol", None);
+            return None;
+        }
+        let params: Vec<RemlType> = extern_decl
+            .params
+            .iter()
+            .map(|token| crate::integration::parse_reml_type(token))
+            .collect();
+        let names: Vec<String> = (0..params.len())
+            .map(|index| {
+                extern_decl
+                    .param_names
+                    .get(index)
+                    // `_` やタプルパターンなど、名前にならない引数は位置で呼ぶ。
+                    .filter(|name| {
+                        name.as_str() != "_"
+                            && !name.is_empty()
+                            && name
+                                .chars()
+                                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+                    })
+                    .cloned()
+                    .unwrap_or_else(|| format!("arg{index}"))
+            })
+            .collect();
+        let ret_token = extern_decl.return_type.clone();
+        let ret = ret_token
+            .as_deref()
+            .filter(|token| !is_unit_token(token))
+            .map(crate::integration::parse_reml_type);
+        let mut params_text: Vec<String> = names
+            .iter()
+            .zip(&extern_decl.params)
+            .map(|(name, token)| format!("{name}: {token}"))
+            .collect();
+        if extern_decl.varargs {
+            params_text.push("...".to_string());
+        }
+        let signature = format!(
+            "extern \"C\" fn {}({}) -> {}",
+            extern_decl.name,
+            params_text.join(", "),
+            ret_token.as_deref().unwrap_or("()")
+        );
+        let decl = self.function_decl(
+            &symbol,
+            signature,
+            &params,
+            &extern_decl.params,
+            &names,
+            ret.as_ref(),
+            ret_token.as_deref(),
+            Position::ImportReturn,
+            extern_decl.varargs,
+        )?;
+        self.symbols.insert(symbol);
+        Some(decl)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn function_decl(
+        &mut self,
+        symbol: &str,
+        signature: String,
+        params: &[RemlType],
+        tokens: &[String],
+        names: &[String],
+        ret: Option<&RemlType>,
+        ret_token: Option<&str>,
+        ret_position: Position,
+        varargs: bool,
+    ) -> Option<FunctionDecl> {
+        let mut ownership = Vec::new();
+        let mut rendered = Vec::new();
+        for ((ty, token), name) in params.iter().zip(tokens).zip(names) {
+            let mapped = match self.map_type(ty, token, Position::Param) {
+                Ok(mapped) => mapped,
+                Err(unsupported) => {
+                    self.diagnose(
+                        UNKNOWN_TYPE_CODE,
+                        symbol,
+                        Some(token.clone()),
+                        unsupported.reason,
+                        None,
+                    );
+                    return None;
+                }
+            };
+            let name = c_param_name(name);
+            if let Some(kind) = mapped.ownership {
+                ownership.push(format!("{name}: {}", kind.describe()));
+            }
+            rendered.push(format!("{} {}", mapped.c, name));
+        }
+        let ret_c = match ret {
+            Some(ty) => {
+                let token = ret_token.unwrap_or("pointer");
+                match self.map_type(ty, token, ret_position) {
+                    Ok(mapped) => {
+                        if let Some(kind) = mapped.ownership {
+                            ownership.push(format!("戻り値: {}", kind.describe()));
+                        }
+                        mapped.c
+                    }
+                    Err(unsupported) => {
+                        self.diagnose(
+                            UNKNOWN_TYPE_CODE,
+                            symbol,
+                            Some(token.to_string()),
+                            unsupported.reason,
+                            None,
+                        );
+                        return None;
+                    }
+                }
+            }
+            None => {
+                self.record_type("void", ret_token.unwrap_or("()"), &[]);
+                "void".to_string()
+            }
+        };
+        if varargs {
+            rendered.push("...".to_string());
+        }
+        if rendered.is_empty() {
+            rendered.push("void".to_string());
+        }
+        Some(FunctionDecl {
+            symbol: symbol.to_string(),
+            signature,
+            prototype: format!("{} {}({});", ret_c, symbol, rendered.join(", ")),
+            ownership,
+        })
+    }
+
+    fn map_type(
+        &mut self,
+        ty: &RemlType,
+        token: &str,
+        position: Position,
+    ) -> Result<CMapped, Unsupported> {
+        let owned = match position {
+            Position::Param | Position::ImportReturn => Ownership::Borrowed,
+            Position::ExportReturn => Ownership::Transferred,
+        };
+        let mapped = match ty {
+            RemlType::Pointer if is_record_token(token) => CMapped {
+                c: format!("{}*", self.record_struct(token)),
+                ownership: Some(owned),
+            },
+            RemlType::Bool => scalar("bool"),
+            RemlType::I32 => scalar("int32_t"),
+            RemlType::I64 => scalar("int64_t"),
+            RemlType::F64 => scalar("double"),
+            RemlType::Unit => scalar("void*"),
+            RemlType::Pointer | RemlType::Set(_) => CMapped {
+                c: "void*".to_string(),
+                ownership: Some(owned),
+            },
+            RemlType::String => {
+                self.use_bridge_struct(ty);
+                CMapped {
+                    c: "reml_string_t".to_string(),
+                    ownership: Some(owned),
+                }
+            }
+            RemlType::Slice(_) => {
+                self.use_bridge_struct(ty);
+                CMapped {
+                    c: "reml_span_t".to_string(),
+                    ownership: Some(owned),
+                }
+            }
+            RemlType::Ref { mutable, to } => {
+                let pointee = self.pointee(to)?;
+                let c = if *mutable {
+                    format!("{pointee}*")
+                } else {
+                    format!("const {pointee}*")
+                };
+                CMapped {
+                    c,
+                    ownership: Some(if *mutable {
+                        Ownership::BorrowedMut
+                    } else {
+                        Ownership::Borrowed
+                    }),
+                }
+            }
+            RemlType::RowTuple(_) => CMapped {
+                c: self.tuple_struct(ty)?,
+                ownership: None,
+            },
+            RemlType::Array { .. } => {
+                return Err(Unsupported {
+                    reason: "unsupported_array",
+                })
+            }
+            RemlType::Adt { .. } => {
+                return Err(Unsupported {
+                    reason: "unsupported_adt",
+                })
+            }
+        };
+        let mut qualifiers = Vec::new();
+        if matches!(ty, RemlType::Pointer) && !is_pointer_token(token) && !is_record_token(token) {
+            // LLVM 生成でもポインタとして渡される型（レコード・ADT・クロージャなど）。
+            qualifiers.push("opaque".to_string());
+        }
+        if let Some(kind) = mapped.ownership {
+            qualifiers.push(
+                match kind {
+                    Ownership::Borrowed | Ownership::BorrowedMut => "borrowed",
+                    Ownership::Transferred => "transferred",
+                }
+                .to_string(),
+            );
+        }
+        self.record_type(&mapped.c, token, &qualifiers);
+        Ok(mapped)
+    }
+
+    /// 参照先の C 型。配列への参照は先頭要素へのポインタとして扱う。
+    fn pointee(&mut self, ty: &RemlType) -> Result<String, Unsupported> {
+        Ok(match ty {
+            RemlType::Bool => "bool".to_string(),
+            RemlType::I32 => "int32_t".to_string(),
+            RemlType::I64 => "int64_t".to_string(),
+            RemlType::F64 => "double".to_string(),
+            RemlType::String => {
+                self.use_bridge_struct(ty);
+                "reml_string_t".to_string()
+            }
+            RemlType::Slice(_) => {
+                self.use_bridge_struct(ty);
+                "reml_span_t".to_string()
+            }
+            RemlType::Array { element, .. } => self.pointee(element)?,
+            RemlType::RowTuple(_) => self.tuple_struct(ty)?,
+            RemlType::Pointer
+            | RemlType::Set(_)
+            | RemlType::Unit
+            | RemlType::Ref { .. }
+            | RemlType::Adt { .. } => "void".to_string(),
+        })
+    }
+
+    /// `reml_ffi_bridge.h` の構造体を使う。レイアウトは初回だけ記録する。
+    fn use_bridge_struct(&mut self, ty: &RemlType) {
+        self.uses_bridge = true;
+        let (name, fields) = match ty {
+            RemlType::String => (
+                "reml_string_t",
+                [("data", RemlType::Pointer), ("length", RemlType::I64)],
+            ),
+            _ => (
+                "reml_span_t",
+                // size_t は LP64 の 8 バイト整数として扱う。
+                [("data", RemlType::Pointer), ("length", RemlType::I64)],
+            ),
+        };
+        if self.layouts.iter().any(|layout| layout.name == name) {
+            return;
+        }
+        let layout = self.type_mapping.layout_of(ty);
+        let fields = self.field_layouts(fields.iter().map(|(name, ty)| (name.to_string(), ty)));
+        self.layouts.push(CHeaderLayout {
+            name: name.to_string(),
+            size: layout.size,
+            align: layout.align,
+            fields,
+        });
+    }
+
+    /// 値渡しのタプルを構造体として定義し、その名前を返す。
+    fn tuple_struct(&mut self, ty: &RemlType) -> Result<String, Unsupported> {
+        if let Some(def) = self.structs.iter().find(|def| &def.ty == ty) {
+            return Ok(def.name.clone());
+        }
+        let RemlType::RowTuple(elements) = ty else {
+            unreachable!("tuple_struct はタプル型だけを受け取る");
+        };
+        let mut members = Vec::new();
+        for (index, element) in elements.iter().enumerate() {
+            let member = match element {
+                RemlType::Array { element, length } => {
+                    let inner = self.member_type(element)?;
+                    format!("{inner} _{index}[{length}]")
+                }
+                other => format!("{} _{index}", self.member_type(other)?),
+            };
+            members.push(member);
+        }
+        let name = format!("{}_tuple{}_t", self.prefix, self.structs.len());
+        let layout = self.type_mapping.layout_of(ty);
+        let fields = self.field_layouts(
+            elements
+                .iter()
+                .enumerate()
+                .map(|(index, element)| (format!("_{index}"), element)),
+        );
+        self.layouts.push(CHeaderLayout {
+            name: name.clone(),
+            // layout_of は末尾パディングを含まないため、C の sizeof に合わせて切り上げる。
+            size: align_to(layout.size, layout.align),
+            align: layout.align,
+            fields,
+        });
+        self.structs.push(StructDef {
+            name: name.clone(),
+            ty: ty.clone(),
+            reml: None,
+            members,
+        });
+        Ok(name)
+    }
+
+    /// レコードをランタイムの `reml_record_t` と同じ配置の構造体として定義し、その名前を返す。
+    ///
+    /// LLVM 生成ではレコードをヒープ上の値として扱うため、関数にはこの構造体へのポインタが渡る。
+    fn record_struct(&mut self, token: &str) -> String {
+        let token = token.trim();
+        if let Some(def) = self
+            .structs
+            .iter()
+            .find(|def| def.reml.as_deref() == Some(token))
+        {
+            return def.name.clone();
+        }
+        let fields = [
+            ("field_count", RemlType::I64),
+            ("values", RemlType::Pointer),
+        ];
+        let ty = RemlType::RowTuple(fields.iter().map(|(_, ty)| ty.clone()).collect());
+        let name = format!("{}_record{}_t", self.prefix, self.structs.len());
+        let layout = self.type_mapping.layout_of(&ty);
+        let field_layouts =
+            self.field_layouts(fields.iter().map(|(name, ty)| (name.to_string(), ty)));
+        self.layouts.push(CHeaderLayout {
+            name: name.clone(),
+            size: align_to(layout.size, layout.align),
+            align: layout.align,
+            fields: field_layouts,
+        });
+        self.structs.push(StructDef {
+            name: name.clone(),
+            ty,
+            reml: Some(token.to_string()),
+            members: vec![
+                "int64_t field_count".to_string(),
+                "void** values".to_string(),
+            ],
+        });
+        name
+    }
+
+    fn member_type(&mut self, ty: &RemlType) -> Result<String, Unsupported> {
+        match ty {
+            RemlType::Unit => Err(Unsupported {
+                reason: "unsupported_unit_member",
+            }),
+            RemlType::Array { .. } => Err(Unsupported {
+                reason: "unsupported_array",
+            }),
+            RemlType::Adt { .. } => Err(Unsupported {
+                reason: "unsupported_adt",
+            }),
+            RemlType::Ref { to, .. } => Ok(format!("{}*", self.pointee(to)?)),
+            RemlType::Pointer | RemlType::Set(_) => Ok("void*".to_string()),
+            RemlType::RowTuple(_) => self.tuple_struct(ty),
+            other => self.pointee(other),
+        }
+    }
+
+    /// メンバを自然なアラインメントで並べたときのオフセット。
+    fn field_layouts<'t>(
+        &self,
+        fields: impl Iterator<Item = (String, &'t RemlType)>,
+    ) -> Vec<CHeaderField> {
+        let mut offset = 0;
+        fields
+            .map(|(name, ty)| {
+                let layout = self.type_mapping.layout_of(ty);
+                offset = align_to(offset, layout.align);
+                let field = CHeaderField {
+                    name,
+                    offset,
+                    size: layout.size,
+                    reml: layout.description,
+                };
+                offset += layout.size;
+                field
+            })
+            .collect()
+    }
+
+    fn record_type(&mut self, c: &str, reml: &str, qualifiers: &[String]) {
+        let entry = CHeaderType {
+            c: c.to_string(),
+            reml: reml.trim().to_string(),
+            qualifiers: qualifiers.to_vec(),
+        };
+        if !self.types.contains(&entry) {
+            self.types.push(entry);
+        }
+    }
+
+    fn diagnose(
+        &mut self,
+        code: &str,
+        symbol: &str,
+        c_type: Option<String>,
+        reason: &str,
+        hint: Option<&str>,
+    ) {
+        self.diagnostics.push(CHeaderDiagnostic {
+            code: code.to_string(),
+            symbol: symbol.to_string(),
+            c_type,
+            reason: reason.to_string(),
+            hint: hint.map(str::to_string),
+        });
+    }
+
+    fn render(
+        &self,
+        module: &str,
+        guard: &str,
+        exports: &[FunctionDecl],
+        imports: &[FunctionDecl],
+    ) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "/* {module}: generated by reml-bindgen --export-header */"
+        );
+        let _ = writeln!(out, "#ifndef {guard}");
+        let _ = writeln!(out, "#define {guard}");
+        out.push('\n');
+        out.push_str("#include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n");
+        if self.uses_bridge {
+            out.push_str("#include \"reml_ffi_bridge.h\"\n");
+        }
+        out.push_str("\n#ifdef __cplusplus\nextern \"C\" {\n#endif\n");
+
+        for def in &self.structs {
+            out.push('\n');
+            if let Some(reml) = &def.reml {
+                let _ = writeln!(
+                    out,
+                    "/* {reml}: values にはボックス化したフィールド値がフィールド名の昇順に並ぶ */"
+                );
+            }
+            out.push_str("typedef struct {\n");
+            for member in &def.members {
+                let _ = writeln!(out, "    {member};");
+            }
+            let _ = writeln!(out, "}} {};", def.name);
+        }
+
+        if !self.layouts.is_empty() {
+            out.push_str(concat!(
+                "\n#ifndef REML_EXPORT_STATIC_ASSERT\n",
+                "#ifdef __cplusplus\n",
+                "#define REML_EXPORT_STATIC_ASSERT(cond, msg) static_assert(cond, msg)\n",
+                "#else\n",
+                "#define REML_EXPORT_STATIC_ASSERT(cond, msg) _Static_assert(cond, msg)\n",
+                "#endif\n",
+                "#endif\n\n",
+            ));
+            for layout in &self.layouts {
+                let _ = writeln!(
+                    out,
+                    "REML_EXPORT_STATIC_ASSERT(sizeof({name}) == {size}, \"{name}: size\");",
+                    name = layout.name,
+                    size = layout.size
+                );
+                for field in &layout.fields {
+                    let _ = writeln!(
+                        out,
+                        "REML_EXPORT_STATIC_ASSERT(offsetof({name}, {field}) == {offset}, \"{name}.{field}: offset\");",
+                        name = layout.name,
+                        field = field.name,
+                        offset = field.offset
+                    );
+                }
+            }
+        }
+
+        if !exports.is_empty() {
+            out.push_str("\n/* Reml が公開する関数 */\n");
+            for decl in exports {
+                render_decl(&mut out, decl);
+            }
+        }
+        if !imports.is_empty() {
+            out.push_str("\n/* Reml が呼び出す C 関数（C 側で定義する） */\n");
+            for decl in imports {
+                render_decl(&mut out, decl);
+            }
+        }
+
+        out.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n");
+        let _ = writeln!(out, "#endif /* {guard} */");
+        out
+    }
+}
+
+fn render_decl(out: &mut String, decl: &FunctionDecl) {
+    out.push('\n');
+    if decl.ownership.is_empty() {
+        let _ = writeln!(out, "/* {} */", decl.signature);
+    } else {
+        let _ = writeln!(out, "/* {}", decl.signature);
+        for note in &decl.ownership {
+            let _ = writeln!(out, " * {note}");
+        }
+        out.push_str(" */\n");
+    }
+    let _ = writeln!(out, "{}", decl.prototype);
+}
+
+fn scalar(c: &str) -> CMapped {
+    CMapped {
+        c: c.to_string(),
+        ownership: None,
+    }
+}
+
+fn align_to(value: u64, align: u64) -> u64 {
+    if align <= 1 {
+        value
+    } else {
+        value.div_ceil(align) * align
+    }
+}
+
+fn is_unit_token(token: &str) -> bool {
+    matches!(
+        token.trim().to_ascii_lowercase().as_str(),
+        "()" | "unit" | "void"
+    )
+}
+
+/// `Record<...>` の型トークン（型検査が付けるレコード型のラベル）。
+fn is_record_token(token: &str) -> bool {
+    let token = token.trim();
+    token == "Record" || (token.starts_with("Record<") && token.ends_with('>'))
+}
+
+fn is_pointer_token(token: &str) -> bool {
+    matches!(
+        token.trim().to_ascii_lowercase().as_str(),
+        "pointer" | "ptr" | "i8*"
+    )
+}
+
+fn is_c_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(ch) if ch.is_ascii_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        && !is_c_keyword(name)
+}
+
+/// 英数字と `_` 以外を `_` に置き換えた識別子。
+fn c_identifier(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|ch: char| ch.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+fn c_param_name(name: &str) -> String {
+    let ident = c_identifier(name);
+    if is_c_keyword(&ident) {
+        format!("{ident}_")
+    } else {
+        ident
+    }
+}
+
+fn is_c_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "auto"
+            | "bool"
+            | "break"
+            | "case"
+            | "char"
+            | "const"
+            | "continue"
+            | "default"
+            | "do"
+            | "double"
+            | "else"
+            | "enum"
+            | "extern"
+            | "float"
+            | "for"
+            | "goto"
+            | "if"
+            | "inline"
+            | "int"
+            | "long"
+            | "register"
+            | "restrict"
+            | "return"
+            | "short"
+            | "signed"
+            | "sizeof"
+            | "static"
+            | "struct"
+            | "switch"
+            | "typedef"
+            | "union"
+            | "unsigned"
+            | "void"
+            | "volatile"
+            | "while"
+            | "class"
+            | "delete"
+            | "new"
+            | "this"
+            | "template"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target_machine::DataLayoutSpec;
+
+    fn mapping() -> TypeMappingContext {
+        TypeMappingContext::new(DataLayoutSpec::system_v())
+    }
+
+    fn exported(name: &str, params: &[(&str, &str)], ret: &str) -> MirFunction {
+        let mut function = MirFunction::new(name, "ccc").with_attribute("export");
+        for (param, token) in params {
+            function =
+                function.with_named_param(*param, crate::integration::parse_reml_type(token));
+            function.param_type_tokens.push(token.to_string());
+        }
+        function = function.with_return(crate::integration::parse_reml_type(ret));
+        function.return_type_token = Some(ret.to_string());
+        function
+    }
+
+    #[test]
+    fn exported_functions_become_prototypes() {
+        let functions = vec![
+            exported("add", &[("a", "i64"), ("b", "i64")], "i64"),
+            exported("greet", &[("name", "Str")], "Str"),
+            exported("reset", &[], "()"),
+            exported("scale", &[("x", "Float")], "Float"),
+            MirFunction::new("internal", "ccc"),
+        ];
+        let header = generate_c_header(
+            &functions,
+            &[],
+            &mapping(),
+            &CHeaderOptions {
+                guard: None,
+                module: Some("demo.app".into()),
+            },
+        );
+        assert!(header.text.contains("#ifndef DEMO_APP_H"));
+        assert!(header.text.contains("int64_t add(int64_t a, int64_t b);"));
+        assert!(header
+            .text
+            .contains("reml_string_t greet(reml_string_t name);"));
+        assert!(header.text.contains("void reset(void);"));
+        assert!(header.text.contains("double scale(double x);"));
+        assert!(header.text.contains("#include \"reml_ffi_bridge.h\""));
+        assert!(header.text.contains(" * name: borrowed"));
+        assert!(header.text.contains(" * 戻り値: transferred"));
+        assert!(header
+            .text
+            .contains("REML_EXPORT_STATIC_ASSERT(offsetof(reml_string_t, length) == 8"));
+        assert!(!header.text.contains("internal"));
+        assert_eq!(header.exports, vec!["add", "greet", "reset", "scale"]);
+        assert_eq!(header.layouts[0].name, "reml_string_t");
+        assert_eq!(header.layouts[0].size, 16);
+    }
+
+    #[test]
+    fn tuples_are_emitted_with_padded_layout() {
+        let tuple = RemlType::RowTuple(vec![RemlType::I64, RemlType::Bool]);
+        let function = MirFunction::new("pair", "ccc")
+            .with_attribute("export")
+            .with_named_param("value", tuple.clone())
+            .with_return(RemlType::I64);
+        let header = generate_c_header(&[function], &[], &mapping(), &CHeaderOptions::default());
+        assert!(header
+            .text
+            .contains("    int64_t _0;\n    bool _1;\n} reml_tuple0_t;"));
+        assert!(header.text.contains("int64_t pair(reml_tuple0_t value);"));
+        let layout = &header.layouts[0];
+        assert_eq!((layout.size, layout.align), (16, 8));
+        assert_eq!(layout.fields[1].offset, 8);
+    }
+
+    #[test]
+    fn records_are_emitted_as_runtime_record_structs() {
+        let functions = vec![
+            exported("getx", &[("p", "Record<i64, i64>")], "i64"),
+            exported("swap", &[("p", "Record<i64, i64>")], "Record<i64, i64>"),
+            exported("scale", &[("size", "Record<i64, Float>")], "Float"),
+        ];
+        let header = generate_c_header(&functions, &[], &mapping(), &CHeaderOptions::default());
+        assert!(header.diagnostics.is_empty(), "{:?}", header.diagnostics);
+        assert!(header.text.contains(
+            "/* Record<i64, i64>: values にはボックス化したフィールド値がフィールド名の昇順に並ぶ */\ntypedef struct {\n    int64_t field_count;\n    void** values;\n} reml_record0_t;"
+        ));
+        assert!(header.text.contains("int64_t getx(reml_record0_t* p);"));
+        assert!(header
+            .text
+            .contains("reml_record0_t* swap(reml_record0_t* p);"));
+        assert!(header.text.contains("double scale(reml_record1_t* size);"));
+        assert!(header
+            .text
+            .contains("REML_EXPORT_STATIC_ASSERT(offsetof(reml_record0_t, values) == 8"));
+        let layout = &header.layouts[0];
+        assert_eq!(
+            (layout.name.as_str(), layout.size, layout.align),
+            ("reml_record0_t", 16, 8)
+        );
+        let record = header
+            .types
+            .iter()
+            .find(|entry| entry.reml == "Record<i64, i64>")
+            .expect("record type");
+        assert!(!record.qualifiers.contains(&"opaque".to_string()));
+    }
+
+    #[test]
+    fn unsupported_exports_are_reported() {
+        let functions = vec![
+            exported("identity", &[("x", "'a")], "'a"),
+            exported("sum", &[("items", "[i64; 4]")], "i64"),
+        ];
+        let externs = vec![
+            CHeaderExtern {
+                name: "puts".into(),
+                abi: Some("C".into()),
+                params: vec!["&str".into()],
+                return_type: Some("i32".into()),
+                ..CHeaderExtern::default()
+            },
+            CHeaderExtern {
+                name: "clamp".into(),
+                abi: Some("C".into()),
+                params: vec!["Float".into(), "Float".into(), "Float".into()],
+                param_names: vec!["value".into(), "_".into(), "double".into()],
+                return_type: Some("Float".into()),
+                ..CHeaderExtern::default()
+            },
+        ];
+        let header =
+            generate_c_header(&functions, &externs, &mapping(), &CHeaderOptions::default());
+        let reasons: Vec<_> = header
+            .diagnostics
+            .iter()
+            .map(|diag| (diag.code.as_str(), diag.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (SKIPPED_CODE, "generic_function"),
+                (UNKNOWN_TYPE_CODE, "unsupported_array")
+            ]
+        );
+        assert!(header.text.contains("int32_t puts(reml_string_t arg0);"));
+        assert!(header
+            .text
+            .contains("double clamp(double value, double arg1, double double_);"));
+        assert!(header
+            .text
+            .contains("extern \"C\" fn clamp(value: Float, arg1: Float, double: Float) -> Float"));
+        assert_eq!(header.imports, vec!["puts", "clamp"]);
+    }
+}
@@ -0,0 +1,719 @@
+//! MIR 段階のクロージャ変換。
+//!
+//! ラムダ式ごとに「環境ポインタ + ラムダ引数」を取る関数を持ち上げ、
+//! 親関数側の `MirExprKind::Lambda` へ持ち上げ先シンボルを記録する。
+//! 環境はキャプチャ値をキャプチャ順に並べた Record（`reml_record_from`）で、
+//! 持ち上げた関数はプロローグで `reml_record_get` から値を取り出す。
+//! 名前付き関数を値として渡す箇所は環境なしのラムダへ η 展開し、
+//! 関数値の呼び出しを `reml_closure_code_ptr` 経由の間接呼び出しに統一する。
+//!
+//! キャプチャは生成時点の値をコピーする。可変キャプチャのうち関数内で再代入されないものは
+//! コピーでも意味が変わらないため `mutable = false` に正規化する。再代入される可変キャプチャは
+//! 書き込みが共有されないため、コード生成が縮退として報告する（参照セルによる共有は未対応）。
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::codegen::{
+    MirExpr, MirExprId, MirExprKind, MirFunction, MirLambdaCapture, MirLambdaParam, MirPattern,
+    MirPatternKind, MirStmtKind,
+};
+use crate::type_mapping::RemlType;
+
+/// 持ち上げた関数が第 1 引数で受け取る環境ポインタの名前。
+pub const CLOSURE_ENV_PARAM: &str = "__env";
+
+/// 持ち上げた関数のシンボル名を組み立てる。
+fn lifted_symbol(parent: &str, expr_id: MirExprId) -> String {
+    let parent = parent.trim_start_matches('@');
+    let parent: String = parent
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+        .collect();
+    format!("@reml_closure_{parent}_{expr_id}")
+}
+
+/// `(i64, Str) -> Bool` 形式の関数型トークンを引数型と戻り値型へ分解する。
+pub fn parse_function_type_token(token: &str) -> Option<(Vec<String>, String)> {
+    let trimmed = token.trim();
+    if !trimmed.starts_with('(') {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut close = None;
+    let mut prev = None;
+    for (index, ch) in trimmed.char_indices() {
+        let after_dash = prev == Some('-');
+        prev = Some(ch);
+        match ch {
+            '(' | '[' | '<' | '{' => depth += 1,
+            // `->` の `>` は括弧として数えない。
+            '>' if after_dash => {}
+            ')' | ']' | '>' | '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    close = Some(index);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close?;
+    let ret = trimmed[close + 1..].trim().strip_prefix("->")?.trim();
+    if ret.is_empty() {
+        return None;
+    }
+    let params = split_top_level(&trimmed[1..close]);
+    Some((params, ret.to_string()))
+}
+
+fn split_top_level(source: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut current = String::new();
+    let mut chars = source.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '(' | '[' | '<' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            // `->` の `>` は括弧として数えない。
+            '>' if !current.ends_with('-') => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(ch);
+        if chars.peek().is_none() && !current.trim().is_empty() {
+            items.push(current.trim().to_string());
+            current.clear();
+        }
+    }
+    items
+}
+
+/// MIR の型トークンを持ち上げ関数のシグネチャ用 `RemlType` へ写す。
+/// 関数型やヒープ値はクロージャ/オブジェクトへのポインタとして扱う。
+pub fn reml_type_from_token(token: &str) -> RemlType {
+    match token.trim().to_ascii_lowercase().as_str() {
+        "bool" => RemlType::Bool,
+        "i32" | "int32" => RemlType::I32,
+        "i64" | "int64" | "int" => RemlType::I64,
+        "f64" | "double" | "float" => RemlType::F64,
+        "str" | "string" => RemlType::String,
+        "unit" | "()" => RemlType::Unit,
+        _ => RemlType::Pointer,
+    }
+}
+
+/// 関数内の全ラムダを持ち上げ、書き換えた親関数と持ち上げた関数を返す。
+///
+/// 既にシンボルが割り当て済みのラムダは対象外とするため、複数回適用しても結果は変わらない。
+/// 別のラムダ本体に含まれるラムダは、持ち上げた関数側の変換で処理する。
+pub fn convert_closures(mir: &MirFunction) -> (MirFunction, Vec<MirFunction>) {
+    let mut function = mir.clone();
+    if function.exprs.is_empty() {
+        return (function, Vec::new());
+    }
+    eta_expand_function_values(&mut function);
+
+    let expr_map: HashMap<MirExprId, &MirExpr> =
+        function.exprs.iter().map(|expr| (expr.id, expr)).collect();
+    let mut nested = HashSet::new();
+    for expr in &function.exprs {
+        if let MirExprKind::Lambda { body, .. } = &expr.kind {
+            let mut reachable = BTreeSet::new();
+            collect_reachable(*body, &expr_map, &mut reachable);
+            nested.extend(reachable);
+        }
+    }
+
+    // 効果ハンドラの本体・節は Runtime から呼ばれるため、ボックス化 ABI で持ち上げる。
+    let mut handler_lambdas = HashSet::new();
+    for expr in &function.exprs {
+        if let MirExprKind::Handle {
+            target,
+            operations,
+            return_clause,
+            lowered: true,
+            ..
+        } = &expr.kind
+        {
+            handler_lambdas.insert(*target);
+            handler_lambdas.extend(operations.iter().map(|operation| operation.body));
+            handler_lambdas.extend(return_clause.iter().map(|clause| clause.body));
+        }
+    }
+
+    let assigned = assigned_names(&function, &expr_map);
+    let mut lifted = Vec::new();
+    let mut symbols = HashMap::new();
+    let mut capture_types = HashMap::new();
+    for expr in &function.exprs {
+        let MirExprKind::Lambda {
+            params,
+            body,
+            captures,
+            symbol: None,
+        } = &expr.kind
+        else {
+            continue;
+        };
+        if nested.contains(&expr.id) {
+            continue;
+        }
+        let symbol = lifted_symbol(&function.name, expr.id);
+        let mut reachable = BTreeSet::new();
+        collect_reachable(*body, &expr_map, &mut reachable);
+        let captures: Vec<MirLambdaCapture> = captures
+            .iter()
+            .map(|capture| MirLambdaCapture {
+                ty: if capture.ty.is_empty() {
+                    infer_capture_type(&capture.name, &reachable, &expr_map)
+                } else {
+                    capture.ty.clone()
+                },
+                mutable: capture.mutable && assigned.contains(&capture.name),
+                ..capture.clone()
+            })
+            .collect();
+        let mut function_value = lift_lambda(
+            &function, &symbol, expr, params, *body, &captures, &reachable,
+        );
+        function_value.boxed_abi = handler_lambdas.contains(&expr.id);
+        lifted.push(function_value);
+        symbols.insert(expr.id, symbol);
+        capture_types.insert(expr.id, captures);
+    }
+
+    for expr in &mut function.exprs {
+        if let MirExprKind::Lambda {
+            symbol, captures, ..
+        } = &mut expr.kind
+        {
+            if let Some(lifted_symbol) = symbols.remove(&expr.id) {
+                *symbol = Some(lifted_symbol);
+            }
+            if let Some(typed) = capture_types.remove(&expr.id) {
+                *captures = typed;
+            }
+        }
+    }
+    (function, lifted)
+}
+
+fn lift_lambda(
+    parent: &MirFunction,
+    symbol: &str,
+    lambda: &MirExpr,
+    params: &[MirLambdaParam],
+    body: MirExprId,
+    captures: &[MirLambdaCapture],
+    reachable: &BTreeSet<MirExprId>,
+) -> MirFunction {
+    let mut lifted = MirFunction::new(symbol, parent.calling_conv.clone())
+        .with_named_param(CLOSURE_ENV_PARAM, RemlType::Pointer);
+    for param in params {
+        lifted = lifted.with_named_param(param.name.clone(), reml_type_from_token(&param.ty));
+    }
+    let ret = parse_function_type_token(&lambda.ty)
+        .map(|(_, ret)| ret)
+        .or_else(|| {
+            parent
+                .exprs
+                .iter()
+                .find(|expr| expr.id == body)
+                .map(|expr| expr.ty.clone())
+        })
+        .map(|token| reml_type_from_token(&token))
+        .unwrap_or(RemlType::Pointer);
+    lifted = lifted.with_return(ret);
+    lifted.closure_env = Some(captures.to_vec());
+    let exprs = parent
+        .exprs
+        .iter()
+        .filter(|expr| reachable.contains(&expr.id))
+        .cloned()
+        .collect();
+    lifted = lifted.with_exprs(Some(body), exprs);
+    if let Some(span) = lambda.span {
+        lifted = lifted.with_span(span);
+    }
+    lifted
+}
+
+/// 代入文の左辺（フィールド・添字アクセスは根の識別子）に現れる名前を集める。
+fn assigned_names(
+    function: &MirFunction,
+    expr_map: &HashMap<MirExprId, &MirExpr>,
+) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for expr in &function.exprs {
+        let MirExprKind::Block { statements, .. } = &expr.kind else {
+            continue;
+        };
+        for stmt in statements {
+            let MirStmtKind::Assign { target, .. } = &stmt.kind else {
+                continue;
+            };
+            let mut current = expr_map.get(target);
+            while let Some(expr) = current {
+                match &expr.kind {
+                    MirExprKind::Identifier { summary } => {
+                        names.insert(identifier_name(summary));
+                        break;
+                    }
+                    MirExprKind::FieldAccess { target, .. } | MirExprKind::Index { target, .. } => {
+                        current = expr_map.get(target);
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+    names
+}
+
+/// キャプチャ名を参照する識別子式の型からスロットの型を推定する。
+fn infer_capture_type(
+    name: &str,
+    reachable: &BTreeSet<MirExprId>,
+    expr_map: &HashMap<MirExprId, &MirExpr>,
+) -> String {
+    reachable
+        .iter()
+        .filter_map(|id| expr_map.get(id))
+        .find_map(|expr| match &expr.kind {
+            MirExprKind::Identifier { summary } if identifier_name(summary) == name => {
+                Some(expr.ty.clone())
+            }
+            _ => None,
+        })
+        .unwrap_or_else(|| "ptr".into())
+}
+
+/// 識別子式のサマリ（`{"name": ...}` または素の名前）から名前を取り出す。
+pub fn identifier_name(summary: &str) -> String {
+    let trimmed = summary.trim();
+    if trimmed.starts_with('{') {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(trimmed) {
+            if let Some(name) = value.get("name").and_then(|v| v.as_str()) {
+                return name.to_string();
+            }
+        }
+    }
+    trimmed.to_string()
+}
+
+/// 呼び出し位置以外に現れる名前付き関数の参照を、環境なしのラムダへ η 展開する。
+fn eta_expand_function_values(function: &mut MirFunction) {
+    let mut locals: HashSet<String> = function.param_names.iter().cloned().collect();
+    let mut callees = HashSet::new();
+    for expr in &function.exprs {
+        match &expr.kind {
+            MirExprKind::Call { callee, .. } => {
+                callees.insert(*callee);
+            }
+            MirExprKind::Lambda { params, .. } => {
+                locals.extend(params.iter().map(|param| param.name.clone()));
+            }
+            MirExprKind::Block { statements, .. } => {
+                for stmt in statements {
+                    if let MirStmtKind::Let { pattern, .. } = &stmt.kind {
+                        collect_pattern_names(pattern, &mut locals);
+                    }
+                }
+            }
+            MirExprKind::Match { arms, .. } => {
+                for arm in arms {
+                    collect_pattern_names(&arm.pattern, &mut locals);
+                    if let Some(alias) = &arm.alias {
+                        locals.insert(alias.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(captures) = &function.closure_env {
+        locals.extend(captures.iter().map(|capture| capture.name.clone()));
+    }
+
+    let mut next_id = function
+        .exprs
+        .iter()
+        .map(|expr| expr.id + 1)
+        .max()
+        .unwrap_or(0);
+    let mut synthesized = Vec::new();
+    for expr in &mut function.exprs {
+        let MirExprKind::Identifier { summary } = &expr.kind else {
+            continue;
+        };
+        let name = identifier_name(summary);
+        if callees.contains(&expr.id) || locals.contains(&name) {
+            continue;
+        }
+        let Some((param_types, ret)) = parse_function_type_token(&expr.ty) else {
+            continue;
+        };
+        let callee_id = next_id;
+        synthesized.push(MirExpr {
+            id: callee_id,
+            ty: expr.ty.clone(),
+            kind: expr.kind.clone(),
+            span: expr.span,
+        });
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+        for (index, ty) in param_types.iter().enumerate() {
+            let arg_name = format!("__arg{index}");
+            let arg_id = callee_id + 1 + index;
+            synthesized.push(MirExpr {
+                id: arg_id,
+                ty: ty.clone(),
+                kind: MirExprKind::Identifier {
+                    summary: arg_name.clone(),
+                },
+                span: expr.span,
+            });
+            params.push(MirLambdaParam {
+                name: arg_name,
+                ty: ty.clone(),
+            });
+            args.push(arg_id);
+        }
+        let call_id = callee_id + 1 + param_types.len();
+        synthesized.push(MirExpr {
+            id: call_id,
+            ty: ret,
+            kind: MirExprKind::Call {
+                callee: callee_id,
+                args,
+            },
+            span: expr.span,
+        });
+        next_id = call_id + 1;
+        expr.kind = MirExprKind::Lambda {
+            params,
+            body: call_id,
+            captures: Vec::new(),
+            symbol: None,
+        };
+    }
+    function.exprs.extend(synthesized);
+}
+
+pub(crate) fn collect_pattern_names(pattern: &MirPattern, names: &mut HashSet<String>) {
+    match &pattern.kind {
+        MirPatternKind::Var { name } => {
+            names.insert(name.clone());
+        }
+        MirPatternKind::Binding { name, pattern, .. } => {
+            names.insert(name.clone());
+            collect_pattern_names(pattern, names);
+        }
+        MirPatternKind::Tuple { elements } => {
+            for element in elements {
+                collect_pattern_names(element, names);
+            }
+        }
+        MirPatternKind::Record { fields, .. } => {
+            for field in fields {
+                match &field.value {
+                    Some(value) => collect_pattern_names(value, names),
+                    None => {
+                        names.insert(field.key.clone());
+                    }
+                }
+            }
+        }
+        MirPatternKind::Constructor { args, .. } => {
+            for arg in args {
+                collect_pattern_names(arg, names);
+            }
+        }
+        MirPatternKind::Or { variants } => {
+            for variant in variants {
+                collect_pattern_names(variant, names);
+            }
+        }
+        MirPatternKind::Slice(slice) => {
+            for item in slice.head.iter().chain(slice.tail.iter()) {
+                collect_pattern_names(item, names);
+            }
+            if let Some(binding) = slice.rest.as_ref().and_then(|rest| rest.binding.as_ref()) {
+                names.insert(binding.clone());
+            }
+        }
+        MirPatternKind::Active(active) => {
+            if let Some(binding) = &active.input_binding {
+                names.insert(binding.clone());
+            }
+            if let Some(argument) = &active.argument {
+                collect_pattern_names(argument, names);
+            }
+        }
+        MirPatternKind::Range { .. }
+        | MirPatternKind::Wildcard
+        | MirPatternKind::Literal { .. }
+        | MirPatternKind::Regex { .. } => {}
+    }
+}
+
+/// `root` から辿れる式 ID をすべて集める。
+pub(crate) fn collect_reachable(
+    root: MirExprId,
+    expr_map: &HashMap<MirExprId, &MirExpr>,
+    out: &mut BTreeSet<MirExprId>,
+) {
+    if !out.insert(root) {
+        return;
+    }
+    let Some(expr) = expr_map.get(&root) else {
+        return;
+    };
+    let mut children = Vec::new();
+    match &expr.kind {
+        MirExprKind::Literal { .. } | MirExprKind::Identifier { .. } | MirExprKind::Unknown => {}
+        MirExprKind::FieldAccess { target, .. } | MirExprKind::Rec { target, .. } => {
+            children.push(*target)
+        }
+        MirExprKind::Index { target, index } => children.extend([*target, *index]),
+        MirExprKind::Call { callee, args } => {
+            children.push(*callee);
+            children.extend(args.iter().copied());
+        }
+        MirExprKind::Lambda { body, .. } => children.push(*body),
+        MirExprKind::Block {
+            statements,
+            tail,
+            defers,
+            defer_lifo,
+        } => {
+            for stmt in statements {
+                match &stmt.kind {
+                    MirStmtKind::Let { value, .. } => children.push(*value),
+                    MirStmtKind::Expr { expr } | MirStmtKind::Defer { expr } => {
+                        children.push(*expr)
+                    }
+                    MirStmtKind::Assign { target, value } => children.extend([*target, *value]),
+                }
+            }
+            children.extend(tail.iter().copied());
+            children.extend(defers.iter().copied());
+            children.extend(defer_lifo.iter().copied());
+        }
+        MirExprKind::Return { value } => children.extend(value.iter().copied()),
+        MirExprKind::Panic { argument } => children.extend(argument.iter().copied()),
+        MirExprKind::Propagate { expr } => children.push(*expr),
+        MirExprKind::Binary { left, right, .. } => children.extend([*left, *right]),
+        MirExprKind::Match { target, arms, .. } => {
+            children.push(*target);
+            for arm in arms {
+                children.extend(arm.guard.iter().copied());
+                children.push(arm.body);
+            }
+        }
+        MirExprKind::IfElse {
+            condition,
+            then_branch,
+            else_branch,
+        } => children.extend([*condition, *then_branch, *else_branch]),
+        MirExprKind::PerformCall { argument, .. } => children.push(*argument),
+        MirExprKind::Handle {
+            target,
+            operations,
+            return_clause,
+            ..
+        } => {
+            children.push(*target);
+            children.extend(operations.iter().map(|operation| operation.body));
+            children.extend(return_clause.iter().map(|clause| clause.body));
+        }
+        MirExprKind::Resume {
+            continuation,
+            value,
+        } => children.extend([*continuation, *value]),
+        MirExprKind::EffectBlock { body } | MirExprKind::Unsafe { body } => children.push(*body),
+        MirExprKind::InlineAsm {
+            outputs, inputs, ..
+        } => {
+            children.extend(outputs.iter().map(|output| output.target));
+            children.extend(inputs.iter().map(|input| input.expr));
+        }
+        MirExprKind::LlvmIr { inputs, .. } => children.extend(inputs.iter().copied()),
+    }
+    for child in children {
+        collect_reachable(child, expr_map, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::MirStmt;
+
+    fn ident(id: MirExprId, name: &str, ty: &str) -> MirExpr {
+        MirExpr {
+            id,
+            ty: ty.into(),
+            kind: MirExprKind::Identifier {
+                summary: format!("{{\"name\":\"{name}\"}}"),
+            },
+            span: None,
+        }
+    }
+
+    #[test]
+    fn function_type_tokens_split_nested_params() {
+        assert_eq!(
+            parse_function_type_token("((i64) -> i64, i64) -> i64"),
+            Some((vec!["(i64) -> i64".into(), "i64".into()], "i64".into()))
+        );
+        assert_eq!(
+            parse_function_type_token("() -> Bool"),
+            Some((Vec::new(), "Bool".into()))
+        );
+        assert_eq!(parse_function_type_token("(i64, i64)"), None);
+        assert_eq!(parse_function_type_token("i64"), None);
+    }
+
+    #[test]
+    fn only_reassigned_captures_stay_mutable() {
+        let capture = |name: &str| MirLambdaCapture {
+            name: name.into(),
+            mutable: true,
+            ty: "i64".into(),
+        };
+        let exprs = vec![
+            ident(0, "n", "i64"),
+            MirExpr {
+                id: 1,
+                ty: "() -> i64".into(),
+                kind: MirExprKind::Lambda {
+                    params: Vec::new(),
+                    body: 0,
+                    captures: vec![capture("n"), capture("m")],
+                    symbol: None,
+                },
+                span: None,
+            },
+            ident(2, "n", "i64"),
+            ident(3, "m", "i64"),
+            MirExpr {
+                id: 4,
+                ty: "unit".into(),
+                kind: MirExprKind::Block {
+                    statements: vec![MirStmt {
+                        kind: MirStmtKind::Assign {
+                            target: 2,
+                            value: 3,
+                        },
+                    }],
+                    tail: Some(1),
+                    defers: Vec::new(),
+                    defer_lifo: Vec::new(),
+                },
+                span: None,
+            },
+        ];
+        let mir = MirFunction::new("@main", "ccc").with_exprs(Some(4), exprs);
+
+        let (_, lifted) = convert_closures(&mir);
+        let env = lifted[0].closure_env.as_ref().expect("closure env");
+        let mutable: Vec<_> = env
+            .iter()
+            .map(|capture| (capture.name.as_str(), capture.mutable))
+            .collect();
+        assert_eq!(mutable, vec![("n", true), ("m", false)]);
+    }
+
+    #[test]
+    fn lifts_capturing_lambda_and_eta_expands_function_values() {
+        let exprs = vec![
+            ident(0, "y", "i64"),
+            ident(1, "k", "i64"),
+            MirExpr {
+                id: 2,
+                ty: "i64".into(),
+                kind: MirExprKind::Binary {
+                    operator: "+".into(),
+                    left: 0,
+                    right: 1,
+                },
+                span: None,
+            },
+            MirExpr {
+                id: 3,
+                ty: "(i64) -> i64".into(),
+                kind: MirExprKind::Lambda {
+                    params: vec![MirLambdaParam {
+                        name: "y".into(),
+                        ty: "i64".into(),
+                    }],
+                    body: 2,
+                    captures: vec![MirLambdaCapture {
+                        name: "k".into(),
+                        mutable: false,
+                        ty: String::new(),
+                    }],
+                    symbol: None,
+                },
+                span: None,
+            },
+            ident(4, "apply", "((i64) -> i64, i64) -> i64"),
+            ident(5, "double", "(i64) -> i64"),
+            ident(6, "k", "i64"),
+            MirExpr {
+                id: 7,
+                ty: "i64".into(),
+                kind: MirExprKind::Call {
+                    callee: 4,
+                    args: vec![5, 6],
+                },
+                span: None,
+            },
+        ];
+        let mir = MirFunction::new("@main", "ccc")
+            .with_named_param("k", RemlType::I64)
+            .with_exprs(Some(7), exprs);
+
+        let (converted, lifted) = convert_closures(&mir);
+        assert_eq!(lifted.len(), 2, "ラムダと η 展開した関数値を持ち上げること");
+
+        let lambda = &lifted[0];
+        assert_eq!(lambda.name, "@reml_closure_main_3");
+        assert_eq!(lambda.param_names, vec!["__env", "y"]);
+        assert_eq!(lambda.params, vec![RemlType::Pointer, RemlType::I64]);
+        assert_eq!(lambda.ret, Some(RemlType::I64));
+        let env = lambda.closure_env.as_ref().expect("closure env");
+        assert_eq!(env.len(), 1);
+        assert_eq!((env[0].name.as_str(), env[0].ty.as_str()), ("k", "i64"));
+        assert!(lambda.exprs.iter().all(|expr| expr.id <= 2));
+
+        let thunk = &lifted[1];
+        assert_eq!(thunk.name, "@reml_closure_main_5");
+        assert_eq!(thunk.param_names, vec!["__env", "__arg0"]);
+        assert!(thunk.closure_env.as_ref().is_some_and(Vec::is_empty));
+
+        let symbol_of = |id| {
+            converted
+                .exprs
+                .iter()
+                .find(|expr| expr.id == id)
+                .and_then(|expr| match &expr.kind {
+                    MirExprKind::Lambda { symbol, .. } => symbol.clone(),
+                    _ => None,
+                })
+        };
+        assert_eq!(symbol_of(3).as_deref(), Some("@reml_closure_main_3"));
+        assert_eq!(symbol_of(5).as_deref(), Some("@reml_closure_main_5"));
+
+        let (again, relifted) = convert_closures(&converted);
+        assert!(relifted.is_empty(), "変換済みの関数は再度持ち上げないこと");
+        assert_eq!(again.exprs.len(), converted.exprs.len());
+    }
+}
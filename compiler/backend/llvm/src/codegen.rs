@@ -1,23 +1,54 @@
+use std::cell::RefCell;
 use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use std::hash::{Hash, Hasher};
+use std::rc::Rc;
 
 use crate::bridge_metadata::BridgeMetadataContext;
+use crate::closure_conversion::{
+    convert_closures, identifier_name, parse_function_type_token, CLOSURE_ENV_PARAM,
+};
+use crate::debug_info::{DebugInfoBuilder, DebugSourceMap};
+use crate::effect_lowering::lower_effect_handlers;
 use crate::ffi_lowering::{FfiCallSignature, FfiLowering, LoweredFfiCall};
 use crate::intrinsics::{
     parse_intrinsic_attribute, resolve_intrinsic_use, IntrinsicSignature, IntrinsicUse,
 };
+use crate::match_tree::{
+    compile_match, describe_occurrence, describe_test, Access, DecisionNode, DecisionTree, NodeId,
+    Occurrence, PatternLiteral, SwitchKind, Test,
+};
+use crate::tail_calls::{
+    plan_tail_call, requires_tail_call_guarantee, FunctionPrototype, MirTailCall, TailCallFailure,
+    TailCallKind,
+};
 use crate::target_diagnostics::TargetDiagnosticContext;
 use crate::target_machine::{TargetMachine, WindowsToolchainConfig};
 use crate::type_mapping::{RemlType, TypeLayout, TypeMappingContext};
 use crate::unstable::{
     native_unstable_enabled, parse_unstable_attribute, UnstableStatus, UnstableUse,
 };
+use crate::verify::Diagnostic;
 
 pub type MirExprId = usize;
 pub type MirBlockLabel = String;
 
+// 縮退箇所の診断コード。
+const FALLBACK_EXPR_MISSING: &str = "codegen.fallback.expr_missing";
+const FALLBACK_EXPR_UNSUPPORTED: &str = "codegen.fallback.expr_unsupported";
+const FALLBACK_BINARY_OPERATOR: &str = "codegen.fallback.binary_operator";
+const FALLBACK_GUARD_OPERATOR: &str = "codegen.fallback.guard_operator";
+const FALLBACK_ASSIGN_TARGET: &str = "codegen.fallback.assign_target";
+const FALLBACK_DEFER_STATEMENT: &str = "codegen.fallback.defer_statement";
+const FALLBACK_ARRAY_ELEMENT: &str = "codegen.fallback.array_element";
+const FALLBACK_RECORD_FIELD: &str = "codegen.fallback.record_field";
+const FALLBACK_SET_ELEMENT: &str = "codegen.fallback.set_element";
+const FALLBACK_LITERAL: &str = "codegen.fallback.literal";
+const FALLBACK_LAMBDA: &str = "codegen.fallback.lambda";
+const FALLBACK_MUTABLE_CAPTURE: &str = "codegen.fallback.mutable_capture";
+const FALLBACK_LLVM_IR_PLACEHOLDER: &str = "codegen.fallback.llvm_ir_placeholder";
+
 // LLVM 風 IR で使用する暫定 intrinsic（将来の実 LLVM IR/Runtime Bridge へ移行するための境界）。
 const INTRINSIC_VALUE_I64: &str = "@reml_value_i64";
 const INTRINSIC_VALUE_BOOL: &str = "@reml_value_bool";
@@ -36,12 +67,40 @@ const INTRINSIC_BOX_BOOL: &str = "@reml_box_bool";
 const INTRINSIC_BOX_STRING: &str = "@reml_box_string";
 const INTRINSIC_BOX_FLOAT: &str = "@reml_box_float";
 const INTRINSIC_BOX_CHAR: &str = "@reml_box_char";
+const INTRINSIC_UNBOX_I64: &str = "@reml_unbox_i64";
+const INTRINSIC_UNBOX_BOOL: &str = "@reml_unbox_bool";
+const INTRINSIC_UNBOX_STRING: &str = "@reml_unbox_string";
+const INTRINSIC_UNBOX_FLOAT: &str = "@reml_unbox_float";
+const INTRINSIC_RECORD_GET: &str = "@reml_record_get";
+const INTRINSIC_CLOSURE_NEW: &str = "@reml_closure_new";
+const INTRINSIC_CLOSURE_ENV: &str = "@reml_closure_env";
+const INTRINSIC_CLOSURE_CODE_PTR: &str = "@reml_closure_code_ptr";
+const INTRINSIC_DEC_REF: &str = "@dec_ref";
 const INTRINSIC_CALL: &str = "@reml_call";
 const INTRINSIC_STR_CONCAT: &str = "@reml_str_concat";
 const INTRINSIC_STR_DATA: &str = "@reml_str_data";
 const INTRINSIC_IF_ELSE: &str = "@reml_if_else";
 const INTRINSIC_PERFORM: &str = "@reml_perform";
+const INTRINSIC_HANDLER_NEW: &str = "@reml_handler_new";
+const INTRINSIC_HANDLER_ADD_OP: &str = "@reml_handler_add_op";
+const INTRINSIC_HANDLE: &str = "@reml_handle";
+const INTRINSIC_RESUME: &str = "@reml_resume";
 const INTRINSIC_PANIC: &str = "@panic";
+const INTRINSIC_CTOR_TAG: &str = "@reml_ctor_tag";
+const INTRINSIC_SLICE_REST: &str = "@reml_slice_rest";
+
+/// 可変長引数を取る Runtime 関数と、その固定引数の型。
+const VARIADIC_INTRINSICS: &[(&str, &str)] = &[
+    (INTRINSIC_RECORD_FROM, "i64"),
+    (INTRINSIC_ARRAY_FROM, "i64"),
+];
+
+fn variadic_fixed_params(callee: &str) -> Option<&'static str> {
+    VARIADIC_INTRINSICS
+        .iter()
+        .find(|(name, _)| *name == callee)
+        .map(|(_, params)| *params)
+}
 
 fn sanitize_llvm_ident(source: &str) -> String {
     let mut buf = String::new();
@@ -89,11 +148,6 @@ fn intrinsic_ctor_payload(name: &str) -> String {
     format!("@reml_ctor_payload_{}", sanitize_llvm_ident(name))
 }
 
-fn lambda_stub_symbol(expr_id: MirExprId, has_captures: bool) -> String {
-    let suffix = if has_captures { "capture" } else { "nocapture" };
-    sanitize_llvm_symbol(&format!("@reml_lambda_{suffix}_{expr_id}"))
-}
-
 fn intrinsic_value_for_type<'a>(ty: &str, ssa: &'a LlvmBuilder) -> &'a str {
     if ty == "i64" {
         return INTRINSIC_VALUE_I64;
@@ -110,6 +164,78 @@ fn intrinsic_value_for_type<'a>(ty: &str, ssa: &'a LlvmBuilder) -> &'a str {
     INTRINSIC_VALUE_PTR
 }
 
+/// 関数本体の `ptr "..."` 文字列オペランドを、NUL 終端したモジュール定数へ置き換える。
+#[derive(Default)]
+struct StringConstants {
+    symbols: HashMap<String, String>,
+    globals: Vec<String>,
+}
+
+impl StringConstants {
+    fn hoist(&mut self, ir: &str) -> String {
+        const PREFIX: &str = "ptr \"";
+        let mut out = String::with_capacity(ir.len());
+        let mut rest = ir;
+        while let Some(start) = rest.find(PREFIX) {
+            let body = &rest[start + PREFIX.len()..];
+            let Some((text, len)) = read_quoted_operand(body) else {
+                break;
+            };
+            out.push_str(&rest[..start]);
+            out.push_str("ptr ");
+            out.push_str(&self.symbol(text));
+            rest = &body[len..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn symbol(&mut self, text: String) -> String {
+        if let Some(symbol) = self.symbols.get(&text) {
+            return symbol.clone();
+        }
+        let symbol = format!("@.str.{}", self.globals.len());
+        let mut bytes = text.clone().into_bytes();
+        bytes.push(0);
+        self.globals.push(format!(
+            "{symbol} = private unnamed_addr constant [{} x i8] c\"{}\"",
+            bytes.len(),
+            escape_llvm_bytes(&bytes)
+        ));
+        self.symbols.insert(text, symbol.clone());
+        symbol
+    }
+}
+
+/// 開き引用符の直後から `\"` を解きつつ閉じ引用符まで読み、内容と消費したバイト数を返す。
+fn read_quoted_operand(body: &str) -> Option<(String, usize)> {
+    let mut text = String::new();
+    let mut chars = body.char_indices();
+    while let Some((index, ch)) = chars.next() {
+        match ch {
+            '\\' if body[index + 1..].starts_with('"') => {
+                text.push('"');
+                chars.next();
+            }
+            '"' => return Some((text, index + 1)),
+            _ => text.push(ch),
+        }
+    }
+    None
+}
+
+fn escape_llvm_bytes(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for &byte in bytes {
+        if matches!(byte, b' '..=b'~') && byte != b'"' && byte != b'\\' {
+            escaped.push(byte as char);
+        } else {
+            let _ = write!(escaped, "\\{byte:02X}");
+        }
+    }
+    escaped
+}
+
 fn escape_llvm_string(value: &str) -> String {
     let mut escaped = String::new();
     for ch in value.chars() {
@@ -309,11 +435,20 @@ fn infer_output_llvm_type(
     infer_expr_llvm_type(expr_id, expr_map, ssa)
 }
 
+/// フロントエンドのソース上の位置（バイトオフセット）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MirSpan {
+    pub start: u32,
+    pub end: u32,
+}
+
 #[derive(Clone, Debug)]
 pub struct MirExpr {
     pub id: MirExprId,
     pub ty: String,
     pub kind: MirExprKind,
+    /// デバッグ情報の行・列に使う位置。
+    pub span: Option<MirSpan>,
 }
 
 #[derive(Clone, Debug)]
@@ -326,6 +461,25 @@ pub struct MirLambdaParam {
 pub struct MirLambdaCapture {
     pub name: String,
     pub mutable: bool,
+    /// 環境スロットに格納する値の型トークン。空ならクロージャ変換で推定する。
+    pub ty: String,
+}
+
+/// ハンドラの操作節 `operation name(arg, resume) { body }`。
+#[derive(Clone, Debug)]
+pub struct MirHandlerOperation {
+    pub name: String,
+    pub params: Vec<MirLambdaParam>,
+    pub body: MirExprId,
+    /// 継続を末尾で一度だけ再開する節。Runtime は継続を作らずに直接呼び出す。
+    pub tail_resumptive: bool,
+}
+
+/// ハンドラの `return value { body }` 節。
+#[derive(Clone, Debug)]
+pub struct MirHandlerReturn {
+    pub value: MirLambdaParam,
+    pub body: MirExprId,
 }
 
 #[derive(Clone, Debug)]
@@ -376,6 +530,8 @@ pub enum MirExprKind {
         params: Vec<MirLambdaParam>,
         body: MirExprId,
         captures: Vec<MirLambdaCapture>,
+        /// クロージャ変換で持ち上げた関数のシンボル。
+        symbol: Option<String>,
     },
     Rec {
         target: MirExprId,
@@ -415,6 +571,20 @@ pub enum MirExprKind {
         effect: String,
         argument: MirExprId,
     },
+    /// `handle target with handler Effect { ... }`。
+    Handle {
+        effect: String,
+        target: MirExprId,
+        operations: Vec<MirHandlerOperation>,
+        return_clause: Option<MirHandlerReturn>,
+        /// 効果ローアリング済みなら true。`target` と各節の `body` はラムダ式を指す。
+        lowered: bool,
+    },
+    /// 操作節内の `resume(value)`。`continuation` は節が受け取った継続を指す。
+    Resume {
+        continuation: MirExprId,
+        value: MirExprId,
+    },
     EffectBlock {
         body: MirExprId,
     },
@@ -546,6 +716,10 @@ pub struct MatchLoweringPlan {
     pub target_type: Option<String>,
     pub arm_count: Option<usize>,
     pub arms: Vec<MatchArmLowering>,
+    /// ガードのない腕で対象の値を覆い尽くすか。決定木の既定分岐を省く根拠に使う。
+    pub exhaustive: bool,
+    /// 対象が和型なら宣言順のコンストラクタ名（タグ値の順）。
+    pub constructors: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -578,6 +752,21 @@ pub struct MirFunction {
     pub exprs: Vec<MirExpr>,
     /// エントリ式 ID。
     pub body: Option<MirExprId>,
+    /// 関数定義の位置。`DISubprogram` の行に使う。
+    pub span: Option<MirSpan>,
+    /// 引数名（`params` と同順）。空の場合は名前なしで描画する。
+    pub param_names: Vec<String>,
+    /// クロージャ変換で持ち上げた関数の環境スロット（キャプチャ順）。
+    pub closure_env: Option<Vec<MirLambdaCapture>>,
+    /// フロントエンド MIR の引数型トークン（型変数 `'tN` を含み得る）。単相化で使う。
+    pub param_type_tokens: Vec<String>,
+    /// フロントエンド MIR の戻り値型トークン。
+    pub return_type_token: Option<String>,
+    /// 効果ハンドラの本体・節として持ち上げた関数なら true。
+    /// 環境以外の引数と戻り値をボックス化した `ptr` で受け渡し、`params`/`ret` は本体側の型を表す。
+    pub boxed_abi: bool,
+    /// フロントエンドの末尾位置解析で見つかった末尾呼び出し。
+    pub tail_calls: Vec<MirTailCall>,
 }
 
 impl MirFunction {
@@ -592,6 +781,13 @@ impl MirFunction {
             match_plans: Vec::new(),
             exprs: Vec::new(),
             body: None,
+            span: None,
+            param_names: Vec::new(),
+            closure_env: None,
+            param_type_tokens: Vec::new(),
+            return_type_token: None,
+            boxed_abi: false,
+            tail_calls: Vec::new(),
         }
     }
 
@@ -619,6 +815,13 @@ impl MirFunction {
         self
     }
 
+    pub fn with_named_param(mut self, name: impl Into<String>, ty: RemlType) -> Self {
+        self.param_names.resize(self.params.len(), String::new());
+        self.param_names.push(name.into());
+        self.params.push(ty);
+        self
+    }
+
     pub fn with_return(mut self, ret: RemlType) -> Self {
         self.ret = Some(ret);
         self
@@ -644,6 +847,16 @@ impl MirFunction {
         self.exprs = exprs;
         self
     }
+
+    pub fn with_span(mut self, span: MirSpan) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_tail_calls(mut self, tail_calls: Vec<MirTailCall>) -> Self {
+        self.tail_calls = tail_calls;
+        self
+    }
 }
 
 /// 生成された関数の LLVM 風表現。
@@ -754,12 +967,21 @@ pub struct LlvmBlock {
 
 impl LlvmBlock {
     pub fn describe(&self) -> String {
+        self.render_with(self.terminator.describe())
+    }
+
+    /// 関数の戻り値型 `ret` で `ret` 命令を型付けして描画する。
+    pub fn render(&self, ret: &str) -> String {
+        self.render_with(self.terminator.render(ret))
+    }
+
+    fn render_with(&self, terminator: String) -> String {
         let mut buf = Vec::new();
         buf.push(format!("{}:", self.label));
-        for instr in &self.instrs {
+        for instr in self.instrs.iter().filter(|instr| !instr.is_debug_marker()) {
             buf.push(format!("  {}", instr.describe()));
         }
-        buf.push(format!("  {}", self.terminator.describe()));
+        buf.push(format!("  {terminator}"));
         buf.join("\n")
     }
 }
@@ -774,16 +996,25 @@ pub struct LlvmFunction {
 }
 
 impl LlvmFunction {
+    /// 定義に使う `@` 付きのシンボル。
+    pub fn symbol(&self) -> String {
+        if self.name.starts_with('@') {
+            self.name.clone()
+        } else {
+            format!("@{}", self.name)
+        }
+    }
+
     pub fn describe(&self) -> String {
         let mut buf = Vec::new();
         buf.push(format!(
             "define {} {}({}) {{",
             self.ret,
-            self.name,
+            self.symbol(),
             self.params.join(", ")
         ));
         for block in &self.blocks {
-            buf.push(block.describe());
+            buf.push(block.render(&self.ret));
         }
         buf.push("}".into());
         buf.join("\n")
@@ -795,6 +1026,21 @@ struct LlvmBuilder {
     type_mapping: TypeMappingContext,
     counter: usize,
     scopes: Vec<HashMap<String, LocalBinding>>,
+    /// 名前付き引数と LLVM 型。
+    params: HashMap<String, String>,
+    /// 縮退箇所の記録先。ブロック生成ごとの複製でも共有する。
+    fallbacks: Rc<RefCell<FallbackSink>>,
+    /// 評価中の式。縮退箇所の帰属先に使う。
+    current_expr: Option<MirExprId>,
+    /// 直接呼び出しに使うモジュール内関数のプロトタイプ（MIR 関数名で引く）。
+    prototypes: Rc<HashMap<String, FunctionPrototype>>,
+}
+
+#[derive(Debug, Default)]
+struct FallbackSink {
+    function: String,
+    spans: HashMap<MirExprId, MirSpan>,
+    sites: Vec<CodegenFallback>,
 }
 
 #[derive(Clone, Debug)]
@@ -809,9 +1055,52 @@ impl LlvmBuilder {
             type_mapping,
             counter: 0,
             scopes: vec![HashMap::new()],
+            params: HashMap::new(),
+            fallbacks: Rc::new(RefCell::new(FallbackSink::default())),
+            current_expr: None,
+            prototypes: Rc::default(),
+        }
+    }
+
+    /// 縮退箇所を `function` に帰属させ、式 ID から位置を引けるようにする。
+    fn track_fallbacks(&mut self, function: &str, exprs: &[MirExpr]) {
+        let mut sink = self.fallbacks.borrow_mut();
+        sink.function = function.to_string();
+        sink.spans = exprs
+            .iter()
+            .filter_map(|expr| expr.span.map(|span| (expr.id, span)))
+            .collect();
+    }
+
+    fn enter_expr(&mut self, expr_id: MirExprId) -> Option<MirExprId> {
+        self.current_expr.replace(expr_id)
+    }
+
+    fn leave_expr(&mut self, outer: Option<MirExprId>) {
+        self.current_expr = outer;
+    }
+
+    /// 生成できずにフォールバックした箇所を記録する。
+    /// 式 ID を省略した場合は評価中の式に帰属させる。
+    fn record_fallback(&self, code: &str, expr_id: Option<MirExprId>, detail: impl Into<String>) {
+        let mut sink = self.fallbacks.borrow_mut();
+        let expr_id = expr_id.or(self.current_expr);
+        let site = CodegenFallback {
+            code: code.to_string(),
+            function: sink.function.clone(),
+            expr_id,
+            span: expr_id.and_then(|id| sink.spans.get(&id).copied()),
+            detail: detail.into(),
+        };
+        if !sink.sites.contains(&site) {
+            sink.sites.push(site);
         }
     }
 
+    fn take_fallbacks(&self) -> Vec<CodegenFallback> {
+        std::mem::take(&mut self.fallbacks.borrow_mut().sites)
+    }
+
     fn new_tmp(&mut self, hint: &str) -> String {
         self.counter += 1;
         let hint = sanitize_llvm_ident(hint);
@@ -850,6 +1139,14 @@ impl LlvmBuilder {
         }
         None
     }
+
+    fn bind_param(&mut self, name: String, ty: String) {
+        self.params.insert(name, ty);
+    }
+
+    fn resolve_param(&self, name: &str) -> Option<String> {
+        self.params.get(name).cloned()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -920,9 +1217,25 @@ pub enum LlvmInstr {
         ty: String,
         incomings: Vec<(String, String)>,
     },
+    /// 以降の命令に付与するソース位置。デバッグ情報を出力しない場合は描画しない。
+    DebugLoc(MirSpan),
+    /// `alloca` で確保したローカル束縛の宣言（`llvm.dbg.declare`）。
+    DebugDeclare {
+        ptr: String,
+        name: String,
+        ty: String,
+    },
 }
 
 impl LlvmInstr {
+    /// デバッグ情報専用の疑似命令かどうか。
+    pub fn is_debug_marker(&self) -> bool {
+        matches!(
+            self,
+            LlvmInstr::DebugLoc(_) | LlvmInstr::DebugDeclare { .. }
+        )
+    }
+
     pub fn describe(&self) -> String {
         match self {
             LlvmInstr::Comment(text) => format!("; {text}"),
@@ -962,10 +1275,15 @@ impl LlvmInstr {
                     .map(|(ty, val)| format!("{ty} {val}"))
                     .collect::<Vec<_>>()
                     .join(", ");
+                // 可変長引数の関数は呼び出し側で関数型を明示する。
+                let fn_ty = match variadic_fixed_params(callee) {
+                    Some(params) => format!("{ret_ty} ({params}, ...)"),
+                    None => ret_ty.clone(),
+                };
                 if let Some(var) = result {
-                    format!("{var} = call {ret_ty} {callee}({args_rendered})")
+                    format!("{var} = call {fn_ty} {callee}({args_rendered})")
                 } else {
-                    format!("call {ret_ty} {callee}({args_rendered})")
+                    format!("call {fn_ty} {callee}({args_rendered})")
                 }
             }
             LlvmInstr::InlineAsm {
@@ -1025,6 +1343,10 @@ impl LlvmInstr {
                     .join(", ");
                 format!("{result} = phi {ty} {inputs}")
             }
+            LlvmInstr::DebugLoc(span) => format!("; dbg.loc {}..{}", span.start, span.end),
+            LlvmInstr::DebugDeclare { ptr, name, ty } => {
+                format!("call void @llvm.dbg.declare(metadata ptr {ptr}) ; {name}: {ty}")
+            }
         }
     }
 }
@@ -1040,6 +1362,13 @@ pub enum LlvmTerminator {
         else_bb: String,
     },
     Ret(Option<String>),
+    /// 整数値による多分岐。`cases` は (値, 分岐先) の組。
+    Switch {
+        ty: String,
+        value: String,
+        default: String,
+        cases: Vec<(String, String)>,
+    },
     Unreachable,
 }
 
@@ -1054,9 +1383,30 @@ impl LlvmTerminator {
             } => format!("br i1 {cond}, label %{then_bb}, label %{else_bb}"),
             LlvmTerminator::Ret(Some(val)) => format!("ret {}", val),
             LlvmTerminator::Ret(None) => "ret void".into(),
+            LlvmTerminator::Switch {
+                ty,
+                value,
+                default,
+                cases,
+            } => {
+                let arms = cases
+                    .iter()
+                    .map(|(case, target)| format!("{ty} {case}, label %{target}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("switch {ty} {value}, label %{default} [ {arms} ]")
+            }
             LlvmTerminator::Unreachable => "unreachable".into(),
         }
     }
+
+    /// 関数の戻り値型 `ret` で `ret` 命令を型付けして描画する。
+    pub fn render(&self, ret: &str) -> String {
+        match self {
+            LlvmTerminator::Ret(Some(val)) => format!("ret {ret} {val}"),
+            other => other.describe(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -1078,6 +1428,59 @@ pub struct LlvmIrUse {
     pub invalid_placeholders: Vec<usize>,
 }
 
+/// 生成できずにフォールバックで続行した箇所（縮退箇所）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodegenFallback {
+    pub code: String,
+    pub function: String,
+    pub expr_id: Option<MirExprId>,
+    pub span: Option<MirSpan>,
+    pub detail: String,
+}
+
+impl CodegenFallback {
+    pub fn describe(&self) -> String {
+        let mut buf = format!("{} function={}", self.code, self.function);
+        if let Some(expr_id) = self.expr_id {
+            let _ = write!(buf, " expr_id={expr_id}");
+        }
+        if let Some(span) = self.span {
+            let _ = write!(buf, " span={}..{}", span.start, span.end);
+        }
+        if !self.detail.is_empty() {
+            let _ = write!(buf, " {}", self.detail);
+        }
+        buf
+    }
+
+    /// 構造化したバックエンド診断へ変換する。
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let location = self
+            .expr_id
+            .map(|id| format!("式 #{id}"))
+            .unwrap_or_else(|| "式".into());
+        let mut diagnostic = Diagnostic::new(
+            "Backend",
+            self.code.clone(),
+            format!(
+                "関数 {} の{location}を生成できずフォールバックしました（{}）。",
+                self.function, self.detail
+            ),
+        )
+        .with_extension("function", self.function.clone())
+        .with_extension("backend", "rust");
+        if let Some(expr_id) = self.expr_id {
+            diagnostic = diagnostic.with_extension("expr_id", expr_id.to_string());
+        }
+        if let Some(span) = self.span {
+            diagnostic = diagnostic
+                .with_extension("span.start", span.start.to_string())
+                .with_extension("span.end", span.end.to_string());
+        }
+        diagnostic
+    }
+}
+
 /// LLVM 風モジュール IR。
 #[derive(Clone, Debug)]
 pub struct ModuleIr {
@@ -1093,9 +1496,105 @@ pub struct ModuleIr {
     pub windows_toolchain: Option<WindowsToolchainConfig>,
     pub target_context: TargetDiagnosticContext,
     pub bridge_metadata: BridgeMetadataContext,
+    /// デバッグ情報を有効にした場合のメタデータ。
+    pub debug_info: Option<DebugInfoBuilder>,
+    /// フォールバックで生成した縮退箇所。
+    pub codegen_fallbacks: Vec<CodegenFallback>,
+    /// 縮退箇所をエラーとして扱うか（`--strict-codegen`）。
+    pub strict_codegen: bool,
+    /// `@tailrec` 関数で末尾呼び出しを保証できなかった箇所。
+    pub tail_call_failures: Vec<TailCallFailure>,
 }
 
 impl ModuleIr {
+    /// `.ll` ファイルとして書き出すモジュール全体の IR を描画する。
+    pub fn render_ll(&self) -> String {
+        let mut buf = Vec::new();
+        let source_filename = self
+            .debug_info
+            .as_ref()
+            .map(|debug| debug.source().path.clone())
+            .unwrap_or_else(|| self.name.clone());
+        buf.push(format!(
+            "source_filename = \"{}\"",
+            escape_llvm_string(&source_filename)
+        ));
+        buf.push(format!(
+            "target datalayout = \"{}\"",
+            self.target.data_layout.description
+        ));
+        buf.push(format!("target triple = \"{}\"", self.target.triple));
+        let mut strings = StringConstants::default();
+        let bodies: Vec<String> = self
+            .functions
+            .iter()
+            .map(|function| strings.hoist(&function.llvm_ir))
+            .collect();
+        if !strings.globals.is_empty() {
+            buf.push(String::new());
+            buf.extend(strings.globals);
+        }
+        for body in bodies {
+            buf.push(String::new());
+            buf.push(body);
+        }
+        let declarations = self.external_declarations();
+        if !declarations.is_empty() {
+            buf.push(String::new());
+            buf.extend(declarations);
+        }
+        if let Some(debug) = &self.debug_info {
+            if debug.uses_dbg_declare() {
+                buf.push(String::new());
+                buf.push("declare void @llvm.dbg.declare(metadata, metadata, metadata)".into());
+            }
+            buf.push(String::new());
+            buf.push(debug.render_metadata());
+        }
+        buf.push(String::new());
+        buf.join("\n")
+    }
+
+    /// モジュール内で定義していない呼び出し先（Runtime 関数）の `declare` 行。
+    /// 引数型は最初に現れた呼び出しから取る。
+    fn external_declarations(&self) -> Vec<String> {
+        let mut seen: HashSet<String> = self
+            .llvm_functions
+            .iter()
+            .map(LlvmFunction::symbol)
+            .collect();
+        let mut declarations = Vec::new();
+        let instrs = self
+            .llvm_functions
+            .iter()
+            .flat_map(|function| &function.blocks)
+            .flat_map(|block| &block.instrs);
+        for instr in instrs {
+            let LlvmInstr::Call {
+                ret_ty,
+                callee,
+                args,
+                ..
+            } = instr
+            else {
+                continue;
+            };
+            if !callee.starts_with('@') || !seen.insert(callee.clone()) {
+                continue;
+            }
+            let params = match variadic_fixed_params(callee) {
+                Some(params) => format!("{params}, ..."),
+                None => args
+                    .iter()
+                    .map(|(ty, _)| ty.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            declarations.push(format!("declare {ret_ty} {callee}({params})"));
+        }
+        declarations
+    }
+
     pub fn describe(&self) -> String {
         let mut summary = Vec::new();
         summary.push(format!(
@@ -1143,6 +1642,12 @@ pub struct CodegenContext {
     target_context: TargetDiagnosticContext,
     bridge_metadata: BridgeMetadataContext,
     llvm_ir_builder: LlvmIrBuilder,
+    debug_info: Option<DebugInfoBuilder>,
+    fallbacks: Vec<CodegenFallback>,
+    strict_codegen: bool,
+    /// 直接呼び出しに使うモジュール内関数のプロトタイプ（MIR 関数名で引く）。
+    prototypes: Rc<HashMap<String, FunctionPrototype>>,
+    tail_call_failures: Vec<TailCallFailure>,
 }
 
 impl CodegenContext {
@@ -1161,7 +1666,6 @@ impl CodegenContext {
             llvm_ir_builder: LlvmIrBuilder::new(type_mapping.clone()),
             type_mapping,
             ffi_lowering,
-            target_machine,
             functions: Vec::new(),
             llvm_functions: Vec::new(),
             module_metadata: Vec::new(),
@@ -1171,9 +1675,53 @@ impl CodegenContext {
             llvm_ir_uses: Vec::new(),
             target_context,
             bridge_metadata,
+            debug_info: None,
+            fallbacks: Vec::new(),
+            strict_codegen: target_machine.opt_level.is_release(),
+            prototypes: Rc::default(),
+            tail_call_failures: Vec::new(),
+            target_machine,
+        }
+    }
+
+    /// 以降に生成する関数へ DWARF デバッグ情報を付与する。
+    pub fn enable_debug_info(&mut self, source: DebugSourceMap) {
+        self.debug_info = Some(DebugInfoBuilder::new(source, self.type_mapping.clone()));
+    }
+
+    /// 縮退箇所をエラーとして扱うかを切り替える（`--strict-codegen`）。
+    /// 既定ではリリース相当の最適化レベルで有効になる。
+    pub fn set_strict_codegen(&mut self, strict: bool) {
+        self.strict_codegen = strict;
+    }
+
+    pub fn strict_codegen(&self) -> bool {
+        self.strict_codegen
+    }
+
+    /// これまでに生成した関数の縮退箇所。
+    pub fn fallbacks(&self) -> &[CodegenFallback] {
+        &self.fallbacks
+    }
+
+    /// モジュール内の関数のプロトタイプを登録する。
+    /// 登録した関数への呼び出しは `@reml_call` を経由せず、直接呼び出しとして生成する。
+    pub fn declare_functions(&mut self, functions: &[MirFunction]) {
+        let prototypes = Rc::make_mut(&mut self.prototypes);
+        for mir in functions {
+            if mir.boxed_abi || mir.closure_env.is_some() {
+                continue;
+            }
+            let prototype = function_prototype(mir, &self.type_mapping);
+            prototypes.insert(mir.name.clone(), prototype);
         }
     }
 
+    /// これまでに生成した関数で末尾呼び出しを保証できなかった箇所。
+    pub fn tail_call_failures(&self) -> &[TailCallFailure] {
+        &self.tail_call_failures
+    }
+
     pub fn describe(&self) -> String {
         format!(
             "codegen(target={}, functions={})",
@@ -1194,9 +1742,19 @@ impl CodegenContext {
         &self.target_machine
     }
 
+    /// 効果ハンドラ変換とクロージャ変換を適用して関数を生成し、持ち上げた関数を続けて生成する。
     pub fn emit_function(&mut self, mir: &MirFunction) -> GeneratedFunction {
-        let ret_layout = mir
-            .ret
+        let (converted, lifted) = convert_closures(&lower_effect_handlers(mir));
+        let generated = self.emit_converted_function(&converted);
+        for function in &lifted {
+            self.emit_function(function);
+        }
+        generated
+    }
+
+    fn emit_converted_function(&mut self, mir: &MirFunction) -> GeneratedFunction {
+        let (signature_params, signature_names, signature_ret) = function_signature(mir);
+        let ret_layout = signature_ret
             .as_ref()
             .map(|ty| self.type_mapping.layout_of(ty))
             .unwrap_or_else(|| TypeLayout {
@@ -1232,7 +1790,8 @@ impl CodegenContext {
                 });
             }
         }
-        if !mir.exprs.is_empty() {
+        // 持ち上げた関数の式は親関数の式の部分集合なので、二重に記録しない。
+        if !mir.exprs.is_empty() && mir.closure_env.is_none() {
             self.inline_asm_uses
                 .extend(collect_inline_asm_uses(&mir.name, &mir.exprs));
             self.llvm_ir_uses.extend(collect_llvm_ir_uses(
@@ -1246,13 +1805,30 @@ impl CodegenContext {
         } else {
             render_branch_plans(&mir.exprs)
         };
-        let (basic_blocks, llvm_blocks) = if mir.exprs.is_empty() {
+        let (ssa, prologue) = function_builder(mir, &self.type_mapping, &self.prototypes);
+        let tail_lowering = mir
+            .body
+            .filter(|_| mir.closure_env.is_none())
+            .and_then(|body| {
+                let caller = function_prototype(mir, &self.type_mapping);
+                lower_tail_calls_to_blocks(mir, body, &caller, &self.prototypes, &ssa)
+            });
+        if requires_tail_call_guarantee(&mir.attributes) {
+            let failures = match &tail_lowering {
+                Some(lowered) => lowered.failures.clone(),
+                None => unlowered_tail_call_failures(mir),
+            };
+            self.tail_call_failures.extend(failures);
+        }
+        let (basic_blocks, mut llvm_blocks) = if let Some(lowered) = tail_lowering {
+            (lowered.basic_blocks, lowered.llvm_blocks)
+        } else if mir.exprs.is_empty() {
             (Vec::new(), Vec::new())
         } else {
-            let (basic_blocks, llvm_blocks) = lower_match_to_blocks(&mir.exprs, &self.type_mapping);
+            let (basic_blocks, llvm_blocks) = lower_match_to_blocks(&mir.exprs, &ssa);
             if llvm_blocks.is_empty() {
                 if let Some(body) = mir.body {
-                    lower_entry_expr_to_blocks(&mir.exprs, body, &self.type_mapping)
+                    lower_entry_expr_to_blocks(&mir.exprs, body, &ssa)
                 } else {
                     (basic_blocks, llvm_blocks)
                 }
@@ -1260,13 +1836,24 @@ impl CodegenContext {
                 (basic_blocks, llvm_blocks)
             }
         };
+        if let Some(entry) = llvm_blocks.first_mut() {
+            entry.instrs.splice(0..0, prologue);
+        }
+        if mir.boxed_abi {
+            box_return_values(mir, &mut llvm_blocks, &ssa);
+        }
+        self.fallbacks.extend(ssa.take_fallbacks());
         let llvm_fn = self.llvm_ir_builder.build_function(
             &mir.name,
-            &mir.params,
-            mir.ret.as_ref(),
+            &signature_params,
+            &signature_names,
+            signature_ret.as_ref(),
             llvm_blocks.clone(),
         );
-        let llvm_ir = self.llvm_ir_builder.render_ir(&llvm_fn);
+        let llvm_ir = match self.debug_info.as_mut() {
+            Some(debug) => debug.render_function(&llvm_fn, mir),
+            None => self.llvm_ir_builder.render_ir(&llvm_fn),
+        };
         let generated = GeneratedFunction {
             name: mir.name.clone(),
             layout: ret_layout,
@@ -1301,6 +1888,10 @@ impl CodegenContext {
             windows_toolchain: self.target_machine.windows_toolchain.clone(),
             target_context: self.target_context.clone(),
             bridge_metadata: self.bridge_metadata.clone(),
+            debug_info: self.debug_info,
+            codegen_fallbacks: self.fallbacks,
+            strict_codegen: self.strict_codegen,
+            tail_call_failures: self.tail_call_failures,
         }
     }
 }
@@ -1319,12 +1910,20 @@ impl LlvmIrBuilder {
         &self,
         name: &str,
         params: &[RemlType],
+        param_names: &[String],
         ret: Option<&RemlType>,
         blocks: Vec<LlvmBlock>,
     ) -> LlvmFunction {
         let params = params
             .iter()
-            .map(|ty| self.type_mapping.layout_of(ty).description)
+            .enumerate()
+            .map(|(index, ty)| {
+                let ty = self.type_mapping.layout_of(ty).description;
+                match param_names.get(index).filter(|name| !name.is_empty()) {
+                    Some(name) => format!("{ty} %{}", sanitize_llvm_ident(name)),
+                    None => ty,
+                }
+            })
             .collect();
         let ret = ret
             .map(|ty| self.type_mapping.layout_of(ty).description)
@@ -1469,9 +2068,197 @@ fn render_branch_plans(exprs: &[MirExpr]) -> Vec<String> {
     plans
 }
 
+/// 末尾呼び出しの判定に使うプロトタイプを、描画するシグネチャから作る。
+fn function_prototype(mir: &MirFunction, type_mapping: &TypeMappingContext) -> FunctionPrototype {
+    let (params, _, ret) = function_signature(mir);
+    FunctionPrototype {
+        symbol: format!("@{}", sanitize_llvm_ident(&mir.name)),
+        params: params
+            .iter()
+            .map(|ty| type_mapping.layout_of(ty).description)
+            .collect(),
+        ret: ret
+            .map(|ty| type_mapping.layout_of(&ty).description)
+            .unwrap_or_else(|| "void".into()),
+        calling_conv: mir.calling_conv.clone(),
+    }
+}
+
+/// 描画するシグネチャ（引数型・引数名・戻り値型）を返す。
+/// ボックス化 ABI の関数は環境以外の引数を `<name>__boxed` の `ptr` で受け取り、`ptr` を返す。
+fn function_signature(mir: &MirFunction) -> (Vec<RemlType>, Vec<String>, Option<RemlType>) {
+    if !mir.boxed_abi {
+        return (mir.params.clone(), mir.param_names.clone(), mir.ret.clone());
+    }
+    let names = mir
+        .param_names
+        .iter()
+        .map(|name| boxed_param_name(name))
+        .collect();
+    (
+        vec![RemlType::Pointer; mir.params.len()],
+        names,
+        Some(RemlType::Pointer),
+    )
+}
+
+fn boxed_param_name(name: &str) -> String {
+    if name.is_empty() || name == CLOSURE_ENV_PARAM {
+        name.to_string()
+    } else {
+        format!("{name}__boxed")
+    }
+}
+
+/// ボックス化 ABI の関数で、戻り値を `ptr` へボックス化してから返す。
+/// Unit を返す節は `null` を返す。
+fn box_return_values(mir: &MirFunction, blocks: &mut [LlvmBlock], ssa: &LlvmBuilder) {
+    for block in blocks.iter_mut() {
+        if matches!(block.terminator, LlvmTerminator::Ret(None)) {
+            block.terminator = LlvmTerminator::Ret(Some("null".into()));
+        }
+    }
+    let Some(ret) = mir.ret.as_ref() else {
+        return;
+    };
+    let ty = ssa.type_mapping.layout_of(ret).description;
+    let Some(callee) = box_intrinsic_for_type(&ty, ssa) else {
+        return;
+    };
+    for (index, block) in blocks.iter_mut().enumerate() {
+        let LlvmTerminator::Ret(Some(operand)) = &block.terminator else {
+            continue;
+        };
+        if operand == "null" {
+            continue;
+        }
+        let boxed = format!("%ret_box{index}");
+        block.instrs.push(LlvmInstr::Call {
+            result: Some(boxed.clone()),
+            ret_ty: ssa.pointer_type(),
+            callee: callee.into(),
+            args: vec![(ty.clone(), operand.clone())],
+        });
+        block.terminator = LlvmTerminator::Ret(Some(boxed));
+    }
+}
+
+/// 名前付き引数とクロージャ環境を束縛した `LlvmBuilder` と、
+/// 環境スロットを取り出すプロローグ命令を用意する。
+fn function_builder(
+    mir: &MirFunction,
+    type_mapping: &TypeMappingContext,
+    prototypes: &Rc<HashMap<String, FunctionPrototype>>,
+) -> (LlvmBuilder, Vec<LlvmInstr>) {
+    let mut ssa = LlvmBuilder::new(type_mapping.clone());
+    ssa.prototypes = Rc::clone(prototypes);
+    ssa.track_fallbacks(&mir.name, &mir.exprs);
+    let mut prologue = Vec::new();
+    for (name, ty) in mir.param_names.iter().zip(&mir.params) {
+        if name.is_empty() {
+            continue;
+        }
+        let description = type_mapping.layout_of(ty).description;
+        if !mir.boxed_abi || name == CLOSURE_ENV_PARAM {
+            ssa.bind_param(name.clone(), description);
+            continue;
+        }
+        // ボックス化された引数はプロローグで取り出してローカルへ束縛する。
+        let boxed = boxed_param_name(name);
+        ssa.bind_param(boxed.clone(), ssa.pointer_type());
+        let boxed = format!("%{}", sanitize_llvm_ident(&boxed));
+        let (value, ty) = match unbox_intrinsic_for_type(&description, &ssa) {
+            Some(unbox) => {
+                let value = ssa.new_tmp(name);
+                prologue.push(LlvmInstr::Call {
+                    result: Some(value.clone()),
+                    ret_ty: description.clone(),
+                    callee: unbox.into(),
+                    args: vec![(ssa.pointer_type(), boxed)],
+                });
+                (value, description)
+            }
+            None => (boxed, ssa.pointer_type()),
+        };
+        let pattern = MirPattern {
+            kind: MirPatternKind::Var { name: name.clone() },
+        };
+        prologue.extend(bind_pattern_operand(&pattern, value, ty, &mut ssa));
+    }
+    let Some(captures) = &mir.closure_env else {
+        return (ssa, prologue);
+    };
+    let env = format!("%{}", sanitize_llvm_ident(CLOSURE_ENV_PARAM));
+    for (index, capture) in captures.iter().enumerate() {
+        let slot = ssa.new_tmp(&format!("{}_slot", capture.name));
+        prologue.push(LlvmInstr::Comment(format!(
+            "closure env slot {index} -> {}",
+            capture.name
+        )));
+        prologue.push(LlvmInstr::Call {
+            result: Some(slot.clone()),
+            ret_ty: ssa.pointer_type(),
+            callee: INTRINSIC_RECORD_GET.into(),
+            args: vec![
+                (ssa.pointer_type(), env.clone()),
+                ("i64".into(), index.to_string()),
+            ],
+        });
+        let ty = map_type_token_to_llvm(&capture.ty, &ssa).unwrap_or_else(|| ssa.pointer_type());
+        let value = match unbox_intrinsic_for_type(&ty, &ssa) {
+            Some(unbox) => {
+                let value = ssa.new_tmp(&capture.name);
+                prologue.push(LlvmInstr::Call {
+                    result: Some(value.clone()),
+                    ret_ty: ty.clone(),
+                    callee: unbox.into(),
+                    args: vec![(ssa.pointer_type(), slot)],
+                });
+                value
+            }
+            None => slot,
+        };
+        let pattern = MirPattern {
+            kind: MirPatternKind::Var {
+                name: capture.name.clone(),
+            },
+        };
+        prologue.extend(bind_pattern_operand(&pattern, value, ty, &mut ssa));
+    }
+    (ssa, prologue)
+}
+
+fn box_intrinsic_for_type(ty: &str, ssa: &LlvmBuilder) -> Option<&'static str> {
+    if ty == "i64" {
+        Some(INTRINSIC_BOX_I64)
+    } else if ty == ssa.bool_type() {
+        Some(INTRINSIC_BOX_BOOL)
+    } else if ty == "Str" {
+        Some(INTRINSIC_BOX_STRING)
+    } else if ty == "double" {
+        Some(INTRINSIC_BOX_FLOAT)
+    } else {
+        None
+    }
+}
+
+fn unbox_intrinsic_for_type(ty: &str, ssa: &LlvmBuilder) -> Option<&'static str> {
+    if ty == "i64" {
+        Some(INTRINSIC_UNBOX_I64)
+    } else if ty == ssa.bool_type() {
+        Some(INTRINSIC_UNBOX_BOOL)
+    } else if ty == "Str" {
+        Some(INTRINSIC_UNBOX_STRING)
+    } else if ty == "double" {
+        Some(INTRINSIC_UNBOX_FLOAT)
+    } else {
+        None
+    }
+}
+
 fn lower_match_to_blocks(
     exprs: &[MirExpr],
-    type_mapping: &TypeMappingContext,
+    ssa_template: &LlvmBuilder,
 ) -> (Vec<BasicBlock>, Vec<LlvmBlock>) {
     let mut expr_map = HashMap::new();
     for expr in exprs {
@@ -1495,88 +2282,62 @@ fn lower_match_to_blocks(
                 })
                 .unwrap_or_else(|| format!("#{}", target));
             let target_operand = format_operand_from_summary(&target_desc);
-            let mut ssa = LlvmBuilder::new(type_mapping.clone());
-            let mut phi_sources: Vec<(String, String)> = Vec::new();
-            for (index, arm) in arms.iter().enumerate() {
-                let next_arm = if index + 1 == arms.len() {
-                    end_label.clone()
-                } else {
-                    format!("arm{}", index + 1)
-                };
-                let guard_label = arm.guard.map(|gid| format!("arm{index}.guard#{gid}"));
-                let alias_label = arm.alias.as_ref().map(|_| format!("arm{index}.alias"));
-                let body_label = format!("arm{index}.body#{}", arm.body);
-                let post_guard_label = alias_label.clone().unwrap_or_else(|| body_label.clone());
-                let success_label = guard_label
-                    .clone()
-                    .or(alias_label.clone())
-                    .unwrap_or_else(|| body_label.clone());
-
-                let (mut arm_blocks, mut arm_llvm_blocks) = emit_pattern_blocks(
-                    index,
-                    &arm.pattern,
-                    &success_label,
-                    &next_arm,
-                    &target_operand,
-                    &target_desc,
-                    &mut ssa,
-                );
-                blocks.append(&mut arm_blocks);
-                llvm_blocks.append(&mut arm_llvm_blocks);
+            let mut ssa = ssa_template.clone();
+            let result_type = lowering
+                .as_ref()
+                .and_then(|plan| plan.target_type.clone())
+                .unwrap_or_else(|| "unknown".into());
+            let tree = compile_match(arms, lowering.as_ref());
+            let mut lowering_ctx = MatchTreeLowering::new(
+                expr.id,
+                &tree,
+                arms,
+                lowering.as_ref(),
+                target_operand,
+                target_desc,
+                &mut ssa,
+            );
+            let mut phi_sources: Vec<(String, String)> = Vec::new();
+            let mut match_blocks = Vec::new();
+            let mut match_llvm_blocks = Vec::new();
+            for node in tree.reachable() {
+                if let Some(llvm_block_list) = lowering_ctx.lower_node(node, &mut ssa) {
+                    for block in llvm_block_list {
+                        match_blocks.push(basic_block_from_llvm(&block));
+                        match_llvm_blocks.push(block);
+                    }
+                }
+            }
 
-                if let Some(label) = guard_label {
-                    blocks.push(BasicBlock {
-                        label: label.clone(),
-                        instrs: vec![format!("guard check {}", label)],
-                        terminator: format!(
-                            "br_if {label} then {success} else {next}",
-                            success = post_guard_label,
-                            next = next_arm
-                        ),
-                    });
-                    let (cond, mut guard_instrs) =
-                        emit_guard_cond(arm.guard.unwrap_or(0), &expr_map, &mut ssa);
-                    guard_instrs.insert(
-                        0,
-                        LlvmInstr::Comment(format!(
-                            "guard {label} -> {success}/{next}",
-                            success = post_guard_label,
-                            next = next_arm
-                        )),
-                    );
-                    llvm_blocks.push(LlvmBlock {
-                        label: label.clone(),
-                        instrs: guard_instrs,
-                        terminator: LlvmTerminator::BrCond {
-                            cond,
-                            then_bb: post_guard_label.clone(),
-                            else_bb: next_arm.clone(),
-                        },
-                    });
+            for index in tree.reachable_arms() {
+                let arm = &arms[index];
+                let body_label = format!("arm{index}.body#{}", arm.body);
+                ssa.push_scope();
+                lowering_ctx.bind_arm_slots(index, &mut ssa);
+                for block in lowering_ctx.lower_guards(index, &expr_map, &mut ssa) {
+                    match_blocks.push(basic_block_from_llvm(&block));
+                    match_llvm_blocks.push(block);
                 }
 
                 if let Some(alias) = &arm.alias {
-                    let alias_block = alias_label
-                        .clone()
-                        .unwrap_or_else(|| format!("arm{index}.alias"));
-                    blocks.push(BasicBlock {
+                    let alias_block = format!("arm{index}.alias");
+                    match_blocks.push(BasicBlock {
                         label: alias_block.clone(),
-                        instrs: vec![format!("alias {alias} = {target_desc}")],
+                        instrs: vec![format!("alias {alias} = {}", lowering_ctx.target_desc)],
                         terminator: format!("br {body}", body = body_label),
                     });
-                    llvm_blocks.push(LlvmBlock {
+                    match_llvm_blocks.push(LlvmBlock {
                         label: alias_block.clone(),
-                        instrs: vec![LlvmInstr::Comment(format!("alias {alias} = {target_desc}"))],
+                        instrs: vec![LlvmInstr::Comment(format!(
+                            "alias {alias} = {}",
+                            lowering_ctx.target_desc
+                        ))],
                         terminator: LlvmTerminator::Br {
                             target: body_label.clone(),
                         },
                     });
                 }
 
-                let result_type = lowering
-                    .as_ref()
-                    .and_then(|plan| plan.target_type.clone())
-                    .unwrap_or_else(|| "unknown".into());
                 let early_exit = detect_arm_early_exit(arm.body, &expr_map);
                 match early_exit {
                     Some(ArmEarlyExit::Panic) => {
@@ -1587,8 +2348,8 @@ fn lower_match_to_blocks(
                             value,
                             &mut ssa,
                         );
-                        blocks.push(block);
-                        llvm_blocks.push(llvm_block);
+                        match_blocks.push(block);
+                        match_llvm_blocks.push(llvm_block);
                     }
                     Some(ArmEarlyExit::Propagate) => {
                         let value = emit_value_expr(arm.body, &expr_map, &mut ssa);
@@ -1603,12 +2364,12 @@ fn lower_match_to_blocks(
                                 &end_label,
                                 &mut ssa,
                             );
-                        blocks.extend(prop_blocks);
-                        llvm_blocks.extend(prop_llvm_blocks);
+                        match_blocks.extend(prop_blocks);
+                        match_llvm_blocks.extend(prop_llvm_blocks);
                         phi_sources.push(phi_source);
                     }
                     None => {
-                        blocks.push(BasicBlock {
+                        match_blocks.push(BasicBlock {
                             label: body_label.clone(),
                             instrs: vec![format!("exec body#{}", arm.body)],
                             terminator: format!("br {}", end_label),
@@ -1616,7 +2377,7 @@ fn lower_match_to_blocks(
                         let (value, value_label, mut value_instrs) =
                             emit_body_value(index, arm.body, &expr_map, &result_type, &mut ssa);
                         phi_sources.push((value, value_label));
-                        llvm_blocks.push(LlvmBlock {
+                        match_llvm_blocks.push(LlvmBlock {
                             label: body_label.clone(),
                             instrs: {
                                 let mut instrs = Vec::new();
@@ -1630,12 +2391,15 @@ fn lower_match_to_blocks(
                         });
                     }
                 }
+                ssa.pop_scope();
             }
 
-            let result_type = lowering
-                .as_ref()
-                .and_then(|plan| plan.target_type.clone())
-                .unwrap_or_else(|| "unknown".into());
+            let entry = lowering_ctx.entry_block(&tree);
+            blocks.push(basic_block_from_llvm(&entry));
+            llvm_blocks.push(entry);
+            blocks.append(&mut match_blocks);
+            llvm_blocks.append(&mut match_llvm_blocks);
+
             let phi_inputs = if phi_sources.is_empty() {
                 "[]".into()
             } else {
@@ -1671,6 +2435,870 @@ fn lower_match_to_blocks(
     (blocks, llvm_blocks)
 }
 
+/// `LlvmBlock` から文字列ベースの `BasicBlock` 要約を作る。
+fn basic_block_from_llvm(block: &LlvmBlock) -> BasicBlock {
+    let instrs = block
+        .instrs
+        .iter()
+        .filter_map(|instr| match instr {
+            LlvmInstr::Comment(text) => Some(text.clone()),
+            _ => None,
+        })
+        .collect();
+    let terminator = match &block.terminator {
+        LlvmTerminator::Br { target } => format!("br {target}"),
+        LlvmTerminator::BrCond {
+            cond,
+            then_bb,
+            else_bb,
+        } => format!("br_if {cond} then {then_bb} else {else_bb}"),
+        other => other.describe(),
+    };
+    BasicBlock {
+        label: block.label.clone(),
+        instrs,
+        terminator,
+    }
+}
+
+/// 決定木を LLVM ブロックへ下ろすときの match 単位の状態。
+///
+/// 判定位置の値は各ブロックで照合対象から射影し直す（射影は純粋な取り出しなので、
+/// 共有ノードへどの経路から来ても同じ値になる）。アクティブパターンの結果と腕の束縛は
+/// 入口ブロックで確保したスロットに保存し、後続のブロックと腕本体から読み出す。
+struct MatchTreeLowering<'a> {
+    match_id: MirExprId,
+    tree: &'a DecisionTree,
+    arms: &'a [MirMatchArm],
+    exhaustive: bool,
+    constructors: Vec<String>,
+    target_operand: String,
+    target_desc: String,
+    root_ty: String,
+    labels: HashMap<NodeId, String>,
+    /// (腕, 名前) ごとの束縛スロットと型。
+    binding_slots: Vec<((usize, String), LocalBinding)>,
+    /// アクティブパターン結果の判定位置ごとのスロット。
+    active_slots: HashMap<Occurrence, String>,
+    entry_instrs: Vec<LlvmInstr>,
+}
+
+impl<'a> MatchTreeLowering<'a> {
+    fn new(
+        match_id: MirExprId,
+        tree: &'a DecisionTree,
+        arms: &'a [MirMatchArm],
+        plan: Option<&MatchLoweringPlan>,
+        target_operand: String,
+        target_desc: String,
+        ssa: &mut LlvmBuilder,
+    ) -> Self {
+        let root_ty = plan
+            .and_then(|plan| plan.target_type.as_deref())
+            .and_then(|ty| map_type_token_to_llvm(ty, ssa))
+            .unwrap_or_else(|| ssa.pointer_type());
+        let mut lowering = Self {
+            match_id,
+            tree,
+            arms,
+            exhaustive: plan.map(|plan| plan.exhaustive).unwrap_or(false),
+            constructors: plan
+                .map(|plan| plan.constructors.clone())
+                .unwrap_or_default(),
+            target_operand,
+            target_desc,
+            root_ty,
+            labels: HashMap::new(),
+            binding_slots: Vec::new(),
+            active_slots: HashMap::new(),
+            entry_instrs: Vec::new(),
+        };
+        lowering.assign_labels();
+        lowering.allocate_slots(ssa);
+        lowering
+    }
+
+    fn arm_entry_label(&self, arm: usize) -> String {
+        let info = &self.arms[arm];
+        if info.alias.is_some() {
+            format!("arm{arm}.alias")
+        } else {
+            format!("arm{arm}.body#{}", info.body)
+        }
+    }
+
+    fn assign_labels(&mut self) {
+        let reachable = self.tree.reachable();
+        let mut guard_nodes: HashMap<usize, usize> = HashMap::new();
+        for id in &reachable {
+            if let DecisionNode::Guard { arm, .. } = &self.tree.nodes[*id] {
+                *guard_nodes.entry(*arm).or_default() += 1;
+            }
+        }
+        for id in reachable {
+            let label = match &self.tree.nodes[id] {
+                DecisionNode::Fail => format!("match{}.fail", self.match_id),
+                DecisionNode::Leaf { arm, bindings } if bindings.is_empty() => {
+                    self.arm_entry_label(*arm)
+                }
+                DecisionNode::Leaf { arm, .. } => format!("arm{arm}.bind.n{id}"),
+                DecisionNode::Guard { arm, .. } => {
+                    let guard = self.arms[*arm].guard.unwrap_or(0);
+                    if guard_nodes.get(arm).copied().unwrap_or(0) > 1 {
+                        format!("arm{arm}.guard#{guard}.n{id}")
+                    } else {
+                        format!("arm{arm}.guard#{guard}")
+                    }
+                }
+                DecisionNode::Switch { .. } | DecisionNode::Test { .. } => {
+                    format!("match{}.dt{id}", self.match_id)
+                }
+            };
+            self.labels.insert(id, label);
+        }
+    }
+
+    fn label(&self, id: NodeId) -> String {
+        self.labels
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| format!("match{}.dt{id}", self.match_id))
+    }
+
+    /// 束縛とアクティブパターン結果のスロットを入口ブロックに確保する。
+    fn allocate_slots(&mut self, ssa: &mut LlvmBuilder) {
+        for id in self.tree.reachable() {
+            match &self.tree.nodes[id] {
+                DecisionNode::Leaf { arm, bindings }
+                | DecisionNode::Guard { arm, bindings, .. } => {
+                    for (name, occurrence) in bindings {
+                        let key = (*arm, name.clone());
+                        if self
+                            .binding_slots
+                            .iter()
+                            .any(|(existing, _)| *existing == key)
+                        {
+                            continue;
+                        }
+                        let ty = if occurrence.is_empty() {
+                            self.root_ty.clone()
+                        } else {
+                            ssa.pointer_type()
+                        };
+                        let ptr = ssa.new_tmp(&format!("{name}_slot"));
+                        self.entry_instrs.push(LlvmInstr::Alloca {
+                            result: ptr.clone(),
+                            ty: ty.clone(),
+                        });
+                        self.binding_slots.push((key, LocalBinding { ptr, ty }));
+                    }
+                }
+                DecisionNode::Test {
+                    occurrence,
+                    test: Test::Active { name, partial },
+                    ..
+                } => {
+                    let mut result = occurrence.clone();
+                    result.push(Access::ActiveResult {
+                        name: name.clone(),
+                        partial: *partial,
+                    });
+                    if self.active_slots.contains_key(&result) {
+                        continue;
+                    }
+                    let ptr = ssa.new_tmp(&format!("{name}_result_slot"));
+                    self.entry_instrs.push(LlvmInstr::Alloca {
+                        result: ptr.clone(),
+                        ty: ssa.pointer_type(),
+                    });
+                    self.active_slots.insert(result, ptr);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn entry_block(&mut self, tree: &DecisionTree) -> LlvmBlock {
+        let mut instrs = vec![LlvmInstr::Comment(format!(
+            "match#{} on {}: decision tree with {} tests",
+            self.match_id,
+            self.target_desc,
+            tree.test_count()
+        ))];
+        instrs.append(&mut self.entry_instrs);
+        LlvmBlock {
+            label: format!("match{}.entry", self.match_id),
+            instrs,
+            terminator: LlvmTerminator::Br {
+                target: self.label(tree.root),
+            },
+        }
+    }
+
+    /// 判定位置の値を照合対象から射影する。
+    fn project(
+        &self,
+        occurrence: &Occurrence,
+        instrs: &mut Vec<LlvmInstr>,
+        ssa: &mut LlvmBuilder,
+    ) -> String {
+        let mut current = self.target_operand.clone();
+        for (depth, access) in occurrence.iter().enumerate() {
+            let ptr = ssa.pointer_type();
+            current = match access {
+                Access::CtorField { ctor, index, arity } => {
+                    let payload = ssa.new_tmp("payload");
+                    instrs.push(LlvmInstr::Call {
+                        result: Some(payload.clone()),
+                        ret_ty: ptr.clone(),
+                        callee: intrinsic_ctor_payload(ctor),
+                        args: vec![(ptr.clone(), current)],
+                    });
+                    if *arity == 1 {
+                        payload
+                    } else {
+                        index_access(payload, *index, instrs, ssa)
+                    }
+                }
+                Access::TupleElem(index) | Access::SliceHead(index) => {
+                    index_access(current, *index, instrs, ssa)
+                }
+                Access::RecordField(key) => {
+                    let field = ssa.new_tmp("field");
+                    instrs.push(LlvmInstr::Call {
+                        result: Some(field.clone()),
+                        ret_ty: ptr.clone(),
+                        callee: INTRINSIC_FIELD_ACCESS.into(),
+                        args: vec![
+                            (ptr.clone(), current),
+                            (ptr.clone(), format!("\"{}\"", key.replace('"', "\\\""))),
+                        ],
+                    });
+                    field
+                }
+                Access::SliceBack(offset) => {
+                    let len = slice_len(&current, instrs, ssa);
+                    let index = ssa.new_tmp("back_index");
+                    instrs.push(LlvmInstr::BinOp {
+                        result: index.clone(),
+                        op: "sub".into(),
+                        ty: "i64".into(),
+                        lhs: len,
+                        rhs: offset.to_string(),
+                    });
+                    let element = ssa.new_tmp("elem");
+                    instrs.push(LlvmInstr::Call {
+                        result: Some(element.clone()),
+                        ret_ty: ptr.clone(),
+                        callee: INTRINSIC_INDEX_ACCESS.into(),
+                        args: vec![(ptr.clone(), current), ("i64".into(), index)],
+                    });
+                    element
+                }
+                Access::SliceRest { head, tail } => {
+                    let rest = ssa.new_tmp("rest");
+                    instrs.push(LlvmInstr::Call {
+                        result: Some(rest.clone()),
+                        ret_ty: ptr.clone(),
+                        callee: INTRINSIC_SLICE_REST.into(),
+                        args: vec![
+                            (ptr.clone(), current),
+                            ("i64".into(), head.to_string()),
+                            ("i64".into(), tail.to_string()),
+                        ],
+                    });
+                    rest
+                }
+                Access::ActiveResult { partial, .. } => {
+                    let key = occurrence[..=depth].to_vec();
+                    let slot = self
+                        .active_slots
+                        .get(&key)
+                        .cloned()
+                        .unwrap_or_else(|| "null".into());
+                    let result = ssa.new_tmp("active_result");
+                    instrs.push(LlvmInstr::Load {
+                        result: result.clone(),
+                        ty: ptr.clone(),
+                        ptr: slot,
+                    });
+                    if *partial {
+                        let payload = ssa.new_tmp("payload");
+                        instrs.push(LlvmInstr::Call {
+                            result: Some(payload.clone()),
+                            ret_ty: ptr.clone(),
+                            callee: intrinsic_ctor_payload("Some"),
+                            args: vec![(ptr.clone(), result)],
+                        });
+                        payload
+                    } else {
+                        result
+                    }
+                }
+            };
+        }
+        current
+    }
+
+    /// 整数・真偽値として比べる値を得る。入れ子の値はボックス化されているので取り出す。
+    fn project_scalar(
+        &self,
+        occurrence: &Occurrence,
+        ty: &str,
+        instrs: &mut Vec<LlvmInstr>,
+        ssa: &mut LlvmBuilder,
+    ) -> String {
+        let value = self.project(occurrence, instrs, ssa);
+        if occurrence.is_empty() {
+            return value;
+        }
+        let Some(unbox) = unbox_intrinsic_for_type(ty, ssa) else {
+            return value;
+        };
+        let scalar = ssa.new_tmp("scalar");
+        instrs.push(LlvmInstr::Call {
+            result: Some(scalar.clone()),
+            ret_ty: ty.to_string(),
+            callee: unbox.into(),
+            args: vec![(ssa.pointer_type(), value)],
+        });
+        scalar
+    }
+
+    fn lower_node(&mut self, id: NodeId, ssa: &mut LlvmBuilder) -> Option<Vec<LlvmBlock>> {
+        let label = self.label(id);
+        match &self.tree.nodes[id] {
+            DecisionNode::Fail => {
+                let mut instrs = Vec::new();
+                if self.exhaustive {
+                    instrs.push(LlvmInstr::Comment(format!(
+                        "match#{} is exhaustive; no arm can fail",
+                        self.match_id
+                    )));
+                } else {
+                    instrs.push(LlvmInstr::Comment(format!(
+                        "match#{} failed: no arm matched {}",
+                        self.match_id, self.target_desc
+                    )));
+                    instrs.push(LlvmInstr::Call {
+                        result: None,
+                        ret_ty: "void".into(),
+                        callee: INTRINSIC_PANIC.into(),
+                        args: vec![(ssa.pointer_type(), "\"match.no_arm\"".into())],
+                    });
+                }
+                Some(vec![LlvmBlock {
+                    label,
+                    instrs,
+                    terminator: LlvmTerminator::Unreachable,
+                }])
+            }
+            DecisionNode::Leaf { bindings, .. } if bindings.is_empty() => None,
+            DecisionNode::Leaf { arm, bindings } => {
+                let mut instrs = Vec::new();
+                self.store_bindings(*arm, bindings, &mut instrs, ssa);
+                Some(vec![LlvmBlock {
+                    label,
+                    instrs,
+                    terminator: LlvmTerminator::Br {
+                        target: self.arm_entry_label(*arm),
+                    },
+                }])
+            }
+            // ガードは腕の束縛を見えるようにしてから腕ごとに下ろす。
+            DecisionNode::Guard { .. } => None,
+            DecisionNode::Switch {
+                occurrence,
+                kind,
+                cases,
+                default,
+            } => Some(self.lower_switch(label, occurrence, *kind, cases, *default, ssa)),
+            DecisionNode::Test {
+                occurrence,
+                test,
+                then,
+                otherwise,
+            } => Some(vec![
+                self.lower_test(label, occurrence, test, *then, *otherwise, ssa)
+            ]),
+        }
+    }
+
+    fn store_bindings(
+        &self,
+        arm: usize,
+        bindings: &[(String, Occurrence)],
+        instrs: &mut Vec<LlvmInstr>,
+        ssa: &mut LlvmBuilder,
+    ) {
+        for (name, occurrence) in bindings {
+            instrs.push(LlvmInstr::Comment(format!(
+                "binding {name} <- {}",
+                describe_occurrence(occurrence)
+            )));
+            let Some((_, slot)) = self
+                .binding_slots
+                .iter()
+                .find(|((slot_arm, slot_name), _)| *slot_arm == arm && slot_name == name)
+            else {
+                continue;
+            };
+            let value = self.project(occurrence, instrs, ssa);
+            instrs.push(LlvmInstr::Store {
+                ty: slot.ty.clone(),
+                ptr: slot.ptr.clone(),
+                value,
+            });
+        }
+    }
+
+    /// 腕の束縛を現在のスコープへ登録する。
+    fn bind_arm_slots(&self, arm: usize, ssa: &mut LlvmBuilder) {
+        for ((slot_arm, name), binding) in &self.binding_slots {
+            if *slot_arm == arm {
+                ssa.bind_local(name.clone(), binding.clone());
+            }
+        }
+    }
+
+    fn lower_guards(
+        &self,
+        arm: usize,
+        expr_map: &HashMap<MirExprId, &MirExpr>,
+        ssa: &mut LlvmBuilder,
+    ) -> Vec<LlvmBlock> {
+        let mut blocks = Vec::new();
+        for id in self.tree.reachable() {
+            let DecisionNode::Guard {
+                arm: guard_arm,
+                bindings,
+                otherwise,
+            } = &self.tree.nodes[id]
+            else {
+                continue;
+            };
+            if *guard_arm != arm {
+                continue;
+            }
+            let label = self.label(id);
+            let success = self.arm_entry_label(arm);
+            let next = self.label(*otherwise);
+            let mut instrs = vec![LlvmInstr::Comment(format!(
+                "guard {label} -> {success}/{next}"
+            ))];
+            self.store_bindings(arm, bindings, &mut instrs, ssa);
+            let (cond, mut guard_instrs) =
+                emit_guard_cond(self.arms[arm].guard.unwrap_or(0), expr_map, ssa);
+            instrs.append(&mut guard_instrs);
+            blocks.push(LlvmBlock {
+                label,
+                instrs,
+                terminator: LlvmTerminator::BrCond {
+                    cond,
+                    then_bb: success,
+                    else_bb: next,
+                },
+            });
+        }
+        blocks
+    }
+
+    fn lower_switch(
+        &self,
+        label: String,
+        occurrence: &Occurrence,
+        kind: SwitchKind,
+        cases: &[(Test, NodeId)],
+        default: Option<NodeId>,
+        ssa: &mut LlvmBuilder,
+    ) -> Vec<LlvmBlock> {
+        let place = describe_occurrence(occurrence);
+        let case_labels = cases
+            .iter()
+            .map(|(test, target)| (test.clone(), self.label(*target)))
+            .collect::<Vec<_>>();
+        // 既定分岐が不要なら（case が値を尽くすか網羅済み）最後の case を既定先にして
+        // 到達しない既定ブロックを作らない。
+        let default_label = default
+            .map(|id| self.label(id))
+            .or_else(|| case_labels.last().map(|(_, label)| label.clone()))
+            .unwrap_or_else(|| format!("match{}.fail", self.match_id));
+        let summary = format!(
+            "switch {} on {place}: [{}]{}",
+            match kind {
+                SwitchKind::Ctor => "ctor",
+                SwitchKind::Int => "int",
+                SwitchKind::Bool => "bool",
+                SwitchKind::SliceLen => "len",
+            },
+            cases
+                .iter()
+                .map(|(test, _)| describe_test(test))
+                .collect::<Vec<_>>()
+                .join(", "),
+            if default.is_some() { " + default" } else { "" }
+        );
+        let mut instrs = vec![LlvmInstr::Comment(summary)];
+        match kind {
+            SwitchKind::Bool => {
+                let value = self.project_scalar(occurrence, &ssa.bool_type(), &mut instrs, ssa);
+                let target_of = |wanted: bool| {
+                    case_labels
+                        .iter()
+                        .find(|(test, _)| *test == Test::Bool(wanted))
+                        .map(|(_, label)| label.clone())
+                        .unwrap_or_else(|| default_label.clone())
+                };
+                vec![LlvmBlock {
+                    label,
+                    instrs,
+                    terminator: LlvmTerminator::BrCond {
+                        cond: value,
+                        then_bb: target_of(true),
+                        else_bb: target_of(false),
+                    },
+                }]
+            }
+            SwitchKind::Int | SwitchKind::SliceLen => {
+                let value = if kind == SwitchKind::SliceLen {
+                    let operand = self.project(occurrence, &mut instrs, ssa);
+                    slice_len(&operand, &mut instrs, ssa)
+                } else {
+                    self.project_scalar(occurrence, "i64", &mut instrs, ssa)
+                };
+                let switch_cases = case_labels
+                    .iter()
+                    .map(|(test, target)| {
+                        let value = match test {
+                            Test::Int(value) => value.to_string(),
+                            Test::SliceLen { len, .. } => len.to_string(),
+                            other => describe_test(other),
+                        };
+                        (value, target.clone())
+                    })
+                    .collect();
+                vec![LlvmBlock {
+                    label,
+                    instrs,
+                    terminator: LlvmTerminator::Switch {
+                        ty: "i64".into(),
+                        value,
+                        default: default_label,
+                        cases: switch_cases,
+                    },
+                }]
+            }
+            SwitchKind::Ctor => self.lower_ctor_switch(
+                label,
+                occurrence,
+                instrs,
+                &case_labels,
+                default.map(|_| default_label),
+                ssa,
+            ),
+        }
+    }
+
+    fn lower_ctor_switch(
+        &self,
+        label: String,
+        occurrence: &Occurrence,
+        mut instrs: Vec<LlvmInstr>,
+        cases: &[(Test, String)],
+        default: Option<String>,
+        ssa: &mut LlvmBuilder,
+    ) -> Vec<LlvmBlock> {
+        let value = self.project(occurrence, &mut instrs, ssa);
+        let names = cases
+            .iter()
+            .filter_map(|(test, target)| match test {
+                Test::Ctor { name, .. } => Some((name.clone(), target.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let fallback = default
+            .clone()
+            .or_else(|| names.last().map(|(_, target)| target.clone()))
+            .unwrap_or_else(|| format!("match{}.fail", self.match_id));
+        let target_of = |wanted: &str| {
+            names
+                .iter()
+                .find(|(name, _)| name == wanted)
+                .map(|(_, target)| target.clone())
+                .unwrap_or_else(|| fallback.clone())
+        };
+
+        // Option は null 判定 1 回で分かれる。
+        if names
+            .iter()
+            .all(|(name, _)| name == "Some" || name == "None")
+        {
+            let cond = ssa.new_tmp("is_some");
+            instrs.push(LlvmInstr::Icmp {
+                result: cond.clone(),
+                pred: "ne".into(),
+                ty: ssa.pointer_type(),
+                lhs: value,
+                rhs: "null".into(),
+            });
+            return vec![LlvmBlock {
+                label,
+                instrs,
+                terminator: LlvmTerminator::BrCond {
+                    cond,
+                    then_bb: target_of("Some"),
+                    else_bb: target_of("None"),
+                },
+            }];
+        }
+
+        // 照合対象が和型ならタグ（宣言順の番号）で 1 回の switch にする。
+        let tag_indices = names
+            .iter()
+            .map(|(name, target)| {
+                self.constructors
+                    .iter()
+                    .position(|ctor| ctor == name)
+                    .map(|index| (index.to_string(), target.clone()))
+            })
+            .collect::<Option<Vec<_>>>();
+        if let (true, Some(switch_cases)) = (occurrence.is_empty(), tag_indices) {
+            let tag = ssa.new_tmp("tag");
+            instrs.push(LlvmInstr::Call {
+                result: Some(tag.clone()),
+                ret_ty: "i64".into(),
+                callee: INTRINSIC_CTOR_TAG.into(),
+                args: vec![(ssa.pointer_type(), value)],
+            });
+            return vec![LlvmBlock {
+                label,
+                instrs,
+                terminator: LlvmTerminator::Switch {
+                    ty: "i64".into(),
+                    value: tag,
+                    default: fallback,
+                    cases: switch_cases,
+                },
+            }];
+        }
+
+        // 型が分からないコンストラクタは 1 つずつ判定する。既定分岐が不要なら最後の判定を省く。
+        let tested = if default.is_some() {
+            names.len()
+        } else {
+            names.len().saturating_sub(1)
+        };
+        let mut blocks = Vec::new();
+        let mut current_label = label;
+        for (index, (name, target)) in names.iter().take(tested).enumerate() {
+            let cond = ssa.new_tmp("ctor");
+            instrs.push(LlvmInstr::Call {
+                result: Some(cond.clone()),
+                ret_ty: ssa.bool_type(),
+                callee: intrinsic_is_ctor(name),
+                args: vec![(ssa.pointer_type(), value.clone())],
+            });
+            let next = if index + 1 < tested {
+                format!("{current_label}.c{}", index + 1)
+            } else {
+                fallback.clone()
+            };
+            blocks.push(LlvmBlock {
+                label: current_label,
+                instrs: std::mem::take(&mut instrs),
+                terminator: LlvmTerminator::BrCond {
+                    cond,
+                    then_bb: target.clone(),
+                    else_bb: next.clone(),
+                },
+            });
+            current_label = next;
+        }
+        if blocks.is_empty() {
+            blocks.push(LlvmBlock {
+                label: current_label,
+                instrs,
+                terminator: LlvmTerminator::Br { target: fallback },
+            });
+        }
+        blocks
+    }
+
+    fn lower_test(
+        &self,
+        label: String,
+        occurrence: &Occurrence,
+        test: &Test,
+        then: NodeId,
+        otherwise: Option<NodeId>,
+        ssa: &mut LlvmBuilder,
+    ) -> LlvmBlock {
+        let place = describe_occurrence(occurrence);
+        let then_label = self.label(then);
+        let else_label = otherwise.map(|id| self.label(id));
+        let mut instrs = vec![LlvmInstr::Comment(format!(
+            "test {} on {place}",
+            describe_test(test)
+        ))];
+        let cond = match test {
+            Test::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                let value = self.project_scalar(occurrence, "i64", &mut instrs, ssa);
+                let mut conds = Vec::new();
+                if let Some(bound) = start {
+                    let cond = ssa.new_tmp("range_lo");
+                    instrs.push(LlvmInstr::Icmp {
+                        result: cond.clone(),
+                        pred: "sge".into(),
+                        ty: "i64".into(),
+                        lhs: value.clone(),
+                        rhs: bound.operand.clone(),
+                    });
+                    conds.push(cond);
+                }
+                if let Some(bound) = end {
+                    let cond = ssa.new_tmp("range_hi");
+                    instrs.push(LlvmInstr::Icmp {
+                        result: cond.clone(),
+                        pred: if *inclusive { "sle" } else { "slt" }.into(),
+                        ty: "i64".into(),
+                        lhs: value.clone(),
+                        rhs: bound.operand.clone(),
+                    });
+                    conds.push(cond);
+                }
+                match conds.as_slice() {
+                    [lo, hi] => {
+                        let cond = ssa.new_tmp("range");
+                        instrs.push(LlvmInstr::And {
+                            result: cond.clone(),
+                            lhs: lo.clone(),
+                            rhs: hi.clone(),
+                        });
+                        cond
+                    }
+                    [single] => single.clone(),
+                    _ => "true".into(),
+                }
+            }
+            Test::SliceLen { len, rest } => {
+                let operand = self.project(occurrence, &mut instrs, ssa);
+                let actual = slice_len(&operand, &mut instrs, ssa);
+                let cond = ssa.new_tmp("len_check");
+                instrs.push(LlvmInstr::Icmp {
+                    result: cond.clone(),
+                    pred: if *rest { "uge" } else { "eq" }.into(),
+                    ty: "i64".into(),
+                    lhs: actual,
+                    rhs: len.to_string(),
+                });
+                cond
+            }
+            Test::Active { name, partial } => {
+                let operand = self.project(occurrence, &mut instrs, ssa);
+                let result = ssa.new_tmp("active");
+                instrs.push(LlvmInstr::Call {
+                    result: Some(result.clone()),
+                    ret_ty: ssa.pointer_type(),
+                    callee: format!("@{}", sanitize_llvm_ident(name)),
+                    args: vec![(ssa.pointer_type(), operand)],
+                });
+                let mut key = occurrence.clone();
+                key.push(Access::ActiveResult {
+                    name: name.clone(),
+                    partial: *partial,
+                });
+                if let Some(slot) = self.active_slots.get(&key) {
+                    instrs.push(LlvmInstr::Store {
+                        ty: ssa.pointer_type(),
+                        ptr: slot.clone(),
+                        value: result.clone(),
+                    });
+                }
+                if *partial {
+                    let cond = ssa.new_tmp("is_some");
+                    instrs.push(LlvmInstr::Icmp {
+                        result: cond.clone(),
+                        pred: "ne".into(),
+                        ty: ssa.pointer_type(),
+                        lhs: result,
+                        rhs: "null".into(),
+                    });
+                    cond
+                } else {
+                    "true".into()
+                }
+            }
+            Test::Literal(summary) | Test::Regex(summary) => {
+                let operand = self.project(occurrence, &mut instrs, ssa);
+                let pattern = MirPattern {
+                    kind: match test {
+                        Test::Regex(_) => MirPatternKind::Regex {
+                            pattern: summary.clone(),
+                        },
+                        _ => MirPatternKind::Literal {
+                            summary: summary.clone(),
+                        },
+                    },
+                };
+                let miss = else_label.clone().unwrap_or_else(|| then_label.clone());
+                let (cond, mut cond_instrs) =
+                    emit_pattern_cond(ssa, &pattern, &operand, &place, &miss, "pat");
+                instrs.append(&mut cond_instrs);
+                cond
+            }
+            // 値の種類で分かれるテストは `switch` として下ろすのでここには来ない。
+            Test::Ctor { .. } | Test::Int(_) | Test::Bool(_) => "true".into(),
+        };
+        let terminator = match else_label {
+            Some(else_bb) => LlvmTerminator::BrCond {
+                cond,
+                then_bb: then_label,
+                else_bb,
+            },
+            None => LlvmTerminator::Br { target: then_label },
+        };
+        LlvmBlock {
+            label,
+            instrs,
+            terminator,
+        }
+    }
+}
+
+fn index_access(
+    base: String,
+    index: usize,
+    instrs: &mut Vec<LlvmInstr>,
+    ssa: &mut LlvmBuilder,
+) -> String {
+    let element = ssa.new_tmp("elem");
+    instrs.push(LlvmInstr::Call {
+        result: Some(element.clone()),
+        ret_ty: ssa.pointer_type(),
+        callee: INTRINSIC_INDEX_ACCESS.into(),
+        args: vec![
+            (ssa.pointer_type(), base),
+            ("i64".into(), index.to_string()),
+        ],
+    });
+    element
+}
+
+fn slice_len(operand: &str, instrs: &mut Vec<LlvmInstr>, ssa: &mut LlvmBuilder) -> String {
+    let len = ssa.new_tmp("len");
+    instrs.push(LlvmInstr::Call {
+        result: Some(len.clone()),
+        ret_ty: "i64".into(),
+        callee: "@len".into(),
+        args: vec![(ssa.pointer_type(), operand.to_string())],
+    });
+    len
+}
+
 #[derive(Clone, Copy, Debug)]
 enum ArmEarlyExit {
     Panic,
@@ -1705,13 +3333,13 @@ fn detect_arm_early_exit(
 fn lower_entry_expr_to_blocks(
     exprs: &[MirExpr],
     body: MirExprId,
-    type_mapping: &TypeMappingContext,
+    ssa_template: &LlvmBuilder,
 ) -> (Vec<BasicBlock>, Vec<LlvmBlock>) {
     let mut expr_map = HashMap::new();
     for expr in exprs {
         expr_map.insert(expr.id, expr);
     }
-    let mut ssa = LlvmBuilder::new(type_mapping.clone());
+    let mut ssa = ssa_template.clone();
     let body_ty_hint = infer_expr_type_hint(body, &expr_map, &ssa);
     if let Some(expr) = expr_map.get(&body) {
         match &expr.kind {
@@ -1724,7 +3352,7 @@ fn lower_entry_expr_to_blocks(
                 return lower_propagate_value_to_blocks(body, value, &body_ty_hint, &mut ssa);
             }
             MirExprKind::EffectBlock { body } | MirExprKind::Unsafe { body } => {
-                return lower_entry_expr_to_blocks(exprs, *body, type_mapping);
+                return lower_entry_expr_to_blocks(exprs, *body, ssa_template);
             }
             MirExprKind::Block {
                 statements,
@@ -1824,50 +3452,515 @@ fn lower_entry_expr_to_blocks(
                     return (blocks, llvm_blocks);
                 }
             }
-            _ => {}
+            _ => {}
+        }
+    }
+    let value = emit_value_expr(body, &expr_map, &mut ssa);
+    let block = BasicBlock {
+        label: "entry".into(),
+        instrs: vec![format!("exec body#{body}")],
+        terminator: format!("ret {}", value.operand),
+    };
+    let llvm_block = LlvmBlock {
+        label: "entry".into(),
+        instrs: {
+            let mut instrs = vec![LlvmInstr::Comment(format!("exec body#{body}"))];
+            instrs.extend(value.instrs);
+            instrs
+        },
+        terminator: LlvmTerminator::Ret(Some(value.operand)),
+    };
+    (vec![block], vec![llvm_block])
+}
+
+fn lower_entry_expr_to_blocks_via_operand(
+    expr_map: &HashMap<MirExprId, &MirExpr>,
+    body: MirExprId,
+    ssa: &mut LlvmBuilder,
+) -> (Vec<BasicBlock>, Vec<LlvmBlock>) {
+    let end_label = "entry.end".to_string();
+    let (mut blocks, mut llvm_blocks, operand, terminated) =
+        lower_expr_to_operand_blocks("entry".to_string(), body, expr_map, ssa, &end_label, None);
+    if terminated {
+        return (blocks, llvm_blocks);
+    }
+    let (operand, _) = operand.unwrap_or_else(|| ("null".into(), ssa.pointer_type()));
+    blocks.push(BasicBlock {
+        label: end_label.clone(),
+        instrs: vec![format!("ret {operand}")],
+        terminator: format!("ret {operand}"),
+    });
+    llvm_blocks.push(LlvmBlock {
+        label: end_label,
+        instrs: vec![LlvmInstr::Comment("ret operand".into())],
+        terminator: LlvmTerminator::Ret(Some(operand)),
+    });
+    (blocks, llvm_blocks)
+}
+
+/// 自己末尾呼び出しが戻るループ先頭のラベル。
+const TAILREC_LOOP_LABEL: &str = "tailrec.loop";
+
+/// 末尾呼び出しを下ろした関数本体。
+struct TailCallBlocks {
+    basic_blocks: Vec<BasicBlock>,
+    llvm_blocks: Vec<LlvmBlock>,
+    failures: Vec<TailCallFailure>,
+}
+
+/// 末尾呼び出しを含む関数本体を基本ブロックへ下ろす。
+///
+/// 自己末尾呼び出しは引数をループ用スロットへ書き戻して `tailrec.loop` へ分岐し、
+/// 他の関数への末尾呼び出しは `musttail`/`tail` 付きの直接呼び出しと `ret` にする。
+/// 下ろせる末尾呼び出しがない場合や、本体が早期脱出（`?`/panic）を含む場合は `None` を返す。
+fn lower_tail_calls_to_blocks(
+    mir: &MirFunction,
+    body: MirExprId,
+    caller: &FunctionPrototype,
+    prototypes: &HashMap<String, FunctionPrototype>,
+    ssa_template: &LlvmBuilder,
+) -> Option<TailCallBlocks> {
+    if mir.boxed_abi
+        || mir
+            .tail_calls
+            .iter()
+            .all(|call| call.kind == TailCallKind::Indirect)
+    {
+        return None;
+    }
+    let expr_map: HashMap<MirExprId, &MirExpr> =
+        mir.exprs.iter().map(|expr| (expr.id, expr)).collect();
+    if expr_contains_early_exit(body, &expr_map) {
+        return None;
+    }
+    let mut ssa = ssa_template.clone();
+    let mut lowering = TailCallLowering {
+        function: &mir.name,
+        expr_map,
+        kinds: mir
+            .tail_calls
+            .iter()
+            .map(|call| (call.expr, call.kind))
+            .collect(),
+        caller,
+        prototypes,
+        loop_slots: None,
+        blocks: Vec::new(),
+        failures: Vec::new(),
+    };
+    let body_label = lowering.begin_loop(mir, &mut ssa);
+    lowering.lower_tail(body, body_label, Vec::new(), &mut ssa);
+    let mut llvm_blocks = lowering.blocks;
+    if lowering.loop_slots.is_some() {
+        hoist_allocas_to_entry(&mut llvm_blocks);
+    }
+    Some(TailCallBlocks {
+        basic_blocks: llvm_blocks.iter().map(basic_block_from_llvm).collect(),
+        llvm_blocks,
+        failures: lowering.failures,
+    })
+}
+
+/// 末尾呼び出しを下ろせなかった `@tailrec` 関数の失敗箇所を集める。
+fn unlowered_tail_call_failures(mir: &MirFunction) -> Vec<TailCallFailure> {
+    mir.tail_calls
+        .iter()
+        .map(|call| {
+            let reason = if call.kind == TailCallKind::Indirect {
+                "間接呼び出しは呼び出し先のプロトタイプを確定できない"
+            } else if mir.boxed_abi {
+                "ボックス化 ABI の関数は戻り値を詰め直すため末尾呼び出しにできない"
+            } else {
+                "本体に `?` または panic による早期脱出がある"
+            };
+            TailCallFailure {
+                function: mir.name.clone(),
+                expr_id: Some(call.expr),
+                span: mir
+                    .exprs
+                    .iter()
+                    .find(|expr| expr.id == call.expr)
+                    .and_then(|expr| expr.span),
+                reason: reason.into(),
+            }
+        })
+        .collect()
+}
+
+/// ループ内のブロックにある `alloca` を入口ブロックの先頭へ移す（反復ごとにスタックを伸ばさないため）。
+fn hoist_allocas_to_entry(blocks: &mut [LlvmBlock]) {
+    let Some((entry, rest)) = blocks.split_first_mut() else {
+        return;
+    };
+    let mut hoisted = Vec::new();
+    for block in rest {
+        block.instrs.retain(|instr| {
+            if matches!(instr, LlvmInstr::Alloca { .. }) {
+                hoisted.push(instr.clone());
+                false
+            } else {
+                true
+            }
+        });
+    }
+    entry.instrs.splice(0..0, hoisted);
+}
+
+/// 末尾位置を辿りながらブロックを組み立てるときの関数単位の状態。
+struct TailCallLowering<'a> {
+    function: &'a str,
+    expr_map: HashMap<MirExprId, &'a MirExpr>,
+    kinds: HashMap<MirExprId, TailCallKind>,
+    caller: &'a FunctionPrototype,
+    prototypes: &'a HashMap<String, FunctionPrototype>,
+    /// 自己末尾呼び出しで書き戻す引数スロット（引数順）。
+    loop_slots: Option<Vec<LocalBinding>>,
+    blocks: Vec<LlvmBlock>,
+    failures: Vec<TailCallFailure>,
+}
+
+impl<'a> TailCallLowering<'a> {
+    /// 自己末尾呼び出しがあれば、引数をスロットへ移す入口ブロックを作ってループ先頭のラベルを返す。
+    fn begin_loop(&mut self, mir: &MirFunction, ssa: &mut LlvmBuilder) -> String {
+        let loops = self
+            .kinds
+            .values()
+            .any(|kind| *kind == TailCallKind::SelfRecursive);
+        if !loops
+            || mir.param_names.len() != mir.params.len()
+            || mir.param_names.iter().any(|name| name.is_empty())
+        {
+            return "entry".into();
+        }
+        let mut instrs = vec![LlvmInstr::Comment(format!(
+            "self tail calls of {} loop back to {TAILREC_LOOP_LABEL}",
+            self.function
+        ))];
+        let mut slots = Vec::new();
+        for (name, ty) in mir.param_names.iter().zip(&mir.params) {
+            let ty = ssa.type_mapping.layout_of(ty).description;
+            let value = match ssa.resolve_local(name) {
+                Some(binding) => {
+                    let value = ssa.new_tmp(name);
+                    instrs.push(LlvmInstr::Load {
+                        result: value.clone(),
+                        ty: binding.ty,
+                        ptr: binding.ptr,
+                    });
+                    value
+                }
+                None => format!("%{}", sanitize_llvm_ident(name)),
+            };
+            let ptr = ssa.new_tmp(&format!("{name}_loop"));
+            instrs.push(LlvmInstr::Alloca {
+                result: ptr.clone(),
+                ty: ty.clone(),
+            });
+            instrs.push(LlvmInstr::Store {
+                ty: ty.clone(),
+                ptr: ptr.clone(),
+                value,
+            });
+            let binding = LocalBinding { ptr, ty };
+            ssa.bind_local(name.clone(), binding.clone());
+            slots.push(binding);
+        }
+        self.blocks.push(LlvmBlock {
+            label: "entry".into(),
+            instrs,
+            terminator: LlvmTerminator::Br {
+                target: TAILREC_LOOP_LABEL.into(),
+            },
+        });
+        self.loop_slots = Some(slots);
+        TAILREC_LOOP_LABEL.into()
+    }
+
+    /// 末尾位置の式 `expr_id` を `label` のブロックから下ろす。`instrs` はそのブロックの先行命令。
+    fn lower_tail(
+        &mut self,
+        expr_id: MirExprId,
+        label: String,
+        mut instrs: Vec<LlvmInstr>,
+        ssa: &mut LlvmBuilder,
+    ) {
+        let Some(expr) = self.expr_map.get(&expr_id).copied() else {
+            self.lower_value_return(expr_id, label, instrs, ssa);
+            return;
+        };
+        match &expr.kind {
+            MirExprKind::Call { callee, args } => match self.kinds.get(&expr_id).copied() {
+                Some(TailCallKind::SelfRecursive) if self.loop_slots.is_some() => {
+                    self.lower_self_tail_call(expr_id, *callee, args, label, instrs, ssa)
+                }
+                Some(TailCallKind::SelfRecursive | TailCallKind::Direct) => {
+                    self.lower_direct_tail_call(expr_id, *callee, args, label, instrs, ssa)
+                }
+                Some(TailCallKind::Indirect) => {
+                    self.fail(
+                        expr_id,
+                        "間接呼び出しは呼び出し先のプロトタイプを確定できない",
+                    );
+                    self.lower_value_return(expr_id, label, instrs, ssa);
+                }
+                None => self.lower_value_return(expr_id, label, instrs, ssa),
+            },
+            MirExprKind::IfElse {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let (cond, cond_instrs) = emit_bool_expr(*condition, &self.expr_map, ssa);
+                instrs.extend(cond_instrs);
+                let then_label = format!("tail{expr_id}.then");
+                let else_label = format!("tail{expr_id}.else");
+                self.blocks.push(LlvmBlock {
+                    label,
+                    instrs,
+                    terminator: LlvmTerminator::BrCond {
+                        cond,
+                        then_bb: then_label.clone(),
+                        else_bb: else_label.clone(),
+                    },
+                });
+                for (branch, branch_label) in
+                    [(*then_branch, then_label), (*else_branch, else_label)]
+                {
+                    ssa.push_scope();
+                    self.lower_tail(branch, branch_label, Vec::new(), ssa);
+                    ssa.pop_scope();
+                }
+            }
+            MirExprKind::Block {
+                statements,
+                tail: Some(tail),
+                defers,
+                defer_lifo,
+            } if defers.is_empty() && defer_lifo.is_empty() => {
+                ssa.push_scope();
+                instrs.extend(emit_block_statement_instrs(statements, &self.expr_map, ssa));
+                self.lower_tail(*tail, label, instrs, ssa);
+                ssa.pop_scope();
+            }
+            MirExprKind::EffectBlock { body } | MirExprKind::Unsafe { body } => {
+                self.lower_tail(*body, label, instrs, ssa)
+            }
+            MirExprKind::Return { value: Some(value) } => {
+                self.lower_tail(*value, label, instrs, ssa)
+            }
+            MirExprKind::Match {
+                target,
+                arms,
+                lowering,
+            } => self.lower_match(
+                expr_id,
+                *target,
+                arms,
+                lowering.as_ref(),
+                label,
+                instrs,
+                ssa,
+            ),
+            _ => self.lower_value_return(expr_id, label, instrs, ssa),
+        }
+    }
+
+    /// 末尾位置にない形の式は値を求めてそのまま返す。
+    fn lower_value_return(
+        &mut self,
+        expr_id: MirExprId,
+        label: String,
+        mut instrs: Vec<LlvmInstr>,
+        ssa: &mut LlvmBuilder,
+    ) {
+        let value = emit_value_expr(expr_id, &self.expr_map, ssa);
+        instrs.extend(value.instrs);
+        self.blocks.push(LlvmBlock {
+            label,
+            instrs,
+            terminator: LlvmTerminator::Ret(Some(value.operand)),
+        });
+    }
+
+    /// 引数をすべて評価してからループ用スロットへ書き戻し、ループ先頭へ分岐する。
+    fn lower_self_tail_call(
+        &mut self,
+        expr_id: MirExprId,
+        callee: MirExprId,
+        args: &[MirExprId],
+        label: String,
+        mut instrs: Vec<LlvmInstr>,
+        ssa: &mut LlvmBuilder,
+    ) {
+        let slots = self.loop_slots.clone().unwrap_or_default();
+        if slots.len() != args.len() {
+            self.lower_direct_tail_call(expr_id, callee, args, label, instrs, ssa);
+            return;
+        }
+        instrs.push(LlvmInstr::Comment(format!(
+            "tail call self#{expr_id} -> {TAILREC_LOOP_LABEL}"
+        )));
+        let mut values = Vec::new();
+        for arg in args {
+            let value = emit_value_expr(*arg, &self.expr_map, ssa);
+            instrs.extend(value.instrs);
+            values.push(value.operand);
+        }
+        for (slot, value) in slots.into_iter().zip(values) {
+            instrs.push(LlvmInstr::Store {
+                ty: slot.ty,
+                ptr: slot.ptr,
+                value,
+            });
+        }
+        self.blocks.push(LlvmBlock {
+            label,
+            instrs,
+            terminator: LlvmTerminator::Br {
+                target: TAILREC_LOOP_LABEL.into(),
+            },
+        });
+    }
+
+    /// モジュール内の関数への末尾呼び出しを `musttail`/`tail` 付きの直接呼び出しにする。
+    fn lower_direct_tail_call(
+        &mut self,
+        expr_id: MirExprId,
+        callee: MirExprId,
+        args: &[MirExprId],
+        label: String,
+        mut instrs: Vec<LlvmInstr>,
+        ssa: &mut LlvmBuilder,
+    ) {
+        let name = match self.expr_map.get(&callee).map(|expr| &expr.kind) {
+            Some(MirExprKind::Identifier { summary }) => identifier_name(summary),
+            _ => String::new(),
+        };
+        let prototype = if self.kinds.get(&expr_id) == Some(&TailCallKind::SelfRecursive) {
+            Some(self.caller)
+        } else {
+            self.prototypes.get(&name)
+        };
+        let Some(prototype) = prototype.filter(|prototype| prototype.params.len() == args.len())
+        else {
+            self.fail(
+                expr_id,
+                format!("呼び出し先 {name} のプロトタイプが見つからない"),
+            );
+            self.lower_value_return(expr_id, label, instrs, ssa);
+            return;
+        };
+        let plan = plan_tail_call(self.caller, prototype);
+        if !plan.guaranteed {
+            self.fail(
+                expr_id,
+                format!("{name} とプロトタイプが一致せず、呼び出し規約も fastcc 同士ではない"),
+            );
+        }
+        let mut rendered_args = Vec::new();
+        for (arg, ty) in args.iter().zip(&prototype.params) {
+            let value = emit_value_expr(*arg, &self.expr_map, ssa);
+            instrs.extend(value.instrs);
+            rendered_args.push(format!("{ty} {}", value.operand));
+        }
+        let calling_conv = plan
+            .calling_conv
+            .map(|cc| format!("{cc} "))
+            .unwrap_or_default();
+        let call = format!(
+            "{} call {calling_conv}{} {}({})",
+            plan.marker.keyword(),
+            prototype.ret,
+            prototype.symbol,
+            rendered_args.join(", ")
+        );
+        let terminator = if prototype.ret == "void" {
+            instrs.push(LlvmInstr::Raw(call));
+            LlvmTerminator::Ret(None)
+        } else {
+            let result = ssa.new_tmp("tailcall");
+            instrs.push(LlvmInstr::Raw(format!("{result} = {call}")));
+            LlvmTerminator::Ret(Some(result))
+        };
+        self.blocks.push(LlvmBlock {
+            label,
+            instrs,
+            terminator,
+        });
+    }
+
+    /// 照合対象を評価して決定木へ下ろし、腕本体を末尾位置として続けて下ろす。
+    #[allow(clippy::too_many_arguments)]
+    fn lower_match(
+        &mut self,
+        match_id: MirExprId,
+        target: MirExprId,
+        arms: &[MirMatchArm],
+        plan: Option<&MatchLoweringPlan>,
+        label: String,
+        mut instrs: Vec<LlvmInstr>,
+        ssa: &mut LlvmBuilder,
+    ) {
+        let target_desc = match self.expr_map.get(&target).map(|expr| &expr.kind) {
+            Some(MirExprKind::Identifier { summary }) => summary.clone(),
+            _ => format!("#{target}"),
+        };
+        let target_value = emit_value_expr(target, &self.expr_map, ssa);
+        instrs.extend(target_value.instrs);
+        let tree = compile_match(arms, plan);
+        let mut lowering_ctx = MatchTreeLowering::new(
+            match_id,
+            &tree,
+            arms,
+            plan,
+            target_value.operand,
+            target_desc,
+            ssa,
+        );
+        let entry = lowering_ctx.entry_block(&tree);
+        instrs.extend(entry.instrs);
+        self.blocks.push(LlvmBlock {
+            label,
+            instrs,
+            terminator: entry.terminator,
+        });
+        for node in tree.reachable() {
+            if let Some(blocks) = lowering_ctx.lower_node(node, ssa) {
+                self.blocks.extend(blocks);
+            }
+        }
+        for index in tree.reachable_arms() {
+            let arm = &arms[index];
+            let body_label = format!("arm{index}.body#{}", arm.body);
+            ssa.push_scope();
+            lowering_ctx.bind_arm_slots(index, ssa);
+            self.blocks
+                .extend(lowering_ctx.lower_guards(index, &self.expr_map, ssa));
+            if let Some(alias) = &arm.alias {
+                self.blocks.push(LlvmBlock {
+                    label: format!("arm{index}.alias"),
+                    instrs: vec![LlvmInstr::Comment(format!(
+                        "alias {alias} = {}",
+                        lowering_ctx.target_desc
+                    ))],
+                    terminator: LlvmTerminator::Br {
+                        target: body_label.clone(),
+                    },
+                });
+            }
+            self.lower_tail(arm.body, body_label, Vec::new(), ssa);
+            ssa.pop_scope();
         }
     }
-    let value = emit_value_expr(body, &expr_map, &mut ssa);
-    let block = BasicBlock {
-        label: "entry".into(),
-        instrs: vec![format!("exec body#{body}")],
-        terminator: format!("ret {}", value.operand),
-    };
-    let llvm_block = LlvmBlock {
-        label: "entry".into(),
-        instrs: {
-            let mut instrs = vec![LlvmInstr::Comment(format!("exec body#{body}"))];
-            instrs.extend(value.instrs);
-            instrs
-        },
-        terminator: LlvmTerminator::Ret(Some(value.operand)),
-    };
-    (vec![block], vec![llvm_block])
-}
 
-fn lower_entry_expr_to_blocks_via_operand(
-    expr_map: &HashMap<MirExprId, &MirExpr>,
-    body: MirExprId,
-    ssa: &mut LlvmBuilder,
-) -> (Vec<BasicBlock>, Vec<LlvmBlock>) {
-    let end_label = "entry.end".to_string();
-    let (mut blocks, mut llvm_blocks, operand, terminated) =
-        lower_expr_to_operand_blocks("entry".to_string(), body, expr_map, ssa, &end_label, None);
-    if terminated {
-        return (blocks, llvm_blocks);
+    fn fail(&mut self, expr_id: MirExprId, reason: impl Into<String>) {
+        self.failures.push(TailCallFailure {
+            function: self.function.to_string(),
+            expr_id: Some(expr_id),
+            span: self.expr_map.get(&expr_id).and_then(|expr| expr.span),
+            reason: reason.into(),
+        });
     }
-    let (operand, _) = operand.unwrap_or_else(|| ("null".into(), ssa.pointer_type()));
-    blocks.push(BasicBlock {
-        label: end_label.clone(),
-        instrs: vec![format!("ret {operand}")],
-        terminator: format!("ret {operand}"),
-    });
-    llvm_blocks.push(LlvmBlock {
-        label: end_label,
-        instrs: vec![LlvmInstr::Comment("ret operand".into())],
-        terminator: LlvmTerminator::Ret(Some(operand)),
-    });
-    (blocks, llvm_blocks)
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -1977,8 +4070,11 @@ fn lower_call_with_propagate_to_blocks(
     let mut step_label = "entry".to_string();
     let mut next_index = 0usize;
     let mut operands: Vec<(String, String)> = Vec::new();
+    let direct = direct_call_prototype(callee, args.len(), expr_map, ssa);
     let mut steps = Vec::new();
-    steps.push(callee);
+    if direct.is_none() {
+        steps.push(callee);
+    }
     steps.extend_from_slice(args);
 
     for expr_id in steps {
@@ -2003,39 +4099,22 @@ fn lower_call_with_propagate_to_blocks(
         step_label = next_label;
     }
 
-    let callee_type = operands
-        .first()
-        .map(|(ty, _)| ty.clone())
-        .unwrap_or_else(|| ssa.pointer_type());
-    let callee_operand = operands
-        .first()
-        .map(|(_, op)| op.clone())
-        .unwrap_or_else(|| "null".into());
-    let mut call_args = Vec::new();
-    call_args.push((callee_type, callee_operand));
-    for (ty, op) in operands.into_iter().skip(1) {
-        call_args.push((ty, op));
-    }
-
-    let ret_ty = infer_call_return_type(callee, expr_map, ssa);
-    let result = ssa.new_tmp("call");
+    let (call, result) = match &direct {
+        Some(prototype) => direct_call_instr(prototype, operands, ssa),
+        None => intrinsic_call_instr(callee, operands, expr_map, ssa),
+    };
     let block = BasicBlock {
         label: step_label.clone(),
         instrs: vec![format!("exec call#{body}")],
-        terminator: format!("ret {result}"),
+        terminator: match &result {
+            Some((result, _)) => format!("ret {result}"),
+            None => "ret void".into(),
+        },
     };
     let llvm_block = LlvmBlock {
         label: step_label,
-        instrs: vec![
-            LlvmInstr::Comment(format!("exec call#{body}")),
-            LlvmInstr::Call {
-                result: Some(result.clone()),
-                ret_ty: ret_ty.clone(),
-                callee: INTRINSIC_CALL.into(),
-                args: call_args,
-            },
-        ],
-        terminator: LlvmTerminator::Ret(Some(result)),
+        instrs: vec![LlvmInstr::Comment(format!("exec call#{body}")), call],
+        terminator: LlvmTerminator::Ret(result.map(|(result, _)| result)),
     };
     blocks.push(block);
     llvm_blocks.push(llvm_block);
@@ -2061,8 +4140,11 @@ fn lower_call_with_propagate_to_operand_blocks(
     let mut step_label = label;
     let mut next_index = 0usize;
     let mut operands: Vec<(String, String)> = Vec::new();
+    let direct = direct_call_prototype(callee, args.len(), expr_map, ssa);
     let mut steps = Vec::new();
-    steps.push(callee);
+    if direct.is_none() {
+        steps.push(callee);
+    }
     steps.extend_from_slice(args);
 
     for expr_id in steps {
@@ -2087,22 +4169,10 @@ fn lower_call_with_propagate_to_operand_blocks(
         step_label = next_step_label;
     }
 
-    let callee_type = operands
-        .first()
-        .map(|(ty, _)| ty.clone())
-        .unwrap_or_else(|| ssa.pointer_type());
-    let callee_operand = operands
-        .first()
-        .map(|(_, op)| op.clone())
-        .unwrap_or_else(|| "null".into());
-    let mut call_args = Vec::new();
-    call_args.push((callee_type, callee_operand));
-    for (ty, op) in operands.into_iter().skip(1) {
-        call_args.push((ty, op));
-    }
-
-    let ret_ty = infer_call_return_type(callee, expr_map, ssa);
-    let result = ssa.new_tmp("call");
+    let (call, result) = match &direct {
+        Some(prototype) => direct_call_instr(prototype, operands, ssa),
+        None => intrinsic_call_instr(callee, operands, expr_map, ssa),
+    };
     let block = BasicBlock {
         label: step_label.clone(),
         instrs: vec![format!("exec call#{body}")],
@@ -2110,22 +4180,15 @@ fn lower_call_with_propagate_to_operand_blocks(
     };
     let llvm_block = LlvmBlock {
         label: step_label,
-        instrs: vec![
-            LlvmInstr::Comment(format!("exec call#{body}")),
-            LlvmInstr::Call {
-                result: Some(result.clone()),
-                ret_ty: ret_ty.clone(),
-                callee: INTRINSIC_CALL.into(),
-                args: call_args,
-            },
-        ],
+        instrs: vec![LlvmInstr::Comment(format!("exec call#{body}")), call],
         terminator: LlvmTerminator::Br {
             target: next_label.to_string(),
         },
     };
     blocks.push(block);
     llvm_blocks.push(llvm_block);
-    (blocks, llvm_blocks, Some((result, ret_ty)), false)
+    let result = result.unwrap_or_else(|| ("null".into(), ssa.pointer_type()));
+    (blocks, llvm_blocks, Some(result), false)
 }
 
 fn lower_binary_with_propagate_to_blocks(
@@ -3929,6 +5992,7 @@ fn lower_expr_to_operand_blocks(
     bool,
 ) {
     let Some(expr) = expr_map.get(&expr_id) else {
+        ssa.record_fallback(FALLBACK_EXPR_MISSING, Some(expr_id), "expr not found");
         let block = BasicBlock {
             label: label.clone(),
             instrs: vec![format!("exec expr#{expr_id} (missing)")],
@@ -4375,6 +6439,8 @@ fn infer_expr_type_hint(
         | MirExprKind::IfElse { .. }
         | MirExprKind::Match { .. }
         | MirExprKind::PerformCall { .. }
+        | MirExprKind::Handle { .. }
+        | MirExprKind::Resume { .. }
         | MirExprKind::Block { .. }
         | MirExprKind::Return { .. }
         | MirExprKind::Propagate { .. }
@@ -4555,6 +6621,7 @@ fn emit_block_statement_instrs(
                 ));
             }
             MirStmtKind::Defer { .. } => {
+                ssa.record_fallback(FALLBACK_DEFER_STATEMENT, None, "defer in statements");
                 instrs.push(LlvmInstr::Comment(
                     "defer statement skipped in block statements".into(),
                 ));
@@ -4598,6 +6665,11 @@ fn bind_pattern_operand(
             ptr: ptr.clone(),
             value: operand.clone(),
         });
+        instrs.push(LlvmInstr::DebugDeclare {
+            ptr: ptr.clone(),
+            name: name.clone(),
+            ty: ty.clone(),
+        });
         ssa.bind_local(
             name,
             LocalBinding {
@@ -4641,6 +6713,11 @@ fn rebind_target_operand(
                     value: value_operand,
                 });
             } else {
+                ssa.record_fallback(
+                    FALLBACK_ASSIGN_TARGET,
+                    Some(target_id),
+                    "missing target operand",
+                );
                 instrs.push(LlvmInstr::Comment(
                     "field assign skipped: missing target operand".into(),
                 ));
@@ -4654,6 +6731,11 @@ fn rebind_target_operand(
                     value: value_operand,
                 });
             } else {
+                ssa.record_fallback(
+                    FALLBACK_ASSIGN_TARGET,
+                    Some(target_id),
+                    "unsupported target",
+                );
                 instrs.push(LlvmInstr::Comment(
                     "assign target unsupported -> skipped".into(),
                 ));
@@ -4852,6 +6934,11 @@ fn emit_bool_expr(
                     "i64" => "0".into(),
                     _ => "null".into(),
                 };
+                ssa.record_fallback(
+                    FALLBACK_GUARD_OPERATOR,
+                    Some(expr_id),
+                    format!("operator={operator}"),
+                );
                 instrs.push(LlvmInstr::Comment(format!(
                     "guard binary op={operator} unsupported -> truthy check"
                 )));
@@ -4900,10 +6987,40 @@ fn emit_value_expr(
     expr_id: MirExprId,
     expr_map: &HashMap<MirExprId, &MirExpr>,
     ssa: &mut LlvmBuilder,
+) -> EmittedValue {
+    let outer = ssa.enter_expr(expr_id);
+    let mut value = emit_value_expr_without_loc(expr_id, expr_map, ssa);
+    ssa.leave_expr(outer);
+    if let Some(span) = expr_map.get(&expr_id).and_then(|expr| expr.span) {
+        attach_debug_loc(&mut value.instrs, span);
+    }
+    value
+}
+
+/// 式の命令列の先頭に位置情報を置く。部分式の位置で上書きされている場合は、
+/// 式自身の命令とみなす最後の命令の直前でも位置を張り直す。
+fn attach_debug_loc(instrs: &mut Vec<LlvmInstr>, span: MirSpan) {
+    let Some(last) = instrs.iter().rposition(|instr| !instr.is_debug_marker()) else {
+        return;
+    };
+    let nested = instrs[..last]
+        .iter()
+        .any(|instr| matches!(instr, LlvmInstr::DebugLoc(_)));
+    if nested {
+        instrs.insert(last, LlvmInstr::DebugLoc(span));
+    }
+    instrs.insert(0, LlvmInstr::DebugLoc(span));
+}
+
+fn emit_value_expr_without_loc(
+    expr_id: MirExprId,
+    expr_map: &HashMap<MirExprId, &MirExpr>,
+    ssa: &mut LlvmBuilder,
 ) -> EmittedValue {
     let expr = match expr_map.get(&expr_id) {
         Some(expr) => expr,
         None => {
+            ssa.record_fallback(FALLBACK_EXPR_MISSING, Some(expr_id), "expr not found");
             return EmittedValue {
                 ty: ssa.pointer_type(),
                 operand: format!("#{}", expr_id),
@@ -4991,6 +7108,13 @@ fn emit_value_expr(
                         }],
                     };
                 }
+                if let Some(ty) = ssa.resolve_param(&name) {
+                    return EmittedValue {
+                        ty,
+                        operand: format!("%{}", sanitize_llvm_ident(&name)),
+                        instrs: vec![],
+                    };
+                }
             }
             EmittedValue {
                 ty: ssa.pointer_type(),
@@ -4998,13 +7122,23 @@ fn emit_value_expr(
                 instrs: vec![],
             }
         }
+        MirExprKind::Lambda {
+            captures,
+            symbol: Some(symbol),
+            ..
+        } => emit_closure_value(expr_id, symbol, captures, ssa),
         MirExprKind::Lambda { captures, .. } => {
-            let symbol = lambda_stub_symbol(expr_id, !captures.is_empty());
+            // クロージャ変換で持ち上げられなかったラムダ。存在しない関数を参照しないよう null にする。
+            ssa.record_fallback(
+                FALLBACK_LAMBDA,
+                Some(expr_id),
+                format!("unconverted lambda captures={}", captures.len()),
+            );
             EmittedValue {
                 ty: ssa.pointer_type(),
-                operand: symbol.clone(),
+                operand: "null".into(),
                 instrs: vec![LlvmInstr::Comment(format!(
-                    "lambda expr#{expr_id} -> {symbol}"
+                    "lambda expr#{expr_id} unconverted -> null"
                 ))],
             }
         }
@@ -5083,7 +7217,28 @@ fn emit_value_expr(
                 instrs,
             }
         }
+        MirExprKind::Call { callee, args } if is_closure_callee(*callee, expr_map, ssa) => {
+            emit_closure_call(expr, *callee, args, expr_map, ssa)
+        }
         MirExprKind::Call { callee, args } => {
+            if let Some(prototype) = direct_call_prototype(*callee, args.len(), expr_map, ssa) {
+                let mut instrs = Vec::new();
+                let mut lowered_args = Vec::new();
+                for arg in args {
+                    let value = emit_value_expr(*arg, expr_map, ssa);
+                    instrs.extend(value.instrs);
+                    lowered_args.push((value.ty, value.operand));
+                }
+                let (call, result) = direct_call_instr(&prototype, lowered_args.clone(), ssa);
+                instrs.push(call);
+                release_fresh_closures(args, &lowered_args, expr_map, &mut instrs);
+                let (operand, ty) = result.unwrap_or_else(|| ("null".into(), ssa.pointer_type()));
+                return EmittedValue {
+                    ty,
+                    operand,
+                    instrs,
+                };
+            }
             let callee_value = emit_value_expr(*callee, expr_map, ssa);
             let mut instrs = callee_value.instrs;
             let mut lowered_args: Vec<(String, String)> = Vec::new();
@@ -5099,8 +7254,9 @@ fn emit_value_expr(
                 result: Some(result.clone()),
                 ret_ty: ret_ty.clone(),
                 callee: INTRINSIC_CALL.into(),
-                args: lowered_args,
+                args: lowered_args.clone(),
             });
+            release_fresh_closures(args, &lowered_args[1..], expr_map, &mut instrs);
             EmittedValue {
                 ty: ret_ty,
                 operand: result,
@@ -5347,6 +7503,15 @@ fn emit_value_expr(
                 }
             }
             if !invalid_placeholders.is_empty() {
+                ssa.record_fallback(
+                    FALLBACK_LLVM_IR_PLACEHOLDER,
+                    Some(expr_id),
+                    format!(
+                        "placeholders={:?} inputs={}",
+                        invalid_placeholders,
+                        input_operands.len()
+                    ),
+                );
                 instrs.push(LlvmInstr::Comment(format!(
                     "llvm_ir expr#{expr_id} invalid placeholders: {:?}",
                     invalid_placeholders
@@ -5528,13 +7693,20 @@ fn emit_value_expr(
                     instrs,
                 };
             }
-            _ => EmittedValue {
-                ty: ssa.pointer_type(),
-                operand: format!("#{}", expr_id),
-                instrs: vec![LlvmInstr::Comment(format!(
-                    "binary op {operator} unsupported -> fallback #{expr_id}"
-                ))],
-            },
+            _ => {
+                ssa.record_fallback(
+                    FALLBACK_BINARY_OPERATOR,
+                    Some(expr_id),
+                    format!("operator={operator}"),
+                );
+                EmittedValue {
+                    ty: ssa.pointer_type(),
+                    operand: format!("#{}", expr_id),
+                    instrs: vec![LlvmInstr::Comment(format!(
+                        "binary op {operator} unsupported -> fallback #{expr_id}"
+                    ))],
+                }
+            }
         },
         MirExprKind::IfElse {
             condition,
@@ -5569,35 +7741,450 @@ fn emit_value_expr(
             }
         }
         MirExprKind::PerformCall { effect, argument } => {
-            let value = emit_value_expr(*argument, expr_map, ssa);
+            let name = identifier_name(effect);
+            let (effect_name, operation) = match name.rsplit_once("::") {
+                Some((effect_name, operation)) => (effect_name.to_string(), operation.to_string()),
+                None => (name.clone(), String::new()),
+            };
+            let value = ensure_effect_value_pointer(emit_value_expr(*argument, expr_map, ssa), ssa);
             let mut instrs = value.instrs;
             let result = ssa.new_tmp("perform");
             instrs.push(LlvmInstr::Call {
-                result: Some(result.clone()),
-                ret_ty: ssa.pointer_type(),
-                callee: INTRINSIC_PERFORM.into(),
-                args: vec![
-                    (
-                        ssa.pointer_type(),
-                        format!("\"{}\"", effect.replace('"', "\\\"")),
-                    ),
-                    (value.ty, value.operand),
-                ],
+                result: Some(result.clone()),
+                ret_ty: ssa.pointer_type(),
+                callee: INTRINSIC_PERFORM.into(),
+                args: vec![
+                    (ssa.pointer_type(), llvm_c_string_operand(&effect_name)),
+                    (ssa.pointer_type(), llvm_c_string_operand(&operation)),
+                    (ssa.pointer_type(), value.operand),
+                ],
+            });
+            unbox_effect_result(expr, result, instrs, ssa)
+        }
+        MirExprKind::Handle {
+            effect,
+            target,
+            operations,
+            return_clause,
+            lowered: true,
+        } => {
+            let mut instrs = vec![LlvmInstr::Comment(format!(
+                "handle expr#{expr_id} effect={effect} operations={}",
+                operations.len()
+            ))];
+            let return_closure = match return_clause {
+                Some(clause) => {
+                    let value = emit_value_expr(clause.body, expr_map, ssa);
+                    instrs.extend(value.instrs);
+                    value.operand
+                }
+                None => "null".to_string(),
+            };
+            let handler = ssa.new_tmp("handler");
+            instrs.push(LlvmInstr::Call {
+                result: Some(handler.clone()),
+                ret_ty: ssa.pointer_type(),
+                callee: INTRINSIC_HANDLER_NEW.into(),
+                args: vec![
+                    (ssa.pointer_type(), llvm_c_string_operand(effect)),
+                    (ssa.pointer_type(), return_closure),
+                ],
+            });
+            for operation in operations {
+                let clause = emit_value_expr(operation.body, expr_map, ssa);
+                instrs.extend(clause.instrs);
+                let tail_resumptive = u8::from(operation.tail_resumptive).to_string();
+                instrs.push(LlvmInstr::Call {
+                    result: None,
+                    ret_ty: "void".into(),
+                    callee: INTRINSIC_HANDLER_ADD_OP.into(),
+                    args: vec![
+                        (ssa.pointer_type(), handler.clone()),
+                        (ssa.pointer_type(), llvm_c_string_operand(&operation.name)),
+                        (ssa.pointer_type(), clause.operand),
+                        ("i32".into(), tail_resumptive),
+                    ],
+                });
+            }
+            let body = emit_value_expr(*target, expr_map, ssa);
+            instrs.extend(body.instrs);
+            let result = ssa.new_tmp("handle");
+            instrs.push(LlvmInstr::Call {
+                result: Some(result.clone()),
+                ret_ty: ssa.pointer_type(),
+                callee: INTRINSIC_HANDLE.into(),
+                args: vec![
+                    (ssa.pointer_type(), handler),
+                    (ssa.pointer_type(), body.operand),
+                ],
+            });
+            unbox_effect_result(expr, result, instrs, ssa)
+        }
+        MirExprKind::Resume {
+            continuation,
+            value,
+        } => {
+            let continuation = emit_value_expr(*continuation, expr_map, ssa);
+            let value = ensure_effect_value_pointer(emit_value_expr(*value, expr_map, ssa), ssa);
+            let mut instrs = continuation.instrs;
+            instrs.extend(value.instrs);
+            let result = ssa.new_tmp("resume");
+            instrs.push(LlvmInstr::Call {
+                result: Some(result.clone()),
+                ret_ty: ssa.pointer_type(),
+                callee: INTRINSIC_RESUME.into(),
+                args: vec![
+                    (ssa.pointer_type(), continuation.operand),
+                    (ssa.pointer_type(), value.operand),
+                ],
+            });
+            unbox_effect_result(expr, result, instrs, ssa)
+        }
+        MirExprKind::Handle { .. } => {
+            ssa.record_fallback(
+                FALLBACK_EXPR_UNSUPPORTED,
+                Some(expr_id),
+                "kind=handle (not lowered)",
+            );
+            EmittedValue {
+                ty: ssa.pointer_type(),
+                operand: format!("#{}", expr_id),
+                instrs: vec![LlvmInstr::Comment(format!(
+                    "expr#{expr_id} handle without effect lowering -> fallback operand"
+                ))],
+            }
+        }
+        MirExprKind::Match { .. } | MirExprKind::Unknown => {
+            let kind = if matches!(expr.kind, MirExprKind::Match { .. }) {
+                "match"
+            } else {
+                "unknown"
+            };
+            ssa.record_fallback(
+                FALLBACK_EXPR_UNSUPPORTED,
+                Some(expr_id),
+                format!("kind={kind}"),
+            );
+            EmittedValue {
+                ty: ssa.pointer_type(),
+                operand: format!("#{}", expr_id),
+                instrs: vec![LlvmInstr::Comment(format!(
+                    "expr#{expr_id} unsupported -> fallback operand"
+                ))],
+            }
+        }
+    }
+}
+
+/// 効果操作へ渡す値をボックス化する（Unit は `null` のまま渡す）。
+fn ensure_effect_value_pointer(value: EmittedValue, ssa: &mut LlvmBuilder) -> EmittedValue {
+    if value.ty == ssa.pointer_type() {
+        return value;
+    }
+    ensure_record_field_pointer(value, ssa)
+}
+
+/// Runtime から返るボックス化された値を式の型へ取り出す。
+fn unbox_effect_result(
+    expr: &MirExpr,
+    boxed: String,
+    mut instrs: Vec<LlvmInstr>,
+    ssa: &mut LlvmBuilder,
+) -> EmittedValue {
+    let ty = map_type_token_to_llvm(&expr.ty, ssa).unwrap_or_else(|| ssa.pointer_type());
+    let Some(unbox) = unbox_intrinsic_for_type(&ty, ssa) else {
+        return EmittedValue {
+            ty: ssa.pointer_type(),
+            operand: boxed,
+            instrs,
+        };
+    };
+    let result = ssa.new_tmp("unbox");
+    instrs.push(LlvmInstr::Call {
+        result: Some(result.clone()),
+        ret_ty: ty.clone(),
+        callee: unbox.into(),
+        args: vec![(ssa.pointer_type(), boxed)],
+    });
+    EmittedValue {
+        ty,
+        operand: result,
+        instrs,
+    }
+}
+
+/// 効果名・操作名を Runtime の `const char*` 引数として渡すオペランド。
+fn llvm_c_string_operand(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\\\""))
+}
+
+/// 持ち上げた関数と環境 Record からクロージャ値を組み立てる。
+/// 環境はキャプチャ順に値を格納し、所有権は `reml_closure_new` 後にクロージャへ移す。
+fn emit_closure_value(
+    expr_id: MirExprId,
+    symbol: &str,
+    captures: &[MirLambdaCapture],
+    ssa: &mut LlvmBuilder,
+) -> EmittedValue {
+    let symbol = sanitize_llvm_symbol(symbol);
+    let mut instrs = vec![LlvmInstr::Comment(format!(
+        "closure expr#{expr_id} -> {symbol} captures={}",
+        captures.len()
+    ))];
+    let env = if captures.is_empty() {
+        "null".to_string()
+    } else {
+        let mut args = vec![("i64".to_string(), captures.len().to_string())];
+        let mut boxed = Vec::new();
+        for capture in captures {
+            if capture.mutable {
+                // 再代入される可変キャプチャは値のコピーになり、書き込みが共有されない。
+                ssa.record_fallback(
+                    FALLBACK_MUTABLE_CAPTURE,
+                    Some(expr_id),
+                    format!("mutable capture `{}` is copied", capture.name),
+                );
+            }
+            let value = emit_capture_value(&capture.name, ssa);
+            let needs_box = value.ty != ssa.pointer_type();
+            let value = ensure_record_field_pointer(value, ssa);
+            instrs.extend(value.instrs);
+            if needs_box && value.operand != "null" {
+                boxed.push(value.operand.clone());
+            }
+            args.push((ssa.pointer_type(), value.operand));
+        }
+        let env = ssa.new_tmp("closure_env");
+        instrs.push(LlvmInstr::Call {
+            result: Some(env.clone()),
+            ret_ty: ssa.pointer_type(),
+            callee: INTRINSIC_RECORD_FROM.into(),
+            args,
+        });
+        // reml_record_from が inc_ref するため、一時ボックスの参照を手放す。
+        for operand in boxed {
+            instrs.push(emit_dec_ref(operand, ssa));
+        }
+        env
+    };
+    let result = ssa.new_tmp("closure");
+    instrs.push(LlvmInstr::Call {
+        result: Some(result.clone()),
+        ret_ty: ssa.pointer_type(),
+        callee: INTRINSIC_CLOSURE_NEW.into(),
+        args: vec![
+            (ssa.pointer_type(), env.clone()),
+            (ssa.pointer_type(), symbol),
+        ],
+    });
+    if !captures.is_empty() {
+        instrs.push(emit_dec_ref(env, ssa));
+    }
+    EmittedValue {
+        ty: ssa.pointer_type(),
+        operand: result,
+        instrs,
+    }
+}
+
+/// キャプチャ対象の現在値を読み出す（ローカル束縛・引数・その他の順に解決する）。
+fn emit_capture_value(name: &str, ssa: &mut LlvmBuilder) -> EmittedValue {
+    if let Some(binding) = ssa.resolve_local(name) {
+        let result = ssa.new_tmp("load");
+        return EmittedValue {
+            ty: binding.ty.clone(),
+            operand: result.clone(),
+            instrs: vec![LlvmInstr::Load {
+                result,
+                ty: binding.ty,
+                ptr: binding.ptr,
+            }],
+        };
+    }
+    EmittedValue {
+        ty: ssa
+            .resolve_param(name)
+            .unwrap_or_else(|| ssa.pointer_type()),
+        operand: format!("%{}", sanitize_llvm_ident(name)),
+        instrs: vec![],
+    }
+}
+
+fn emit_dec_ref(operand: String, ssa: &LlvmBuilder) -> LlvmInstr {
+    LlvmInstr::Call {
+        result: None,
+        ret_ty: "void".into(),
+        callee: INTRINSIC_DEC_REF.into(),
+        args: vec![(ssa.pointer_type(), operand)],
+    }
+}
+
+/// 呼び出し先がクロージャ値（ローカル・引数・ラムダ式・関数値を返す式）かどうか。
+fn is_closure_callee(
+    callee: MirExprId,
+    expr_map: &HashMap<MirExprId, &MirExpr>,
+    ssa: &LlvmBuilder,
+) -> bool {
+    let Some(expr) = expr_map.get(&callee) else {
+        return false;
+    };
+    match &expr.kind {
+        MirExprKind::Lambda { symbol, .. } => symbol.is_some(),
+        MirExprKind::Identifier { summary } => {
+            let name = identifier_name(summary);
+            parse_function_type_token(&expr.ty).is_some()
+                && (ssa.resolve_local(&name).is_some() || ssa.resolve_param(&name).is_some())
+        }
+        MirExprKind::Call { .. } | MirExprKind::FieldAccess { .. } | MirExprKind::Index { .. } => {
+            parse_function_type_token(&expr.ty).is_some()
+        }
+        _ => false,
+    }
+}
+
+/// ローカルに束縛されていないモジュール内関数を名指しする呼び出しなら、そのプロトタイプを返す。
+/// 引数の数が一致しない場合は `@reml_call` 経由のまま残す。
+fn direct_call_prototype(
+    callee: MirExprId,
+    arity: usize,
+    expr_map: &HashMap<MirExprId, &MirExpr>,
+    ssa: &LlvmBuilder,
+) -> Option<FunctionPrototype> {
+    let MirExprKind::Identifier { summary } = &expr_map.get(&callee)?.kind else {
+        return None;
+    };
+    let name = identifier_name(summary);
+    if ssa.resolve_local(&name).is_some() || ssa.resolve_param(&name).is_some() {
+        return None;
+    }
+    ssa.prototypes
+        .get(&name)
+        .filter(|prototype| prototype.params.len() == arity)
+        .cloned()
+}
+
+/// 評価済みの引数でモジュール内関数を直接呼び出す。戻り値のない関数は Unit（`null`）を返す。
+fn direct_call_instr(
+    prototype: &FunctionPrototype,
+    args: Vec<(String, String)>,
+    ssa: &mut LlvmBuilder,
+) -> (LlvmInstr, Option<(String, String)>) {
+    let args = prototype
+        .params
+        .iter()
+        .zip(args)
+        .map(|(ty, (_, operand))| (ty.clone(), operand))
+        .collect();
+    let result = (prototype.ret != "void").then(|| ssa.new_tmp("call"));
+    let instr = LlvmInstr::Call {
+        result: result.clone(),
+        ret_ty: prototype.ret.clone(),
+        callee: prototype.symbol.clone(),
+        args,
+    };
+    (instr, result.map(|result| (result, prototype.ret.clone())))
+}
+
+/// 呼び出し先を先頭に置いた評価済みオペランドで `@reml_call` を呼び出す。
+fn intrinsic_call_instr(
+    callee: MirExprId,
+    operands: Vec<(String, String)>,
+    expr_map: &HashMap<MirExprId, &MirExpr>,
+    ssa: &mut LlvmBuilder,
+) -> (LlvmInstr, Option<(String, String)>) {
+    let mut args = operands;
+    if args.is_empty() {
+        args.push((ssa.pointer_type(), "null".into()));
+    }
+    let ret_ty = infer_call_return_type(callee, expr_map, ssa);
+    let result = ssa.new_tmp("call");
+    let instr = LlvmInstr::Call {
+        result: Some(result.clone()),
+        ret_ty: ret_ty.clone(),
+        callee: INTRINSIC_CALL.into(),
+        args,
+    };
+    (instr, Some((result, ret_ty)))
+}
+
+/// クロージャ値の呼び出しを `code_ptr(env, args...)` の間接呼び出しへ下ろす。
+fn emit_closure_call(
+    expr: &MirExpr,
+    callee: MirExprId,
+    args: &[MirExprId],
+    expr_map: &HashMap<MirExprId, &MirExpr>,
+    ssa: &mut LlvmBuilder,
+) -> EmittedValue {
+    let closure = emit_value_expr(callee, expr_map, ssa);
+    let mut instrs = closure.instrs;
+    let code = ssa.new_tmp("closure_code");
+    instrs.push(LlvmInstr::Call {
+        result: Some(code.clone()),
+        ret_ty: ssa.pointer_type(),
+        callee: INTRINSIC_CLOSURE_CODE_PTR.into(),
+        args: vec![(ssa.pointer_type(), closure.operand.clone())],
+    });
+    let env = ssa.new_tmp("closure_env");
+    instrs.push(LlvmInstr::Call {
+        result: Some(env.clone()),
+        ret_ty: ssa.pointer_type(),
+        callee: INTRINSIC_CLOSURE_ENV.into(),
+        args: vec![(ssa.pointer_type(), closure.operand.clone())],
+    });
+    let mut lowered_args = Vec::new();
+    for arg in args {
+        let value = emit_value_expr(*arg, expr_map, ssa);
+        instrs.extend(value.instrs);
+        lowered_args.push((value.ty, value.operand));
+    }
+    let ret_ty = map_type_token_to_llvm(&expr.ty, ssa)
+        .unwrap_or_else(|| infer_call_return_type(callee, expr_map, ssa));
+    let result = ssa.new_tmp("call");
+    let mut call_args = vec![(ssa.pointer_type(), env)];
+    call_args.extend(lowered_args.iter().cloned());
+    instrs.push(LlvmInstr::Call {
+        result: Some(result.clone()),
+        ret_ty: ret_ty.clone(),
+        callee: code,
+        args: call_args,
+    });
+    release_fresh_closures(args, &lowered_args, expr_map, &mut instrs);
+    release_fresh_closures(
+        &[callee],
+        &[(ssa.pointer_type(), closure.operand)],
+        expr_map,
+        &mut instrs,
+    );
+    EmittedValue {
+        ty: ret_ty,
+        operand: result,
+        instrs,
+    }
+}
+
+/// 呼び出しのためだけに生成したクロージャ（ラムダ式の実引数）を呼び出し後に解放する。
+fn release_fresh_closures(
+    ids: &[MirExprId],
+    lowered: &[(String, String)],
+    expr_map: &HashMap<MirExprId, &MirExpr>,
+    instrs: &mut Vec<LlvmInstr>,
+) {
+    for (id, (ty, operand)) in ids.iter().zip(lowered) {
+        let fresh = matches!(
+            expr_map.get(id).map(|expr| &expr.kind),
+            Some(MirExprKind::Lambda {
+                symbol: Some(_),
+                ..
+            })
+        );
+        if fresh {
+            instrs.push(LlvmInstr::Call {
+                result: None,
+                ret_ty: "void".into(),
+                callee: INTRINSIC_DEC_REF.into(),
+                args: vec![(ty.clone(), operand.clone())],
             });
-            EmittedValue {
-                ty: ssa.pointer_type(),
-                operand: result,
-                instrs,
-            }
         }
-        MirExprKind::Match { .. }
-        | MirExprKind::Unknown => EmittedValue {
-            ty: ssa.pointer_type(),
-            operand: format!("#{}", expr_id),
-            instrs: vec![LlvmInstr::Comment(format!(
-                "expr#{expr_id} unsupported -> fallback operand"
-            ))],
-        },
     }
 }
 
@@ -5855,6 +8442,16 @@ fn extract_literal_operand(summary: &str) -> Option<String> {
     }
 }
 
+/// パターンのリテラル要約を決定木が区別する種類に分ける。
+pub(crate) fn classify_pattern_literal(summary: &str) -> PatternLiteral {
+    match parse_literal_summary(summary) {
+        LiteralSummary::Int(value) => PatternLiteral::Int(value),
+        LiteralSummary::Bool(value) => PatternLiteral::Bool(value),
+        LiteralSummary::String(_) => PatternLiteral::String,
+        _ => PatternLiteral::Other,
+    }
+}
+
 fn parse_literal_summary(summary: &str) -> LiteralSummary {
     let trimmed = summary.trim();
     if trimmed == "unit" {
@@ -6216,6 +8813,7 @@ fn emit_array_element_expr(element: &serde_json::Value, ssa: &mut LlvmBuilder) -
             _ => {}
         }
     }
+    ssa.record_fallback(FALLBACK_ARRAY_ELEMENT, None, "unsupported array element");
     EmittedValue {
         ty: ssa.pointer_type(),
         operand: "null".into(),
@@ -6265,6 +8863,7 @@ fn emit_record_field_expr(field: &serde_json::Value, ssa: &mut LlvmBuilder) -> E
             _ => {}
         }
     }
+    ssa.record_fallback(FALLBACK_RECORD_FIELD, None, "unsupported record field");
     EmittedValue {
         ty: ssa.pointer_type(),
         operand: "null".into(),
@@ -6287,6 +8886,7 @@ fn ensure_array_element_pointer(value: EmittedValue, ssa: &mut LlvmBuilder) -> E
     } else if value.ty == "Str" {
         (INTRINSIC_BOX_STRING, "Str".to_string())
     } else {
+        ssa.record_fallback(FALLBACK_ARRAY_ELEMENT, None, format!("unsupported type {}", value.ty));
         instrs.push(LlvmInstr::Comment(format!(
             "array element unsupported type {} -> null",
             value.ty
@@ -6329,7 +8929,10 @@ fn ensure_record_field_pointer(value: EmittedValue, ssa: &mut LlvmBuilder) -> Em
         (INTRINSIC_BOX_BOOL, ssa.bool_type())
     } else if value.ty == "Str" {
         (INTRINSIC_BOX_STRING, "Str".to_string())
+    } else if value.ty == "double" {
+        (INTRINSIC_BOX_FLOAT, "double".to_string())
     } else {
+        ssa.record_fallback(FALLBACK_RECORD_FIELD, None, format!("unsupported type {}", value.ty));
         instrs.push(LlvmInstr::Comment(format!(
             "record field unsupported type {} -> null",
             value.ty
@@ -6436,6 +9039,7 @@ fn emit_set_element_expr(element: &serde_json::Value, ssa: &mut LlvmBuilder) ->
             _ => {}
         }
     }
+    ssa.record_fallback(FALLBACK_SET_ELEMENT, None, "unsupported set element");
     EmittedValue {
         ty: ssa.pointer_type(),
         operand: "null".into(),
@@ -6503,10 +9107,18 @@ fn emit_unsupported_literal_value(
     detail: Option<String>,
 ) -> EmittedValue {
     let mut message = format!("diag backend.literal.unsupported.{kind}");
-    if let Some(detail) = detail {
+    if let Some(detail) = &detail {
         message.push_str(": ");
-        message.push_str(&detail);
-    }
+        message.push_str(detail);
+    }
+    ssa.record_fallback(
+        FALLBACK_LITERAL,
+        None,
+        match detail {
+            Some(detail) => format!("kind={kind} {detail}"),
+            None => format!("kind={kind}"),
+        },
+    );
     EmittedValue {
         ty: ssa.pointer_type(),
         operand: "null".into(),
@@ -6660,409 +9272,6 @@ fn emit_pattern_cond(
     }
 }
 
-fn emit_pattern_blocks(
-    arm_index: usize,
-    pattern: &MirPattern,
-    success_label: &str,
-    next_arm_label: &str,
-    target_operand: &str,
-    target_desc: &str,
-    ssa: &mut LlvmBuilder,
-) -> (Vec<BasicBlock>, Vec<LlvmBlock>) {
-    match &pattern.kind {
-        MirPatternKind::Constructor { name, args } if !args.is_empty() => {
-            let outer_label = format!("arm{arm_index}.pat");
-            let payload_label = format!("arm{arm_index}.ctor_payload");
-
-            let (outer_cond, mut outer_instrs) = if name == "Some" {
-                let cond = ssa.new_tmp("ctor");
-                (
-                    cond.clone(),
-                    vec![
-                        LlvmInstr::Comment(format!(
-                            "ctor_check(Some) on {target_desc} -> non-null then {payload_label} else {next_arm_label}"
-                        )),
-                        LlvmInstr::Icmp {
-                            result: cond,
-                            pred: "ne".into(),
-                            ty: ssa.pointer_type(),
-                            lhs: target_operand.to_string(),
-                            rhs: "null".into(),
-                        },
-                    ],
-                )
-            } else {
-                let cond = ssa.new_tmp("ctor");
-                (
-                    cond.clone(),
-                    vec![
-                        LlvmInstr::Comment(format!(
-                            "ctor_check({name}) on {target_desc} -> then {payload_label} else {next_arm_label}"
-                        )),
-                        LlvmInstr::Call {
-                            result: Some(cond),
-                            ret_ty: ssa.bool_type(),
-                            callee: intrinsic_is_ctor(name),
-                            args: vec![(ssa.pointer_type(), target_operand.to_string())],
-                        },
-                    ],
-                )
-            };
-
-            let outer_bb = BasicBlock {
-                label: outer_label.clone(),
-                instrs: vec![format!(
-                    "check ctor({name}, args={}) on {target_desc}",
-                    args.len()
-                )],
-                terminator: format!(
-                    "br_if {outer_cond} then {payload_label} else {next_arm_label}"
-                ),
-            };
-            let outer_llvm_bb = LlvmBlock {
-                label: outer_label.clone(),
-                instrs: {
-                    outer_instrs.insert(
-                        0,
-                        LlvmInstr::Comment(pattern_check_label(
-                            pattern,
-                            target_desc,
-                            next_arm_label,
-                        )),
-                    );
-                    outer_instrs
-                },
-                terminator: LlvmTerminator::BrCond {
-                    cond: outer_cond,
-                    then_bb: payload_label.clone(),
-                    else_bb: next_arm_label.to_string(),
-                },
-            };
-
-            let payload_var = ssa.new_tmp("payload");
-            let payload_desc = format!("payload({target_desc}.{name})");
-            let mut payload_instrs = Vec::new();
-            payload_instrs.push(LlvmInstr::Comment(format!(
-                "{payload_desc} <- {target_desc}"
-            )));
-            payload_instrs.push(LlvmInstr::Call {
-                result: Some(payload_var.clone()),
-                ret_ty: ssa.pointer_type(),
-                callee: intrinsic_ctor_payload(name),
-                args: vec![(ssa.pointer_type(), target_operand.to_string())],
-            });
-            let (inner_cond, mut inner_instrs) = match args.len() {
-                1 => emit_pattern_cond(
-                    ssa,
-                    &args[0],
-                    &payload_var,
-                    &payload_desc,
-                    next_arm_label,
-                    "ctor",
-                ),
-                _ => synthesize_pattern_check_cond(
-                    ssa,
-                    format!(
-                        "ctor_check({name}, args={}) (multi-arg payload matching unsupported)",
-                        args.len()
-                    ),
-                    &payload_var,
-                    "ctor",
-                ),
-            };
-            payload_instrs.append(&mut inner_instrs);
-            let payload_bb = BasicBlock {
-                label: payload_label.clone(),
-                instrs: vec![format!(
-                    "check ctor payload args={} on {payload_desc}",
-                    args.len()
-                )],
-                terminator: format!(
-                    "br_if {inner_cond} then {success_label} else {next_arm_label}"
-                ),
-            };
-            let payload_llvm_bb = LlvmBlock {
-                label: payload_label.clone(),
-                instrs: payload_instrs,
-                terminator: LlvmTerminator::BrCond {
-                    cond: inner_cond,
-                    then_bb: success_label.to_string(),
-                    else_bb: next_arm_label.to_string(),
-                },
-            };
-
-            (
-                vec![outer_bb, payload_bb],
-                vec![outer_llvm_bb, payload_llvm_bb],
-            )
-        }
-        MirPatternKind::Or { variants } => {
-            let mut blocks = Vec::new();
-            let mut llvm_blocks = Vec::new();
-            for (idx, variant) in variants.iter().enumerate() {
-                let miss_target = if idx + 1 == variants.len() {
-                    next_arm_label.to_string()
-                } else {
-                    format!("arm{arm_index}.or{}", idx + 1)
-                };
-                let check = pattern_check_label(variant, target_desc, &miss_target);
-                let (cond, llvm_instrs) = emit_pattern_cond(
-                    ssa,
-                    variant,
-                    target_operand,
-                    target_desc,
-                    &miss_target,
-                    "or",
-                );
-                let label = format!("arm{arm_index}.or{idx}");
-                blocks.push(BasicBlock {
-                    label: label.clone(),
-                    instrs: vec![format!("check {}", check)],
-                    terminator: format!(
-                        "br_if {cond} then {success} else {miss}",
-                        cond = cond,
-                        success = success_label,
-                        miss = miss_target
-                    ),
-                });
-                llvm_blocks.push(LlvmBlock {
-                    label: label.clone(),
-                    instrs: llvm_instrs,
-                    terminator: LlvmTerminator::BrCond {
-                        cond,
-                        then_bb: success_label.to_string(),
-                        else_bb: miss_target.clone(),
-                    },
-                });
-            }
-            (blocks, llvm_blocks)
-        }
-        MirPatternKind::Range {
-            start,
-            end,
-            inclusive,
-        } => {
-            let mut instrs = Vec::new();
-            let mut llvm_instrs = Vec::new();
-            let mut cond = String::from("true");
-            if let Some(lo) = start {
-                let lhs = render_range_bound(lo);
-                let var = format!("tmp_arm{arm_index}_ge");
-                instrs.push(format!("{var} = icmp_ge {target_desc}, {lhs}"));
-                llvm_instrs.push(LlvmInstr::Icmp {
-                    result: var.clone(),
-                    pred: "sge".into(),
-                    ty: "i64".into(),
-                    lhs: target_operand.into(),
-                    rhs: lhs,
-                });
-                cond = var.clone();
-            }
-            if let Some(hi) = end {
-                let rhs = render_range_bound(hi);
-                let op = if *inclusive { "icmp_le" } else { "icmp_lt" };
-                let var = format!("tmp_arm{arm_index}_hi");
-                instrs.push(format!("{var} = {op} {target_desc}, {rhs}"));
-                llvm_instrs.push(LlvmInstr::Icmp {
-                    result: var.clone(),
-                    pred: if *inclusive {
-                        "sle".into()
-                    } else {
-                        "slt".into()
-                    },
-                    ty: "i64".into(),
-                    lhs: target_operand.into(),
-                    rhs,
-                });
-                if cond != "true" {
-                    let and_var = format!("tmp_arm{arm_index}_range");
-                    instrs.push(format!("{and_var} = and {cond}, {var}"));
-                    llvm_instrs.push(LlvmInstr::And {
-                        result: and_var.clone(),
-                        lhs: cond.clone(),
-                        rhs: var.clone(),
-                    });
-                    cond = and_var;
-                } else {
-                    cond = var;
-                }
-            }
-            let bb = BasicBlock {
-                label: format!("arm{arm_index}.pat"),
-                instrs,
-                terminator: format!(
-                    "br_if {cond} then {success} else {miss}",
-                    success = success_label,
-                    miss = next_arm_label
-                ),
-            };
-            let llvm_bb = LlvmBlock {
-                label: bb.label.clone(),
-                instrs: llvm_instrs,
-                terminator: LlvmTerminator::BrCond {
-                    cond,
-                    then_bb: success_label.to_string(),
-                    else_bb: next_arm_label.to_string(),
-                },
-            };
-            (vec![bb], vec![llvm_bb])
-        }
-        MirPatternKind::Slice(MirSlicePattern { head, rest, tail }) => {
-            let mut instrs = Vec::new();
-            let mut llvm_instrs = Vec::new();
-            let len_var = format!("len_arm{arm_index}");
-            let need = head.len() + tail.len();
-            instrs.push(format!("{len_var} = len({target_desc})"));
-            llvm_instrs.push(LlvmInstr::Call {
-                result: Some(len_var.clone()),
-                ret_ty: "i64".into(),
-                callee: "@len".into(),
-                args: vec![(ssa.pointer_type(), target_operand.into())],
-            });
-            let check_var = format!("tmp_arm{arm_index}_len");
-            if rest.is_some() {
-                instrs.push(format!("{check_var} = icmp_uge {len_var}, {need}"));
-                llvm_instrs.push(LlvmInstr::Icmp {
-                    result: check_var.clone(),
-                    pred: "uge".into(),
-                    ty: "i64".into(),
-                    lhs: len_var.clone(),
-                    rhs: need.to_string(),
-                });
-            } else {
-                instrs.push(format!("{check_var} = icmp_eq {len_var}, {need}"));
-                llvm_instrs.push(LlvmInstr::Icmp {
-                    result: check_var.clone(),
-                    pred: "eq".into(),
-                    ty: "i64".into(),
-                    lhs: len_var.clone(),
-                    rhs: need.to_string(),
-                });
-            }
-            instrs.push(format!(
-                "slice_bind head[{}], tail[{}], rest={}",
-                head.len(),
-                tail.len(),
-                rest.is_some()
-            ));
-            let bb = BasicBlock {
-                label: format!("arm{arm_index}.pat"),
-                instrs,
-                terminator: format!(
-                    "br_if {check} then {success} else {miss}",
-                    check = check_var,
-                    success = success_label,
-                    miss = next_arm_label
-                ),
-            };
-            let llvm_bb = LlvmBlock {
-                label: bb.label.clone(),
-                instrs: llvm_instrs,
-                terminator: LlvmTerminator::BrCond {
-                    cond: check_var,
-                    then_bb: success_label.to_string(),
-                    else_bb: next_arm_label.to_string(),
-                },
-            };
-            (vec![bb], vec![llvm_bb])
-        }
-        MirPatternKind::Active(MirActivePatternCall {
-            name,
-            kind,
-            miss_target,
-            ..
-        }) => {
-            let mut instrs = Vec::new();
-            let mut llvm_instrs = Vec::new();
-            let call_var = format!("tmp_arm{arm_index}_active");
-            instrs.push(format!("{call_var} = call active {name}({target_desc})"));
-            llvm_instrs.push(LlvmInstr::Call {
-                result: Some(call_var.clone()),
-                ret_ty: "ptr".into(),
-                callee: format!("@{}", sanitize_llvm_ident(name)),
-                args: vec![(ssa.pointer_type(), target_operand.into())],
-            });
-            let cond = match kind {
-                ActivePatternKind::Partial => {
-                    let check_var = format!("tmp_arm{arm_index}_is_some");
-                    instrs.push(format!("{check_var} = option_is_some {call_var}"));
-                    llvm_instrs.push(LlvmInstr::Icmp {
-                        result: check_var.clone(),
-                        pred: "ne".into(),
-                        ty: "ptr".into(),
-                        lhs: call_var.clone(),
-                        rhs: "null".into(),
-                    });
-                    check_var
-                }
-                _ => "true".into(),
-            };
-            let miss = miss_target
-                .as_ref()
-                .map(|_| next_arm_label.to_string())
-                .unwrap_or_else(|| next_arm_label.to_string());
-            let bb = BasicBlock {
-                label: format!("arm{arm_index}.pat"),
-                instrs,
-                terminator: format!(
-                    "br_if {cond} then {success} else {miss}",
-                    success = success_label,
-                    miss = miss
-                ),
-            };
-            let llvm_bb = LlvmBlock {
-                label: bb.label.clone(),
-                instrs: llvm_instrs,
-                terminator: LlvmTerminator::BrCond {
-                    cond,
-                    then_bb: success_label.to_string(),
-                    else_bb: miss,
-                },
-            };
-            (vec![bb], vec![llvm_bb])
-        }
-        _ => {
-            let check = pattern_check_label(pattern, target_desc, next_arm_label);
-            let (cond, llvm_instrs) = emit_pattern_cond(
-                ssa,
-                pattern,
-                target_operand,
-                target_desc,
-                next_arm_label,
-                "pat",
-            );
-            let bb = BasicBlock {
-                label: format!("arm{arm_index}.pat"),
-                instrs: vec![format!("check {}", check)],
-                terminator: format!(
-                    "br_if {cond} then {success} else {miss}",
-                    cond = cond,
-                    success = success_label,
-                    miss = next_arm_label
-                ),
-            };
-            let llvm_bb = LlvmBlock {
-                label: bb.label.clone(),
-                instrs: llvm_instrs,
-                terminator: LlvmTerminator::BrCond {
-                    cond,
-                    then_bb: success_label.to_string(),
-                    else_bb: next_arm_label.to_string(),
-                },
-            };
-            (vec![bb], vec![llvm_bb])
-        }
-    }
-}
-
-fn render_range_bound(pattern: &MirPattern) -> String {
-    match &pattern.kind {
-        MirPatternKind::Literal { summary } => summary.clone(),
-        MirPatternKind::Var { name } => name.clone(),
-        _ => summarize_pattern(pattern),
-    }
-}
-
 fn pattern_check_label(pattern: &MirPattern, target_label: &str, miss_label: &str) -> String {
     match &pattern.kind {
         MirPatternKind::Wildcard => format!("match_any({target_label})"),
@@ -0,0 +1,586 @@
+//! LLVM IR へ DWARF デバッグ情報（`!dbg` メタデータ）を付与する。
+//!
+//! MIR に残ったソース位置を `DILocation` へ、関数を `DISubprogram` へ、
+//! ローカル束縛を `DILocalVariable` へ変換し、型は `TypeMappingContext` の
+//! レイアウトから `DIBasicType` などの型記述子を組み立てる。
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::codegen::{LlvmFunction, LlvmInstr, MirFunction, MirSpan};
+use crate::type_mapping::{RemlType, TypeMappingContext};
+
+/// 生成する DWARF のバージョン。
+const DWARF_VERSION: u32 = 4;
+/// LLVM が要求するデバッグ情報メタデータ形式のバージョン。
+const DEBUG_INFO_VERSION: u32 = 3;
+const PRODUCER: &str = "remlc";
+
+/// バイトオフセットを 1 始まりの行・列へ変換するソース対応表。
+#[derive(Clone, Debug)]
+pub struct DebugSourceMap {
+    pub path: String,
+    line_starts: Vec<u32>,
+}
+
+impl DebugSourceMap {
+    /// `line_starts` は各行の先頭バイトオフセット（昇順）。
+    pub fn new(path: impl Into<String>, line_starts: Vec<u32>) -> Self {
+        let mut line_starts = line_starts;
+        if line_starts.first() != Some(&0) {
+            line_starts.insert(0, 0);
+        }
+        Self {
+            path: path.into(),
+            line_starts,
+        }
+    }
+
+    pub fn from_source(path: impl Into<String>, source: &str) -> Self {
+        let line_starts = source
+            .match_indices('\n')
+            .map(|(offset, _)| offset as u32 + 1)
+            .collect();
+        Self::new(path, line_starts)
+    }
+
+    /// オフセットに対応する行と列（どちらも 1 始まり、列はバイト単位）。
+    pub fn line_col(&self, offset: u32) -> (u32, u32) {
+        let line = self
+            .line_starts
+            .partition_point(|start| *start <= offset)
+            .max(1);
+        let column = offset - self.line_starts[line - 1] + 1;
+        (line as u32, column)
+    }
+
+    fn file_name(&self) -> String {
+        Path::new(&self.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.clone())
+    }
+
+    fn directory(&self) -> String {
+        Path::new(&self.path)
+            .parent()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .filter(|dir| !dir.is_empty())
+            .unwrap_or_else(|| ".".into())
+    }
+}
+
+/// モジュール単位でメタデータノードを採番し、関数本体へ `!dbg` を付与する。
+#[derive(Clone, Debug)]
+pub struct DebugInfoBuilder {
+    source: DebugSourceMap,
+    type_mapping: TypeMappingContext,
+    nodes: Vec<String>,
+    uniqued: HashMap<String, usize>,
+    file: usize,
+    compile_unit: usize,
+    module_flags: Vec<usize>,
+    uses_declare: bool,
+}
+
+impl DebugInfoBuilder {
+    pub fn new(source: DebugSourceMap, type_mapping: TypeMappingContext) -> Self {
+        let mut builder = Self {
+            source,
+            type_mapping,
+            nodes: Vec::new(),
+            uniqued: HashMap::new(),
+            file: 0,
+            compile_unit: 0,
+            module_flags: Vec::new(),
+            uses_declare: false,
+        };
+        builder.file = builder.node(format!(
+            "!DIFile(filename: \"{}\", directory: \"{}\")",
+            escape_metadata_string(&builder.source.file_name()),
+            escape_metadata_string(&builder.source.directory())
+        ));
+        builder.compile_unit = builder.distinct_node(format!(
+            "!DICompileUnit(language: DW_LANG_C, file: !{}, producer: \"{PRODUCER}\", isOptimized: false, runtimeVersion: 0, emissionKind: FullDebug)",
+            builder.file
+        ));
+        builder.module_flags = vec![
+            builder.node(format!(
+                "!{{i32 2, !\"Dwarf Version\", i32 {DWARF_VERSION}}}"
+            )),
+            builder.node(format!(
+                "!{{i32 2, !\"Debug Info Version\", i32 {DEBUG_INFO_VERSION}}}"
+            )),
+        ];
+        builder
+    }
+
+    pub fn source(&self) -> &DebugSourceMap {
+        &self.source
+    }
+
+    /// `llvm.dbg.declare` の宣言がモジュールに必要かどうか。
+    pub fn uses_dbg_declare(&self) -> bool {
+        self.uses_declare
+    }
+
+    /// `!dbg` を付与した関数定義を描画する。
+    pub fn render_function(&mut self, function: &LlvmFunction, mir: &MirFunction) -> String {
+        let line = mir
+            .span
+            .or_else(|| mir.exprs.iter().find_map(|expr| expr.span))
+            .map(|span| self.source.line_col(span.start).0)
+            .unwrap_or(0);
+        let subroutine_type = self.subroutine_type(mir);
+        let subprogram = self.distinct_node(format!(
+            "!DISubprogram(name: \"{}\", scope: !{file}, file: !{file}, line: {line}, type: !{subroutine_type}, scopeLine: {line}, spFlags: DISPFlagDefinition, unit: !{})",
+            escape_metadata_string(mir.name.trim_start_matches('@')),
+            self.compile_unit,
+            file = self.file,
+        ));
+        let symbol = function.symbol();
+        let mut buf = vec![format!(
+            "define {} {}({}) !dbg !{subprogram} {{",
+            function.ret,
+            symbol,
+            function.params.join(", ")
+        )];
+        let mut location = (line, 0);
+        let mut location_node = self.location(location, subprogram);
+        for block in &function.blocks {
+            buf.push(format!("{}:", block.label));
+            for instr in &block.instrs {
+                match instr {
+                    LlvmInstr::DebugLoc(span) => {
+                        location = self.line_col(*span);
+                        location_node = self.location(location, subprogram);
+                    }
+                    LlvmInstr::DebugDeclare { ptr, name, ty } => {
+                        let ty = self.type_node(&reml_type_for_llvm(ty));
+                        let variable = self.node(format!(
+                            "!DILocalVariable(name: \"{}\", scope: !{subprogram}, file: !{}, line: {}, type: {})",
+                            escape_metadata_string(name),
+                            self.file,
+                            location.0,
+                            metadata_ref(ty)
+                        ));
+                        self.uses_declare = true;
+                        buf.push(format!(
+                            "  call void @llvm.dbg.declare(metadata ptr {ptr}, metadata !{variable}, metadata !DIExpression()), !dbg !{location_node}"
+                        ));
+                    }
+                    other => {
+                        let rendered = other.describe();
+                        if accepts_dbg_attachment(other, &rendered) {
+                            buf.push(format!("  {rendered}, !dbg !{location_node}"));
+                        } else {
+                            buf.push(format!("  {rendered}"));
+                        }
+                    }
+                }
+            }
+            buf.push(format!(
+                "  {}, !dbg !{location_node}",
+                block.terminator.render(&function.ret)
+            ));
+        }
+        buf.push("}".into());
+        buf.join("\n")
+    }
+
+    /// 名前付きメタデータと採番済みノードを描画する。
+    pub fn render_metadata(&self) -> String {
+        let flags = self
+            .module_flags
+            .iter()
+            .map(|id| format!("!{id}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut buf = vec![
+            format!("!llvm.dbg.cu = !{{!{}}}", self.compile_unit),
+            format!("!llvm.module.flags = !{{{flags}}}"),
+            String::new(),
+        ];
+        buf.extend(
+            self.nodes
+                .iter()
+                .enumerate()
+                .map(|(id, body)| format!("!{id} = {body}")),
+        );
+        buf.join("\n")
+    }
+
+    fn line_col(&self, span: MirSpan) -> (u32, u32) {
+        self.source.line_col(span.start)
+    }
+
+    fn location(&mut self, (line, column): (u32, u32), scope: usize) -> usize {
+        self.node(format!(
+            "!DILocation(line: {line}, column: {column}, scope: !{scope})"
+        ))
+    }
+
+    fn subroutine_type(&mut self, mir: &MirFunction) -> usize {
+        let mut types = vec![mir.ret.as_ref().and_then(|ty| self.type_node(ty))];
+        for param in &mir.params {
+            types.push(self.type_node(param));
+        }
+        let types = self.node(format!(
+            "!{{{}}}",
+            types
+                .into_iter()
+                .map(metadata_ref)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        self.node(format!("!DISubroutineType(types: !{types})"))
+    }
+
+    /// 型記述子を返す。値を持たない `Unit` は `None`（`null`）とする。
+    fn type_node(&mut self, ty: &RemlType) -> Option<usize> {
+        let bits = self.type_mapping.layout_of(ty).size * 8;
+        let body = match ty {
+            RemlType::Unit => return None,
+            RemlType::Bool => basic_type("Bool", bits, "DW_ATE_boolean"),
+            RemlType::I32 => basic_type("Int32", bits, "DW_ATE_signed"),
+            RemlType::I64 => basic_type("Int", bits, "DW_ATE_signed"),
+            RemlType::F64 => basic_type("Float", bits, "DW_ATE_float"),
+            RemlType::Pointer | RemlType::Set(_) => {
+                format!("!DIDerivedType(tag: DW_TAG_pointer_type, baseType: null, size: {bits})")
+            }
+            RemlType::Ref { to, .. } => {
+                let base = self.type_node(to);
+                format!(
+                    "!DIDerivedType(tag: DW_TAG_pointer_type, baseType: {}, size: {bits})",
+                    metadata_ref(base)
+                )
+            }
+            RemlType::String | RemlType::Slice(_) => {
+                let data = self.type_node(&RemlType::Pointer);
+                let len = self.type_node(&RemlType::I64);
+                let name = if matches!(ty, RemlType::String) {
+                    "Str"
+                } else {
+                    "Slice"
+                };
+                self.structure_type(name, bits, &[("data", data, 64), ("len", len, 64)])
+            }
+            RemlType::Array { element, length } => {
+                let base = self.type_node(element);
+                let subrange = self.node(format!("!{{!DISubrange(count: {length})}}"));
+                format!(
+                    "!DICompositeType(tag: DW_TAG_array_type, baseType: {}, size: {bits}, elements: !{subrange})",
+                    metadata_ref(base)
+                )
+            }
+            RemlType::RowTuple(fields) => {
+                let members = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(index, field)| {
+                        let size = self.type_mapping.layout_of(field).size * 8;
+                        (format!("_{index}"), self.type_node(field), size)
+                    })
+                    .collect::<Vec<_>>();
+                let members = members
+                    .iter()
+                    .map(|(name, ty, size)| (name.as_str(), *ty, *size))
+                    .collect::<Vec<_>>();
+                self.structure_type("Tuple", bits, &members)
+            }
+            RemlType::Adt { .. } => self.structure_type("Adt", bits, &[]),
+        };
+        Some(self.node(body))
+    }
+
+    /// メンバーを先頭から詰めて配置した構造体型。アラインメントはレイアウト計算に従う。
+    fn structure_type(
+        &mut self,
+        name: &str,
+        bits: u64,
+        members: &[(&str, Option<usize>, u64)],
+    ) -> String {
+        let mut offset: u64 = 0;
+        let mut member_nodes = Vec::new();
+        for (member, ty, size) in members {
+            let align = (*size).clamp(8, 64);
+            offset = offset.div_ceil(align) * align;
+            member_nodes.push(self.node(format!(
+                "!DIDerivedType(tag: DW_TAG_member, name: \"{member}\", file: !{}, baseType: {}, size: {size}, offset: {offset})",
+                self.file,
+                metadata_ref(*ty)
+            )));
+            offset += size;
+        }
+        let elements = self.node(format!(
+            "!{{{}}}",
+            member_nodes
+                .iter()
+                .map(|id| format!("!{id}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        format!(
+            "!DICompositeType(tag: DW_TAG_structure_type, name: \"{name}\", file: !{}, size: {bits}, elements: !{elements})",
+            self.file
+        )
+    }
+
+    /// 同一内容のノードは 1 つにまとめる。
+    fn node(&mut self, body: String) -> usize {
+        if let Some(id) = self.uniqued.get(&body) {
+            return *id;
+        }
+        let id = self.nodes.len();
+        self.uniqued.insert(body.clone(), id);
+        self.nodes.push(body);
+        id
+    }
+
+    fn distinct_node(&mut self, body: String) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(format!("distinct {body}"));
+        id
+    }
+}
+
+fn basic_type(name: &str, bits: u64, encoding: &str) -> String {
+    format!("!DIBasicType(name: \"{name}\", size: {bits}, encoding: {encoding})")
+}
+
+fn metadata_ref(id: Option<usize>) -> String {
+    id.map(|id| format!("!{id}"))
+        .unwrap_or_else(|| "null".into())
+}
+
+/// `alloca` の LLVM 型表記から型記述子の元になる Reml 型を推定する。
+fn reml_type_for_llvm(ty: &str) -> RemlType {
+    match ty {
+        "i1" => RemlType::Bool,
+        "i32" => RemlType::I32,
+        "i64" => RemlType::I64,
+        "double" => RemlType::F64,
+        "Str" | "{i8*, i64}" => RemlType::String,
+        "{ptr, i64}" => RemlType::Slice(Box::new(RemlType::Pointer)),
+        _ => RemlType::Pointer,
+    }
+}
+
+/// コメントや複数行の生 IR には `!dbg` を付与できない。
+fn accepts_dbg_attachment(instr: &LlvmInstr, rendered: &str) -> bool {
+    match instr {
+        LlvmInstr::Comment(_) => false,
+        LlvmInstr::Raw(_) => {
+            let trimmed = rendered.trim_start();
+            !trimmed.is_empty() && !trimmed.starts_with(';') && !rendered.contains('\n')
+        }
+        _ => true,
+    }
+}
+
+fn escape_metadata_string(value: &str) -> String {
+    let mut escaped = String::new();
+    for ch in value.chars() {
+        match ch {
+            '"' | '\\' => escaped.push_str(&format!("\\{:02X}", ch as u32)),
+            ch if ch.is_control() => escaped.push_str(&format!("\\{:02X}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DebugInfoBuilder, DebugSourceMap};
+    use crate::codegen::{
+        LlvmBlock, LlvmFunction, LlvmInstr, LlvmTerminator, MirFunction, MirSpan,
+    };
+    use crate::target_machine::DataLayoutSpec;
+    use crate::type_mapping::{RemlType, TypeMappingContext};
+    use std::process::Command;
+    use std::{env, fs};
+
+    const SOURCE: &str = "fn add(a: Int, b: Int) -> Int {\n  let sum = a + b\n  sum\n}\n";
+
+    fn builder() -> DebugInfoBuilder {
+        DebugInfoBuilder::new(
+            DebugSourceMap::from_source("/work/app.reml", SOURCE),
+            TypeMappingContext::new(DataLayoutSpec::system_v()),
+        )
+    }
+
+    fn span_of(text: &str) -> MirSpan {
+        let start = SOURCE.find(text).expect("テキストがソースに含まれること") as u32;
+        MirSpan {
+            start,
+            end: start + text.len() as u32,
+        }
+    }
+
+    fn add_function() -> (LlvmFunction, MirFunction) {
+        let mir = MirFunction::new("add", "ccc")
+            .with_param(RemlType::I64)
+            .with_param(RemlType::I64)
+            .with_return(RemlType::I64)
+            .with_span(span_of("fn add"));
+        let function = LlvmFunction {
+            name: "add".into(),
+            params: vec!["i64 %a".into(), "i64 %b".into()],
+            ret: "i64".into(),
+            blocks: vec![LlvmBlock {
+                label: "entry".into(),
+                instrs: vec![
+                    LlvmInstr::DebugLoc(span_of("a + b")),
+                    LlvmInstr::BinOp {
+                        result: "%sum1".into(),
+                        op: "add".into(),
+                        ty: "i64".into(),
+                        lhs: "%a".into(),
+                        rhs: "%b".into(),
+                    },
+                    LlvmInstr::Comment("let sum".into()),
+                    LlvmInstr::Alloca {
+                        result: "%sum_addr2".into(),
+                        ty: "i64".into(),
+                    },
+                    LlvmInstr::Store {
+                        ty: "i64".into(),
+                        ptr: "%sum_addr2".into(),
+                        value: "%sum1".into(),
+                    },
+                    LlvmInstr::DebugDeclare {
+                        ptr: "%sum_addr2".into(),
+                        name: "sum".into(),
+                        ty: "i64".into(),
+                    },
+                    LlvmInstr::DebugLoc(span_of("sum\n}")),
+                    LlvmInstr::Load {
+                        result: "%sum3".into(),
+                        ty: "i64".into(),
+                        ptr: "%sum_addr2".into(),
+                    },
+                ],
+                terminator: LlvmTerminator::Ret(Some("%sum3".into())),
+            }],
+        };
+        (function, mir)
+    }
+
+    fn render_module(builder: &mut DebugInfoBuilder) -> String {
+        let (function, mir) = add_function();
+        let body = builder.render_function(&function, &mir);
+        format!(
+            "source_filename = \"app.reml\"\n\n{body}\n\ndeclare void @llvm.dbg.declare(metadata, metadata, metadata)\n\n{}\n",
+            builder.render_metadata()
+        )
+    }
+
+    #[test]
+    fn line_col_uses_line_starts() {
+        let map = DebugSourceMap::from_source("app.reml", SOURCE);
+        assert_eq!(map.line_col(0), (1, 1));
+        assert_eq!(map.line_col(span_of("let sum").start), (2, 3));
+        assert_eq!(map.line_col(span_of("sum\n}").start), (3, 3));
+    }
+
+    #[test]
+    fn renders_subprogram_locations_and_variables() {
+        let mut builder = builder();
+        let ir = render_module(&mut builder);
+        assert!(ir.contains("define i64 @add(i64 %a, i64 %b) !dbg !"));
+        assert!(ir.contains("!DIFile(filename: \"app.reml\", directory: \"/work\")"));
+        assert!(ir.contains("distinct !DICompileUnit(language: DW_LANG_C"));
+        assert!(ir.contains("distinct !DISubprogram(name: \"add\""));
+        assert!(ir.contains("line: 1, type: !"));
+        assert!(ir.contains("!DILocation(line: 2, column: 13, scope: !"));
+        assert!(ir.contains("!DILocalVariable(name: \"sum\""));
+        assert!(ir.contains("!DIBasicType(name: \"Int\", size: 64, encoding: DW_ATE_signed)"));
+        assert!(ir.contains("!{i32 2, !\"Debug Info Version\", i32 3}"));
+        assert!(
+            ir.contains("  ; let sum\n"),
+            "コメントには !dbg を付けないこと"
+        );
+        assert!(ir.contains("ret i64 %sum3, !dbg !"));
+        assert!(builder.uses_dbg_declare());
+    }
+
+    #[test]
+    fn type_descriptors_follow_type_mapping_layouts() {
+        let mut builder = builder();
+        for ty in [
+            RemlType::Bool,
+            RemlType::F64,
+            RemlType::String,
+            RemlType::Array {
+                element: Box::new(RemlType::I32),
+                length: 4,
+            },
+        ] {
+            builder.type_node(&ty);
+        }
+        let metadata = builder.render_metadata();
+        assert!(
+            metadata.contains("!DIBasicType(name: \"Bool\", size: 8, encoding: DW_ATE_boolean)")
+        );
+        assert!(
+            metadata.contains("!DIBasicType(name: \"Float\", size: 64, encoding: DW_ATE_float)")
+        );
+        assert!(metadata.contains("name: \"Str\""));
+        assert!(metadata.contains("name: \"len\""));
+        assert!(metadata.contains("size: 64, offset: 64)"));
+        assert!(metadata.contains("!DISubrange(count: 4)"));
+        assert!(metadata.contains("tag: DW_TAG_array_type"));
+        assert!(metadata.contains("size: 128"));
+    }
+
+    /// `llc` が利用できる環境でのみ、生成したオブジェクトの DWARF を検証する。
+    #[test]
+    fn llc_emits_dwarf_for_rendered_module() {
+        let Some(major) = llc_major_version() else {
+            eprintln!("llc が見つからないため DWARF 検証をスキップします");
+            return;
+        };
+        let dir = env::temp_dir().join(format!("reml_debug_info_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let ir_path = dir.join("app.ll");
+        let obj_path = dir.join("app.o");
+        fs::write(&ir_path, render_module(&mut builder())).unwrap();
+        let mut llc = Command::new("llc");
+        // LLVM 15 より前は `ptr` 表記に opaque pointer の明示が必要。
+        if major < 15 {
+            llc.arg("-opaque-pointers");
+        }
+        let output = llc
+            .arg("-filetype=obj")
+            .arg(&ir_path)
+            .arg("-o")
+            .arg(&obj_path)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "llc が失敗しました: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        if let Ok(dump) = Command::new("llvm-dwarfdump")
+            .arg("--debug-info")
+            .arg(&obj_path)
+            .output()
+        {
+            let dump = String::from_utf8_lossy(&dump.stdout);
+            assert!(dump.contains("DW_TAG_subprogram"), "{dump}");
+            assert!(dump.contains("(\"sum\")"), "{dump}");
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    fn llc_major_version() -> Option<u32> {
+        let output = Command::new("llc").arg("--version").output().ok()?;
+        let text = String::from_utf8_lossy(&output.stdout);
+        let version = text
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("LLVM version "))?;
+        version.split('.').next()?.parse().ok()
+    }
+}
@@ -0,0 +1,551 @@
+//! MIR 段階の効果ハンドラ変換。
+//!
+//! `handle` 式の本体・操作節・return 節をそれぞれラムダ式へ包み、
+//! クロージャ変換でボックス化 ABI の関数として持ち上げられる形にする。
+//! 操作節内の `resume(value)` 呼び出しは `MirExprKind::Resume` へ書き換え、
+//! 継続を末尾でちょうど一度だけ再開する節は tail-resumptive として印を付け、
+//! 継続を作らずに直接呼び出せるよう `resume` を値そのものへ置き換える。
+//!
+//! 生成コードは Runtime の `reml_handler_new` / `reml_handler_add_op` /
+//! `reml_handle` / `reml_perform` / `reml_resume` を呼び出す。
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::closure_conversion::{
+    collect_pattern_names, collect_reachable, identifier_name, CLOSURE_ENV_PARAM,
+};
+use crate::codegen::{
+    MirExpr, MirExprId, MirExprKind, MirFunction, MirLambdaCapture, MirLambdaParam, MirStmtKind,
+};
+
+/// 操作節の引数名が省略されたときに補う名前。
+const SYNTHETIC_ARGUMENT: &str = "__arg";
+const SYNTHETIC_RESUME: &str = "__resume";
+
+/// 関数内の未変換 `handle` 式をラムダ式で表した形へ書き換える。
+///
+/// 変換済みの `handle` は `lowered = true` を持つため、複数回適用しても結果は変わらない。
+pub(crate) fn lower_effect_handlers(mir: &MirFunction) -> MirFunction {
+    let needs_lowering = mir.exprs.iter().any(|expr| {
+        matches!(
+            expr.kind,
+            MirExprKind::Handle { lowered: false, .. } | MirExprKind::PerformCall { .. }
+        )
+    });
+    if !needs_lowering {
+        return mir.clone();
+    }
+    let mut function = mir.clone();
+    dedupe_block_tails(&mut function);
+
+    // 取り除いた式文の側に残る `handle` は評価されないため、本体から辿れるものだけを変換する。
+    let reachable = function.body.map(|body| {
+        let expr_map: HashMap<MirExprId, &MirExpr> =
+            function.exprs.iter().map(|expr| (expr.id, expr)).collect();
+        let mut reachable = BTreeSet::new();
+        collect_reachable(body, &expr_map, &mut reachable);
+        reachable
+    });
+    let handles: Vec<MirExprId> = function
+        .exprs
+        .iter()
+        .filter(|expr| matches!(expr.kind, MirExprKind::Handle { lowered: false, .. }))
+        .map(|expr| expr.id)
+        .filter(|id| {
+            reachable
+                .as_ref()
+                .is_none_or(|reachable| reachable.contains(id))
+        })
+        .collect();
+    let mut next_id = function
+        .exprs
+        .iter()
+        .map(|expr| expr.id + 1)
+        .max()
+        .unwrap_or(0);
+    for handle in handles {
+        lower_handle(&mut function, handle, &mut next_id);
+    }
+    function
+}
+
+/// フロントエンドはブロック末尾の式を最後の式文としても出力するため、
+/// 効果を含む関数では `perform` が二度評価されないよう重複した式文を取り除く。
+fn dedupe_block_tails(function: &mut MirFunction) {
+    let spans: HashMap<MirExprId, _> = function
+        .exprs
+        .iter()
+        .map(|expr| (expr.id, expr.span))
+        .collect();
+    for expr in &mut function.exprs {
+        let MirExprKind::Block {
+            statements,
+            tail: Some(tail),
+            ..
+        } = &mut expr.kind
+        else {
+            continue;
+        };
+        let Some(MirStmtKind::Expr { expr: last }) = statements.last().map(|stmt| &stmt.kind)
+        else {
+            continue;
+        };
+        let tail_span = spans.get(tail).copied().flatten();
+        if *last == *tail
+            || (tail_span.is_some() && spans.get(last).copied().flatten() == tail_span)
+        {
+            statements.pop();
+        }
+    }
+}
+
+fn lower_handle(function: &mut MirFunction, handle: MirExprId, next_id: &mut MirExprId) {
+    let Some(expr) = function.exprs.iter().find(|expr| expr.id == handle) else {
+        return;
+    };
+    let MirExprKind::Handle {
+        effect,
+        target,
+        operations,
+        return_clause,
+        lowered: false,
+    } = expr.kind.clone()
+    else {
+        return;
+    };
+    let handle_ty = expr.ty.clone();
+
+    let target_ty = expr_ty(function, target);
+    let body = wrap_lambda(
+        function,
+        Vec::new(),
+        target,
+        format!("() -> {target_ty}"),
+        next_id,
+    );
+
+    let mut lowered_operations = Vec::with_capacity(operations.len());
+    for mut operation in operations {
+        let mut params = operation.params.clone();
+        if params.is_empty() {
+            params.push(MirLambdaParam {
+                name: SYNTHETIC_ARGUMENT.into(),
+                ty: "ptr".into(),
+            });
+        }
+        if params.len() < 2 {
+            params.push(MirLambdaParam {
+                name: SYNTHETIC_RESUME.into(),
+                ty: "ptr".into(),
+            });
+        }
+        params.truncate(2);
+        params[1].ty = "ptr".into();
+
+        let resumes = rewrite_resumes(function, operation.body, &params[1].name);
+        let mut ret_ty = handle_ty.clone();
+        if let [resume] = resumes.as_slice() {
+            if tail_of(function, operation.body) == *resume {
+                ret_ty = inline_tail_resume(function, *resume);
+                operation.tail_resumptive = true;
+            }
+        }
+        let ty = format!("({}, ptr) -> {ret_ty}", params[0].ty);
+        operation.body = wrap_lambda(function, params.clone(), operation.body, ty, next_id);
+        operation.params = params;
+        lowered_operations.push(operation);
+    }
+
+    let return_clause = return_clause.map(|mut clause| {
+        let ty = format!("({}) -> {handle_ty}", clause.value.ty);
+        clause.body = wrap_lambda(
+            function,
+            vec![clause.value.clone()],
+            clause.body,
+            ty,
+            next_id,
+        );
+        clause
+    });
+
+    if let Some(expr) = function.exprs.iter_mut().find(|expr| expr.id == handle) {
+        expr.kind = MirExprKind::Handle {
+            effect,
+            target: body,
+            operations: lowered_operations,
+            return_clause,
+            lowered: true,
+        };
+    }
+}
+
+fn expr_ty(function: &MirFunction, id: MirExprId) -> String {
+    function
+        .exprs
+        .iter()
+        .find(|expr| expr.id == id)
+        .map(|expr| expr.ty.clone())
+        .unwrap_or_else(|| "ptr".into())
+}
+
+/// `body` を評価するラムダ式を追加し、その式 ID を返す。
+fn wrap_lambda(
+    function: &mut MirFunction,
+    params: Vec<MirLambdaParam>,
+    body: MirExprId,
+    ty: String,
+    next_id: &mut MirExprId,
+) -> MirExprId {
+    let captures = free_captures(function, &params, body);
+    let span = function
+        .exprs
+        .iter()
+        .find(|expr| expr.id == body)
+        .and_then(|expr| expr.span);
+    let id = *next_id;
+    *next_id += 1;
+    function.exprs.push(MirExpr {
+        id,
+        ty,
+        kind: MirExprKind::Lambda {
+            params,
+            body,
+            captures,
+            symbol: None,
+        },
+        span,
+    });
+    id
+}
+
+/// ラムダ本体が参照する名前のうち、本体の外側で束縛されたものをキャプチャとして集める。
+/// 型は空のままにし、クロージャ変換で参照箇所の型から推定させる。
+fn free_captures(
+    function: &MirFunction,
+    params: &[MirLambdaParam],
+    body: MirExprId,
+) -> Vec<MirLambdaCapture> {
+    let expr_map: HashMap<MirExprId, &MirExpr> =
+        function.exprs.iter().map(|expr| (expr.id, expr)).collect();
+    let mut inside = BTreeSet::new();
+    collect_reachable(body, &expr_map, &mut inside);
+
+    let mut inner: HashSet<String> = params.iter().map(|param| param.name.clone()).collect();
+    let mut outer: HashSet<String> = function
+        .param_names
+        .iter()
+        .filter(|name| name.as_str() != CLOSURE_ENV_PARAM)
+        .cloned()
+        .collect();
+    if let Some(env) = &function.closure_env {
+        outer.extend(env.iter().map(|capture| capture.name.clone()));
+    }
+    for expr in &function.exprs {
+        if inside.contains(&expr.id) {
+            collect_binders(expr, &mut inner);
+        } else {
+            collect_binders(expr, &mut outer);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut captures = Vec::new();
+    for id in &inside {
+        let Some(MirExprKind::Identifier { summary }) = expr_map.get(id).map(|expr| &expr.kind)
+        else {
+            continue;
+        };
+        let name = identifier_name(summary);
+        if outer.contains(&name) && !inner.contains(&name) && seen.insert(name.clone()) {
+            captures.push(MirLambdaCapture {
+                name,
+                mutable: false,
+                ty: String::new(),
+            });
+        }
+    }
+    captures
+}
+
+/// 式が導入する束縛名を集める。
+fn collect_binders(expr: &MirExpr, names: &mut HashSet<String>) {
+    match &expr.kind {
+        MirExprKind::Block { statements, .. } => {
+            for stmt in statements {
+                if let MirStmtKind::Let { pattern, .. } = &stmt.kind {
+                    collect_pattern_names(pattern, names);
+                }
+            }
+        }
+        MirExprKind::Match { arms, .. } => {
+            for arm in arms {
+                collect_pattern_names(&arm.pattern, names);
+                names.extend(arm.alias.iter().cloned());
+            }
+        }
+        MirExprKind::Lambda { params, .. } => {
+            names.extend(params.iter().map(|param| param.name.clone()));
+        }
+        MirExprKind::Handle {
+            operations,
+            return_clause,
+            ..
+        } => {
+            for operation in operations {
+                names.extend(operation.params.iter().map(|param| param.name.clone()));
+            }
+            names.extend(return_clause.iter().map(|clause| clause.value.name.clone()));
+        }
+        _ => {}
+    }
+}
+
+/// 節本体の `resume(value)` 呼び出しを `Resume` へ書き換え、
+/// 同じ継続名を参照する `Resume` の式 ID をすべて返す。
+fn rewrite_resumes(function: &mut MirFunction, body: MirExprId, resume: &str) -> Vec<MirExprId> {
+    let reachable = {
+        let expr_map: HashMap<MirExprId, &MirExpr> =
+            function.exprs.iter().map(|expr| (expr.id, expr)).collect();
+        let mut reachable = BTreeSet::new();
+        collect_reachable(body, &expr_map, &mut reachable);
+        reachable
+    };
+    let names: HashMap<MirExprId, String> = function
+        .exprs
+        .iter()
+        .filter(|expr| reachable.contains(&expr.id))
+        .filter_map(|expr| match &expr.kind {
+            MirExprKind::Identifier { summary } => Some((expr.id, identifier_name(summary))),
+            _ => None,
+        })
+        .collect();
+    let refers_to_resume = |id: &MirExprId| names.get(id).is_some_and(|name| name == resume);
+
+    let mut continuations = Vec::new();
+    let mut resumes = Vec::new();
+    for expr in &mut function.exprs {
+        if !reachable.contains(&expr.id) {
+            continue;
+        }
+        match &expr.kind {
+            MirExprKind::Call { callee, args } if args.len() == 1 && refers_to_resume(callee) => {
+                continuations.push(*callee);
+                expr.kind = MirExprKind::Resume {
+                    continuation: *callee,
+                    value: args[0],
+                };
+                resumes.push(expr.id);
+            }
+            MirExprKind::Resume { continuation, .. } if refers_to_resume(continuation) => {
+                resumes.push(expr.id);
+            }
+            _ => {}
+        }
+    }
+    for expr in &mut function.exprs {
+        if continuations.contains(&expr.id) {
+            expr.ty = "ptr".into();
+        }
+    }
+    resumes
+}
+
+/// ブロック・`effect`/`unsafe` ブロックを辿り、末尾位置で評価される式を返す。
+fn tail_of(function: &MirFunction, mut id: MirExprId) -> MirExprId {
+    loop {
+        let Some(expr) = function.exprs.iter().find(|expr| expr.id == id) else {
+            return id;
+        };
+        id = match &expr.kind {
+            MirExprKind::Block {
+                tail: Some(tail), ..
+            } => *tail,
+            MirExprKind::EffectBlock { body } | MirExprKind::Unsafe { body } => *body,
+            _ => return id,
+        };
+    }
+}
+
+/// 末尾の `resume(value)` を `value` の評価へ置き換え、節の戻り値型を返す。
+fn inline_tail_resume(function: &mut MirFunction, resume: MirExprId) -> String {
+    let Some(expr) = function.exprs.iter().find(|expr| expr.id == resume) else {
+        return "ptr".into();
+    };
+    let MirExprKind::Resume { value, .. } = expr.kind else {
+        return expr.ty.clone();
+    };
+    let ty = expr_ty(function, value);
+    if let Some(expr) = function.exprs.iter_mut().find(|expr| expr.id == resume) {
+        expr.ty = ty.clone();
+        expr.kind = MirExprKind::Block {
+            statements: Vec::new(),
+            tail: Some(value),
+            defers: Vec::new(),
+            defer_lifo: Vec::new(),
+        };
+    }
+    ty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::{MirHandlerOperation, MirHandlerReturn};
+
+    fn expr(id: MirExprId, ty: &str, kind: MirExprKind) -> MirExpr {
+        MirExpr {
+            id,
+            ty: ty.into(),
+            kind,
+            span: None,
+        }
+    }
+
+    fn ident(id: MirExprId, name: &str, ty: &str) -> MirExpr {
+        expr(
+            id,
+            ty,
+            MirExprKind::Identifier {
+                summary: format!("{{\"name\":\"{name}\"}}"),
+            },
+        )
+    }
+
+    fn handler(operation_body: MirExprId) -> MirExpr {
+        expr(
+            5,
+            "i64",
+            MirExprKind::Handle {
+                effect: "Counter".into(),
+                target: 0,
+                operations: vec![MirHandlerOperation {
+                    name: "next".into(),
+                    params: vec![
+                        MirLambdaParam {
+                            name: "_unit".into(),
+                            ty: "()".into(),
+                        },
+                        MirLambdaParam {
+                            name: "resume".into(),
+                            ty: "(i64) -> 't3".into(),
+                        },
+                    ],
+                    body: operation_body,
+                    tail_resumptive: false,
+                }],
+                return_clause: Some(MirHandlerReturn {
+                    value: MirLambdaParam {
+                        name: "value".into(),
+                        ty: "i64".into(),
+                    },
+                    body: 4,
+                }),
+                lowered: false,
+            },
+        )
+    }
+
+    #[test]
+    fn wraps_handler_clauses_and_detects_tail_resumption() {
+        let exprs = vec![
+            ident(0, "step", "i64"),
+            ident(1, "resume", "(i64) -> 't3"),
+            ident(2, "step", "i64"),
+            expr(
+                3,
+                "i64",
+                MirExprKind::Call {
+                    callee: 1,
+                    args: vec![2],
+                },
+            ),
+            ident(4, "value", "i64"),
+            handler(3),
+        ];
+        let mir = MirFunction::new("@main", "ccc")
+            .with_named_param("step", crate::type_mapping::RemlType::I64)
+            .with_exprs(Some(5), exprs);
+
+        let lowered = lower_effect_handlers(&mir);
+        let find = |id| lowered.exprs.iter().find(|expr| expr.id == id).unwrap();
+        let MirExprKind::Handle {
+            target,
+            operations,
+            return_clause,
+            lowered: true,
+            ..
+        } = &find(5).kind
+        else {
+            panic!("handle は変換済みになること");
+        };
+        assert!(
+            operations[0].tail_resumptive,
+            "末尾の resume は直接呼び出しにすること"
+        );
+        assert_eq!(operations[0].params[1].ty, "ptr");
+        assert!(matches!(
+            find(3).kind,
+            MirExprKind::Block { tail: Some(2), .. }
+        ));
+
+        let MirExprKind::Lambda { captures, .. } = &find(operations[0].body).kind else {
+            panic!("操作節はラムダへ包まれること");
+        };
+        assert_eq!(captures.len(), 1);
+        assert_eq!(captures[0].name, "step");
+        assert_eq!(find(operations[0].body).ty, "((), ptr) -> i64");
+        assert!(matches!(find(*target).kind, MirExprKind::Lambda { .. }));
+        let return_body = return_clause.as_ref().unwrap().body;
+        assert_eq!(find(return_body).ty, "(i64) -> i64");
+
+        let again = lower_effect_handlers(&lowered);
+        assert_eq!(again.exprs.len(), lowered.exprs.len());
+    }
+
+    #[test]
+    fn keeps_continuation_for_non_tail_resumption() {
+        let exprs = vec![
+            ident(0, "step", "i64"),
+            ident(1, "resume", "(i64) -> 't3"),
+            ident(2, "step", "i64"),
+            expr(
+                3,
+                "i64",
+                MirExprKind::Call {
+                    callee: 1,
+                    args: vec![2],
+                },
+            ),
+            ident(4, "value", "i64"),
+            handler(7),
+            ident(6, "step", "i64"),
+            expr(
+                7,
+                "i64",
+                MirExprKind::Binary {
+                    operator: "+".into(),
+                    left: 3,
+                    right: 6,
+                },
+            ),
+        ];
+        let mir = MirFunction::new("@main", "ccc")
+            .with_named_param("step", crate::type_mapping::RemlType::I64)
+            .with_exprs(Some(5), exprs);
+
+        let lowered = lower_effect_handlers(&mir);
+        let find = |id| lowered.exprs.iter().find(|expr| expr.id == id).unwrap();
+        let MirExprKind::Handle { operations, .. } = &find(5).kind else {
+            panic!("handle が残ること");
+        };
+        assert!(!operations[0].tail_resumptive);
+        assert!(matches!(
+            find(3).kind,
+            MirExprKind::Resume {
+                continuation: 1,
+                value: 2
+            }
+        ));
+        assert_eq!(find(1).ty, "ptr");
+    }
+}
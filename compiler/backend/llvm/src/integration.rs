@@ -1,17 +1,24 @@
+use crate::c_header::{generate_c_header, CHeader, CHeaderExtern, CHeaderOptions};
+use crate::closure_conversion::identifier_name;
 use crate::codegen::{
-    summarize_pattern, ActivePatternKind, CodegenContext, GeneratedFunction, MatchArmLowering,
+    summarize_pattern, ActivePatternKind, CodegenContext, CodegenFallback, GeneratedFunction,
+    MatchArmLowering,
     MatchLoweringPlan, MirActivePatternCall, MirExpr, MirExprKind, MirFunction,
+    MirHandlerOperation, MirHandlerReturn,
     MirInlineAsmInput, MirInlineAsmOutput, MirJumpTarget, MirLambdaCapture, MirLambdaParam,
     MirMatchArm, MirPattern, MirPatternKind, MirPatternRecordField, MirSlicePattern, MirSliceRest,
-    MirStmt, MirStmtKind, PatternLowering,
+    MirSpan, MirStmt, MirStmtKind, PatternLowering,
 };
+use crate::debug_info::DebugSourceMap;
 use crate::ffi_lowering::FfiCallSignature;
+use crate::monomorphize::{MonoImpl, MonomorphizeResult, Monomorphizer};
+use crate::tail_calls::{MirTailCall, TailCallFailure, TailCallKind};
 use crate::target_machine::{
     CodeModel, DataLayoutSpec, OptimizationLevel, RelocModel, TargetMachine, TargetMachineBuilder,
     Triple, WindowsToolchainConfig,
 };
-use crate::type_mapping::RemlType;
-use crate::verify::Verifier;
+use crate::type_mapping::{RemlType, TypeMappingContext};
+use crate::verify::{Diagnostic, Verifier};
 use serde::Deserialize;
 use serde_json::Value;
 use std::borrow::Cow;
@@ -235,6 +242,8 @@ struct MirModuleSpec {
     impl_registry_duplicates: Vec<String>,
     #[serde(default)]
     impl_registry_unresolved: Vec<String>,
+    #[serde(default)]
+    debug_source: Option<MirDebugSourceJson>,
 }
 
 impl MirModuleSpec {
@@ -244,6 +253,10 @@ impl MirModuleSpec {
         Ok(spec)
     }
 
+    fn from_json_str(json: &str) -> Result<Self, MirSnapshotError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
     fn collect_todo_diagnostics(&self) -> Vec<String> {
         let mut diagnostics = Vec::new();
         let mut call_keys = BTreeSet::new();
@@ -370,6 +383,51 @@ impl MirModuleSpec {
             .map(MirFunctionJson::into_mir)
             .collect()
     }
+
+    /// impl レジストリとトレイトメソッド呼び出しから単相化パスを組み立てる。
+    fn monomorphizer(&self) -> Monomorphizer {
+        let mut monomorphizer = Monomorphizer::new();
+        for (impl_id, spec) in &self.impls {
+            let Some(target) = spec.target.clone() else {
+                continue;
+            };
+            monomorphizer = monomorphizer.with_impl(MonoImpl {
+                impl_id: impl_id.clone(),
+                trait_name: spec.trait_name.clone(),
+                target,
+                methods: spec.methods.clone(),
+            });
+        }
+        for (key, call) in &self.qualified_calls {
+            if call.kind != MirQualifiedCallKindJson::TraitMethod {
+                continue;
+            }
+            let (Some(owner), Some(name)) = (call.owner.as_ref(), call.name.as_ref()) else {
+                continue;
+            };
+            let Some((function, expr_id)) = key.rsplit_once('#') else {
+                continue;
+            };
+            let Ok(expr_id) = expr_id.parse() else {
+                continue;
+            };
+            monomorphizer =
+                monomorphizer.with_trait_call(function, expr_id, owner.clone(), name.clone());
+        }
+        monomorphizer
+    }
+
+    fn into_monomorphized_functions(self) -> MonomorphizeResult {
+        let monomorphizer = self.monomorphizer();
+        monomorphizer.run(self.into_functions())
+    }
+}
+
+fn render_diagnostic(diagnostic: &Diagnostic) -> String {
+    format!(
+        "{}.{}: {}",
+        diagnostic.domain, diagnostic.code, diagnostic.message
+    )
 }
 
 fn default_calling_conv() -> String {
@@ -463,6 +521,23 @@ struct MirSpanJson {
     end: u32,
 }
 
+impl MirSpanJson {
+    fn into_span(self) -> MirSpan {
+        MirSpan {
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+/// フロントエンドが `--debug-info` 指定時に埋め込むソース対応表。
+#[derive(Debug, Deserialize)]
+struct MirDebugSourceJson {
+    path: String,
+    #[serde(default)]
+    line_starts: Vec<u32>,
+}
+
 /// 単体 MIR 関数の JSON 表現。
 #[derive(Debug, Deserialize)]
 struct MirFunctionJson {
@@ -487,6 +562,29 @@ struct MirFunctionJson {
     exprs: Vec<MirExprJson>,
     #[serde(default)]
     body: Option<usize>,
+    #[serde(default)]
+    span: Option<MirSpanJson>,
+    #[serde(default)]
+    tail_calls: Vec<MirTailCallJson>,
+}
+
+/// フロントエンドの末尾位置解析結果（`tail_calls` の要素）。
+#[derive(Debug, Deserialize)]
+struct MirTailCallJson {
+    expr: usize,
+    #[serde(default)]
+    callee: Option<String>,
+    kind: String,
+}
+
+impl MirTailCallJson {
+    fn into_tail_call(self) -> Option<MirTailCall> {
+        Some(MirTailCall {
+            expr: self.expr,
+            callee: self.callee,
+            kind: TailCallKind::parse(&self.kind)?,
+        })
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -494,25 +592,25 @@ struct MirFunctionJson {
 enum MirParamJson {
     Bare(String),
     Detailed {
+        #[serde(default)]
+        name: Option<String>,
         #[serde(default)]
         ty: Option<String>,
     },
 }
 
 impl MirParamJson {
-    fn into_type_token(self) -> String {
+    fn into_name_and_type_token(self) -> (Option<String>, String) {
         match self {
-            MirParamJson::Bare(token) => token,
-            MirParamJson::Detailed { ty } => ty.unwrap_or_else(|| "pointer".into()),
+            MirParamJson::Bare(token) => (None, token),
+            MirParamJson::Detailed { name, ty } => (name, ty.unwrap_or_else(|| "pointer".into())),
         }
     }
 
     fn type_token(&self) -> Cow<'_, str> {
         match self {
             MirParamJson::Bare(token) => Cow::Borrowed(token),
-            MirParamJson::Detailed { ty } => {
-                Cow::Borrowed(ty.as_deref().unwrap_or("pointer"))
-            }
+            MirParamJson::Detailed { ty, .. } => Cow::Borrowed(ty.as_deref().unwrap_or("pointer")),
         }
     }
 }
@@ -520,11 +618,19 @@ impl MirParamJson {
 impl MirFunctionJson {
     fn into_mir(self) -> MirFunction {
         let mut builder = MirFunction::new(self.name, self.calling_conv);
+        let mut param_type_tokens = Vec::new();
         for param in self.params {
-            builder = builder.with_param(parse_reml_type(&param.into_type_token()));
+            let (name, token) = param.into_name_and_type_token();
+            builder = match name {
+                Some(name) => builder.with_named_param(name, parse_reml_type(&token)),
+                None => builder.with_param(parse_reml_type(&token)),
+            };
+            param_type_tokens.push(token);
         }
+        builder.param_type_tokens = param_type_tokens;
         if let Some(ret) = self.return_type {
             builder = builder.with_return(parse_reml_type(&ret));
+            builder.return_type_token = Some(ret);
         }
 
         for attr in self.attributes {
@@ -536,7 +642,15 @@ impl MirFunctionJson {
         let exprs = convert_exprs(self.exprs);
         builder.match_plans = extract_match_plans(&exprs);
         builder = builder.with_exprs(self.body, exprs);
-        builder
+        if let Some(span) = self.span {
+            builder = builder.with_span(span.into_span());
+        }
+        builder.with_tail_calls(
+            self.tail_calls
+                .into_iter()
+                .filter_map(MirTailCallJson::into_tail_call)
+                .collect(),
+        )
     }
 }
 
@@ -576,6 +690,8 @@ struct MirExprJson {
     #[serde(default)]
     ty: String,
     kind: MirExprKindJson,
+    #[serde(default)]
+    span: Option<MirSpanJson>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -626,6 +742,8 @@ enum MirExprKindJson {
         #[serde(default)]
         args: Vec<usize>,
     },
+    // 単項演算はコード生成が未対応のため、演算子と被演算子は読み捨てる。
+    Unary {},
     Binary {
         #[serde(default)]
         operator: String,
@@ -640,6 +758,10 @@ enum MirExprKindJson {
     PerformCall {
         call: MirEffectCallJson,
     },
+    Handle {
+        target: usize,
+        handler: MirHandlerJson,
+    },
     EffectBlock {
         body: usize,
     },
@@ -699,6 +821,28 @@ struct MirExternJson {
     symbol: Option<String>,
     #[serde(default)]
     span: Option<MirSpanJson>,
+    #[serde(default)]
+    params: Vec<String>,
+    #[serde(default)]
+    param_names: Vec<String>,
+    #[serde(default)]
+    return_type: Option<String>,
+    #[serde(default)]
+    varargs: bool,
+}
+
+impl MirExternJson {
+    fn to_c_header_extern(&self) -> Option<CHeaderExtern> {
+        Some(CHeaderExtern {
+            name: self.name.clone()?,
+            symbol: self.symbol.clone(),
+            abi: self.abi.clone(),
+            params: self.params.clone(),
+            param_names: self.param_names.clone(),
+            return_type: self.return_type.clone(),
+            varargs: self.varargs,
+        })
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -715,6 +859,8 @@ struct MirLambdaCaptureJson {
     name: String,
     #[serde(default)]
     mutable: bool,
+    #[serde(default)]
+    ty: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -749,6 +895,29 @@ struct MirEffectCallJson {
     argument: usize,
 }
 
+#[derive(Debug, Deserialize)]
+struct MirHandlerJson {
+    effect: Value,
+    #[serde(default)]
+    operations: Vec<MirHandlerOperationJson>,
+    #[serde(default)]
+    return_clause: Option<MirHandlerReturnJson>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MirHandlerOperationJson {
+    name: Value,
+    #[serde(default)]
+    params: Vec<MirLambdaParamJson>,
+    body: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct MirHandlerReturnJson {
+    value: MirLambdaParamJson,
+    body: usize,
+}
+
 #[derive(Debug, Deserialize)]
 struct MirInlineAsmOutputJson {
     constraint: String,
@@ -893,6 +1062,10 @@ struct MatchLoweringPlanJson {
     arm_count: Option<usize>,
     #[serde(default)]
     arms: Vec<MatchArmLoweringJson>,
+    #[serde(default)]
+    exhaustive: bool,
+    #[serde(default)]
+    constructors: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -922,6 +1095,7 @@ fn convert_exprs(exprs: Vec<MirExprJson>) -> Vec<MirExpr> {
             id: expr.id,
             ty: expr.ty,
             kind: convert_expr_kind(expr.kind),
+            span: expr.span.map(MirSpanJson::into_span),
         })
         .collect()
 }
@@ -947,21 +1121,17 @@ fn convert_expr_kind(kind: MirExprKindJson) -> MirExprKind {
             body,
             captures,
         } => MirExprKind::Lambda {
-            params: params
-                .into_iter()
-                .map(|param| MirLambdaParam {
-                    name: param.name,
-                    ty: param.ty,
-                })
-                .collect(),
+            params: params.into_iter().map(convert_lambda_param).collect(),
             body,
             captures: captures
                 .into_iter()
                 .map(|capture| MirLambdaCapture {
                     name: capture.name,
                     mutable: capture.mutable,
+                    ty: capture.ty,
                 })
                 .collect(),
+            symbol: None,
         },
         MirExprKindJson::Rec { target, ident } => MirExprKind::Rec {
             target,
@@ -999,9 +1169,34 @@ fn convert_expr_kind(kind: MirExprKindJson) -> MirExprKind {
             effect: value_summary(call.effect),
             argument: call.argument,
         },
+        MirExprKindJson::Handle { target, handler } => MirExprKind::Handle {
+            effect: identifier_name(&value_summary(handler.effect)),
+            target,
+            operations: handler
+                .operations
+                .into_iter()
+                .map(|operation| MirHandlerOperation {
+                    name: identifier_name(&value_summary(operation.name)),
+                    params: operation
+                        .params
+                        .into_iter()
+                        .map(convert_lambda_param)
+                        .collect(),
+                    body: operation.body,
+                    tail_resumptive: false,
+                })
+                .collect(),
+            return_clause: handler.return_clause.map(|clause| MirHandlerReturn {
+                value: convert_lambda_param(clause.value),
+                body: clause.body,
+            }),
+            lowered: false,
+        },
         MirExprKindJson::EffectBlock { body } => MirExprKind::EffectBlock { body },
         MirExprKindJson::Async { .. } => MirExprKind::Unknown,
         MirExprKindJson::Await { .. } => MirExprKind::Unknown,
+        // フロントエンドの MIR 最適化で畳み込めなかった単項演算だけが届く。
+        MirExprKindJson::Unary {} => MirExprKind::Unknown,
         MirExprKindJson::Unsafe { body } => MirExprKind::Unsafe { body },
         MirExprKindJson::InlineAsm {
             template,
@@ -1083,6 +1278,8 @@ fn convert_match_lowering(plan: Option<MatchLoweringPlanJson>) -> Option<MatchLo
                 alias: arm.alias,
             })
             .collect(),
+        exhaustive: plan.exhaustive,
+        constructors: plan.constructors,
     })
 }
 
@@ -1239,6 +1436,13 @@ fn extract_match_plans(exprs: &[MirExpr]) -> Vec<String> {
     plans
 }
 
+fn convert_lambda_param(param: MirLambdaParamJson) -> MirLambdaParam {
+    MirLambdaParam {
+        name: param.name,
+        ty: param.ty,
+    }
+}
+
 fn value_summary(value: Value) -> String {
     match value {
         Value::String(text) => text,
@@ -1253,6 +1457,14 @@ fn value_summary(value: Value) -> String {
 pub enum MirSnapshotError {
     Io(io::Error),
     Json(serde_json::Error),
+    /// デバッグ情報を要求したが MIR にソース対応表が含まれていない。
+    MissingDebugSource,
+    /// 単相化が具体化深さの上限を超えた。
+    Monomorphize(Diagnostic),
+    /// `--strict-codegen` でフォールバックによる縮退箇所が見つかった。
+    StrictCodegen(CodegenFallback),
+    /// `@tailrec` 関数の末尾呼び出しを保証できなかった。
+    TailCallGuarantee(TailCallFailure),
 }
 
 impl fmt::Display for MirSnapshotError {
@@ -1260,6 +1472,21 @@ impl fmt::Display for MirSnapshotError {
         match self {
             MirSnapshotError::Io(err) => write!(f, "I/O エラー: {}", err),
             MirSnapshotError::Json(err) => write!(f, "JSON パースエラー: {}", err),
+            MirSnapshotError::MissingDebugSource => write!(
+                f,
+                "デバッグ情報の生成には --debug-info 付きで出力した MIR が必要です"
+            ),
+            MirSnapshotError::Monomorphize(diagnostic) => {
+                write!(f, "単相化に失敗しました: {}", render_diagnostic(diagnostic))
+            }
+            MirSnapshotError::StrictCodegen(fallback) => write!(
+                f,
+                "--strict-codegen: {}",
+                render_diagnostic(&fallback.to_diagnostic())
+            ),
+            MirSnapshotError::TailCallGuarantee(failure) => {
+                write!(f, "{}", render_diagnostic(&failure.to_diagnostic()))
+            }
         }
     }
 }
@@ -1269,6 +1496,10 @@ impl Error for MirSnapshotError {
         match self {
             MirSnapshotError::Io(err) => Some(err),
             MirSnapshotError::Json(err) => Some(err),
+            MirSnapshotError::MissingDebugSource
+            | MirSnapshotError::Monomorphize(_)
+            | MirSnapshotError::StrictCodegen(_)
+            | MirSnapshotError::TailCallGuarantee(_) => None,
         }
     }
 }
@@ -1298,6 +1529,7 @@ pub fn generate_snapshot(
     metadata
         .into_iter()
         .for_each(|entry| codegen.with_metadata(entry));
+    codegen.declare_functions(&functions);
     for function in &functions {
         codegen.emit_function(function);
     }
@@ -1352,18 +1584,135 @@ pub fn generate_snapshot_from_mir_json<P: AsRef<Path>>(
     runtime_symbols.extend(spec.runtime_symbols.iter().cloned());
     let mut metadata = metadata;
     metadata.extend(spec.metadata.iter().cloned());
-    let functions = spec.into_functions();
+    let monomorphized = spec.into_monomorphized_functions();
     let mut snapshot = generate_snapshot(
         module_name,
         target_machine,
         runtime_symbols,
         metadata,
-        functions,
+        monomorphized.functions,
     );
     snapshot.diagnostics.extend(todo_diagnostics);
+    snapshot
+        .diagnostics
+        .extend(monomorphized.diagnostics.iter().map(render_diagnostic));
     Ok(snapshot)
 }
 
+/// `.ll` 生成のオプション。
+#[derive(Clone, Debug, Default)]
+pub struct LlvmEmitOptions {
+    /// DWARF デバッグ情報を付与する（`--debug-info`）。
+    pub debug_info: bool,
+    /// 縮退箇所で生成を中断する（`--strict-codegen`）。
+    /// `None` の場合は最適化レベルから決める。
+    pub strict_codegen: Option<bool>,
+}
+
+impl LlvmEmitOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_debug_info(mut self, enabled: bool) -> Self {
+        self.debug_info = enabled;
+        self
+    }
+
+    pub fn with_strict_codegen(mut self, strict: bool) -> Self {
+        self.strict_codegen = Some(strict);
+        self
+    }
+
+    /// コマンドラインフラグを取り込む。対応するフラグなら `true` を返す。
+    pub fn apply_flag(&mut self, flag: &str) -> bool {
+        match flag {
+            "--debug-info" => self.debug_info = true,
+            "--strict-codegen" => self.strict_codegen = Some(true),
+            "--no-strict-codegen" => self.strict_codegen = Some(false),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// MIR JSON から `.ll` として書き出せるモジュール IR を生成する。
+///
+/// `debug_info` を有効にすると、フロントエンドの `--debug-info` が埋め込んだ
+/// ソース対応表を使って DWARF デバッグ情報を付与する。
+pub fn emit_llvm_module_from_mir_json<P: AsRef<Path>>(
+    path: P,
+    target_machine: TargetMachine,
+    runtime_symbols: Vec<String>,
+    debug_info: bool,
+    default_module_name: impl Into<String>,
+) -> Result<String, MirSnapshotError> {
+    emit_llvm_module_from_mir_json_with_options(
+        path,
+        target_machine,
+        runtime_symbols,
+        LlvmEmitOptions::new().with_debug_info(debug_info),
+        default_module_name,
+    )
+}
+
+/// オプションを指定して MIR JSON から `.ll` を生成する。
+///
+/// strict モードでは関数ごとに生成を終えた時点で縮退箇所を確認し、縮退箇所を含む
+/// 最初の関数で、その関数内で最初に記録された縮退箇所を
+/// [`MirSnapshotError::StrictCodegen`] として返す（以降の関数は生成しない）。
+pub fn emit_llvm_module_from_mir_json_with_options<P: AsRef<Path>>(
+    path: P,
+    target_machine: TargetMachine,
+    runtime_symbols: Vec<String>,
+    options: LlvmEmitOptions,
+    default_module_name: impl Into<String>,
+) -> Result<String, MirSnapshotError> {
+    let mut spec = MirModuleSpec::from_file(path)?;
+    let module_name = spec
+        .module
+        .clone()
+        .unwrap_or_else(|| default_module_name.into());
+    let mut runtime_symbols = runtime_symbols;
+    runtime_symbols.extend(spec.runtime_symbols.iter().cloned());
+    let mut codegen = CodegenContext::new(target_machine, runtime_symbols);
+    if let Some(strict) = options.strict_codegen {
+        codegen.set_strict_codegen(strict);
+    }
+    if options.debug_info {
+        let source = spec
+            .debug_source
+            .take()
+            .ok_or(MirSnapshotError::MissingDebugSource)?;
+        codegen.enable_debug_info(DebugSourceMap::new(source.path, source.line_starts));
+    }
+    spec.metadata
+        .iter()
+        .cloned()
+        .for_each(|entry| codegen.with_metadata(entry));
+    let monomorphized = spec.into_monomorphized_functions();
+    if let Some(overflow) = monomorphized
+        .diagnostics
+        .iter()
+        .find(|diagnostic| diagnostic.code == "monomorphize.depth_overflow")
+    {
+        return Err(MirSnapshotError::Monomorphize(overflow.clone()));
+    }
+    codegen.declare_functions(&monomorphized.functions);
+    for function in &monomorphized.functions {
+        codegen.emit_function(function);
+        if codegen.strict_codegen() {
+            if let Some(fallback) = codegen.fallbacks().first() {
+                return Err(MirSnapshotError::StrictCodegen(fallback.clone()));
+            }
+        }
+        if let Some(failure) = codegen.tail_call_failures().first() {
+            return Err(MirSnapshotError::TailCallGuarantee(failure.clone()));
+        }
+    }
+    Ok(codegen.finish_module(module_name).render_ll())
+}
+
 /// JSON ファイルから MIR 関数リストをロードする。
 pub fn load_mir_functions_from_json<P: AsRef<Path>>(
     path: P,
@@ -1372,6 +1721,98 @@ pub fn load_mir_functions_from_json<P: AsRef<Path>>(
     Ok(spec.into_functions())
 }
 
+/// 単相化まで済ませた MIR モジュール。LLVM 以外のバックエンドが前処理を共有するために使う。
+#[derive(Clone, Debug)]
+pub struct MonomorphizedMirModule {
+    /// MIR JSON の `module` 名（省略時は `None`）。
+    pub module: Option<String>,
+    pub functions: Vec<MirFunction>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// JSON ファイルから MIR をロードし、単相化した関数リストを返す。
+///
+/// 具体化深さの超過は [`MirSnapshotError::Monomorphize`] として返す。
+pub fn load_monomorphized_mir_from_json<P: AsRef<Path>>(
+    path: P,
+) -> Result<MonomorphizedMirModule, MirSnapshotError> {
+    monomorphize_mir_spec(MirModuleSpec::from_file(path)?)
+}
+
+/// メモリ上の MIR JSON 文字列から単相化した関数リストを返す（埋め込み実行などファイルを介さない経路向け）。
+pub fn load_monomorphized_mir_from_json_str(
+    json: &str,
+) -> Result<MonomorphizedMirModule, MirSnapshotError> {
+    monomorphize_mir_spec(MirModuleSpec::from_json_str(json)?)
+}
+
+/// MIR JSON の `@export` 関数と `extern` 宣言から C ヘッダを生成する。
+///
+/// `options.module` を省略した場合は MIR JSON の `module` 名を使う。
+pub fn emit_c_header_from_mir_json<P: AsRef<Path>>(
+    path: P,
+    data_layout: DataLayoutSpec,
+    options: &CHeaderOptions,
+) -> Result<CHeader, MirSnapshotError> {
+    Ok(c_header_from_spec(
+        MirModuleSpec::from_file(path)?,
+        data_layout,
+        options,
+    ))
+}
+
+/// メモリ上の MIR JSON 文字列から C ヘッダを生成する。
+pub fn emit_c_header_from_mir_json_str(
+    json: &str,
+    data_layout: DataLayoutSpec,
+    options: &CHeaderOptions,
+) -> Result<CHeader, MirSnapshotError> {
+    Ok(c_header_from_spec(
+        MirModuleSpec::from_json_str(json)?,
+        data_layout,
+        options,
+    ))
+}
+
+fn c_header_from_spec(
+    spec: MirModuleSpec,
+    data_layout: DataLayoutSpec,
+    options: &CHeaderOptions,
+) -> CHeader {
+    let mut options = options.clone();
+    if options.module.is_none() {
+        options.module = spec.module.clone();
+    }
+    let externs: Vec<CHeaderExtern> = spec
+        .externs
+        .iter()
+        .filter_map(MirExternJson::to_c_header_extern)
+        .collect();
+    generate_c_header(
+        &spec.into_functions(),
+        &externs,
+        &TypeMappingContext::new(data_layout),
+        &options,
+    )
+}
+
+fn monomorphize_mir_spec(spec: MirModuleSpec) -> Result<MonomorphizedMirModule, MirSnapshotError> {
+    let module = spec.module.clone();
+    let monomorphized = spec.into_monomorphized_functions();
+    if let Some(overflow) = monomorphized
+        .diagnostics
+        .iter()
+        .find(|diagnostic| diagnostic.code == "monomorphize.depth_overflow")
+    {
+        return Err(MirSnapshotError::Monomorphize(overflow.clone()));
+    }
+    Ok(MonomorphizedMirModule {
+        module,
+        functions: monomorphized.functions,
+        diagnostics: monomorphized.diagnostics,
+    })
+}
+
 /// W3 相当の差分スナップショットを生成する。
 pub fn generate_w3_snapshot() -> BackendDiffSnapshot {
     let windows_toolchain = WindowsToolchainConfig {
@@ -1459,7 +1900,7 @@ pub fn generate_w3_snapshot() -> BackendDiffSnapshot {
     }
 }
 
-fn parse_reml_type(token: &str) -> RemlType {
+pub(crate) fn parse_reml_type(token: &str) -> RemlType {
     parse_reml_type_with_diagnostics(token, None)
 }
 
@@ -1563,7 +2004,7 @@ fn parse_reml_type_with_diagnostics(
         "bool" => RemlType::Bool,
         "i32" | "int32" => RemlType::I32,
         "i64" | "int64" => RemlType::I64,
-        "f64" | "double" => RemlType::F64,
+        "f64" | "float" | "double" => RemlType::F64,
         "pointer" | "ptr" | "i8*" => RemlType::Pointer,
         "string" | "str" => RemlType::String,
         _ => RemlType::Pointer,
@@ -1599,9 +2040,11 @@ fn split_generic_type(token: &str) -> Option<(&str, &str)> {
 #[cfg(test)]
 mod tests {
     use super::{
+        emit_llvm_module_from_mir_json, emit_llvm_module_from_mir_json_with_options,
         generate_snapshot_from_mir_json, load_mir_functions_from_json, parse_reml_type,
-        parse_reml_type_with_diagnostics, MirModuleSpec, MirSnapshotError,
+        parse_reml_type_with_diagnostics, LlvmEmitOptions, MirModuleSpec, MirSnapshotError,
     };
+    use crate::codegen::MirSpan;
     use crate::target_machine::{
         CodeModel, DataLayoutSpec, OptimizationLevel, RelocModel, TargetMachineBuilder, Triple,
         WindowsToolchainConfig,
@@ -1664,6 +2107,7 @@ mod tests {
     fn parse_reml_type_synonyms() {
         assert_eq!(parse_reml_type("i32"), RemlType::I32);
         assert_eq!(parse_reml_type("Int64"), RemlType::I64);
+        assert_eq!(parse_reml_type("Float"), RemlType::F64);
         assert_eq!(parse_reml_type("ptr"), RemlType::Pointer);
         assert_eq!(parse_reml_type("unknown"), RemlType::Pointer);
         assert_eq!(
@@ -1786,6 +2230,741 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn module_ir_carries_debug_info_from_mir_spans() -> Result<(), MirSnapshotError> {
+        // fn add(a: Int, b: Int) -> Int {\n  let sum = a + b\n  sum\n}\n
+        let spec = r#"
+    {
+      "debug_source": {"path": "/work/add.reml", "line_starts": [0, 32, 50, 56, 58]},
+      "functions": [
+        {
+          "name": "add",
+          "span": {"start": 0, "end": 57},
+          "params": [{"name": "a", "ty": "i64"}, {"name": "b", "ty": "i64"}],
+          "return_type": "i64",
+          "body": 5,
+          "exprs": [
+            {"id": 0, "span": {"start": 44, "end": 45}, "ty": "i64",
+             "kind": {"kind": "identifier", "ident": {"name": "a"}}},
+            {"id": 1, "span": {"start": 48, "end": 49}, "ty": "i64",
+             "kind": {"kind": "identifier", "ident": {"name": "b"}}},
+            {"id": 2, "span": {"start": 44, "end": 49}, "ty": "i64",
+             "kind": {"kind": "binary", "operator": "+", "left": 0, "right": 1}},
+            {"id": 3, "span": {"start": 52, "end": 55}, "ty": "i64",
+             "kind": {"kind": "identifier", "ident": {"name": "sum"}}},
+            {"id": 4, "span": {"start": 52, "end": 55}, "ty": "i64",
+             "kind": {"kind": "identifier", "ident": {"name": "sum"}}},
+            {"id": 5, "span": {"start": 30, "end": 57}, "ty": "i64",
+             "kind": {"kind": "block", "statements": [
+               {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "sum"}},
+                         "value": 2, "mutable": false}},
+               {"kind": {"kind": "expr", "expr": 3}}
+             ], "tail": 4}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let tmp = env::temp_dir().join("reml_mir_debug_info.json");
+        fs::write(&tmp, spec)?;
+        let plain =
+            emit_llvm_module_from_mir_json(&tmp, test_target_machine(), vec![], false, "dbg")?;
+        assert!(!plain.contains("!dbg"), "既定ではデバッグ情報を出力しないこと");
+        assert!(!plain.contains("dbg.loc"));
+
+        let ir = emit_llvm_module_from_mir_json(&tmp, test_target_machine(), vec![], true, "dbg")?;
+        assert!(ir.contains("source_filename = \"/work/add.reml\""));
+        assert!(ir.contains("define i64 @add(i64 %a, i64 %b) !dbg !"));
+        assert!(ir.contains("distinct !DISubprogram(name: \"add\""));
+        assert!(ir.contains("!DILocation(line: 2, column: 13"));
+        assert!(ir.contains("!DILocalVariable(name: \"sum\""));
+        assert!(ir.contains("call void @llvm.dbg.declare(metadata ptr %sum_addr"));
+        assert!(ir.contains("declare void @llvm.dbg.declare(metadata, metadata, metadata)"));
+        assert!(ir.contains("!llvm.dbg.cu = !{!1}"));
+
+        let spec_without_source = spec.replacen("\"debug_source\"", "\"unused_source\"", 1);
+        fs::write(&tmp, spec_without_source)?;
+        assert!(matches!(
+            emit_llvm_module_from_mir_json(&tmp, test_target_machine(), vec![], true, "dbg"),
+            Err(MirSnapshotError::MissingDebugSource)
+        ));
+        fs::remove_file(tmp)?;
+        Ok(())
+    }
+
+    #[test]
+    fn closures_lift_lambdas_and_call_through_closure_values() -> Result<(), MirSnapshotError>
+    {
+        // fn apply(f: (Int) -> Int, x: Int) -> Int = f(x)
+        // fn main() -> Int { let k = 10; apply(|y: Int| { y + k }, 1) + apply(double, 2) }
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "apply",
+          "params": [{"name": "f", "ty": "(i64) -> i64"}, {"name": "x", "ty": "i64"}],
+          "return_type": "i64",
+          "body": 2,
+          "exprs": [
+            {"id": 0, "ty": "(i64) -> i64",
+             "kind": {"kind": "identifier", "ident": {"name": "f"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "x"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "call", "callee": 0, "args": [1]}}
+          ]
+        },
+        {
+          "name": "main",
+          "return_type": "i64",
+          "body": 13,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 10, "raw": "10", "base": "base10"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "y"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "k"}}},
+            {"id": 3, "ty": "i64",
+             "kind": {"kind": "binary", "operator": "+", "left": 1, "right": 2}},
+            {"id": 4, "ty": "(i64) -> i64",
+             "kind": {"kind": "lambda", "params": [{"name": "y", "ty": "i64"}], "body": 3,
+                      "captures": [{"name": "k"}]}},
+            {"id": 5, "ty": "((i64) -> i64, i64) -> i64",
+             "kind": {"kind": "identifier", "ident": {"name": "apply"}}},
+            {"id": 6, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 1, "raw": "1", "base": "base10"}}},
+            {"id": 7, "ty": "i64", "kind": {"kind": "call", "callee": 5, "args": [4, 6]}},
+            {"id": 8, "ty": "((i64) -> i64, i64) -> i64",
+             "kind": {"kind": "identifier", "ident": {"name": "apply"}}},
+            {"id": 9, "ty": "(i64) -> i64",
+             "kind": {"kind": "identifier", "ident": {"name": "double"}}},
+            {"id": 10, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 2, "raw": "2", "base": "base10"}}},
+            {"id": 11, "ty": "i64", "kind": {"kind": "call", "callee": 8, "args": [9, 10]}},
+            {"id": 12, "ty": "i64",
+             "kind": {"kind": "binary", "operator": "+", "left": 7, "right": 11}},
+            {"id": 13, "ty": "i64",
+             "kind": {"kind": "block", "statements": [
+               {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "k"}},
+                         "value": 0, "mutable": false}}
+             ], "tail": 12}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let tmp = env::temp_dir().join("reml_mir_closures.json");
+        fs::write(&tmp, spec)?;
+        let ir =
+            emit_llvm_module_from_mir_json(&tmp, test_target_machine(), vec![], false, "closures")?;
+        fs::remove_file(tmp)?;
+
+        // 関数値の引数は環境ポインタを先頭に足した間接呼び出しになる。
+        assert!(ir.contains("define i64 @apply(ptr %f, i64 %x)"));
+        assert!(ir.contains("call ptr @reml_closure_code_ptr(ptr %f)"));
+        assert!(ir.contains("call ptr @reml_closure_env(ptr %f)"));
+        assert!(ir.contains("= call i64 %closure_code1(ptr %closure_env2, i64 %x)"));
+
+        // キャプチャはボックス化して環境 Record に格納し、所有権をクロージャへ移す。
+        assert!(ir.contains("call ptr @reml_box_i64(i64 %load"));
+        assert!(ir.contains("call ptr (i64, ...) @reml_record_from(i64 1, ptr %box"));
+        assert!(ir.contains("ptr @reml_closure_main_4)"));
+        assert!(ir.contains("call void @dec_ref(ptr %closure_env"));
+
+        // 持ち上げた関数はプロローグで環境スロットを取り出す。
+        assert!(ir.contains("define i64 @reml_closure_main_4(ptr %__env, i64 %y)"));
+        assert!(ir.contains("call ptr @reml_record_get(ptr %__env, i64 0)"));
+        assert!(ir.contains("call i64 @reml_unbox_i64(ptr %k_slot"));
+        assert!(!ir.contains("@reml_lambda_"), "スタブシンボルを残さないこと");
+
+        // 名前付き関数を値として渡す箇所は環境なしのクロージャへ η 展開する。
+        assert!(ir.contains("call ptr @reml_closure_new(ptr null, ptr @reml_closure_main_9)"));
+        assert!(ir.contains("define i64 @reml_closure_main_9(ptr %__env, i64 %__arg0)"));
+        assert!(ir.contains("call ptr @reml_call(ptr %double, i64 %__arg0)"));
+        assert!(ir.contains("declare ptr @reml_record_from(i64, ...)"));
+        Ok(())
+    }
+
+    #[test]
+    fn effect_handlers_lower_to_runtime_handler_frames() -> Result<(), MirSnapshotError> {
+        // fn tick() -> Int = perform Counter.next(())
+        // fn main() -> Int {
+        //   let step = 2
+        //   handle tick() with handler Counter {
+        //     operation next(_unit, resume) { resume(step) }
+        //     operation reset(_unit, resume) { 0 }
+        //     operation peek(_unit, resume) { resume(step) + 1 }
+        //     return value { value }
+        //   }
+        // }
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "tick",
+          "return_type": "i64",
+          "body": 1,
+          "exprs": [
+            {"id": 0, "ty": "()", "kind": {"kind": "literal", "value": {"kind": "unit"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "perform_call",
+             "call": {"effect": {"name": "Counter::next"}, "argument": 0}}}
+          ]
+        },
+        {
+          "name": "main",
+          "return_type": "i64",
+          "body": 9,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 2, "raw": "2", "base": "base10"}}},
+            {"id": 1, "ty": "() -> i64", "kind": {"kind": "identifier", "ident": {"name": "tick"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "call", "callee": 1, "args": []}},
+            {"id": 3, "ty": "(i64) -> 't3",
+             "kind": {"kind": "identifier", "ident": {"name": "resume"}}},
+            {"id": 4, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "step"}}},
+            {"id": 5, "ty": "i64", "kind": {"kind": "call", "callee": 3, "args": [4]}},
+            {"id": 6, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 0, "raw": "0", "base": "base10"}}},
+            {"id": 7, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "value"}}},
+            {"id": 8, "ty": "i64", "kind": {"kind": "handle", "target": 2, "handler": {
+              "effect": {"name": "Counter"},
+              "operations": [
+                {"name": {"name": "next"}, "body": 5, "params": [
+                  {"name": "_unit", "ty": "()"}, {"name": "resume", "ty": "(i64) -> 't3"}]},
+                {"name": {"name": "reset"}, "body": 6, "params": [
+                  {"name": "_unit", "ty": "()"}, {"name": "resume", "ty": "(i64) -> 't3"}]},
+                {"name": {"name": "peek"}, "body": 14, "params": [
+                  {"name": "_unit", "ty": "()"}, {"name": "resume", "ty": "(i64) -> 't3"}]}
+              ],
+              "return_clause": {"value": {"name": "value", "ty": "i64"}, "body": 7}}}},
+            {"id": 9, "ty": "i64",
+             "kind": {"kind": "block", "statements": [
+               {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "step"}},
+                         "value": 0, "mutable": false}}
+             ], "tail": 8}},
+            {"id": 10, "ty": "(i64) -> 't3",
+             "kind": {"kind": "identifier", "ident": {"name": "resume"}}},
+            {"id": 11, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "step"}}},
+            {"id": 12, "ty": "i64", "kind": {"kind": "call", "callee": 10, "args": [11]}},
+            {"id": 13, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 1, "raw": "1", "base": "base10"}}},
+            {"id": 14, "ty": "i64",
+             "kind": {"kind": "binary", "operator": "+", "left": 12, "right": 13}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let tmp = env::temp_dir().join("reml_mir_effect_handlers.json");
+        fs::write(&tmp, spec)?;
+        let ir =
+            emit_llvm_module_from_mir_json(&tmp, test_target_machine(), vec![], false, "effects")?;
+        fs::remove_file(tmp)?;
+
+        // 効果名・操作名は NUL 終端したモジュール定数として渡す。
+        let counter = string_constant(&ir, "Counter");
+        let next = string_constant(&ir, "next");
+        let reset = string_constant(&ir, "reset");
+        let peek = string_constant(&ir, "peek");
+
+        // perform は効果名・操作名・ボックス化した引数で Runtime を呼び、結果を取り出す。
+        assert!(ir.contains(&format!(
+            "call ptr @reml_perform(ptr {counter}, ptr {next}, ptr null)"
+        )));
+        assert!(ir.contains("call i64 @reml_unbox_i64(ptr %perform"));
+        assert!(ir.contains("declare ptr @reml_perform(ptr, ptr, ptr)"));
+
+        // handle はハンドラフレームを組み立て、本体クロージャを Runtime に渡す。
+        assert!(ir.contains(&format!(
+            "call ptr @reml_handler_new(ptr {counter}, ptr %closure"
+        )));
+        assert!(ir.contains(&format!("ptr {next}, ptr %closure")));
+        assert!(ir.contains(&format!("ptr {reset}, ptr %closure")));
+        assert!(ir.contains("call ptr @reml_handle(ptr %handler"));
+
+        // 末尾で一度だけ resume する節は継続を作らずに直接呼び出す。
+        assert!(ir.contains(&format!("ptr {next}, ptr %closure7, i32 1)")));
+        assert!(ir.contains(&format!("ptr {reset}, ptr %closure8, i32 0)")));
+        assert!(ir.contains(&format!("ptr {peek}, ptr %closure12, i32 0)")));
+        assert!(ir.contains("call ptr @reml_resume(ptr %load"));
+
+        // 節はボックス化 ABI で持ち上げ、キャプチャを環境から取り出す。
+        assert!(ir.contains(
+            "define ptr @reml_closure_main_16(ptr %__env, ptr %_unit__boxed, ptr %resume__boxed)"
+        ));
+        assert!(ir.contains("call ptr @reml_record_get(ptr %__env, i64 0)"));
+        assert!(ir.contains("define ptr @reml_closure_main_19(ptr %__env, ptr %value__boxed)"));
+        assert!(ir.contains("call i64 @reml_unbox_i64(ptr %value__boxed)"));
+        assert!(ir.contains("call ptr @reml_box_i64(i64"));
+        Ok(())
+    }
+
+    #[test]
+    fn monomorphize_instantiates_generics_and_specializes_trait_calls(
+    ) -> Result<(), MirSnapshotError> {
+        // fn describe<T: Show>(x: T) -> Str = show(x)
+        // fn main() -> Str = describe(1)
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "Int__show",
+          "params": [{"name": "self", "ty": "i64"}],
+          "return_type": "Str",
+          "body": 0,
+          "exprs": [
+            {"id": 0, "ty": "Str", "kind": {"kind": "literal",
+             "value": {"kind": "string", "value": "int"}}}
+          ]
+        },
+        {
+          "name": "describe",
+          "params": [{"name": "x", "ty": "'t1"}],
+          "return_type": "Str",
+          "body": 2,
+          "exprs": [
+            {"id": 0, "ty": "Unknown",
+             "kind": {"kind": "identifier", "ident": {"name": "Show__show"}}},
+            {"id": 1, "ty": "'t1", "kind": {"kind": "identifier", "ident": {"name": "x"}}},
+            {"id": 2, "ty": "Str", "kind": {"kind": "call", "callee": 0, "args": [1]}}
+          ]
+        },
+        {
+          "name": "main",
+          "return_type": "Str",
+          "body": 2,
+          "exprs": [
+            {"id": 0, "ty": "('t5) -> Str",
+             "kind": {"kind": "identifier", "ident": {"name": "describe"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 1, "raw": "1", "base": "base10"}}},
+            {"id": 2, "ty": "Str", "kind": {"kind": "call", "callee": 0, "args": [1]}}
+          ]
+        }
+      ],
+      "impls": {
+        "Show::Int": {"trait": "Show", "target": "Int", "methods": ["show"]}
+      },
+      "qualified_calls": {
+        "describe#2": {"kind": "trait_method", "owner": "Show", "name": "show",
+                       "receiver_ty": "'t1"}
+      }
+    }
+    "#;
+        let tmp = env::temp_dir().join("reml_mir_monomorphize.json");
+        fs::write(&tmp, spec)?;
+        let ir = emit_llvm_module_from_mir_json(
+            &tmp,
+            test_target_machine(),
+            vec![],
+            false,
+            "monomorphize",
+        )?;
+        fs::remove_file(tmp)?;
+
+        assert!(ir.contains("define {i8*, i64} @describe__3i64(i64 %x)"));
+        assert!(ir.contains("call {i8*, i64} @describe__3i64(i64 1)"));
+        assert!(ir.contains("call {i8*, i64} @Int__show(i64 %x)"));
+        assert!(
+            !ir.contains(" describe("),
+            "ジェネリック本体は出力しないこと"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn strict_codegen_reports_record_field_and_literal_fallbacks() -> Result<(), MirSnapshotError> {
+        let cases = [
+            (
+                "record_field",
+                r#"{"kind": "record", "fields": [
+                    {"key": "x", "value": {"kind": "call", "callee": "f"}}
+                ]}"#,
+                "codegen.fallback.record_field",
+                "record field unsupported -> null",
+            ),
+            (
+                "literal",
+                r#"{"kind": "tuple", "elements": [
+                    {"kind": "int", "value": 1, "raw": "1", "base": "base10"}
+                ]}"#,
+                "codegen.fallback.literal",
+                "diag backend.literal.unsupported.tuple: len=1",
+            ),
+        ];
+        for (name, literal, code, comment) in cases {
+            let spec = format!(
+                r#"
+    {{
+      "functions": [
+        {{
+          "name": "main",
+          "return_type": "ptr",
+          "body": 0,
+          "exprs": [
+            {{"id": 0, "ty": "ptr", "span": {{"start": 4, "end": 9}},
+             "kind": {{"kind": "literal", "value": {literal}}}}}
+          ]
+        }}
+      ]
+    }}
+    "#
+            );
+            let tmp = env::temp_dir().join(format!("reml_mir_strict_{name}.json"));
+            fs::write(&tmp, spec)?;
+            let lenient = emit_llvm_module_from_mir_json_with_options(
+                &tmp,
+                test_target_machine(),
+                vec![],
+                LlvmEmitOptions::new().with_strict_codegen(false),
+                "strict",
+            )?;
+            assert!(lenient.contains(comment), "{name}: {lenient}");
+
+            let err = emit_llvm_module_from_mir_json_with_options(
+                &tmp,
+                test_target_machine(),
+                vec![],
+                LlvmEmitOptions::new().with_strict_codegen(true),
+                "strict",
+            )
+            .expect_err("strict モードは縮退箇所で中断すること");
+            fs::remove_file(tmp)?;
+            let MirSnapshotError::StrictCodegen(fallback) = err else {
+                panic!("{name}: StrictCodegen を返すこと");
+            };
+            assert_eq!(fallback.code, code);
+            assert_eq!(fallback.function, "main");
+            assert_eq!(fallback.expr_id, Some(0));
+            assert_eq!(fallback.span, Some(MirSpan { start: 4, end: 9 }));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn strict_codegen_rejects_reassigned_mutable_captures() -> Result<(), MirSnapshotError> {
+        // fn main() -> Int { var n = 0; var m = 5; let f = || n + m; n = 1; f() }
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "main",
+          "return_type": "i64",
+          "body": 10,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 0, "raw": "0", "base": "base10"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 5, "raw": "5", "base": "base10"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "n"}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "m"}}},
+            {"id": 4, "ty": "i64",
+             "kind": {"kind": "binary", "operator": "+", "left": 2, "right": 3}},
+            {"id": 5, "ty": "() -> i64",
+             "kind": {"kind": "lambda", "params": [], "body": 4,
+                      "captures": [{"name": "n", "mutable": true},
+                                   {"name": "m", "mutable": true}]}},
+            {"id": 6, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "n"}}},
+            {"id": 7, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 1, "raw": "1", "base": "base10"}}},
+            {"id": 8, "ty": "() -> i64",
+             "kind": {"kind": "identifier", "ident": {"name": "f"}}},
+            {"id": 9, "ty": "i64", "kind": {"kind": "call", "callee": 8, "args": []}},
+            {"id": 10, "ty": "i64",
+             "kind": {"kind": "block", "statements": [
+               {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "n"}},
+                         "value": 0, "mutable": true}},
+               {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "m"}},
+                         "value": 1, "mutable": true}},
+               {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "f"}},
+                         "value": 5, "mutable": false}},
+               {"kind": {"kind": "assign", "target": 6, "value": 7}}
+             ], "tail": 9}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let tmp = env::temp_dir().join("reml_mir_strict_mutable_capture.json");
+        fs::write(&tmp, spec)?;
+        let err = emit_llvm_module_from_mir_json_with_options(
+            &tmp,
+            test_target_machine(),
+            vec![],
+            LlvmEmitOptions::new().with_strict_codegen(true),
+            "strict",
+        )
+        .expect_err("再代入される可変キャプチャは strict モードで中断すること");
+        fs::remove_file(tmp)?;
+        let MirSnapshotError::StrictCodegen(fallback) = err else {
+            panic!("StrictCodegen を返すこと");
+        };
+        assert_eq!(fallback.code, "codegen.fallback.mutable_capture");
+        assert_eq!(fallback.expr_id, Some(5));
+        assert!(fallback.detail.contains("`n`"), "{}", fallback.detail);
+        Ok(())
+    }
+
+    #[test]
+    fn strict_codegen_reports_invalid_llvm_ir_placeholders() -> Result<(), MirSnapshotError> {
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "main",
+          "return_type": "i64",
+          "body": 1,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 2, "raw": "2", "base": "base10"}}},
+            {"id": 1, "ty": "i64", "span": {"start": 3, "end": 30},
+             "kind": {"kind": "llvm_ir", "result_type": "i64",
+                      "template": "%r = add i64 $0, $1", "inputs": [0]}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let tmp = env::temp_dir().join("reml_mir_strict_llvm_ir.json");
+        fs::write(&tmp, spec)?;
+        let lenient = emit_llvm_module_from_mir_json_with_options(
+            &tmp,
+            test_target_machine(),
+            vec![],
+            LlvmEmitOptions::new().with_strict_codegen(false),
+            "strict",
+        )?;
+        assert!(lenient.contains("llvm_ir expr#1 invalid placeholders: [1]"));
+
+        let err = emit_llvm_module_from_mir_json_with_options(
+            &tmp,
+            test_target_machine(),
+            vec![],
+            LlvmEmitOptions::new().with_strict_codegen(true),
+            "strict",
+        )
+        .expect_err("strict モードは範囲外のプレースホルダで中断すること");
+        fs::remove_file(tmp)?;
+        let MirSnapshotError::StrictCodegen(fallback) = err else {
+            panic!("StrictCodegen を返すこと");
+        };
+        assert_eq!(fallback.code, "codegen.fallback.llvm_ir_placeholder");
+        assert_eq!(fallback.expr_id, Some(1));
+        assert_eq!(fallback.span, Some(MirSpan { start: 3, end: 30 }));
+        Ok(())
+    }
+
+    #[test]
+    fn strict_codegen_reports_fallback_sites() -> Result<(), MirSnapshotError> {
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "main",
+          "return_type": "i64",
+          "body": 2,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 2, "raw": "2", "base": "base10"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 3, "raw": "3", "base": "base10"}}},
+            {"id": 2, "ty": "i64", "span": {"start": 12, "end": 18},
+             "kind": {"kind": "binary", "operator": "<=>", "left": 0, "right": 1}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let tmp = env::temp_dir().join("reml_mir_strict_codegen.json");
+        fs::write(&tmp, spec)?;
+        let lenient = emit_llvm_module_from_mir_json_with_options(
+            &tmp,
+            test_target_machine(),
+            vec![],
+            LlvmEmitOptions::new().with_strict_codegen(false),
+            "strict",
+        )?;
+        assert!(lenient.contains("binary op <=> unsupported -> fallback #2"));
+
+        let mut options = LlvmEmitOptions::new();
+        assert!(options.apply_flag("--strict-codegen"));
+        let err = emit_llvm_module_from_mir_json_with_options(
+            &tmp,
+            test_target_machine(),
+            vec![],
+            options,
+            "strict",
+        )
+        .expect_err("strict モードは縮退箇所で中断すること");
+        let MirSnapshotError::StrictCodegen(fallback) = err else {
+            panic!("StrictCodegen を返すこと");
+        };
+        assert_eq!(fallback.code, "codegen.fallback.binary_operator");
+        assert_eq!(fallback.expr_id, Some(2));
+        assert_eq!(fallback.span, Some(MirSpan { start: 12, end: 18 }));
+
+        // リリース相当の最適化レベルでは既定で strict になり、検証器が縮退箇所を報告する。
+        let release = TargetMachineBuilder::new()
+            .with_triple(Triple::LinuxGNU)
+            .with_optimization_level(OptimizationLevel::O2)
+            .build();
+        let snapshot = generate_snapshot_from_mir_json(&tmp, release, vec![], vec![], "strict")?;
+        fs::remove_file(tmp)?;
+        assert!(!snapshot.passed);
+        let diagnostic = "Backend.codegen.fallback.binary_operator: 関数 main の式 #2";
+        assert!(snapshot
+            .diagnostics
+            .iter()
+            .any(|diag| diag.starts_with(diagnostic)));
+        let degraded = "codegen.degraded=codegen.fallback.binary_operator function=main \
+                        expr_id=2 span=12..18 operator=<=>";
+        assert!(snapshot.audit_entries.iter().any(|entry| entry == degraded));
+        assert!(snapshot
+            .audit_entries
+            .iter()
+            .any(|entry| entry == "codegen.degraded.count=1"));
+        Ok(())
+    }
+
+    #[test]
+    fn self_tail_calls_lower_to_loop() -> Result<(), MirSnapshotError> {
+        // fn sum(n: Int, acc: Int) -> Int = if n == 0 then acc else sum(n - 1, acc + n)
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "sum",
+          "params": [{"name": "n", "ty": "i64"}, {"name": "acc", "ty": "i64"}],
+          "return_type": "i64",
+          "body": 12,
+          "tail_calls": [{"expr": 11, "callee": "sum", "kind": "self_recursive"}],
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "n"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 0, "raw": "0", "base": "base10"}}},
+            {"id": 2, "ty": "Bool",
+             "kind": {"kind": "binary", "operator": "==", "left": 0, "right": 1}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "acc"}}},
+            {"id": 4, "ty": "Unknown", "kind": {"kind": "identifier", "ident": {"name": "sum"}}},
+            {"id": 5, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "n"}}},
+            {"id": 6, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 1, "raw": "1", "base": "base10"}}},
+            {"id": 7, "ty": "i64",
+             "kind": {"kind": "binary", "operator": "-", "left": 5, "right": 6}},
+            {"id": 8, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "acc"}}},
+            {"id": 9, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "n"}}},
+            {"id": 10, "ty": "i64",
+             "kind": {"kind": "binary", "operator": "+", "left": 8, "right": 9}},
+            {"id": 11, "ty": "i64", "kind": {"kind": "call", "callee": 4, "args": [7, 10]}},
+            {"id": 12, "ty": "i64", "kind": {"kind": "if_else", "condition": 2,
+             "then_branch": 3, "else_branch": 11}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let tmp = env::temp_dir().join("reml_mir_tail_loop.json");
+        fs::write(&tmp, spec)?;
+        let ir =
+            emit_llvm_module_from_mir_json(&tmp, test_target_machine(), vec![], false, "tail")?;
+        fs::remove_file(tmp)?;
+
+        assert!(ir.contains("store i64 %n, ptr %n_loop1"));
+        assert!(ir.contains("store i64 %acc, ptr %acc_loop2"));
+        assert!(ir.contains("tailrec.loop:\n  %load3 = load i64, ptr %n_loop1"));
+        assert!(ir.contains("store i64 %sub7, ptr %n_loop1\n  store i64 %add10, ptr %acc_loop2\n  br label %tailrec.loop"));
+        assert!(
+            !ir.contains("@reml_call"),
+            "自己末尾呼び出しは呼び出しとして残さないこと"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn direct_tail_calls_use_musttail_or_report_missing_guarantee() -> Result<(), MirSnapshotError>
+    {
+        let spec = |relay_conv: &str, other_conv: &str| {
+            format!(
+                r#"
+    {{
+      "functions": [
+        {{
+          "name": "other",
+          "calling_conv": "{other_conv}",
+          "params": [{{"name": "a", "ty": "i64"}}, {{"name": "b", "ty": "i64"}}],
+          "return_type": "i64",
+          "body": 2,
+          "exprs": [
+            {{"id": 0, "ty": "i64", "kind": {{"kind": "identifier", "ident": {{"name": "a"}}}}}},
+            {{"id": 1, "ty": "i64", "kind": {{"kind": "identifier", "ident": {{"name": "b"}}}}}},
+            {{"id": 2, "ty": "i64",
+             "kind": {{"kind": "binary", "operator": "+", "left": 0, "right": 1}}}}
+          ]
+        }},
+        {{
+          "name": "swap",
+          "calling_conv": "{other_conv}",
+          "params": [{{"name": "x", "ty": "i64"}}, {{"name": "y", "ty": "i64"}}],
+          "return_type": "i64",
+          "body": 3,
+          "tail_calls": [{{"expr": 3, "callee": "other", "kind": "direct"}}],
+          "exprs": [
+            {{"id": 0, "ty": "(i64, i64) -> i64",
+             "kind": {{"kind": "identifier", "ident": {{"name": "other"}}}}}},
+            {{"id": 1, "ty": "i64", "kind": {{"kind": "identifier", "ident": {{"name": "y"}}}}}},
+            {{"id": 2, "ty": "i64", "kind": {{"kind": "identifier", "ident": {{"name": "x"}}}}}},
+            {{"id": 3, "ty": "i64", "kind": {{"kind": "call", "callee": 0, "args": [1, 2]}}}}
+          ]
+        }},
+        {{
+          "name": "relay",
+          "calling_conv": "{relay_conv}",
+          "attributes": ["tailrec"],
+          "params": [{{"name": "x", "ty": "i64"}}],
+          "return_type": "i64",
+          "body": 2,
+          "tail_calls": [{{"expr": 2, "callee": "other", "kind": "direct"}}],
+          "exprs": [
+            {{"id": 0, "ty": "(i64, i64) -> i64",
+             "kind": {{"kind": "identifier", "ident": {{"name": "other"}}}}}},
+            {{"id": 1, "ty": "i64", "kind": {{"kind": "identifier", "ident": {{"name": "x"}}}}}},
+            {{"id": 2, "ty": "i64", "span": {{"start": 40, "end": 51}},
+             "kind": {{"kind": "call", "callee": 0, "args": [1, 1]}}}}
+          ]
+        }}
+      ]
+    }}
+    "#
+            )
+        };
+        let tmp = env::temp_dir().join("reml_mir_tail_direct.json");
+        fs::write(&tmp, spec("fastcc", "fastcc"))?;
+        let ir =
+            emit_llvm_module_from_mir_json(&tmp, test_target_machine(), vec![], false, "tail")?;
+        assert!(ir.contains(
+            "%tailcall1 = musttail call fastcc i64 @other(i64 %y, i64 %x)\n  ret i64 %tailcall1"
+        ));
+        assert!(ir.contains("%tailcall1 = tail call fastcc i64 @other(i64 %x, i64 %x)"));
+
+        // ccc から引数の数が違う関数へは musttail も fastcc の保証も得られない。
+        fs::write(&tmp, spec("ccc", "fastcc"))?;
+        let err =
+            emit_llvm_module_from_mir_json(&tmp, test_target_machine(), vec![], false, "tail")
+                .expect_err("@tailrec 関数の保証できない末尾呼び出しで中断すること");
+        let MirSnapshotError::TailCallGuarantee(failure) = err else {
+            panic!("TailCallGuarantee を返すこと");
+        };
+        assert_eq!(failure.function, "relay");
+        assert_eq!(failure.expr_id, Some(2));
+        assert_eq!(failure.span, Some(MirSpan { start: 40, end: 51 }));
+
+        let snapshot =
+            generate_snapshot_from_mir_json(&tmp, test_target_machine(), vec![], vec![], "tail")?;
+        fs::remove_file(tmp)?;
+        assert!(!snapshot.passed);
+        assert!(snapshot.diagnostics.iter().any(|diag| diag.starts_with(
+            "Backend.codegen.tail_call.unguaranteed: @tailrec 関数 relay の式 #2 の"
+        )));
+        Ok(())
+    }
+
     #[test]
     fn load_functions_from_json_file() -> Result<(), MirSnapshotError> {
         let spec = r#"
@@ -2203,6 +3382,16 @@ mod tests {
         Ok(())
     }
 
+    /// `text` を保持するモジュール定数のシンボルを返す。
+    fn string_constant(ir: &str, text: &str) -> String {
+        let initializer = format!(" x i8] c\"{text}\\00\"");
+        ir.lines()
+            .find(|line| line.ends_with(&initializer))
+            .and_then(|line| line.split_whitespace().next())
+            .unwrap_or_else(|| panic!("文字列定数 {text} が見つからない: {ir}"))
+            .to_string()
+    }
+
     fn assert_ordered_occurrences(llvm_ir: &str, labels: &[&str], note: &str) {
         let mut last = None;
         for label in labels {
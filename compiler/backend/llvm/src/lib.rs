@@ -5,28 +5,51 @@
 //! 仕様と実装の差分を抑えるための出発点となる。
 
 pub mod bridge_metadata;
+pub mod c_header;
+mod closure_conversion;
 pub mod codegen;
+pub mod debug_info;
+mod effect_lowering;
 pub mod ffi_lowering;
 pub mod integration;
 pub mod intrinsics;
+mod match_tree;
+pub mod monomorphize;
 pub mod runtime_link;
+pub mod tail_calls;
 pub mod target_diagnostics;
 pub mod target_machine;
 pub mod type_mapping;
 pub mod unstable;
 pub mod verify;
 
-pub use codegen::{CodegenContext, GeneratedFunction, MirFunction, ModuleIr};
+pub use c_header::{
+    generate_c_header, CHeader, CHeaderDiagnostic, CHeaderExtern, CHeaderField, CHeaderLayout,
+    CHeaderOptions, CHeaderType,
+};
+pub use closure_conversion::{
+    convert_closures, identifier_name, parse_function_type_token, reml_type_from_token,
+    CLOSURE_ENV_PARAM,
+};
+pub use codegen::{
+    CodegenContext, CodegenFallback, GeneratedFunction, MirFunction, MirSpan, ModuleIr,
+};
+pub use debug_info::{DebugInfoBuilder, DebugSourceMap};
 pub use ffi_lowering::{FfiCallSignature, FfiLowering, LoweredFfiCall};
 pub use integration::{
-    generate_snapshot, generate_snapshot_from_mir_json, generate_w3_snapshot,
-    load_mir_functions_from_json, BackendDiffSnapshot, BackendFunctionRecord, MirSnapshotError,
+    emit_c_header_from_mir_json, emit_c_header_from_mir_json_str, emit_llvm_module_from_mir_json,
+    emit_llvm_module_from_mir_json_with_options, generate_snapshot,
+    generate_snapshot_from_mir_json, generate_w3_snapshot, load_mir_functions_from_json,
+    load_monomorphized_mir_from_json, load_monomorphized_mir_from_json_str, BackendDiffSnapshot,
+    BackendFunctionRecord, LlvmEmitOptions, MirSnapshotError, MonomorphizedMirModule,
 };
 pub use intrinsics::{IntrinsicSignature, IntrinsicStatus, IntrinsicUse};
+pub use monomorphize::{MonoImpl, MonomorphizeResult, Monomorphizer};
 pub use runtime_link::{
     compile_ir_with_llc, find_runtime_library, generate_link_command, link_object_with_runtime,
     link_with_runtime, LinkCommand, Platform, RuntimeLinkError,
 };
+pub use tail_calls::{FunctionPrototype, MirTailCall, TailCallFailure, TailCallKind};
 pub use target_diagnostics::{PlatformInfo, RunConfigTarget, TargetDiagnosticContext};
 pub use target_machine::{
     CodeModel, DataLayoutSpec, OptimizationLevel, RelocModel, TargetMachine, TargetMachineBuilder,
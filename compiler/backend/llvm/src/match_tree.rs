@@ -0,0 +1,1233 @@
+//! match 式を決定木へコンパイルする。
+//!
+//! 各腕を「判定位置（occurrence）ごとの未解決テスト」の集合として持ち、列選択の
+//! ヒューリスティックで判定位置を 1 つ選んで分岐する（Maranget の決定木コンパイル）。
+//! 分岐先では結果が確定したテストを行から取り除き、コンストラクタ引数や要素のテストを
+//! 新しい判定位置として加えるため、同じ判定位置のテストは 1 経路につき 1 回しか走らない。
+//! 構造が等しい部分木は 1 つのノードに共有し、ガード失敗時の後戻り先も重複させない。
+
+use std::collections::HashMap;
+
+use crate::codegen::{
+    classify_pattern_literal, summarize_pattern, ActivePatternKind, MatchLoweringPlan,
+    MirActivePatternCall, MirMatchArm, MirPattern, MirPatternKind, MirSlicePattern,
+};
+
+/// 範囲パターンを個々の値へ展開して `switch` の case にする幅の上限。
+const SWITCH_RANGE_EXPANSION_LIMIT: i64 = 16;
+
+/// 決定木が区別するリテラルの種類。
+pub(crate) enum PatternLiteral {
+    Int(i64),
+    Bool(bool),
+    String,
+    Other,
+}
+
+/// 判定位置を親の値から取り出す 1 段の射影。
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Access {
+    /// コンストラクタ `ctor`（引数 `arity` 個）の `index` 番目の引数。
+    CtorField {
+        ctor: String,
+        index: usize,
+        arity: usize,
+    },
+    TupleElem(usize),
+    RecordField(String),
+    /// スライスの先頭から `index` 番目（0 始まり）。
+    SliceHead(usize),
+    /// スライスの末尾から `offset` 番目（1 始まり）。
+    SliceBack(usize),
+    /// 先頭 `head` 個と末尾 `tail` 個を除いた残り。
+    SliceRest {
+        head: usize,
+        tail: usize,
+    },
+    /// アクティブパターン `name` の結果。部分パターンなら `Some` の中身。
+    ActiveResult {
+        name: String,
+        partial: bool,
+    },
+}
+
+/// 照合対象からの射影の列。空なら照合対象そのもの。
+pub(crate) type Occurrence = Vec<Access>;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct RangeBound {
+    /// IR に書くオペランド（リテラルか定数名）。
+    pub operand: String,
+    /// 整数リテラルなら値。
+    pub value: Option<i64>,
+}
+
+/// 判定位置に対するテスト。`switch` の case 値も兼ねる。
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Test {
+    Ctor {
+        name: String,
+        arity: usize,
+    },
+    Int(i64),
+    Bool(bool),
+    /// 整数・真偽値以外のリテラル（文字列など）との等値比較。
+    Literal(String),
+    Range {
+        start: Option<RangeBound>,
+        end: Option<RangeBound>,
+        inclusive: bool,
+    },
+    /// 長さがちょうど `len`（`rest` なら `len` 以上）。
+    SliceLen {
+        len: usize,
+        rest: bool,
+    },
+    Regex(String),
+    Active {
+        name: String,
+        partial: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum SwitchKind {
+    Ctor,
+    Int,
+    Bool,
+    SliceLen,
+}
+
+pub(crate) type NodeId = usize;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum DecisionNode {
+    /// どの腕にも一致しない。
+    Fail,
+    /// 腕 `arm` に確定した。束縛は判定位置から取り出す。
+    Leaf {
+        arm: usize,
+        bindings: Vec<(String, Occurrence)>,
+    },
+    /// ガード付きの腕。ガードが偽なら `otherwise` から照合を続ける。
+    Guard {
+        arm: usize,
+        bindings: Vec<(String, Occurrence)>,
+        otherwise: NodeId,
+    },
+    /// 互いに素な値による多分岐。`default` が None なら case が取り得る値をすべて覆う。
+    Switch {
+        occurrence: Occurrence,
+        kind: SwitchKind,
+        cases: Vec<(Test, NodeId)>,
+        default: Option<NodeId>,
+    },
+    /// 2 分岐のテスト。`otherwise` が None なら失敗しない（全域アクティブパターンなど）。
+    Test {
+        occurrence: Occurrence,
+        test: Test,
+        then: NodeId,
+        otherwise: Option<NodeId>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DecisionTree {
+    pub nodes: Vec<DecisionNode>,
+    pub root: NodeId,
+}
+
+impl DecisionTree {
+    /// 根から到達できるノードを前順（根が先頭）で返す。
+    pub(crate) fn reachable(&self) -> Vec<NodeId> {
+        let mut order = Vec::new();
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            if std::mem::replace(&mut seen[id], true) {
+                continue;
+            }
+            order.push(id);
+            let mut children = self.children(id);
+            children.reverse();
+            stack.extend(children);
+        }
+        order
+    }
+
+    pub(crate) fn children(&self, id: NodeId) -> Vec<NodeId> {
+        match &self.nodes[id] {
+            DecisionNode::Fail | DecisionNode::Leaf { .. } => Vec::new(),
+            DecisionNode::Guard { otherwise, .. } => vec![*otherwise],
+            DecisionNode::Switch { cases, default, .. } => cases
+                .iter()
+                .map(|(_, target)| *target)
+                .chain(*default)
+                .collect(),
+            DecisionNode::Test {
+                then, otherwise, ..
+            } => std::iter::once(*then).chain(*otherwise).collect(),
+        }
+    }
+
+    /// 到達できる腕の番号（昇順）。
+    pub(crate) fn reachable_arms(&self) -> Vec<usize> {
+        let mut arms = self
+            .reachable()
+            .into_iter()
+            .filter_map(|id| match &self.nodes[id] {
+                DecisionNode::Leaf { arm, .. } | DecisionNode::Guard { arm, .. } => Some(*arm),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        arms.sort_unstable();
+        arms.dedup();
+        arms
+    }
+
+    /// 到達できる判定ノード（`Switch`/`Test`）の数。
+    pub(crate) fn test_count(&self) -> usize {
+        self.reachable()
+            .into_iter()
+            .filter(|id| {
+                matches!(
+                    self.nodes[*id],
+                    DecisionNode::Switch { .. } | DecisionNode::Test { .. }
+                )
+            })
+            .count()
+    }
+}
+
+/// 腕の列を決定木へコンパイルする。
+pub(crate) fn compile_match(
+    arms: &[MirMatchArm],
+    plan: Option<&MatchLoweringPlan>,
+) -> DecisionTree {
+    let mut compiler = MatchCompiler {
+        guarded: arms.iter().map(|arm| arm.guard.is_some()).collect(),
+        exhaustive: plan.map(|plan| plan.exhaustive).unwrap_or(false),
+        constructors: plan
+            .map(|plan| plan.constructors.clone())
+            .unwrap_or_default(),
+        nodes: Vec::new(),
+        interned: HashMap::new(),
+    };
+    let mut rows = Vec::new();
+    for (index, arm) in arms.iter().enumerate() {
+        for fragment in expand(Vec::new(), &arm.pattern) {
+            rows.push(Row {
+                tests: fragment.tests,
+                bindings: fragment.bindings,
+                arm: index,
+            });
+        }
+    }
+    let root = compiler.compile(rows);
+    DecisionTree {
+        nodes: compiler.nodes,
+        root,
+    }
+}
+
+/// 行に残っている未解決のテスト。引数や要素のパターンはテスト成功後に展開する。
+#[derive(Clone, Debug)]
+enum Pending {
+    Ctor {
+        name: String,
+        args: Vec<MirPattern>,
+    },
+    Int(i64),
+    Bool(bool),
+    Literal(String),
+    Range {
+        start: Option<RangeBound>,
+        end: Option<RangeBound>,
+        inclusive: bool,
+    },
+    Slice {
+        head: Vec<MirPattern>,
+        rest: Option<Option<String>>,
+        tail: Vec<MirPattern>,
+    },
+    Regex(String),
+    Active {
+        name: String,
+        partial: bool,
+        argument: Option<MirPattern>,
+    },
+}
+
+impl Pending {
+    fn test(&self) -> Test {
+        match self {
+            Pending::Ctor { name, args } => Test::Ctor {
+                name: name.clone(),
+                arity: args.len(),
+            },
+            Pending::Int(value) => Test::Int(*value),
+            Pending::Bool(value) => Test::Bool(*value),
+            Pending::Literal(summary) => Test::Literal(summary.clone()),
+            Pending::Range {
+                start,
+                end,
+                inclusive,
+            } => Test::Range {
+                start: start.clone(),
+                end: end.clone(),
+                inclusive: *inclusive,
+            },
+            Pending::Slice { head, rest, tail } => Test::SliceLen {
+                len: head.len() + tail.len(),
+                rest: rest.is_some(),
+            },
+            Pending::Regex(pattern) => Test::Regex(pattern.clone()),
+            Pending::Active { name, partial, .. } => Test::Active {
+                name: name.clone(),
+                partial: *partial,
+            },
+        }
+    }
+
+    /// テストが成功したときに新しく判定する引数・要素と束縛。
+    fn decompose(&self, occurrence: &Occurrence) -> Vec<Fragment> {
+        let mut parts: Vec<(Occurrence, &MirPattern)> = Vec::new();
+        let mut bindings = Vec::new();
+        match self {
+            Pending::Ctor { name, args } => {
+                for (index, arg) in args.iter().enumerate() {
+                    parts.push((
+                        child(
+                            occurrence,
+                            Access::CtorField {
+                                ctor: name.clone(),
+                                index,
+                                arity: args.len(),
+                            },
+                        ),
+                        arg,
+                    ));
+                }
+            }
+            Pending::Slice { head, rest, tail } => {
+                for (index, element) in head.iter().enumerate() {
+                    parts.push((child(occurrence, Access::SliceHead(index)), element));
+                }
+                for (index, element) in tail.iter().enumerate() {
+                    parts.push((
+                        child(occurrence, Access::SliceBack(tail.len() - index)),
+                        element,
+                    ));
+                }
+                if let Some(Some(name)) = rest {
+                    bindings.push((
+                        name.clone(),
+                        child(
+                            occurrence,
+                            Access::SliceRest {
+                                head: head.len(),
+                                tail: tail.len(),
+                            },
+                        ),
+                    ));
+                }
+            }
+            Pending::Active {
+                name,
+                partial,
+                argument: Some(argument),
+            } => {
+                parts.push((
+                    child(
+                        occurrence,
+                        Access::ActiveResult {
+                            name: name.clone(),
+                            partial: *partial,
+                        },
+                    ),
+                    argument,
+                ));
+            }
+            _ => {}
+        }
+        let mut fragments = vec![Fragment {
+            tests: Vec::new(),
+            bindings,
+        }];
+        for (occurrence, pattern) in parts {
+            fragments = product(fragments, expand(occurrence, pattern));
+        }
+        fragments
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Fragment {
+    tests: Vec<(Occurrence, Pending)>,
+    bindings: Vec<(String, Occurrence)>,
+}
+
+#[derive(Clone, Debug)]
+struct Row {
+    tests: Vec<(Occurrence, Pending)>,
+    bindings: Vec<(String, Occurrence)>,
+    arm: usize,
+}
+
+impl Row {
+    fn position(&self, occurrence: &Occurrence) -> Option<usize> {
+        self.tests.iter().position(|(occ, _)| occ == occurrence)
+    }
+}
+
+/// 分岐先で分かっている判定位置の事実。
+enum Fact<'a> {
+    /// `switch` の case 値と一致した。
+    Is(&'a Test),
+    /// `switch` のどの case とも一致しなかった。
+    Otherwise(&'a [Test]),
+    /// 2 分岐テストが成功した。
+    Passes(&'a Test),
+    /// 2 分岐テストが失敗した。
+    Fails(&'a Test),
+}
+
+enum Outcome {
+    /// テストは成功が確定した。引数や要素を展開する。
+    Agree,
+    /// 一致し得ない。行を捨てる。
+    Drop,
+    /// まだ分からない。テストを残す。
+    Keep,
+}
+
+fn outcome(pending: &Pending, fact: &Fact<'_>) -> Outcome {
+    let own = pending.test();
+    match fact {
+        Fact::Is(case) => match (pending, *case) {
+            (Pending::Ctor { .. }, Test::Ctor { .. })
+            | (Pending::Int(_), Test::Int(_))
+            | (Pending::Bool(_), Test::Bool(_)) => agree_if(own == **case),
+            (Pending::Range { .. }, Test::Int(value)) => match range_contains(&own, *value) {
+                Some(inside) => agree_if(inside),
+                None => Outcome::Keep,
+            },
+            (Pending::Slice { rest, .. }, Test::SliceLen { len, .. }) => {
+                let need = slice_need(pending);
+                agree_if(if rest.is_some() {
+                    *len >= need
+                } else {
+                    *len == need
+                })
+            }
+            _ => Outcome::Keep,
+        },
+        Fact::Otherwise(cases) => {
+            let enumerated = match pending {
+                Pending::Ctor { .. } | Pending::Int(_) | Pending::Bool(_) => cases.contains(&own),
+                Pending::Slice { rest: None, .. } => cases.contains(&own),
+                Pending::Range { .. } => range_values(&own)
+                    .map(|values| values.into_iter().all(|v| cases.contains(&Test::Int(v))))
+                    .unwrap_or(false),
+                _ => false,
+            };
+            if enumerated {
+                Outcome::Drop
+            } else {
+                Outcome::Keep
+            }
+        }
+        Fact::Passes(test) => {
+            if own == **test {
+                return Outcome::Agree;
+            }
+            match (pending, *test) {
+                (Pending::Literal(own), Test::Literal(other))
+                    if is_string_literal(own) && is_string_literal(other) =>
+                {
+                    Outcome::Drop
+                }
+                (Pending::Int(value), Test::Range { .. }) => match range_contains(test, *value) {
+                    Some(false) => Outcome::Drop,
+                    _ => Outcome::Keep,
+                },
+                (Pending::Range { .. }, Test::Range { .. }) => {
+                    match (range_bounds(test), range_bounds(&own)) {
+                        (Some((lo, hi)), Some((own_lo, own_hi))) => {
+                            if own_lo <= lo && hi <= own_hi {
+                                Outcome::Agree
+                            } else if hi < own_lo || own_hi < lo {
+                                Outcome::Drop
+                            } else {
+                                Outcome::Keep
+                            }
+                        }
+                        _ => Outcome::Keep,
+                    }
+                }
+                (Pending::Slice { rest, .. }, Test::SliceLen { len, rest: true }) => {
+                    let need = slice_need(pending);
+                    match rest {
+                        Some(_) if need <= *len => Outcome::Agree,
+                        None if need < *len => Outcome::Drop,
+                        _ => Outcome::Keep,
+                    }
+                }
+                _ => Outcome::Keep,
+            }
+        }
+        Fact::Fails(test) => {
+            if own == **test {
+                return Outcome::Drop;
+            }
+            match (pending, *test) {
+                (Pending::Int(value), Test::Range { .. }) => match range_contains(test, *value) {
+                    Some(true) => Outcome::Drop,
+                    _ => Outcome::Keep,
+                },
+                (Pending::Range { .. }, Test::Range { .. }) => {
+                    match (range_bounds(test), range_bounds(&own)) {
+                        (Some((lo, hi)), Some((own_lo, own_hi)))
+                            if lo <= own_lo && own_hi <= hi =>
+                        {
+                            Outcome::Drop
+                        }
+                        _ => Outcome::Keep,
+                    }
+                }
+                (Pending::Slice { .. }, Test::SliceLen { len, rest: true }) => {
+                    if slice_need(pending) >= *len {
+                        Outcome::Drop
+                    } else {
+                        Outcome::Keep
+                    }
+                }
+                _ => Outcome::Keep,
+            }
+        }
+    }
+}
+
+/// 文字列リテラル同士なら要約が異なれば値も異なる（浮動小数などは表記揺れがあるため除く）。
+fn is_string_literal(summary: &str) -> bool {
+    matches!(classify_pattern_literal(summary), PatternLiteral::String)
+}
+
+fn agree_if(condition: bool) -> Outcome {
+    if condition {
+        Outcome::Agree
+    } else {
+        Outcome::Drop
+    }
+}
+
+fn slice_need(pending: &Pending) -> usize {
+    match pending {
+        Pending::Slice { head, tail, .. } => head.len() + tail.len(),
+        _ => 0,
+    }
+}
+
+/// 整数リテラルの境界を閉区間に直す。境界が定数名なら None。
+fn range_bounds(test: &Test) -> Option<(i64, i64)> {
+    let Test::Range {
+        start,
+        end,
+        inclusive,
+    } = test
+    else {
+        return None;
+    };
+    let lo = match start {
+        Some(bound) => bound.value?,
+        None => i64::MIN,
+    };
+    let hi = match end {
+        Some(bound) if *inclusive => bound.value?,
+        Some(bound) => bound.value?.checked_sub(1)?,
+        None => i64::MAX,
+    };
+    Some((lo, hi))
+}
+
+fn range_contains(test: &Test, value: i64) -> Option<bool> {
+    range_bounds(test).map(|(lo, hi)| lo <= value && value <= hi)
+}
+
+/// `switch` の case へ展開できる狭い範囲なら、含まれる値を返す。
+fn range_values(test: &Test) -> Option<Vec<i64>> {
+    let (lo, hi) = range_bounds(test)?;
+    if lo > hi {
+        return Some(Vec::new());
+    }
+    if hi.checked_sub(lo)? >= SWITCH_RANGE_EXPANSION_LIMIT {
+        return None;
+    }
+    Some((lo..=hi).collect())
+}
+
+fn child(parent: &Occurrence, access: Access) -> Occurrence {
+    let mut occurrence = parent.clone();
+    occurrence.push(access);
+    occurrence
+}
+
+fn product(left: Vec<Fragment>, right: Vec<Fragment>) -> Vec<Fragment> {
+    let mut out = Vec::with_capacity(left.len() * right.len());
+    for lhs in &left {
+        for rhs in &right {
+            let mut merged = lhs.clone();
+            merged.tests.extend(rhs.tests.iter().cloned());
+            merged.bindings.extend(rhs.bindings.iter().cloned());
+            out.push(merged);
+        }
+    }
+    out
+}
+
+/// パターンを判定位置ごとのテストへ分解する。or パターンは選択肢ごとの断片になる。
+fn expand(occurrence: Occurrence, pattern: &MirPattern) -> Vec<Fragment> {
+    let single = |pending: Pending| {
+        vec![Fragment {
+            tests: vec![(occurrence.clone(), pending)],
+            bindings: Vec::new(),
+        }]
+    };
+    match &pattern.kind {
+        MirPatternKind::Wildcard => vec![Fragment::default()],
+        MirPatternKind::Var { name } => vec![Fragment {
+            tests: Vec::new(),
+            bindings: vec![(name.clone(), occurrence)],
+        }],
+        MirPatternKind::Binding { name, pattern, .. } => {
+            let mut fragments = expand(occurrence.clone(), pattern);
+            for fragment in &mut fragments {
+                fragment
+                    .bindings
+                    .insert(0, (name.clone(), occurrence.clone()));
+            }
+            fragments
+        }
+        MirPatternKind::Or { variants } => variants
+            .iter()
+            .flat_map(|variant| expand(occurrence.clone(), variant))
+            .collect(),
+        MirPatternKind::Tuple { elements } => {
+            let mut fragments = vec![Fragment::default()];
+            for (index, element) in elements.iter().enumerate() {
+                fragments = product(
+                    fragments,
+                    expand(child(&occurrence, Access::TupleElem(index)), element),
+                );
+            }
+            fragments
+        }
+        MirPatternKind::Record { fields, .. } => {
+            let mut fragments = vec![Fragment::default()];
+            for field in fields {
+                let field_occurrence = child(&occurrence, Access::RecordField(field.key.clone()));
+                let part = match &field.value {
+                    Some(value) => expand(field_occurrence, value),
+                    None => vec![Fragment {
+                        tests: Vec::new(),
+                        bindings: vec![(field.key.clone(), field_occurrence)],
+                    }],
+                };
+                fragments = product(fragments, part);
+            }
+            fragments
+        }
+        MirPatternKind::Constructor { name, args } => single(Pending::Ctor {
+            name: name.clone(),
+            args: args.clone(),
+        }),
+        MirPatternKind::Literal { summary } => single(match classify_pattern_literal(summary) {
+            PatternLiteral::Int(value) => Pending::Int(value),
+            PatternLiteral::Bool(value) => Pending::Bool(value),
+            _ => Pending::Literal(summary.trim().to_string()),
+        }),
+        MirPatternKind::Range {
+            start: None,
+            end: None,
+            ..
+        } => vec![Fragment::default()],
+        MirPatternKind::Range {
+            start,
+            end,
+            inclusive,
+        } => single(Pending::Range {
+            start: start.as_deref().map(range_bound),
+            end: end.as_deref().map(range_bound),
+            inclusive: *inclusive,
+        }),
+        MirPatternKind::Slice(MirSlicePattern { head, rest, tail }) => single(Pending::Slice {
+            head: head.clone(),
+            rest: rest.as_ref().map(|rest| rest.binding.clone()),
+            tail: tail.clone(),
+        }),
+        MirPatternKind::Regex { pattern } => single(Pending::Regex(pattern.clone())),
+        MirPatternKind::Active(MirActivePatternCall {
+            name,
+            kind,
+            argument,
+            input_binding,
+            ..
+        }) => {
+            let mut fragments = single(Pending::Active {
+                name: name.clone(),
+                partial: matches!(kind, ActivePatternKind::Partial),
+                argument: argument.as_deref().cloned(),
+            });
+            if let Some(input) = input_binding {
+                fragments[0].bindings.push((input.clone(), occurrence));
+            }
+            fragments
+        }
+    }
+}
+
+fn range_bound(pattern: &MirPattern) -> RangeBound {
+    match &pattern.kind {
+        MirPatternKind::Literal { summary } => match classify_pattern_literal(summary) {
+            PatternLiteral::Int(value) => RangeBound {
+                operand: value.to_string(),
+                value: Some(value),
+            },
+            _ => RangeBound {
+                operand: summary.trim().to_string(),
+                value: None,
+            },
+        },
+        MirPatternKind::Var { name } => RangeBound {
+            operand: format!("%{name}"),
+            value: None,
+        },
+        _ => RangeBound {
+            operand: summarize_pattern(pattern),
+            value: None,
+        },
+    }
+}
+
+struct MatchCompiler {
+    guarded: Vec<bool>,
+    exhaustive: bool,
+    constructors: Vec<String>,
+    nodes: Vec<DecisionNode>,
+    interned: HashMap<DecisionNode, NodeId>,
+}
+
+impl MatchCompiler {
+    fn intern(&mut self, node: DecisionNode) -> NodeId {
+        if let Some(id) = self.interned.get(&node) {
+            return *id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(node.clone());
+        self.interned.insert(node, id);
+        id
+    }
+
+    fn is_fail(&self, id: NodeId) -> bool {
+        matches!(self.nodes[id], DecisionNode::Fail)
+    }
+
+    fn compile(&mut self, rows: Vec<Row>) -> NodeId {
+        let Some(first) = rows.first() else {
+            return self.intern(DecisionNode::Fail);
+        };
+        if first.tests.is_empty() {
+            let arm = first.arm;
+            let bindings = first.bindings.clone();
+            if self.guarded[arm] {
+                let otherwise = self.compile(rows[1..].to_vec());
+                return self.intern(DecisionNode::Guard {
+                    arm,
+                    bindings,
+                    otherwise,
+                });
+            }
+            return self.intern(DecisionNode::Leaf { arm, bindings });
+        }
+        let occurrence = select_occurrence(&rows);
+        let position = first.position(&occurrence).unwrap_or(0);
+        let head = first.tests[position].1.test();
+        match switch_kind(&head) {
+            Some(kind) => self.compile_switch(rows, occurrence, kind),
+            None => self.compile_test(rows, occurrence, head),
+        }
+    }
+
+    fn compile_switch(
+        &mut self,
+        rows: Vec<Row>,
+        occurrence: Occurrence,
+        kind: SwitchKind,
+    ) -> NodeId {
+        let mut values = Vec::new();
+        for row in &rows {
+            let Some(position) = row.position(&occurrence) else {
+                continue;
+            };
+            let test = row.tests[position].1.test();
+            let found = match (&test, kind) {
+                (Test::Ctor { .. }, SwitchKind::Ctor)
+                | (Test::Int(_), SwitchKind::Int)
+                | (Test::SliceLen { rest: false, .. }, SwitchKind::SliceLen) => vec![test],
+                (Test::Range { .. }, SwitchKind::Int) => range_values(&test)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(Test::Int)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for value in found {
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+        }
+        match kind {
+            SwitchKind::Bool => values = vec![Test::Bool(false), Test::Bool(true)],
+            SwitchKind::Int | SwitchKind::SliceLen => values.sort_by_key(switch_value),
+            SwitchKind::Ctor => {}
+        }
+        let complete = match kind {
+            SwitchKind::Bool => true,
+            SwitchKind::Ctor => self.ctor_signature_complete(&occurrence, &values),
+            SwitchKind::Int | SwitchKind::SliceLen => false,
+        };
+        let mut cases = Vec::with_capacity(values.len());
+        for value in &values {
+            let specialized = specialize(&rows, &occurrence, &Fact::Is(value));
+            cases.push((value.clone(), self.compile(specialized)));
+        }
+        let mut default = if complete {
+            None
+        } else {
+            let specialized = specialize(&rows, &occurrence, &Fact::Otherwise(&values));
+            Some(self.compile(specialized))
+        };
+        if self.exhaustive && default.map(|id| self.is_fail(id)).unwrap_or(false) {
+            default = None;
+        }
+        if let Some(&(_, target)) = cases.first() {
+            // 既定分岐がなく case が 1 つなら値は確定している。分岐先が全部同じでも、
+            // 引数や要素を取り出す分岐ではテストを省くと射影が不正になるので残す。
+            let decomposes = kind == SwitchKind::SliceLen
+                || values
+                    .iter()
+                    .any(|value| matches!(value, Test::Ctor { arity, .. } if *arity > 0));
+            let uniform = cases.iter().all(|(_, other)| *other == target)
+                && default.map(|other| other == target).unwrap_or(true);
+            if (cases.len() == 1 && default.is_none()) || (uniform && !decomposes) {
+                return target;
+            }
+        }
+        self.intern(DecisionNode::Switch {
+            occurrence,
+            kind,
+            cases,
+            default,
+        })
+    }
+
+    fn compile_test(&mut self, rows: Vec<Row>, occurrence: Occurrence, test: Test) -> NodeId {
+        let then = self.compile(specialize(&rows, &occurrence, &Fact::Passes(&test)));
+        let total_active = matches!(test, Test::Active { partial: false, .. });
+        let mut otherwise = if total_active {
+            None
+        } else {
+            Some(self.compile(specialize(&rows, &occurrence, &Fact::Fails(&test))))
+        };
+        let is_active = matches!(test, Test::Active { .. });
+        if self.exhaustive && otherwise.map(|id| self.is_fail(id)).unwrap_or(false) {
+            if !is_active {
+                return then;
+            }
+            otherwise = None;
+        }
+        if !is_active && otherwise == Some(then) {
+            return then;
+        }
+        self.intern(DecisionNode::Test {
+            occurrence,
+            test,
+            then,
+            otherwise,
+        })
+    }
+
+    /// case のコンストラクタが型のコンストラクタをすべて尽くしているか。
+    fn ctor_signature_complete(&self, occurrence: &Occurrence, values: &[Test]) -> bool {
+        let names = values
+            .iter()
+            .filter_map(|value| match value {
+                Test::Ctor { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let covers = |signature: &[&str]| {
+            names.iter().all(|name| signature.contains(name))
+                && signature.iter().all(|name| names.contains(name))
+        };
+        if occurrence.is_empty() && !self.constructors.is_empty() {
+            let signature = self
+                .constructors
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            if names.iter().all(|name| signature.contains(name)) {
+                return covers(&signature);
+            }
+        }
+        covers(&["Some", "None"]) || covers(&["Ok", "Err"])
+    }
+}
+
+fn switch_kind(test: &Test) -> Option<SwitchKind> {
+    match test {
+        Test::Ctor { .. } => Some(SwitchKind::Ctor),
+        Test::Int(_) => Some(SwitchKind::Int),
+        Test::Range { .. } if range_values(test).is_some() => Some(SwitchKind::Int),
+        Test::Bool(_) => Some(SwitchKind::Bool),
+        Test::SliceLen { rest: false, .. } => Some(SwitchKind::SliceLen),
+        _ => None,
+    }
+}
+
+fn switch_value(test: &Test) -> i64 {
+    match test {
+        Test::Int(value) => *value,
+        Test::SliceLen { len, .. } => *len as i64,
+        Test::Bool(value) => i64::from(*value),
+        _ => 0,
+    }
+}
+
+/// 列選択のヒューリスティック。
+///
+/// 先頭行がテストを持つ判定位置のうち、上から連続してテストを要求する行が最も多いもの
+/// （必要度）を選び、同点なら分岐数の少ないもの、さらに同点なら左にあるものを選ぶ。
+fn select_occurrence(rows: &[Row]) -> Occurrence {
+    let first = &rows[0];
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (index, (occurrence, _)) in first.tests.iter().enumerate() {
+        let needed = rows
+            .iter()
+            .take_while(|row| row.position(occurrence).is_some())
+            .count();
+        let mut distinct: Vec<Test> = Vec::new();
+        for row in rows {
+            if let Some(position) = row.position(occurrence) {
+                let test = row.tests[position].1.test();
+                if !distinct.contains(&test) {
+                    distinct.push(test);
+                }
+            }
+        }
+        let candidate = (needed, distinct.len(), index);
+        let better = match best {
+            None => true,
+            Some((best_needed, best_branching, _)) => {
+                needed > best_needed || (needed == best_needed && distinct.len() < best_branching)
+            }
+        };
+        if better {
+            best = Some(candidate);
+        }
+    }
+    let index = best.map(|(_, _, index)| index).unwrap_or(0);
+    first.tests[index].0.clone()
+}
+
+/// 判定位置 `occurrence` について `fact` が成り立つ分岐先の行列を作る。行の順序は保つ。
+fn specialize(rows: &[Row], occurrence: &Occurrence, fact: &Fact<'_>) -> Vec<Row> {
+    let mut out = Vec::new();
+    for row in rows {
+        let Some(position) = row.position(occurrence) else {
+            out.push(row.clone());
+            continue;
+        };
+        let pending = &row.tests[position].1;
+        match outcome(pending, fact) {
+            Outcome::Drop => {}
+            Outcome::Keep => out.push(row.clone()),
+            Outcome::Agree => {
+                for fragment in pending.decompose(occurrence) {
+                    let mut tests = row.tests[..position].to_vec();
+                    tests.extend(fragment.tests);
+                    tests.extend(row.tests[position + 1..].iter().cloned());
+                    let mut bindings = row.bindings.clone();
+                    bindings.extend(fragment.bindings);
+                    out.push(Row {
+                        tests,
+                        bindings,
+                        arm: row.arm,
+                    });
+                }
+            }
+        }
+    }
+    out
+}
+
+/// 判定位置を人が読める形にする（`$` が照合対象）。
+pub(crate) fn describe_occurrence(occurrence: &Occurrence) -> String {
+    let mut buf = String::from("$");
+    for access in occurrence {
+        match access {
+            Access::CtorField { ctor, index, .. } => buf.push_str(&format!(".{ctor}.{index}")),
+            Access::TupleElem(index) => buf.push_str(&format!(".{index}")),
+            Access::RecordField(key) => buf.push_str(&format!(".{key}")),
+            Access::SliceHead(index) => buf.push_str(&format!("[{index}]")),
+            Access::SliceBack(offset) => buf.push_str(&format!("[-{offset}]")),
+            Access::SliceRest { head, tail } => buf.push_str(&format!("[{head}..-{tail}]")),
+            Access::ActiveResult { name, .. } => buf.push_str(&format!(".{name}()")),
+        }
+    }
+    buf
+}
+
+pub(crate) fn describe_test(test: &Test) -> String {
+    match test {
+        Test::Ctor { name, arity } => format!("{name}/{arity}"),
+        Test::Int(value) => value.to_string(),
+        Test::Bool(value) => value.to_string(),
+        Test::Literal(summary) => format!("lit({summary})"),
+        Test::Range {
+            start,
+            end,
+            inclusive,
+        } => format!(
+            "{}{}{}",
+            start.as_ref().map(|b| b.operand.as_str()).unwrap_or(""),
+            if *inclusive { "..=" } else { ".." },
+            end.as_ref().map(|b| b.operand.as_str()).unwrap_or("")
+        ),
+        Test::SliceLen { len, rest } => {
+            if *rest {
+                format!("len>={len}")
+            } else {
+                format!("len=={len}")
+            }
+        }
+        Test::Regex(pattern) => format!("regex({pattern})"),
+        Test::Active { name, partial } => {
+            if *partial {
+                format!("active_partial({name})")
+            } else {
+                format!("active_total({name})")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::{MirSlicePattern, MirSliceRest};
+
+    fn pat(kind: MirPatternKind) -> MirPattern {
+        MirPattern { kind }
+    }
+
+    fn wildcard() -> MirPattern {
+        pat(MirPatternKind::Wildcard)
+    }
+
+    fn var(name: &str) -> MirPattern {
+        pat(MirPatternKind::Var {
+            name: name.to_string(),
+        })
+    }
+
+    fn lit(summary: &str) -> MirPattern {
+        pat(MirPatternKind::Literal {
+            summary: summary.to_string(),
+        })
+    }
+
+    fn ctor(name: &str, args: Vec<MirPattern>) -> MirPattern {
+        pat(MirPatternKind::Constructor {
+            name: name.to_string(),
+            args,
+        })
+    }
+
+    fn arm(pattern: MirPattern, guarded: bool) -> MirMatchArm {
+        MirMatchArm {
+            pattern,
+            guard: guarded.then_some(0),
+            alias: None,
+            body: 0,
+        }
+    }
+
+    fn plan(exhaustive: bool, constructors: &[&str]) -> MatchLoweringPlan {
+        MatchLoweringPlan {
+            owner: None,
+            target_type: None,
+            arm_count: None,
+            arms: Vec::new(),
+            exhaustive,
+            constructors: constructors.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    fn tests_on(tree: &DecisionTree, target: &Occurrence) -> usize {
+        tree.reachable()
+            .into_iter()
+            .filter(|id| match &tree.nodes[*id] {
+                DecisionNode::Switch { occurrence, .. } | DecisionNode::Test { occurrence, .. } => {
+                    occurrence == target
+                }
+                _ => false,
+            })
+            .count()
+    }
+
+    #[test]
+    fn constructor_shared_by_arms_is_tested_once() {
+        let arms = vec![
+            arm(ctor("Some", vec![lit("0")]), false),
+            arm(ctor("Some", vec![var("x")]), false),
+            arm(ctor("None", vec![]), false),
+        ];
+        let tree = compile_match(&arms, Some(&plan(true, &[])));
+        assert_eq!(
+            tests_on(&tree, &Vec::new()),
+            1,
+            "Some の判定は 1 回だけのはず"
+        );
+        assert_eq!(tree.test_count(), 2);
+        assert_eq!(tree.reachable_arms(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn small_range_expands_into_int_switch() {
+        let range = pat(MirPatternKind::Range {
+            start: Some(Box::new(lit("2"))),
+            end: Some(Box::new(lit("5"))),
+            inclusive: false,
+        });
+        let arms = vec![
+            arm(lit("0"), false),
+            arm(lit("1"), false),
+            arm(range, false),
+            arm(wildcard(), false),
+        ];
+        let tree = compile_match(&arms, None);
+        let DecisionNode::Switch {
+            kind,
+            cases,
+            default,
+            ..
+        } = &tree.nodes[tree.root]
+        else {
+            panic!("整数 switch になるはず: {:?}", tree.nodes[tree.root]);
+        };
+        assert_eq!(*kind, SwitchKind::Int);
+        let values = cases
+            .iter()
+            .map(|(test, _)| switch_value(test))
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![0, 1, 2, 3, 4]);
+        assert!(default.is_some(), "範囲外はワイルドカードへ進むはず");
+    }
+
+    #[test]
+    fn exhaustive_sum_switch_has_no_default() {
+        let arms = vec![
+            arm(ctor("Circle", vec![var("r")]), false),
+            arm(ctor("Rect", vec![wildcard(), wildcard()]), false),
+            arm(ctor("Empty", vec![]), false),
+        ];
+        let tree = compile_match(&arms, Some(&plan(true, &["Circle", "Rect", "Empty"])));
+        let DecisionNode::Switch { cases, default, .. } = &tree.nodes[tree.root] else {
+            panic!("コンストラクタ switch になるはず");
+        };
+        assert_eq!(cases.len(), 3);
+        assert!(
+            default.is_none(),
+            "網羅的な match では既定分岐を持たないはず"
+        );
+        assert!(!tree
+            .reachable()
+            .into_iter()
+            .any(|id| tree.nodes[id] == DecisionNode::Fail));
+    }
+
+    #[test]
+    fn guard_failure_continues_with_following_arms() {
+        let arms = vec![
+            arm(ctor("Rect", vec![var("w"), var("h")]), true),
+            arm(ctor("Rect", vec![var("w"), var("h")]), false),
+            arm(wildcard(), false),
+        ];
+        let tree = compile_match(&arms, None);
+        let guard = tree
+            .reachable()
+            .into_iter()
+            .find_map(|id| match &tree.nodes[id] {
+                DecisionNode::Guard { arm, otherwise, .. } => Some((*arm, *otherwise)),
+                _ => None,
+            })
+            .expect("ガードノードがあるはず");
+        assert_eq!(guard.0, 0);
+        assert!(
+            matches!(&tree.nodes[guard.1], DecisionNode::Leaf { arm: 1, bindings } if bindings.len() == 2),
+            "ガード失敗時は Rect を再判定せず次の腕へ進むはず"
+        );
+        assert_eq!(tests_on(&tree, &Vec::new()), 1);
+    }
+
+    #[test]
+    fn arms_after_irrefutable_pattern_are_unreachable() {
+        let arms = vec![arm(var("x"), false), arm(lit("1"), false)];
+        let tree = compile_match(&arms, None);
+        assert_eq!(tree.reachable_arms(), vec![0]);
+        assert_eq!(tree.test_count(), 0);
+    }
+
+    #[test]
+    fn slice_patterns_switch_on_length() {
+        let empty = pat(MirPatternKind::Slice(MirSlicePattern {
+            head: vec![],
+            rest: None,
+            tail: vec![],
+        }));
+        let cons = pat(MirPatternKind::Slice(MirSlicePattern {
+            head: vec![var("head")],
+            rest: Some(MirSliceRest {
+                binding: Some("tail".to_string()),
+            }),
+            tail: vec![],
+        }));
+        let tree = compile_match(&[arm(empty, false), arm(cons, false)], None);
+        let leaf = tree
+            .reachable()
+            .into_iter()
+            .find_map(|id| match &tree.nodes[id] {
+                DecisionNode::Leaf { arm: 1, bindings } => Some(bindings.clone()),
+                _ => None,
+            })
+            .expect("2 番目の腕に到達できるはず");
+        let mut paths = leaf
+            .iter()
+            .map(|(name, occurrence)| format!("{name}={}", describe_occurrence(occurrence)))
+            .collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, vec!["head=$[0]", "tail=$[1..-0]"]);
+        assert!(tree.reachable().into_iter().any(|id| matches!(
+            &tree.nodes[id],
+            DecisionNode::Switch {
+                kind: SwitchKind::SliceLen,
+                ..
+            } | DecisionNode::Test {
+                test: Test::SliceLen { .. },
+                ..
+            }
+        )));
+    }
+}
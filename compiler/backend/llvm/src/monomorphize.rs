@@ -0,0 +1,912 @@
+//! MIR とコード生成の間で行うジェネリクスの単相化。
+//!
+//! フロントエンド MIR の型トークンに残る型変数（`'t0` など）を、呼び出し位置の
+//! 具体型と照合して解決し、型引数ごとに関数を複製する。起点は単相な関数
+//! （`main` やエクスポート関数を含む）で、そこから到達する具体化だけを生成する。
+//! 型変数を受け取っていたトレイトメソッド呼び出しは、具体化後の受け手型から
+//! impl を引き当てて直接呼び出しへ書き換える。
+//! 呼び出し位置の型も型変数のままで具体化できない関数は、複製せずに元の本体を残す。
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+use crate::codegen::{MirExprId, MirExprKind, MirFunction};
+use crate::integration::parse_reml_type;
+use crate::verify::Diagnostic;
+
+/// 具体化の連鎖深さの既定上限。多相再帰による無限展開を打ち切る。
+pub const DEFAULT_MAX_INSTANTIATION_DEPTH: usize = 64;
+
+/// impl レジストリの 1 エントリ。
+#[derive(Clone, Debug)]
+pub struct MonoImpl {
+    pub impl_id: String,
+    pub trait_name: Option<String>,
+    pub target: String,
+    pub methods: Vec<String>,
+}
+
+/// 単相化の結果。
+#[derive(Clone, Debug)]
+pub struct MonomorphizeResult {
+    pub functions: Vec<MirFunction>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl MonomorphizeResult {
+    /// 生成を続けられない診断（具体化深さの超過）を含むか。
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diag| diag.code == "monomorphize.depth_overflow")
+    }
+}
+
+/// 単相化パス。
+#[derive(Clone, Debug)]
+pub struct Monomorphizer {
+    impls: Vec<MonoImpl>,
+    /// (関数名, 式 ID) → (トレイト名, メソッド名)。
+    trait_calls: HashMap<(String, MirExprId), (String, String)>,
+    max_depth: usize,
+}
+
+impl Default for Monomorphizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Monomorphizer {
+    pub fn new() -> Self {
+        Self {
+            impls: Vec::new(),
+            trait_calls: HashMap::new(),
+            max_depth: DEFAULT_MAX_INSTANTIATION_DEPTH,
+        }
+    }
+
+    pub fn with_impl(mut self, entry: MonoImpl) -> Self {
+        self.impls.push(entry);
+        self
+    }
+
+    /// `function` 内の式 `expr_id` がトレイトメソッド呼び出しであることを登録する。
+    pub fn with_trait_call(
+        mut self,
+        function: impl Into<String>,
+        expr_id: MirExprId,
+        trait_name: impl Into<String>,
+        method: impl Into<String>,
+    ) -> Self {
+        self.trait_calls.insert(
+            (normalize_function_name(&function.into()), expr_id),
+            (trait_name.into(), method.into()),
+        );
+        self
+    }
+
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    pub fn run(&self, functions: Vec<MirFunction>) -> MonomorphizeResult {
+        let mut generics = HashMap::new();
+        let mut queue = VecDeque::new();
+        for function in functions {
+            if is_generic(&function) {
+                generics.insert(normalize_function_name(&function.name), function);
+            } else {
+                let origin = normalize_function_name(&function.name);
+                queue.push_back(WorkItem {
+                    function,
+                    origin,
+                    chain: Vec::new(),
+                });
+            }
+        }
+
+        let mut instances: HashMap<InstanceKey, String> = HashMap::new();
+        let mut overflowed = HashSet::new();
+        let mut kept = HashSet::new();
+        let mut output = Vec::new();
+        let mut diagnostics = Vec::new();
+        while let Some(mut item) = queue.pop_front() {
+            let mut unresolved = BTreeSet::new();
+            let requests =
+                self.rewrite_calls(&mut item, &generics, &mut unresolved, &mut diagnostics);
+            // 型変数しか得られない参照は具体化できないため、本体を型変数のまま（一様表現で）残す。
+            for generic in unresolved {
+                if kept.insert(generic.clone()) {
+                    queue.push_back(WorkItem {
+                        function: generics[&generic].clone(),
+                        origin: generic,
+                        chain: item.chain.clone(),
+                    });
+                }
+            }
+            for request in requests {
+                let key = InstanceKey {
+                    origin: request.generic.clone(),
+                    bindings: request.bindings.clone(),
+                };
+                let name = match instances.get(&key) {
+                    Some(name) => name.clone(),
+                    None => {
+                        let generic = &generics[&request.generic];
+                        let name = instance_name(&generic.name, &request.bindings);
+                        if item.chain.len() + 1 > self.max_depth {
+                            if overflowed.insert(request.generic.clone()) {
+                                diagnostics.push(depth_overflow_diagnostic(
+                                    &request.generic,
+                                    &name,
+                                    &item,
+                                    self.max_depth,
+                                ));
+                            }
+                            // 具体化しない関数を呼び出し先に残すと未定義シンボルになるため、
+                            // 呼び出しごと panic に置き換える。
+                            for expr_id in request.sites {
+                                abandon_call_site(&mut item.function, expr_id);
+                            }
+                            continue;
+                        }
+                        instances.insert(key, name.clone());
+                        let mut chain = item.chain.clone();
+                        chain.push(name.clone());
+                        queue.push_back(WorkItem {
+                            function: instantiate(generic, &name, &request.bindings),
+                            origin: request.generic.clone(),
+                            chain,
+                        });
+                        name
+                    }
+                };
+                for expr_id in request.sites {
+                    rename_identifier(&mut item.function, expr_id, &name, &request.bindings);
+                }
+            }
+            output.push(item.function);
+        }
+        MonomorphizeResult {
+            functions: output,
+            diagnostics,
+        }
+    }
+
+    /// 関数内のジェネリック関数参照を集め、トレイトメソッド呼び出しを直接呼び出しへ書き換える。
+    fn rewrite_calls(
+        &self,
+        item: &mut WorkItem,
+        generics: &HashMap<String, MirFunction>,
+        unresolved: &mut BTreeSet<String>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Vec<InstanceRequest> {
+        let types: HashMap<MirExprId, String> = item
+            .function
+            .exprs
+            .iter()
+            .map(|expr| (expr.id, expr.ty.clone()))
+            .collect();
+        let mut call_sites = HashMap::new();
+        for expr in &item.function.exprs {
+            if let MirExprKind::Call { callee, args } = &expr.kind {
+                let arg_types = args
+                    .iter()
+                    .map(|arg| types.get(arg).cloned().unwrap_or_default())
+                    .collect::<Vec<_>>();
+                call_sites.insert(*callee, (expr.id, arg_types, expr.ty.clone()));
+            }
+        }
+
+        let mut direct_calls = Vec::new();
+        let mut requests: BTreeMap<(String, Bindings), Vec<MirExprId>> = BTreeMap::new();
+        for expr in &item.function.exprs {
+            let MirExprKind::Identifier { summary } = &expr.kind else {
+                continue;
+            };
+            let name = normalize_function_name(&identifier_name(summary));
+            if let Some((call_id, arg_types, _)) = call_sites.get(&expr.id) {
+                if let Some((trait_name, method)) =
+                    self.trait_calls.get(&(item.origin.clone(), *call_id))
+                {
+                    let receiver = arg_types.first().cloned().unwrap_or_default();
+                    match self.resolve_impl(trait_name, method, &receiver) {
+                        Some(symbol) => direct_calls.push((expr.id, symbol)),
+                        None if !has_type_vars(&receiver) => diagnostics.push(
+                            Diagnostic::new(
+                                "Backend",
+                                "monomorphize.impl_unresolved",
+                                format!(
+                                    "function={} trait={trait_name} method={method} receiver={receiver}",
+                                    item.function.name
+                                ),
+                            )
+                            .with_extension("expr_id", call_id.to_string()),
+                        ),
+                        None => {}
+                    }
+                    continue;
+                }
+            }
+            let Some(generic) = generics.get(&name) else {
+                continue;
+            };
+            let (param_types, ret_type) = match call_sites.get(&expr.id) {
+                Some((_, arg_types, ret)) => (arg_types.clone(), ret.clone()),
+                None => match split_function_type(&expr.ty) {
+                    Some(signature) => signature,
+                    None => continue,
+                },
+            };
+            let mut bindings = BTreeMap::new();
+            for (pattern, concrete) in generic.param_type_tokens.iter().zip(&param_types) {
+                unify_tokens(pattern, concrete, &mut bindings);
+            }
+            if let Some(pattern) = &generic.return_type_token {
+                unify_tokens(pattern, &ret_type, &mut bindings);
+            }
+            if bindings.is_empty() {
+                unresolved.insert(name);
+                continue;
+            }
+            requests
+                .entry((name, bindings.into_iter().collect()))
+                .or_default()
+                .push(expr.id);
+        }
+
+        for (expr_id, symbol) in direct_calls {
+            if let Some(expr) = item.function.exprs.iter_mut().find(|e| e.id == expr_id) {
+                expr.kind = MirExprKind::Identifier {
+                    summary: identifier_summary(&symbol),
+                };
+            }
+        }
+        requests
+            .into_iter()
+            .map(|((generic, bindings), sites)| InstanceRequest {
+                generic,
+                bindings,
+                sites,
+            })
+            .collect()
+    }
+
+    /// トレイト名と受け手の具体型から impl メソッドのシンボルを引き当てる。
+    fn resolve_impl(&self, trait_name: &str, method: &str, receiver: &str) -> Option<String> {
+        if has_type_vars(receiver) {
+            return None;
+        }
+        let receiver = normalize_impl_target(receiver);
+        let mut candidates = self.impls.iter().filter(|entry| {
+            entry.trait_name.as_deref() == Some(trait_name)
+                && normalize_impl_target(&entry.target) == receiver
+                && entry.methods.iter().any(|name| name == method)
+        });
+        let entry = candidates.next()?;
+        if candidates.next().is_some() {
+            return None;
+        }
+        Some(format!("{}__{method}", entry.target))
+    }
+}
+
+/// 型変数名と具体型の組（型変数名順）。
+type Bindings = Vec<(String, String)>;
+
+struct WorkItem {
+    function: MirFunction,
+    /// 具体化元のジェネリック関数名（単相関数なら自身の名前）。
+    origin: String,
+    /// 起点から辿った具体化の連鎖。
+    chain: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct InstanceKey {
+    origin: String,
+    bindings: Bindings,
+}
+
+struct InstanceRequest {
+    generic: String,
+    bindings: Bindings,
+    sites: Vec<MirExprId>,
+}
+
+fn depth_overflow_diagnostic(
+    generic: &str,
+    instance: &str,
+    item: &WorkItem,
+    max_depth: usize,
+) -> Diagnostic {
+    let root = item
+        .chain
+        .first()
+        .cloned()
+        .unwrap_or_else(|| item.function.name.clone());
+    Diagnostic::new(
+        "Backend",
+        "monomorphize.depth_overflow",
+        format!(
+            "function={generic} instance={instance} depth={} limit={max_depth} root={root}",
+            item.chain.len() + 1
+        ),
+    )
+    .with_extension("caller", item.function.name.clone())
+}
+
+fn normalize_function_name(name: &str) -> String {
+    name.trim().trim_start_matches('@').to_string()
+}
+
+fn normalize_impl_target(target: &str) -> String {
+    match target.trim() {
+        "Int" | "Int64" => "i64".to_string(),
+        "Unit" => "()".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn identifier_name(summary: &str) -> String {
+    let trimmed = summary.trim();
+    if trimmed.starts_with('{') {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(trimmed) {
+            if let Some(name) = value.get("name").and_then(|v| v.as_str()) {
+                return name.to_string();
+            }
+        }
+    }
+    trimmed.to_string()
+}
+
+fn identifier_summary(name: &str) -> String {
+    serde_json::json!({ "name": name }).to_string()
+}
+
+fn is_generic(function: &MirFunction) -> bool {
+    let name = normalize_function_name(&function.name);
+    if name == "main"
+        || function
+            .attributes
+            .iter()
+            .any(|attr| is_export_attribute(attr))
+    {
+        return false;
+    }
+    function
+        .param_type_tokens
+        .iter()
+        .chain(function.return_type_token.iter())
+        .any(|token| has_type_vars(token))
+}
+
+pub(crate) fn is_export_attribute(attr: &str) -> bool {
+    let attr = attr.trim().trim_start_matches('@');
+    attr.starts_with("export") || attr.starts_with("no_mangle")
+}
+
+/// 型トークン内の型変数（`'` で始まる識別子）を含むか。
+pub(crate) fn has_type_vars(token: &str) -> bool {
+    tokenize(token).iter().any(|tok| tok.starts_with('\''))
+}
+
+fn tokenize(token: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = token.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch.is_whitespace() {
+            continue;
+        }
+        if ch == '-' && chars.peek() == Some(&'>') {
+            chars.next();
+            tokens.push("->".to_string());
+            continue;
+        }
+        if ch == '\'' || ch.is_alphanumeric() || ch == '_' || ch == '.' {
+            let mut ident = ch.to_string();
+            while let Some(&next) = chars.peek() {
+                if next.is_alphanumeric() || next == '_' || next == '.' {
+                    ident.push(next);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(ident);
+            continue;
+        }
+        tokens.push(ch.to_string());
+    }
+    tokens
+}
+
+/// ジェネリック側のトークン列と具体型を突き合わせ、型変数の束縛を集める。
+/// 具体側に型変数が残る位置は束縛しない。
+fn unify_tokens(pattern: &str, concrete: &str, bindings: &mut BTreeMap<String, String>) {
+    let pattern = tokenize(pattern);
+    let concrete = tokenize(concrete);
+    let mut index = 0;
+    for tok in &pattern {
+        if index >= concrete.len() {
+            return;
+        }
+        if tok.starts_with('\'') {
+            let start = index;
+            let mut depth = 0usize;
+            while index < concrete.len() {
+                match concrete[index].as_str() {
+                    "(" | "[" | "<" | "{" => depth += 1,
+                    ")" | "]" | ">" | "}" | "," | ";" if depth == 0 => break,
+                    ")" | "]" | ">" | "}" => depth -= 1,
+                    _ => {}
+                }
+                index += 1;
+            }
+            let bound = render_tokens(&concrete[start..index]);
+            if !bound.is_empty() && !has_type_vars(&bound) {
+                bindings.entry(tok.clone()).or_insert(bound);
+            }
+        } else if *tok == concrete[index] {
+            index += 1;
+        } else {
+            return;
+        }
+    }
+}
+
+fn render_tokens(tokens: &[String]) -> String {
+    let mut buf = String::new();
+    for tok in tokens {
+        match tok.as_str() {
+            "," => buf.push_str(", "),
+            "->" => buf.push_str(" -> "),
+            ";" => buf.push_str("; "),
+            _ => buf.push_str(tok),
+        }
+    }
+    buf
+}
+
+/// 型トークン中の型変数を束縛に従って置き換える。
+fn substitute(token: &str, bindings: &[(String, String)]) -> String {
+    if !token.contains('\'') {
+        return token.to_string();
+    }
+    let mut buf = String::new();
+    let mut chars = token.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\'' {
+            buf.push(ch);
+            continue;
+        }
+        let mut var = String::from("'");
+        while let Some(&next) = chars.peek() {
+            if next.is_alphanumeric() || next == '_' {
+                var.push(next);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        match bindings.iter().find(|(name, _)| *name == var) {
+            Some((_, ty)) => buf.push_str(ty),
+            None => buf.push_str(&var),
+        }
+    }
+    buf
+}
+
+/// `(A, B) -> R` 形式を引数型と戻り値型へ分解する。
+fn split_function_type(token: &str) -> Option<(Vec<String>, String)> {
+    let tokens = tokenize(token);
+    if tokens.first().map(String::as_str) != Some("(") {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut params = Vec::new();
+    let mut current = Vec::new();
+    let mut close = None;
+    for (index, tok) in tokens.iter().enumerate() {
+        match tok.as_str() {
+            "(" | "[" | "<" | "{" => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            }
+            ")" | "]" | ">" | "}" => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(index);
+                    break;
+                }
+            }
+            "," if depth == 1 => {
+                params.push(render_tokens(&current));
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(tok.clone());
+    }
+    let close = close?;
+    if !current.is_empty() {
+        params.push(render_tokens(&current));
+    }
+    if tokens.get(close + 1).map(String::as_str) != Some("->") {
+        return None;
+    }
+    Some((params, render_tokens(&tokens[close + 2..])))
+}
+
+/// 型トークンを識別子に使える文字へ写す。英数字以外（`_` を含む）は
+/// `_<16 進のコードポイント>_` とし、元のトークンを復元できるようにする。
+fn mangle_type(token: &str) -> String {
+    let mut buf = String::new();
+    for ch in token.chars() {
+        if ch.is_ascii_alphanumeric() {
+            buf.push(ch);
+        } else {
+            let _ = write!(buf, "_{:x}_", ch as u32);
+        }
+    }
+    buf
+}
+
+/// 具体化した関数名。型変数の名前順に、長さを前置した具体型を連結する
+/// （`id` を `[i64]` で具体化すると `id__11_5b_i64_5d_`）。
+fn instance_name(generic: &str, bindings: &[(String, String)]) -> String {
+    let mut name = generic.to_string();
+    for (_, ty) in bindings {
+        let mangled = mangle_type(ty);
+        let _ = write!(name, "__{}{mangled}", mangled.len());
+    }
+    name
+}
+
+fn instantiate(generic: &MirFunction, name: &str, bindings: &[(String, String)]) -> MirFunction {
+    let mut function = generic.clone();
+    function.name = name.to_string();
+    function.param_type_tokens = generic
+        .param_type_tokens
+        .iter()
+        .map(|token| substitute(token, bindings))
+        .collect();
+    function.return_type_token = generic
+        .return_type_token
+        .as_ref()
+        .map(|token| substitute(token, bindings));
+    if function.params.len() == function.param_type_tokens.len() {
+        function.params = function
+            .param_type_tokens
+            .iter()
+            .map(|token| parse_reml_type(token))
+            .collect();
+    }
+    if let Some(token) = &function.return_type_token {
+        function.ret = Some(parse_reml_type(token));
+    }
+    for expr in &mut function.exprs {
+        expr.ty = substitute(&expr.ty, bindings);
+        if let MirExprKind::Lambda {
+            params, captures, ..
+        } = &mut expr.kind
+        {
+            for param in params {
+                param.ty = substitute(&param.ty, bindings);
+            }
+            for capture in captures {
+                capture.ty = substitute(&capture.ty, bindings);
+            }
+        }
+    }
+    function
+}
+
+/// 具体化を打ち切った参照を panic に置き換える。呼び出し先なら呼び出し式ごと置き換える。
+fn abandon_call_site(function: &mut MirFunction, expr_id: MirExprId) {
+    for expr in &mut function.exprs {
+        let is_site = expr.id == expr_id
+            || matches!(&expr.kind, MirExprKind::Call { callee, .. } if *callee == expr_id);
+        if is_site {
+            expr.kind = MirExprKind::Panic { argument: None };
+        }
+    }
+}
+
+fn rename_identifier(
+    function: &mut MirFunction,
+    expr_id: MirExprId,
+    name: &str,
+    bindings: &[(String, String)],
+) {
+    if let Some(expr) = function.exprs.iter_mut().find(|expr| expr.id == expr_id) {
+        expr.kind = MirExprKind::Identifier {
+            summary: identifier_summary(name),
+        };
+        expr.ty = substitute(&expr.ty, bindings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::MirExpr;
+    use crate::type_mapping::RemlType;
+
+    fn expr(id: MirExprId, ty: &str, kind: MirExprKind) -> MirExpr {
+        MirExpr {
+            id,
+            ty: ty.into(),
+            kind,
+            span: None,
+        }
+    }
+
+    fn ident(id: MirExprId, ty: &str, name: &str) -> MirExpr {
+        expr(
+            id,
+            ty,
+            MirExprKind::Identifier {
+                summary: identifier_summary(name),
+            },
+        )
+    }
+
+    fn call(id: MirExprId, ty: &str, callee: MirExprId, args: Vec<MirExprId>) -> MirExpr {
+        expr(id, ty, MirExprKind::Call { callee, args })
+    }
+
+    fn literal(id: MirExprId, ty: &str, summary: &str) -> MirExpr {
+        expr(
+            id,
+            ty,
+            MirExprKind::Literal {
+                summary: summary.into(),
+            },
+        )
+    }
+
+    fn function(name: &str, params: &[(&str, &str)], ret: &str, body: MirExprId) -> MirFunction {
+        let mut function = MirFunction::new(name, "ccc");
+        for (param, ty) in params {
+            function = function.with_named_param(*param, parse_reml_type(ty));
+            function.param_type_tokens.push(ty.to_string());
+        }
+        function.return_type_token = Some(ret.into());
+        function
+            .with_return(parse_reml_type(ret))
+            .with_exprs(Some(body), Vec::new())
+    }
+
+    fn callee_name(function: &MirFunction, id: MirExprId) -> String {
+        function
+            .exprs
+            .iter()
+            .find(|expr| expr.id == id)
+            .and_then(|expr| match &expr.kind {
+                MirExprKind::Identifier { summary } => Some(identifier_name(summary)),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn instantiates_generic_functions_per_type_arguments() {
+        let mut id = function("id", &[("x", "'t0")], "'t0", 0);
+        id.exprs = vec![ident(0, "'t0", "x")];
+        let mut main = function("main", &[], "i64", 9);
+        main.exprs = vec![
+            ident(0, "('t7) -> 't7", "id"),
+            literal(1, "Str", "\"hi\""),
+            call(2, "Str", 0, vec![1]),
+            ident(3, "('t12) -> 't12", "id"),
+            literal(4, "i64", "5"),
+            call(5, "i64", 3, vec![4]),
+            ident(6, "('t13) -> 't13", "id"),
+            literal(7, "i64", "6"),
+            call(8, "i64", 6, vec![7]),
+            ident(9, "i64", "unused"),
+        ];
+
+        let result = Monomorphizer::new().run(vec![id, main]);
+        assert!(result.diagnostics.is_empty());
+        let names: Vec<_> = result.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["main", "id__3Str", "id__3i64"]);
+
+        let main = &result.functions[0];
+        assert_eq!(callee_name(main, 0), "id__3Str");
+        assert_eq!(callee_name(main, 3), "id__3i64");
+        assert_eq!(
+            callee_name(main, 6),
+            "id__3i64",
+            "同じ型引数の具体化は共有すること"
+        );
+
+        let id_i64 = &result.functions[2];
+        assert_eq!(id_i64.params, vec![RemlType::I64]);
+        assert_eq!(id_i64.ret, Some(RemlType::I64));
+        assert_eq!(id_i64.exprs[0].ty, "i64");
+    }
+
+    #[test]
+    fn specializes_trait_method_calls_to_impl_symbols() {
+        let mut describe = function("describe", &[("x", "'t1")], "Str", 2);
+        describe.exprs = vec![
+            ident(0, "Unknown", "Show__show"),
+            ident(1, "'t1", "x"),
+            call(2, "Str", 0, vec![1]),
+        ];
+        let mut main = function("main", &[], "Str", 2);
+        main.exprs = vec![
+            ident(0, "('t5) -> Str", "describe"),
+            literal(1, "Bool", "true"),
+            call(2, "Str", 0, vec![1]),
+        ];
+        let monomorphizer = Monomorphizer::new()
+            .with_impl(MonoImpl {
+                impl_id: "Show::Int".into(),
+                trait_name: Some("Show".into()),
+                target: "Int".into(),
+                methods: vec!["show".into()],
+            })
+            .with_impl(MonoImpl {
+                impl_id: "Show::Bool".into(),
+                trait_name: Some("Show".into()),
+                target: "Bool".into(),
+                methods: vec!["show".into()],
+            })
+            .with_trait_call("describe", 2, "Show", "show");
+
+        let result = monomorphizer.run(vec![describe, main]);
+        assert!(result.diagnostics.is_empty());
+        let instance = result
+            .functions
+            .iter()
+            .find(|f| f.name == "describe__4Bool")
+            .expect("describe の Bool 具体化");
+        assert_eq!(callee_name(instance, 0), "Bool__show");
+    }
+
+    #[test]
+    fn keeps_generic_body_when_call_site_types_are_unresolved() {
+        // fn getx(p: Record<i64, i64>) -> 't0 = p.x（戻り値の型が推論されていない）
+        let mut getx = function("getx", &[("p", "Record<i64, i64>")], "'t0", 1);
+        getx.exprs = vec![
+            ident(0, "Record<i64, i64>", "p"),
+            expr(
+                1,
+                "'t0",
+                MirExprKind::FieldAccess {
+                    target: 0,
+                    field: "x".into(),
+                },
+            ),
+        ];
+        let mut main = function("main", &[], "'t3", 2);
+        main.exprs = vec![
+            ident(0, "(Record<i64, i64>) -> 't3", "getx"),
+            ident(1, "Record<i64, i64>", "p"),
+            call(2, "'t3", 0, vec![1]),
+        ];
+
+        let result = Monomorphizer::new().run(vec![getx, main]);
+        assert!(result.diagnostics.is_empty());
+        let names: Vec<_> = result.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["main", "getx"], "呼び出し先を落とさないこと");
+        assert_eq!(callee_name(&result.functions[0], 0), "getx");
+    }
+
+    #[test]
+    fn reports_instantiation_depth_overflow() {
+        // fn grow<T>(x: T) -> i64 = grow([x]) の多相再帰。
+        let mut grow = function("grow", &[("x", "'t0")], "i64", 3);
+        grow.exprs = vec![
+            ident(0, "(['t0]) -> i64", "grow"),
+            ident(1, "'t0", "x"),
+            expr(
+                2,
+                "['t0]",
+                MirExprKind::Literal {
+                    summary: "[]".into(),
+                },
+            ),
+            call(3, "i64", 0, vec![2]),
+        ];
+        let mut main = function("main", &[], "i64", 2);
+        main.exprs = vec![
+            ident(0, "(i64) -> i64", "grow"),
+            literal(1, "i64", "1"),
+            call(2, "i64", 0, vec![1]),
+        ];
+
+        let result = Monomorphizer::new().with_max_depth(4).run(vec![grow, main]);
+        assert!(result.has_errors());
+        assert_eq!(result.diagnostics.len(), 1);
+        assert!(result.diagnostics[0].message.contains("function=grow"));
+        assert!(result.diagnostics[0].message.contains("limit=4"));
+        assert_eq!(
+            result.functions.len(),
+            5,
+            "main と上限までの具体化を残すこと"
+        );
+        let generated: HashSet<_> = result.functions.iter().map(|f| f.name.as_str()).collect();
+        for function in &result.functions {
+            for expr in &function.exprs {
+                if let MirExprKind::Identifier { summary } = &expr.kind {
+                    let name = identifier_name(summary);
+                    assert!(
+                        name == "x" || generated.contains(name.as_str()),
+                        "{} が生成されない関数 {name} を参照している",
+                        function.name
+                    );
+                }
+            }
+        }
+        let deepest = result.functions.last().unwrap();
+        assert!(matches!(
+            deepest
+                .exprs
+                .iter()
+                .find(|expr| expr.id == 3)
+                .map(|expr| &expr.kind),
+            Some(MirExprKind::Panic { argument: None })
+        ));
+    }
+
+    #[test]
+    fn instance_names_do_not_collide() {
+        let types = [
+            "[i64]",
+            "[[i64]]",
+            "List<i64>",
+            "List_i64",
+            "(i64, Str)",
+            "(i64) -> Str",
+            "Pair<i64, Str>",
+        ];
+        let mut names = HashSet::new();
+        for ty in types {
+            let name = instance_name("f", &[("'a".into(), ty.into())]);
+            assert!(
+                name.chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_'),
+                "{name}"
+            );
+            assert!(
+                names.insert(name.clone()),
+                "{ty} の具体化名 {name} が衝突した"
+            );
+        }
+        assert_eq!(
+            instance_name("f", &[("'a".into(), "[i64]".into())]),
+            "f__11_5b_i64_5d_"
+        );
+        // 型引数の区切りが型の中身と混ざらないこと。
+        assert_ne!(
+            instance_name(
+                "f",
+                &[("'a".into(), "i64".into()), ("'b".into(), "Str".into())]
+            ),
+            instance_name("f", &[("'a".into(), "i64__3Str".into())])
+        );
+    }
+
+    #[test]
+    fn unify_tokens_binds_nested_positions() {
+        let mut bindings = BTreeMap::new();
+        unify_tokens("List<'a>", "List<(i64) -> Str>", &mut bindings);
+        unify_tokens("('b, 'c)", "(Bool, 't9)", &mut bindings);
+        assert_eq!(bindings.get("'a").map(String::as_str), Some("(i64) -> Str"));
+        assert_eq!(bindings.get("'b").map(String::as_str), Some("Bool"));
+        assert!(!bindings.contains_key("'c"), "型変数同士は束縛しないこと");
+        assert_eq!(
+            split_function_type("((i64) -> i64, 't1) -> 't1"),
+            Some((vec!["(i64) -> i64".into(), "'t1".into()], "'t1".into()))
+        );
+    }
+}
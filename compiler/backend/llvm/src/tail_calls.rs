@@ -0,0 +1,225 @@
+//! 末尾呼び出しの保証。
+//!
+//! フロントエンドの末尾位置解析（MIR 関数の `tail_calls`）を受け取り、末尾呼び出しの下ろし方を決める。
+//! 自己末尾呼び出しは `codegen` が引数スロットを書き戻してループ先頭へ分岐する形に書き換え、
+//! 他の関数への末尾呼び出しはプロトタイプが一致すれば `musttail`、呼び出し側と呼び出し先が
+//! 共に `fastcc` なら `tail call fastcc`、それ以外は最適化任せの `tail` を付けた直接呼び出しにする。
+//! `@tailrec` を付けた関数で保証を得られない末尾呼び出しは `codegen.tail_call.unguaranteed` として報告する。
+
+use std::fmt::Write;
+
+use crate::codegen::{MirExprId, MirSpan};
+use crate::verify::Diagnostic;
+
+/// 末尾呼び出しの保証を要求する関数属性（`@tailrec`）。
+pub const TAILREC_ATTRIBUTE: &str = "tailrec";
+
+/// 末尾呼び出しを保証できなかった箇所の診断コード。
+pub const TAIL_CALL_UNGUARANTEED: &str = "codegen.tail_call.unguaranteed";
+
+/// 末尾位置にある呼び出しの種類。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TailCallKind {
+    /// 引数の数が一致する自分自身への呼び出し。
+    SelfRecursive,
+    /// モジュールの関数名を直接呼び出す。
+    Direct,
+    /// 局所変数やクロージャ経由の呼び出し。
+    Indirect,
+}
+
+impl TailCallKind {
+    /// MIR JSON の `kind`（`self_recursive` / `direct` / `indirect`）を解釈する。
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "self_recursive" => Some(Self::SelfRecursive),
+            "direct" => Some(Self::Direct),
+            "indirect" => Some(Self::Indirect),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SelfRecursive => "self_recursive",
+            Self::Direct => "direct",
+            Self::Indirect => "indirect",
+        }
+    }
+}
+
+/// フロントエンドが末尾位置と判定した呼び出し。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MirTailCall {
+    /// `Call` 式の ID。
+    pub expr: MirExprId,
+    pub callee: Option<String>,
+    pub kind: TailCallKind,
+}
+
+/// 末尾呼び出しの可否を判定するための関数プロトタイプ（LLVM 型で表す）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionPrototype {
+    pub symbol: String,
+    pub params: Vec<String>,
+    pub ret: String,
+    pub calling_conv: String,
+}
+
+impl FunctionPrototype {
+    /// 呼び出し規約・引数型・戻り値型が一致するか（`musttail` の条件）。
+    pub fn matches(&self, other: &FunctionPrototype) -> bool {
+        self.calling_conv == other.calling_conv
+            && self.params == other.params
+            && self.ret == other.ret
+    }
+}
+
+/// 呼び出し命令に付ける末尾呼び出しマーカー。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TailCallMarker {
+    MustTail,
+    Tail,
+}
+
+impl TailCallMarker {
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Self::MustTail => "musttail",
+            Self::Tail => "tail",
+        }
+    }
+}
+
+/// 他の関数への末尾呼び出しの下ろし方。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TailCallPlan {
+    pub marker: TailCallMarker,
+    /// `call` の後ろに書く呼び出し規約（`ccc` の場合は省略する）。
+    pub calling_conv: Option<String>,
+    /// スタックを消費しないことが保証されるか。
+    pub guaranteed: bool,
+}
+
+/// 呼び出し側と呼び出し先のプロトタイプから末尾呼び出しの下ろし方を選ぶ。
+///
+/// `musttail` はプロトタイプの一致が必要になる。一致しない場合でも `fastcc` 同士なら
+/// `tail call fastcc` は末尾呼び出し最適化（`-tailcallopt`）の対象として保証される。
+pub fn plan_tail_call(caller: &FunctionPrototype, callee: &FunctionPrototype) -> TailCallPlan {
+    let calling_conv = (callee.calling_conv != "ccc").then(|| callee.calling_conv.clone());
+    if caller.matches(callee) {
+        return TailCallPlan {
+            marker: TailCallMarker::MustTail,
+            calling_conv,
+            guaranteed: true,
+        };
+    }
+    TailCallPlan {
+        marker: TailCallMarker::Tail,
+        calling_conv,
+        guaranteed: caller.calling_conv == "fastcc" && callee.calling_conv == "fastcc",
+    }
+}
+
+/// 関数属性に `@tailrec` が含まれるか。
+pub fn requires_tail_call_guarantee(attributes: &[String]) -> bool {
+    attributes
+        .iter()
+        .any(|attr| attr.trim().trim_start_matches('@') == TAILREC_ATTRIBUTE)
+}
+
+/// `@tailrec` 関数で末尾呼び出しを保証できなかった箇所。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TailCallFailure {
+    pub function: String,
+    pub expr_id: Option<MirExprId>,
+    pub span: Option<MirSpan>,
+    pub reason: String,
+}
+
+impl TailCallFailure {
+    pub fn describe(&self) -> String {
+        let mut buf = format!("{TAIL_CALL_UNGUARANTEED} function={}", self.function);
+        if let Some(expr_id) = self.expr_id {
+            let _ = write!(buf, " expr_id={expr_id}");
+        }
+        if let Some(span) = self.span {
+            let _ = write!(buf, " span={}..{}", span.start, span.end);
+        }
+        let _ = write!(buf, " {}", self.reason);
+        buf
+    }
+
+    /// 構造化したバックエンド診断へ変換する。
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let location = self
+            .expr_id
+            .map(|id| format!("式 #{id} の"))
+            .unwrap_or_default();
+        let mut diagnostic = Diagnostic::new(
+            "Backend",
+            TAIL_CALL_UNGUARANTEED,
+            format!(
+                "@tailrec 関数 {} の{location}末尾呼び出しを保証できません（{}）。",
+                self.function, self.reason
+            ),
+        )
+        .with_extension("function", self.function.clone())
+        .with_extension("backend", "rust");
+        if let Some(expr_id) = self.expr_id {
+            diagnostic = diagnostic.with_extension("expr_id", expr_id.to_string());
+        }
+        if let Some(span) = self.span {
+            diagnostic = diagnostic
+                .with_extension("span.start", span.start.to_string())
+                .with_extension("span.end", span.end.to_string());
+        }
+        diagnostic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prototype(params: &[&str], ret: &str, calling_conv: &str) -> FunctionPrototype {
+        FunctionPrototype {
+            symbol: "@f".into(),
+            params: params.iter().map(|param| param.to_string()).collect(),
+            ret: ret.into(),
+            calling_conv: calling_conv.into(),
+        }
+    }
+
+    #[test]
+    fn matching_prototypes_use_musttail() {
+        let caller = prototype(&["i64", "i64"], "i64", "ccc");
+        let callee = prototype(&["i64", "i64"], "i64", "ccc");
+        let plan = plan_tail_call(&caller, &callee);
+        assert_eq!(plan.marker, TailCallMarker::MustTail);
+        assert_eq!(plan.calling_conv, None);
+        assert!(plan.guaranteed);
+    }
+
+    #[test]
+    fn fastcc_callers_get_guaranteed_tail_calls() {
+        let caller = prototype(&["i64"], "i64", "fastcc");
+        let callee = prototype(&["i64", "ptr"], "i64", "fastcc");
+        let plan = plan_tail_call(&caller, &callee);
+        assert_eq!(plan.marker, TailCallMarker::Tail);
+        assert_eq!(plan.calling_conv.as_deref(), Some("fastcc"));
+        assert!(plan.guaranteed);
+
+        let ccc_caller = prototype(&["i64"], "i64", "ccc");
+        let plan = plan_tail_call(&ccc_caller, &callee);
+        assert_eq!(plan.marker, TailCallMarker::Tail);
+        assert!(!plan.guaranteed);
+    }
+
+    #[test]
+    fn tailrec_attribute_is_recognized() {
+        assert!(requires_tail_call_guarantee(&["tailrec".into()]));
+        assert!(requires_tail_call_guarantee(&["@tailrec".into()]));
+        assert!(!requires_tail_call_guarantee(&["inline".into()]));
+    }
+}
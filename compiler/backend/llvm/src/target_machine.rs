@@ -90,6 +90,16 @@ pub enum OptimizationLevel {
     Os,
 }
 
+impl OptimizationLevel {
+    /// リリースビルド相当の最適化レベルか。
+    pub fn is_release(self) -> bool {
+        matches!(
+            self,
+            OptimizationLevel::O2 | OptimizationLevel::O3 | OptimizationLevel::Os
+        )
+    }
+}
+
 /// DataLayout 文字列と関連情報。
 #[derive(Clone, Debug)]
 pub struct DataLayoutSpec {
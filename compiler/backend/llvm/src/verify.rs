@@ -273,6 +273,22 @@ impl Verifier {
                 );
             }
         }
+        if !module.codegen_fallbacks.is_empty() {
+            audit.record(
+                "codegen.degraded.count",
+                module.codegen_fallbacks.len().to_string(),
+            );
+        }
+        for fallback in &module.codegen_fallbacks {
+            audit.record("codegen.degraded", fallback.describe());
+            if module.strict_codegen {
+                diagnostics.push(fallback.to_diagnostic());
+            }
+        }
+        for failure in &module.tail_call_failures {
+            audit.record("codegen.tail_call.unguaranteed", failure.describe());
+            diagnostics.push(failure.to_diagnostic());
+        }
         audit.record(
             "audit.verdict",
             if diagnostics.is_empty() {
@@ -0,0 +1,336 @@
+//! Wasm バイナリ形式へのエンコーダ。
+//!
+//! MVP のセクション（type/import/function/table/memory/global/export/elem/code/data）と、
+//! デバッグ用の `name` カスタムセクションを書き出す。
+
+use crate::module::{FuncType, Function, Instr, MemArg, ValType, WasmModule};
+
+const MAGIC: &[u8] = b"\0asm";
+const VERSION: &[u8] = &[0x01, 0x00, 0x00, 0x00];
+
+const SECTION_CUSTOM: u8 = 0;
+const SECTION_TYPE: u8 = 1;
+const SECTION_IMPORT: u8 = 2;
+const SECTION_FUNCTION: u8 = 3;
+const SECTION_TABLE: u8 = 4;
+const SECTION_MEMORY: u8 = 5;
+const SECTION_GLOBAL: u8 = 6;
+const SECTION_EXPORT: u8 = 7;
+const SECTION_ELEMENT: u8 = 9;
+const SECTION_CODE: u8 = 10;
+const SECTION_DATA: u8 = 11;
+
+const EXPORT_FUNC: u8 = 0x00;
+const EXPORT_MEMORY: u8 = 0x02;
+const BLOCK_EMPTY: u8 = 0x40;
+const FUNCREF: u8 = 0x70;
+
+/// 線形メモリのエクスポート名。プラグインホストはこの名前でメモリを参照する。
+pub const MEMORY_EXPORT: &str = "memory";
+
+/// モジュールをバイナリ形式へ書き出す。
+pub fn encode_module(module: &WasmModule) -> Vec<u8> {
+    let types = module.types();
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(VERSION);
+
+    let mut section = Vec::new();
+    write_u32(&mut section, types.len() as u32);
+    for ty in &types {
+        section.push(0x60);
+        write_val_types(&mut section, &ty.params);
+        write_val_types(&mut section, &ty.results);
+    }
+    write_section(&mut out, SECTION_TYPE, &section);
+
+    if !module.imports.is_empty() {
+        let mut section = Vec::new();
+        write_u32(&mut section, module.imports.len() as u32);
+        for import in &module.imports {
+            write_name(&mut section, &import.module);
+            write_name(&mut section, &import.name);
+            section.push(EXPORT_FUNC);
+            write_u32(&mut section, WasmModule::type_index(&types, &import.ty));
+        }
+        write_section(&mut out, SECTION_IMPORT, &section);
+    }
+
+    let mut section = Vec::new();
+    write_u32(&mut section, module.functions.len() as u32);
+    for function in &module.functions {
+        write_u32(&mut section, WasmModule::type_index(&types, &function.ty));
+    }
+    write_section(&mut out, SECTION_FUNCTION, &section);
+
+    if !module.table.is_empty() {
+        let mut section = Vec::new();
+        write_u32(&mut section, 1);
+        section.push(FUNCREF);
+        section.push(0x00);
+        write_u32(&mut section, module.table.len() as u32);
+        write_section(&mut out, SECTION_TABLE, &section);
+    }
+
+    let mut section = Vec::new();
+    write_u32(&mut section, 1);
+    section.push(0x00);
+    write_u32(&mut section, module.memory_pages);
+    write_section(&mut out, SECTION_MEMORY, &section);
+
+    if !module.globals.is_empty() {
+        let mut section = Vec::new();
+        write_u32(&mut section, module.globals.len() as u32);
+        for global in &module.globals {
+            section.push(ValType::I32.code());
+            section.push(u8::from(global.mutable));
+            section.push(0x41);
+            write_i64(&mut section, i64::from(global.init));
+            section.push(0x0b);
+        }
+        write_section(&mut out, SECTION_GLOBAL, &section);
+    }
+
+    let import_count = module.imports.len() as u32;
+    let exports: Vec<(&str, u32)> = module
+        .functions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, function)| {
+            function
+                .export
+                .as_deref()
+                .map(|name| (name, import_count + index as u32))
+        })
+        .collect();
+    let mut section = Vec::new();
+    write_u32(&mut section, exports.len() as u32 + 1);
+    write_name(&mut section, MEMORY_EXPORT);
+    section.push(EXPORT_MEMORY);
+    write_u32(&mut section, 0);
+    for (name, index) in exports {
+        write_name(&mut section, name);
+        section.push(EXPORT_FUNC);
+        write_u32(&mut section, index);
+    }
+    write_section(&mut out, SECTION_EXPORT, &section);
+
+    if !module.table.is_empty() {
+        let mut section = Vec::new();
+        write_u32(&mut section, 1);
+        section.push(0x00);
+        section.push(0x41);
+        write_i64(&mut section, 0);
+        section.push(0x0b);
+        write_u32(&mut section, module.table.len() as u32);
+        for index in &module.table {
+            write_u32(&mut section, *index);
+        }
+        write_section(&mut out, SECTION_ELEMENT, &section);
+    }
+
+    let mut section = Vec::new();
+    write_u32(&mut section, module.functions.len() as u32);
+    for function in &module.functions {
+        let body = encode_function_body(function, &types);
+        write_u32(&mut section, body.len() as u32);
+        section.extend_from_slice(&body);
+    }
+    write_section(&mut out, SECTION_CODE, &section);
+
+    if !module.data.is_empty() {
+        let mut section = Vec::new();
+        write_u32(&mut section, module.data.len() as u32);
+        for segment in &module.data {
+            section.push(0x00);
+            section.push(0x41);
+            write_i64(&mut section, i64::from(segment.offset));
+            section.push(0x0b);
+            write_u32(&mut section, segment.bytes.len() as u32);
+            section.extend_from_slice(&segment.bytes);
+        }
+        write_section(&mut out, SECTION_DATA, &section);
+    }
+
+    write_section(&mut out, SECTION_CUSTOM, &encode_name_section(module));
+    out
+}
+
+fn encode_function_body(function: &Function, types: &[FuncType]) -> Vec<u8> {
+    let mut body = Vec::new();
+    // 同じ型が連続するローカルはまとめて宣言する。
+    let mut runs: Vec<(u32, ValType)> = Vec::new();
+    for local in &function.locals {
+        match runs.last_mut() {
+            Some((count, ty)) if ty == local => *count += 1,
+            _ => runs.push((1, *local)),
+        }
+    }
+    write_u32(&mut body, runs.len() as u32);
+    for (count, ty) in runs {
+        write_u32(&mut body, count);
+        body.push(ty.code());
+    }
+    for instr in &function.body {
+        encode_instr(&mut body, instr, types);
+    }
+    body.push(0x0b);
+    body
+}
+
+fn encode_instr(out: &mut Vec<u8>, instr: &Instr, types: &[FuncType]) {
+    if let Some((opcode, _)) = instr.plain() {
+        out.push(opcode);
+        return;
+    }
+    match instr {
+        Instr::Block(ty) => write_block(out, 0x02, *ty),
+        Instr::Loop(ty) => write_block(out, 0x03, *ty),
+        Instr::If(ty) => write_block(out, 0x04, *ty),
+        Instr::Br(depth) => write_indexed(out, 0x0c, *depth),
+        Instr::BrIf(depth) => write_indexed(out, 0x0d, *depth),
+        Instr::Call(index) => write_indexed(out, 0x10, *index),
+        Instr::CallIndirect(ty) => {
+            write_indexed(out, 0x11, WasmModule::type_index(types, ty));
+            out.push(0x00);
+        }
+        Instr::LocalGet(index) => write_indexed(out, 0x20, *index),
+        Instr::LocalSet(index) => write_indexed(out, 0x21, *index),
+        Instr::LocalTee(index) => write_indexed(out, 0x22, *index),
+        Instr::GlobalGet(index) => write_indexed(out, 0x23, *index),
+        Instr::GlobalSet(index) => write_indexed(out, 0x24, *index),
+        Instr::I32Load(arg) => write_memory(out, 0x28, *arg),
+        Instr::I64Load(arg) => write_memory(out, 0x29, *arg),
+        Instr::F64Load(arg) => write_memory(out, 0x2b, *arg),
+        Instr::I32Load8U(arg) => write_memory(out, 0x2d, *arg),
+        Instr::I32Store(arg) => write_memory(out, 0x36, *arg),
+        Instr::I64Store(arg) => write_memory(out, 0x37, *arg),
+        Instr::F64Store(arg) => write_memory(out, 0x39, *arg),
+        Instr::I32Store8(arg) => write_memory(out, 0x3a, *arg),
+        Instr::MemorySize => out.extend_from_slice(&[0x3f, 0x00]),
+        Instr::MemoryGrow => out.extend_from_slice(&[0x40, 0x00]),
+        Instr::I32Const(value) => {
+            out.push(0x41);
+            write_i64(out, i64::from(*value));
+        }
+        Instr::I64Const(value) => {
+            out.push(0x42);
+            write_i64(out, *value);
+        }
+        Instr::F64Const(value) => {
+            out.push(0x44);
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        _ => unreachable!("即値なし命令は plain() で処理済み: {instr:?}"),
+    }
+}
+
+fn write_block(out: &mut Vec<u8>, opcode: u8, ty: Option<ValType>) {
+    out.push(opcode);
+    out.push(ty.map(ValType::code).unwrap_or(BLOCK_EMPTY));
+}
+
+fn write_indexed(out: &mut Vec<u8>, opcode: u8, index: u32) {
+    out.push(opcode);
+    write_u32(out, index);
+}
+
+fn write_memory(out: &mut Vec<u8>, opcode: u8, arg: MemArg) {
+    out.push(opcode);
+    write_u32(out, arg.align);
+    write_u32(out, arg.offset);
+}
+
+/// `name` カスタムセクション（モジュール名と関数名）。
+fn encode_name_section(module: &WasmModule) -> Vec<u8> {
+    let mut section = Vec::new();
+    write_name(&mut section, "name");
+
+    let mut module_name = Vec::new();
+    write_name(&mut module_name, &module.name);
+    section.push(0);
+    write_u32(&mut section, module_name.len() as u32);
+    section.extend_from_slice(&module_name);
+
+    let names = module.function_names();
+    let mut function_names = Vec::new();
+    write_u32(&mut function_names, names.len() as u32);
+    for (index, name) in names.iter().enumerate() {
+        write_u32(&mut function_names, index as u32);
+        write_name(&mut function_names, name);
+    }
+    section.push(1);
+    write_u32(&mut section, function_names.len() as u32);
+    section.extend_from_slice(&function_names);
+    section
+}
+
+fn write_section(out: &mut Vec<u8>, id: u8, payload: &[u8]) {
+    out.push(id);
+    write_u32(out, payload.len() as u32);
+    out.extend_from_slice(payload);
+}
+
+fn write_val_types(out: &mut Vec<u8>, types: &[ValType]) {
+    write_u32(out, types.len() as u32);
+    out.extend(types.iter().map(|ty| ty.code()));
+}
+
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    write_u32(out, name.len() as u32);
+    out.extend_from_slice(name.as_bytes());
+}
+
+/// 符号なし LEB128。
+pub(crate) fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// 符号付き LEB128（`i32.const` の即値も同じ形式で書く）。
+pub(crate) fn write_i64(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leb_u32(value: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_u32(&mut out, value);
+        out
+    }
+
+    fn leb_i64(value: i64) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_i64(&mut out, value);
+        out
+    }
+
+    #[test]
+    fn leb128_matches_spec_examples() {
+        assert_eq!(leb_u32(0), vec![0x00]);
+        assert_eq!(leb_u32(624_485), vec![0xe5, 0x8e, 0x26]);
+        assert_eq!(leb_i64(-1), vec![0x7f]);
+        assert_eq!(leb_i64(63), vec![0x3f]);
+        assert_eq!(leb_i64(64), vec![0xc0, 0x00]);
+        assert_eq!(leb_i64(-123_456), vec![0xc0, 0xbb, 0x78]);
+    }
+}
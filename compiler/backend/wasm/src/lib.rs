@@ -0,0 +1,1224 @@
+//! Reml の WebAssembly バックエンド。
+//!
+//! LLVM バックエンドと同じ MIR JSON（単相化済み）を入力に、wasm32 の `.wasm` バイナリと
+//! WAT テキストを生成する。メモリ管理は `runtime/native` と同じヘッダ配置・型タグの
+//! 参照カウントを線形メモリ上に実装し、FFI extern は `env` モジュールからの取り込み関数になる。
+
+pub mod encoder;
+mod lower;
+pub mod module;
+pub mod plugin_abi;
+pub mod runtime;
+pub mod wat;
+
+use std::path::Path;
+
+use reml_llvm_backend::{load_monomorphized_mir_from_json, MirFunction};
+
+pub use encoder::{encode_module, MEMORY_EXPORT};
+pub use lower::IMPORT_MODULE;
+pub use module::WasmModule;
+pub use reml_llvm_backend::{CodegenFallback, MirSnapshotError};
+pub use wat::render_wat;
+
+/// wasm 生成のオプション。
+#[derive(Clone, Debug, Default)]
+pub struct WasmEmitOptions {
+    /// 縮退箇所で生成を中断する（`--strict-codegen`）。
+    pub strict_codegen: bool,
+    /// プラグイン呼び出し ABI のエクスポートとアダプタを生成する（`--plugin-abi`）。
+    pub plugin_abi: bool,
+}
+
+impl WasmEmitOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_strict_codegen(mut self, strict: bool) -> Self {
+        self.strict_codegen = strict;
+        self
+    }
+
+    pub fn with_plugin_abi(mut self, plugin_abi: bool) -> Self {
+        self.plugin_abi = plugin_abi;
+        self
+    }
+
+    /// コマンドラインフラグを取り込む。対応するフラグなら `true` を返す。
+    pub fn apply_flag(&mut self, flag: &str) -> bool {
+        match flag {
+            "--strict-codegen" => self.strict_codegen = true,
+            "--no-strict-codegen" => self.strict_codegen = false,
+            "--plugin-abi" => self.plugin_abi = true,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// 生成結果。
+#[derive(Clone, Debug)]
+pub struct WasmArtifact {
+    pub module: WasmModule,
+    /// `.wasm` バイナリ。
+    pub binary: Vec<u8>,
+    /// WAT テキスト。
+    pub wat: String,
+    /// `unreachable` に置き換えた縮退箇所。
+    pub fallbacks: Vec<CodegenFallback>,
+}
+
+/// MIR 関数列から wasm モジュールを生成する。
+///
+/// strict モードでは最初の縮退箇所で [`MirSnapshotError::StrictCodegen`] を返す。
+pub fn emit_wasm_module(
+    module_name: &str,
+    functions: &[MirFunction],
+    options: &WasmEmitOptions,
+) -> Result<WasmArtifact, MirSnapshotError> {
+    let (module, fallbacks) = lower::lower_module(module_name, functions, options.plugin_abi);
+    if options.strict_codegen {
+        if let Some(fallback) = fallbacks.first() {
+            return Err(MirSnapshotError::StrictCodegen(fallback.clone()));
+        }
+    }
+    Ok(WasmArtifact {
+        binary: encode_module(&module),
+        wat: render_wat(&module),
+        module,
+        fallbacks,
+    })
+}
+
+/// MIR JSON から wasm モジュールを生成する。モジュール名は JSON の `module` を優先する。
+pub fn emit_wasm_module_from_mir_json<P: AsRef<Path>>(
+    path: P,
+    options: &WasmEmitOptions,
+    default_module_name: &str,
+) -> Result<WasmArtifact, MirSnapshotError> {
+    let loaded = load_monomorphized_mir_from_json(path)?;
+    let module_name = loaded
+        .module
+        .unwrap_or_else(|| default_module_name.to_string());
+    emit_wasm_module(&module_name, &loaded.functions, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::{env, fs};
+    use wasmtime::{Engine, Instance, Linker, Memory, Module, Store};
+
+    type TestResult = Result<(), Box<dyn Error>>;
+
+    fn emit_from_spec(
+        spec: &str,
+        file_name: &str,
+        options: &WasmEmitOptions,
+    ) -> Result<WasmArtifact, MirSnapshotError> {
+        let tmp = env::temp_dir().join(file_name);
+        fs::write(&tmp, spec)?;
+        let artifact = emit_wasm_module_from_mir_json(&tmp, options, "test_module");
+        fs::remove_file(tmp)?;
+        artifact
+    }
+
+    fn read_u32(memory: &Memory, store: &Store<()>, address: i32) -> u32 {
+        let offset = address as usize;
+        let bytes = &memory.data(store)[offset..offset + 4];
+        u32::from_le_bytes(bytes.try_into().expect("4 バイト"))
+    }
+
+    fn read_u64(memory: &Memory, store: &Store<()>, address: i32) -> u64 {
+        let offset = address as usize;
+        let bytes = &memory.data(store)[offset..offset + 8];
+        u64::from_le_bytes(bytes.try_into().expect("8 バイト"))
+    }
+
+    fn instantiate_pure(binary: &[u8]) -> Result<(Store<()>, Instance), Box<dyn Error>> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, binary)?;
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+        Ok((store, instance))
+    }
+
+    #[test]
+    fn pure_functions_run_under_wasmtime() -> TestResult {
+        // fn add(a: i64, b: i64) -> i64 = a + b
+        // fn main() -> i64 { let x = add(40, 2); if x > 41 { x } else { 0 } }
+        // fn greeting() -> Str = "hi"
+        let spec = r#"
+    {
+      "module": "pure",
+      "functions": [
+        {
+          "name": "add",
+          "params": [{"name": "a", "ty": "i64"}, {"name": "b", "ty": "i64"}],
+          "return_type": "i64",
+          "body": 2,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "a"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "b"}}},
+            {"id": 2, "ty": "i64",
+             "kind": {"kind": "binary", "operator": "+", "left": 0, "right": 1}}
+          ]
+        },
+        {
+          "name": "main",
+          "return_type": "i64",
+          "body": 10,
+          "exprs": [
+            {"id": 0, "ty": "(i64, i64) -> i64",
+             "kind": {"kind": "identifier", "ident": {"name": "add"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 40, "raw": "40", "base": "base10"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 2, "raw": "2", "base": "base10"}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "call", "callee": 0, "args": [1, 2]}},
+            {"id": 4, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "x"}}},
+            {"id": 5, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 41, "raw": "41", "base": "base10"}}},
+            {"id": 6, "ty": "Bool",
+             "kind": {"kind": "binary", "operator": ">", "left": 4, "right": 5}},
+            {"id": 7, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "x"}}},
+            {"id": 8, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 0, "raw": "0", "base": "base10"}}},
+            {"id": 9, "ty": "i64", "span": {"start": 60, "end": 90}, "kind": {"kind": "if_else",
+             "condition": 6, "then_branch": 7, "else_branch": 8}},
+            {"id": 10, "ty": "i64",
+             "kind": {"kind": "block", "statements": [
+               {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "x"}},
+                         "value": 3, "mutable": false}}
+             ], "tail": 9}}
+          ]
+        },
+        {
+          "name": "greeting",
+          "return_type": "Str",
+          "body": 0,
+          "exprs": [
+            {"id": 0, "ty": "Str", "kind": {"kind": "literal",
+             "value": {"kind": "string", "value": "hi"}}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let artifact = emit_from_spec(spec, "reml_wasm_pure.json", &WasmEmitOptions::new())?;
+        assert!(artifact.fallbacks.is_empty(), "{:?}", artifact.fallbacks);
+        assert!(artifact.module.imports.is_empty());
+
+        let (mut store, instance) = instantiate_pure(&artifact.binary)?;
+        let main = instance.get_typed_func::<(), i64>(&mut store, "main")?;
+        assert_eq!(main.call(&mut store, ())?, 42);
+        let add = instance.get_typed_func::<(i64, i64), i64>(&mut store, "add")?;
+        assert_eq!(add.call(&mut store, (-5, 3))?, -2);
+
+        // 文字列リテラルは native と同じヘッダ（refcount/type_tag）を持つ静的オブジェクトになる。
+        let greeting = instance.get_typed_func::<(), i32>(&mut store, "greeting")?;
+        let text = greeting.call(&mut store, ())?;
+        let memory = instance
+            .get_memory(&mut store, MEMORY_EXPORT)
+            .expect("memory をエクスポートすること");
+        assert!(text as u32 >= runtime::HOST_SCRATCH_BYTES);
+        assert_eq!(
+            read_u32(&memory, &store, text - 4),
+            runtime::TAG_STRING as u32
+        );
+        assert_eq!(read_u32(&memory, &store, text - 8), 1);
+        let data = read_u32(&memory, &store, text) as usize;
+        assert_eq!(read_u64(&memory, &store, text + 8), 2);
+        assert_eq!(&memory.data(&store)[data..data + 2], b"hi");
+
+        assert!(artifact.wat.starts_with("(module $pure"));
+        assert!(artifact
+            .wat
+            .contains("(func $add (export \"add\") (param i64 i64) (result i64)"));
+        assert!(artifact.wat.contains("    call $add\n"));
+        // 末尾式は一度だけ評価する。
+        assert_eq!(artifact.wat.matches("i64.gt_s").count(), 1);
+        assert!(artifact.wat.contains("(memory (export \"memory\") 1)"));
+        Ok(())
+    }
+
+    #[test]
+    fn refcount_release_frees_children_and_reuses_blocks() -> TestResult {
+        let artifact = emit_wasm_module("rc", &[], &WasmEmitOptions::new())?;
+        let (mut store, instance) = instantiate_pure(&artifact.binary)?;
+        let mem_alloc = instance.get_typed_func::<i32, i32>(&mut store, "mem_alloc")?;
+        let inc_ref = instance.get_typed_func::<i32, ()>(&mut store, "inc_ref")?;
+        let dec_ref = instance.get_typed_func::<i32, ()>(&mut store, "dec_ref")?;
+        let set_tag = instance.get_typed_func::<(i32, i32), ()>(&mut store, "reml_set_type_tag")?;
+        let memory = instance
+            .get_memory(&mut store, MEMORY_EXPORT)
+            .expect("memory をエクスポートすること");
+
+        // 要素 1 つのタプル {len i64, items i32} を組み立てる。
+        let tuple = mem_alloc.call(&mut store, 16)?;
+        let items = mem_alloc.call(&mut store, 4)?;
+        let child = mem_alloc.call(&mut store, 8)?;
+        assert_eq!(read_u32(&memory, &store, tuple - 8), 1);
+        set_tag.call(&mut store, (tuple, runtime::TAG_TUPLE))?;
+        memory.write(&mut store, tuple as usize, &1u64.to_le_bytes())?;
+        memory.write(&mut store, tuple as usize + 8, &items.to_le_bytes())?;
+        memory.write(&mut store, items as usize, &child.to_le_bytes())?;
+        inc_ref.call(&mut store, child)?;
+        assert_eq!(read_u32(&memory, &store, child - 8), 2);
+
+        // タプルの破棄で要素の参照が 1 つ減り、タプル本体のブロックは再利用される。
+        dec_ref.call(&mut store, tuple)?;
+        assert_eq!(read_u32(&memory, &store, child - 8), 1);
+        let reused = mem_alloc.call(&mut store, 16)?;
+        assert_eq!(reused, tuple);
+        assert_eq!(read_u32(&memory, &store, reused - 8), 1);
+        assert_eq!(
+            read_u64(&memory, &store, reused),
+            0,
+            "ペイロードをゼロ埋めすること"
+        );
+
+        // ページを越える確保では memory.grow で拡張する。
+        let large = mem_alloc.call(&mut store, 100_000)?;
+        assert!(memory.data_size(&store) >= large as usize + 100_000);
+        Ok(())
+    }
+
+    #[test]
+    fn ffi_externs_become_env_imports() -> TestResult {
+        // extern "C" fn host_add(a: i64, b: i64) -> i64
+        // fn main() -> i64 = host_add(1, 2)
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "@main",
+          "return_type": "i64",
+          "ffi_calls": [
+            {"name": "host_add", "calling_conv": "ccc", "args": ["i64", "i64"], "return": "i64"},
+            {"name": "printf", "calling_conv": "ccc", "args": ["ptr"], "return": "i32",
+             "variadic": true}
+          ],
+          "body": 3,
+          "exprs": [
+            {"id": 0, "ty": "(i64, i64) -> i64",
+             "kind": {"kind": "identifier", "ident": {"name": "host_add"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 1, "raw": "1", "base": "base10"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "literal",
+             "value": {"kind": "int", "value": 2, "raw": "2", "base": "base10"}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "call", "callee": 0, "args": [1, 2]}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let artifact = emit_from_spec(spec, "reml_wasm_ffi.json", &WasmEmitOptions::new())?;
+        assert_eq!(artifact.module.imports.len(), 1);
+        assert_eq!(artifact.module.imports[0].module, IMPORT_MODULE);
+        // 可変長引数の extern は wasm の取り込み関数で表せないため縮退として報告する。
+        assert_eq!(artifact.fallbacks.len(), 1);
+        assert_eq!(artifact.fallbacks[0].code, "wasm.fallback.variadic_ffi");
+        assert!(artifact.wat.contains(
+            "(import \"env\" \"host_add\" (func $host_add (param i64 i64) (result i64)))"
+        ));
+
+        let engine = Engine::default();
+        let module = Module::new(&engine, &artifact.binary)?;
+        let mut store = Store::new(&engine, ());
+        let mut linker = Linker::new(&engine);
+        linker.func_wrap(IMPORT_MODULE, "host_add", |a: i64, b: i64| a * 10 + b)?;
+        let instance = linker.instantiate(&mut store, &module)?;
+        let main = instance.get_typed_func::<(), i64>(&mut store, "main")?;
+        assert_eq!(main.call(&mut store, ())?, 12);
+
+        let strict = emit_from_spec(
+            spec,
+            "reml_wasm_ffi_strict.json",
+            &WasmEmitOptions::new().with_strict_codegen(true),
+        );
+        assert!(matches!(strict, Err(MirSnapshotError::StrictCodegen(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_expressions_trap_and_are_reported() -> TestResult {
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "main",
+          "return_type": "i64",
+          "body": 1,
+          "exprs": [
+            {"id": 0, "ty": "()", "kind": {"kind": "literal", "value": {"kind": "unit"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "perform_call",
+             "call": {"effect": {"name": "Counter::next"}, "argument": 0}}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let artifact = emit_from_spec(spec, "reml_wasm_fallback.json", &WasmEmitOptions::new())?;
+        assert_eq!(artifact.fallbacks.len(), 1);
+        assert_eq!(artifact.fallbacks[0].code, "wasm.fallback.expr_unsupported");
+        assert_eq!(artifact.fallbacks[0].expr_id, Some(1));
+
+        let (mut store, instance) = instantiate_pure(&artifact.binary)?;
+        let main = instance.get_typed_func::<(), i64>(&mut store, "main")?;
+        assert!(
+            main.call(&mut store, ()).is_err(),
+            "unreachable でトラップすること"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn containers_use_native_layout_and_release_temporaries() -> TestResult {
+        // fn pair() -> (i64, Bool) = (40, true)
+        // fn pick() -> i64 { let r = { y: 2, x: 40 }; r.x + r.y }
+        // fn second() -> i64 { let xs = [10, 20, 30]; xs[1] }
+        // fn inner() -> (i64, i64) { let t = ((1, 2), 3); t.0 }
+        // fn outside() -> i64 { let xs = [1]; xs[5] }
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "pair",
+          "return_type": "Tuple<i64, Bool>",
+          "body": 0,
+          "exprs": [
+            {"id": 0, "ty": "Tuple<i64, Bool>", "kind": {"kind": "literal", "value": {
+              "kind": "tuple", "elements": [
+                {"kind": {"kind": "literal", "value": {"kind": "int", "value": 40}}},
+                {"kind": {"kind": "literal", "value": {"kind": "bool", "value": true}}}
+              ]}}}
+          ]
+        },
+        {
+          "name": "pick",
+          "return_type": "i64",
+          "body": 6,
+          "exprs": [
+            {"id": 0, "ty": "Record<i64, i64>", "kind": {"kind": "literal", "value": {
+              "kind": "record", "fields": [
+                {"key": {"name": "y"},
+                 "value": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 2}}}},
+                {"key": {"name": "x"},
+                 "value": {"kind": {"kind": "binary", "operator": "Mul",
+                   "left": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 20}}},
+                   "right": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 2}}}}}}
+              ]}}},
+            {"id": 1, "ty": "Record<i64, i64>", "kind": {"kind": "identifier", "ident": {"name": "r"}}},
+            {"id": 2, "ty": "'t3", "kind": {"kind": "field_access", "target": 1, "field": "x"}},
+            {"id": 3, "ty": "Record<i64, i64>", "kind": {"kind": "identifier", "ident": {"name": "r"}}},
+            {"id": 4, "ty": "'t4", "kind": {"kind": "field_access", "target": 3, "field": "y"}},
+            {"id": 5, "ty": "i64", "kind": {"kind": "binary", "operator": "+", "left": 2, "right": 4}},
+            {"id": 6, "ty": "i64", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "r"}},
+                        "value": 0, "mutable": false}}
+            ], "tail": 5}}
+          ]
+        },
+        {
+          "name": "second",
+          "return_type": "i64",
+          "body": 4,
+          "exprs": [
+            {"id": 0, "ty": "[i64]", "kind": {"kind": "literal", "value": {
+              "kind": "array", "elements": [
+                {"kind": {"kind": "literal", "value": {"kind": "int", "value": 10}}},
+                {"kind": {"kind": "literal", "value": {"kind": "int", "value": 20}}},
+                {"kind": {"kind": "literal", "value": {"kind": "int", "value": 30}}}
+              ]}}},
+            {"id": 1, "ty": "[i64]", "kind": {"kind": "identifier", "ident": {"name": "xs"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 1}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "index", "target": 1, "index": 2}},
+            {"id": 4, "ty": "i64", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "xs"}},
+                        "value": 0, "mutable": false}}
+            ], "tail": 3}}
+          ]
+        },
+        {
+          "name": "inner",
+          "return_type": "Tuple<i64, i64>",
+          "body": 3,
+          "exprs": [
+            {"id": 0, "ty": "Tuple<Tuple<i64, i64>, i64>", "kind": {"kind": "literal", "value": {
+              "kind": "tuple", "elements": [
+                {"kind": {"kind": "literal", "value": {"kind": "tuple", "elements": [
+                  {"kind": {"kind": "literal", "value": {"kind": "int", "value": 1}}},
+                  {"kind": {"kind": "literal", "value": {"kind": "int", "value": 2}}}
+                ]}}},
+                {"kind": {"kind": "literal", "value": {"kind": "int", "value": 3}}}
+              ]}}},
+            {"id": 1, "ty": "Tuple<Tuple<i64, i64>, i64>",
+             "kind": {"kind": "identifier", "ident": {"name": "t"}}},
+            {"id": 2, "ty": "Tuple<i64, i64>",
+             "kind": {"kind": "field_access", "target": 1, "field": "0"}},
+            {"id": 3, "ty": "Tuple<i64, i64>", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "t"}},
+                        "value": 0, "mutable": false}}
+            ], "tail": 2}}
+          ]
+        },
+        {
+          "name": "outside",
+          "return_type": "i64",
+          "body": 4,
+          "exprs": [
+            {"id": 0, "ty": "[i64]", "kind": {"kind": "literal", "value": {
+              "kind": "array", "elements": [
+                {"kind": {"kind": "literal", "value": {"kind": "int", "value": 1}}}
+              ]}}},
+            {"id": 1, "ty": "[i64]", "kind": {"kind": "identifier", "ident": {"name": "xs"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 5}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "index", "target": 1, "index": 2}},
+            {"id": 4, "ty": "i64", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "xs"}},
+                        "value": 0, "mutable": false}}
+            ], "tail": 3}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let artifact = emit_from_spec(spec, "reml_wasm_containers.json", &WasmEmitOptions::new())?;
+        assert!(artifact.fallbacks.is_empty(), "{:?}", artifact.fallbacks);
+
+        let (mut store, instance) = instantiate_pure(&artifact.binary)?;
+        let memory = instance
+            .get_memory(&mut store, MEMORY_EXPORT)
+            .expect("memory をエクスポートすること");
+
+        // タプルは {len i64, items i32 @8}、要素は型タグ付きのボックス。
+        let pair = instance.get_typed_func::<(), i32>(&mut store, "pair")?;
+        let tuple = pair.call(&mut store, ())?;
+        assert_eq!(
+            read_u32(&memory, &store, tuple - 4),
+            runtime::TAG_TUPLE as u32
+        );
+        assert_eq!(read_u32(&memory, &store, tuple - 8), 1);
+        assert_eq!(read_u64(&memory, &store, tuple), 2);
+        let items = read_u32(&memory, &store, tuple + 8) as i32;
+        let first = read_u32(&memory, &store, items) as i32;
+        let second_item = read_u32(&memory, &store, items + 4) as i32;
+        assert_eq!(
+            read_u32(&memory, &store, first - 4),
+            runtime::TAG_INT as u32
+        );
+        assert_eq!(read_u64(&memory, &store, first), 40);
+        assert_eq!(
+            read_u32(&memory, &store, second_item - 4),
+            runtime::TAG_BOOL as u32
+        );
+        assert_eq!(memory.data(&store)[second_item as usize], 1);
+
+        // フィールドは名前順に並ぶ。束縛したコンテナはスコープの終わりで解放され、再利用される。
+        let pick = instance.get_typed_func::<(), i64>(&mut store, "pick")?;
+        let second = instance.get_typed_func::<(), i64>(&mut store, "second")?;
+        let pages = memory.size(&store);
+        for _ in 0..2000 {
+            assert_eq!(pick.call(&mut store, ())?, 42);
+            assert_eq!(second.call(&mut store, ())?, 20);
+        }
+        assert_eq!(memory.size(&store), pages, "一時的なコンテナを解放すること");
+
+        // 取り出した要素は外側のコンテナの解放後も所有参照として残る。
+        let inner = instance.get_typed_func::<(), i32>(&mut store, "inner")?;
+        let value = inner.call(&mut store, ())?;
+        assert_eq!(
+            read_u32(&memory, &store, value - 4),
+            runtime::TAG_TUPLE as u32
+        );
+        assert_eq!(read_u32(&memory, &store, value - 8), 1);
+        let items = read_u32(&memory, &store, value + 8) as i32;
+        let last = read_u32(&memory, &store, items + 4) as i32;
+        assert_eq!(read_u64(&memory, &store, last), 2);
+
+        let outside = instance.get_typed_func::<(), i64>(&mut store, "outside")?;
+        assert!(
+            outside.call(&mut store, ()).is_err(),
+            "範囲外の添字はトラップすること"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn adts_and_match_patterns_run_on_the_heap() -> TestResult {
+        // type Shape = Circle(i64) | Rect(i64, i64) | Empty
+        // fn area(s) = match s { Circle(r) -> r * 3 | Rect(w, h) -> w * h | Empty -> 0 }
+        // fn circle() -> i64 = area(Circle(2))
+        // fn rect() -> i64 = area(Rect(3, 4))
+        // fn empty() -> i64 = area(Empty)
+        // fn classify(n: i64) -> i64 =
+        //   match (n, n) { (0, _) -> 100 | (x, _) if x > 10 -> 1 | (1, _) | (2, _) -> 2 | _ -> 3 }
+        // fn split() -> i64 { let { x, y } = { y: 5, x: 7 }; x - y }
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "area",
+          "params": [{"name": "s", "ty": "'t0"}],
+          "return_type": "i64",
+          "body": 8,
+          "exprs": [
+            {"id": 0, "ty": "'t0", "kind": {"kind": "identifier", "ident": {"name": "s"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "r"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 3}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "binary", "operator": "*", "left": 1, "right": 2}},
+            {"id": 4, "ty": "'t5", "kind": {"kind": "identifier", "ident": {"name": "w"}}},
+            {"id": 5, "ty": "'t5", "kind": {"kind": "identifier", "ident": {"name": "h"}}},
+            {"id": 6, "ty": "Unknown", "kind": {"kind": "binary", "operator": "*", "left": 4, "right": 5}},
+            {"id": 7, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 0}}},
+            {"id": 8, "ty": "i64", "kind": {"kind": "match", "target": 0, "arms": [
+              {"pattern": {"kind": {"kind": "constructor", "name": "Circle",
+                "args": [{"kind": {"kind": "var", "name": "r"}}]}}, "body": 3},
+              {"pattern": {"kind": {"kind": "constructor", "name": "Rect",
+                "args": [{"kind": {"kind": "var", "name": "w"}}, {"kind": {"kind": "var", "name": "h"}}]}},
+               "body": 6},
+              {"pattern": {"kind": {"kind": "constructor", "name": "Empty", "args": []}}, "body": 7}
+            ]}}
+          ]
+        },
+        {
+          "name": "circle",
+          "return_type": "i64",
+          "body": 4,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 2}}},
+            {"id": 1, "ty": "Unknown", "kind": {"kind": "identifier", "ident": {"name": "Circle"}}},
+            {"id": 2, "ty": "Unknown", "kind": {"kind": "call", "callee": 1, "args": [0]}},
+            {"id": 3, "ty": "Unknown", "kind": {"kind": "identifier", "ident": {"name": "area"}}},
+            {"id": 4, "ty": "i64", "kind": {"kind": "call", "callee": 3, "args": [2]}}
+          ]
+        },
+        {
+          "name": "rect",
+          "return_type": "i64",
+          "body": 5,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 3}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 4}}},
+            {"id": 2, "ty": "Unknown", "kind": {"kind": "identifier", "ident": {"name": "Rect"}}},
+            {"id": 3, "ty": "Unknown", "kind": {"kind": "call", "callee": 2, "args": [0, 1]}},
+            {"id": 4, "ty": "Unknown", "kind": {"kind": "identifier", "ident": {"name": "area"}}},
+            {"id": 5, "ty": "i64", "kind": {"kind": "call", "callee": 4, "args": [3]}}
+          ]
+        },
+        {
+          "name": "empty",
+          "return_type": "i64",
+          "body": 2,
+          "exprs": [
+            {"id": 0, "ty": "Unknown", "kind": {"kind": "identifier", "ident": {"name": "Empty"}}},
+            {"id": 1, "ty": "Unknown", "kind": {"kind": "identifier", "ident": {"name": "area"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "call", "callee": 1, "args": [0]}}
+          ]
+        },
+        {
+          "name": "classify",
+          "params": [{"name": "n", "ty": "i64"}],
+          "return_type": "i64",
+          "body": 8,
+          "exprs": [
+            {"id": 0, "ty": "Tuple<i64, i64>", "kind": {"kind": "literal", "value": {
+              "kind": "tuple", "elements": [
+                {"kind": {"kind": "identifier", "name": "n"}},
+                {"kind": {"kind": "identifier", "name": "n"}}
+              ]}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 100}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "x"}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 10}}},
+            {"id": 4, "ty": "Bool", "kind": {"kind": "binary", "operator": ">", "left": 2, "right": 3}},
+            {"id": 5, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 1}}},
+            {"id": 6, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 2}}},
+            {"id": 7, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 3}}},
+            {"id": 8, "ty": "i64", "kind": {"kind": "match", "target": 0, "arms": [
+              {"pattern": {"kind": {"kind": "tuple", "elements": [
+                {"kind": {"kind": "literal", "value": {"kind": "int", "value": 0}}},
+                {"kind": {"kind": "wildcard"}}]}}, "body": 1},
+              {"pattern": {"kind": {"kind": "tuple", "elements": [
+                {"kind": {"kind": "var", "name": "x"}}, {"kind": {"kind": "wildcard"}}]}},
+               "guard": 4, "body": 5},
+              {"pattern": {"kind": {"kind": "or", "variants": [
+                {"kind": {"kind": "tuple", "elements": [
+                  {"kind": {"kind": "literal", "value": {"kind": "int", "value": 1}}},
+                  {"kind": {"kind": "wildcard"}}]}},
+                {"kind": {"kind": "tuple", "elements": [
+                  {"kind": {"kind": "literal", "value": {"kind": "int", "value": 2}}},
+                  {"kind": {"kind": "wildcard"}}]}}]}}, "body": 6},
+              {"pattern": {"kind": {"kind": "wildcard"}}, "body": 7}
+            ]}}
+          ]
+        },
+        {
+          "name": "split",
+          "return_type": "i64",
+          "body": 4,
+          "exprs": [
+            {"id": 0, "ty": "Record<i64, i64>", "kind": {"kind": "literal", "value": {
+              "kind": "record", "fields": [
+                {"key": {"name": "y"},
+                 "value": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 5}}}},
+                {"key": {"name": "x"},
+                 "value": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 7}}}}
+              ]}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "x"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "y"}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "binary", "operator": "-", "left": 1, "right": 2}},
+            {"id": 4, "ty": "i64", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "record",
+                "fields": [{"key": "x"}, {"key": "y"}], "has_rest": false}},
+               "value": 0, "mutable": false}}
+            ], "tail": 3}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let artifact = emit_from_spec(spec, "reml_wasm_adts.json", &WasmEmitOptions::new())?;
+        assert!(artifact.fallbacks.is_empty(), "{:?}", artifact.fallbacks);
+
+        let (mut store, instance) = instantiate_pure(&artifact.binary)?;
+        let memory = instance
+            .get_memory(&mut store, MEMORY_EXPORT)
+            .expect("memory をエクスポートすること");
+        let circle = instance.get_typed_func::<(), i64>(&mut store, "circle")?;
+        let rect = instance.get_typed_func::<(), i64>(&mut store, "rect")?;
+        let empty = instance.get_typed_func::<(), i64>(&mut store, "empty")?;
+        let classify = instance.get_typed_func::<i64, i64>(&mut store, "classify")?;
+        let split = instance.get_typed_func::<(), i64>(&mut store, "split")?;
+
+        // 構築した ADT は引数の借用が終わった時点で解放される。
+        let pages = memory.size(&store);
+        for _ in 0..2000 {
+            assert_eq!(circle.call(&mut store, ())?, 6);
+            assert_eq!(rect.call(&mut store, ())?, 12);
+            assert_eq!(empty.call(&mut store, ())?, 0);
+        }
+        assert_eq!(memory.size(&store), pages, "ADT の値を解放すること");
+
+        for (input, expected) in [(0, 100), (11, 1), (10, 3), (1, 2), (2, 2), (5, 3)] {
+            assert_eq!(classify.call(&mut store, input)?, expected, "n = {input}");
+        }
+        assert_eq!(split.call(&mut store, ())?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn unresolved_generics_keep_a_boxed_body() -> TestResult {
+        // type Point = { x: i64, y: i64 }
+        // fn getx(p: Point) = p.x       （戻り値の型は推論されずに 't0 のまま）
+        // fn main() { let p = { x: 3, y: 4 }; getx(p) }
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "getx",
+          "params": [{"name": "p", "ty": "Record<i64, i64>"}],
+          "return_type": "'t0",
+          "body": 1,
+          "exprs": [
+            {"id": 0, "ty": "Record<i64, i64>", "kind": {"kind": "identifier", "ident": {"name": "p"}}},
+            {"id": 1, "ty": "'t0", "kind": {"kind": "field_access", "target": 0, "field": "x"}}
+          ]
+        },
+        {
+          "name": "main",
+          "return_type": "'t3",
+          "body": 4,
+          "exprs": [
+            {"id": 0, "ty": "Record<i64, i64>", "kind": {"kind": "literal", "value": {
+              "kind": "record", "fields": [
+                {"key": {"name": "x"},
+                 "value": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 3}}}},
+                {"key": {"name": "y"},
+                 "value": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 4}}}}
+              ]}}},
+            {"id": 1, "ty": "(Record<i64, i64>) -> 't3",
+             "kind": {"kind": "identifier", "ident": {"name": "getx"}}},
+            {"id": 2, "ty": "Record<i64, i64>", "kind": {"kind": "identifier", "ident": {"name": "p"}}},
+            {"id": 3, "ty": "'t3", "kind": {"kind": "call", "callee": 1, "args": [2]}},
+            {"id": 4, "ty": "'t3", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "p"}},
+                        "value": 0, "mutable": false}}
+            ], "tail": 3}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let strict = WasmEmitOptions::new().with_strict_codegen(true);
+        let artifact = emit_from_spec(spec, "reml_wasm_unresolved_generic.json", &strict)?;
+        assert!(artifact.wat.contains("(func $getx"), "{}", artifact.wat);
+
+        // 型の分からない戻り値はボックス化した値として返る。
+        let (mut store, instance) = instantiate_pure(&artifact.binary)?;
+        let memory = instance
+            .get_memory(&mut store, MEMORY_EXPORT)
+            .expect("memory をエクスポートすること");
+        let main = instance.get_typed_func::<(), i32>(&mut store, "main")?;
+        let boxed = main.call(&mut store, ())?;
+        assert_eq!(
+            read_u32(&memory, &store, boxed - 4),
+            runtime::TAG_INT as u32
+        );
+        assert_eq!(read_u64(&memory, &store, boxed), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn literal_elements_project_fields_of_bound_records() -> TestResult {
+        // type Point = { x: i64, y: i64 }
+        // fn shift(p: Point, dx: i64) -> Point { { x: p.x + dx, y: p.y } }
+        // fn pair(p: Point) -> [i64] { [p.x, p.x * 2] }
+        // fn main() -> i64 { let q = shift({ x: 1, y: 2 }, 40); q.x + q.y }
+        // fn doubled() -> i64 { let xs = pair({ x: 21, y: 0 }); xs[1] }
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "shift",
+          "params": [{"name": "p", "ty": "Record<i64, i64>"}, {"name": "dx", "ty": "i64"}],
+          "return_type": "Record<i64, i64>",
+          "body": 0,
+          "exprs": [
+            {"id": 0, "ty": "Record<i64, i64>", "kind": {"kind": "literal", "value": {
+              "kind": "record", "fields": [
+                {"key": {"name": "x"},
+                 "value": {"kind": {"kind": "binary", "operator": "Add",
+                   "left": {"kind": {"kind": "field_access",
+                     "target": {"kind": {"kind": "identifier", "name": "p"}},
+                     "field": {"name": "x"}}},
+                   "right": {"kind": {"kind": "identifier", "name": "dx"}}}}},
+                {"key": {"name": "y"},
+                 "value": {"kind": {"kind": "field_access",
+                   "target": {"kind": {"kind": "identifier", "name": "p"}},
+                   "field": {"name": "y"}}}}
+              ]}}}
+          ]
+        },
+        {
+          "name": "pair",
+          "params": [{"name": "p", "ty": "Record<i64, i64>"}],
+          "return_type": "[i64]",
+          "body": 0,
+          "exprs": [
+            {"id": 0, "ty": "[i64]", "kind": {"kind": "literal", "value": {
+              "kind": "array", "elements": [
+                {"kind": {"kind": "field_access",
+                  "target": {"kind": {"kind": "identifier", "name": "p"}},
+                  "field": {"name": "x"}}},
+                {"kind": {"kind": "binary", "operator": "Mul",
+                  "left": {"kind": {"kind": "field_access",
+                    "target": {"kind": {"kind": "identifier", "name": "p"}},
+                    "field": {"name": "x"}}},
+                  "right": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 2}}}}}
+              ]}}}
+          ]
+        },
+        {
+          "name": "main",
+          "return_type": "i64",
+          "body": 9,
+          "exprs": [
+            {"id": 0, "ty": "(Record<i64, i64>, i64) -> Record<i64, i64>",
+             "kind": {"kind": "identifier", "ident": {"name": "shift"}}},
+            {"id": 1, "ty": "Record<i64, i64>", "kind": {"kind": "literal", "value": {
+              "kind": "record", "fields": [
+                {"key": {"name": "x"},
+                 "value": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 1}}}},
+                {"key": {"name": "y"},
+                 "value": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 2}}}}
+              ]}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 40}}},
+            {"id": 3, "ty": "Record<i64, i64>", "kind": {"kind": "call", "callee": 0, "args": [1, 2]}},
+            {"id": 4, "ty": "Record<i64, i64>", "kind": {"kind": "identifier", "ident": {"name": "q"}}},
+            {"id": 5, "ty": "i64", "kind": {"kind": "field_access", "target": 4, "field": "x"}},
+            {"id": 6, "ty": "Record<i64, i64>", "kind": {"kind": "identifier", "ident": {"name": "q"}}},
+            {"id": 7, "ty": "i64", "kind": {"kind": "field_access", "target": 6, "field": "y"}},
+            {"id": 8, "ty": "i64", "kind": {"kind": "binary", "operator": "+", "left": 5, "right": 7}},
+            {"id": 9, "ty": "i64", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "q"}},
+                        "value": 3, "mutable": false}}
+            ], "tail": 8}}
+          ]
+        },
+        {
+          "name": "doubled",
+          "return_type": "i64",
+          "body": 6,
+          "exprs": [
+            {"id": 0, "ty": "(Record<i64, i64>) -> [i64]",
+             "kind": {"kind": "identifier", "ident": {"name": "pair"}}},
+            {"id": 1, "ty": "Record<i64, i64>", "kind": {"kind": "literal", "value": {
+              "kind": "record", "fields": [
+                {"key": {"name": "x"},
+                 "value": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 21}}}},
+                {"key": {"name": "y"},
+                 "value": {"kind": {"kind": "literal", "value": {"kind": "int", "value": 0}}}}
+              ]}}},
+            {"id": 2, "ty": "[i64]", "kind": {"kind": "call", "callee": 0, "args": [1]}},
+            {"id": 3, "ty": "[i64]", "kind": {"kind": "identifier", "ident": {"name": "xs"}}},
+            {"id": 4, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 1}}},
+            {"id": 5, "ty": "i64", "kind": {"kind": "index", "target": 3, "index": 4}},
+            {"id": 6, "ty": "i64", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "xs"}},
+                        "value": 2, "mutable": false}}
+            ], "tail": 5}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let strict = WasmEmitOptions::new().with_strict_codegen(true);
+        let artifact = emit_from_spec(spec, "reml_wasm_literal_fields.json", &strict)?;
+        assert!(artifact.fallbacks.is_empty(), "{:?}", artifact.fallbacks);
+
+        let (mut store, instance) = instantiate_pure(&artifact.binary)?;
+        let memory = instance
+            .get_memory(&mut store, MEMORY_EXPORT)
+            .expect("memory をエクスポートすること");
+        let main = instance.get_typed_func::<(), i64>(&mut store, "main")?;
+        let pages = memory.size(&store);
+        for _ in 0..2000 {
+            assert_eq!(main.call(&mut store, ())?, 43);
+        }
+        assert_eq!(memory.size(&store), pages, "引数のレコードを解放すること");
+
+        // 借用した引数から要素を取り出し、新しい配列へ詰め直す。
+        let doubled = instance.get_typed_func::<(), i64>(&mut store, "doubled")?;
+        for _ in 0..2000 {
+            assert_eq!(doubled.call(&mut store, ())?, 42);
+        }
+        assert_eq!(memory.size(&store), pages, "詰め直した配列を解放すること");
+        Ok(())
+    }
+
+    #[test]
+    fn record_shapes_follow_the_literal_field_order() -> TestResult {
+        // type Point = { y: Float, x: i64 }      （宣言順とリテラルの記述順が異なる）
+        // fn scale(p: Point, k: i64, f: Float) -> Point { { x: p.x * k, y: p.y * f } }
+        //   （フィールド参照は型が付かず、y の型は引数 f から求める）
+        // fn scaled_x() -> i64 { let q = scale({ x: 2, y: 1.5 }, 3, 2.0); q.x }
+        // fn scaled_y() -> Float { let q = scale({ x: 2, y: 1.5 }, 3, 2.0); q.y }
+        let scaled = |name: &str, result: &str, field: &str| {
+            format!(
+                r#"{{
+          "name": "{name}",
+          "return_type": "{result}",
+          "body": 7,
+          "exprs": [
+            {{"id": 0, "ty": "(Record<Float, i64>, i64, Float) -> Record<i64, Float>",
+             "kind": {{"kind": "identifier", "ident": {{"name": "scale"}}}}}},
+            {{"id": 1, "ty": "Record<i64, Float>", "kind": {{"kind": "literal", "value": {{
+              "kind": "record", "fields": [
+                {{"key": {{"name": "x"}},
+                 "value": {{"kind": {{"kind": "literal", "value": {{"kind": "int", "value": 2}}}}}}}},
+                {{"key": {{"name": "y"}},
+                 "value": {{"kind": {{"kind": "literal", "value": {{"kind": "float", "raw": "1.5"}}}}}}}}
+              ]}}}}}},
+            {{"id": 2, "ty": "i64", "kind": {{"kind": "literal", "value": {{"kind": "int", "value": 3}}}}}},
+            {{"id": 3, "ty": "Float", "kind": {{"kind": "literal", "value": {{"kind": "float", "raw": "2.0"}}}}}},
+            {{"id": 4, "ty": "Record<i64, Float>", "kind": {{"kind": "call", "callee": 0, "args": [1, 2, 3]}}}},
+            {{"id": 5, "ty": "Record<i64, Float>", "kind": {{"kind": "identifier", "ident": {{"name": "q"}}}}}},
+            {{"id": 6, "ty": "{result}", "kind": {{"kind": "field_access", "target": 5, "field": "{field}"}}}},
+            {{"id": 7, "ty": "{result}", "kind": {{"kind": "block", "statements": [
+              {{"kind": {{"kind": "let", "pattern": {{"kind": {{"kind": "var", "name": "q"}}}},
+                        "value": 4, "mutable": false}}}}
+            ], "tail": 6}}}}
+          ]
+        }}"#
+            )
+        };
+        let spec = format!(
+            r#"
+    {{
+      "functions": [
+        {{
+          "name": "scale",
+          "params": [
+            {{"name": "p", "ty": "Record<Float, i64>"}},
+            {{"name": "k", "ty": "i64"}},
+            {{"name": "f", "ty": "Float"}}
+          ],
+          "return_type": "Record<i64, Unknown>",
+          "body": 0,
+          "exprs": [
+            {{"id": 0, "ty": "Record<i64, Unknown>", "kind": {{"kind": "literal", "value": {{
+              "kind": "record", "fields": [
+                {{"key": {{"name": "x"}},
+                 "value": {{"kind": {{"kind": "binary", "operator": "Mul",
+                   "left": {{"kind": {{"kind": "field_access",
+                     "target": {{"kind": {{"kind": "identifier", "name": "p"}}}},
+                     "field": {{"name": "x"}}}}}},
+                   "right": {{"kind": {{"kind": "identifier", "name": "k"}}}}}}}}}},
+                {{"key": {{"name": "y"}},
+                 "value": {{"kind": {{"kind": "binary", "operator": "Mul",
+                   "left": {{"kind": {{"kind": "field_access",
+                     "target": {{"kind": {{"kind": "identifier", "name": "p"}}}},
+                     "field": {{"name": "y"}}}}}},
+                   "right": {{"kind": {{"kind": "identifier", "name": "f"}}}}}}}}}}
+              ]}}}}}}
+          ]
+        }},
+        {},
+        {}
+      ]
+    }}
+    "#,
+            scaled("scaled_x", "i64", "x"),
+            scaled("scaled_y", "Float", "y")
+        );
+        let strict = WasmEmitOptions::new().with_strict_codegen(true);
+        let artifact = emit_from_spec(&spec, "reml_wasm_record_shapes.json", &strict)?;
+
+        let (mut store, instance) = instantiate_pure(&artifact.binary)?;
+        let scaled_x = instance.get_typed_func::<(), i64>(&mut store, "scaled_x")?;
+        let scaled_y = instance.get_typed_func::<(), f64>(&mut store, "scaled_y")?;
+        assert_eq!(scaled_x.call(&mut store, ())?, 6);
+        assert_eq!(scaled_y.call(&mut store, ())?, 3.0);
+        Ok(())
+    }
+
+    #[test]
+    fn closures_are_called_through_the_function_table() -> TestResult {
+        // fn apply(f: (i64) -> i64, v: i64) -> i64 = f(v)
+        // fn double(x: i64) -> i64 = x * 2
+        // fn local(n: i64) -> i64 { let k = 10; let add = |x: i64| x + n + k; add(n) + add(1) }
+        // fn make() -> (i64) -> i64 { let t = (40, 0); |x: i64| x + t.0 }
+        // fn made() -> i64 { let f = make(); f(2) }
+        // fn passed() -> i64 = apply(double, 21)
+        let spec = r#"
+    {
+      "functions": [
+        {
+          "name": "apply",
+          "params": [{"name": "f", "ty": "(i64) -> i64"}, {"name": "v", "ty": "i64"}],
+          "return_type": "i64",
+          "body": 2,
+          "exprs": [
+            {"id": 0, "ty": "(i64) -> i64", "kind": {"kind": "identifier", "ident": {"name": "f"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "v"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "call", "callee": 0, "args": [1]}}
+          ]
+        },
+        {
+          "name": "double",
+          "params": [{"name": "x", "ty": "i64"}],
+          "return_type": "i64",
+          "body": 2,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "x"}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 2}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "binary", "operator": "*", "left": 0, "right": 1}}
+          ]
+        },
+        {
+          "name": "local",
+          "params": [{"name": "n", "ty": "i64"}],
+          "return_type": "i64",
+          "body": 15,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 10}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "x"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "n"}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "binary", "operator": "+", "left": 1, "right": 2}},
+            {"id": 4, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "k"}}},
+            {"id": 5, "ty": "i64", "kind": {"kind": "binary", "operator": "+", "left": 3, "right": 4}},
+            {"id": 6, "ty": "(i64) -> i64", "kind": {"kind": "lambda",
+              "params": [{"name": "x", "ty": "i64"}], "body": 5,
+              "captures": [{"name": "n"}, {"name": "k"}]}},
+            {"id": 7, "ty": "(i64) -> i64", "kind": {"kind": "identifier", "ident": {"name": "add"}}},
+            {"id": 8, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "n"}}},
+            {"id": 9, "ty": "i64", "kind": {"kind": "call", "callee": 7, "args": [8]}},
+            {"id": 10, "ty": "(i64) -> i64", "kind": {"kind": "identifier", "ident": {"name": "add"}}},
+            {"id": 11, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 1}}},
+            {"id": 12, "ty": "i64", "kind": {"kind": "call", "callee": 10, "args": [11]}},
+            {"id": 13, "ty": "i64", "kind": {"kind": "binary", "operator": "+", "left": 9, "right": 12}},
+            {"id": 15, "ty": "i64", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "k"}},
+                        "value": 0, "mutable": false}},
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "add"}},
+                        "value": 6, "mutable": false}}
+            ], "tail": 13}}
+          ]
+        },
+        {
+          "name": "make",
+          "return_type": "(i64) -> i64",
+          "body": 6,
+          "exprs": [
+            {"id": 0, "ty": "Tuple<i64, i64>", "kind": {"kind": "literal", "value": {
+              "kind": "tuple", "elements": [
+                {"kind": {"kind": "literal", "value": {"kind": "int", "value": 40}}},
+                {"kind": {"kind": "literal", "value": {"kind": "int", "value": 0}}}
+              ]}}},
+            {"id": 1, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "x"}}},
+            {"id": 2, "ty": "Tuple<i64, i64>", "kind": {"kind": "identifier", "ident": {"name": "t"}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "field_access", "target": 2, "field": "0"}},
+            {"id": 4, "ty": "i64", "kind": {"kind": "binary", "operator": "+", "left": 1, "right": 3}},
+            {"id": 5, "ty": "(i64) -> i64", "kind": {"kind": "lambda",
+              "params": [{"name": "x", "ty": "i64"}], "body": 4, "captures": [{"name": "t"}]}},
+            {"id": 6, "ty": "(i64) -> i64", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "t"}},
+                        "value": 0, "mutable": false}}
+            ], "tail": 5}}
+          ]
+        },
+        {
+          "name": "made",
+          "return_type": "i64",
+          "body": 5,
+          "exprs": [
+            {"id": 0, "ty": "() -> (i64) -> i64", "kind": {"kind": "identifier", "ident": {"name": "make"}}},
+            {"id": 1, "ty": "(i64) -> i64", "kind": {"kind": "call", "callee": 0, "args": []}},
+            {"id": 2, "ty": "(i64) -> i64", "kind": {"kind": "identifier", "ident": {"name": "f"}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 2}}},
+            {"id": 4, "ty": "i64", "kind": {"kind": "call", "callee": 2, "args": [3]}},
+            {"id": 5, "ty": "i64", "kind": {"kind": "block", "statements": [
+              {"kind": {"kind": "let", "pattern": {"kind": {"kind": "var", "name": "f"}},
+                        "value": 1, "mutable": false}}
+            ], "tail": 4}}
+          ]
+        },
+        {
+          "name": "passed",
+          "return_type": "i64",
+          "body": 3,
+          "exprs": [
+            {"id": 0, "ty": "((i64) -> i64, i64) -> i64",
+             "kind": {"kind": "identifier", "ident": {"name": "apply"}}},
+            {"id": 1, "ty": "(i64) -> i64", "kind": {"kind": "identifier", "ident": {"name": "double"}}},
+            {"id": 2, "ty": "i64", "kind": {"kind": "literal", "value": {"kind": "int", "value": 21}}},
+            {"id": 3, "ty": "i64", "kind": {"kind": "call", "callee": 0, "args": [1, 2]}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let artifact = emit_from_spec(spec, "reml_wasm_closures.json", &WasmEmitOptions::new())?;
+        assert!(artifact.fallbacks.is_empty(), "{:?}", artifact.fallbacks);
+        assert!(
+            artifact.wat.contains("(table 3 funcref)"),
+            "{}",
+            artifact.wat
+        );
+        assert!(artifact.wat.contains("call_indirect"), "{}", artifact.wat);
+
+        let (mut store, instance) = instantiate_pure(&artifact.binary)?;
+        let memory = instance
+            .get_memory(&mut store, MEMORY_EXPORT)
+            .expect("memory をエクスポートすること");
+        // 持ち上げた関数はエクスポートしない。
+        assert!(instance
+            .get_func(&mut store, "reml_closure_local_6")
+            .is_none());
+
+        let local = instance.get_typed_func::<i64, i64>(&mut store, "local")?;
+        let made = instance.get_typed_func::<(), i64>(&mut store, "made")?;
+        let passed = instance.get_typed_func::<(), i64>(&mut store, "passed")?;
+        let pages = memory.size(&store);
+        for _ in 0..2000 {
+            assert_eq!(local.call(&mut store, 5)?, 36);
+            assert_eq!(made.call(&mut store, ())?, 42);
+            assert_eq!(passed.call(&mut store, ())?, 42);
+        }
+        assert_eq!(memory.size(&store), pages, "クロージャと環境を解放すること");
+        Ok(())
+    }
+
+    #[test]
+    fn plugin_abi_exports_adapters_for_string_entrypoints() -> TestResult {
+        // @export fn echo(input: Str) -> Str = input
+        // @export fn banner(input: Str) -> Str = "reml"
+        // fn alloc(n: i64) -> i64 = n     （ABI の予約名と衝突する）
+        let spec = r#"
+    {
+      "module": "plugin",
+      "functions": [
+        {
+          "name": "echo",
+          "attributes": ["export"],
+          "params": [{"name": "input", "ty": "Str"}],
+          "return_type": "Str",
+          "body": 0,
+          "exprs": [
+            {"id": 0, "ty": "Str", "kind": {"kind": "identifier", "ident": {"name": "input"}}}
+          ]
+        },
+        {
+          "name": "banner",
+          "attributes": ["export"],
+          "params": [{"name": "input", "ty": "Str"}],
+          "return_type": "Str",
+          "body": 0,
+          "exprs": [
+            {"id": 0, "ty": "Str", "kind": {"kind": "literal",
+             "value": {"kind": "string", "value": "reml"}}}
+          ]
+        },
+        {
+          "name": "alloc",
+          "params": [{"name": "n", "ty": "i64"}],
+          "return_type": "i64",
+          "body": 0,
+          "exprs": [
+            {"id": 0, "ty": "i64", "kind": {"kind": "identifier", "ident": {"name": "n"}}}
+          ]
+        }
+      ]
+    }
+    "#;
+        let options = WasmEmitOptions::new().with_plugin_abi(true);
+        let artifact = emit_from_spec(spec, "reml_wasm_plugin_abi.json", &options)?;
+        assert_eq!(artifact.fallbacks.len(), 1);
+        assert_eq!(
+            artifact.fallbacks[0].code,
+            "wasm.fallback.plugin_abi_export"
+        );
+        assert!(artifact
+            .wat
+            .contains("(export \"echo\") (param i32 i32) (result i64)"));
+
+        let (mut store, instance) = instantiate_pure(&artifact.binary)?;
+        let version = instance.get_typed_func::<(), i32>(&mut store, "reml_abi_version")?;
+        assert_eq!(
+            version.call(&mut store, ())?,
+            plugin_abi::PLUGIN_ABI_VERSION
+        );
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let dealloc = instance.get_typed_func::<(i32, i32), ()>(&mut store, "dealloc")?;
+        let memory = instance
+            .get_memory(&mut store, MEMORY_EXPORT)
+            .expect("memory をエクスポートすること");
+
+        for (entry, input, expected) in [
+            ("echo", &b"hello plugin"[..], &b"hello plugin"[..]),
+            ("banner", &b"ignored"[..], &b"reml"[..]),
+        ] {
+            let call = instance.get_typed_func::<(i32, i32), i64>(&mut store, entry)?;
+            let ptr = alloc.call(&mut store, input.len() as i32)?;
+            memory.write(&mut store, ptr as usize, input)?;
+            let packed = call.call(&mut store, (ptr, input.len() as i32))?;
+            let (out_ptr, out_len) = ((packed >> 32) as u32, packed as u32);
+            assert!(out_ptr >= runtime::HOST_SCRATCH_BYTES);
+            let start = out_ptr as usize;
+            assert_eq!(
+                &memory.data(&store)[start..start + out_len as usize],
+                expected
+            );
+            dealloc.call(&mut store, (out_ptr as i32, out_len as i32))?;
+        }
+
+        // 返却したバッファは次の確保で再利用される。
+        let first = alloc.call(&mut store, 8)?;
+        dealloc.call(&mut store, (first, 8))?;
+        assert_eq!(alloc.call(&mut store, 8)?, first);
+        Ok(())
+    }
+}
@@ -0,0 +1,2407 @@
+//! MIR 関数から wasm 関数へのローアリング。
+//!
+//! 値型（整数・真偽値・浮動小数点）はそのまま wasm の値型へ、それ以外の値は
+//! 線形メモリ上のオブジェクトを指す `i32` ポインタへ写像する。
+//! タプル・レコード・配列は `runtime` と同じレイアウトでヒープに確保し、要素はボックス化して持つ。
+//! ADT は `{tag, payload}` として確保し、`match` はタグと要素を順に照合するブロックの連なりにする。
+//! ラムダはクロージャ変換で持ち上げた関数を関数テーブルへ並べ、`{env, code}` のクロージャから
+//! `call_indirect` で呼び出す。`code` はテーブル上の位置、`env` はキャプチャのレコード。
+//! ヒープ上の値は参照カウントで管理し、`let` 束縛や一時値が所有する参照をスコープの終わりで
+//! `dec_ref` する。関数は戻り値の参照を 1 つ所有した状態で返す。
+//! 対応していない式は `unreachable` に置き換え、縮退箇所として記録する。
+
+use std::collections::HashMap;
+
+use reml_llvm_backend::codegen::{
+    MirExpr, MirExprId, MirExprKind, MirLambdaCapture, MirMatchArm, MirPattern, MirPatternKind,
+    MirStmt, MirStmtKind,
+};
+use reml_llvm_backend::{
+    convert_closures, identifier_name, parse_function_type_token, reml_type_from_token,
+    CodegenFallback, MirFunction, RemlType, CLOSURE_ENV_PARAM,
+};
+use serde_json::Value;
+
+use crate::module::{FuncType, Function, Import, Instr, MemArg, ValType, WasmModule};
+use crate::plugin_abi;
+use crate::runtime::{
+    initial_pages, runtime_functions, runtime_globals, RuntimeFn, RuntimeIndices, StaticData,
+    TAG_ADT, TAG_ARRAY, TAG_CLOSURE, TAG_RECORD, TAG_TUPLE,
+};
+
+/// FFI extern を取り込むモジュール名。
+pub const IMPORT_MODULE: &str = "env";
+
+// 縮退箇所の診断コード。
+const FALLBACK_EXPR_MISSING: &str = "wasm.fallback.expr_missing";
+const FALLBACK_EXPR_UNSUPPORTED: &str = "wasm.fallback.expr_unsupported";
+const FALLBACK_BODY_MISSING: &str = "wasm.fallback.body_missing";
+const FALLBACK_BINARY_OPERATOR: &str = "wasm.fallback.binary_operator";
+const FALLBACK_LITERAL: &str = "wasm.fallback.literal";
+const FALLBACK_LITERAL_ELEMENT: &str = "wasm.fallback.literal_element";
+const FALLBACK_IDENTIFIER: &str = "wasm.fallback.identifier";
+const FALLBACK_CALL: &str = "wasm.fallback.call";
+const FALLBACK_FIELD_ACCESS: &str = "wasm.fallback.field_access";
+const FALLBACK_INDEX: &str = "wasm.fallback.index";
+const FALLBACK_PATTERN: &str = "wasm.fallback.pattern";
+const FALLBACK_MUTABLE_CAPTURE: &str = "wasm.fallback.mutable_capture";
+const FALLBACK_ASSIGN_TARGET: &str = "wasm.fallback.assign_target";
+const FALLBACK_DEFER_STATEMENT: &str = "wasm.fallback.defer_statement";
+const FALLBACK_TYPE_MISMATCH: &str = "wasm.fallback.type_mismatch";
+const FALLBACK_VARIADIC_FFI: &str = "wasm.fallback.variadic_ffi";
+const FALLBACK_PLUGIN_ABI_EXPORT: &str = "wasm.fallback.plugin_abi_export";
+
+/// コンテナ（タプル/レコード/配列）のペイロード内での要素配列のオフセット。
+const ITEMS_OFFSET: u32 = 8;
+/// ADT `{tag i32, payload i32}` のペイロードのオフセット。
+const PAYLOAD_OFFSET: u32 = 4;
+
+/// MIR 関数列を 1 つの wasm モジュールへまとめる。
+///
+/// 関数インデックスは FFI の取り込み関数、ランタイム関数、MIR 関数の順に並ぶ。
+/// MIR 関数はクロージャ変換後の並びで、各関数の直後にそのラムダを持ち上げた関数が続く。
+/// `plugin_abi` ならその後ろにプラグイン ABI の関数とエントリポイントのアダプタを置く。
+pub(crate) fn lower_module(
+    name: &str,
+    functions: &[MirFunction],
+    plugin_abi: bool,
+) -> (WasmModule, Vec<CodegenFallback>) {
+    let functions: Vec<MirFunction> = functions
+        .iter()
+        .flat_map(|function| {
+            let (converted, lifted) = convert_closures(function);
+            std::iter::once(converted).chain(lifted)
+        })
+        .collect();
+    let functions = functions.as_slice();
+    let mut fallbacks = Vec::new();
+    let defined: Vec<String> = functions
+        .iter()
+        .map(|function| export_name(&function.name))
+        .collect();
+
+    let mut imports: Vec<Import> = Vec::new();
+    let mut import_signatures: Vec<(Vec<Repr>, Repr)> = Vec::new();
+    for function in functions {
+        for ffi in &function.ffi_calls {
+            let import_name = export_name(&ffi.name);
+            if defined.contains(&import_name)
+                || imports.iter().any(|import| import.name == import_name)
+            {
+                continue;
+            }
+            if ffi.variadic {
+                fallbacks.push(CodegenFallback {
+                    code: FALLBACK_VARIADIC_FFI.into(),
+                    function: function.name.clone(),
+                    expr_id: None,
+                    span: None,
+                    detail: format!("extern={}", ffi.name),
+                });
+                continue;
+            }
+            let params: Vec<Repr> = ffi.args.iter().map(Repr::from_reml).collect();
+            let result = ffi.ret.as_ref().map_or(Repr::Unit, Repr::from_reml);
+            imports.push(Import {
+                module: IMPORT_MODULE.into(),
+                name: import_name,
+                ty: signature_type(&params, result),
+            });
+            import_signatures.push((params, result));
+        }
+    }
+
+    let indices = RuntimeIndices::new(imports.len() as u32);
+    let mut callees: HashMap<String, Callee> = HashMap::new();
+    for (index, (import, (params, result))) in imports.iter().zip(import_signatures).enumerate() {
+        callees.insert(
+            import.name.clone(),
+            Callee {
+                index: index as u32,
+                params,
+                result,
+                // ホストが返す値の参照は呼び出し側が所有しない。
+                owned_result: false,
+                slot: None,
+            },
+        );
+    }
+    let mut table = Vec::new();
+    for (offset, function) in functions.iter().enumerate() {
+        let (params, result) = function_signature(function);
+        let index = indices.end() + offset as u32;
+        let slot = function.closure_env.is_some().then(|| {
+            table.push(index);
+            table.len() as u32 - 1
+        });
+        callees.insert(
+            export_name(&function.name),
+            Callee {
+                index,
+                params,
+                result,
+                owned_result: true,
+                slot,
+            },
+        );
+    }
+
+    let mut statics = StaticData::new();
+    let mut tags = ConstructorTags::new(functions);
+    let shapes = RecordShapes::new(functions);
+    let mut lowered = Vec::with_capacity(functions.len());
+    for function in functions {
+        let mut lowering = FunctionLowering::new(
+            function,
+            &callees,
+            &shapes,
+            indices,
+            &mut statics,
+            &mut tags,
+        );
+        lowering.lower_body();
+        fallbacks.append(&mut lowering.fallbacks);
+        let mut generated = Function::new(function.name.clone(), function_type(function));
+        // 持ち上げた関数はクロージャ経由でだけ呼ばれる。
+        if function.closure_env.is_none() {
+            generated = generated.with_export(export_name(&function.name));
+        }
+        generated.locals = lowering.locals;
+        generated.body = lowering.body;
+        lowered.push(generated);
+    }
+
+    let mut abi_functions = Vec::new();
+    if plugin_abi {
+        abi_functions = plugin_abi::abi_functions(indices);
+        for (offset, (function, generated)) in functions.iter().zip(&mut lowered).enumerate() {
+            let reserved = matches!(
+                generated.export.as_deref(),
+                Some(export) if plugin_abi::RESERVED_EXPORTS.contains(&export)
+            );
+            if reserved {
+                fallbacks.push(CodegenFallback {
+                    code: FALLBACK_PLUGIN_ABI_EXPORT.into(),
+                    function: function.name.clone(),
+                    expr_id: None,
+                    span: None,
+                    detail: format!("reserved={}", export_name(&function.name)),
+                });
+                generated.export = None;
+            } else if plugin_abi::is_plugin_entry(function) {
+                // 元の関数はアダプタ経由でだけ呼ばれるようにエクスポート名を譲る。
+                if let Some(export) = generated.export.take() {
+                    let target = indices.end() + offset as u32;
+                    abi_functions.push(plugin_abi::entry_adapter(indices, target, &export));
+                }
+            }
+        }
+    }
+
+    let heap_base = statics.heap_base();
+    let mut module = WasmModule::new(name);
+    module.imports = imports;
+    module.functions = runtime_functions(indices);
+    module.functions.extend(lowered);
+    module.functions.extend(abi_functions);
+    module.globals = runtime_globals(heap_base);
+    module.data = statics.into_segments();
+    module.table = table;
+    module.memory_pages = initial_pages(heap_base);
+    (module, fallbacks)
+}
+
+/// MIR の関数名からシンボル接頭辞 `@` を除いた名前。
+pub(crate) fn export_name(name: &str) -> String {
+    name.trim_start_matches('@').to_string()
+}
+
+/// 引数と戻り値の表現。型トークンがあればそれを優先する。
+fn function_signature(function: &MirFunction) -> (Vec<Repr>, Repr) {
+    let params = (0..function.params.len())
+        .map(|index| match function.param_type_tokens.get(index) {
+            Some(token) if !token.trim().is_empty() => Repr::of(token),
+            _ => Repr::from_reml(&function.params[index]),
+        })
+        .collect();
+    let result = match function.return_type_token.as_deref() {
+        Some(token) if !token.trim().is_empty() => Repr::of(token),
+        _ => function.ret.as_ref().map_or(Repr::Unit, Repr::from_reml),
+    };
+    (params, result)
+}
+
+fn function_type(function: &MirFunction) -> FuncType {
+    let (params, result) = function_signature(function);
+    signature_type(&params, result)
+}
+
+/// Unit の引数は呼び出し規約を揃えるため `i32` の 0 で渡す。
+fn signature_type(params: &[Repr], result: Repr) -> FuncType {
+    FuncType::new(
+        params.iter().map(|repr| repr.param_type()).collect(),
+        result.val_type().into_iter().collect(),
+    )
+}
+
+/// 数値・真偽値として wasm の値型へ直接載る型トークンなら、その値型を返す。
+fn scalar_type(token: &str) -> Option<ValType> {
+    match token.trim() {
+        "Bool" | "bool" | "Char" | "char" | "i8" | "i16" | "i32" | "u8" | "u16" | "u32"
+        | "Int32" => Some(ValType::I32),
+        "Int" | "int" | "i64" | "u64" | "isize" | "usize" | "Int64" => Some(ValType::I64),
+        "Float" | "f32" | "f64" | "double" => Some(ValType::F64),
+        _ => None,
+    }
+}
+
+/// 型変数や推論できなかった型を除いた、表現を決められる型トークンか。
+fn is_known_token(token: &str) -> bool {
+    let trimmed = token.trim();
+    !(trimmed.is_empty() || trimmed.starts_with('\'') || trimmed == "Unknown" || trimmed == "_")
+}
+
+/// 値の表現。wasm の値型と、ボックス化・参照カウントの扱いを決める。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Repr {
+    /// 値を持たない。
+    Unit,
+    Bool,
+    Char,
+    /// `i64` 以外の整数。ボックス化では `i64` に広げる。
+    Int32,
+    Int,
+    Float,
+    /// 参照カウント付きヒープオブジェクト（または静的オブジェクト）へのポインタ。
+    Heap,
+}
+
+impl Repr {
+    fn of(token: &str) -> Self {
+        match token.trim() {
+            "()" | "Unit" | "unit" | "void" | "Never" | "!" => Repr::Unit,
+            "Bool" | "bool" => Repr::Bool,
+            "Char" | "char" => Repr::Char,
+            trimmed => match scalar_type(trimmed) {
+                Some(ValType::I32) => Repr::Int32,
+                Some(ValType::I64) => Repr::Int,
+                Some(ValType::F64) => Repr::Float,
+                None => Repr::Heap,
+            },
+        }
+    }
+
+    /// 型トークンが分かる場合だけ表現を返す。
+    fn known(token: &str) -> Option<Self> {
+        is_known_token(token).then(|| Repr::of(token))
+    }
+
+    fn from_reml(ty: &RemlType) -> Self {
+        match ty {
+            RemlType::Bool => Repr::Bool,
+            RemlType::I32 => Repr::Int32,
+            RemlType::I64 => Repr::Int,
+            RemlType::F64 => Repr::Float,
+            RemlType::Unit => Repr::Unit,
+            _ => Repr::Heap,
+        }
+    }
+
+    fn val_type(self) -> Option<ValType> {
+        match self {
+            Repr::Unit => None,
+            Repr::Int => Some(ValType::I64),
+            Repr::Float => Some(ValType::F64),
+            Repr::Bool | Repr::Char | Repr::Int32 | Repr::Heap => Some(ValType::I32),
+        }
+    }
+
+    fn param_type(self) -> ValType {
+        self.val_type().unwrap_or(ValType::I32)
+    }
+
+    fn is_heap(self) -> bool {
+        self == Repr::Heap
+    }
+}
+
+/// 型トークン `Head<A, B>` の型引数。ネストした `<>`/`()`/`[]` の中のカンマでは区切らない。
+fn type_arguments<'t>(token: &'t str, head: &str) -> Option<Vec<&'t str>> {
+    let inner = token
+        .trim()
+        .strip_prefix(head)?
+        .strip_prefix('<')?
+        .strip_suffix('>')?;
+    let mut arguments = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0usize;
+    for (index, ch) in inner.char_indices() {
+        match ch {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                arguments.push(inner[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if !inner[start..].trim().is_empty() {
+        arguments.push(inner[start..].trim());
+    }
+    Some(arguments)
+}
+
+/// 配列型トークン（`[T]`/`[T; N]`）の要素型。
+fn array_element_token(token: &str) -> Option<&str> {
+    let inner = token.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let element = match inner.rsplit_once(';') {
+        Some((element, _)) => element,
+        None => inner,
+    };
+    Some(element.trim())
+}
+
+/// ヒープ上のコンテナのレイアウト。要素の表現はボックス化前の型を表す。
+#[derive(Clone, Debug, PartialEq)]
+enum Layout {
+    Tuple(Vec<Repr>),
+    /// フィールド名の昇順（native の `reml_record_from` と同じ並び）。
+    Record(Vec<(String, Repr)>),
+    Array(Repr),
+    /// 関数値。環境を除いた引数と戻り値の表現。
+    Closure(Vec<Repr>, Repr),
+}
+
+impl Layout {
+    /// フィールドの要素番号と表現。タプルは `0`, `1`, ... をフィールド名とする。
+    fn field(&self, field: &str) -> Option<(usize, Repr)> {
+        match self {
+            Layout::Tuple(elements) => field
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| elements.get(index).map(|repr| (index, *repr))),
+            Layout::Record(fields) => fields
+                .iter()
+                .position(|(name, _)| name == field)
+                .map(|index| (index, fields[index].1)),
+            Layout::Array(_) | Layout::Closure(..) => None,
+        }
+    }
+
+    fn from_token(token: &str) -> Option<Self> {
+        if let Some((params, result)) = parse_function_type_token(token) {
+            // 持ち上げた関数のシグネチャと同じ規則で型トークンを写す。
+            let repr = |token: &str| Repr::from_reml(&reml_type_from_token(token));
+            return Some(Layout::Closure(
+                params.iter().map(|param| repr(param)).collect(),
+                repr(&result),
+            ));
+        }
+        if let Some(arguments) = type_arguments(token, "Tuple") {
+            return arguments
+                .iter()
+                .map(|argument| Repr::known(argument))
+                .collect::<Option<Vec<_>>>()
+                .map(Layout::Tuple);
+        }
+        array_element_token(token)
+            .and_then(Repr::known)
+            .map(Layout::Array)
+    }
+}
+
+/// スカラとして扱えるリテラル。
+enum ScalarLiteral {
+    Unit,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Char(u32),
+    String(String),
+}
+
+/// 式 ID のリテラル要約を JSON として読む。`{"value": {"kind": ...}}` の包みは外す。
+fn parse_literal_json(summary: &str) -> Option<Value> {
+    let trimmed = summary.trim();
+    let literal = match trimmed {
+        "unit" => serde_json::json!({"kind": "unit"}),
+        "true" | "false" => serde_json::json!({"kind": "bool", "value": trimmed == "true"}),
+        _ => match trimmed.parse::<i64>() {
+            Ok(value) => serde_json::json!({"kind": "int", "value": value}),
+            Err(_) => serde_json::from_str(trimmed).ok()?,
+        },
+    };
+    Some(unwrap_literal(&literal)?.clone())
+}
+
+fn unwrap_literal(value: &Value) -> Option<&Value> {
+    match value.get("kind") {
+        Some(Value::String(_)) => Some(value),
+        _ => value
+            .get("value")
+            .filter(|inner| inner.get("kind").is_some()),
+    }
+}
+
+fn scalar_literal(literal: &Value) -> Option<ScalarLiteral> {
+    let field = |key: &str| literal.get(key);
+    match literal.get("kind")?.as_str()? {
+        "unit" => Some(ScalarLiteral::Unit),
+        "bool" => field("value")?.as_bool().map(ScalarLiteral::Bool),
+        "int" => field("value")?.as_i64().map(ScalarLiteral::Int),
+        "float" => field("raw")?
+            .as_str()?
+            .replace('_', "")
+            .parse::<f64>()
+            .ok()
+            .map(ScalarLiteral::Float),
+        "char" => field("value")?
+            .as_str()?
+            .chars()
+            .next()
+            .map(|ch| ScalarLiteral::Char(ch as u32)),
+        "string" => field("value")?
+            .as_str()
+            .map(|text| ScalarLiteral::String(text.to_string())),
+        _ => None,
+    }
+}
+
+/// リテラル中の要素式。`{"kind": {"kind": "identifier", ...}}` の包みを外す。
+fn unwrap_element(element: &Value) -> &Value {
+    match element.get("kind") {
+        Some(inner @ Value::Object(_)) => inner,
+        _ => element,
+    }
+}
+
+fn element_identifier(element: &Value) -> Option<&str> {
+    element
+        .get("name")
+        .or_else(|| element.get("ident").and_then(|ident| ident.get("name")))
+        .and_then(Value::as_str)
+}
+
+/// リテラルの要素に現れる演算子名（`Add` 等）を記号へ揃える。
+fn operator_symbol(operator: &str) -> &str {
+    match operator {
+        "Add" => "+",
+        "Sub" => "-",
+        "Mul" => "*",
+        "Div" => "/",
+        "Mod" => "%",
+        "Eq" => "==",
+        "Ne" => "!=",
+        "Lt" => "<",
+        "Le" => "<=",
+        "Gt" => ">",
+        "Ge" => ">=",
+        "And" => "&&",
+        "Or" => "||",
+        other => other,
+    }
+}
+
+/// レコードリテラルのフィールドをフィールド名の昇順で返す。
+fn record_fields(literal: &Value) -> Vec<(String, &Value)> {
+    let mut fields = source_record_fields(literal);
+    fields.sort_by(|lhs, rhs| lhs.0.cmp(&rhs.0));
+    fields
+}
+
+/// レコードリテラルのフィールドを書かれた順に返す。
+fn source_record_fields(literal: &Value) -> Vec<(String, &Value)> {
+    literal
+        .get("fields")
+        .and_then(Value::as_array)
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|field| {
+                    let key = match field.get("key")? {
+                        Value::String(name) => name.clone(),
+                        key => key.get("name")?.as_str()?.to_string(),
+                    };
+                    Some((key, field.get("value")?))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 要素式の表現のうち、式だけから分かるもの。スカラリテラル、関数の引数、
+/// それらを被演算子に持つ二項演算を辿る。
+fn inline_element_repr(element: &Value, function: &MirFunction) -> Option<Repr> {
+    let element = unwrap_element(element);
+    match element.get("kind").and_then(Value::as_str)? {
+        "literal" => {
+            let repr = match scalar_literal(element.get("value").and_then(unwrap_literal)?)? {
+                ScalarLiteral::Unit => Repr::Unit,
+                ScalarLiteral::Bool(_) => Repr::Bool,
+                ScalarLiteral::Int(_) => Repr::Int,
+                ScalarLiteral::Float(_) => Repr::Float,
+                ScalarLiteral::Char(_) => Repr::Char,
+                ScalarLiteral::String(_) => Repr::Heap,
+            };
+            Some(repr)
+        }
+        "identifier" => {
+            let name = element_identifier(element)?;
+            let index = function
+                .param_names
+                .iter()
+                .position(|param| param == name)?;
+            Repr::known(function.param_type_tokens.get(index)?)
+        }
+        "binary" => {
+            let operator = element.get("operator").and_then(Value::as_str)?;
+            match operator_symbol(operator) {
+                "+" | "-" | "*" | "/" | "%" => {
+                    let operand = |side: &str| inline_element_repr(element.get(side)?, function);
+                    operand("left").or_else(|| operand("right"))
+                }
+                _ => Some(Repr::Bool),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// モジュール内のレコードリテラルの形（フィールド名と、要素式かリテラルの型トークンから
+/// 分かる表現を書かれた順に）。
+///
+/// 型トークン `Record<T, ...>` はフィールド名を持たないため、フィールド数が一致する
+/// リテラルの形が 1 種類に決まるときだけ、それを型のレイアウトとして使う。
+struct RecordShapes {
+    shapes: Vec<Vec<(String, Option<Repr>)>>,
+}
+
+impl RecordShapes {
+    fn new(functions: &[MirFunction]) -> Self {
+        let mut shapes = Vec::new();
+        let literals = functions
+            .iter()
+            .flat_map(|function| function.exprs.iter().map(move |expr| (function, expr)))
+            .filter_map(|(function, expr)| match &expr.kind {
+                MirExprKind::Literal { summary } => {
+                    parse_literal_json(summary).map(|literal| (function, literal, expr.ty.as_str()))
+                }
+                _ => None,
+            })
+            .filter(|(_, literal, _)| {
+                literal.get("kind").and_then(Value::as_str) == Some("record")
+            });
+        for (function, literal, token) in literals {
+            // リテラル自身の型トークンはフィールドをリテラルの記述順に並べる。
+            let arguments = type_arguments(token, "Record").unwrap_or_default();
+            let shape: Vec<(String, Option<Repr>)> = source_record_fields(&literal)
+                .into_iter()
+                .enumerate()
+                .map(|(index, (key, value))| {
+                    let repr = inline_element_repr(value, function).or_else(|| {
+                        arguments
+                            .get(index)
+                            .and_then(|argument| Repr::known(argument))
+                    });
+                    (key, repr)
+                })
+                .collect();
+            if !shapes.contains(&shape) {
+                shapes.push(shape);
+            }
+        }
+        Self { shapes }
+    }
+
+    fn layout(&self, token: &str) -> Option<Layout> {
+        let arguments = type_arguments(token, "Record")?;
+        let mut candidates = self
+            .shapes
+            .iter()
+            .filter(|shape| shape.len() == arguments.len());
+        let shape = candidates.next()?;
+        let keys = |shape: &Vec<(String, Option<Repr>)>| {
+            let mut keys: Vec<String> = shape.iter().map(|(key, _)| key.clone()).collect();
+            keys.sort_unstable();
+            keys
+        };
+        if candidates.any(|other| keys(other) != keys(shape)) {
+            return None;
+        }
+        let mut fields: Vec<(String, Repr)> = shape
+            .iter()
+            .zip(&arguments)
+            .map(|((key, literal), argument)| {
+                let repr = literal
+                    .or_else(|| Repr::known(argument))
+                    .unwrap_or(Repr::Heap);
+                (key.clone(), repr)
+            })
+            .collect();
+        fields.sort_by(|lhs, rhs| lhs.0.cmp(&rhs.0));
+        Some(Layout::Record(fields))
+    }
+}
+
+fn literal_elements(literal: &Value) -> &[Value] {
+    literal
+        .get("elements")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// ADT コンストラクタのタグ値。
+///
+/// match の分岐計画が宣言順のコンストラクタ一覧を持つ型はその番号を使い、
+/// それ以外のコンストラクタにはモジュール内で重複しない番号を出現順に振る。
+struct ConstructorTags {
+    tags: HashMap<String, i32>,
+    next: i32,
+}
+
+impl ConstructorTags {
+    fn new(functions: &[MirFunction]) -> Self {
+        let mut tags = HashMap::new();
+        let plans = functions
+            .iter()
+            .flat_map(|function| &function.exprs)
+            .filter_map(|expr| match &expr.kind {
+                MirExprKind::Match {
+                    lowering: Some(plan),
+                    ..
+                } => Some(plan),
+                _ => None,
+            });
+        for plan in plans {
+            for (index, name) in plan.constructors.iter().enumerate() {
+                tags.entry(name.clone()).or_insert(index as i32);
+            }
+        }
+        let next = tags.values().copied().max().map_or(0, |max| max + 1);
+        Self { tags, next }
+    }
+
+    fn tag(&mut self, name: &str) -> i32 {
+        if let Some(tag) = self.tags.get(name) {
+            return *tag;
+        }
+        let tag = self.next;
+        self.next += 1;
+        self.tags.insert(name.to_string(), tag);
+        tag
+    }
+}
+
+/// 大文字で始まる名前はコンストラクタとして扱う。
+fn is_constructor_name(name: &str) -> bool {
+    name.chars().next().is_some_and(char::is_uppercase)
+}
+
+/// コンテナへ格納する要素。リテラル中の要素式か、MIR の式。
+#[derive(Clone, Copy)]
+enum Element<'v> {
+    Inline(&'v Value),
+    Expr(MirExprId),
+}
+
+/// パターン照合の対象。`local` は値を保持するローカル（Unit なら `None`）。
+#[derive(Clone, Debug)]
+struct Subject {
+    local: Option<u32>,
+    repr: Repr,
+    layout: Option<Layout>,
+}
+
+/// 式を評価した結果、スタックに何が積まれたか。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Emitted {
+    /// 値を 1 つ積んだ（`Repr::Unit` は何も積んでいない）。ヒープ値なら参照は借用している。
+    Value(Repr),
+    /// ヒープ値を積み、その参照を 1 つ所有している。
+    Owned,
+    /// `return`/`unreachable` で制御が戻らない。スタックは任意の型として扱える。
+    Diverged,
+}
+
+impl Emitted {
+    fn repr(self) -> Option<Repr> {
+        match self {
+            Emitted::Value(repr) => Some(repr),
+            Emitted::Owned => Some(Repr::Heap),
+            Emitted::Diverged => None,
+        }
+    }
+}
+
+/// 呼び出し先の関数。
+#[derive(Clone, Debug)]
+struct Callee {
+    index: u32,
+    params: Vec<Repr>,
+    result: Repr,
+    /// 戻り値のヒープ参照を呼び出し側が所有するか。
+    owned_result: bool,
+    /// 持ち上げたラムダなら関数テーブル上の位置。
+    slot: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+struct Binding {
+    /// Unit の変数はローカルを割り当てない。
+    index: Option<u32>,
+    repr: Repr,
+    /// スコープの終わりで `dec_ref` する参照を所有しているか。
+    owned: bool,
+    layout: Option<Layout>,
+}
+
+#[derive(Default)]
+struct Scope {
+    bindings: HashMap<String, Binding>,
+    /// スコープの終わりで解放するローカル。
+    owned: Vec<u32>,
+}
+
+struct FunctionLowering<'a> {
+    function: &'a MirFunction,
+    exprs: HashMap<MirExprId, &'a MirExpr>,
+    callees: &'a HashMap<String, Callee>,
+    shapes: &'a RecordShapes,
+    runtime: RuntimeIndices,
+    statics: &'a mut StaticData,
+    tags: &'a mut ConstructorTags,
+    result: Repr,
+    param_count: u32,
+    locals: Vec<ValType>,
+    /// 参照の複製（`inc_ref`）に使う作業用ローカル。
+    scratch: Option<u32>,
+    scopes: Vec<Scope>,
+    body: Vec<Instr>,
+    fallbacks: Vec<CodegenFallback>,
+}
+
+impl<'a> FunctionLowering<'a> {
+    fn new(
+        function: &'a MirFunction,
+        callees: &'a HashMap<String, Callee>,
+        shapes: &'a RecordShapes,
+        runtime: RuntimeIndices,
+        statics: &'a mut StaticData,
+        tags: &'a mut ConstructorTags,
+    ) -> Self {
+        let (params, result) = function_signature(function);
+        let mut scope = Scope::default();
+        for (index, name) in function.param_names.iter().enumerate() {
+            let token = function
+                .param_type_tokens
+                .get(index)
+                .map(String::as_str)
+                .unwrap_or_default();
+            let repr = params.get(index).copied().unwrap_or(Repr::Heap);
+            scope.bindings.insert(
+                name.clone(),
+                Binding {
+                    index: Some(index as u32),
+                    repr,
+                    owned: false,
+                    layout: Layout::from_token(token).or_else(|| shapes.layout(token)),
+                },
+            );
+        }
+        Self {
+            function,
+            exprs: function.exprs.iter().map(|expr| (expr.id, expr)).collect(),
+            callees,
+            shapes,
+            runtime,
+            statics,
+            tags,
+            result,
+            param_count: params.len() as u32,
+            locals: Vec::new(),
+            scratch: None,
+            scopes: vec![scope],
+            body: Vec::new(),
+            fallbacks: Vec::new(),
+        }
+    }
+
+    fn lower_body(&mut self) {
+        if let Some(captures) = &self.function.closure_env {
+            self.unpack_captures(captures);
+        }
+        match self.function.body {
+            Some(body) => self.emit_owned(body, self.result),
+            None => {
+                self.fallback(FALLBACK_BODY_MISSING, None, String::new());
+                self.body.push(Instr::Unreachable);
+            }
+        }
+    }
+
+    /// 持ち上げた関数のプロローグ。環境レコードからキャプチャを取り出して借用として束縛する。
+    fn unpack_captures(&mut self, captures: &[MirLambdaCapture]) {
+        let Some(env) = self
+            .function
+            .param_names
+            .iter()
+            .position(|name| name == CLOSURE_ENV_PARAM)
+        else {
+            return;
+        };
+        for (slot, capture) in captures.iter().enumerate() {
+            let repr = Repr::known(&capture.ty)
+                .or_else(|| self.infer_var_repr(&capture.name))
+                .unwrap_or(Repr::Heap);
+            self.body.extend([
+                Instr::LocalGet(env as u32),
+                Instr::I32Load(MemArg::i32(ITEMS_OFFSET)),
+                Instr::I32Load(MemArg::i32(4 * slot as u32)),
+            ]);
+            self.unbox(repr);
+            let index = repr.val_type().map(|ty| {
+                let index = self.new_local(ty);
+                self.body.push(Instr::LocalSet(index));
+                index
+            });
+            self.bind(
+                &capture.name,
+                Binding {
+                    index,
+                    repr,
+                    owned: false,
+                    layout: Layout::from_token(&capture.ty),
+                },
+            );
+        }
+    }
+
+    fn fallback(&mut self, code: &str, expr_id: Option<MirExprId>, detail: String) {
+        let span = expr_id
+            .and_then(|id| self.exprs.get(&id))
+            .and_then(|expr| expr.span);
+        self.fallbacks.push(CodegenFallback {
+            code: code.into(),
+            function: self.function.name.clone(),
+            expr_id,
+            span,
+            detail,
+        });
+    }
+
+    /// 生成できない式を `unreachable` に置き換える。
+    fn unsupported(&mut self, code: &str, expr_id: MirExprId, detail: String) -> Emitted {
+        self.fallback(code, Some(expr_id), detail);
+        self.body.push(Instr::Unreachable);
+        Emitted::Diverged
+    }
+
+    fn new_local(&mut self, ty: ValType) -> u32 {
+        self.locals.push(ty);
+        self.param_count + self.locals.len() as u32 - 1
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.bindings.get(name))
+    }
+
+    fn bind(&mut self, name: &str, binding: Binding) {
+        if let Some(scope) = self.scopes.last_mut() {
+            if binding.owned {
+                scope.owned.extend(binding.index);
+            }
+            scope.bindings.insert(name.to_string(), binding);
+        }
+    }
+
+    fn call_runtime(&mut self, function: RuntimeFn) {
+        self.body.push(Instr::Call(self.runtime.index(function)));
+    }
+
+    /// スタック先頭のヒープ参照を複製する（`inc_ref` してそのまま残す）。
+    fn retain_top(&mut self) {
+        let scratch = match self.scratch {
+            Some(index) => index,
+            None => {
+                let index = self.new_local(ValType::I32);
+                self.scratch = Some(index);
+                index
+            }
+        };
+        self.body.push(Instr::LocalTee(scratch));
+        self.body.push(Instr::LocalGet(scratch));
+        self.call_runtime(RuntimeFn::IncRef);
+    }
+
+    fn release_locals(&mut self, locals: &[u32]) {
+        for index in locals.iter().rev() {
+            self.body.push(Instr::LocalGet(*index));
+            self.call_runtime(RuntimeFn::DecRef);
+        }
+    }
+
+    /// `return` で抜けるときに全スコープの所有参照を解放する。
+    fn release_all_scopes(&mut self) {
+        let owned: Vec<u32> = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.owned.iter().copied())
+            .collect();
+        self.release_locals(&owned);
+    }
+
+    /// 式を評価し、結果を `target` の表現へ揃える。
+    fn emit_value(&mut self, id: MirExprId, target: Repr) -> Emitted {
+        let emitted = self.emit_expr(id);
+        self.coerce(id, emitted, target)
+    }
+
+    /// 評価結果を捨てる。所有しているヒープ参照は解放する。
+    fn emit_discard(&mut self, id: MirExprId) {
+        self.emit_value(id, Repr::Unit);
+    }
+
+    /// 値の参照を 1 つ所有した状態でスタックに残す（束縛・格納・返却用）。
+    fn emit_owned(&mut self, id: MirExprId, target: Repr) {
+        let emitted = self.emit_value(id, target);
+        self.own(emitted);
+    }
+
+    fn own(&mut self, emitted: Emitted) -> Emitted {
+        if emitted == Emitted::Value(Repr::Heap) {
+            self.retain_top();
+            return Emitted::Owned;
+        }
+        emitted
+    }
+
+    /// 値を借用として積む。所有参照は一時ローカルへ退避し、利用後に `release_locals` で解放する。
+    fn emit_borrowed(&mut self, id: MirExprId, target: Repr, temps: &mut Vec<u32>) -> Emitted {
+        let emitted = self.emit_value(id, target);
+        self.borrow(emitted, temps)
+    }
+
+    fn borrow(&mut self, emitted: Emitted, temps: &mut Vec<u32>) -> Emitted {
+        if emitted != Emitted::Owned {
+            return emitted;
+        }
+        let temp = self.new_local(ValType::I32);
+        self.body.push(Instr::LocalTee(temp));
+        temps.push(temp);
+        Emitted::Value(Repr::Heap)
+    }
+
+    fn coerce(&mut self, id: MirExprId, emitted: Emitted, target: Repr) -> Emitted {
+        let Some(actual) = emitted.repr() else {
+            return Emitted::Diverged;
+        };
+        let owned = emitted == Emitted::Owned;
+        match (actual, target) {
+            (actual, target) if actual == target => emitted,
+            (Repr::Unit, target) => {
+                if let Some(ty) = target.val_type() {
+                    self.body.push(zero_const(ty));
+                }
+                Emitted::Value(target)
+            }
+            (_, Repr::Unit) => {
+                self.drop_value(owned);
+                Emitted::Value(Repr::Unit)
+            }
+            (actual, Repr::Heap) => {
+                self.box_scalar(actual);
+                Emitted::Owned
+            }
+            (Repr::Heap, target) => self.mismatch(id, actual, target, owned),
+            (actual, target) => {
+                match (actual.val_type(), target.val_type()) {
+                    (Some(ValType::I32), Some(ValType::I64)) => {
+                        self.body.push(Instr::I64ExtendI32S)
+                    }
+                    (Some(ValType::I64), Some(ValType::I32)) => self.body.push(Instr::I32WrapI64),
+                    (from, to) if from == to => {}
+                    _ => return self.mismatch(id, actual, target, owned),
+                }
+                Emitted::Value(target)
+            }
+        }
+    }
+
+    fn mismatch(&mut self, id: MirExprId, actual: Repr, target: Repr, owned: bool) -> Emitted {
+        let name = |repr: Repr| repr.val_type().map_or("unit", ValType::name);
+        self.fallback(
+            FALLBACK_TYPE_MISMATCH,
+            Some(id),
+            format!("{} -> {}", name(actual), name(target)),
+        );
+        self.drop_value(owned);
+        if let Some(ty) = target.val_type() {
+            self.body.push(zero_const(ty));
+        }
+        Emitted::Value(target)
+    }
+
+    fn drop_value(&mut self, owned: bool) {
+        if owned {
+            self.call_runtime(RuntimeFn::DecRef);
+        } else {
+            self.body.push(Instr::Drop);
+        }
+    }
+
+    /// スタック先頭のスカラをボックス化したヒープオブジェクトにする。
+    fn box_scalar(&mut self, repr: Repr) {
+        let function = match repr {
+            Repr::Int => RuntimeFn::BoxI64,
+            Repr::Int32 => {
+                self.body.push(Instr::I64ExtendI32S);
+                RuntimeFn::BoxI64
+            }
+            Repr::Float => RuntimeFn::BoxFloat,
+            Repr::Bool => RuntimeFn::BoxBool,
+            Repr::Char => RuntimeFn::BoxChar,
+            Repr::Unit | Repr::Heap => return,
+        };
+        self.call_runtime(function);
+    }
+
+    /// スタック先頭のボックスから `repr` の値を取り出す。ヒープ値はポインタのまま残す。
+    fn unbox(&mut self, repr: Repr) {
+        let instr = match repr {
+            Repr::Int => Instr::I64Load(MemArg::i64(0)),
+            Repr::Int32 => {
+                self.body.push(Instr::I64Load(MemArg::i64(0)));
+                Instr::I32WrapI64
+            }
+            Repr::Float => Instr::F64Load(MemArg::i64(0)),
+            Repr::Bool => Instr::I32Load8U(MemArg::i8(0)),
+            Repr::Char => Instr::I32Load(MemArg::i32(0)),
+            Repr::Unit => Instr::Drop,
+            Repr::Heap => return,
+        };
+        self.body.push(instr);
+    }
+
+    /// コンテナから読み出した要素（スタック先頭のボックス）を値にする。
+    ///
+    /// コンテナが一時値なら、ヒープ要素の参照を複製してからコンテナを解放する。
+    fn finish_projection(&mut self, repr: Repr, temps: &[u32]) -> Emitted {
+        self.unbox(repr);
+        let emitted = if repr.is_heap() && !temps.is_empty() {
+            self.retain_top();
+            Emitted::Owned
+        } else {
+            Emitted::Value(repr)
+        };
+        self.release_locals(temps);
+        emitted
+    }
+
+    fn emit_expr(&mut self, id: MirExprId) -> Emitted {
+        let Some(expr) = self.exprs.get(&id).copied() else {
+            return self.unsupported(FALLBACK_EXPR_MISSING, id, String::new());
+        };
+        match &expr.kind {
+            MirExprKind::Literal { summary } => self.emit_literal(expr, summary),
+            MirExprKind::Identifier { summary } => {
+                self.emit_identifier(id, &identifier_name(summary))
+            }
+            MirExprKind::FieldAccess { target, field } => {
+                self.emit_field_access(id, *target, field)
+            }
+            MirExprKind::Index { target, index } => self.emit_index(id, *target, *index),
+            MirExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => self.emit_binary(id, operator, *left, *right),
+            MirExprKind::IfElse {
+                condition,
+                then_branch,
+                else_branch,
+            } => self.emit_if(expr, *condition, *then_branch, *else_branch),
+            MirExprKind::Block {
+                statements, tail, ..
+            } => self.emit_block(statements, *tail),
+            MirExprKind::Call { callee, args } => self.emit_call(id, *callee, args),
+            MirExprKind::Lambda {
+                symbol: Some(symbol),
+                captures,
+                ..
+            } => self.emit_closure(id, symbol, captures),
+            MirExprKind::Match { target, arms, .. } => self.emit_match(expr, *target, arms),
+            MirExprKind::Return { value } => {
+                match value {
+                    Some(value) => self.emit_owned(*value, self.result),
+                    None => {
+                        if let Some(result) = self.result.val_type() {
+                            self.body.push(zero_const(result));
+                        }
+                    }
+                }
+                self.release_all_scopes();
+                self.body.push(Instr::Return);
+                Emitted::Diverged
+            }
+            MirExprKind::Panic { argument } => {
+                if let Some(argument) = argument {
+                    self.emit_discard(*argument);
+                }
+                self.body.push(Instr::Unreachable);
+                Emitted::Diverged
+            }
+            MirExprKind::EffectBlock { body } | MirExprKind::Unsafe { body } => {
+                self.emit_expr(*body)
+            }
+            other => self.unsupported(
+                FALLBACK_EXPR_UNSUPPORTED,
+                id,
+                format!("kind={}", expr_kind_name(other)),
+            ),
+        }
+    }
+
+    fn emit_identifier(&mut self, id: MirExprId, name: &str) -> Emitted {
+        match self.lookup(name) {
+            Some(Binding {
+                index: Some(index),
+                repr,
+                ..
+            }) => {
+                let (index, repr) = (*index, *repr);
+                self.body.push(Instr::LocalGet(index));
+                Emitted::Value(repr)
+            }
+            Some(Binding { index: None, .. }) => Emitted::Value(Repr::Unit),
+            None if is_constructor_name(name) => self.emit_constructor(id, name, &[]),
+            None => self.unsupported(FALLBACK_IDENTIFIER, id, format!("name={name}")),
+        }
+    }
+
+    fn emit_literal(&mut self, expr: &MirExpr, summary: &str) -> Emitted {
+        match parse_literal_json(summary) {
+            Some(literal) => self.emit_literal_value(expr.id, &literal, &expr.ty),
+            None => self.unsupported(FALLBACK_LITERAL, expr.id, summary.to_string()),
+        }
+    }
+
+    /// リテラル（要素に含まれるものを含む）を生成する。`token` は整数リテラルの幅に使う。
+    fn emit_literal_value(&mut self, id: MirExprId, literal: &Value, token: &str) -> Emitted {
+        match literal.get("kind").and_then(Value::as_str) {
+            Some("tuple") => {
+                let elements: Vec<Element> = literal_elements(literal)
+                    .iter()
+                    .map(Element::Inline)
+                    .collect();
+                return self.emit_container(id, TAG_TUPLE, &elements);
+            }
+            Some("array") => {
+                let elements: Vec<Element> = literal_elements(literal)
+                    .iter()
+                    .map(Element::Inline)
+                    .collect();
+                return self.emit_container(id, TAG_ARRAY, &elements);
+            }
+            Some("record") => {
+                let fields = record_fields(literal);
+                let values: Vec<Element> = fields
+                    .iter()
+                    .map(|(_, value)| Element::Inline(value))
+                    .collect();
+                return self.emit_container(id, TAG_RECORD, &values);
+            }
+            _ => {}
+        }
+        let Some(scalar) = scalar_literal(literal) else {
+            return self.unsupported(FALLBACK_LITERAL, id, literal.to_string());
+        };
+        let (instr, repr) = match scalar {
+            ScalarLiteral::Unit => return Emitted::Value(Repr::Unit),
+            ScalarLiteral::Bool(value) => (Instr::I32Const(i32::from(value)), Repr::Bool),
+            ScalarLiteral::Int(value) => match Repr::of(token) {
+                Repr::Int32 => (Instr::I32Const(value as i32), Repr::Int32),
+                Repr::Float => (Instr::F64Const(value as f64), Repr::Float),
+                _ => (Instr::I64Const(value), Repr::Int),
+            },
+            ScalarLiteral::Float(value) => (Instr::F64Const(value), Repr::Float),
+            ScalarLiteral::Char(value) => (Instr::I32Const(value as i32), Repr::Char),
+            ScalarLiteral::String(text) => (
+                Instr::I32Const(self.statics.intern_string(&text) as i32),
+                Repr::Heap,
+            ),
+        };
+        self.body.push(instr);
+        Emitted::Value(repr)
+    }
+
+    /// `{len i64, items i32 @8}` のコンテナを確保し、要素をボックス化して格納する。
+    fn emit_container(&mut self, id: MirExprId, tag: i32, elements: &[Element]) -> Emitted {
+        let object = self.new_local(ValType::I32);
+        self.body.extend([
+            Instr::I32Const(16),
+            Instr::Call(self.runtime.index(RuntimeFn::MemAlloc)),
+            Instr::LocalTee(object),
+            Instr::I32Const(tag),
+            Instr::Call(self.runtime.index(RuntimeFn::SetTypeTag)),
+            Instr::LocalGet(object),
+            Instr::I64Const(elements.len() as i64),
+            Instr::I64Store(MemArg::i64(0)),
+        ]);
+        if !elements.is_empty() {
+            let items = self.new_local(ValType::I32);
+            self.body.extend([
+                Instr::LocalGet(object),
+                Instr::I32Const(4 * elements.len() as i32),
+                Instr::Call(self.runtime.index(RuntimeFn::MemAlloc)),
+                Instr::LocalTee(items),
+                Instr::I32Store(MemArg::i32(ITEMS_OFFSET)),
+            ]);
+            for (slot, element) in elements.iter().enumerate() {
+                self.body.push(Instr::LocalGet(items));
+                match element {
+                    Element::Inline(element) => {
+                        let emitted = self.emit_element(id, element);
+                        let emitted = self.coerce(id, emitted, Repr::Heap);
+                        self.own(emitted);
+                    }
+                    Element::Expr(element) => self.emit_owned(*element, Repr::Heap),
+                }
+                self.body
+                    .push(Instr::I32Store(MemArg::i32(4 * slot as u32)));
+            }
+        }
+        self.body.push(Instr::LocalGet(object));
+        Emitted::Owned
+    }
+
+    /// リテラルの要素式（リテラル・識別子・二項演算）を生成する。
+    fn emit_element(&mut self, id: MirExprId, element: &Value) -> Emitted {
+        let element = unwrap_element(element);
+        match element.get("kind").and_then(Value::as_str) {
+            Some("literal") => match element.get("value").and_then(unwrap_literal) {
+                Some(literal) => self.emit_literal_value(id, literal, ""),
+                None => self.unsupported(FALLBACK_LITERAL_ELEMENT, id, element.to_string()),
+            },
+            Some("identifier") => match element_identifier(element) {
+                Some(name) => self.emit_identifier(id, name),
+                None => self.unsupported(FALLBACK_LITERAL_ELEMENT, id, element.to_string()),
+            },
+            Some("binary") => {
+                let operator = element
+                    .get("operator")
+                    .and_then(Value::as_str)
+                    .map(operator_symbol)
+                    .unwrap_or_default();
+                let (Some(left), Some(right)) = (element.get("left"), element.get("right")) else {
+                    return self.unsupported(FALLBACK_LITERAL_ELEMENT, id, element.to_string());
+                };
+                let operand = self.emit_element(id, left);
+                let Some(operand) = operand.repr().and_then(Repr::val_type) else {
+                    return self.unsupported(FALLBACK_BINARY_OPERATOR, id, operator.to_string());
+                };
+                let logical = match operator {
+                    "&&" => Some(Instr::I32And),
+                    "||" => Some(Instr::I32Or),
+                    _ => None,
+                };
+                let (instr, result) = match logical {
+                    Some(instr) => (instr, ValType::I32),
+                    None => match binary_instr(operator, operand) {
+                        Some(entry) => entry,
+                        None => {
+                            return self.unsupported(
+                                FALLBACK_BINARY_OPERATOR,
+                                id,
+                                format!("operator={operator} operand={}", operand.name()),
+                            )
+                        }
+                    },
+                };
+                let right = self.emit_element(id, right);
+                let operand_repr = self.element_repr(left);
+                self.coerce(id, right, operand_repr);
+                self.body.push(instr);
+                if result == operand {
+                    Emitted::Value(operand_repr)
+                } else {
+                    Emitted::Value(Repr::Bool)
+                }
+            }
+            Some("field_access") => {
+                let (Some(target), Some((index, repr))) =
+                    (element.get("target"), self.element_field(element))
+                else {
+                    return self.unsupported(FALLBACK_FIELD_ACCESS, id, element.to_string());
+                };
+                let emitted = self.emit_element(id, target);
+                let mut temps = Vec::new();
+                if self.borrow(emitted, &mut temps) == Emitted::Diverged {
+                    return Emitted::Diverged;
+                }
+                self.body.push(Instr::I32Load(MemArg::i32(ITEMS_OFFSET)));
+                self.body
+                    .push(Instr::I32Load(MemArg::i32(4 * index as u32)));
+                self.finish_projection(repr, &temps)
+            }
+            _ => self.unsupported(FALLBACK_LITERAL_ELEMENT, id, element.to_string()),
+        }
+    }
+
+    /// 要素式 `target.field` のフィールド位置。対象は束縛された変数か、入れ子のフィールド参照。
+    fn element_field(&self, element: &Value) -> Option<(usize, Repr)> {
+        let field = match element.get("field")? {
+            Value::String(name) => name.as_str(),
+            field => field.get("name")?.as_str()?,
+        };
+        self.element_layout(element.get("target")?)?.field(field)
+    }
+
+    fn element_layout(&self, element: &Value) -> Option<Layout> {
+        let element = unwrap_element(element);
+        match element.get("kind").and_then(Value::as_str)? {
+            "identifier" => self.lookup(element_identifier(element)?)?.layout.clone(),
+            _ => None,
+        }
+    }
+
+    /// 要素式を評価したときの表現（`emit_element` と同じ規則）。
+    fn element_repr(&self, element: &Value) -> Repr {
+        let element = unwrap_element(element);
+        match element.get("kind").and_then(Value::as_str) {
+            Some("literal") => match element
+                .get("value")
+                .and_then(unwrap_literal)
+                .and_then(scalar_literal)
+            {
+                Some(ScalarLiteral::Unit) => Repr::Unit,
+                Some(ScalarLiteral::Bool(_)) => Repr::Bool,
+                Some(ScalarLiteral::Int(_)) => Repr::Int,
+                Some(ScalarLiteral::Float(_)) => Repr::Float,
+                Some(ScalarLiteral::Char(_)) => Repr::Char,
+                Some(ScalarLiteral::String(_)) | None => Repr::Heap,
+            },
+            Some("identifier") => element_identifier(element)
+                .and_then(|name| self.lookup(name))
+                .map_or(Repr::Heap, |binding| binding.repr),
+            Some("binary") => {
+                let operator = element
+                    .get("operator")
+                    .and_then(Value::as_str)
+                    .map(operator_symbol)
+                    .unwrap_or_default();
+                match operator {
+                    "+" | "-" | "*" | "/" | "%" => element
+                        .get("left")
+                        .map_or(Repr::Heap, |left| self.element_repr(left)),
+                    _ => Repr::Bool,
+                }
+            }
+            Some("field_access") => self
+                .element_field(element)
+                .map_or(Repr::Heap, |(_, repr)| repr),
+            _ => Repr::Heap,
+        }
+    }
+
+    /// 式の値がコンテナなら、そのレイアウト。リテラル・束縛・型トークンから求める。
+    fn layout_of(&self, id: MirExprId) -> Option<Layout> {
+        let expr = self.exprs.get(&id)?;
+        let from_expr = match &expr.kind {
+            MirExprKind::Literal { summary } => {
+                let literal = parse_literal_json(summary)?;
+                match literal.get("kind").and_then(Value::as_str) {
+                    Some("tuple") => Some(Layout::Tuple(
+                        literal_elements(&literal)
+                            .iter()
+                            .map(|element| self.element_repr(element))
+                            .collect(),
+                    )),
+                    Some("record") => Some(Layout::Record(
+                        record_fields(&literal)
+                            .into_iter()
+                            .map(|(key, value)| (key, self.element_repr(value)))
+                            .collect(),
+                    )),
+                    Some("array") => literal_elements(&literal)
+                        .first()
+                        .map(|element| Layout::Array(self.element_repr(element))),
+                    _ => None,
+                }
+            }
+            MirExprKind::Identifier { summary } => self
+                .lookup(&identifier_name(summary))
+                .and_then(|binding| binding.layout.clone()),
+            MirExprKind::Lambda {
+                symbol: Some(symbol),
+                ..
+            } => self.callees.get(&export_name(symbol)).map(|callee| {
+                let params = callee.params.iter().skip(1).copied().collect();
+                Layout::Closure(params, callee.result)
+            }),
+            _ => None,
+        };
+        from_expr
+            .or_else(|| Layout::from_token(&expr.ty))
+            .or_else(|| self.shapes.layout(&expr.ty))
+    }
+
+    /// クロージャ `{env i32, code i32}` を確保する。環境はキャプチャを値のコピーとして
+    /// キャプチャ順に並べたレコードで、キャプチャがなければ NULL。
+    fn emit_closure(
+        &mut self,
+        id: MirExprId,
+        symbol: &str,
+        captures: &[MirLambdaCapture],
+    ) -> Emitted {
+        let Some(slot) = self
+            .callees
+            .get(&export_name(symbol))
+            .and_then(|callee| callee.slot)
+        else {
+            return self.unsupported(FALLBACK_CALL, id, format!("closure={symbol}"));
+        };
+        for capture in captures.iter().filter(|capture| capture.mutable) {
+            // 再代入される可変キャプチャはコピーでは書き込みを共有できない。
+            self.fallback(
+                FALLBACK_MUTABLE_CAPTURE,
+                Some(id),
+                format!("capture={}", capture.name),
+            );
+        }
+        let object = self.new_local(ValType::I32);
+        self.body.extend([
+            Instr::I32Const(8),
+            Instr::Call(self.runtime.index(RuntimeFn::MemAlloc)),
+            Instr::LocalTee(object),
+            Instr::I32Const(TAG_CLOSURE),
+            Instr::Call(self.runtime.index(RuntimeFn::SetTypeTag)),
+            Instr::LocalGet(object),
+        ]);
+        if captures.is_empty() {
+            self.body.push(Instr::I32Const(0));
+        } else {
+            let names: Vec<Value> = captures
+                .iter()
+                .map(|capture| serde_json::json!({"kind": "identifier", "name": capture.name}))
+                .collect();
+            let elements: Vec<Element> = names.iter().map(Element::Inline).collect();
+            self.emit_container(id, TAG_RECORD, &elements);
+        }
+        self.body.extend([
+            Instr::I32Store(MemArg::i32(0)),
+            Instr::LocalGet(object),
+            Instr::I32Const(slot as i32),
+            Instr::I32Store(MemArg::i32(4)),
+            Instr::LocalGet(object),
+        ]);
+        Emitted::Owned
+    }
+
+    /// ADT の値 `{tag i32, payload i32}` を確保する。ペイロードは引数が 1 つならそのボックス、
+    /// 複数ならボックスを並べたタプル、引数なしなら NULL。
+    fn emit_constructor(&mut self, id: MirExprId, name: &str, args: &[MirExprId]) -> Emitted {
+        let tag = self.tags.tag(name);
+        let object = self.new_local(ValType::I32);
+        self.body.extend([
+            Instr::I32Const(8),
+            Instr::Call(self.runtime.index(RuntimeFn::MemAlloc)),
+            Instr::LocalTee(object),
+            Instr::I32Const(TAG_ADT),
+            Instr::Call(self.runtime.index(RuntimeFn::SetTypeTag)),
+            Instr::LocalGet(object),
+            Instr::I32Const(tag),
+            Instr::I32Store(MemArg::i32(0)),
+        ]);
+        match args {
+            [] => {}
+            [arg] => {
+                self.body.push(Instr::LocalGet(object));
+                self.emit_owned(*arg, Repr::Heap);
+                self.body.push(Instr::I32Store(MemArg::i32(PAYLOAD_OFFSET)));
+            }
+            args => {
+                self.body.push(Instr::LocalGet(object));
+                let elements: Vec<Element> = args.iter().copied().map(Element::Expr).collect();
+                self.emit_container(id, TAG_TUPLE, &elements);
+                self.body.push(Instr::I32Store(MemArg::i32(PAYLOAD_OFFSET)));
+            }
+        }
+        self.body.push(Instr::LocalGet(object));
+        Emitted::Owned
+    }
+
+    /// 腕を順に試す。各腕はブロックで囲み、照合に失敗したらブロックを抜けて次の腕へ進む。
+    fn emit_match(&mut self, expr: &MirExpr, target: MirExprId, arms: &[MirMatchArm]) -> Emitted {
+        let layout = self.layout_of(target);
+        let emitted = self.emit_expr(target);
+        let Some(repr) = emitted.repr() else {
+            return Emitted::Diverged;
+        };
+        self.scopes.push(Scope::default());
+        let local = repr.val_type().map(|ty| {
+            let local = self.new_local(ty);
+            self.body.push(Instr::LocalSet(local));
+            local
+        });
+        if emitted == Emitted::Owned {
+            if let Some(scope) = self.scopes.last_mut() {
+                scope.owned.extend(local);
+            }
+        }
+        let subject = Subject {
+            local,
+            repr,
+            layout,
+        };
+        let at = self.body.len();
+        self.body.push(Instr::Block(None));
+        let mut result = Repr::known(&expr.ty);
+        for arm in arms {
+            self.body.push(Instr::Block(None));
+            self.scopes.push(Scope::default());
+            if let Some(alias) = &arm.alias {
+                self.bind_pattern_var(alias, &subject);
+            }
+            self.emit_pattern(expr.id, &arm.pattern, &subject, 0);
+            if let Some(guard) = arm.guard {
+                self.emit_value(guard, Repr::Bool);
+                self.body.push(Instr::I32Eqz);
+                self.body.push(Instr::BrIf(0));
+            }
+            let emitted = self.emit_expr(arm.body);
+            if result.is_none() {
+                result = emitted.repr();
+            }
+            if let Some(result) = result {
+                self.finish_branch(arm.body, emitted, result);
+            }
+            let scope = self.scopes.pop().unwrap_or_default();
+            self.release_locals(&scope.owned);
+            self.body.push(Instr::Br(1));
+            self.body.push(Instr::End);
+        }
+        // どの腕にも一致しなければ native と同じく実行を止める。
+        self.body.push(Instr::Unreachable);
+        self.body.push(Instr::End);
+        let scope = self.scopes.pop().unwrap_or_default();
+        let Some(result) = result else {
+            self.body.push(Instr::Unreachable);
+            return Emitted::Diverged;
+        };
+        self.body[at] = Instr::Block(result.val_type());
+        self.release_locals(&scope.owned);
+        if result.is_heap() {
+            Emitted::Owned
+        } else {
+            Emitted::Value(result)
+        }
+    }
+
+    /// `subject` が `pattern` に一致しなければ `miss` 段外側のブロックへ抜ける。
+    /// 変数は照合対象（またはその要素）を借用して束縛する。
+    fn emit_pattern(&mut self, id: MirExprId, pattern: &MirPattern, subject: &Subject, miss: u32) {
+        match &pattern.kind {
+            MirPatternKind::Wildcard => {}
+            MirPatternKind::Var { name } => self.bind_pattern_var(name, subject),
+            MirPatternKind::Literal { summary } => {
+                self.emit_literal_test(id, summary, subject, miss)
+            }
+            MirPatternKind::Tuple { elements } => {
+                let reprs = match &subject.layout {
+                    Some(Layout::Tuple(reprs)) => reprs.clone(),
+                    _ => Vec::new(),
+                };
+                for (slot, element) in elements.iter().enumerate() {
+                    let hint = reprs.get(slot).copied();
+                    self.emit_item_pattern(id, element, subject, Some(slot), hint, miss);
+                }
+            }
+            MirPatternKind::Record { fields, has_rest } => {
+                let mut layout: Vec<(String, Option<Repr>)> = match &subject.layout {
+                    Some(Layout::Record(fields)) => fields
+                        .iter()
+                        .map(|(name, repr)| (name.clone(), Some(*repr)))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                if layout.is_empty() && !has_rest {
+                    // 残りのないパターンはフィールドを網羅するので、名前順が格納順になる。
+                    layout = fields
+                        .iter()
+                        .map(|field| (field.key.clone(), None))
+                        .collect();
+                    layout.sort_by(|lhs, rhs| lhs.0.cmp(&rhs.0));
+                }
+                for field in fields {
+                    let Some(slot) = layout.iter().position(|(name, _)| *name == field.key) else {
+                        self.fallback(FALLBACK_PATTERN, Some(id), format!("field={}", field.key));
+                        self.body.push(Instr::Unreachable);
+                        return;
+                    };
+                    let shorthand = MirPattern {
+                        kind: MirPatternKind::Var {
+                            name: field.key.clone(),
+                        },
+                    };
+                    let value = field.value.as_deref().unwrap_or(&shorthand);
+                    let hint = layout[slot].1;
+                    self.emit_item_pattern(id, value, subject, Some(slot), hint, miss);
+                }
+            }
+            MirPatternKind::Constructor { name, args } => {
+                if !subject.repr.is_heap() {
+                    self.fallback(FALLBACK_PATTERN, Some(id), format!("constructor={name}"));
+                    self.body.push(Instr::Unreachable);
+                    return;
+                }
+                let tag = self.tags.tag(name);
+                self.body.extend(subject.local.map(Instr::LocalGet));
+                self.body.extend([
+                    Instr::I32Load(MemArg::i32(0)),
+                    Instr::I32Const(tag),
+                    Instr::I32Ne,
+                    Instr::BrIf(miss),
+                ]);
+                match args.as_slice() {
+                    [] => {}
+                    [arg] => self.emit_item_pattern(id, arg, subject, None, None, miss),
+                    args => {
+                        let payload = self.new_local(ValType::I32);
+                        self.body.extend(subject.local.map(Instr::LocalGet));
+                        self.body.push(Instr::I32Load(MemArg::i32(PAYLOAD_OFFSET)));
+                        self.body.push(Instr::LocalSet(payload));
+                        let payload = Subject {
+                            local: Some(payload),
+                            repr: Repr::Heap,
+                            layout: None,
+                        };
+                        for (slot, arg) in args.iter().enumerate() {
+                            self.emit_item_pattern(id, arg, &payload, Some(slot), None, miss);
+                        }
+                    }
+                }
+            }
+            MirPatternKind::Binding { name, pattern, .. } => {
+                self.bind_pattern_var(name, subject);
+                self.emit_pattern(id, pattern, subject, miss);
+            }
+            MirPatternKind::Or { variants } => {
+                let Some((last, rest)) = variants.split_last() else {
+                    return;
+                };
+                self.body.push(Instr::Block(None));
+                for variant in rest {
+                    self.body.push(Instr::Block(None));
+                    self.emit_pattern(id, variant, subject, 0);
+                    self.body.push(Instr::Br(1));
+                    self.body.push(Instr::End);
+                }
+                self.emit_pattern(id, last, subject, miss + 1);
+                self.body.push(Instr::End);
+            }
+            other => {
+                self.fallback(
+                    FALLBACK_PATTERN,
+                    Some(id),
+                    format!("kind={}", pattern_kind_name(other)),
+                );
+                self.body.push(Instr::Unreachable);
+            }
+        }
+    }
+
+    /// コンテナの要素（`slot`）または ADT のペイロード（`None`）を取り出して照合する。
+    fn emit_item_pattern(
+        &mut self,
+        id: MirExprId,
+        pattern: &MirPattern,
+        subject: &Subject,
+        slot: Option<usize>,
+        hint: Option<Repr>,
+        miss: u32,
+    ) {
+        if matches!(pattern.kind, MirPatternKind::Wildcard) {
+            return;
+        }
+        if !subject.repr.is_heap() {
+            self.fallback(FALLBACK_PATTERN, Some(id), "projection".into());
+            self.body.push(Instr::Unreachable);
+            return;
+        }
+        self.body.extend(subject.local.map(Instr::LocalGet));
+        match slot {
+            Some(slot) => {
+                self.body.push(Instr::I32Load(MemArg::i32(ITEMS_OFFSET)));
+                self.body.push(Instr::I32Load(MemArg::i32(4 * slot as u32)));
+            }
+            None => self.body.push(Instr::I32Load(MemArg::i32(PAYLOAD_OFFSET))),
+        }
+        let repr = self.pattern_repr(pattern, hint);
+        self.unbox(repr);
+        let local = repr.val_type().map(|ty| {
+            let local = self.new_local(ty);
+            self.body.push(Instr::LocalSet(local));
+            local
+        });
+        let item = Subject {
+            local,
+            repr,
+            layout: None,
+        };
+        self.emit_pattern(id, pattern, &item, miss);
+    }
+
+    fn emit_literal_test(&mut self, id: MirExprId, summary: &str, subject: &Subject, miss: u32) {
+        let literal = parse_literal_json(summary);
+        let compare = match (literal.as_ref().and_then(scalar_literal), subject.repr) {
+            (Some(ScalarLiteral::Unit), _) => return,
+            (Some(ScalarLiteral::Int(value)), Repr::Int) => {
+                Some((Instr::I64Const(value), Instr::I64Ne))
+            }
+            (Some(ScalarLiteral::Float(value)), Repr::Float) => {
+                Some((Instr::F64Const(value), Instr::F64Ne))
+            }
+            (Some(ScalarLiteral::Int(value)), Repr::Int32 | Repr::Char) => {
+                Some((Instr::I32Const(value as i32), Instr::I32Ne))
+            }
+            (Some(ScalarLiteral::Bool(value)), Repr::Bool) => {
+                Some((Instr::I32Const(i32::from(value)), Instr::I32Ne))
+            }
+            (Some(ScalarLiteral::Char(value)), Repr::Char) => {
+                Some((Instr::I32Const(value as i32), Instr::I32Ne))
+            }
+            _ => None,
+        };
+        let (Some((constant, not_equal)), Some(local)) = (compare, subject.local) else {
+            self.fallback(FALLBACK_PATTERN, Some(id), format!("literal={summary}"));
+            self.body.push(Instr::Unreachable);
+            return;
+        };
+        self.body.extend([
+            Instr::LocalGet(local),
+            constant,
+            not_equal,
+            Instr::BrIf(miss),
+        ]);
+    }
+
+    /// パターン変数を束縛する。or パターンの 2 つ目以降の選択肢では、先に割り当てたローカルへ写す。
+    fn bind_pattern_var(&mut self, name: &str, subject: &Subject) {
+        let existing = self
+            .scopes
+            .last()
+            .and_then(|scope| scope.bindings.get(name))
+            .filter(|binding| binding.repr == subject.repr)
+            .and_then(|binding| binding.index);
+        match (existing, subject.local) {
+            (Some(existing), Some(local)) if existing != local => {
+                self.body.push(Instr::LocalGet(local));
+                self.body.push(Instr::LocalSet(existing));
+            }
+            _ => self.bind(
+                name,
+                Binding {
+                    index: subject.local,
+                    repr: subject.repr,
+                    owned: false,
+                    layout: subject.layout.clone(),
+                },
+            ),
+        }
+    }
+
+    /// 要素を取り出したときの表現。変数は本体での使われ方から型を推す。
+    fn pattern_repr(&self, pattern: &MirPattern, hint: Option<Repr>) -> Repr {
+        match &pattern.kind {
+            MirPatternKind::Tuple { .. }
+            | MirPatternKind::Record { .. }
+            | MirPatternKind::Constructor { .. } => Repr::Heap,
+            MirPatternKind::Var { name } => hint
+                .or_else(|| self.infer_var_repr(name))
+                .unwrap_or(Repr::Heap),
+            MirPatternKind::Binding { name, pattern, .. } => match pattern.kind {
+                MirPatternKind::Wildcard | MirPatternKind::Var { .. } => hint
+                    .or_else(|| self.infer_var_repr(name))
+                    .unwrap_or(Repr::Heap),
+                _ => self.pattern_repr(pattern, hint),
+            },
+            MirPatternKind::Literal { summary } => {
+                hint.unwrap_or_else(|| {
+                    match parse_literal_json(summary)
+                        .as_ref()
+                        .and_then(scalar_literal)
+                    {
+                        Some(ScalarLiteral::Int(_)) => Repr::Int,
+                        Some(ScalarLiteral::Float(_)) => Repr::Float,
+                        Some(ScalarLiteral::Bool(_)) => Repr::Bool,
+                        Some(ScalarLiteral::Char(_)) => Repr::Char,
+                        Some(ScalarLiteral::Unit) => Repr::Unit,
+                        Some(ScalarLiteral::String(_)) | None => Repr::Heap,
+                    }
+                })
+            }
+            MirPatternKind::Or { variants } => variants
+                .first()
+                .map_or(Repr::Heap, |variant| self.pattern_repr(variant, hint)),
+            _ => hint.unwrap_or(Repr::Heap),
+        }
+    }
+
+    /// 変数を参照する式の型トークン、または変数と演算する相手の型から表現を推す。
+    fn infer_var_repr(&self, name: &str) -> Option<Repr> {
+        let refers = |id: &MirExprId| {
+            matches!(
+                self.exprs.get(id).map(|expr| &expr.kind),
+                Some(MirExprKind::Identifier { summary }) if identifier_name(summary) == name
+            )
+        };
+        let uses = self.function.exprs.iter().filter(|expr| refers(&expr.id));
+        if let Some(repr) = uses.filter_map(|expr| Repr::known(&expr.ty)).next() {
+            return Some(repr);
+        }
+        self.function
+            .exprs
+            .iter()
+            .find_map(|expr| match &expr.kind {
+                MirExprKind::Binary {
+                    operator,
+                    left,
+                    right,
+                } if refers(left) || refers(right) => {
+                    let other = if refers(left) { right } else { left };
+                    self.exprs
+                        .get(other)
+                        .and_then(|other| Repr::known(&other.ty))
+                        .or_else(|| {
+                            matches!(operator.as_str(), "+" | "-" | "*" | "/" | "%")
+                                .then(|| self.context_repr(expr.id))
+                                .flatten()
+                        })
+                }
+                _ => None,
+            })
+    }
+
+    /// 式の型が未確定のとき、その値をそのまま返す外側の式（分岐・ブロック末尾・関数本体）の型を使う。
+    fn context_repr(&self, id: MirExprId) -> Option<Repr> {
+        if let Some(repr) = self.exprs.get(&id).and_then(|expr| Repr::known(&expr.ty)) {
+            return Some(repr);
+        }
+        if Some(id) == self.function.body {
+            return Some(self.result);
+        }
+        let parent = self.function.exprs.iter().find(|expr| match &expr.kind {
+            MirExprKind::IfElse {
+                then_branch,
+                else_branch,
+                ..
+            } => *then_branch == id || *else_branch == id,
+            MirExprKind::Block { tail, .. } => *tail == Some(id),
+            MirExprKind::Match { arms, .. } => arms.iter().any(|arm| arm.body == id),
+            MirExprKind::EffectBlock { body } | MirExprKind::Unsafe { body } => *body == id,
+            _ => false,
+        })?;
+        self.context_repr(parent.id)
+    }
+
+    /// 生成前に分かる式の表現。型トークンが型変数なら束縛やコンテナのレイアウトから求める。
+    fn static_repr(&self, id: MirExprId) -> Option<Repr> {
+        let expr = self.exprs.get(&id)?;
+        if let Some(repr) = Repr::known(&expr.ty) {
+            return Some(repr);
+        }
+        match &expr.kind {
+            MirExprKind::Identifier { summary } => self
+                .lookup(&identifier_name(summary))
+                .map(|binding| binding.repr),
+            MirExprKind::FieldAccess { target, field } => {
+                self.field_position(*target, field).map(|(_, repr)| repr)
+            }
+            MirExprKind::Index { target, .. } => match self.layout_of(*target)? {
+                Layout::Array(repr) => Some(repr),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// フィールドの要素番号と表現。タプルは `0`, `1`, ... をフィールド名とする。
+    fn field_position(&self, target: MirExprId, field: &str) -> Option<(usize, Repr)> {
+        self.layout_of(target)?.field(field)
+    }
+
+    fn emit_field_access(&mut self, id: MirExprId, target: MirExprId, field: &str) -> Emitted {
+        let Some((index, repr)) = self.field_position(target, field) else {
+            let target_ty = self
+                .exprs
+                .get(&target)
+                .map(|expr| expr.ty.clone())
+                .unwrap_or_default();
+            return self.unsupported(
+                FALLBACK_FIELD_ACCESS,
+                id,
+                format!("field={field} type={target_ty}"),
+            );
+        };
+        let mut temps = Vec::new();
+        if self.emit_borrowed(target, Repr::Heap, &mut temps) == Emitted::Diverged {
+            return Emitted::Diverged;
+        }
+        self.body.push(Instr::I32Load(MemArg::i32(ITEMS_OFFSET)));
+        self.body
+            .push(Instr::I32Load(MemArg::i32(4 * index as u32)));
+        self.finish_projection(repr, &temps)
+    }
+
+    /// 配列の添字アクセス。範囲外は native の `index out of bounds` と同じくトラップする。
+    fn emit_index(&mut self, id: MirExprId, target: MirExprId, index: MirExprId) -> Emitted {
+        let Some(Layout::Array(repr)) = self.layout_of(target) else {
+            let target_ty = self
+                .exprs
+                .get(&target)
+                .map(|expr| expr.ty.clone())
+                .unwrap_or_default();
+            return self.unsupported(FALLBACK_INDEX, id, format!("type={target_ty}"));
+        };
+        let mut temps = Vec::new();
+        if self.emit_borrowed(target, Repr::Heap, &mut temps) == Emitted::Diverged {
+            return Emitted::Diverged;
+        }
+        let array = self.new_local(ValType::I32);
+        self.body.push(Instr::LocalSet(array));
+        if self.emit_value(index, Repr::Int) == Emitted::Diverged {
+            return Emitted::Diverged;
+        }
+        let position = self.new_local(ValType::I64);
+        self.body.extend([
+            Instr::LocalTee(position),
+            Instr::LocalGet(array),
+            Instr::I64Load(MemArg::i64(0)),
+            Instr::I64GeU,
+            Instr::If(None),
+            Instr::Unreachable,
+            Instr::End,
+            Instr::LocalGet(array),
+            Instr::I32Load(MemArg::i32(ITEMS_OFFSET)),
+            Instr::LocalGet(position),
+            Instr::I32WrapI64,
+            Instr::I32Const(2),
+            Instr::I32Shl,
+            Instr::I32Add,
+            Instr::I32Load(MemArg::i32(0)),
+        ]);
+        self.finish_projection(repr, &temps)
+    }
+
+    fn emit_binary(
+        &mut self,
+        id: MirExprId,
+        operator: &str,
+        left: MirExprId,
+        right: MirExprId,
+    ) -> Emitted {
+        if operator == "&&" || operator == "||" {
+            self.emit_value(left, Repr::Bool);
+            self.body.push(Instr::If(Some(ValType::I32)));
+            if operator == "&&" {
+                self.emit_value(right, Repr::Bool);
+                self.body.push(Instr::Else);
+                self.body.push(Instr::I32Const(0));
+            } else {
+                self.body.push(Instr::I32Const(1));
+                self.body.push(Instr::Else);
+                self.emit_value(right, Repr::Bool);
+            }
+            self.body.push(Instr::End);
+            return Emitted::Value(Repr::Bool);
+        }
+        // 被演算子の型が型変数のままなら、もう一方の被演算子や算術演算の結果型から決める。
+        let arithmetic = matches!(operator, "+" | "-" | "*" | "/" | "%");
+        let operand = [Some(left), Some(right), arithmetic.then_some(id)]
+            .into_iter()
+            .flatten()
+            .find_map(|candidate| self.static_repr(candidate).filter(|repr| !repr.is_heap()))
+            .or_else(|| {
+                arithmetic
+                    .then(|| self.context_repr(id))
+                    .flatten()
+                    .filter(|repr| !repr.is_heap())
+            });
+        let lowered = operand.and_then(|operand| {
+            let ty = operand.val_type()?;
+            binary_instr(operator, ty).map(|entry| (operand, ty, entry))
+        });
+        let Some((operand, ty, (instr, result))) = lowered else {
+            let operand_token = self
+                .exprs
+                .get(&left)
+                .map(|expr| expr.ty.clone())
+                .unwrap_or_default();
+            return self.unsupported(
+                FALLBACK_BINARY_OPERATOR,
+                id,
+                format!("operator={operator} operand={operand_token}"),
+            );
+        };
+        self.emit_value(left, operand);
+        self.emit_value(right, operand);
+        self.body.push(instr);
+        if result == ty {
+            Emitted::Value(operand)
+        } else {
+            Emitted::Value(Repr::Bool)
+        }
+    }
+
+    /// 分岐の結果をそろえる。ヒープ値はどちらの分岐でも所有参照にする。
+    fn emit_if(
+        &mut self,
+        expr: &MirExpr,
+        condition: MirExprId,
+        then_branch: MirExprId,
+        else_branch: MirExprId,
+    ) -> Emitted {
+        self.emit_value(condition, Repr::Bool);
+        let at = self.body.len();
+        self.body.push(Instr::If(None));
+        let then_emitted = self.emit_expr(then_branch);
+        let mut repr = Repr::known(&expr.ty).or(then_emitted.repr());
+        if let Some(repr) = repr {
+            self.finish_branch(then_branch, then_emitted, repr);
+        }
+        self.body.push(Instr::Else);
+        let else_emitted = self.emit_expr(else_branch);
+        if repr.is_none() {
+            repr = else_emitted.repr();
+        }
+        let Some(repr) = repr else {
+            self.body[at] = Instr::If(None);
+            self.body.push(Instr::End);
+            self.body.push(Instr::Unreachable);
+            return Emitted::Diverged;
+        };
+        self.finish_branch(else_branch, else_emitted, repr);
+        self.body.push(Instr::End);
+        self.body[at] = Instr::If(repr.val_type());
+        if repr.is_heap() {
+            Emitted::Owned
+        } else {
+            Emitted::Value(repr)
+        }
+    }
+
+    fn finish_branch(&mut self, id: MirExprId, emitted: Emitted, repr: Repr) {
+        let emitted = self.coerce(id, emitted, repr);
+        self.own(emitted);
+    }
+
+    fn emit_block(&mut self, statements: &[MirStmt], tail: Option<MirExprId>) -> Emitted {
+        self.scopes.push(Scope::default());
+        for stmt in statements {
+            self.emit_stmt(stmt);
+        }
+        let mut emitted = match tail {
+            Some(tail) => self.emit_expr(tail),
+            None => Emitted::Value(Repr::Unit),
+        };
+        let scope = self.scopes.pop().unwrap_or_default();
+        if !scope.owned.is_empty() && emitted != Emitted::Diverged {
+            // 末尾の値がスコープの変数を借用していても解放後に使えるよう、参照を複製する。
+            emitted = self.own(emitted);
+            self.release_locals(&scope.owned);
+        }
+        emitted
+    }
+
+    fn emit_stmt(&mut self, stmt: &MirStmt) {
+        match &stmt.kind {
+            MirStmtKind::Let { pattern, value, .. } => match &pattern.kind {
+                MirPatternKind::Var { name } => {
+                    let layout = self.layout_of(*value);
+                    let declared = self
+                        .exprs
+                        .get(value)
+                        .map_or(Repr::Unit, |expr| Repr::of(&expr.ty));
+                    let emitted = self.emit_expr(*value);
+                    let emitted = self.own(emitted);
+                    let repr = emitted.repr().unwrap_or(declared);
+                    let index = repr.val_type().map(|ty| {
+                        let index = self.new_local(ty);
+                        if emitted != Emitted::Diverged {
+                            self.body.push(Instr::LocalSet(index));
+                        }
+                        index
+                    });
+                    self.bind(
+                        name,
+                        Binding {
+                            index,
+                            repr,
+                            owned: emitted == Emitted::Owned,
+                            layout,
+                        },
+                    );
+                }
+                MirPatternKind::Wildcard => self.emit_discard(*value),
+                _ => self.emit_let_pattern(pattern, *value),
+            },
+            MirStmtKind::Expr { expr } => self.emit_discard(*expr),
+            MirStmtKind::Assign { target, value } => {
+                let binding = self.exprs.get(target).and_then(|expr| match &expr.kind {
+                    MirExprKind::Identifier { summary } => {
+                        self.lookup(&identifier_name(summary)).cloned()
+                    }
+                    _ => None,
+                });
+                match binding {
+                    Some(Binding {
+                        index: Some(index),
+                        repr,
+                        owned,
+                        ..
+                    }) => {
+                        self.emit_owned(*value, repr);
+                        if owned {
+                            self.release_locals(&[index]);
+                        }
+                        self.body.push(Instr::LocalSet(index));
+                    }
+                    Some(Binding { index: None, .. }) => self.emit_discard(*value),
+                    None => {
+                        self.fallback(FALLBACK_ASSIGN_TARGET, Some(*target), String::new());
+                        self.emit_discard(*value);
+                    }
+                }
+            }
+            MirStmtKind::Defer { expr } => {
+                self.fallback(FALLBACK_DEFER_STATEMENT, Some(*expr), String::new());
+            }
+        }
+    }
+
+    /// 分解束縛。束縛した変数は値を借用するため、値はスコープの終わりまで保持する。
+    /// 照合に失敗したら（反駁可能なパターン）実行を止める。
+    fn emit_let_pattern(&mut self, pattern: &MirPattern, value: MirExprId) {
+        let layout = self.layout_of(value);
+        let emitted = self.emit_expr(value);
+        let emitted = self.own(emitted);
+        let Some(repr) = emitted.repr() else {
+            return;
+        };
+        let local = repr.val_type().map(|ty| {
+            let local = self.new_local(ty);
+            self.body.push(Instr::LocalSet(local));
+            local
+        });
+        if emitted == Emitted::Owned {
+            if let Some(scope) = self.scopes.last_mut() {
+                scope.owned.extend(local);
+            }
+        }
+        let subject = Subject {
+            local,
+            repr,
+            layout,
+        };
+        self.body.push(Instr::Block(None));
+        self.body.push(Instr::Block(None));
+        self.emit_pattern(value, pattern, &subject, 0);
+        self.body.push(Instr::Br(1));
+        self.body.push(Instr::End);
+        self.body.push(Instr::Unreachable);
+        self.body.push(Instr::End);
+    }
+
+    fn emit_call(&mut self, id: MirExprId, callee: MirExprId, args: &[MirExprId]) -> Emitted {
+        let name = match self.exprs.get(&callee).map(|expr| &expr.kind) {
+            Some(MirExprKind::Identifier { summary }) => identifier_name(summary),
+            _ => return self.emit_closure_call(id, callee, args),
+        };
+        // ローカル変数の呼び出しはクロージャ呼び出し。
+        if self.lookup(&name).is_some() {
+            return self.emit_closure_call(id, callee, args);
+        }
+        let Some(target) = self.callees.get(&export_name(&name)).cloned() else {
+            if is_constructor_name(&name) {
+                return self.emit_constructor(id, &name, args);
+            }
+            return self.unsupported(FALLBACK_CALL, id, format!("callee={name}"));
+        };
+        if target.params.len() != args.len() {
+            return self.unsupported(
+                FALLBACK_CALL,
+                id,
+                format!(
+                    "callee={name} arity={} args={}",
+                    target.params.len(),
+                    args.len()
+                ),
+            );
+        }
+        let mut temps = Vec::new();
+        for (arg, param) in args.iter().zip(&target.params) {
+            // Unit の引数も `i32` の 0 で渡す。
+            let param = match param {
+                Repr::Unit => Repr::Int32,
+                param => *param,
+            };
+            self.emit_borrowed(*arg, param, &mut temps);
+        }
+        self.body.push(Instr::Call(target.index));
+        self.release_locals(&temps);
+        if target.result.is_heap() && target.owned_result {
+            Emitted::Owned
+        } else {
+            Emitted::Value(target.result)
+        }
+    }
+
+    /// 関数値の呼び出し。環境を第 1 引数に渡し、`code` の位置のテーブル要素を間接呼び出しする。
+    fn emit_closure_call(
+        &mut self,
+        id: MirExprId,
+        callee: MirExprId,
+        args: &[MirExprId],
+    ) -> Emitted {
+        let Some(Layout::Closure(params, result)) = self.layout_of(callee) else {
+            let ty = self
+                .exprs
+                .get(&callee)
+                .map(|expr| expr.ty.clone())
+                .unwrap_or_default();
+            return self.unsupported(FALLBACK_CALL, id, format!("closure type={ty}"));
+        };
+        if params.len() != args.len() {
+            return self.unsupported(
+                FALLBACK_CALL,
+                id,
+                format!("closure arity={} args={}", params.len(), args.len()),
+            );
+        }
+        let mut temps = Vec::new();
+        self.emit_borrowed(callee, Repr::Heap, &mut temps);
+        let closure = self.new_local(ValType::I32);
+        self.body.push(Instr::LocalTee(closure));
+        self.body.push(Instr::I32Load(MemArg::i32(0)));
+        for (arg, param) in args.iter().zip(&params) {
+            let param = match param {
+                Repr::Unit => Repr::Int32,
+                param => *param,
+            };
+            self.emit_borrowed(*arg, param, &mut temps);
+        }
+        self.body.push(Instr::LocalGet(closure));
+        self.body.push(Instr::I32Load(MemArg::i32(4)));
+        let signature: Vec<Repr> = std::iter::once(Repr::Heap).chain(params).collect();
+        self.body
+            .push(Instr::CallIndirect(signature_type(&signature, result)));
+        self.release_locals(&temps);
+        if result.is_heap() {
+            Emitted::Owned
+        } else {
+            Emitted::Value(result)
+        }
+    }
+}
+
+fn zero_const(ty: ValType) -> Instr {
+    match ty {
+        ValType::I32 => Instr::I32Const(0),
+        ValType::I64 => Instr::I64Const(0),
+        ValType::F64 => Instr::F64Const(0.0),
+    }
+}
+
+/// 二項演算子の命令と結果型。
+fn binary_instr(operator: &str, operand: ValType) -> Option<(Instr, ValType)> {
+    use ValType::{F64, I32, I64};
+    let instr = match (operand, operator) {
+        (I32, "+") => Instr::I32Add,
+        (I32, "-") => Instr::I32Sub,
+        (I32, "*") => Instr::I32Mul,
+        (I32, "/") => Instr::I32DivS,
+        (I32, "%") => Instr::I32RemS,
+        (I32, "==") => Instr::I32Eq,
+        (I32, "!=") => Instr::I32Ne,
+        (I32, "<") => Instr::I32LtS,
+        (I32, "<=") => Instr::I32LeS,
+        (I32, ">") => Instr::I32GtS,
+        (I32, ">=") => Instr::I32GeS,
+        (I64, "+") => Instr::I64Add,
+        (I64, "-") => Instr::I64Sub,
+        (I64, "*") => Instr::I64Mul,
+        (I64, "/") => Instr::I64DivS,
+        (I64, "%") => Instr::I64RemS,
+        (I64, "==") => Instr::I64Eq,
+        (I64, "!=") => Instr::I64Ne,
+        (I64, "<") => Instr::I64LtS,
+        (I64, "<=") => Instr::I64LeS,
+        (I64, ">") => Instr::I64GtS,
+        (I64, ">=") => Instr::I64GeS,
+        (F64, "+") => Instr::F64Add,
+        (F64, "-") => Instr::F64Sub,
+        (F64, "*") => Instr::F64Mul,
+        (F64, "/") => Instr::F64Div,
+        (F64, "==") => Instr::F64Eq,
+        (F64, "!=") => Instr::F64Ne,
+        (F64, "<") => Instr::F64Lt,
+        (F64, "<=") => Instr::F64Le,
+        (F64, ">") => Instr::F64Gt,
+        (F64, ">=") => Instr::F64Ge,
+        _ => return None,
+    };
+    let result = match operator {
+        "+" | "-" | "*" | "/" | "%" => operand,
+        _ => I32,
+    };
+    Some((instr, result))
+}
+
+fn expr_kind_name(kind: &MirExprKind) -> &'static str {
+    match kind {
+        MirExprKind::Literal { .. } => "literal",
+        MirExprKind::Identifier { .. } => "identifier",
+        MirExprKind::FieldAccess { .. } => "field_access",
+        MirExprKind::Index { .. } => "index",
+        MirExprKind::Call { .. } => "call",
+        MirExprKind::Lambda { .. } => "lambda",
+        MirExprKind::Rec { .. } => "rec",
+        MirExprKind::Block { .. } => "block",
+        MirExprKind::Return { .. } => "return",
+        MirExprKind::Propagate { .. } => "propagate",
+        MirExprKind::Panic { .. } => "panic",
+        MirExprKind::Binary { .. } => "binary",
+        MirExprKind::Match { .. } => "match",
+        MirExprKind::IfElse { .. } => "if_else",
+        MirExprKind::PerformCall { .. } => "perform_call",
+        MirExprKind::Handle { .. } => "handle",
+        MirExprKind::Resume { .. } => "resume",
+        MirExprKind::EffectBlock { .. } => "effect_block",
+        MirExprKind::Unsafe { .. } => "unsafe",
+        MirExprKind::InlineAsm { .. } => "inline_asm",
+        MirExprKind::LlvmIr { .. } => "llvm_ir",
+        MirExprKind::Unknown => "unknown",
+    }
+}
+
+fn pattern_kind_name(kind: &MirPatternKind) -> &'static str {
+    match kind {
+        MirPatternKind::Wildcard => "wildcard",
+        MirPatternKind::Var { .. } => "var",
+        MirPatternKind::Literal { .. } => "literal",
+        MirPatternKind::Tuple { .. } => "tuple",
+        MirPatternKind::Record { .. } => "record",
+        MirPatternKind::Constructor { .. } => "constructor",
+        MirPatternKind::Binding { .. } => "binding",
+        MirPatternKind::Or { .. } => "or",
+        MirPatternKind::Slice(_) => "slice",
+        MirPatternKind::Range { .. } => "range",
+        MirPatternKind::Regex { .. } => "regex",
+        MirPatternKind::Active(_) => "active",
+    }
+}
@@ -0,0 +1,337 @@
+//! WebAssembly モジュールの中間表現。
+//!
+//! MIR からのローアリング結果を保持し、バイナリ（`encoder`）と
+//! テキスト形式（`wat`）の双方へ同じ表現から書き出す。
+
+/// Wasm の値型。ポインタは wasm32 の線形メモリアドレスとして `I32` で表す。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValType {
+    I32,
+    I64,
+    F64,
+}
+
+impl ValType {
+    pub fn name(self) -> &'static str {
+        match self {
+            ValType::I32 => "i32",
+            ValType::I64 => "i64",
+            ValType::F64 => "f64",
+        }
+    }
+
+    pub(crate) fn code(self) -> u8 {
+        match self {
+            ValType::I32 => 0x7f,
+            ValType::I64 => 0x7e,
+            ValType::F64 => 0x7c,
+        }
+    }
+}
+
+/// 関数型。
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FuncType {
+    pub params: Vec<ValType>,
+    pub results: Vec<ValType>,
+}
+
+impl FuncType {
+    pub fn new(params: Vec<ValType>, results: Vec<ValType>) -> Self {
+        Self { params, results }
+    }
+}
+
+/// ロード/ストア命令のメモリ引数（`align` は 2 の冪の指数）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemArg {
+    pub align: u32,
+    pub offset: u32,
+}
+
+impl MemArg {
+    pub fn i32(offset: u32) -> Self {
+        Self { align: 2, offset }
+    }
+
+    pub fn i64(offset: u32) -> Self {
+        Self { align: 3, offset }
+    }
+
+    pub fn i8(offset: u32) -> Self {
+        Self { align: 0, offset }
+    }
+}
+
+/// 生成する命令の部分集合。
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instr {
+    Unreachable,
+    Nop,
+    Block(Option<ValType>),
+    Loop(Option<ValType>),
+    If(Option<ValType>),
+    Else,
+    End,
+    Br(u32),
+    BrIf(u32),
+    Return,
+    Call(u32),
+    /// テーブル 0 経由の間接呼び出し。オペランドの末尾がテーブル上の位置。
+    CallIndirect(FuncType),
+    Drop,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    I32Load(MemArg),
+    I64Load(MemArg),
+    F64Load(MemArg),
+    I32Load8U(MemArg),
+    I32Store(MemArg),
+    I64Store(MemArg),
+    F64Store(MemArg),
+    I32Store8(MemArg),
+    MemorySize,
+    MemoryGrow,
+    I32Const(i32),
+    I64Const(i64),
+    F64Const(f64),
+    I32Eqz,
+    I32Eq,
+    I32Ne,
+    I32LtS,
+    I32LtU,
+    I32GtS,
+    I32GtU,
+    I32LeS,
+    I32GeS,
+    I32GeU,
+    I64Eqz,
+    I64Eq,
+    I64Ne,
+    I64LtS,
+    I64GtS,
+    I64LeS,
+    I64GeS,
+    I64GeU,
+    F64Eq,
+    F64Ne,
+    F64Lt,
+    F64Gt,
+    F64Le,
+    F64Ge,
+    I32Add,
+    I32Sub,
+    I32Mul,
+    I32DivS,
+    I32RemS,
+    I32And,
+    I32Or,
+    I32Xor,
+    I32Shl,
+    I32ShrU,
+    I64Add,
+    I64Sub,
+    I64Mul,
+    I64DivS,
+    I64RemS,
+    I64And,
+    I64Or,
+    I64Xor,
+    I64Shl,
+    F64Neg,
+    F64Add,
+    F64Sub,
+    F64Mul,
+    F64Div,
+    I32WrapI64,
+    I64ExtendI32S,
+    I64ExtendI32U,
+}
+
+impl Instr {
+    /// 即値を持たない命令のオペコードと WAT ニーモニック。
+    pub(crate) fn plain(&self) -> Option<(u8, &'static str)> {
+        let entry = match self {
+            Instr::Unreachable => (0x00, "unreachable"),
+            Instr::Nop => (0x01, "nop"),
+            Instr::Else => (0x05, "else"),
+            Instr::End => (0x0b, "end"),
+            Instr::Return => (0x0f, "return"),
+            Instr::Drop => (0x1a, "drop"),
+            Instr::I32Eqz => (0x45, "i32.eqz"),
+            Instr::I32Eq => (0x46, "i32.eq"),
+            Instr::I32Ne => (0x47, "i32.ne"),
+            Instr::I32LtS => (0x48, "i32.lt_s"),
+            Instr::I32LtU => (0x49, "i32.lt_u"),
+            Instr::I32GtS => (0x4a, "i32.gt_s"),
+            Instr::I32GtU => (0x4b, "i32.gt_u"),
+            Instr::I32LeS => (0x4c, "i32.le_s"),
+            Instr::I32GeS => (0x4e, "i32.ge_s"),
+            Instr::I32GeU => (0x4f, "i32.ge_u"),
+            Instr::I64Eqz => (0x50, "i64.eqz"),
+            Instr::I64Eq => (0x51, "i64.eq"),
+            Instr::I64Ne => (0x52, "i64.ne"),
+            Instr::I64LtS => (0x53, "i64.lt_s"),
+            Instr::I64GtS => (0x55, "i64.gt_s"),
+            Instr::I64LeS => (0x57, "i64.le_s"),
+            Instr::I64GeS => (0x59, "i64.ge_s"),
+            Instr::I64GeU => (0x5a, "i64.ge_u"),
+            Instr::F64Eq => (0x61, "f64.eq"),
+            Instr::F64Ne => (0x62, "f64.ne"),
+            Instr::F64Lt => (0x63, "f64.lt"),
+            Instr::F64Gt => (0x64, "f64.gt"),
+            Instr::F64Le => (0x65, "f64.le"),
+            Instr::F64Ge => (0x66, "f64.ge"),
+            Instr::I32Add => (0x6a, "i32.add"),
+            Instr::I32Sub => (0x6b, "i32.sub"),
+            Instr::I32Mul => (0x6c, "i32.mul"),
+            Instr::I32DivS => (0x6d, "i32.div_s"),
+            Instr::I32RemS => (0x6f, "i32.rem_s"),
+            Instr::I32And => (0x71, "i32.and"),
+            Instr::I32Or => (0x72, "i32.or"),
+            Instr::I32Xor => (0x73, "i32.xor"),
+            Instr::I32Shl => (0x74, "i32.shl"),
+            Instr::I32ShrU => (0x76, "i32.shr_u"),
+            Instr::I64Add => (0x7c, "i64.add"),
+            Instr::I64Sub => (0x7d, "i64.sub"),
+            Instr::I64Mul => (0x7e, "i64.mul"),
+            Instr::I64DivS => (0x7f, "i64.div_s"),
+            Instr::I64RemS => (0x81, "i64.rem_s"),
+            Instr::I64And => (0x83, "i64.and"),
+            Instr::I64Or => (0x84, "i64.or"),
+            Instr::I64Xor => (0x85, "i64.xor"),
+            Instr::I64Shl => (0x86, "i64.shl"),
+            Instr::F64Neg => (0x9a, "f64.neg"),
+            Instr::F64Add => (0xa0, "f64.add"),
+            Instr::F64Sub => (0xa1, "f64.sub"),
+            Instr::F64Mul => (0xa2, "f64.mul"),
+            Instr::F64Div => (0xa3, "f64.div"),
+            Instr::I32WrapI64 => (0xa7, "i32.wrap_i64"),
+            Instr::I64ExtendI32S => (0xac, "i64.extend_i32_s"),
+            Instr::I64ExtendI32U => (0xad, "i64.extend_i32_u"),
+            _ => return None,
+        };
+        Some(entry)
+    }
+}
+
+/// 外部から取り込む関数（FFI extern の写像先）。
+#[derive(Clone, Debug, PartialEq)]
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    pub ty: FuncType,
+}
+
+/// モジュール内で定義する関数。
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub ty: FuncType,
+    /// 引数以降のローカル変数の型。
+    pub locals: Vec<ValType>,
+    /// 命令列（末尾の `end` は含めない）。
+    pub body: Vec<Instr>,
+    /// `Some` ならその名前でエクスポートする。
+    pub export: Option<String>,
+}
+
+impl Function {
+    pub fn new(name: impl Into<String>, ty: FuncType) -> Self {
+        Self {
+            name: name.into(),
+            ty,
+            locals: Vec::new(),
+            body: Vec::new(),
+            export: None,
+        }
+    }
+
+    pub fn with_export(mut self, name: impl Into<String>) -> Self {
+        self.export = Some(name.into());
+        self
+    }
+}
+
+/// `i32` のグローバル変数。
+#[derive(Clone, Debug, PartialEq)]
+pub struct Global {
+    pub name: String,
+    pub mutable: bool,
+    pub init: i32,
+}
+
+/// 線形メモリへ配置する初期化データ。
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataSegment {
+    pub offset: u32,
+    pub bytes: Vec<u8>,
+}
+
+/// Wasm モジュール全体。関数インデックスは取り込み関数の後に定義関数が続く。
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WasmModule {
+    pub name: String,
+    pub imports: Vec<Import>,
+    pub functions: Vec<Function>,
+    pub globals: Vec<Global>,
+    pub data: Vec<DataSegment>,
+    /// 関数テーブル（`funcref`）へ先頭から並べる関数インデックス。空ならテーブルを作らない。
+    pub table: Vec<u32>,
+    /// 線形メモリの初期ページ数（1 ページ = 64 KiB）。
+    pub memory_pages: u32,
+}
+
+impl WasmModule {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            memory_pages: 1,
+            ..Self::default()
+        }
+    }
+
+    /// 取り込み関数・定義関数・間接呼び出しの型を重複なく並べる。
+    pub fn types(&self) -> Vec<FuncType> {
+        let mut types: Vec<FuncType> = Vec::new();
+        let indirect = self
+            .functions
+            .iter()
+            .flat_map(|function| &function.body)
+            .filter_map(|instr| match instr {
+                Instr::CallIndirect(ty) => Some(ty),
+                _ => None,
+            });
+        let signatures = self
+            .imports
+            .iter()
+            .map(|import| &import.ty)
+            .chain(self.functions.iter().map(|function| &function.ty))
+            .chain(indirect);
+        for ty in signatures {
+            if !types.contains(ty) {
+                types.push(ty.clone());
+            }
+        }
+        types
+    }
+
+    pub(crate) fn type_index(types: &[FuncType], ty: &FuncType) -> u32 {
+        types
+            .iter()
+            .position(|candidate| candidate == ty)
+            .expect("型テーブルに登録済みであること") as u32
+    }
+
+    /// 関数インデックス空間での名前（取り込み関数を含む）。
+    pub fn function_names(&self) -> Vec<&str> {
+        self.imports
+            .iter()
+            .map(|import| import.name.as_str())
+            .chain(self.functions.iter().map(|function| function.name.as_str()))
+            .collect()
+    }
+}
@@ -0,0 +1,146 @@
+//! プラグイン呼び出し ABI（`--plugin-abi`）。
+//!
+//! `runtime` のプラグインホストは ABI v1 で次のエクスポートを使う。
+//!
+//! ```text
+//! reml_abi_version() -> i32            ABI の版数（1）
+//! alloc(len i32) -> ptr i32            入力バッファの確保
+//! dealloc(ptr i32, len i32)            出力バッファの返却
+//! <entry>(ptr i32, len i32) -> i64     (out_ptr << 32) | out_len
+//! ```
+//!
+//! 入力バッファの所有権は呼び出しでゲストへ移り、出力バッファはホストが `dealloc` で返す。
+//! `@export` 付きの `Str -> Str` 関数には、入力を文字列オブジェクトに包んで呼び出し、
+//! 結果の文字列を新しいバッファへ複写するアダプタを元の名前でエクスポートする。
+
+use reml_llvm_backend::MirFunction;
+
+use crate::module::{FuncType, Function, Instr, MemArg, ValType};
+use crate::runtime::{RuntimeFn, RuntimeIndices, TAG_STRING};
+
+/// 生成するモジュールが名乗る ABI の版数。
+pub const PLUGIN_ABI_VERSION: i32 = 1;
+
+pub const ABI_VERSION_EXPORT: &str = "reml_abi_version";
+pub const ALLOC_EXPORT: &str = "alloc";
+pub const DEALLOC_EXPORT: &str = "dealloc";
+
+/// ABI が予約するエクスポート名。
+pub(crate) const RESERVED_EXPORTS: [&str; 3] = [ABI_VERSION_EXPORT, ALLOC_EXPORT, DEALLOC_EXPORT];
+
+/// アダプタを生成するエントリポイント（`@export` かつ `Str -> Str`）か。
+pub(crate) fn is_plugin_entry(function: &MirFunction) -> bool {
+    function
+        .attributes
+        .iter()
+        .any(|attribute| attribute == "export")
+        && function.param_type_tokens.len() == 1
+        && is_string_token(&function.param_type_tokens[0])
+        && matches!(function.return_type_token.as_deref(), Some(token) if is_string_token(token))
+}
+
+fn is_string_token(token: &str) -> bool {
+    matches!(token.trim(), "Str" | "String")
+}
+
+/// `reml_abi_version`/`alloc`/`dealloc` を生成する。
+pub(crate) fn abi_functions(indices: RuntimeIndices) -> Vec<Function> {
+    use Instr::*;
+    use ValType::I32;
+    let mut version = Function::new(ABI_VERSION_EXPORT, FuncType::new(Vec::new(), vec![I32]))
+        .with_export(ABI_VERSION_EXPORT);
+    version.body = vec![I32Const(PLUGIN_ABI_VERSION)];
+
+    let mut alloc =
+        Function::new(ALLOC_EXPORT, FuncType::new(vec![I32], vec![I32])).with_export(ALLOC_EXPORT);
+    alloc.body = vec![LocalGet(0), Call(indices.index(RuntimeFn::MemAlloc))];
+
+    // 長さはアロケータのブロックヘッダが持つため使わない。
+    let mut dealloc = Function::new(DEALLOC_EXPORT, FuncType::new(vec![I32, I32], Vec::new()))
+        .with_export(DEALLOC_EXPORT);
+    dealloc.body = vec![LocalGet(0), Call(indices.index(RuntimeFn::MemFree))];
+
+    vec![version, alloc, dealloc]
+}
+
+/// `Str -> Str` の関数 `target` を ABI v1 で呼び出すアダプタ。
+pub(crate) fn entry_adapter(indices: RuntimeIndices, target: u32, export: &str) -> Function {
+    use Instr::*;
+    use ValType::{I32, I64};
+    let mem_alloc = indices.index(RuntimeFn::MemAlloc);
+    let mem_free = indices.index(RuntimeFn::MemFree);
+    // local 0: ptr, 1: len, 2: input, 3: result, 4: out, 5: out_len, 6: cursor
+    let mut adapter = Function::new(
+        format!("{export}.plugin_abi"),
+        FuncType::new(vec![I32, I32], vec![I64]),
+    )
+    .with_export(export);
+    adapter.locals = vec![I32; 5];
+    adapter.body = vec![
+        // 入力バッファを指す文字列オブジェクト {data i32, len i64 @8}
+        I32Const(16),
+        Call(mem_alloc),
+        LocalTee(2),
+        I32Const(TAG_STRING),
+        Call(indices.index(RuntimeFn::SetTypeTag)),
+        LocalGet(2),
+        LocalGet(0),
+        I32Store(MemArg::i32(0)),
+        LocalGet(2),
+        LocalGet(1),
+        I64ExtendI32U,
+        I64Store(MemArg::i64(8)),
+        LocalGet(2),
+        Call(target),
+        LocalSet(3),
+        // 結果を新しいバッファへ複写する（結果が入力を指していても安全なよう先に複写する）。
+        LocalGet(3),
+        I64Load(MemArg::i64(8)),
+        I32WrapI64,
+        LocalTee(5),
+        Call(mem_alloc),
+        LocalSet(4),
+        Block(None),
+        Loop(None),
+        LocalGet(6),
+        LocalGet(5),
+        I32GeU,
+        BrIf(1),
+        LocalGet(4),
+        LocalGet(6),
+        I32Add,
+        LocalGet(3),
+        I32Load(MemArg::i32(0)),
+        LocalGet(6),
+        I32Add,
+        I32Load8U(MemArg::i8(0)),
+        I32Store8(MemArg::i8(0)),
+        LocalGet(6),
+        I32Const(1),
+        I32Add,
+        LocalSet(6),
+        Br(0),
+        End,
+        End,
+        // 結果が別オブジェクトならそれを解放し、入力側は文字列オブジェクトとバッファを返却する。
+        LocalGet(3),
+        LocalGet(2),
+        I32Ne,
+        If(None),
+        LocalGet(3),
+        Call(indices.index(RuntimeFn::DecRef)),
+        End,
+        LocalGet(2),
+        Call(mem_free),
+        LocalGet(0),
+        Call(mem_free),
+        LocalGet(4),
+        I64ExtendI32U,
+        I64Const(32),
+        I64Shl,
+        LocalGet(5),
+        I64ExtendI32U,
+        I64Or,
+    ];
+    adapter
+}
@@ -0,0 +1,622 @@
+//! 線形メモリ上のアロケータと参照カウント。
+//!
+//! `runtime/native` の `mem_alloc`/`inc_ref`/`dec_ref` と同じ契約を wasm 関数として
+//! モジュール内に生成する。オブジェクトは native と同じく
+//! `[refcount u32][type_tag u32]` のヘッダをペイロード直前 8 バイトに持ち、
+//! その手前にアロケータ用の `[size u32][next_free u32]` を置く。
+//!
+//! ```text
+//! block + 0   size       ペイロードの確保バイト数（8 の倍数）
+//! block + 4   next_free  フリーリストの次ブロック
+//! block + 8   refcount   ← ヘッダ（native と同じ並び）
+//! block + 12  type_tag
+//! block + 16  payload    ← mem_alloc が返すポインタ
+//! ```
+//!
+//! ペイロードのレイアウトは wasm32（ポインタ 4 バイト）での native 構造体と一致させる。
+//! 文字列は `{data i32, len i64 @8}`、タプル/レコード/配列は `{len i64, items i32 @8}`、
+//! Set は `{len i64, capacity i64, items i32 @16}`、クロージャは `{env i32, code i32}`、
+//! ADT は `{tag i32, payload i32}`。コンテナの要素はボックス化した値へのポインタで、
+//! 整数は `i64`、浮動小数点は `f64`、真偽値は 1 バイト、文字は `u32` をペイロードに持つ。
+
+use crate::module::{DataSegment, FuncType, Function, Global, Instr, MemArg, ValType};
+
+/// ホストがペイロード受け渡しに使う先頭領域。静的データとヒープはこの後ろに置く。
+///
+/// プラグインホストは入力をオフセット 0 から書き込むため、その範囲を避ける。
+pub const HOST_SCRATCH_BYTES: u32 = 1024;
+
+/// ブロック先頭からペイロードまでのバイト数（アロケータ 8 + ヘッダ 8）。
+pub const BLOCK_PREFIX_BYTES: u32 = 16;
+
+const PAGE_SHIFT: i32 = 16;
+
+pub const TAG_INT: i32 = 1;
+pub const TAG_FLOAT: i32 = 2;
+pub const TAG_BOOL: i32 = 3;
+pub const TAG_STRING: i32 = 4;
+pub const TAG_TUPLE: i32 = 5;
+pub const TAG_RECORD: i32 = 6;
+pub const TAG_CLOSURE: i32 = 7;
+pub const TAG_ADT: i32 = 8;
+pub const TAG_SET: i32 = 9;
+pub const TAG_CHAR: i32 = 10;
+pub const TAG_ARRAY: i32 = 11;
+
+pub(crate) const GLOBAL_HEAP_BASE: u32 = 0;
+pub(crate) const GLOBAL_HEAP_PTR: u32 = 1;
+pub(crate) const GLOBAL_FREE_LIST: u32 = 2;
+
+/// モジュールに生成するランタイム関数。並び順がそのまま関数インデックスになる。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RuntimeFn {
+    MemAlloc,
+    MemFree,
+    IncRef,
+    DecRef,
+    SetTypeTag,
+    GetTypeTag,
+    InitBlock,
+    ReleaseItems,
+    BoxI64,
+    BoxFloat,
+    BoxBool,
+    BoxChar,
+}
+
+impl RuntimeFn {
+    pub(crate) const ALL: [RuntimeFn; 12] = [
+        RuntimeFn::MemAlloc,
+        RuntimeFn::MemFree,
+        RuntimeFn::IncRef,
+        RuntimeFn::DecRef,
+        RuntimeFn::SetTypeTag,
+        RuntimeFn::GetTypeTag,
+        RuntimeFn::InitBlock,
+        RuntimeFn::ReleaseItems,
+        RuntimeFn::BoxI64,
+        RuntimeFn::BoxFloat,
+        RuntimeFn::BoxBool,
+        RuntimeFn::BoxChar,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            RuntimeFn::MemAlloc => "mem_alloc",
+            RuntimeFn::MemFree => "mem_free",
+            RuntimeFn::IncRef => "inc_ref",
+            RuntimeFn::DecRef => "dec_ref",
+            RuntimeFn::SetTypeTag => "reml_set_type_tag",
+            RuntimeFn::GetTypeTag => "reml_get_type_tag",
+            RuntimeFn::InitBlock => "reml_init_block",
+            RuntimeFn::ReleaseItems => "reml_release_items",
+            RuntimeFn::BoxI64 => "reml_box_i64",
+            RuntimeFn::BoxFloat => "reml_box_float",
+            RuntimeFn::BoxBool => "reml_box_bool",
+            RuntimeFn::BoxChar => "reml_box_char",
+        }
+    }
+
+    /// ホストへエクスポートするか（内部ヘルパは公開しない）。
+    fn exported(self) -> bool {
+        !matches!(self, RuntimeFn::InitBlock | RuntimeFn::ReleaseItems)
+    }
+
+    fn ty(self) -> FuncType {
+        use ValType::{F64, I32, I64};
+        match self {
+            RuntimeFn::MemAlloc
+            | RuntimeFn::GetTypeTag
+            | RuntimeFn::InitBlock
+            | RuntimeFn::BoxBool
+            | RuntimeFn::BoxChar => FuncType::new(vec![I32], vec![I32]),
+            RuntimeFn::BoxI64 => FuncType::new(vec![I64], vec![I32]),
+            RuntimeFn::BoxFloat => FuncType::new(vec![F64], vec![I32]),
+            RuntimeFn::MemFree | RuntimeFn::IncRef | RuntimeFn::DecRef => {
+                FuncType::new(vec![I32], Vec::new())
+            }
+            RuntimeFn::SetTypeTag | RuntimeFn::ReleaseItems => {
+                FuncType::new(vec![I32, I32], Vec::new())
+            }
+        }
+    }
+}
+
+/// ランタイム関数のインデックス解決。取り込み関数の直後に並ぶ。
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RuntimeIndices {
+    base: u32,
+}
+
+impl RuntimeIndices {
+    pub(crate) fn new(import_count: u32) -> Self {
+        Self { base: import_count }
+    }
+
+    pub(crate) fn index(self, function: RuntimeFn) -> u32 {
+        let position = RuntimeFn::ALL
+            .iter()
+            .position(|candidate| *candidate == function)
+            .expect("ランタイム関数表に登録済みであること");
+        self.base + position as u32
+    }
+
+    /// ランタイム関数の後に続く最初のインデックス。
+    pub(crate) fn end(self) -> u32 {
+        self.base + RuntimeFn::ALL.len() as u32
+    }
+}
+
+/// ヒープ管理用のグローバル変数（`__heap_base`/`__heap_ptr`/`__free_list`）。
+pub(crate) fn runtime_globals(heap_base: u32) -> Vec<Global> {
+    vec![
+        Global {
+            name: "__heap_base".into(),
+            mutable: false,
+            init: heap_base as i32,
+        },
+        Global {
+            name: "__heap_ptr".into(),
+            mutable: true,
+            init: heap_base as i32,
+        },
+        Global {
+            name: "__free_list".into(),
+            mutable: true,
+            init: 0,
+        },
+    ]
+}
+
+/// ランタイム関数を生成する。
+pub(crate) fn runtime_functions(indices: RuntimeIndices) -> Vec<Function> {
+    RuntimeFn::ALL
+        .iter()
+        .map(|function| {
+            let (locals, body) = match function {
+                RuntimeFn::MemAlloc => mem_alloc_body(indices),
+                RuntimeFn::MemFree => mem_free_body(),
+                RuntimeFn::IncRef => inc_ref_body(),
+                RuntimeFn::DecRef => dec_ref_body(indices),
+                RuntimeFn::SetTypeTag => set_type_tag_body(),
+                RuntimeFn::GetTypeTag => get_type_tag_body(),
+                RuntimeFn::InitBlock => init_block_body(),
+                RuntimeFn::ReleaseItems => release_items_body(indices),
+                RuntimeFn::BoxI64 => box_body(indices, TAG_INT, 8, Instr::I64Store(MemArg::i64(0))),
+                RuntimeFn::BoxFloat => {
+                    box_body(indices, TAG_FLOAT, 8, Instr::F64Store(MemArg::i64(0)))
+                }
+                RuntimeFn::BoxBool => {
+                    box_body(indices, TAG_BOOL, 1, Instr::I32Store8(MemArg::i8(0)))
+                }
+                RuntimeFn::BoxChar => {
+                    box_body(indices, TAG_CHAR, 4, Instr::I32Store(MemArg::i32(0)))
+                }
+            };
+            let mut generated = Function::new(function.name(), function.ty());
+            generated.locals = locals;
+            generated.body = body;
+            if function.exported() {
+                generated = generated.with_export(function.name());
+            }
+            generated
+        })
+        .collect()
+}
+
+/// 静的領域へ置く不変オブジェクト（文字列リテラル）の配置。
+///
+/// 静的オブジェクトも通常のブロックと同じヘッダを持つが `__heap_base` より下にあるため、
+/// `inc_ref`/`dec_ref`/`mem_free` は何もしない。
+#[derive(Clone, Debug)]
+pub(crate) struct StaticData {
+    next: u32,
+    strings: Vec<(String, u32)>,
+    segments: Vec<DataSegment>,
+}
+
+impl StaticData {
+    pub(crate) fn new() -> Self {
+        Self {
+            next: HOST_SCRATCH_BYTES,
+            strings: Vec::new(),
+            segments: Vec::new(),
+        }
+    }
+
+    /// 文字列オブジェクトを配置し、ペイロードのアドレスを返す。同じ内容は共有する。
+    pub(crate) fn intern_string(&mut self, value: &str) -> u32 {
+        if let Some((_, address)) = self.strings.iter().find(|(text, _)| text == value) {
+            return *address;
+        }
+        let block = self.next;
+        let payload = block + BLOCK_PREFIX_BYTES;
+        let data = payload + 16;
+        let payload_size = align8(16 + value.len() as u32);
+        let mut bytes = Vec::with_capacity(32 + value.len());
+        bytes.extend_from_slice(&payload_size.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&(TAG_STRING as u32).to_le_bytes());
+        bytes.extend_from_slice(&data.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(value.as_bytes());
+        self.next = align8(block + bytes.len() as u32);
+        self.segments.push(DataSegment {
+            offset: block,
+            bytes,
+        });
+        self.strings.push((value.to_string(), payload));
+        payload
+    }
+
+    /// ヒープの開始アドレス（静的領域の直後）。
+    pub(crate) fn heap_base(&self) -> u32 {
+        self.next
+    }
+
+    pub(crate) fn into_segments(self) -> Vec<DataSegment> {
+        self.segments
+    }
+}
+
+/// 静的領域を収めるのに必要な初期ページ数。
+pub(crate) fn initial_pages(heap_base: u32) -> u32 {
+    let page = 1u32 << PAGE_SHIFT;
+    heap_base.div_ceil(page).max(1)
+}
+
+fn align8(value: u32) -> u32 {
+    (value + 7) & !7
+}
+
+/// `mem_alloc(size) -> ptr`: フリーリストを先頭から探し、なければヒープ末尾から切り出す。
+fn mem_alloc_body(indices: RuntimeIndices) -> (Vec<ValType>, Vec<Instr>) {
+    use Instr::*;
+    // local 0: size, 1: aligned, 2: block, 3: prev, 4: end
+    let locals = vec![ValType::I32; 4];
+    let body = vec![
+        LocalGet(0),
+        I32Const(7),
+        I32Add,
+        I32Const(-8),
+        I32And,
+        LocalSet(1),
+        GlobalGet(GLOBAL_FREE_LIST),
+        LocalSet(2),
+        I32Const(0),
+        LocalSet(3),
+        Block(None),
+        Loop(None),
+        LocalGet(2),
+        I32Eqz,
+        BrIf(1),
+        LocalGet(2),
+        I32Load(MemArg::i32(0)),
+        LocalGet(1),
+        I32GeU,
+        If(None),
+        LocalGet(3),
+        I32Eqz,
+        If(None),
+        LocalGet(2),
+        I32Load(MemArg::i32(4)),
+        GlobalSet(GLOBAL_FREE_LIST),
+        Else,
+        LocalGet(3),
+        LocalGet(2),
+        I32Load(MemArg::i32(4)),
+        I32Store(MemArg::i32(4)),
+        End,
+        LocalGet(2),
+        Call(indices.index(RuntimeFn::InitBlock)),
+        Return,
+        End,
+        LocalGet(2),
+        LocalSet(3),
+        LocalGet(2),
+        I32Load(MemArg::i32(4)),
+        LocalSet(2),
+        Br(0),
+        End,
+        End,
+        GlobalGet(GLOBAL_HEAP_PTR),
+        LocalSet(2),
+        LocalGet(2),
+        I32Const(BLOCK_PREFIX_BYTES as i32),
+        I32Add,
+        LocalGet(1),
+        I32Add,
+        LocalSet(4),
+        LocalGet(4),
+        MemorySize,
+        I32Const(PAGE_SHIFT),
+        I32Shl,
+        I32GtU,
+        If(None),
+        LocalGet(4),
+        MemorySize,
+        I32Const(PAGE_SHIFT),
+        I32Shl,
+        I32Sub,
+        I32Const((1 << PAGE_SHIFT) - 1),
+        I32Add,
+        I32Const(PAGE_SHIFT),
+        I32ShrU,
+        MemoryGrow,
+        I32Const(-1),
+        I32Eq,
+        If(None),
+        Unreachable,
+        End,
+        End,
+        LocalGet(4),
+        GlobalSet(GLOBAL_HEAP_PTR),
+        LocalGet(2),
+        LocalGet(1),
+        I32Store(MemArg::i32(0)),
+        LocalGet(2),
+        Call(indices.index(RuntimeFn::InitBlock)),
+    ];
+    (locals, body)
+}
+
+/// `reml_init_block(block) -> ptr`: ヘッダを refcount=1 で初期化し、ペイロードをゼロ埋めする。
+fn init_block_body() -> (Vec<ValType>, Vec<Instr>) {
+    use Instr::*;
+    // local 0: block, 1: cursor, 2: end
+    let locals = vec![ValType::I32; 2];
+    let body = vec![
+        LocalGet(0),
+        I32Const(0),
+        I32Store(MemArg::i32(4)),
+        LocalGet(0),
+        I32Const(1),
+        I32Store(MemArg::i32(8)),
+        LocalGet(0),
+        I32Const(0),
+        I32Store(MemArg::i32(12)),
+        LocalGet(0),
+        I32Const(BLOCK_PREFIX_BYTES as i32),
+        I32Add,
+        LocalTee(1),
+        LocalGet(0),
+        I32Load(MemArg::i32(0)),
+        I32Add,
+        LocalSet(2),
+        Block(None),
+        Loop(None),
+        LocalGet(1),
+        LocalGet(2),
+        I32GeU,
+        BrIf(1),
+        LocalGet(1),
+        I64Const(0),
+        I64Store(MemArg::i64(0)),
+        LocalGet(1),
+        I32Const(8),
+        I32Add,
+        LocalSet(1),
+        Br(0),
+        End,
+        End,
+        LocalGet(0),
+        I32Const(BLOCK_PREFIX_BYTES as i32),
+        I32Add,
+    ];
+    (locals, body)
+}
+
+/// 静的領域（`__heap_base` 未満）と NULL を除外するガード。
+fn static_guard() -> Vec<Instr> {
+    vec![
+        Instr::LocalGet(0),
+        Instr::GlobalGet(GLOBAL_HEAP_BASE),
+        Instr::I32LtU,
+        Instr::If(None),
+        Instr::Return,
+        Instr::End,
+    ]
+}
+
+/// `mem_free(ptr)`: ブロックをフリーリストの先頭へ戻す。
+fn mem_free_body() -> (Vec<ValType>, Vec<Instr>) {
+    use Instr::*;
+    // local 0: ptr, 1: block
+    let mut body = static_guard();
+    body.extend([
+        LocalGet(0),
+        I32Const(BLOCK_PREFIX_BYTES as i32),
+        I32Sub,
+        LocalTee(1),
+        GlobalGet(GLOBAL_FREE_LIST),
+        I32Store(MemArg::i32(4)),
+        LocalGet(1),
+        GlobalSet(GLOBAL_FREE_LIST),
+    ]);
+    (vec![ValType::I32], body)
+}
+
+/// `inc_ref(ptr)`。
+fn inc_ref_body() -> (Vec<ValType>, Vec<Instr>) {
+    use Instr::*;
+    // local 0: ptr, 1: block
+    let mut body = static_guard();
+    body.extend([
+        LocalGet(0),
+        I32Const(BLOCK_PREFIX_BYTES as i32),
+        I32Sub,
+        LocalTee(1),
+        LocalGet(1),
+        I32Load(MemArg::i32(8)),
+        I32Const(1),
+        I32Add,
+        I32Store(MemArg::i32(8)),
+    ]);
+    (vec![ValType::I32], body)
+}
+
+/// `dec_ref(ptr)`: 0 になったら型タグで子オブジェクトを解放してからブロックを返却する。
+fn dec_ref_body(indices: RuntimeIndices) -> (Vec<ValType>, Vec<Instr>) {
+    use Instr::*;
+    // local 0: ptr, 1: block, 2: refcount, 3: tag
+    let release_items = indices.index(RuntimeFn::ReleaseItems);
+    let dec_ref = indices.index(RuntimeFn::DecRef);
+    let mut body = static_guard();
+    body.extend([
+        LocalGet(0),
+        I32Const(BLOCK_PREFIX_BYTES as i32),
+        I32Sub,
+        LocalTee(1),
+        LocalGet(1),
+        I32Load(MemArg::i32(8)),
+        I32Const(1),
+        I32Sub,
+        LocalTee(2),
+        I32Store(MemArg::i32(8)),
+        LocalGet(2),
+        If(None),
+        Return,
+        End,
+        LocalGet(1),
+        I32Load(MemArg::i32(12)),
+        LocalSet(3),
+        // タプル/レコード/配列: {len i64, items i32 @8}
+        LocalGet(3),
+        I32Const(TAG_TUPLE),
+        I32Eq,
+        LocalGet(3),
+        I32Const(TAG_RECORD),
+        I32Eq,
+        I32Or,
+        LocalGet(3),
+        I32Const(TAG_ARRAY),
+        I32Eq,
+        I32Or,
+        If(None),
+        LocalGet(0),
+        I32Load(MemArg::i32(8)),
+        LocalGet(0),
+        I64Load(MemArg::i64(0)),
+        I32WrapI64,
+        Call(release_items),
+        End,
+        // Set: {len i64, capacity i64, items i32 @16}
+        LocalGet(3),
+        I32Const(TAG_SET),
+        I32Eq,
+        If(None),
+        LocalGet(0),
+        I32Load(MemArg::i32(16)),
+        LocalGet(0),
+        I64Load(MemArg::i64(0)),
+        I32WrapI64,
+        Call(release_items),
+        End,
+        // クロージャ: env
+        LocalGet(3),
+        I32Const(TAG_CLOSURE),
+        I32Eq,
+        If(None),
+        LocalGet(0),
+        I32Load(MemArg::i32(0)),
+        Call(dec_ref),
+        End,
+        // ADT: payload
+        LocalGet(3),
+        I32Const(TAG_ADT),
+        I32Eq,
+        If(None),
+        LocalGet(0),
+        I32Load(MemArg::i32(4)),
+        Call(dec_ref),
+        End,
+        LocalGet(0),
+        Call(indices.index(RuntimeFn::MemFree)),
+    ]);
+    (vec![ValType::I32; 3], body)
+}
+
+/// `reml_release_items(items, len)`: 要素を dec_ref し、要素配列を解放する。
+fn release_items_body(indices: RuntimeIndices) -> (Vec<ValType>, Vec<Instr>) {
+    use Instr::*;
+    // local 0: items, 1: len, 2: cursor, 3: end
+    let body = vec![
+        LocalGet(0),
+        I32Eqz,
+        If(None),
+        Return,
+        End,
+        LocalGet(0),
+        LocalSet(2),
+        LocalGet(0),
+        LocalGet(1),
+        I32Const(2),
+        I32Shl,
+        I32Add,
+        LocalSet(3),
+        Block(None),
+        Loop(None),
+        LocalGet(2),
+        LocalGet(3),
+        I32GeU,
+        BrIf(1),
+        LocalGet(2),
+        I32Load(MemArg::i32(0)),
+        Call(indices.index(RuntimeFn::DecRef)),
+        LocalGet(2),
+        I32Const(4),
+        I32Add,
+        LocalSet(2),
+        Br(0),
+        End,
+        End,
+        LocalGet(0),
+        Call(indices.index(RuntimeFn::MemFree)),
+    ];
+    (vec![ValType::I32; 2], body)
+}
+
+/// `reml_box_*(value) -> ptr`: 値を型タグ付きのヒープオブジェクトへ包む。
+fn box_body(
+    indices: RuntimeIndices,
+    tag: i32,
+    size: i32,
+    store: Instr,
+) -> (Vec<ValType>, Vec<Instr>) {
+    use Instr::*;
+    // local 0: value, 1: ptr
+    let body = vec![
+        I32Const(size),
+        Call(indices.index(RuntimeFn::MemAlloc)),
+        LocalTee(1),
+        I32Const(tag),
+        Call(indices.index(RuntimeFn::SetTypeTag)),
+        LocalGet(1),
+        LocalGet(0),
+        store,
+        LocalGet(1),
+    ];
+    (vec![ValType::I32], body)
+}
+
+/// `reml_set_type_tag(ptr, tag)`。
+fn set_type_tag_body() -> (Vec<ValType>, Vec<Instr>) {
+    use Instr::*;
+    let body = vec![
+        LocalGet(0),
+        I32Const(4),
+        I32Sub,
+        LocalGet(1),
+        I32Store(MemArg::i32(0)),
+    ];
+    (Vec::new(), body)
+}
+
+/// `reml_get_type_tag(ptr) -> tag`。
+fn get_type_tag_body() -> (Vec<ValType>, Vec<Instr>) {
+    use Instr::*;
+    let body = vec![LocalGet(0), I32Const(4), I32Sub, I32Load(MemArg::i32(0))];
+    (Vec::new(), body)
+}
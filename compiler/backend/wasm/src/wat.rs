@@ -0,0 +1,229 @@
+//! WebAssembly テキスト形式（WAT）のダンプ。
+//!
+//! バイナリと同じ `WasmModule` から生成するため、`wat2wasm` 等で組み立て直すと
+//! 同じ構造のモジュールになる。関数は `$名前` で参照し、ローカルはインデックスで示す。
+
+use std::fmt::Write;
+
+use crate::encoder::MEMORY_EXPORT;
+use crate::module::{FuncType, Function, Instr, MemArg, ValType, WasmModule};
+
+/// モジュールを WAT 文字列へ書き出す。
+pub fn render_wat(module: &WasmModule) -> String {
+    let names: Vec<String> = module
+        .function_names()
+        .into_iter()
+        .map(sanitize_identifier)
+        .collect();
+    let mut out = String::new();
+    let _ = writeln!(out, "(module ${}", sanitize_identifier(&module.name));
+
+    for (index, import) in module.imports.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "  (import \"{}\" \"{}\" (func ${}{}))",
+            import.module,
+            import.name,
+            names[index],
+            render_signature(&import.ty)
+        );
+    }
+
+    if !module.table.is_empty() {
+        let _ = writeln!(out, "  (table {} funcref)", module.table.len());
+    }
+
+    let _ = writeln!(
+        out,
+        "  (memory (export \"{MEMORY_EXPORT}\") {})",
+        module.memory_pages
+    );
+
+    for global in &module.globals {
+        let ty = if global.mutable {
+            "(mut i32)".to_string()
+        } else {
+            "i32".to_string()
+        };
+        let _ = writeln!(
+            out,
+            "  (global ${} {ty} (i32.const {}))",
+            sanitize_identifier(&global.name),
+            global.init
+        );
+    }
+
+    let offset = module.imports.len();
+    for (index, function) in module.functions.iter().enumerate() {
+        render_function(&mut out, module, function, &names[offset + index], &names);
+    }
+
+    if !module.table.is_empty() {
+        let elements: Vec<String> = module
+            .table
+            .iter()
+            .map(|index| format!("${}", names[*index as usize]))
+            .collect();
+        let _ = writeln!(out, "  (elem (i32.const 0) func {})", elements.join(" "));
+    }
+
+    for segment in &module.data {
+        let _ = writeln!(
+            out,
+            "  (data (i32.const {}) \"{}\")",
+            segment.offset,
+            escape_bytes(&segment.bytes)
+        );
+    }
+    out.push_str(")\n");
+    out
+}
+
+fn render_function(
+    out: &mut String,
+    module: &WasmModule,
+    function: &Function,
+    name: &str,
+    names: &[String],
+) {
+    let _ = write!(out, "  (func ${name}");
+    if let Some(export) = &function.export {
+        let _ = write!(out, " (export \"{export}\")");
+    }
+    out.push_str(&render_signature(&function.ty));
+    out.push('\n');
+    if !function.locals.is_empty() {
+        let locals: Vec<&str> = function.locals.iter().map(|ty| ty.name()).collect();
+        let _ = writeln!(out, "    (local {})", locals.join(" "));
+    }
+    let mut depth = 2usize;
+    for instr in &function.body {
+        if matches!(instr, Instr::End | Instr::Else) {
+            depth = depth.saturating_sub(1);
+        }
+        let _ = writeln!(
+            out,
+            "{}{}",
+            "  ".repeat(depth),
+            render_instr(module, instr, names)
+        );
+        if matches!(
+            instr,
+            Instr::Block(_) | Instr::Loop(_) | Instr::If(_) | Instr::Else
+        ) {
+            depth += 1;
+        }
+    }
+    out.push_str("  )\n");
+}
+
+fn render_signature(ty: &FuncType) -> String {
+    let mut text = String::new();
+    if !ty.params.is_empty() {
+        let params: Vec<&str> = ty.params.iter().map(|ty| ty.name()).collect();
+        let _ = write!(text, " (param {})", params.join(" "));
+    }
+    if !ty.results.is_empty() {
+        let results: Vec<&str> = ty.results.iter().map(|ty| ty.name()).collect();
+        let _ = write!(text, " (result {})", results.join(" "));
+    }
+    text
+}
+
+fn render_instr(module: &WasmModule, instr: &Instr, names: &[String]) -> String {
+    if let Some((_, mnemonic)) = instr.plain() {
+        return mnemonic.to_string();
+    }
+    match instr {
+        Instr::Block(ty) => render_block("block", *ty),
+        Instr::Loop(ty) => render_block("loop", *ty),
+        Instr::If(ty) => render_block("if", *ty),
+        Instr::Br(depth) => format!("br {depth}"),
+        Instr::BrIf(depth) => format!("br_if {depth}"),
+        Instr::Call(index) => match names.get(*index as usize) {
+            Some(name) => format!("call ${name}"),
+            None => format!("call {index}"),
+        },
+        Instr::CallIndirect(ty) => format!("call_indirect{}", render_signature(ty)),
+        Instr::LocalGet(index) => format!("local.get {index}"),
+        Instr::LocalSet(index) => format!("local.set {index}"),
+        Instr::LocalTee(index) => format!("local.tee {index}"),
+        Instr::GlobalGet(index) => format!("global.get {}", global_ref(module, *index)),
+        Instr::GlobalSet(index) => format!("global.set {}", global_ref(module, *index)),
+        Instr::I32Load(arg) => render_memory("i32.load", *arg, 2),
+        Instr::I64Load(arg) => render_memory("i64.load", *arg, 3),
+        Instr::F64Load(arg) => render_memory("f64.load", *arg, 3),
+        Instr::I32Load8U(arg) => render_memory("i32.load8_u", *arg, 0),
+        Instr::I32Store(arg) => render_memory("i32.store", *arg, 2),
+        Instr::I64Store(arg) => render_memory("i64.store", *arg, 3),
+        Instr::F64Store(arg) => render_memory("f64.store", *arg, 3),
+        Instr::I32Store8(arg) => render_memory("i32.store8", *arg, 0),
+        Instr::MemorySize => "memory.size".to_string(),
+        Instr::MemoryGrow => "memory.grow".to_string(),
+        Instr::I32Const(value) => format!("i32.const {value}"),
+        Instr::I64Const(value) => format!("i64.const {value}"),
+        Instr::F64Const(value) => format!("f64.const {value:?}"),
+        _ => unreachable!("即値なし命令は plain() で処理済み: {instr:?}"),
+    }
+}
+
+fn render_block(keyword: &str, ty: Option<ValType>) -> String {
+    match ty {
+        Some(ty) => format!("{keyword} (result {})", ty.name()),
+        None => keyword.to_string(),
+    }
+}
+
+fn render_memory(mnemonic: &str, arg: MemArg, natural_align: u32) -> String {
+    let mut text = mnemonic.to_string();
+    if arg.offset != 0 {
+        let _ = write!(text, " offset={}", arg.offset);
+    }
+    if arg.align != natural_align {
+        let _ = write!(text, " align={}", 1u32 << arg.align);
+    }
+    text
+}
+
+fn global_ref(module: &WasmModule, index: u32) -> String {
+    match module.globals.get(index as usize) {
+        Some(global) => format!("${}", sanitize_identifier(&global.name)),
+        None => index.to_string(),
+    }
+}
+
+/// WAT の識別子に使えない文字を `_` へ置き換える。
+fn sanitize_identifier(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || "!#$%&'*+-./:<=>?@\\^_`|~".contains(ch) {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if sanitized.is_empty() {
+        "_".to_string()
+    } else {
+        sanitized
+    }
+}
+
+fn escape_bytes(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' => {
+                text.push('\\');
+                text.push(*byte as char);
+            }
+            0x20..=0x7e => text.push(*byte as char),
+            _ => {
+                let _ = write!(text, "\\{byte:02x}");
+            }
+        }
+    }
+    text
+}
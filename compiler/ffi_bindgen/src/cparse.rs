@@ -0,0 +1,1162 @@
+//! 前処理済みトークン列から C の宣言を解析する。
+//!
+//! 関数プロトタイプ・typedef・構造体/共用体・列挙型を型環境へ登録し、
+//! 出力できない宣言は理由付きの `Item::Skipped` として残す。
+
+use crate::ctype::{
+  CType, EnumDef, FieldDef, FnType, Param, RecordDef, RecordKind, ResolvedType, TypeEnv, TypeRef,
+  TypedefDef,
+};
+use crate::expr::{self, IntValue};
+use crate::lexer::{render_tokens, Token, TokenKind};
+
+const UNKNOWN_TYPE_CODE: &str = "ffi.bindgen.unknown_type";
+const PARSE_FAILED_CODE: &str = "ffi.bindgen.parse_failed";
+pub const SKIPPED_CODE: &str = "ffi.bindgen.skipped";
+
+/// 解析結果の宣言（出現順）。
+#[derive(Debug, Clone)]
+pub enum Item {
+  Function {
+    name: String,
+    function: FnType,
+    file: usize,
+  },
+  Typedef {
+    name: String,
+  },
+  Record {
+    tag: String,
+  },
+  Enum {
+    tag: String,
+  },
+  Skipped(Skipped),
+}
+
+#[derive(Debug, Clone)]
+pub struct Skipped {
+  pub code: &'static str,
+  pub symbol: Option<String>,
+  pub c_type: Option<String>,
+  pub reason: String,
+  pub hint: &'static str,
+  pub file: usize,
+}
+
+#[derive(Debug)]
+struct DeclError {
+  code: &'static str,
+  symbol: Option<String>,
+  c_type: Option<String>,
+  reason: String,
+  hint: &'static str,
+}
+
+impl DeclError {
+  fn syntax(message: impl Into<String>) -> Self {
+    Self {
+      code: PARSE_FAILED_CODE,
+      symbol: None,
+      c_type: None,
+      reason: format!("syntax_error: {}", message.into()),
+      hint: "manual_wrapper",
+    }
+  }
+
+  fn unsupported(reason: &str, c_type: Option<String>) -> Self {
+    Self {
+      code: UNKNOWN_TYPE_CODE,
+      symbol: None,
+      c_type,
+      reason: reason.to_string(),
+      hint: "phase2",
+    }
+  }
+}
+
+type DeclResult<T> = Result<T, DeclError>;
+
+#[derive(Debug, Default)]
+struct Specifiers {
+  base: Option<CType>,
+  base_const: bool,
+  qualifiers: Vec<String>,
+  is_typedef: bool,
+  is_static: bool,
+  /// C 表記の復元用（記憶域指定子を除く）。
+  tokens: Vec<Token>,
+  /// 無名の構造体・列挙型を定義した場合のタグ（typedef 名で付け替える）。
+  anonymous_tag: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Declarator {
+  name: Option<String>,
+  name_index: Option<usize>,
+  /// 各 `*` の直後に `const` があるか。
+  pointers: Vec<bool>,
+  qualifiers: Vec<String>,
+  inner: Option<Box<Declarator>>,
+  suffixes: Vec<Suffix>,
+  start: usize,
+  end: usize,
+}
+
+#[derive(Debug)]
+enum Suffix {
+  Array(Option<u64>),
+  Function { params: Vec<Param>, variadic: bool },
+}
+
+impl Declarator {
+  fn apply(&self, base: CType, base_const: bool) -> CType {
+    let mut ty = base;
+    let mut pointee_const = base_const;
+    for pointer_const in &self.pointers {
+      ty = CType::Pointer {
+        pointee: Box::new(ty),
+        const_pointee: pointee_const,
+      };
+      pointee_const = *pointer_const;
+    }
+    for suffix in self.suffixes.iter().rev() {
+      ty = match suffix {
+        Suffix::Array(len) => CType::Array {
+          element: Box::new(ty),
+          len: *len,
+        },
+        Suffix::Function { params, variadic } => CType::Function(Box::new(FnType {
+          params: params.clone(),
+          returns: TypeRef {
+            ty,
+            c_text: String::new(),
+            qualifiers: Vec::new(),
+          },
+          variadic: *variadic,
+        })),
+      };
+    }
+    match &self.inner {
+      Some(inner) => inner.apply(ty, pointee_const),
+      None => ty,
+    }
+  }
+
+  /// 名前と外側のポインタだけを持つ単純な宣言子か。
+  fn is_plain(&self) -> bool {
+    self.pointers.is_empty() && self.inner.is_none() && self.suffixes.is_empty()
+  }
+
+  fn all_qualifiers(&self) -> Vec<String> {
+    let mut qualifiers = self.qualifiers.clone();
+    if let Some(inner) = &self.inner {
+      qualifiers.extend(inner.all_qualifiers());
+    }
+    qualifiers
+  }
+}
+
+pub struct DeclParser {
+  tokens: Vec<Token>,
+  pos: usize,
+  extern_depth: usize,
+  anonymous: usize,
+  pub env: TypeEnv,
+  pub items: Vec<Item>,
+}
+
+impl DeclParser {
+  pub fn new(tokens: Vec<Token>) -> Self {
+    Self {
+      tokens: clean_tokens(tokens),
+      pos: 0,
+      extern_depth: 0,
+      anonymous: 0,
+      env: TypeEnv::default(),
+      items: Vec::new(),
+    }
+  }
+
+  pub fn parse(mut self) -> (TypeEnv, Vec<Item>) {
+    while self.pos < self.tokens.len() {
+      let start = self.pos;
+      if self.eat(";") {
+        continue;
+      }
+      if self.extern_depth > 0 && self.eat("}") {
+        self.extern_depth -= 1;
+        continue;
+      }
+      if self.peek_is("extern")
+        && self
+          .peek_at(1)
+          .is_some_and(|token| token.kind == TokenKind::Str)
+      {
+        self.pos += 2;
+        if self.eat("{") {
+          self.extern_depth += 1;
+        }
+        continue;
+      }
+      if self.peek_is("_Static_assert") || self.peek_is("static_assert") {
+        self.recover(start);
+        continue;
+      }
+      if let Err(err) = self.declaration() {
+        let file = self.tokens[start].file;
+        self.recover(start);
+        let symbol = err
+          .symbol
+          .or_else(|| guess_symbol(&self.tokens[start..self.pos]));
+        self.items.push(Item::Skipped(Skipped {
+          code: err.code,
+          symbol,
+          c_type: err.c_type,
+          reason: err.reason,
+          hint: err.hint,
+          file,
+        }));
+      }
+    }
+    (self.env, self.items)
+  }
+
+  fn declaration(&mut self) -> DeclResult<()> {
+    let file = self.tokens[self.pos].file;
+    let mut spec = self.specifiers()?;
+    let base = spec
+      .base
+      .clone()
+      .ok_or_else(|| DeclError::syntax("型指定子がありません"))?;
+    if self.eat(";") {
+      return Ok(());
+    }
+    let mut first = true;
+    loop {
+      let declarator = self.declarator()?;
+      let name = declarator
+        .name
+        .clone()
+        .ok_or_else(|| DeclError::syntax("宣言子の名前がありません"))?;
+      let mut qualifiers = spec.qualifiers.clone();
+      push_unique(&mut qualifiers, declarator.all_qualifiers());
+
+      if spec.is_typedef {
+        if first && declarator.is_plain() {
+          if let Some(anonymous) = spec.anonymous_tag.take() {
+            self.rename_anonymous(&anonymous, &name);
+            for token in spec
+              .tokens
+              .iter_mut()
+              .filter(|token| token.text == anonymous)
+            {
+              token.text = name.clone();
+            }
+            spec.base = Some(match &base {
+              CType::Record { kind, .. } => CType::Record {
+                kind: *kind,
+                tag: name.clone(),
+              },
+              _ => CType::Enum(name.clone()),
+            });
+          }
+        }
+        let ty = declarator.apply(
+          spec.base.clone().unwrap_or_else(|| base.clone()),
+          spec.base_const,
+        );
+        let c_text = self.render_type(&spec.tokens, &declarator);
+        self.env.typedefs.insert(
+          name.clone(),
+          TypedefDef {
+            ty: TypeRef {
+              ty,
+              c_text,
+              qualifiers,
+            },
+            file,
+          },
+        );
+        self.items.push(Item::Typedef { name });
+      } else {
+        let ty = declarator.apply(
+          spec.base.clone().unwrap_or_else(|| base.clone()),
+          spec.base_const,
+        );
+        match ty {
+          CType::Function(mut function) => {
+            if self.peek_is("{") {
+              self.skip_balanced();
+              self.items.push(skipped(
+                SKIPPED_CODE,
+                &name,
+                "inline_definition",
+                "manual_wrapper",
+                file,
+              ));
+              return Ok(());
+            }
+            if spec.is_static {
+              self.items.push(skipped(
+                SKIPPED_CODE,
+                &name,
+                "static_declaration",
+                "manual_wrapper",
+                file,
+              ));
+            } else {
+              let mut return_tokens = spec.tokens.clone();
+              return_tokens.extend(
+                (declarator.start..declarator.end)
+                  .map(|index| self.tokens[index].clone())
+                  .take_while(|token| token.is("*") || is_qualifier(&token.text)),
+              );
+              function.returns.c_text = render_tokens(&return_tokens);
+              function.returns.qualifiers = qualifiers;
+              self.items.push(Item::Function {
+                name,
+                function: *function,
+                file,
+              });
+            }
+          }
+          _ => {
+            self.items.push(skipped(
+              SKIPPED_CODE,
+              &name,
+              "unsupported_global",
+              "manual_wrapper",
+              file,
+            ));
+            if self.eat("=") {
+              self.skip_until(&[",", ";"]);
+            }
+          }
+        }
+      }
+      first = false;
+      if self.eat(",") {
+        continue;
+      }
+      return self.expect(";");
+    }
+  }
+
+  fn rename_anonymous(&mut self, anonymous: &str, name: &str) {
+    if let Some(mut def) = self.env.records.remove(anonymous) {
+      def.tag = name.to_string();
+      self.env.records.insert(name.to_string(), def);
+    }
+    if let Some(mut def) = self.env.enums.remove(anonymous) {
+      def.tag = name.to_string();
+      self.env.enums.insert(name.to_string(), def);
+    }
+    for item in self.items.iter_mut().rev() {
+      match item {
+        Item::Record { tag } | Item::Enum { tag } if tag == anonymous => {
+          *tag = name.to_string();
+          break;
+        }
+        _ => {}
+      }
+    }
+  }
+
+  fn specifiers(&mut self) -> DeclResult<Specifiers> {
+    let mut spec = Specifiers::default();
+    let mut words: Vec<String> = Vec::new();
+    let mut packed = false;
+    while let Some(token) = self.peek().cloned() {
+      if !token.is_ident() {
+        break;
+      }
+      let text = token.text.as_str();
+      match text {
+        "typedef" => spec.is_typedef = true,
+        "static" => spec.is_static = true,
+        "extern" | "inline" | "register" | "auto" | "_Thread_local" | "thread_local"
+        | "_Noreturn" => {}
+        "__reml_packed" => packed = true,
+        _ if is_qualifier(text) => {
+          if text == "const" {
+            spec.base_const = true;
+          }
+          push_unique(&mut spec.qualifiers, [text.to_string()]);
+          spec.tokens.push(token.clone());
+        }
+        "struct" | "union" | "enum" if spec.base.is_none() && words.is_empty() => {
+          let ty = if text == "enum" {
+            self.enum_specifier(&mut spec)?
+          } else {
+            self.record_specifier(&mut spec, packed)?
+          };
+          spec.base = Some(ty);
+          continue;
+        }
+        _ if is_basic_type_word(text) && spec.base.is_none() => {
+          words.push(text.to_string());
+          spec.tokens.push(token.clone());
+        }
+        _ if spec.base.is_none() && words.is_empty() => {
+          if self.env.is_typedef_name(text) {
+            spec.base = Some(CType::Named(text.to_string()));
+            spec.tokens.push(token.clone());
+          } else {
+            return Err(DeclError::unsupported(
+              "unsupported_type",
+              Some(text.to_string()),
+            ));
+          }
+        }
+        _ => break,
+      }
+      self.pos += 1;
+    }
+    if spec.base.is_none() && !words.is_empty() {
+      spec.base = Some(basic_type(&words));
+    }
+    Ok(spec)
+  }
+
+  fn record_specifier(&mut self, spec: &mut Specifiers, mut packed: bool) -> DeclResult<CType> {
+    let keyword = self.next_token()?;
+    let kind = if keyword.text == "union" {
+      RecordKind::Union
+    } else {
+      RecordKind::Struct
+    };
+    packed |= self.eat("__reml_packed");
+    let file = keyword.file;
+    let tag = self
+      .peek()
+      .filter(|token| token.is_ident())
+      .map(|token| token.text.clone());
+    if tag.is_some() {
+      self.pos += 1;
+    }
+    spec.tokens.push(keyword.clone());
+    if !self.peek_is("{") {
+      let tag = tag.ok_or_else(|| DeclError::syntax("構造体のタグがありません"))?;
+      spec.tokens.push(Token::new(
+        TokenKind::Ident,
+        tag.clone(),
+        file,
+        keyword.line,
+      ));
+      self
+        .env
+        .records
+        .entry(tag.clone())
+        .or_insert_with(|| RecordDef {
+          kind,
+          tag: tag.clone(),
+          fields: None,
+          packed: false,
+          unsupported: None,
+          file,
+        });
+      return Ok(CType::Record { kind, tag });
+    }
+
+    self.pos += 1;
+    let mut fields = Vec::new();
+    let mut unsupported = None;
+    while !self.eat("}") {
+      if self.peek().is_none() {
+        return Err(DeclError::syntax("構造体の `}` がありません"));
+      }
+      if self.peek_is("_Static_assert") || self.peek_is("static_assert") {
+        self.skip_until(&[";"]);
+        self.eat(";");
+        continue;
+      }
+      let field_spec = self.specifiers()?;
+      let base = field_spec
+        .base
+        .clone()
+        .ok_or_else(|| DeclError::syntax("メンバの型がありません"))?;
+      if self.eat(";") {
+        unsupported = Some("anonymous_member");
+        continue;
+      }
+      loop {
+        let declarator = if self.peek_is(":") {
+          Declarator::default()
+        } else {
+          self.declarator()?
+        };
+        let ty = declarator.apply(base.clone(), field_spec.base_const);
+        let bit_width = if self.eat(":") {
+          Some(self.const_expr(&[",", ";"])?.value as u64)
+        } else {
+          None
+        };
+        if matches!(ty, CType::Array { len: None, .. }) {
+          unsupported = Some("flexible_array");
+        }
+        let mut qualifiers = field_spec.qualifiers.clone();
+        push_unique(&mut qualifiers, declarator.all_qualifiers());
+        fields.push(FieldDef {
+          name: declarator.name.clone().unwrap_or_default(),
+          ty: TypeRef {
+            ty,
+            c_text: self.render_type(&field_spec.tokens, &declarator),
+            qualifiers,
+          },
+          bit_width,
+        });
+        if self.eat(",") {
+          continue;
+        }
+        self.expect(";")?;
+        break;
+      }
+    }
+    packed |= self.eat("__reml_packed");
+
+    let tag = tag.unwrap_or_else(|| {
+      let name = self.anonymous_tag();
+      spec.anonymous_tag = Some(name.clone());
+      name
+    });
+    spec.tokens.push(Token::new(
+      TokenKind::Ident,
+      tag.clone(),
+      file,
+      keyword.line,
+    ));
+    self.env.records.insert(
+      tag.clone(),
+      RecordDef {
+        kind,
+        tag: tag.clone(),
+        fields: Some(fields),
+        packed,
+        unsupported,
+        file,
+      },
+    );
+    self.items.push(Item::Record { tag: tag.clone() });
+    Ok(CType::Record { kind, tag })
+  }
+
+  fn enum_specifier(&mut self, spec: &mut Specifiers) -> DeclResult<CType> {
+    let keyword = self.next_token()?;
+    let file = keyword.file;
+    let tag = self
+      .peek()
+      .filter(|token| token.is_ident())
+      .map(|token| token.text.clone());
+    if tag.is_some() {
+      self.pos += 1;
+    }
+    spec.tokens.push(keyword.clone());
+    // C23 の基底型指定（`enum E : uint8_t`）は値域から決め直すので読み飛ばす
+    if self.eat(":") {
+      self.specifiers()?;
+    }
+    if !self.peek_is("{") {
+      let tag = tag.ok_or_else(|| DeclError::syntax("列挙型のタグがありません"))?;
+      spec.tokens.push(Token::new(
+        TokenKind::Ident,
+        tag.clone(),
+        file,
+        keyword.line,
+      ));
+      self
+        .env
+        .enums
+        .entry(tag.clone())
+        .or_insert_with(|| EnumDef {
+          tag: tag.clone(),
+          variants: Vec::new(),
+          complete: false,
+          file,
+        });
+      return Ok(CType::Enum(tag));
+    }
+
+    self.pos += 1;
+    let mut variants = Vec::new();
+    let mut next = IntValue::int(0);
+    while !self.eat("}") {
+      let name = self.next_token()?;
+      if !name.is_ident() {
+        return Err(DeclError::syntax(format!(
+          "列挙子が必要です: `{}`",
+          name.text
+        )));
+      }
+      let value = if self.eat("=") {
+        self.const_expr(&[",", "}"]).map_err(|err| DeclError {
+          symbol: Some(name.text.clone()),
+          ..DeclError::unsupported(&format!("unsupported_enum_value: {}", err.reason), None)
+        })?
+      } else {
+        next
+      };
+      self.env.constants.insert(name.text.clone(), value);
+      variants.push((name.text.clone(), value));
+      next = IntValue {
+        value: value.value + 1,
+        ..value
+      };
+      if !self.eat(",") {
+        self.expect("}")?;
+        break;
+      }
+    }
+
+    let tag = tag.unwrap_or_else(|| {
+      let name = self.anonymous_tag();
+      spec.anonymous_tag = Some(name.clone());
+      name
+    });
+    spec.tokens.push(Token::new(
+      TokenKind::Ident,
+      tag.clone(),
+      file,
+      keyword.line,
+    ));
+    self.env.enums.insert(
+      tag.clone(),
+      EnumDef {
+        tag: tag.clone(),
+        variants,
+        complete: true,
+        file,
+      },
+    );
+    self.items.push(Item::Enum { tag: tag.clone() });
+    Ok(CType::Enum(tag))
+  }
+
+  fn anonymous_tag(&mut self) -> String {
+    self.anonymous += 1;
+    format!("anon_{}", self.anonymous)
+  }
+
+  fn declarator(&mut self) -> DeclResult<Declarator> {
+    let mut declarator = Declarator {
+      start: self.pos,
+      ..Declarator::default()
+    };
+    while self.eat("*") {
+      let mut pointer_const = false;
+      while let Some(token) = self
+        .peek()
+        .filter(|token| is_qualifier(&token.text) || token.text == "__reml_packed")
+      {
+        if token.text == "const" {
+          pointer_const = true;
+        }
+        if token.text != "__reml_packed" {
+          let qualifier = token.text.clone();
+          push_unique(&mut declarator.qualifiers, [qualifier]);
+        }
+        self.pos += 1;
+      }
+      declarator.pointers.push(pointer_const);
+    }
+    match self.peek() {
+      Some(token) if token.is_ident() && !is_keyword(&token.text) => {
+        declarator.name = Some(token.text.clone());
+        declarator.name_index = Some(self.pos);
+        self.pos += 1;
+      }
+      Some(token) if token.is("(") && self.is_nested_declarator() => {
+        self.pos += 1;
+        let inner = self.declarator()?;
+        self.expect(")")?;
+        declarator.name = inner.name.clone();
+        declarator.name_index = inner.name_index;
+        declarator.inner = Some(Box::new(inner));
+      }
+      _ => {}
+    }
+    loop {
+      if self.eat("[") {
+        // `[static 4]` / `[const]` などの修飾は読み飛ばす
+        while self
+          .peek()
+          .is_some_and(|token| token.text == "static" || is_qualifier(&token.text))
+        {
+          self.pos += 1;
+        }
+        let len = if self.peek_is("]") || self.peek_is("*") {
+          self.eat("*");
+          None
+        } else {
+          let value = self.const_expr(&["]"])?;
+          Some(
+            u64::try_from(value.value)
+              .map_err(|_| DeclError::unsupported("unsupported_array", None))?,
+          )
+        };
+        self.expect("]")?;
+        declarator.suffixes.push(Suffix::Array(len));
+      } else if self.peek_is("(") {
+        let (params, variadic) = self.params()?;
+        declarator
+          .suffixes
+          .push(Suffix::Function { params, variadic });
+      } else {
+        break;
+      }
+    }
+    declarator.end = self.pos;
+    Ok(declarator)
+  }
+
+  /// `(` の後が入れ子の宣言子か（引数リストではないか）。
+  fn is_nested_declarator(&self) -> bool {
+    match self.peek_at(1) {
+      Some(token) if token.is("*") || token.is("^") || token.is("(") => true,
+      Some(token) if token.is_ident() => !self.is_type_start(&token.text),
+      _ => false,
+    }
+  }
+
+  fn is_type_start(&self, text: &str) -> bool {
+    is_keyword(text) || self.env.is_typedef_name(text)
+  }
+
+  fn params(&mut self) -> DeclResult<(Vec<Param>, bool)> {
+    self.expect("(")?;
+    let mut params = Vec::new();
+    if self.eat(")") {
+      return Ok((params, false));
+    }
+    if self.peek_is("void") && self.peek_at(1).is_some_and(|token| token.is(")")) {
+      self.pos += 2;
+      return Ok((params, false));
+    }
+    loop {
+      if self.eat("...") {
+        self.expect(")")?;
+        return Ok((params, true));
+      }
+      let spec = self.specifiers()?;
+      let base = spec
+        .base
+        .clone()
+        .ok_or_else(|| DeclError::syntax("引数の型がありません"))?;
+      let declarator = self.declarator()?;
+      let ty = self.decay(declarator.apply(base, spec.base_const));
+      let mut qualifiers = spec.qualifiers.clone();
+      push_unique(&mut qualifiers, declarator.all_qualifiers());
+      params.push(Param {
+        name: declarator.name.clone(),
+        ty: TypeRef {
+          ty,
+          c_text: self.render_type(&spec.tokens, &declarator),
+          qualifiers,
+        },
+      });
+      if self.eat(",") {
+        continue;
+      }
+      self.expect(")")?;
+      return Ok((params, false));
+    }
+  }
+
+  /// 引数位置の配列・関数型をポインタへ読み替える。
+  fn decay(&self, ty: CType) -> CType {
+    match ty {
+      CType::Array { element, .. } => CType::Pointer {
+        pointee: element,
+        const_pointee: false,
+      },
+      CType::Function(_) => CType::Pointer {
+        pointee: Box::new(ty),
+        const_pointee: false,
+      },
+      CType::Named(_)
+        if matches!(
+          self.env.resolve(&ty),
+          ResolvedType::Type(CType::Function(_))
+        ) =>
+      {
+        CType::Pointer {
+          pointee: Box::new(ty),
+          const_pointee: false,
+        }
+      }
+      _ => ty,
+    }
+  }
+
+  fn render_type(&self, spec_tokens: &[Token], declarator: &Declarator) -> String {
+    let mut tokens = spec_tokens.to_vec();
+    tokens.extend(
+      (declarator.start..declarator.end)
+        .filter(|index| Some(*index) != declarator.name_index)
+        .map(|index| self.tokens[index].clone()),
+    );
+    render_tokens(&tokens)
+  }
+
+  fn const_expr(&mut self, terminators: &[&str]) -> DeclResult<IntValue> {
+    let start = self.pos;
+    self.skip_until(terminators);
+    expr::evaluate(&self.tokens[start..self.pos], &self.env).map_err(|message| {
+      DeclError::unsupported(&format!("unsupported_constant: {}", message), None)
+    })
+  }
+
+  /// 括弧の深さ 0 で `terminators` のいずれかが現れる直前まで進める。
+  fn skip_until(&mut self, terminators: &[&str]) {
+    let mut depth = 0usize;
+    while let Some(token) = self.peek() {
+      if depth == 0 && terminators.iter().any(|terminator| token.is(terminator)) {
+        return;
+      }
+      if token.is("(") || token.is("[") || token.is("{") {
+        depth += 1;
+      } else if token.is(")") || token.is("]") || token.is("}") {
+        if depth == 0 {
+          return;
+        }
+        depth -= 1;
+      }
+      self.pos += 1;
+    }
+  }
+
+  fn skip_balanced(&mut self) {
+    let mut depth = 0usize;
+    while let Some(token) = self.peek() {
+      if token.is("{") {
+        depth += 1;
+      } else if token.is("}") {
+        depth = depth.saturating_sub(1);
+        if depth == 0 {
+          self.pos += 1;
+          return;
+        }
+      }
+      self.pos += 1;
+    }
+  }
+
+  /// 解析に失敗した宣言を読み飛ばす。`start` から数え直して `;` か本体の `}` の後まで進める。
+  fn recover(&mut self, start: usize) {
+    self.pos = start;
+    let mut depth = 0usize;
+    while let Some(token) = self.peek().cloned() {
+      self.pos += 1;
+      if token.is("(") || token.is("[") || token.is("{") {
+        depth += 1;
+      } else if token.is(")") || token.is("]") {
+        depth = depth.saturating_sub(1);
+      } else if token.is("}") {
+        if depth == 0 {
+          // `extern "C" { ... }` の閉じ括弧は残す
+          self.pos -= 1;
+          return;
+        }
+        depth -= 1;
+        let closes_body = depth == 0
+          && self.tokens[..self.pos - 1]
+            .iter()
+            .rev()
+            .find(|token| token.is("{"))
+            .is_some();
+        if closes_body
+          && !self
+            .peek()
+            .is_some_and(|next| next.is_ident() || next.is("*") || next.is(";"))
+        {
+          return;
+        }
+      } else if token.is(";") && depth == 0 {
+        return;
+      }
+    }
+  }
+
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.pos)
+  }
+
+  fn peek_at(&self, offset: usize) -> Option<&Token> {
+    self.tokens.get(self.pos + offset)
+  }
+
+  fn peek_is(&self, text: &str) -> bool {
+    self.peek().is_some_and(|token| token.is(text))
+  }
+
+  fn eat(&mut self, text: &str) -> bool {
+    if self.peek_is(text) {
+      self.pos += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn expect(&mut self, text: &str) -> DeclResult<()> {
+    if self.eat(text) {
+      return Ok(());
+    }
+    let found = self
+      .peek()
+      .map_or("<EOF>".to_string(), |token| token.text.clone());
+    Err(DeclError::syntax(format!(
+      "`{}` が必要ですが `{}` がありました",
+      text, found
+    )))
+  }
+
+  fn next_token(&mut self) -> DeclResult<Token> {
+    let token = self
+      .peek()
+      .cloned()
+      .ok_or_else(|| DeclError::syntax("宣言が途中で終わっています"))?;
+    self.pos += 1;
+    Ok(token)
+  }
+}
+
+fn skipped(
+  code: &'static str,
+  symbol: &str,
+  reason: &str,
+  hint: &'static str,
+  file: usize,
+) -> Item {
+  Item::Skipped(Skipped {
+    code,
+    symbol: Some(symbol.to_string()),
+    c_type: None,
+    reason: reason.to_string(),
+    hint,
+    file,
+  })
+}
+
+/// 失敗した宣言から診断用のシンボル名を推定する（`(` 直前の識別子を優先）。
+fn guess_symbol(tokens: &[Token]) -> Option<String> {
+  let mut depth = 0usize;
+  let mut last_ident = None;
+  for (index, token) in tokens.iter().enumerate() {
+    if token.is("(") {
+      if depth == 0 {
+        if let Some(prev) = index.checked_sub(1).map(|prev| &tokens[prev]) {
+          if prev.is_ident() && !is_keyword(&prev.text) {
+            return Some(prev.text.clone());
+          }
+        }
+      }
+      depth += 1;
+    } else if token.is(")") {
+      depth = depth.saturating_sub(1);
+    } else if token.is("{") {
+      return last_ident;
+    } else if token.is_ident() && depth == 0 && !is_keyword(&token.text) {
+      last_ident = Some(token.text.clone());
+    }
+  }
+  last_ident
+}
+
+fn push_unique(target: &mut Vec<String>, values: impl IntoIterator<Item = String>) {
+  for value in values {
+    if !target.contains(&value) {
+      target.push(value);
+    }
+  }
+}
+
+fn is_qualifier(text: &str) -> bool {
+  matches!(text, "const" | "volatile" | "restrict" | "_Atomic")
+}
+
+fn is_basic_type_word(text: &str) -> bool {
+  matches!(
+    text,
+    "void"
+      | "char"
+      | "short"
+      | "int"
+      | "long"
+      | "signed"
+      | "unsigned"
+      | "float"
+      | "double"
+      | "_Bool"
+      | "bool"
+      | "_Complex"
+      | "__int128"
+      | "_Float16"
+      | "__fp16"
+      | "_Float128"
+      | "__float128"
+  )
+}
+
+fn is_keyword(text: &str) -> bool {
+  is_basic_type_word(text)
+    || is_qualifier(text)
+    || matches!(
+      text,
+      "struct"
+        | "union"
+        | "enum"
+        | "typedef"
+        | "extern"
+        | "static"
+        | "inline"
+        | "register"
+        | "auto"
+        | "_Thread_local"
+        | "thread_local"
+        | "_Noreturn"
+        | "__reml_packed"
+    )
+}
+
+fn basic_type(words: &[String]) -> CType {
+  let has = |word: &str| words.iter().any(|value| value == word);
+  let longs = words.iter().filter(|value| *value == "long").count();
+  let unsigned = has("unsigned");
+  if has("_Complex") {
+    return CType::Unsupported("unsupported_complex");
+  }
+  if has("__int128") {
+    return CType::Unsupported("unsupported_int128");
+  }
+  if has("_Float16") || has("__fp16") || has("_Float128") || has("__float128") {
+    return CType::Unsupported("unsupported_float");
+  }
+  if has("void") {
+    CType::Void
+  } else if has("_Bool") || has("bool") {
+    CType::Bool
+  } else if has("float") {
+    CType::Float
+  } else if has("double") {
+    if longs > 0 {
+      CType::Unsupported("unsupported_long_double")
+    } else {
+      CType::Double
+    }
+  } else if has("char") {
+    if unsigned {
+      CType::UChar
+    } else if has("signed") {
+      CType::SChar
+    } else {
+      CType::Char
+    }
+  } else if has("short") {
+    if unsigned {
+      CType::UShort
+    } else {
+      CType::Short
+    }
+  } else if longs >= 2 {
+    if unsigned {
+      CType::ULongLong
+    } else {
+      CType::LongLong
+    }
+  } else if longs == 1 {
+    if unsigned {
+      CType::ULong
+    } else {
+      CType::Long
+    }
+  } else if unsigned {
+    CType::UInt
+  } else {
+    CType::Int
+  }
+}
+
+/// 属性・インラインアセンブリ・処理系固有のキーワードを取り除く。
+/// `__attribute__((packed))` は `__reml_packed` に置き換えて構造体へ伝える。
+fn clean_tokens(tokens: Vec<Token>) -> Vec<Token> {
+  let mut output = Vec::with_capacity(tokens.len());
+  let mut index = 0;
+  while index < tokens.len() {
+    let token = &tokens[index];
+    if token.is_ident() {
+      match token.text.as_str() {
+        "__attribute__" | "__attribute" | "__declspec" | "__asm__" | "__asm" | "asm"
+        | "_Alignas" | "alignas" | "__typeof__" => {
+          let mut open = index + 1;
+          while tokens
+            .get(open)
+            .is_some_and(|next| next.is("volatile") || next.is("__volatile__"))
+          {
+            open += 1;
+          }
+          if tokens.get(open).is_some_and(|next| next.is("(")) {
+            let close = matching_paren(&tokens, open);
+            if tokens[open..close]
+              .iter()
+              .any(|inner| inner.text == "packed" || inner.text == "__packed__")
+            {
+              output.push(Token::new(
+                TokenKind::Ident,
+                "__reml_packed",
+                token.file,
+                token.line,
+              ));
+            }
+            index = close + 1;
+            continue;
+          }
+        }
+        "__extension__" | "_Nullable" | "_Nonnull" | "_Null_unspecified" | "__nullable"
+        | "__nonnull" | "__cdecl" | "__stdcall" | "__fastcall" | "__restrict_arr" | "__wur"
+        | "__THROW" | "__nonnull__" => {
+          index += 1;
+          continue;
+        }
+        alias => {
+          let canonical = match alias {
+            "__restrict" | "__restrict__" => Some("restrict"),
+            "__inline" | "__inline__" => Some("inline"),
+            "__const" | "__const__" => Some("const"),
+            "__volatile" | "__volatile__" => Some("volatile"),
+            "__signed" | "__signed__" => Some("signed"),
+            _ => None,
+          };
+          if let Some(canonical) = canonical {
+            output.push(Token::new(
+              TokenKind::Ident,
+              canonical,
+              token.file,
+              token.line,
+            ));
+            index += 1;
+            continue;
+          }
+        }
+      }
+    }
+    output.push(token.clone());
+    index += 1;
+  }
+  output
+}
+
+fn matching_paren(tokens: &[Token], open: usize) -> usize {
+  let mut depth = 0usize;
+  for (index, token) in tokens.iter().enumerate().skip(open) {
+    if token.is("(") {
+      depth += 1;
+    } else if token.is(")") {
+      depth -= 1;
+      if depth == 0 {
+        return index;
+      }
+    }
+  }
+  tokens.len() - 1
+}
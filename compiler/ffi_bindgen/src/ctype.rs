@@ -0,0 +1,680 @@
+//! C の型表現・型環境・レイアウト計算と、Reml 型への変換。
+//!
+//! レイアウトは LP64（x86_64 / aarch64 の System V）を基準とする。
+
+use crate::expr::{ConstEnv, IntValue};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+  Struct,
+  Union,
+}
+
+impl RecordKind {
+  pub fn keyword(self) -> &'static str {
+    match self {
+      RecordKind::Struct => "struct",
+      RecordKind::Union => "union",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CType {
+  Void,
+  Bool,
+  Char,
+  SChar,
+  UChar,
+  Short,
+  UShort,
+  Int,
+  UInt,
+  Long,
+  ULong,
+  LongLong,
+  ULongLong,
+  Float,
+  Double,
+  /// 変換表にないスカラ型（`long double` / `_Complex` / `__int128` など）と未対応理由。
+  Unsupported(&'static str),
+  /// typedef 名。型環境で解決する。
+  Named(String),
+  Pointer {
+    pointee: Box<CType>,
+    const_pointee: bool,
+  },
+  Array {
+    element: Box<CType>,
+    len: Option<u64>,
+  },
+  Record {
+    kind: RecordKind,
+    tag: String,
+  },
+  Enum(String),
+  Function(Box<FnType>),
+}
+
+/// 宣言中の型。マニフェスト用に C 表記と修飾子を保持する。
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeRef {
+  pub ty: CType,
+  pub c_text: String,
+  pub qualifiers: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnType {
+  pub params: Vec<Param>,
+  pub returns: TypeRef,
+  pub variadic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+  pub name: Option<String>,
+  pub ty: TypeRef,
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldDef {
+  pub name: String,
+  pub ty: TypeRef,
+  pub bit_width: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordDef {
+  pub kind: RecordKind,
+  pub tag: String,
+  /// 本体を持たない前方宣言なら `None`。
+  pub fields: Option<Vec<FieldDef>>,
+  pub packed: bool,
+  /// 本体にあっても変換できない構成（無名メンバ・可変長配列メンバなど）。
+  pub unsupported: Option<&'static str>,
+  pub file: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumDef {
+  pub tag: String,
+  pub variants: Vec<(String, IntValue)>,
+  pub complete: bool,
+  pub file: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct TypedefDef {
+  pub ty: TypeRef,
+  pub file: usize,
+}
+
+/// 宣言の解析中に蓄積する型環境。
+#[derive(Debug, Default)]
+pub struct TypeEnv {
+  pub typedefs: HashMap<String, TypedefDef>,
+  pub records: HashMap<String, RecordDef>,
+  pub enums: HashMap<String, EnumDef>,
+  pub constants: HashMap<String, IntValue>,
+}
+
+impl ConstEnv for TypeEnv {
+  fn value_of(&self, name: &str) -> Option<IntValue> {
+    self.constants.get(name).copied()
+  }
+
+  fn is_type_name(&self, name: &str) -> bool {
+    self.is_typedef_name(name)
+  }
+}
+
+impl TypeEnv {
+  pub fn is_typedef_name(&self, name: &str) -> bool {
+    self.typedefs.contains_key(name) || builtin_typedef(name).is_some()
+  }
+
+  /// typedef を辿り、typedef 以外の型に解決する。
+  pub fn resolve<'a>(&'a self, ty: &'a CType) -> ResolvedType<'a> {
+    let mut current = ty;
+    for _ in 0..64 {
+      let CType::Named(name) = current else {
+        return ResolvedType::Type(current);
+      };
+      if let Some(special) = special_typedef(name) {
+        return ResolvedType::Special(special);
+      }
+      match self.typedefs.get(name) {
+        Some(def) => current = &def.ty.ty,
+        None => match builtin_typedef(name) {
+          Some(builtin) => return ResolvedType::Builtin(builtin),
+          None => return ResolvedType::Unknown,
+        },
+      }
+    }
+    ResolvedType::Unknown
+  }
+
+  pub fn layout(&self, ty: &CType) -> Result<Layout, &'static str> {
+    let scalar = |size: u64| Ok(Layout { size, align: size });
+    match ty {
+      CType::Void => Err("incomplete_type"),
+      CType::Bool | CType::Char | CType::SChar | CType::UChar => scalar(1),
+      CType::Short | CType::UShort => scalar(2),
+      CType::Int | CType::UInt | CType::Float => scalar(4),
+      CType::Long | CType::ULong | CType::LongLong | CType::ULongLong | CType::Double => scalar(8),
+      CType::Pointer { .. } => scalar(8),
+      CType::Unsupported(reason) => Err(reason),
+      CType::Named(_) => match self.resolve(ty) {
+        ResolvedType::Type(inner) => self.layout(inner),
+        ResolvedType::Builtin(builtin) => self.layout(&builtin),
+        ResolvedType::Special(special) => scalar(special.size),
+        ResolvedType::Unknown => Err("unsupported_type"),
+      },
+      CType::Array { element, len } => {
+        let element = self.layout(element)?;
+        let len = len.ok_or("flexible_array")?;
+        Ok(Layout {
+          size: element.size * len,
+          align: element.align,
+        })
+      }
+      CType::Record { tag, .. } => self.record_layout(tag).map(|layout| layout.layout),
+      CType::Enum(tag) => {
+        let def = self
+          .enums
+          .get(tag)
+          .filter(|def| def.complete)
+          .ok_or("incomplete_type")?;
+        scalar(enum_repr(def).size())
+      }
+      CType::Function(_) => Err("unsupported_fn_value"),
+    }
+  }
+
+  /// 構造体のレイアウトとフィールドオフセット。変換できない構成なら理由を返す。
+  pub fn record_layout(&self, tag: &str) -> Result<RecordLayout, &'static str> {
+    let def = self.records.get(tag).ok_or("incomplete_type")?;
+    if def.kind == RecordKind::Union {
+      return Err("unsupported_union");
+    }
+    if let Some(reason) = def.unsupported {
+      return Err(reason);
+    }
+    let fields = def.fields.as_ref().ok_or("incomplete_type")?;
+    let mut offset = 0u64;
+    let mut align = 1u64;
+    let mut offsets = Vec::with_capacity(fields.len());
+    for field in fields {
+      if field.bit_width.is_some() {
+        return Err("unsupported_bitfield");
+      }
+      let layout = self.layout(&field.ty.ty)?;
+      let field_align = if def.packed { 1 } else { layout.align };
+      offset = offset.next_multiple_of(field_align);
+      offsets.push(FieldLayout {
+        offset,
+        size: layout.size,
+      });
+      offset += layout.size;
+      align = align.max(field_align);
+    }
+    Ok(RecordLayout {
+      layout: Layout {
+        size: offset.next_multiple_of(align),
+        align,
+      },
+      fields: offsets,
+    })
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+  pub size: u64,
+  pub align: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLayout {
+  pub offset: u64,
+  pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordLayout {
+  pub layout: Layout,
+  pub fields: Vec<FieldLayout>,
+}
+
+pub enum ResolvedType<'a> {
+  Type(&'a CType),
+  Builtin(CType),
+  Special(SpecialTypedef),
+  Unknown,
+}
+
+/// Reml 側で専用の型を持つ typedef（`size_t` → `USize` など）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpecialTypedef {
+  pub reml: &'static str,
+  pub manifest: &'static str,
+  pub dsl: &'static str,
+  pub size: u64,
+}
+
+fn special_typedef(name: &str) -> Option<SpecialTypedef> {
+  let unsigned = SpecialTypedef {
+    reml: "usize",
+    manifest: "USize",
+    dsl: "ffi.U64",
+    size: 8,
+  };
+  let signed = SpecialTypedef {
+    reml: "isize",
+    manifest: "ISize",
+    dsl: "ffi.I64",
+    size: 8,
+  };
+  match name {
+    "size_t" | "uintptr_t" => Some(unsigned),
+    "intptr_t" | "ssize_t" | "ptrdiff_t" => Some(signed),
+    _ => None,
+  }
+}
+
+/// 標準ヘッダが見つからない環境でも解決できるようにする既知の typedef。
+pub fn builtin_typedef(name: &str) -> Option<CType> {
+  Some(match name {
+    "size_t" | "uintptr_t" | "uint64_t" | "uintmax_t" | "uint_least64_t" | "uint_fast64_t" => {
+      CType::ULong
+    }
+    "intptr_t" | "ssize_t" | "ptrdiff_t" | "int64_t" | "intmax_t" | "off_t" | "int_least64_t"
+    | "int_fast64_t" | "time_t" => CType::Long,
+    "int8_t" | "int_least8_t" | "int_fast8_t" => CType::SChar,
+    "uint8_t" | "uint_least8_t" | "uint_fast8_t" => CType::UChar,
+    "int16_t" | "int_least16_t" => CType::Short,
+    "uint16_t" | "uint_least16_t" | "char16_t" => CType::UShort,
+    "int32_t" | "int_least32_t" | "wchar_t" | "pid_t" => CType::Int,
+    "uint32_t" | "uint_least32_t" | "char32_t" => CType::UInt,
+    "va_list" | "__builtin_va_list" => CType::Unsupported("unsupported_va_list"),
+    _ => return None,
+  })
+}
+
+/// 列挙型の基底整数型（GCC/Clang と同じく値域から決める）。
+pub fn enum_repr(def: &EnumDef) -> IntRepr {
+  let min = def
+    .variants
+    .iter()
+    .map(|(_, value)| value.value)
+    .min()
+    .unwrap_or(0);
+  let max = def
+    .variants
+    .iter()
+    .map(|(_, value)| value.value)
+    .max()
+    .unwrap_or(0);
+  if min >= i32::MIN as i128 && max <= i32::MAX as i128 {
+    IntRepr::I32
+  } else if min >= 0 && max <= u32::MAX as i128 {
+    IntRepr::U32
+  } else if min >= i64::MIN as i128 && max <= i64::MAX as i128 {
+    IntRepr::I64
+  } else {
+    IntRepr::U64
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntRepr {
+  I32,
+  U32,
+  I64,
+  U64,
+}
+
+impl IntRepr {
+  pub fn size(self) -> u64 {
+    match self {
+      IntRepr::I32 | IntRepr::U32 => 4,
+      IntRepr::I64 | IntRepr::U64 => 8,
+    }
+  }
+
+  pub fn of(value: IntValue) -> Self {
+    match (value.unsigned, value.wide) {
+      (false, false) => IntRepr::I32,
+      (true, false) => IntRepr::U32,
+      (false, true) => IntRepr::I64,
+      (true, true) => IntRepr::U64,
+    }
+  }
+
+  pub fn reml(self) -> &'static str {
+    match self {
+      IntRepr::I32 => "i32",
+      IntRepr::U32 => "u32",
+      IntRepr::I64 => "i64",
+      IntRepr::U64 => "u64",
+    }
+  }
+
+  pub fn manifest(self) -> &'static str {
+    match self {
+      IntRepr::I32 => "I32",
+      IntRepr::U32 => "U32",
+      IntRepr::I64 => "I64",
+      IntRepr::U64 => "U64",
+    }
+  }
+}
+
+/// 変換後の型表記。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedType {
+  /// 生成 `.reml` 中の表記（`i32` / `Ptr<i8>` / `Point` など）。
+  pub reml: String,
+  /// マニフェストの表記（`I32` / `Ptr<I8>` など、typedef は解決済み）。
+  pub manifest: String,
+  /// `Core.Ffi.Dsl` の型式（`ffi.I32` / `ffi.ptr(ffi.I8)` など）。
+  pub dsl: String,
+}
+
+impl MappedType {
+  fn new(reml: impl Into<String>, manifest: impl Into<String>, dsl: impl Into<String>) -> Self {
+    Self {
+      reml: reml.into(),
+      manifest: manifest.into(),
+      dsl: dsl.into(),
+    }
+  }
+}
+
+/// 出力対象の判定。生成側（`lib.rs`）が一次ファイル・除外パターンから決める。
+pub trait EmitScope {
+  /// 一次ファイル由来で、除外されていない宣言か。
+  fn emits(&self, name: &str, file: usize) -> bool;
+}
+
+/// C 型を Reml 型へ変換する。値渡しで参照した一次ファイル外の構造体は `dependencies` に集める。
+pub struct TypeMapper<'a> {
+  env: &'a TypeEnv,
+  scope: &'a dyn EmitScope,
+  pub dependencies: Vec<String>,
+}
+
+impl<'a> TypeMapper<'a> {
+  pub fn new(env: &'a TypeEnv, scope: &'a dyn EmitScope) -> Self {
+    Self {
+      env,
+      scope,
+      dependencies: Vec::new(),
+    }
+  }
+
+  /// typedef を `type alias` として出力するか。
+  pub fn emits_typedef(&self, name: &str) -> bool {
+    let Some(def) = self.env.typedefs.get(name) else {
+      return false;
+    };
+    if special_typedef(name).is_some() || !self.scope.emits(name, def.file) {
+      return false;
+    }
+    // `typedef struct Foo Foo;` は構造体名と同じなので別名を作らない
+    !matches!(&def.ty.ty, CType::Record { tag, .. } | CType::Enum(tag) if tag == name)
+  }
+
+  /// 構造体を定義として出力するか（一次ファイル由来、または値渡しの依存）。
+  pub fn emits_record(&self, tag: &str) -> bool {
+    self
+      .env
+      .records
+      .get(tag)
+      .is_some_and(|def| self.scope.emits(tag, def.file))
+  }
+
+  pub fn emits_enum(&self, tag: &str) -> bool {
+    self
+      .env
+      .enums
+      .get(tag)
+      .is_some_and(|def| def.complete && self.scope.emits(tag, def.file))
+  }
+
+  pub fn map(&mut self, ty: &CType) -> Result<MappedType, &'static str> {
+    let primitive =
+      |reml: &str, manifest: &str, dsl: &str| Ok(MappedType::new(reml, manifest, dsl));
+    match ty {
+      CType::Void => primitive("()", "Unit", "ffi.Void"),
+      CType::Bool => primitive("bool", "Bool", "ffi.Bool"),
+      CType::Char | CType::SChar => primitive("i8", "I8", "ffi.I8"),
+      CType::UChar => primitive("u8", "U8", "ffi.U8"),
+      CType::Short => primitive("i16", "I16", "ffi.I16"),
+      CType::UShort => primitive("u16", "U16", "ffi.U16"),
+      CType::Int => primitive("i32", "I32", "ffi.I32"),
+      CType::UInt => primitive("u32", "U32", "ffi.U32"),
+      CType::Long | CType::LongLong => primitive("i64", "I64", "ffi.I64"),
+      CType::ULong | CType::ULongLong => primitive("u64", "U64", "ffi.U64"),
+      CType::Float => primitive("f32", "F32", "ffi.F32"),
+      CType::Double => primitive("f64", "F64", "ffi.F64"),
+      CType::Unsupported(reason) => Err(reason),
+      CType::Named(name) => self.map_named(ty, name),
+      CType::Pointer {
+        pointee,
+        const_pointee,
+      } => self.map_pointer(pointee, *const_pointee),
+      CType::Array { .. } => Err("unsupported_array"),
+      CType::Record { tag, .. } => {
+        self.env.record_layout(tag)?;
+        let def = &self.env.records[tag];
+        if !self.scope.emits(tag, def.file) && !self.dependencies.contains(tag) {
+          self.dependencies.push(tag.clone());
+        }
+        Ok(MappedType::new(
+          tag.clone(),
+          tag.clone(),
+          ffi_type_binding(tag),
+        ))
+      }
+      CType::Enum(tag) => {
+        let def = self
+          .env
+          .enums
+          .get(tag)
+          .filter(|def| def.complete)
+          .ok_or("incomplete_type")?;
+        let repr = enum_repr(def);
+        if self.emits_enum(tag) {
+          Ok(MappedType::new(
+            tag.clone(),
+            repr.manifest(),
+            ffi_type_binding(tag),
+          ))
+        } else {
+          Ok(MappedType::new(
+            repr.reml(),
+            repr.manifest(),
+            format!("ffi.{}", repr.manifest()),
+          ))
+        }
+      }
+      CType::Function(_) => Err("unsupported_fn_value"),
+    }
+  }
+
+  fn map_named(&mut self, ty: &CType, name: &str) -> Result<MappedType, &'static str> {
+    let resolved = match self.env.resolve(ty) {
+      ResolvedType::Special(special) => {
+        return Ok(MappedType::new(special.reml, special.manifest, special.dsl));
+      }
+      ResolvedType::Builtin(builtin) => return self.map(&builtin),
+      ResolvedType::Unknown => return Err("unsupported_type"),
+      ResolvedType::Type(resolved) => resolved.clone(),
+    };
+    let target = self.env.typedefs[name].ty.ty.clone();
+    let mut mapped = match &resolved {
+      CType::Function(function) => self.map_function(function)?,
+      _ => self.map(&target)?,
+    };
+    if self.emits_typedef(name) {
+      mapped.reml = name.to_string();
+    }
+    Ok(mapped)
+  }
+
+  fn map_pointer(
+    &mut self,
+    pointee: &CType,
+    const_pointee: bool,
+  ) -> Result<MappedType, &'static str> {
+    // 関数ポインタは Reml の関数型として表す
+    if let CType::Named(name) = pointee {
+      if let ResolvedType::Type(CType::Function(function)) = self.env.resolve(pointee) {
+        let mut mapped = self.map_function(function)?;
+        if self.emits_typedef(name) {
+          mapped.reml = name.clone();
+        }
+        return Ok(mapped);
+      }
+    }
+    if let CType::Function(function) = pointee {
+      return self.map_function(function);
+    }
+    let inner = match pointee {
+      // 一次ファイル外・不完全・変換できない構造体へのポインタは不透明ポインタにする
+      CType::Record { tag, .. }
+        if !self.emits_record(tag) || self.env.record_layout(tag).is_err() =>
+      {
+        MappedType::new("()", "Unit", "ffi.Void")
+      }
+      CType::Array { element, .. } => return self.map_pointer(element, const_pointee),
+      _ => match self.map(pointee) {
+        Ok(mapped) => mapped,
+        Err("unsupported_type") => return Err("unsupported_type"),
+        Err(_) => MappedType::new("()", "Unit", "ffi.Void"),
+      },
+    };
+    let constructor = if const_pointee {
+      "ffi.const_ptr"
+    } else {
+      "ffi.ptr"
+    };
+    Ok(MappedType::new(
+      format!("Ptr<{}>", inner.reml),
+      format!("Ptr<{}>", inner.manifest),
+      format!("{}({})", constructor, inner.dsl),
+    ))
+  }
+
+  pub fn map_function(&mut self, function: &FnType) -> Result<MappedType, &'static str> {
+    let mut reml = Vec::new();
+    let mut manifest = Vec::new();
+    let mut dsl = Vec::new();
+    for param in &function.params {
+      let mapped = self.map(&param.ty.ty)?;
+      reml.push(mapped.reml);
+      manifest.push(mapped.manifest);
+      dsl.push(mapped.dsl);
+    }
+    if function.variadic {
+      reml.push("...".to_string());
+      manifest.push("...".to_string());
+    }
+    let returns = self.map(&function.returns.ty)?;
+    Ok(MappedType::new(
+      format!("fn({}) -> {}", reml.join(", "), returns.reml),
+      format!("Fn({}) -> {}", manifest.join(", "), returns.manifest),
+      format!(
+        "ffi.Fn(ffi.fn_sig([{}], {}, {}))",
+        dsl.join(", "),
+        returns.dsl,
+        function.variadic
+      ),
+    ))
+  }
+}
+
+/// 構造体・列挙型の `Core.Ffi.Dsl` 記述子を束縛する名前。
+pub fn ffi_type_binding(tag: &str) -> String {
+  format!("{}_ffi_type", tag)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn type_ref(ty: CType) -> TypeRef {
+    TypeRef {
+      ty,
+      c_text: String::new(),
+      qualifiers: Vec::new(),
+    }
+  }
+
+  fn record(tag: &str, fields: Vec<(&str, CType)>, packed: bool) -> RecordDef {
+    RecordDef {
+      kind: RecordKind::Struct,
+      tag: tag.to_string(),
+      fields: Some(
+        fields
+          .into_iter()
+          .map(|(name, ty)| FieldDef {
+            name: name.to_string(),
+            ty: type_ref(ty),
+            bit_width: None,
+          })
+          .collect(),
+      ),
+      packed,
+      unsupported: None,
+      file: 0,
+    }
+  }
+
+  #[test]
+  fn computes_natural_and_packed_layouts() {
+    let mut env = TypeEnv::default();
+    let fields = vec![
+      ("tag", CType::Char),
+      ("value", CType::Double),
+      (
+        "name",
+        CType::Array {
+          element: Box::new(CType::Char),
+          len: Some(3),
+        },
+      ),
+      ("count", CType::Named("uint16_t".into())),
+    ];
+    env
+      .records
+      .insert("natural".into(), record("natural", fields.clone(), false));
+    env
+      .records
+      .insert("packed".into(), record("packed", fields, true));
+
+    let natural = env.record_layout("natural").unwrap();
+    assert_eq!(natural.layout, Layout { size: 24, align: 8 });
+    assert_eq!(
+      natural
+        .fields
+        .iter()
+        .map(|field| field.offset)
+        .collect::<Vec<_>>(),
+      [0, 8, 16, 20]
+    );
+    let packed = env.record_layout("packed").unwrap();
+    assert_eq!(packed.layout, Layout { size: 14, align: 1 });
+    assert_eq!(
+      packed
+        .fields
+        .iter()
+        .map(|field| field.offset)
+        .collect::<Vec<_>>(),
+      [0, 1, 9, 12]
+    );
+  }
+}
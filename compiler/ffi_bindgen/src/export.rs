@@ -0,0 +1,129 @@
+//! 逆方向の生成（`--export-header`）。
+//!
+//! MIR JSON の `@export` 関数と `extern "C"` 宣言から C ヘッダを作り、
+//! 順方向と同じ形式の `bindings.manifest.json` を書き出す。
+
+use crate::{
+  write_file, BindgenError, BindingDirection, CliOptions, DiagnosticEntry, Manifest, ManifestField,
+  ManifestLayout, ManifestType, RunResult,
+};
+use reml_llvm_backend::{emit_c_header_from_mir_json_str, CHeader, CHeaderOptions, DataLayoutSpec};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn run_export_header(cli: &CliOptions) -> Result<RunResult, BindgenError> {
+  let mir_path = cli.mir.as_deref().map(PathBuf::from).ok_or_else(|| {
+    BindgenError::ConfigInvalid("--export-header には --mir が必要です".to_string())
+  })?;
+  let output_path = cli.output.as_deref().map(PathBuf::from).ok_or_else(|| {
+    BindgenError::ConfigInvalid("--export-header には --output が必要です".to_string())
+  })?;
+  let manifest_path = match cli.manifest.as_deref() {
+    Some(path) => PathBuf::from(path),
+    None => output_path
+      .parent()
+      .unwrap_or_else(|| Path::new("."))
+      .join("bindings.manifest.json"),
+  };
+
+  let json = fs::read_to_string(&mir_path)
+    .map_err(|err| BindgenError::ConfigInvalid(format!("{}: {}", mir_path.display(), err)))?;
+  let options = CHeaderOptions {
+    guard: cli.guard.clone(),
+    module: cli.module.clone(),
+  };
+  let header = emit_c_header_from_mir_json_str(&json, DataLayoutSpec::system_v(), &options)
+    .map_err(|err| BindgenError::ParseFailed(format!("{}: {}", mir_path.display(), err)))?;
+
+  let mut diagnostics = convert_diagnostics(&header);
+  write_file(&output_path, &header.text, &mut diagnostics)?;
+
+  let manifest = Manifest {
+    version: "0.1".to_string(),
+    direction: BindingDirection::Export,
+    source: Some(mir_path.to_string_lossy().to_string()),
+    headers: vec![output_path.to_string_lossy().to_string()],
+    generated: output_path.to_string_lossy().to_string(),
+    input_hash: calculate_input_hash(&json, &options),
+    types: convert_types(&header),
+    layouts: convert_layouts(&header),
+    constants: Vec::new(),
+    diagnostics: diagnostics.clone(),
+  };
+  let manifest_json = serde_json::to_string_pretty(&manifest)
+    .map_err(|err| BindgenError::GenerateFailed(err.to_string()))?;
+  write_file(&manifest_path, &manifest_json, &mut diagnostics)?;
+
+  Ok(RunResult {
+    output_path,
+    manifest_path,
+    diagnostics,
+    manifest,
+  })
+}
+
+fn convert_types(header: &CHeader) -> Vec<ManifestType> {
+  header
+    .types
+    .iter()
+    .map(|entry| ManifestType {
+      c: entry.c.clone(),
+      reml: entry.reml.clone(),
+      qualifiers: (!entry.qualifiers.is_empty()).then(|| entry.qualifiers.clone()),
+    })
+    .collect()
+}
+
+fn convert_layouts(header: &CHeader) -> Vec<ManifestLayout> {
+  header
+    .layouts
+    .iter()
+    .map(|layout| ManifestLayout {
+      name: layout.name.clone(),
+      size: layout.size,
+      align: layout.align,
+      fields: layout
+        .fields
+        .iter()
+        .map(|field| ManifestField {
+          name: field.name.clone(),
+          offset: field.offset,
+          size: field.size,
+          reml: field.reml.clone(),
+        })
+        .collect(),
+    })
+    .collect()
+}
+
+fn convert_diagnostics(header: &CHeader) -> Vec<DiagnosticEntry> {
+  header
+    .diagnostics
+    .iter()
+    .map(|entry| DiagnosticEntry {
+      code: entry.code.clone(),
+      symbol: Some(entry.symbol.clone()),
+      c_type: entry.c_type.clone(),
+      reason: Some(entry.reason.clone()),
+      hint: entry.hint.clone(),
+    })
+    .collect()
+}
+
+fn calculate_input_hash(json: &str, options: &CHeaderOptions) -> String {
+  let mut hasher = Sha256::new();
+  hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+  hasher.update(b"\nexport\n");
+  hasher.update(json.as_bytes());
+  for value in [&options.guard, &options.module].into_iter().flatten() {
+    hasher.update(b"\n");
+    hasher.update(value.as_bytes());
+  }
+  let digest = hasher.finalize();
+  let mut hex = String::new();
+  for byte in digest.iter().take(8) {
+    hex.push_str(&format!("{:02x}", byte));
+  }
+  hex
+}
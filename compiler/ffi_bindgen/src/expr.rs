@@ -0,0 +1,412 @@
+//! C の整数定数式の評価。
+//!
+//! `#if` の条件、列挙子の値、配列長、`#define` 定数で共有する。
+//! 値は `int` / `unsigned int` / `long` / `unsigned long`（LP64）の幅で丸める。
+
+use crate::lexer::{Token, TokenKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntValue {
+  pub value: i128,
+  pub unsigned: bool,
+  pub wide: bool,
+}
+
+impl IntValue {
+  pub fn int(value: i128) -> Self {
+    Self::normalized(value, false, false)
+  }
+
+  fn normalized(value: i128, unsigned: bool, wide: bool) -> Self {
+    let bits = if wide { 64 } else { 32 };
+    let mask = (1i128 << bits) - 1;
+    let truncated = value & mask;
+    let value = if unsigned || truncated < (1i128 << (bits - 1)) {
+      truncated
+    } else {
+      truncated - (1i128 << bits)
+    };
+    Self {
+      value,
+      unsigned,
+      wide,
+    }
+  }
+
+  /// 通常の算術変換で 2 値の型をそろえる。
+  fn promote(self, other: Self) -> (bool, bool) {
+    let wide = self.wide || other.wide;
+    let unsigned = match (self.wide, other.wide) {
+      (true, false) => self.unsigned,
+      (false, true) => other.unsigned,
+      _ => self.unsigned || other.unsigned,
+    };
+    (unsigned, wide)
+  }
+
+  pub fn is_true(self) -> bool {
+    self.value != 0
+  }
+}
+
+/// 識別子の解決と型名判定を提供する環境。
+pub trait ConstEnv {
+  fn value_of(&self, name: &str) -> Option<IntValue>;
+
+  fn is_type_name(&self, _name: &str) -> bool {
+    false
+  }
+}
+
+/// 識別子を持たない環境。
+pub struct NoConstants;
+
+impl ConstEnv for NoConstants {
+  fn value_of(&self, _name: &str) -> Option<IntValue> {
+    None
+  }
+}
+
+/// トークン列全体を 1 つの定数式として評価する。
+pub fn evaluate(tokens: &[Token], env: &dyn ConstEnv) -> Result<IntValue, String> {
+  let mut parser = ExprParser {
+    tokens,
+    pos: 0,
+    env,
+  };
+  let value = parser.conditional()?;
+  match parser.tokens.get(parser.pos) {
+    Some(token) => Err(format!(
+      "式の末尾に余分なトークンがあります: `{}`",
+      token.text
+    )),
+    None => Ok(value),
+  }
+}
+
+/// 整数リテラル（接尾辞付き）を解釈する。
+pub fn parse_int_literal(text: &str) -> Option<IntValue> {
+  let lower = text.to_ascii_lowercase();
+  let digits_end = lower.trim_end_matches(['u', 'l']).len();
+  let (digits, suffix) = lower.split_at(digits_end);
+  if suffix.matches('u').count() > 1 || suffix.matches('l').count() > 2 {
+    return None;
+  }
+  let (radix, body) = if let Some(rest) = digits.strip_prefix("0x") {
+    (16, rest)
+  } else if let Some(rest) = digits.strip_prefix("0b") {
+    (2, rest)
+  } else if digits.len() > 1 && digits.starts_with('0') {
+    (8, &digits[1..])
+  } else {
+    (10, digits)
+  };
+  let body = body.replace('\'', "");
+  let value = u128::from_str_radix(&body, radix).ok()?;
+  if value > u64::MAX as u128 {
+    return None;
+  }
+  let value = value as i128;
+  let explicit_unsigned = suffix.contains('u');
+  let explicit_long = suffix.contains('l');
+  // C11 6.4.4.1: 値が収まる最初の型を選ぶ（10 進は符号付きのみ）。
+  let candidates: &[(bool, bool)] = match (explicit_unsigned, explicit_long, radix == 10) {
+    (true, true, _) => &[(true, true)],
+    (true, false, _) => &[(true, false), (true, true)],
+    (false, true, true) => &[(false, true), (true, true)],
+    (false, true, false) => &[(false, true), (true, true)],
+    (false, false, true) => &[(false, false), (false, true), (true, true)],
+    (false, false, false) => &[(false, false), (true, false), (false, true), (true, true)],
+  };
+  candidates
+    .iter()
+    .find(|(unsigned, wide)| {
+      let bits = if *wide { 64 } else { 32 };
+      let limit = if *unsigned {
+        1i128 << bits
+      } else {
+        1i128 << (bits - 1)
+      };
+      value < limit
+    })
+    .map(|(unsigned, wide)| IntValue::normalized(value, *unsigned, *wide))
+}
+
+/// 文字リテラルの値を返す。
+pub fn parse_char_literal(text: &str) -> Option<IntValue> {
+  let body = text
+    .trim_start_matches(['L', 'u', 'U', '8'])
+    .strip_prefix('\'')?
+    .strip_suffix('\'')?;
+  let mut chars = body.chars();
+  let value = match chars.next()? {
+    '\\' => match chars.next()? {
+      'n' => 10,
+      't' => 9,
+      'r' => 13,
+      'a' => 7,
+      'b' => 8,
+      'f' => 12,
+      'v' => 11,
+      'e' => 27,
+      '\\' => 92,
+      '\'' => 39,
+      '"' => 34,
+      '?' => 63,
+      'x' => i128::from_str_radix(chars.as_str(), 16).ok()?,
+      digit @ '0'..='7' => {
+        let octal: String = std::iter::once(digit).chain(chars.by_ref()).collect();
+        i128::from_str_radix(&octal, 8).ok()?
+      }
+      _ => return None,
+    },
+    ch => ch as i128,
+  };
+  Some(IntValue::int(value))
+}
+
+struct ExprParser<'a> {
+  tokens: &'a [Token],
+  pos: usize,
+  env: &'a dyn ConstEnv,
+}
+
+const BINARY_LEVELS: [&[&str]; 10] = [
+  &["||"],
+  &["&&"],
+  &["|"],
+  &["^"],
+  &["&"],
+  &["==", "!="],
+  &["<", ">", "<=", ">="],
+  &["<<", ">>"],
+  &["+", "-"],
+  &["*", "/", "%"],
+];
+
+impl ExprParser<'_> {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.pos)
+  }
+
+  fn peek_is(&self, text: &str) -> bool {
+    self
+      .peek()
+      .is_some_and(|token| token.kind == TokenKind::Punct && token.text == text)
+  }
+
+  fn expect(&mut self, text: &str) -> Result<(), String> {
+    if self.peek_is(text) {
+      self.pos += 1;
+      Ok(())
+    } else {
+      Err(format!("`{}` が必要です", text))
+    }
+  }
+
+  fn conditional(&mut self) -> Result<IntValue, String> {
+    let condition = self.binary(0)?;
+    if !self.peek_is("?") {
+      return Ok(condition);
+    }
+    self.pos += 1;
+    let then_value = self.conditional()?;
+    self.expect(":")?;
+    let else_value = self.conditional()?;
+    Ok(if condition.is_true() {
+      then_value
+    } else {
+      else_value
+    })
+  }
+
+  fn binary(&mut self, level: usize) -> Result<IntValue, String> {
+    if level == BINARY_LEVELS.len() {
+      return self.unary();
+    }
+    let mut lhs = self.binary(level + 1)?;
+    while let Some(op) = self
+      .peek()
+      .filter(|token| {
+        token.kind == TokenKind::Punct && BINARY_LEVELS[level].contains(&token.text.as_str())
+      })
+      .map(|token| token.text.clone())
+    {
+      self.pos += 1;
+      let rhs = self.binary(level + 1)?;
+      lhs = apply_binary(&op, lhs, rhs)?;
+    }
+    Ok(lhs)
+  }
+
+  fn unary(&mut self) -> Result<IntValue, String> {
+    let Some(token) = self.peek() else {
+      return Err("式が途中で終わっています".to_string());
+    };
+    if token.kind == TokenKind::Punct {
+      let op = token.text.clone();
+      match op.as_str() {
+        "-" | "+" | "!" | "~" => {
+          self.pos += 1;
+          let value = self.unary()?;
+          return Ok(match op.as_str() {
+            "-" => IntValue::normalized(-value.value, value.unsigned, value.wide),
+            "+" => value,
+            "!" => IntValue::int(i128::from(!value.is_true())),
+            _ => IntValue::normalized(!value.value, value.unsigned, value.wide),
+          });
+        }
+        "(" if self.is_cast() => {
+          self.skip_cast();
+          return self.unary();
+        }
+        _ => {}
+      }
+    }
+    self.primary()
+  }
+
+  /// `(unsigned long)` のような型名だけの括弧かを判定する。
+  fn is_cast(&self) -> bool {
+    let mut index = self.pos + 1;
+    let mut saw_type = false;
+    while let Some(token) = self.tokens.get(index) {
+      match token.kind {
+        TokenKind::Ident if is_type_keyword(&token.text) || self.env.is_type_name(&token.text) => {
+          saw_type = true;
+        }
+        TokenKind::Punct if token.text == "*" && saw_type => {}
+        TokenKind::Punct if token.text == ")" => return saw_type,
+        _ => return false,
+      }
+      index += 1;
+    }
+    false
+  }
+
+  fn skip_cast(&mut self) {
+    while let Some(close) = self.peek().map(|token| token.text == ")") {
+      self.pos += 1;
+      if close {
+        break;
+      }
+    }
+  }
+
+  fn primary(&mut self) -> Result<IntValue, String> {
+    let token = self
+      .peek()
+      .cloned()
+      .ok_or_else(|| "式が途中で終わっています".to_string())?;
+    self.pos += 1;
+    match token.kind {
+      TokenKind::Number => parse_int_literal(&token.text)
+        .ok_or_else(|| format!("整数リテラルではありません: `{}`", token.text)),
+      TokenKind::Char => parse_char_literal(&token.text)
+        .ok_or_else(|| format!("文字リテラルを解釈できません: `{}`", token.text)),
+      TokenKind::Punct if token.text == "(" => {
+        let value = self.conditional()?;
+        self.expect(")")?;
+        Ok(value)
+      }
+      TokenKind::Ident => self
+        .env
+        .value_of(&token.text)
+        .ok_or_else(|| format!("定数として解決できない識別子です: `{}`", token.text)),
+      _ => Err(format!("定数式に使えないトークンです: `{}`", token.text)),
+    }
+  }
+}
+
+fn apply_binary(op: &str, lhs: IntValue, rhs: IntValue) -> Result<IntValue, String> {
+  let (unsigned, wide) = lhs.promote(rhs);
+  let bool_value = |value: bool| IntValue::int(i128::from(value));
+  let arith = |value: i128| IntValue::normalized(value, unsigned, wide);
+  let (a, b) = (
+    IntValue::normalized(lhs.value, unsigned, wide).value,
+    IntValue::normalized(rhs.value, unsigned, wide).value,
+  );
+  Ok(match op {
+    "||" => bool_value(lhs.is_true() || rhs.is_true()),
+    "&&" => bool_value(lhs.is_true() && rhs.is_true()),
+    "|" => arith(a | b),
+    "^" => arith(a ^ b),
+    "&" => arith(a & b),
+    "==" => bool_value(a == b),
+    "!=" => bool_value(a != b),
+    "<" => bool_value(a < b),
+    ">" => bool_value(a > b),
+    "<=" => bool_value(a <= b),
+    ">=" => bool_value(a >= b),
+    // シフトは左辺の型を保つ
+    "<<" | ">>" => {
+      if !(0..64).contains(&rhs.value) {
+        return Err("シフト量が範囲外です".to_string());
+      }
+      let shifted = if op == "<<" {
+        lhs.value << rhs.value
+      } else {
+        lhs.value >> rhs.value
+      };
+      IntValue::normalized(shifted, lhs.unsigned, lhs.wide)
+    }
+    "+" => arith(a + b),
+    "-" => arith(a - b),
+    "*" => arith(a * b),
+    "/" | "%" => {
+      if b == 0 {
+        return Err("0 除算です".to_string());
+      }
+      arith(if op == "/" { a / b } else { a % b })
+    }
+    _ => return Err(format!("未対応の演算子です: `{}`", op)),
+  })
+}
+
+fn is_type_keyword(text: &str) -> bool {
+  matches!(
+    text,
+    "void"
+      | "char"
+      | "short"
+      | "int"
+      | "long"
+      | "signed"
+      | "unsigned"
+      | "float"
+      | "double"
+      | "_Bool"
+      | "bool"
+      | "const"
+      | "volatile"
+      | "struct"
+      | "union"
+      | "enum"
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lexer::tokenize;
+
+  fn eval(source: &str) -> Result<IntValue, String> {
+    evaluate(&tokenize(source, 0, 1), &NoConstants)
+  }
+
+  #[test]
+  fn follows_c_integer_promotions() {
+    assert_eq!(eval("1 + 2 * 3").unwrap().value, 7);
+    assert_eq!(eval("(1 << 4) | 0x3").unwrap().value, 19);
+    assert_eq!(eval("~0u").unwrap().value, 0xFFFF_FFFF);
+    assert_eq!(eval("-1 < 0u").unwrap().value, 0);
+    assert_eq!(
+      eval("0x80000000").unwrap(),
+      IntValue::normalized(0x8000_0000, true, false)
+    );
+    assert_eq!(eval("(int)5 + (1)").unwrap().value, 6);
+    assert_eq!(eval("1 ? 'A' : 2").unwrap().value, 65);
+    assert!(eval("1 / 0").is_err());
+    assert!(eval("UNKNOWN + 1").is_err());
+  }
+}
@@ -0,0 +1,265 @@
+//! C ヘッダ向けの字句解析。
+//!
+//! 行継続の結合とコメント除去を行ったうえで、論理行単位にトークンへ分割する。
+//! プリプロセッサと宣言パーサが同じトークン表現を共有する。
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+  Ident,
+  Number,
+  Str,
+  Char,
+  Punct,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+  pub kind: TokenKind,
+  pub text: String,
+  /// 由来ファイル（`Preprocessor` のファイル表の添字）。
+  pub file: usize,
+  pub line: usize,
+}
+
+impl Token {
+  pub fn new(kind: TokenKind, text: impl Into<String>, file: usize, line: usize) -> Self {
+    Self {
+      kind,
+      text: text.into(),
+      file,
+      line,
+    }
+  }
+
+  pub fn is(&self, text: &str) -> bool {
+    self.text == text && matches!(self.kind, TokenKind::Punct | TokenKind::Ident)
+  }
+
+  pub fn is_ident(&self) -> bool {
+    self.kind == TokenKind::Ident
+  }
+}
+
+/// 論理行（行番号付き）。行継続は結合済みで、コメントは空白に置き換え済み。
+#[derive(Debug, Clone)]
+pub struct LogicalLine {
+  pub line: usize,
+  pub text: String,
+}
+
+/// ソース全体を論理行へ分割する。
+pub fn logical_lines(source: &str) -> Vec<LogicalLine> {
+  let mut spliced = Vec::new();
+  let mut current = String::new();
+  let mut start = 1;
+  for (index, raw) in source.lines().enumerate() {
+    let raw = raw.strip_suffix('\r').unwrap_or(raw);
+    if current.is_empty() {
+      start = index + 1;
+    }
+    match raw.strip_suffix('\\') {
+      Some(head) => current.push_str(head),
+      None => {
+        current.push_str(raw);
+        spliced.push(LogicalLine {
+          line: start,
+          text: std::mem::take(&mut current),
+        });
+      }
+    }
+  }
+  if !current.is_empty() {
+    spliced.push(LogicalLine {
+      line: start,
+      text: current,
+    });
+  }
+
+  let joined = spliced
+    .iter()
+    .map(|line| line.text.as_str())
+    .collect::<Vec<_>>()
+    .join("\n");
+  let stripped = strip_comments(&joined);
+  stripped
+    .split('\n')
+    .zip(spliced)
+    .map(|(text, line)| LogicalLine {
+      line: line.line,
+      text: text.to_string(),
+    })
+    .collect()
+}
+
+/// コメントを空白へ置き換える。ブロックコメント内の改行は保持する。
+fn strip_comments(source: &str) -> String {
+  let chars: Vec<char> = source.chars().collect();
+  let mut output = String::with_capacity(source.len());
+  let mut index = 0;
+  while index < chars.len() {
+    let ch = chars[index];
+    match ch {
+      '"' | '\'' => {
+        let end = skip_quoted(&chars, index);
+        output.extend(&chars[index..end]);
+        index = end;
+      }
+      '/' if chars.get(index + 1) == Some(&'/') => {
+        while index < chars.len() && chars[index] != '\n' {
+          index += 1;
+        }
+        output.push(' ');
+      }
+      '/' if chars.get(index + 1) == Some(&'*') => {
+        index += 2;
+        output.push(' ');
+        while index < chars.len() && !(chars[index] == '*' && chars.get(index + 1) == Some(&'/')) {
+          if chars[index] == '\n' {
+            output.push('\n');
+          }
+          index += 1;
+        }
+        index = (index + 2).min(chars.len());
+      }
+      _ => {
+        output.push(ch);
+        index += 1;
+      }
+    }
+  }
+  output
+}
+
+/// 文字列・文字リテラルの終端（閉じ引用符の次）を返す。行末で打ち切る。
+fn skip_quoted(chars: &[char], start: usize) -> usize {
+  let quote = chars[start];
+  let mut index = start + 1;
+  while index < chars.len() {
+    match chars[index] {
+      '\\' => index += 2,
+      '\n' => return index,
+      ch if ch == quote => return index + 1,
+      _ => index += 1,
+    }
+  }
+  chars.len()
+}
+
+const PUNCTUATORS: [&str; 24] = [
+  "...", "<<=", ">>=", "->", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "*=",
+  "/=", "%=", "+=", "-=", "&=", "^=", "|=", "##", "::",
+];
+
+/// 1 論理行をトークンへ分割する。
+pub fn tokenize(text: &str, file: usize, line: usize) -> Vec<Token> {
+  let chars: Vec<char> = text.chars().collect();
+  let mut tokens = Vec::new();
+  let mut index = 0;
+  while index < chars.len() {
+    let ch = chars[index];
+    if ch.is_whitespace() {
+      index += 1;
+      continue;
+    }
+    let start = index;
+    let kind = if ch.is_ascii_alphabetic() || ch == '_' || ch == '$' {
+      while index < chars.len()
+        && (chars[index].is_ascii_alphanumeric() || matches!(chars[index], '_' | '$'))
+      {
+        index += 1;
+      }
+      // `L"..."` / `u8"..."` などの接頭辞付きリテラル
+      let prefix: String = chars[start..index].iter().collect();
+      match chars.get(index) {
+        Some('"' | '\'') if matches!(prefix.as_str(), "L" | "u" | "U" | "u8") => {
+          let quote = chars[index];
+          index = skip_quoted(&chars, index);
+          if quote == '"' {
+            TokenKind::Str
+          } else {
+            TokenKind::Char
+          }
+        }
+        _ => TokenKind::Ident,
+      }
+    } else if ch.is_ascii_digit()
+      || (ch == '.' && chars.get(index + 1).is_some_and(|c| c.is_ascii_digit()))
+    {
+      index += 1;
+      while index < chars.len() {
+        let current = chars[index];
+        let exponent_sign =
+          matches!(current, '+' | '-') && matches!(chars[index - 1], 'e' | 'E' | 'p' | 'P');
+        if current.is_ascii_alphanumeric() || current == '_' || current == '.' || exponent_sign {
+          index += 1;
+        } else {
+          break;
+        }
+      }
+      TokenKind::Number
+    } else if ch == '"' {
+      index = skip_quoted(&chars, index);
+      TokenKind::Str
+    } else if ch == '\'' {
+      index = skip_quoted(&chars, index);
+      TokenKind::Char
+    } else {
+      let rest: String = chars[index..chars.len().min(index + 3)].iter().collect();
+      let width = PUNCTUATORS
+        .iter()
+        .find(|punct| rest.starts_with(*punct))
+        .map(|punct| punct.chars().count())
+        .unwrap_or(1);
+      index += width;
+      TokenKind::Punct
+    };
+    tokens.push(Token::new(
+      kind,
+      chars[start..index].iter().collect::<String>(),
+      file,
+      line,
+    ));
+  }
+  tokens
+}
+
+/// トークン列を C の表記に近い文字列へ戻す（診断・マニフェスト用）。
+pub fn render_tokens(tokens: &[Token]) -> String {
+  let mut output = String::new();
+  let mut previous: Option<&Token> = None;
+  for token in tokens {
+    if let Some(prev) = previous {
+      let tight_after = matches!(prev.text.as_str(), "(" | "[");
+      let tight_before = matches!(token.text.as_str(), "*" | "," | ";" | ")" | "[" | "]")
+        || (token.text == "(" && (prev.text == ")" || is_call_target(prev)));
+      if !tight_after && !tight_before {
+        output.push(' ');
+      }
+    }
+    output.push_str(&token.text);
+    previous = Some(token);
+  }
+  output
+}
+
+/// 直後の `(` を詰めて書く識別子か（型キーワードの後は `void (*)(int)` のように空ける）。
+fn is_call_target(token: &Token) -> bool {
+  token.is_ident()
+    && !matches!(
+      token.text.as_str(),
+      "void"
+        | "char"
+        | "short"
+        | "int"
+        | "long"
+        | "float"
+        | "double"
+        | "signed"
+        | "unsigned"
+        | "_Bool"
+        | "bool"
+        | "const"
+        | "volatile"
+        | "restrict"
+    )
+}
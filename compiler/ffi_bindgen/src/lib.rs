@@ -1,12 +1,29 @@
+mod cparse;
+mod ctype;
+mod export;
+mod expr;
+mod lexer;
+mod preprocess;
+
+pub use export::run_export_header;
+
+use cparse::{DeclParser, Item, SKIPPED_CODE};
+use ctype::{
+  enum_repr, ffi_type_binding, CType, EmitScope, FnType, IntRepr, MappedType, TypeEnv, TypeMapper,
+  TypeRef,
+};
+use lexer::{render_tokens, Token, TokenKind};
+use preprocess::{PreprocessOptions, Preprocessed, Preprocessor};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::BTreeSet;
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(default)]
 pub struct BindgenConfig {
   pub headers: Vec<String>,
   pub include_paths: Vec<String>,
@@ -36,20 +53,6 @@ impl BindgenConfig {
   }
 }
 
-impl Default for BindgenConfig {
-  fn default() -> Self {
-    Self {
-      headers: Vec::new(),
-      include_paths: Vec::new(),
-      compile_commands: None,
-      defines: Vec::new(),
-      output: String::new(),
-      manifest: String::new(),
-      exclude: Vec::new(),
-    }
-  }
-}
-
 #[derive(Debug, Default, Clone)]
 pub struct CliOptions {
   pub config_path: Option<PathBuf>,
@@ -60,6 +63,11 @@ pub struct CliOptions {
   pub output: Option<String>,
   pub manifest: Option<String>,
   pub exclude: Vec<String>,
+  /// `--export-header`: MIR から C ヘッダを生成する逆方向のモード。
+  pub export_header: bool,
+  pub mir: Option<String>,
+  pub guard: Option<String>,
+  pub module: Option<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -78,13 +86,37 @@ pub struct DiagnosticEntry {
 #[derive(Debug, Serialize)]
 pub struct Manifest {
   pub version: String,
+  #[serde(skip_serializing_if = "BindingDirection::is_import")]
+  pub direction: BindingDirection,
+  /// 逆方向の生成元（MIR JSON）。
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub source: Option<String>,
   pub headers: Vec<String>,
   pub generated: String,
   pub input_hash: String,
   pub types: Vec<ManifestType>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub layouts: Vec<ManifestLayout>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub constants: Vec<ManifestConstant>,
   pub diagnostics: Vec<DiagnosticEntry>,
 }
 
+/// 生成の向き。C ヘッダ → Reml が `import`、Reml → C ヘッダが `export`。
+#[derive(Debug, Default, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BindingDirection {
+  #[default]
+  Import,
+  Export,
+}
+
+impl BindingDirection {
+  fn is_import(&self) -> bool {
+    *self == BindingDirection::Import
+  }
+}
+
 #[derive(Debug, Serialize, Clone, PartialEq, Eq)]
 pub struct ManifestType {
   pub c: String,
@@ -93,6 +125,30 @@ pub struct ManifestType {
   pub qualifiers: Option<Vec<String>>,
 }
 
+/// 構造体のレイアウト検証用の値（C コンパイラの `sizeof` / `offsetof` と一致すること）。
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct ManifestLayout {
+  pub name: String,
+  pub size: u64,
+  pub align: u64,
+  pub fields: Vec<ManifestField>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct ManifestField {
+  pub name: String,
+  pub offset: u64,
+  pub size: u64,
+  pub reml: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct ManifestConstant {
+  pub name: String,
+  pub reml: String,
+  pub value: String,
+}
+
 #[derive(Debug)]
 pub struct RunResult {
   pub output_path: PathBuf,
@@ -114,7 +170,7 @@ pub enum BindgenError {
 pub fn load_config(config_path: &Path) -> Result<BindgenConfig, BindgenError> {
   let content = fs::read_to_string(config_path)
     .map_err(|err| BindgenError::ConfigInvalid(err.to_string()))?;
-  let mut config: BindgenConfig = toml::from_str(&content)
+  let config: BindgenConfig = toml::from_str(&content)
     .map_err(|err| BindgenError::ConfigInvalid(err.to_string()))?;
 
   if config.headers.is_empty() {
@@ -159,39 +215,53 @@ pub fn run_bindgen(config_path: &Path, cli: &CliOptions) -> Result<RunResult, Bi
 
   let exclude_patterns = compile_excludes(&config.exclude);
 
-  let mut diagnostics = Vec::new();
-  let mut functions = Vec::new();
-  let mut types = Vec::new();
-  let mut types_seen = BTreeSet::new();
+  let mut options = PreprocessOptions {
+    include_paths: resolve_paths(config_dir, &config.include_paths),
+    defines: config.defines.clone(),
+    undefines: Vec::new(),
+  };
+  if let Some(commands) = &config.compile_commands {
+    let flags = load_compile_commands(&resolve_path(config_dir, commands))?;
+    options.include_paths.extend(flags.include_paths);
+    // 設定ファイルの defines を後から適用して優先させる
+    options.defines.splice(0..0, flags.defines);
+    options.undefines = flags.undefines;
+  }
 
+  let mut preprocessor = Preprocessor::new(options);
   for header in &header_paths {
-    let content = fs::read_to_string(header)
-      .map_err(|err| BindgenError::ParseFailed(err.to_string()))?;
-    let parsed = parse_header(&content, header, &exclude_patterns, &mut diagnostics)?;
-    for func in parsed.functions {
-      for ty in func.types {
-        if types_seen.insert((ty.c.clone(), ty.reml.clone(), ty.qualifiers.clone())) {
-          types.push(ty);
-        }
-      }
-      functions.push(func);
-    }
+    preprocessor
+      .process_header(header)
+      .map_err(|err| BindgenError::ParseFailed(format!("{}: {}", header.display(), err)))?;
   }
+  let preprocessed = preprocessor.finish();
+  let mut diagnostics = preprocessed.diagnostics.clone();
+  let (env, items) = DeclParser::new(preprocessed.tokens.clone()).parse();
+
+  let scope = Scope {
+    preprocessed: &preprocessed,
+    excludes: &exclude_patterns,
+  };
+  let bindings = collect_bindings(&env, &items, &preprocessed, &scope, &mut diagnostics);
 
   let module_name = infer_module_name(&output_path);
-  let reml_source = render_reml(&module_name, &functions);
+  let reml_source = render_reml(&module_name, &bindings);
 
   write_file(&output_path, &reml_source, &mut diagnostics)?;
 
   let manifest = Manifest {
     version: "0.1".to_string(),
+    direction: BindingDirection::Import,
+    source: None,
     headers: header_paths
       .iter()
       .map(|path| path.to_string_lossy().to_string())
       .collect(),
     generated: output_path.to_string_lossy().to_string(),
     input_hash: input_hash.clone(),
-    types,
+    types: bindings.types,
+    layouts: bindings.layouts,
+    constants: bindings.manifest_constants,
     diagnostics: diagnostics.clone(),
   };
 
@@ -259,335 +329,650 @@ fn compile_excludes(excludes: &[String]) -> Vec<Regex> {
     .collect()
 }
 
-#[derive(Debug)]
-struct ParsedHeader {
-  functions: Vec<ParsedFunction>,
+/// `compile_commands.json` から取り出した前処理フラグ。
+#[derive(Debug, Default)]
+struct CompileFlags {
+  include_paths: Vec<PathBuf>,
+  defines: Vec<String>,
+  undefines: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
-struct ParsedFunction {
-  name: String,
-  params: Vec<ParsedParam>,
-  return_type: ParsedType,
-  types: Vec<ManifestType>,
+#[derive(Debug, Deserialize)]
+struct CompileCommand {
+  #[serde(default)]
+  directory: Option<String>,
+  #[serde(default)]
+  command: Option<String>,
+  #[serde(default)]
+  arguments: Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone)]
-struct ParsedParam {
-  name: Option<String>,
-  ty: ParsedType,
-}
+fn load_compile_commands(path: &Path) -> Result<CompileFlags, BindgenError> {
+  let content = fs::read_to_string(path)
+    .map_err(|err| BindgenError::ConfigInvalid(format!("{}: {}", path.display(), err)))?;
+  let commands: Vec<CompileCommand> = serde_json::from_str(&content)
+    .map_err(|err| BindgenError::ConfigInvalid(format!("{}: {}", path.display(), err)))?;
 
-#[derive(Debug, Clone)]
-struct ParsedType {
-  c_type: String,
-  reml: String,
-  manifest: String,
-  qualifiers: Vec<String>,
-}
-
-fn parse_header(
-  content: &str,
-  header_path: &Path,
-  excludes: &[Regex],
-  diagnostics: &mut Vec<DiagnosticEntry>,
-) -> Result<ParsedHeader, BindgenError> {
-  let function_re = Regex::new(r"^\s*([A-Za-z_][\w\s\*]*?)\s+([A-Za-z_]\w*)\s*\(([^)]*)\)\s*;")
-    .map_err(|err| BindgenError::ParseFailed(err.to_string()))?;
-
-  let mut functions = Vec::new();
-  for line in content.lines() {
-    let trimmed = strip_line_comment(line).trim();
-    if trimmed.is_empty() || trimmed.starts_with('#') {
-      continue;
-    }
-    if let Some(caps) = function_re.captures(trimmed) {
-      let return_raw = caps.get(1).unwrap().as_str();
-      let name = caps.get(2).unwrap().as_str();
-      if excludes.iter().any(|regex| regex.is_match(name)) {
-        continue;
-      }
-      let params_raw = caps.get(3).unwrap().as_str();
-
-      let mut local_diagnostics = Vec::new();
-      let return_type = match parse_type(return_raw, name, &mut local_diagnostics) {
-        Some(value) => value,
-        None => {
-          diagnostics.append(&mut local_diagnostics);
-          continue;
+  let base = path.parent().unwrap_or_else(|| Path::new("."));
+  let mut flags = CompileFlags::default();
+  for command in commands {
+    let directory = command
+      .directory
+      .as_deref()
+      .map(|dir| resolve_path(base, dir))
+      .unwrap_or_else(|| base.to_path_buf());
+    let arguments = match (command.arguments, command.command) {
+      (Some(arguments), _) => arguments,
+      (None, Some(command)) => split_command(&command),
+      (None, None) => continue,
+    };
+    let mut iter = arguments.iter().skip(1);
+    while let Some(arg) = iter.next() {
+      let (flag, inline) = match arg.as_str() {
+        "-I" | "-isystem" | "-iquote" | "-D" | "-U" => (arg.as_str(), None),
+        _ if arg.starts_with("-isystem") => ("-isystem", Some(&arg["-isystem".len()..])),
+        _ if arg.starts_with("-I") || arg.starts_with("-D") || arg.starts_with("-U") => {
+          (&arg[..2], Some(&arg[2..]))
         }
+        _ => continue,
       };
-
-      let params = match parse_params(params_raw, name, &mut local_diagnostics) {
-        Some(value) => value,
-        None => {
-          diagnostics.append(&mut local_diagnostics);
-          continue;
-        }
+      let Some(value) = inline.map(str::to_string).or_else(|| iter.next().cloned()) else {
+        break;
       };
-
-      let mut types = Vec::new();
-      let mut types_seen = BTreeSet::new();
-      collect_type_entry(&return_type, &mut types, &mut types_seen);
-      for param in &params {
-        collect_type_entry(&param.ty, &mut types, &mut types_seen);
+      match flag {
+        "-D" => push_unique(&mut flags.defines, value),
+        "-U" => push_unique(&mut flags.undefines, value),
+        _ => {
+          let include = resolve_path(&directory, &value);
+          if !flags.include_paths.contains(&include) {
+            flags.include_paths.push(include);
+          }
+        }
       }
-
-      diagnostics.append(&mut local_diagnostics);
-      functions.push(ParsedFunction {
-        name: name.to_string(),
-        params,
-        return_type,
-        types,
-      });
     }
   }
-
-  Ok(ParsedHeader { functions })
+  Ok(flags)
 }
 
-fn collect_type_entry(
-  parsed: &ParsedType,
-  types: &mut Vec<ManifestType>,
-  types_seen: &mut BTreeSet<(String, String, Option<Vec<String>>)>,
-) {
-  let qualifiers = if parsed.qualifiers.is_empty() {
-    None
-  } else {
-    Some(parsed.qualifiers.clone())
-  };
-  let entry = ManifestType {
-    c: parsed.c_type.clone(),
-    reml: parsed.manifest.clone(),
-    qualifiers,
-  };
-  if types_seen.insert((entry.c.clone(), entry.reml.clone(), entry.qualifiers.clone())) {
-    types.push(entry);
+fn push_unique(values: &mut Vec<String>, value: String) {
+  if !values.contains(&value) {
+    values.push(value);
   }
 }
 
-fn strip_line_comment(line: &str) -> &str {
-  match line.find("//") {
-    Some(index) => &line[..index],
-    None => line,
+/// `command` 文字列を引数へ分割する（引用符とバックスラッシュのみ解釈）。
+fn split_command(command: &str) -> Vec<String> {
+  let mut arguments = Vec::new();
+  let mut current = String::new();
+  let mut quote = None;
+  let mut has_token = false;
+  let mut chars = command.chars();
+  while let Some(ch) = chars.next() {
+    match (ch, quote) {
+      ('\\', Some('\'')) => current.push(ch),
+      ('\\', _) => {
+        if let Some(next) = chars.next() {
+          current.push(next);
+        }
+        has_token = true;
+      }
+      ('"' | '\'', None) => {
+        quote = Some(ch);
+        has_token = true;
+      }
+      (_, Some(open)) if ch == open => quote = None,
+      (_, None) if ch.is_whitespace() => {
+        if has_token {
+          arguments.push(std::mem::take(&mut current));
+          has_token = false;
+        }
+      }
+      _ => {
+        current.push(ch);
+        has_token = true;
+      }
+    }
   }
+  if has_token {
+    arguments.push(current);
+  }
+  arguments
 }
 
-fn parse_params(
-  raw: &str,
-  symbol: &str,
-  diagnostics: &mut Vec<DiagnosticEntry>,
-) -> Option<Vec<ParsedParam>> {
-  let trimmed = raw.trim();
-  if trimmed.is_empty() || trimmed == "void" {
-    return Some(Vec::new());
-  }
-  if trimmed.contains("...") {
-    diagnostics.push(DiagnosticEntry {
-      code: "ffi.bindgen.unknown_type".to_string(),
-      symbol: Some(symbol.to_string()),
-      c_type: Some(trimmed.to_string()),
-      reason: Some("unsupported_variadic".to_string()),
-      hint: Some("phase2".to_string()),
-    });
-    return None;
+pub(crate) fn diagnostic(
+  code: &str,
+  symbol: Option<String>,
+  c_type: Option<String>,
+  reason: String,
+  hint: &str,
+) -> DiagnosticEntry {
+  DiagnosticEntry {
+    code: code.to_string(),
+    symbol,
+    c_type,
+    reason: Some(reason),
+    hint: Some(hint.to_string()),
   }
+}
 
-  let mut params = Vec::new();
-  for chunk in trimmed.split(',') {
-    let chunk = chunk.trim();
-    if chunk.is_empty() {
-      continue;
-    }
-    let (type_part, name_part) = split_type_and_name(chunk);
-    let parsed = match parse_type(&type_part, symbol, diagnostics) {
-      Some(value) => value,
-      None => return None,
-    };
-    params.push(ParsedParam {
-      name: name_part,
-      ty: parsed,
-    });
-  }
+const UNKNOWN_TYPE_CODE: &str = "ffi.bindgen.unknown_type";
+
+/// 配列メンバを `name_0..` へ展開する上限。
+const MAX_EXPANDED_ARRAY: u64 = 256;
 
-  Some(params)
+/// 一次ファイル由来で除外パターンに一致しない宣言だけを出力する。
+struct Scope<'a> {
+  preprocessed: &'a Preprocessed,
+  excludes: &'a [Regex],
 }
 
-fn split_type_and_name(raw: &str) -> (String, Option<String>) {
-  let spaced = raw.replace('*', " * ");
-  let tokens: Vec<&str> = spaced.split_whitespace().collect();
-  if tokens.is_empty() {
-    return (raw.to_string(), None);
+impl EmitScope for Scope<'_> {
+  fn emits(&self, name: &str, file: usize) -> bool {
+    self.preprocessed.is_primary(file) && !self.excludes.iter().any(|regex| regex.is_match(name))
   }
+}
 
-  let qualifiers = ["const", "volatile", "restrict"];
-  let base_tokens = [
-    "bool",
-    "char",
-    "signed",
-    "unsigned",
-    "short",
-    "int",
-    "long",
-    "size_t",
-    "intptr_t",
-    "uintptr_t",
-    "float",
-    "double",
-    "void",
-  ];
+#[derive(Debug, Default)]
+struct Bindings {
+  records: Vec<RecordBinding>,
+  aliases: Vec<(String, String)>,
+  enums: Vec<EnumBinding>,
+  constants: Vec<ConstBinding>,
+  functions: Vec<FunctionBinding>,
+  types: Vec<ManifestType>,
+  layouts: Vec<ManifestLayout>,
+  manifest_constants: Vec<ManifestConstant>,
+  symbols: HashSet<String>,
+}
 
-  let last = tokens[tokens.len() - 1];
-  let is_name = !qualifiers.contains(&last)
-    && !base_tokens.contains(&last)
-    && last != "*"
-    && !last.contains('*');
+#[derive(Debug)]
+struct RecordBinding {
+  tag: String,
+  packed: bool,
+  size: u64,
+  align: u64,
+  fields: Vec<(String, MappedType)>,
+}
+
+#[derive(Debug)]
+struct EnumBinding {
+  tag: String,
+  repr: IntRepr,
+  variants: Vec<(String, i128)>,
+}
 
-  if tokens.len() >= 2 && is_name {
-    let type_tokens = &tokens[..tokens.len() - 1];
-    return (type_tokens.join(" "), Some(last.to_string()));
+#[derive(Debug)]
+struct ConstBinding {
+  name: String,
+  reml: String,
+  value: String,
+}
+
+#[derive(Debug)]
+struct FunctionBinding {
+  name: String,
+  params: Vec<(Option<String>, String)>,
+  returns: String,
+}
+
+impl Bindings {
+  fn push_constant(
+    &mut self,
+    name: &str,
+    reml: &str,
+    manifest: &str,
+    value: String,
+    diagnostics: &mut Vec<DiagnosticEntry>,
+  ) {
+    if !self.symbols.insert(name.to_string()) {
+      diagnostics.push(diagnostic(
+        SKIPPED_CODE,
+        Some(name.to_string()),
+        None,
+        "duplicate_symbol".to_string(),
+        "exclude",
+      ));
+      return;
+    }
+    self.manifest_constants.push(ManifestConstant {
+      name: name.to_string(),
+      reml: manifest.to_string(),
+      value: value.clone(),
+    });
+    self.constants.push(ConstBinding {
+      name: name.to_string(),
+      reml: reml.to_string(),
+      value,
+    });
   }
 
-  (raw.to_string(), None)
+  fn push_type(&mut self, type_ref: &TypeRef, mapped: &MappedType) {
+    let entry = ManifestType {
+      c: type_ref.c_text.clone(),
+      reml: mapped.manifest.clone(),
+      qualifiers: if type_ref.qualifiers.is_empty() {
+        None
+      } else {
+        Some(type_ref.qualifiers.clone())
+      },
+    };
+    if !self.types.contains(&entry) {
+      self.types.push(entry);
+    }
+  }
 }
 
-fn parse_type(
-  raw: &str,
-  symbol: &str,
+fn collect_bindings(
+  env: &TypeEnv,
+  items: &[Item],
+  preprocessed: &Preprocessed,
+  scope: &Scope,
   diagnostics: &mut Vec<DiagnosticEntry>,
-) -> Option<ParsedType> {
-  let trimmed = raw.trim();
-  if trimmed.contains('[') {
-    diagnostics.push(DiagnosticEntry {
-      code: "ffi.bindgen.unknown_type".to_string(),
-      symbol: Some(symbol.to_string()),
-      c_type: Some(trimmed.to_string()),
-      reason: Some("unsupported_array".to_string()),
-      hint: Some("phase2".to_string()),
-    });
-    return None;
+) -> Bindings {
+  let mut mapper = TypeMapper::new(env, scope);
+  let mut bindings = Bindings::default();
+  let mut records_seen = HashSet::new();
+  let mut enums_seen = HashSet::new();
+
+  for item in items {
+    match item {
+      Item::Skipped(skipped) => {
+        if preprocessed.is_primary(skipped.file) {
+          diagnostics.push(diagnostic(
+            skipped.code,
+            skipped.symbol.clone(),
+            skipped.c_type.clone(),
+            skipped.reason.clone(),
+            skipped.hint,
+          ));
+        }
+      }
+      Item::Record { tag } => {
+        if mapper.emits_record(tag) && records_seen.insert(tag.clone()) {
+          collect_record(&mut mapper, env, tag, &mut bindings, diagnostics);
+        }
+      }
+      Item::Enum { tag } => {
+        if mapper.emits_enum(tag) && enums_seen.insert(tag.clone()) {
+          let def = &env.enums[tag];
+          let repr = enum_repr(def);
+          for (name, value) in &def.variants {
+            if scope.emits(name, def.file) {
+              bindings.push_constant(name, tag, repr.manifest(), value.value.to_string(), diagnostics);
+            }
+          }
+          bindings.enums.push(EnumBinding {
+            tag: tag.clone(),
+            repr,
+            variants: def
+              .variants
+              .iter()
+              .map(|(name, value)| (name.clone(), value.value))
+              .collect(),
+          });
+        }
+      }
+      Item::Typedef { name } => {
+        if !mapper.emits_typedef(name) || !bindings.symbols.insert(name.clone()) {
+          continue;
+        }
+        let def = &env.typedefs[name];
+        let mapped = match &def.ty.ty {
+          CType::Function(function) => mapper.map_function(function),
+          ty => mapper.map(ty),
+        };
+        match mapped {
+          // `typedef uint32_t u32;` のように Reml 側で同名になる別名は自己参照になるので出さない
+          Ok(mapped) if mapped.reml == *name => {}
+          Ok(mapped) => bindings.aliases.push((name.clone(), mapped.reml)),
+          Err(reason) => diagnostics.push(diagnostic(
+            UNKNOWN_TYPE_CODE,
+            Some(name.clone()),
+            Some(def.ty.c_text.clone()),
+            reason.to_string(),
+            "phase2",
+          )),
+        }
+      }
+      Item::Function {
+        name,
+        function,
+        file,
+      } => {
+        if scope.emits(name, *file) && !bindings.symbols.contains(name) {
+          collect_function(&mut mapper, name, function, &mut bindings, diagnostics);
+        }
+      }
+    }
   }
-  if trimmed.contains("(*") {
-    diagnostics.push(DiagnosticEntry {
-      code: "ffi.bindgen.unknown_type".to_string(),
-      symbol: Some(symbol.to_string()),
-      c_type: Some(trimmed.to_string()),
-      reason: Some("unsupported_fn_ptr".to_string()),
-      hint: Some("phase2".to_string()),
-    });
-    return None;
+
+  // 値渡しで参照された一次ファイル外の構造体も定義を出す
+  let mut index = 0;
+  while index < mapper.dependencies.len() {
+    let tag = mapper.dependencies[index].clone();
+    index += 1;
+    if records_seen.insert(tag.clone()) {
+      collect_record(&mut mapper, env, &tag, &mut bindings, diagnostics);
+    }
   }
 
-  let spaced = trimmed.replace('*', " * ");
-  let tokens: Vec<&str> = spaced.split_whitespace().collect();
-  if tokens.is_empty() {
-    diagnostics.push(DiagnosticEntry {
-      code: "ffi.bindgen.unknown_type".to_string(),
-      symbol: Some(symbol.to_string()),
-      c_type: Some(trimmed.to_string()),
-      reason: Some("empty".to_string()),
-      hint: Some("phase2".to_string()),
-    });
-    return None;
+  for def in &preprocessed.macros {
+    if !def.file.is_some_and(|file| scope.emits(&def.name, file)) || def.body.is_empty() {
+      continue;
+    }
+    let c_type = Some(render_tokens(&def.body));
+    if def.params.is_some() {
+      diagnostics.push(diagnostic(
+        SKIPPED_CODE,
+        Some(def.name.clone()),
+        c_type,
+        "function_like_macro".to_string(),
+        "manual_wrapper",
+      ));
+      continue;
+    }
+    match macro_constant(&preprocessed.expand(&def.body), env) {
+      Some((reml, manifest, value)) => {
+        bindings.push_constant(&def.name, reml, manifest, value, diagnostics)
+      }
+      None => diagnostics.push(diagnostic(
+        SKIPPED_CODE,
+        Some(def.name.clone()),
+        c_type,
+        "unsupported_macro".to_string(),
+        "manual_wrapper",
+      )),
+    }
   }
 
-  let mut qualifiers = Vec::new();
-  let mut base_tokens = Vec::new();
-  let mut pointer_count = 0;
-  for token in tokens {
-    match token {
-      "const" | "volatile" | "restrict" => qualifiers.push(token.to_string()),
-      "*" => pointer_count += 1,
-      _ => base_tokens.push(token),
+  bindings
+}
+
+fn collect_record(
+  mapper: &mut TypeMapper,
+  env: &TypeEnv,
+  tag: &str,
+  bindings: &mut Bindings,
+  diagnostics: &mut Vec<DiagnosticEntry>,
+) {
+  let def = &env.records[tag];
+  let layout = match env.record_layout(tag) {
+    Ok(layout) => layout,
+    Err(reason) => {
+      diagnostics.push(diagnostic(
+        UNKNOWN_TYPE_CODE,
+        Some(tag.to_string()),
+        Some(format!("{} {}", def.kind.keyword(), tag)),
+        reason.to_string(),
+        "phase2",
+      ));
+      return;
+    }
+  };
+
+  let mut fields = Vec::new();
+  let mut manifest_fields = Vec::new();
+  for (field, field_layout) in def.fields.iter().flatten().zip(&layout.fields) {
+    let (element, count) = match &field.ty.ty {
+      CType::Array {
+        element,
+        len: Some(len),
+      } if *len <= MAX_EXPANDED_ARRAY => (element.as_ref(), Some(*len)),
+      ty => (ty, None),
+    };
+    let mapped = match mapper.map(element) {
+      Ok(mapped) => mapped,
+      Err(reason) => {
+        diagnostics.push(diagnostic(
+          UNKNOWN_TYPE_CODE,
+          Some(format!("{}.{}", tag, field.name)),
+          Some(field.ty.c_text.clone()),
+          reason.to_string(),
+          "phase2",
+        ));
+        return;
+      }
+    };
+    match count {
+      Some(len) => {
+        let element_size = field_layout.size.checked_div(len).unwrap_or(0);
+        for index in 0..len {
+          let name = format!("{}_{}", field.name, index);
+          manifest_fields.push(ManifestField {
+            name: name.clone(),
+            offset: field_layout.offset + index * element_size,
+            size: element_size,
+            reml: mapped.manifest.clone(),
+          });
+          fields.push((name, mapped.clone()));
+        }
+      }
+      None => {
+        manifest_fields.push(ManifestField {
+          name: field.name.clone(),
+          offset: field_layout.offset,
+          size: field_layout.size,
+          reml: mapped.manifest.clone(),
+        });
+        fields.push((field.name.clone(), mapped));
+      }
     }
   }
 
-  if base_tokens.is_empty() {
-    diagnostics.push(DiagnosticEntry {
-      code: "ffi.bindgen.unknown_type".to_string(),
-      symbol: Some(symbol.to_string()),
-      c_type: Some(trimmed.to_string()),
-      reason: Some("unsupported_type".to_string()),
-      hint: Some("phase2".to_string()),
-    });
-    return None;
-  }
-
-  let base = base_tokens.join(" ");
-  let mapped = match base.as_str() {
-    "bool" => Some(("Bool", "bool")),
-    "char" => Some(("I8", "i8")),
-    "signed char" => Some(("I8", "i8")),
-    "unsigned char" => Some(("U8", "u8")),
-    "short" => Some(("I16", "i16")),
-    "unsigned short" => Some(("U16", "u16")),
-    "int" => Some(("I32", "i32")),
-    "unsigned int" => Some(("U32", "u32")),
-    "long" => Some(("I64", "i64")),
-    "unsigned long" => Some(("U64", "u64")),
-    "long long" => Some(("I64", "i64")),
-    "unsigned long long" => Some(("U64", "u64")),
-    "size_t" => Some(("USize", "usize")),
-    "intptr_t" => Some(("ISize", "isize")),
-    "uintptr_t" => Some(("USize", "usize")),
-    "float" => Some(("F32", "f32")),
-    "double" => Some(("F64", "f64")),
-    "void" => Some(("Unit", "()")),
-    _ => None,
+  bindings.symbols.insert(tag.to_string());
+  bindings.layouts.push(ManifestLayout {
+    name: tag.to_string(),
+    size: layout.layout.size,
+    align: layout.layout.align,
+    fields: manifest_fields,
+  });
+  bindings.records.push(RecordBinding {
+    tag: tag.to_string(),
+    packed: def.packed,
+    size: layout.layout.size,
+    align: layout.layout.align,
+    fields,
+  });
+}
+
+fn collect_function(
+  mapper: &mut TypeMapper,
+  name: &str,
+  function: &FnType,
+  bindings: &mut Bindings,
+  diagnostics: &mut Vec<DiagnosticEntry>,
+) {
+  let unknown = |type_ref: &TypeRef, reason: &str| {
+    diagnostic(
+      UNKNOWN_TYPE_CODE,
+      Some(name.to_string()),
+      Some(type_ref.c_text.clone()),
+      reason.to_string(),
+      "phase2",
+    )
   };
+  if function.variadic {
+    let mut params: Vec<&str> = function.params.iter().map(|param| param.ty.c_text.as_str()).collect();
+    params.push("...");
+    diagnostics.push(diagnostic(
+      UNKNOWN_TYPE_CODE,
+      Some(name.to_string()),
+      Some(params.join(", ")),
+      "unsupported_variadic".to_string(),
+      "phase2",
+    ));
+    return;
+  }
 
-  let (manifest_base, reml_base) = match mapped {
-    Some(value) => value,
-    None => {
-      diagnostics.push(DiagnosticEntry {
-        code: "ffi.bindgen.unknown_type".to_string(),
-        symbol: Some(symbol.to_string()),
-        c_type: Some(trimmed.to_string()),
-        reason: Some("unsupported_type".to_string()),
-        hint: Some("phase2".to_string()),
-      });
-      return None;
+  let returns = match mapper.map(&function.returns.ty) {
+    Ok(mapped) => mapped,
+    Err(reason) => {
+      diagnostics.push(unknown(&function.returns, reason));
+      return;
     }
   };
+  let mut params = Vec::new();
+  for param in &function.params {
+    match mapper.map(&param.ty.ty) {
+      Ok(mapped) => params.push((param, mapped)),
+      Err(reason) => {
+        diagnostics.push(unknown(&param.ty, reason));
+        return;
+      }
+    }
+  }
 
-  let mut manifest_type = manifest_base.to_string();
-  let mut reml_type = reml_base.to_string();
-  for _ in 0..pointer_count {
-    manifest_type = format!("Ptr<{}>", manifest_type);
-    reml_type = format!("Ptr<{}>", reml_type);
+  bindings.symbols.insert(name.to_string());
+  bindings.push_type(&function.returns, &returns);
+  for (param, mapped) in &params {
+    bindings.push_type(&param.ty, mapped);
   }
+  bindings.functions.push(FunctionBinding {
+    name: name.to_string(),
+    params: params
+      .into_iter()
+      .map(|(param, mapped)| (param.name.clone(), mapped.reml))
+      .collect(),
+    returns: returns.reml,
+  });
+}
 
-  Some(ParsedType {
-    c_type: trimmed.to_string(),
-    reml: reml_type,
-    manifest: manifest_type,
-    qualifiers,
-  })
+/// オブジェクト形式マクロを定数として解釈する。整数式・浮動小数点リテラル・文字列リテラルに対応する。
+fn macro_constant(tokens: &[Token], env: &TypeEnv) -> Option<(&'static str, &'static str, String)> {
+  let mut tokens = tokens;
+  while tokens.len() >= 2 && tokens[0].is("(") && tokens[tokens.len() - 1].is(")") {
+    tokens = &tokens[1..tokens.len() - 1];
+  }
+  let (sign, last) = match tokens {
+    [token] => ("", token),
+    [sign, token] if sign.is("-") => ("-", token),
+    _ => return expr_constant(tokens, env),
+  };
+  match last.kind {
+    TokenKind::Str if sign.is_empty() && last.text.starts_with('"') => {
+      Some(("Str", "Str", last.text.clone()))
+    }
+    TokenKind::Number if is_float_literal(&last.text) => {
+      let text = last.text.as_str();
+      if let Some(digits) = text.strip_suffix(['f', 'F']) {
+        let value: f32 = format!("{}{}", sign, digits).parse().ok()?;
+        Some(("f32", "F32", format!("{:?}", value)))
+      } else {
+        let value: f64 = format!("{}{}", sign, text).parse().ok()?;
+        Some(("f64", "F64", format!("{:?}", value)))
+      }
+    }
+    _ => expr_constant(tokens, env),
+  }
 }
 
-fn render_reml(module_name: &str, functions: &[ParsedFunction]) -> String {
+fn expr_constant(tokens: &[Token], env: &TypeEnv) -> Option<(&'static str, &'static str, String)> {
+  let value = expr::evaluate(tokens, env).ok()?;
+  let repr = IntRepr::of(value);
+  Some((repr.reml(), repr.manifest(), value.value.to_string()))
+}
+
+fn is_float_literal(text: &str) -> bool {
+  let lower = text.to_ascii_lowercase();
+  !lower.starts_with("0x") && (lower.contains('.') || lower.contains('e'))
+}
+
+fn render_reml(module_name: &str, bindings: &Bindings) -> String {
   let mut output = String::new();
   output.push_str("module ");
   output.push_str(module_name);
   output.push_str("\n\n");
   output.push_str("// generated by reml-bindgen\n");
+
+  if !bindings.records.is_empty() || !bindings.enums.is_empty() {
+    output.push_str("use Core.Ffi.Dsl as ffi\n\n");
+  }
+
+  for record in &bindings.records {
+    let repr = if record.packed { "packed" } else { "C" };
+    output.push_str(&format!("@repr({})\n", repr));
+    output.push_str(&format!("@layout(size = {}, align = {})\n", record.size, record.align));
+    let fields: Vec<String> = record
+      .fields
+      .iter()
+      .map(|(name, ty)| format!("{}: {}", name, ty.reml))
+      .collect();
+    if fields.is_empty() {
+      output.push_str(&format!("type {} = {{}}\n\n", record.tag));
+    } else {
+      output.push_str(&format!("type {} = {{ {} }}\n\n", record.tag, fields.join(", ")));
+    }
+  }
+
+  if !bindings.aliases.is_empty() || !bindings.enums.is_empty() {
+    for (name, target) in &bindings.aliases {
+      output.push_str(&format!("type alias {} = {}\n", name, target));
+    }
+    for binding in &bindings.enums {
+      output.push_str(&format!("type alias {} = {}\n", binding.tag, binding.repr.reml()));
+    }
+    output.push('\n');
+  }
+
+  if !bindings.records.is_empty() || !bindings.enums.is_empty() {
+    for record in &bindings.records {
+      let fields: Vec<String> = record
+        .fields
+        .iter()
+        .map(|(name, ty)| format!("{{ name: \"{}\", ty: {} }}", name, ty.dsl))
+        .collect();
+      output.push_str(&format!(
+        "pub let {} = ffi.Struct({{ name: \"{}\", fields: [{}], repr: ffi.{} }})\n",
+        ffi_type_binding(&record.tag),
+        record.tag,
+        fields.join(", "),
+        if record.packed { "Packed" } else { "C" }
+      ));
+    }
+    for binding in &bindings.enums {
+      let variants: Vec<String> = binding
+        .variants
+        .iter()
+        .map(|(name, value)| format!("{{ name: \"{}\", value: Some({}) }}", name, value))
+        .collect();
+      output.push_str(&format!(
+        "pub let {} = ffi.Enum({{ name: \"{}\", repr: ffi.{}, variants: [{}] }})\n",
+        ffi_type_binding(&binding.tag),
+        binding.tag,
+        binding.repr.manifest(),
+        variants.join(", ")
+      ));
+    }
+    output.push('\n');
+  }
+
+  if !bindings.constants.is_empty() {
+    for constant in &bindings.constants {
+      output.push_str(&format!(
+        "pub const {}: {} = {}\n",
+        constant.name, constant.reml, constant.value
+      ));
+    }
+    output.push('\n');
+  }
+
   output.push_str("extern \"C\" {\n");
-  for func in functions {
+  for func in &bindings.functions {
     output.push_str("  fn ");
     output.push_str(&func.name);
     output.push('(');
-    for (index, param) in func.params.iter().enumerate() {
+    for (index, (name, ty)) in func.params.iter().enumerate() {
       if index > 0 {
         output.push_str(", ");
       }
-      if let Some(name) = &param.name {
+      if let Some(name) = name {
         output.push_str(name);
         output.push_str(": ");
       }
-      output.push_str(&param.ty.reml);
+      output.push_str(ty);
     }
     output.push_str(") -> ");
-    output.push_str(&func.return_type.reml);
+    output.push_str(&func.returns);
     output.push_str(";\n");
   }
   output.push_str("}\n");
@@ -628,7 +1013,7 @@ fn find_repo_root(start: &Path) -> Option<PathBuf> {
   None
 }
 
-fn write_file(
+pub(crate) fn write_file(
   path: &Path,
   content: &str,
   diagnostics: &mut Vec<DiagnosticEntry>,
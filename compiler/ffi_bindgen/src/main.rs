@@ -1,4 +1,4 @@
-use reml_ffi_bindgen::{run_bindgen, BindgenError, CliOptions, DiagnosticEntry};
+use reml_ffi_bindgen::{run_bindgen, run_export_header, BindgenError, CliOptions, DiagnosticEntry};
 use serde_json::json;
 use std::env;
 use std::path::PathBuf;
@@ -27,6 +27,11 @@ fn main() {
     }
   };
 
+  if cli.export_header {
+    run_export(&cli);
+    return;
+  }
+
   let config_path = cli
     .config_path
     .clone()
@@ -70,6 +75,40 @@ fn main() {
   }
 }
 
+fn run_export(cli: &CliOptions) {
+  emit_log(json!({
+    "event": "bindgen.start",
+    "direction": "export",
+    "mir": cli.mir,
+  }));
+
+  match run_export_header(cli) {
+    Ok(result) => {
+      emit_log(json!({
+        "event": "bindgen.generate",
+        "status": "success",
+        "output": result.output_path.to_string_lossy(),
+        "manifest": result.manifest_path.to_string_lossy(),
+      }));
+      emit_log(json!({
+        "event": "bindgen.finish",
+        "status": "success",
+        "generated": result.output_path.to_string_lossy(),
+        "manifest": result.manifest_path.to_string_lossy(),
+        "diagnostics": result.diagnostics,
+      }));
+    }
+    Err(err) => {
+      emit_log(json!({
+        "event": "bindgen.finish",
+        "status": "failed",
+        "diagnostics": vec![error_to_diagnostic(&err)],
+      }));
+      std::process::exit(1);
+    }
+  }
+}
+
 fn parse_args(args: &[String]) -> Result<CliOptions, String> {
   let mut cli = CliOptions::default();
   let mut iter = args.iter().peekable();
@@ -101,6 +140,18 @@ fn parse_args(args: &[String]) -> Result<CliOptions, String> {
       "--exclude" => {
         cli.exclude.push(next_value(&mut iter, "--exclude")?);
       }
+      "--export-header" => {
+        cli.export_header = true;
+      }
+      "--mir" => {
+        cli.mir = Some(next_value(&mut iter, "--mir")?);
+      }
+      "--guard" => {
+        cli.guard = Some(next_value(&mut iter, "--guard")?);
+      }
+      "--module" => {
+        cli.module = Some(next_value(&mut iter, "--module")?);
+      }
       "--help" | "-h" => {
         return Err("help".to_string());
       }
@@ -119,9 +170,9 @@ fn parse_args(args: &[String]) -> Result<CliOptions, String> {
   Ok(cli)
 }
 
-fn next_value<I>(iter: &mut I, flag: &str) -> Result<String, String>
+fn next_value<'a, I>(iter: &mut I, flag: &str) -> Result<String, String>
 where
-  I: Iterator<Item = &'_ String>,
+  I: Iterator<Item = &'a String>,
 {
   iter
     .next()
@@ -155,6 +206,7 @@ fn print_usage() {
 
 USAGE:
   reml-bindgen [options]
+  reml-bindgen --export-header --mir <path> --output <path> [--manifest <path>]
 
 OPTIONS:
   --config <path>           設定ファイルを指定（既定: reml-bindgen.toml）
@@ -162,9 +214,15 @@ OPTIONS:
   --include-path <path>     include パスを追加（-I も可）
   --compile-commands <path> compile_commands.json を指定
   --define <name[=value]>   定義を追加（-D も可）
-  --output <path>           出力 .reml
+  --output <path>           出力 .reml（--export-header では .h）
   --manifest <path>         出力 bindings.manifest.json
   --exclude <pattern>       除外パターン（正規表現）
+
+  --export-header           MIR JSON から C ヘッダを生成（逆方向）
+  --mir <path>              入力 MIR JSON（--export-header 用）
+  --guard <name>            インクルードガード名（--export-header 用）
+  --module <name>           モジュール名（既定: MIR の module）
+
   --help, -h                ヘルプ表示
 "#;
   println!("{}", usage);
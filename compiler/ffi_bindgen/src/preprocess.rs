@@ -0,0 +1,772 @@
+//! C プリプロセッサ。
+//!
+//! `#include` / `#define` / `#undef` / 条件コンパイル / `#pragma once` を処理し、
+//! マクロ展開済みのトークン列を返す。`#include "..."` で辿った設定ヘッダ配下のファイルを
+//! 一次ファイルとして扱い、`<...>` で取り込んだファイルは型情報の参照にだけ使う。
+
+use crate::expr::{self, NoConstants};
+use crate::lexer::{logical_lines, render_tokens, tokenize, Token, TokenKind};
+use crate::{diagnostic, DiagnosticEntry};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MAX_INCLUDE_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Default)]
+pub struct PreprocessOptions {
+  pub include_paths: Vec<PathBuf>,
+  /// `NAME` または `NAME=VALUE`。
+  pub defines: Vec<String>,
+  pub undefines: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+  pub path: PathBuf,
+  pub primary: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct MacroDef {
+  pub name: String,
+  /// 関数形式マクロの仮引数。オブジェクト形式なら `None`。
+  pub params: Option<Vec<String>>,
+  pub variadic: bool,
+  pub body: Vec<Token>,
+  /// 定義元ファイル。事前定義やコマンドラインの定義は `None`。
+  pub file: Option<usize>,
+}
+
+/// 前処理の結果。
+#[derive(Debug)]
+pub struct Preprocessed {
+  pub tokens: Vec<Token>,
+  pub files: Vec<SourceFile>,
+  /// 一次ファイルで定義され、最後まで有効だったマクロ（定義順）。
+  pub macros: Vec<MacroDef>,
+  pub diagnostics: Vec<DiagnosticEntry>,
+  expander: Expander,
+}
+
+impl Preprocessed {
+  pub fn is_primary(&self, file: usize) -> bool {
+    self.files.get(file).is_some_and(|source| source.primary)
+  }
+
+  /// マクロ本体などのトークン列を、最終状態のマクロ表で展開する。
+  pub fn expand(&self, tokens: &[Token]) -> Vec<Token> {
+    self.expander.expand(tokens.to_vec(), &mut Vec::new())
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Conditional {
+  active: bool,
+  taken: bool,
+  parent_active: bool,
+}
+
+pub struct Preprocessor {
+  options: PreprocessOptions,
+  files: Vec<SourceFile>,
+  expander: Expander,
+  order: Vec<String>,
+  once: HashSet<PathBuf>,
+  tokens: Vec<Token>,
+  diagnostics: Vec<DiagnosticEntry>,
+  depth: usize,
+}
+
+impl Preprocessor {
+  pub fn new(options: PreprocessOptions) -> Self {
+    let mut preprocessor = Self {
+      options,
+      files: Vec::new(),
+      expander: Expander::default(),
+      order: Vec::new(),
+      once: HashSet::new(),
+      tokens: Vec::new(),
+      diagnostics: Vec::new(),
+      depth: 0,
+    };
+    for (name, value) in predefined_macros() {
+      preprocessor.define_from_option(&format!("{}={}", name, value));
+    }
+    for define in preprocessor.options.defines.clone() {
+      preprocessor.define_from_option(&define);
+    }
+    for name in preprocessor.options.undefines.clone() {
+      preprocessor.expander.macros.remove(&name);
+    }
+    preprocessor
+  }
+
+  /// 設定に列挙されたヘッダ（一次ファイル）を処理する。
+  pub fn process_header(&mut self, path: &Path) -> Result<(), std::io::Error> {
+    let content = fs::read_to_string(path)?;
+    self.process_source(path, &content, true);
+    Ok(())
+  }
+
+  pub fn finish(self) -> Preprocessed {
+    let mut seen = HashSet::new();
+    let macros = self
+      .order
+      .iter()
+      .filter(|name| seen.insert(name.as_str()))
+      .filter_map(|name| self.expander.macros.get(name))
+      .filter(|def| def.file.is_some_and(|file| self.files[file].primary))
+      .cloned()
+      .collect();
+    Preprocessed {
+      tokens: self.tokens,
+      files: self.files,
+      macros,
+      diagnostics: self.diagnostics,
+      expander: self.expander,
+    }
+  }
+
+  fn define_from_option(&mut self, define: &str) {
+    let (name, value) = define.split_once('=').unwrap_or((define, "1"));
+    let name = name.trim();
+    if name.is_empty() {
+      return;
+    }
+    self.expander.macros.insert(
+      name.to_string(),
+      MacroDef {
+        name: name.to_string(),
+        params: None,
+        variadic: false,
+        body: tokenize(value, usize::MAX, 0),
+        file: None,
+      },
+    );
+  }
+
+  fn process_source(&mut self, path: &Path, content: &str, primary: bool) {
+    let file = self.files.len();
+    self.files.push(SourceFile {
+      path: path.to_path_buf(),
+      primary,
+    });
+    let mut conditions: Vec<Conditional> = Vec::new();
+    let mut pending: Vec<Token> = Vec::new();
+
+    for line in logical_lines(content) {
+      let active = conditions.last().is_none_or(|cond| cond.active);
+      let trimmed = line.text.trim_start();
+      let Some(directive) = trimmed.strip_prefix('#') else {
+        if active {
+          pending.extend(tokenize(&line.text, file, line.line));
+        }
+        continue;
+      };
+      self.flush(&mut pending);
+
+      let directive = directive.trim_start();
+      let name_len = directive
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+        .unwrap_or(directive.len());
+      let (name, rest) = directive.split_at(name_len);
+      let rest_tokens = tokenize(rest, file, line.line);
+      match name {
+        "if" | "ifdef" | "ifndef" => {
+          let value = active
+            && match name {
+              "ifdef" => self.is_defined_directive(&rest_tokens),
+              "ifndef" => !self.is_defined_directive(&rest_tokens),
+              _ => self.evaluate_condition(&rest_tokens, file),
+            };
+          conditions.push(Conditional {
+            active: value,
+            taken: value || !active,
+            parent_active: active,
+          });
+        }
+        "elif" | "elifdef" | "elifndef" | "else" | "endif" => {
+          let Some(cond) = conditions.last_mut() else {
+            self.report(file, "unbalanced_conditional", name);
+            continue;
+          };
+          match name {
+            "endif" => {
+              conditions.pop();
+            }
+            "else" => {
+              cond.active = cond.parent_active && !cond.taken;
+              cond.taken = true;
+            }
+            _ => {
+              let (parent_active, taken) = (cond.parent_active, cond.taken);
+              let value = parent_active
+                && !taken
+                && match name {
+                  "elifdef" => self.is_defined_directive(&rest_tokens),
+                  "elifndef" => !self.is_defined_directive(&rest_tokens),
+                  _ => self.evaluate_condition(&rest_tokens, file),
+                };
+              let cond = conditions.last_mut().expect("直前に確認済み");
+              cond.active = value;
+              cond.taken = taken || value;
+            }
+          }
+        }
+        _ if !active => {}
+        "define" => self.define(rest, file, line.line),
+        "undef" => {
+          if let Some(token) = rest_tokens.first() {
+            self.expander.macros.remove(&token.text);
+          }
+        }
+        "include" | "include_next" | "import" => {
+          self.include(rest, &rest_tokens, file, name == "include_next")
+        }
+        "pragma" => {
+          if rest_tokens
+            .first()
+            .is_some_and(|token| token.text == "once")
+          {
+            self.once.insert(canonical(path));
+          }
+        }
+        "error" => self.report(file, "error_directive", rest.trim()),
+        "" | "line" | "ident" | "sccs" | "warning" | "assert" | "unassert" => {}
+        _ => self.report(file, "unknown_directive", name),
+      }
+    }
+    self.flush(&mut pending);
+    if !conditions.is_empty() {
+      self.report(file, "unterminated_conditional", "#endif がありません");
+    }
+  }
+
+  fn flush(&mut self, pending: &mut Vec<Token>) {
+    if pending.is_empty() {
+      return;
+    }
+    let expanded = self
+      .expander
+      .expand(std::mem::take(pending), &mut Vec::new());
+    self.tokens.extend(expanded);
+  }
+
+  fn report(&mut self, file: usize, reason: &str, detail: &str) {
+    if !self.files[file].primary {
+      return;
+    }
+    self.diagnostics.push(diagnostic(
+      "ffi.bindgen.parse_failed",
+      None,
+      None,
+      format!("{}: {}", reason, detail),
+      "preprocessor",
+    ));
+  }
+
+  fn is_defined_directive(&self, tokens: &[Token]) -> bool {
+    tokens
+      .first()
+      .is_some_and(|token| self.expander.macros.contains_key(&token.text))
+  }
+
+  fn evaluate_condition(&mut self, tokens: &[Token], file: usize) -> bool {
+    // `defined` と `__has_include` はマクロ展開より前に解決する。
+    let mut resolved = Vec::new();
+    let mut index = 0;
+    while index < tokens.len() {
+      let token = &tokens[index];
+      if token.is_ident() && (token.text == "defined" || token.text.starts_with("__has_include")) {
+        let (value, consumed) = if token.text == "defined" {
+          let (name, consumed) = match tokens.get(index + 1) {
+            Some(open) if open.is("(") => (tokens.get(index + 2), 4),
+            other => (other, 2),
+          };
+          let value = name.is_some_and(|name| self.expander.macros.contains_key(&name.text));
+          (value, consumed)
+        } else {
+          let end = find_closing(tokens, index + 1).unwrap_or(tokens.len() - 1);
+          let inner = &tokens[(index + 2).min(end)..end];
+          (self.has_include(inner, file), end + 1 - index)
+        };
+        resolved.push(Token::new(
+          TokenKind::Number,
+          if value { "1" } else { "0" },
+          token.file,
+          token.line,
+        ));
+        index += consumed;
+        continue;
+      }
+      resolved.push(token.clone());
+      index += 1;
+    }
+
+    let expanded = self.expander.expand(resolved, &mut Vec::new());
+    // 残った識別子は 0。`__has_attribute(x)` などの組み込み判定も 0 とする。
+    let mut normalized = Vec::new();
+    let mut index = 0;
+    while index < expanded.len() {
+      let token = &expanded[index];
+      if token.is_ident() {
+        let value = if token.text == "true" { "1" } else { "0" };
+        if token.text.starts_with("__has_")
+          && expanded.get(index + 1).is_some_and(|next| next.is("("))
+        {
+          index = find_closing(&expanded, index + 1).map_or(expanded.len(), |end| end + 1);
+        } else {
+          index += 1;
+        }
+        normalized.push(Token::new(TokenKind::Number, value, token.file, token.line));
+        continue;
+      }
+      normalized.push(token.clone());
+      index += 1;
+    }
+    match expr::evaluate(&normalized, &NoConstants) {
+      Ok(value) => value.is_true(),
+      Err(message) => {
+        self.report(
+          file,
+          "invalid_condition",
+          &format!("{} ({})", render_tokens(tokens), message),
+        );
+        false
+      }
+    }
+  }
+
+  fn has_include(&self, inner: &[Token], file: usize) -> bool {
+    let Some((name, quoted)) = include_target(inner) else {
+      return false;
+    };
+    self.resolve_include(&name, quoted, file, false).is_some()
+  }
+
+  fn define(&mut self, rest: &str, file: usize, line: usize) {
+    let rest = rest.trim_start();
+    let name_len = rest
+      .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '$'))
+      .unwrap_or(rest.len());
+    let (name, after) = rest.split_at(name_len);
+    if name.is_empty() {
+      self.report(file, "invalid_define", rest);
+      return;
+    }
+    let (params, variadic, body) = match after.strip_prefix('(') {
+      Some(params_and_body) => {
+        let Some(close) = params_and_body.find(')') else {
+          self.report(file, "invalid_define", name);
+          return;
+        };
+        let mut variadic = false;
+        let mut params = Vec::new();
+        for param in params_and_body[..close]
+          .split(',')
+          .map(str::trim)
+          .filter(|param| !param.is_empty())
+        {
+          if param == "..." {
+            variadic = true;
+            params.push("__VA_ARGS__".to_string());
+          } else if let Some(named) = param.strip_suffix("...") {
+            variadic = true;
+            params.push(named.trim().to_string());
+          } else {
+            params.push(param.to_string());
+          }
+        }
+        (Some(params), variadic, &params_and_body[close + 1..])
+      }
+      None => (None, false, after),
+    };
+    self.order.push(name.to_string());
+    self.expander.macros.insert(
+      name.to_string(),
+      MacroDef {
+        name: name.to_string(),
+        params,
+        variadic,
+        body: tokenize(body, file, line),
+        file: Some(file),
+      },
+    );
+  }
+
+  fn include(&mut self, rest: &str, tokens: &[Token], file: usize, next: bool) {
+    let target = include_target(tokens).or_else(|| {
+      // `#include MACRO` 形式
+      let expanded = self.expander.expand(tokens.to_vec(), &mut Vec::new());
+      include_target(&expanded)
+    });
+    let Some((name, quoted)) = target else {
+      self.report(file, "invalid_include", rest.trim());
+      return;
+    };
+    let Some(path) = self.resolve_include(&name, quoted, file, next) else {
+      if quoted && self.files[file].primary {
+        self.diagnostics.push(diagnostic(
+          "ffi.bindgen.parse_failed",
+          Some(name),
+          None,
+          "include_not_found".to_string(),
+          "include_paths",
+        ));
+      }
+      return;
+    };
+    if self.once.contains(&canonical(&path)) {
+      return;
+    }
+    if self.depth >= MAX_INCLUDE_DEPTH {
+      self.report(file, "include_depth_exceeded", &name);
+      return;
+    }
+    let Ok(content) = fs::read_to_string(&path) else {
+      self.report(file, "include_unreadable", &name);
+      return;
+    };
+    let primary = quoted && self.files[file].primary;
+    self.depth += 1;
+    self.process_source(&path, &content, primary);
+    self.depth -= 1;
+  }
+
+  fn resolve_include(&self, name: &str, quoted: bool, file: usize, next: bool) -> Option<PathBuf> {
+    let current = &self.files[file].path;
+    let mut candidates = Vec::new();
+    if quoted && !next {
+      if let Some(dir) = current.parent() {
+        candidates.push(dir.join(name));
+      }
+    }
+    candidates.extend(self.options.include_paths.iter().map(|dir| dir.join(name)));
+    candidates
+      .into_iter()
+      .filter(|candidate| !next || canonical(candidate) != canonical(current))
+      .find(|candidate| candidate.is_file())
+  }
+}
+
+/// `"name"` または `<name>` を取り出す。
+fn include_target(tokens: &[Token]) -> Option<(String, bool)> {
+  let first = tokens.first()?;
+  if first.kind == TokenKind::Str {
+    return Some((first.text.trim_matches('"').to_string(), true));
+  }
+  if first.is("<") {
+    let close = tokens.iter().position(|token| token.is(">"))?;
+    let name: String = tokens[1..close]
+      .iter()
+      .map(|token| token.text.as_str())
+      .collect();
+    return Some((name, false));
+  }
+  None
+}
+
+/// `open` 位置の `(` に対応する `)` の位置。
+fn find_closing(tokens: &[Token], open: usize) -> Option<usize> {
+  let mut depth = 0usize;
+  for (index, token) in tokens.iter().enumerate().skip(open) {
+    if token.is("(") {
+      depth += 1;
+    } else if token.is(")") {
+      depth -= 1;
+      if depth == 0 {
+        return Some(index);
+      }
+    }
+  }
+  None
+}
+
+fn canonical(path: &Path) -> PathBuf {
+  fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// LP64 の C11 処理系として振る舞うための事前定義マクロ。
+fn predefined_macros() -> Vec<(&'static str, &'static str)> {
+  let mut macros = vec![
+    ("__STDC__", "1"),
+    ("__STDC_VERSION__", "201112L"),
+    ("__STDC_HOSTED__", "1"),
+    ("__CHAR_BIT__", "8"),
+    ("__SIZEOF_INT__", "4"),
+    ("__SIZEOF_LONG__", "8"),
+    ("__SIZEOF_POINTER__", "8"),
+    ("__LP64__", "1"),
+    ("_LP64", "1"),
+  ];
+  if cfg!(target_arch = "x86_64") {
+    macros.extend([("__x86_64__", "1"), ("__x86_64", "1")]);
+  } else if cfg!(target_arch = "aarch64") {
+    macros.push(("__aarch64__", "1"));
+  }
+  if cfg!(target_os = "linux") {
+    macros.extend([("__linux__", "1"), ("__unix__", "1")]);
+  } else if cfg!(target_os = "macos") {
+    macros.extend([("__APPLE__", "1"), ("__MACH__", "1")]);
+  }
+  macros
+}
+
+/// マクロ表と展開処理。
+#[derive(Debug, Default)]
+struct Expander {
+  macros: HashMap<String, MacroDef>,
+}
+
+impl Expander {
+  /// `active` は展開中のマクロ名（自己参照の再展開を防ぐ）。
+  fn expand(&self, input: Vec<Token>, active: &mut Vec<String>) -> Vec<Token> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut index = 0;
+    while index < input.len() {
+      let token = &input[index];
+      let def = token
+        .is_ident()
+        .then(|| self.macros.get(&token.text))
+        .flatten()
+        .filter(|def| !active.contains(&def.name));
+      let Some(def) = def else {
+        output.push(token.clone());
+        index += 1;
+        continue;
+      };
+      let replaced = match &def.params {
+        None => {
+          index += 1;
+          retag(&def.body, token)
+        }
+        Some(params) => {
+          let Some((args, end)) = input
+            .get(index + 1)
+            .filter(|next| next.is("("))
+            .and_then(|_| collect_args(&input, index + 1))
+          else {
+            output.push(token.clone());
+            index += 1;
+            continue;
+          };
+          let body = self.substitute(def, params, args, token, active);
+          index = end + 1;
+          body
+        }
+      };
+      active.push(def.name.clone());
+      let expanded = self.expand(replaced, active);
+      active.pop();
+      output.extend(expanded);
+    }
+    output
+  }
+
+  fn substitute(
+    &self,
+    def: &MacroDef,
+    params: &[String],
+    mut args: Vec<Vec<Token>>,
+    site: &Token,
+    active: &mut Vec<String>,
+  ) -> Vec<Token> {
+    if params.is_empty() && args.len() == 1 && args[0].is_empty() {
+      args.clear();
+    }
+    if def.variadic && args.len() > params.len() {
+      let rest = args.split_off(params.len() - 1);
+      let mut joined = Vec::new();
+      for (position, arg) in rest.into_iter().enumerate() {
+        if position > 0 {
+          joined.push(Token::new(TokenKind::Punct, ",", site.file, site.line));
+        }
+        joined.extend(arg);
+      }
+      args.push(joined);
+    }
+    args.resize(params.len(), Vec::new());
+    let param_index = |token: &Token| {
+      token
+        .is_ident()
+        .then(|| params.iter().position(|param| *param == token.text))
+        .flatten()
+    };
+
+    let body = retag(&def.body, site);
+    let mut result: Vec<Token> = Vec::new();
+    let mut index = 0;
+    while index < body.len() {
+      let token = &body[index];
+      if token.is("#") {
+        if let Some(param) = body.get(index + 1).and_then(param_index) {
+          result.push(Token::new(
+            TokenKind::Str,
+            stringize(&args[param]),
+            site.file,
+            site.line,
+          ));
+          index += 2;
+          continue;
+        }
+      }
+      if token.is("##") {
+        let rhs = match body.get(index + 1) {
+          Some(next) => match param_index(next) {
+            Some(param) => args[param].clone(),
+            None => vec![next.clone()],
+          },
+          None => Vec::new(),
+        };
+        let rhs_is_va_args = body
+          .get(index + 1)
+          .is_some_and(|next| next.text == "__VA_ARGS__");
+        index += 2;
+        match (result.pop(), rhs.split_first()) {
+          (Some(lhs), Some((first, tail))) => {
+            result.extend(tokenize(
+              &format!("{}{}", lhs.text, first.text),
+              site.file,
+              site.line,
+            ));
+            result.extend(tail.iter().cloned());
+          }
+          // GNU 拡張: `, ## __VA_ARGS__` は可変長引数が空ならカンマを消す
+          (Some(lhs), None) if lhs.is(",") && rhs_is_va_args => {}
+          (Some(lhs), None) => result.push(lhs),
+          (None, _) => result.extend(rhs),
+        }
+        continue;
+      }
+      match param_index(token) {
+        Some(param) if body.get(index + 1).is_some_and(|next| next.is("##")) => {
+          result.extend(args[param].iter().cloned());
+        }
+        Some(param) => result.extend(self.expand(args[param].clone(), active)),
+        None => result.push(token.clone()),
+      }
+      index += 1;
+    }
+    result
+  }
+}
+
+/// `open` の `(` から実引数を集める。戻り値は引数列と `)` の位置。
+fn collect_args(tokens: &[Token], open: usize) -> Option<(Vec<Vec<Token>>, usize)> {
+  let mut args = vec![Vec::new()];
+  let mut depth = 0usize;
+  for (index, token) in tokens.iter().enumerate().skip(open + 1) {
+    if token.is("(") {
+      depth += 1;
+    } else if token.is(")") {
+      if depth == 0 {
+        return Some((args, index));
+      }
+      depth -= 1;
+    } else if token.is(",") && depth == 0 {
+      args.push(Vec::new());
+      continue;
+    }
+    args
+      .last_mut()
+      .expect("少なくとも 1 要素ある")
+      .push(token.clone());
+  }
+  None
+}
+
+/// 展開結果の由来を呼び出し位置に付け替える。
+fn retag(tokens: &[Token], site: &Token) -> Vec<Token> {
+  tokens
+    .iter()
+    .map(|token| Token::new(token.kind, token.text.clone(), site.file, site.line))
+    .collect()
+}
+
+fn stringize(tokens: &[Token]) -> String {
+  let text = render_tokens(tokens)
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"");
+  format!("\"{}\"", text)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn run(source: &str, defines: &[&str]) -> Preprocessed {
+    let mut preprocessor = Preprocessor::new(PreprocessOptions {
+      defines: defines.iter().map(|value| value.to_string()).collect(),
+      ..PreprocessOptions::default()
+    });
+    preprocessor.process_source(Path::new("test.h"), source, true);
+    preprocessor.finish()
+  }
+
+  fn text(unit: &Preprocessed) -> String {
+    render_tokens(&unit.tokens)
+  }
+
+  #[test]
+  fn evaluates_conditionals_and_function_like_macros() {
+    let source = r#"
+#define API(ret) extern ret
+#define CAT(a, b) a ## b
+#define STR(x) #x
+#if defined(FEATURE) && FEATURE >= 2
+API(int) CAT(feature_, two)(void);
+#elif defined FEATURE
+API(int) feature_one(void);
+#else
+int no_feature(void); /* コメント
+   複数行 */
+#endif
+const char *name = STR(a "b");
+"#;
+    assert_eq!(
+      text(&run(source, &["FEATURE=2"])),
+      "extern int feature_two(void); const char* name = \"a \\\"b\\\"\";"
+    );
+    assert_eq!(
+      text(&run(source, &["FEATURE"])),
+      "extern int feature_one(void); const char* name = \"a \\\"b\\\"\";"
+    );
+    let unit = run(source, &[]);
+    assert!(text(&unit).starts_with("int no_feature(void);"));
+    assert_eq!(
+      unit
+        .macros
+        .iter()
+        .map(|def| def.name.as_str())
+        .collect::<Vec<_>>(),
+      ["API", "CAT", "STR"]
+    );
+  }
+
+  #[test]
+  fn handles_variadic_and_self_referential_macros() {
+    let source = "#define LOG(fmt, ...) log_impl(fmt, ## __VA_ARGS__)\n#define foo foo + 1\nLOG(\"a\"); LOG(\"b\", 1, 2); foo;";
+    assert_eq!(
+      text(&run(source, &[])),
+      "log_impl(\"a\"); log_impl(\"b\", 1, 2); foo + 1;"
+    );
+  }
+
+  #[test]
+  fn reports_errors_and_missing_includes() {
+    let unit = run(
+      "#include \"missing.h\"\n#ifdef X\n#error never\n#endif\n#error stop here\n#if\n#endif",
+      &[],
+    );
+    let reasons: Vec<_> = unit
+      .diagnostics
+      .iter()
+      .map(|entry| entry.reason.clone().unwrap())
+      .collect();
+    assert_eq!(reasons[0], "include_not_found");
+    assert_eq!(reasons[1], "error_directive: stop here");
+    assert!(reasons[2].starts_with("invalid_condition"));
+    assert_eq!(reasons.len(), 3);
+  }
+}
@@ -0,0 +1,192 @@
+use reml_ffi_bindgen::{run_bindgen, CliOptions};
+use std::fs;
+use std::path::PathBuf;
+
+fn workspace(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("reml-bindgen-{}-{}", name, std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(dir.join("include")).unwrap();
+  dir
+}
+
+const SAMPLE_HEADER: &str = r#"#ifndef SAMPLE_H
+#define SAMPLE_H
+#include <stdint.h>
+#include "detail.h"
+
+#define SAMPLE_VERSION 3
+#define SAMPLE_MASK (1u << 4)
+#define SAMPLE_NAME "sample"
+#define SAMPLE_RATIO 0.5f
+#define SAMPLE_MAX(a, b) ((a) > (b) ? (a) : (b))
+#define SAMPLE_API
+
+/* 座標 */
+typedef struct point {
+  int32_t x;
+  int32_t y;
+} point_t;
+
+typedef enum { COLOR_RED, COLOR_GREEN = 4, COLOR_BLUE } color;
+
+struct __attribute__((packed)) header {
+  uint8_t tag;
+  uint64_t length;
+  char name[4];
+};
+
+typedef void (*sample_callback)(void *user, int status);
+typedef uint32_t u32;
+
+struct flags {
+  unsigned ready : 1;
+};
+
+#ifdef SAMPLE_FEATURE
+SAMPLE_API int sample_feature(void);
+#endif
+
+SAMPLE_API int sample_draw(point_t origin,
+                           color fill,
+                           const char *label);
+void sample_register(sample_callback cb, void *user);
+size_t sample_header_size(const struct header *h);
+u32 sample_flags(void);
+int sample_log(const char *fmt, ...);
+static inline int sample_twice(int x) { return x * 2; }
+detail_t sample_detail(void);
+#endif
+"#;
+
+#[test]
+fn generates_records_enums_constants_and_functions() {
+  let dir = workspace("sample");
+  fs::write(dir.join("include/sample.h"), SAMPLE_HEADER).unwrap();
+  fs::write(dir.join("include/detail.h"), "typedef long detail_t;\n").unwrap();
+  fs::write(
+    dir.join("reml-bindgen.toml"),
+    r#"headers = ["include/sample.h"]
+include_paths = ["include"]
+defines = ["SAMPLE_FEATURE=1"]
+output = "generated/sample.reml"
+manifest = "generated/bindings.manifest.json"
+exclude = []
+"#,
+  )
+  .unwrap();
+
+  let result = run_bindgen(&dir.join("reml-bindgen.toml"), &CliOptions::default()).unwrap();
+  let output = fs::read_to_string(&result.output_path).unwrap();
+
+  for expected in [
+    "use Core.Ffi.Dsl as ffi\n",
+    "@repr(C)\n@layout(size = 8, align = 4)\ntype point = { x: i32, y: i32 }\n",
+    "@repr(packed)\n@layout(size = 13, align = 1)\ntype header = { tag: u8, length: u64, name_0: i8, name_1: i8, name_2: i8, name_3: i8 }\n",
+    "type alias point_t = point\n",
+    "type alias detail_t = i64\n",
+    "type alias sample_callback = fn(Ptr<()>, i32) -> ()\n",
+    "type alias color = i32\n",
+    "pub let color_ffi_type = ffi.Enum({ name: \"color\", repr: ffi.I32, variants: [{ name: \"COLOR_RED\", value: Some(0) }, { name: \"COLOR_GREEN\", value: Some(4) }, { name: \"COLOR_BLUE\", value: Some(5) }] })\n",
+    "pub const COLOR_BLUE: color = 5\n",
+    "pub const SAMPLE_VERSION: i32 = 3\n",
+    "pub const SAMPLE_MASK: u32 = 16\n",
+    "pub const SAMPLE_NAME: Str = \"sample\"\n",
+    "pub const SAMPLE_RATIO: f32 = 0.5\n",
+    "  fn sample_feature() -> i32;\n",
+    "  fn sample_draw(origin: point_t, fill: color, label: Ptr<i8>) -> i32;\n",
+    "  fn sample_register(cb: sample_callback, user: Ptr<()>) -> ();\n",
+    "  fn sample_header_size(h: Ptr<header>) -> usize;\n",
+    "  fn sample_detail() -> detail_t;\n",
+    "  fn sample_flags() -> u32;\n",
+  ] {
+    assert!(output.contains(expected), "missing `{}` in\n{}", expected, output);
+  }
+  assert!(!output.contains("sample_log"));
+  assert!(!output.contains("sample_twice"));
+  assert!(!output.contains("SAMPLE_API"));
+  assert!(!output.contains("type alias u32"));
+
+  let header = result
+    .manifest
+    .layouts
+    .iter()
+    .find(|layout| layout.name == "header")
+    .unwrap();
+  let offsets: Vec<u64> = header.fields.iter().map(|field| field.offset).collect();
+  assert_eq!(offsets, vec![0, 1, 9, 10, 11, 12]);
+  assert!(result.manifest.types.iter().any(|ty| ty.c == "const char*"
+    && ty.reml == "Ptr<I8>"
+    && ty.qualifiers == Some(vec!["const".to_string()])));
+
+  let skipped: Vec<(String, String)> = result
+    .diagnostics
+    .iter()
+    .map(|entry| {
+      (
+        entry.symbol.clone().unwrap_or_default(),
+        entry.reason.clone().unwrap_or_default(),
+      )
+    })
+    .collect();
+  for expected in [
+    ("sample_log", "unsupported_variadic"),
+    ("sample_twice", "inline_definition"),
+    ("flags", "unsupported_bitfield"),
+    ("SAMPLE_MAX", "function_like_macro"),
+  ] {
+    assert!(
+      skipped
+        .iter()
+        .any(|(symbol, reason)| symbol == expected.0 && reason == expected.1),
+      "missing skip {:?} in {:?}",
+      expected,
+      skipped
+    );
+  }
+
+  let manifest_json = fs::read_to_string(&result.manifest_path).unwrap();
+  assert!(manifest_json.contains("\"layouts\""));
+  assert!(manifest_json.contains("\"reason\": \"unsupported_variadic\""));
+  let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn keeps_function_only_output_and_reads_compile_commands() {
+  let dir = workspace("commands");
+  fs::create_dir_all(dir.join("vendor")).unwrap();
+  fs::write(
+    dir.join("vendor/config.h"),
+    "typedef unsigned int counter_t;\n",
+  )
+  .unwrap();
+  fs::write(
+    dir.join("include/counter.h"),
+    "#include <config.h>\n#if COUNTER_WIDE\ncounter_t counter_next(void);\n#else\nint counter_next(void);\n#endif\nvoid counter_reset(int value);\n",
+  )
+  .unwrap();
+  fs::write(
+    dir.join("compile_commands.json"),
+    r#"[{ "directory": ".", "file": "counter.c", "command": "cc -Ivendor -DCOUNTER_WIDE=1 -c counter.c" }]"#,
+  )
+  .unwrap();
+  fs::write(
+    dir.join("reml-bindgen.toml"),
+    r#"headers = ["include/counter.h"]
+include_paths = ["include"]
+compile_commands = "compile_commands.json"
+defines = []
+output = "out/counter.reml"
+manifest = "out/bindings.manifest.json"
+exclude = ["^counter_reset$"]
+"#,
+  )
+  .unwrap();
+
+  let result = run_bindgen(&dir.join("reml-bindgen.toml"), &CliOptions::default()).unwrap();
+  let output = fs::read_to_string(&result.output_path).unwrap();
+  assert!(output
+    .ends_with("// generated by reml-bindgen\nextern \"C\" {\n  fn counter_next() -> u32;\n}\n"));
+  assert!(result.manifest.layouts.is_empty());
+  assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+  let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,89 @@
+use reml_ffi_bindgen::{run_export_header, BindingDirection, CliOptions};
+use std::fs;
+
+const SAMPLE_MIR: &str = r#"{
+  "schema_version": "frontend-mir/0.2",
+  "module": "app.exports",
+  "functions": [
+    {
+      "name": "area",
+      "attributes": ["export"],
+      "params": [{"name": "w", "ty": "f64"}, {"name": "h", "ty": "f64"}],
+      "return_type": "f64"
+    },
+    {
+      "name": "label",
+      "attributes": ["export"],
+      "params": [{"name": "items", "ty": "[i64]"}],
+      "return_type": "Str"
+    },
+    {
+      "name": "first",
+      "attributes": ["export"],
+      "params": [{"name": "xs", "ty": "['a]"}],
+      "return_type": "'a"
+    },
+    {"name": "helper", "params": [], "return_type": "()"}
+  ],
+  "externs": [
+    {"name": "host_now", "abi": "C", "symbol": "host_now", "params": [], "return_type": "i64"},
+    {
+      "name": "host_log",
+      "abi": "C",
+      "symbol": "host_log",
+      "params": ["i64", "Float"],
+      "param_names": ["level", "ratio"],
+      "return_type": "()"
+    }
+  ]
+}"#;
+
+#[test]
+fn export_header_writes_header_and_shared_manifest() {
+  let dir = std::env::temp_dir().join(format!("reml-bindgen-export-{}", std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  let mir = dir.join("app.mir.json");
+  fs::write(&mir, SAMPLE_MIR).unwrap();
+
+  let cli = CliOptions {
+    export_header: true,
+    mir: Some(mir.to_string_lossy().to_string()),
+    output: Some(dir.join("include/app.h").to_string_lossy().to_string()),
+    ..CliOptions::default()
+  };
+  let result = run_export_header(&cli).expect("export header");
+
+  let header = fs::read_to_string(&result.output_path).unwrap();
+  assert!(header.contains("#ifndef APP_EXPORTS_H"));
+  assert!(header.contains("double area(double w, double h);"));
+  assert!(header.contains("reml_string_t label(reml_span_t items);"));
+  assert!(header.contains("int64_t host_now(void);"));
+  assert!(header.contains("void host_log(int64_t level, double ratio);"));
+  assert!(!header.contains("first") && !header.contains("helper"));
+
+  assert_eq!(
+    result.manifest_path,
+    dir.join("include/bindings.manifest.json")
+  );
+  assert_eq!(result.manifest.direction, BindingDirection::Export);
+  let layouts: Vec<_> = result
+    .manifest
+    .layouts
+    .iter()
+    .map(|layout| layout.name.as_str())
+    .collect();
+  assert_eq!(layouts, vec!["reml_span_t", "reml_string_t"]);
+  assert_eq!(result.manifest.diagnostics.len(), 1);
+  assert_eq!(result.manifest.diagnostics[0].code, "ffi.bindgen.skipped");
+  assert_eq!(
+    result.manifest.diagnostics[0].reason.as_deref(),
+    Some("generic_function")
+  );
+
+  let manifest = fs::read_to_string(&result.manifest_path).unwrap();
+  assert!(manifest.contains("\"direction\": \"export\""));
+  assert!(manifest.contains("\"source\""));
+
+  let _ = fs::remove_dir_all(&dir);
+}
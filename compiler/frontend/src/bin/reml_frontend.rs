@@ -13,7 +13,7 @@ use std::sync::{Arc, Mutex};
 use reml_adapter::target::{self, TargetInference};
 use reml_frontend::diagnostic::messages;
 use reml_frontend::diagnostic::{
-    effects,
+    effects, explain,
     filter::{
         apply_experimental_stage_policy, should_downgrade_experimental, AuditPolicy,
         DiagnosticFilter,
@@ -36,7 +36,7 @@ use reml_frontend::parser::{
     StreamOutcome, StreamingRunner,
 };
 use reml_frontend::pipeline::{AuditEmitter, PipelineDescriptor, PipelineFailure, PipelineOutcome};
-use reml_frontend::semantics::{mir, typed};
+use reml_frontend::semantics::{mir, mir_opt, typed};
 use reml_frontend::span::Span;
 use reml_frontend::streaming::{
     StreamFlowConfig, StreamFlowMetrics, StreamFlowState, StreamingStateConfig, TraceFrame,
@@ -48,6 +48,7 @@ use reml_frontend::typeck::{
     TypeRowMode, TypecheckConfig, TypecheckDriver, TypecheckMetrics, TypecheckReport,
     TypecheckViolation, TypecheckViolationKind, TypedFunctionSummary,
 };
+use reml_llvm_backend::OptimizationLevel;
 use reml_runtime::audit::AuditEvent;
 use reml_runtime::config::{
     compatibility_profile, resolve_compat, CompatibilityLayer, CompatibilityProfileError,
@@ -67,6 +68,7 @@ use reml_runtime::runtime::plugin::{
 };
 use reml_runtime::runtime::plugin_bridge::NativePluginExecutionBridge;
 use reml_runtime::runtime::plugin_manager::PluginRuntimeManager;
+use reml_runtime::runtime::plugin_signing::PluginTrustStore;
 use reml_runtime::stage::StageId as RuntimeStageId;
 use reml_runtime::test as runtime_test;
 use reml_runtime::text::LocaleId;
@@ -87,6 +89,7 @@ const PARSER_NAME: &str = "compilation_unit";
 const PARSER_ORIGIN: &str = "reml_frontend";
 const PARSER_FINGERPRINT: &str = "rust-frontend-0001";
 const SCHEMA_VERSION: &str = "3.0.0-alpha";
+const PLUGIN_TRUST_STORE_ENV: &str = "REML_PLUGIN_TRUST_STORE";
 
 struct CliRunResult {
     envelope: CliDiagnosticEnvelope,
@@ -161,6 +164,7 @@ fn try_run_plugin_command() -> Result<bool, Box<dyn std::error::Error>> {
     match subcommand.as_str() {
         "install" => {
             let mut bundle_path = None;
+            let mut trust_store_path = None;
             let mut policy = VerificationPolicy::Strict;
             let mut output = OutputFormat::Human;
             while let Some(arg) = iter.next() {
@@ -168,6 +172,13 @@ fn try_run_plugin_command() -> Result<bool, Box<dyn std::error::Error>> {
                     "--bundle" => {
                         bundle_path = iter.next().cloned();
                     }
+                    "--trust-store" => {
+                        trust_store_path = Some(
+                            iter.next()
+                                .cloned()
+                                .ok_or("--trust-store には信頼ストアのパスを指定してください")?,
+                        );
+                    }
                     "--policy" => {
                         let value = iter
                             .next()
@@ -183,7 +194,10 @@ fn try_run_plugin_command() -> Result<bool, Box<dyn std::error::Error>> {
                             .next()
                             .ok_or("--output は human|json を指定してください")?;
                         output = OutputFormat::parse(value)?;
-                        if matches!(output, OutputFormat::Lsp | OutputFormat::LspDerive) {
+                        if matches!(
+                            output,
+                            OutputFormat::Lsp | OutputFormat::LspDerive | OutputFormat::Sarif
+                        ) {
                             return Err("--output は human|json のみ対応しています".into());
                         }
                     }
@@ -196,7 +210,8 @@ fn try_run_plugin_command() -> Result<bool, Box<dyn std::error::Error>> {
             }
             let bundle_path =
                 bundle_path.ok_or("plugin install には --bundle <path> が必要です")?;
-            let registration = match run_plugin_install(bundle_path, policy) {
+            let loader = plugin_loader_with_trust_store(trust_store_path)?;
+            let registration = match run_plugin_install(loader, bundle_path, policy) {
                 Ok(registration) => registration,
                 Err(err) => {
                     emit_plugin_audit_events();
@@ -217,6 +232,7 @@ fn try_run_plugin_command() -> Result<bool, Box<dyn std::error::Error>> {
         }
         "verify" => {
             let mut bundle_path = None;
+            let mut trust_store_path = None;
             let mut policy = VerificationPolicy::Strict;
             let mut output = OutputFormat::Human;
             while let Some(arg) = iter.next() {
@@ -224,6 +240,13 @@ fn try_run_plugin_command() -> Result<bool, Box<dyn std::error::Error>> {
                     "--bundle" => {
                         bundle_path = iter.next().cloned();
                     }
+                    "--trust-store" => {
+                        trust_store_path = Some(
+                            iter.next()
+                                .cloned()
+                                .ok_or("--trust-store には信頼ストアのパスを指定してください")?,
+                        );
+                    }
                     "--policy" => {
                         let value = iter
                             .next()
@@ -239,7 +262,10 @@ fn try_run_plugin_command() -> Result<bool, Box<dyn std::error::Error>> {
                             .next()
                             .ok_or("--output は human|json を指定してください")?;
                         output = OutputFormat::parse(value)?;
-                        if matches!(output, OutputFormat::Lsp | OutputFormat::LspDerive) {
+                        if matches!(
+                            output,
+                            OutputFormat::Lsp | OutputFormat::LspDerive | OutputFormat::Sarif
+                        ) {
                             return Err("--output は human|json のみ対応しています".into());
                         }
                     }
@@ -251,7 +277,8 @@ fn try_run_plugin_command() -> Result<bool, Box<dyn std::error::Error>> {
                 }
             }
             let bundle_path = bundle_path.ok_or("plugin verify には --bundle <path> が必要です")?;
-            let verification = match run_plugin_verify(bundle_path, policy) {
+            let loader = plugin_loader_with_trust_store(trust_store_path)?;
+            let verification = match run_plugin_verify(loader, bundle_path, policy) {
                 Ok(verification) => verification,
                 Err(err) => {
                     emit_plugin_audit_events();
@@ -274,21 +301,41 @@ fn try_run_plugin_command() -> Result<bool, Box<dyn std::error::Error>> {
     }
 }
 
+/// `--trust-store`、なければ `REML_PLUGIN_TRUST_STORE` の信頼ストアを設定したローダ。
+fn plugin_loader_with_trust_store(
+    path: Option<String>,
+) -> Result<PluginLoader, Box<dyn std::error::Error>> {
+    let loader = PluginLoader::new();
+    let path = path.or_else(|| {
+        env::var(PLUGIN_TRUST_STORE_ENV)
+            .ok()
+            .filter(|value| !value.is_empty())
+    });
+    match path {
+        Some(path) => {
+            let trust_store =
+                PluginTrustStore::load(path).map_err(|err| format_plugin_load_error(&err))?;
+            Ok(loader.with_trust_store(trust_store))
+        }
+        None => Ok(loader),
+    }
+}
+
 fn run_plugin_install(
+    loader: PluginLoader,
     bundle_path: String,
     policy: VerificationPolicy,
 ) -> Result<PluginBundleRegistration, PluginError> {
-    let loader = PluginLoader::new();
     let bridge = NativePluginExecutionBridge::new();
     let manager = PluginRuntimeManager::new(loader, Box::new(bridge));
     manager.load_bundle_and_attach(bundle_path, policy)
 }
 
 fn run_plugin_verify(
+    loader: PluginLoader,
     bundle_path: String,
     policy: VerificationPolicy,
 ) -> Result<PluginBundleVerification, PluginLoadError> {
-    let loader = PluginLoader::new();
     loader.verify_bundle_path(bundle_path, policy)
 }
 
@@ -327,6 +374,39 @@ fn format_plugin_error(error: &PluginError) -> String {
                 format!("バンドルのインストールに失敗しました: {message}")
             }
         }
+        PluginError::ImportDenied { plugin_id, import } => {
+            format!("プラグイン {plugin_id} が要求していない import を呼び出しました: {import}")
+        }
+        PluginError::ImportUnresolved { plugin_id, import } => {
+            format!("プラグイン {plugin_id} が未知の import を要求しています: {import}")
+        }
+        PluginError::FuelExhausted { plugin_id, fuel } => {
+            format!("プラグイン {plugin_id} が fuel 上限 {fuel} を使い切りました")
+        }
+        PluginError::Timeout {
+            plugin_id,
+            timeout_ms,
+        } => format!("プラグイン {plugin_id} の呼び出しが {timeout_ms} ms でタイムアウトしました"),
+        PluginError::MemoryLimitExceeded {
+            plugin_id,
+            limit_bytes,
+            requested_bytes,
+        } => format!(
+            "プラグイン {plugin_id} がメモリ上限 {limit_bytes} バイトを超えて {requested_bytes} バイトを要求しました"
+        ),
+        PluginError::TableLimitExceeded {
+            plugin_id,
+            limit_elements,
+            requested_elements,
+        } => format!(
+            "プラグイン {plugin_id} がテーブル上限 {limit_elements} 要素を超えて {requested_elements} 要素を要求しました"
+        ),
+        PluginError::AbiMismatch { plugin_id, detail } => {
+            format!("プラグイン {plugin_id} の呼び出し ABI が一致しません: {detail}")
+        }
+        PluginError::PayloadInvalid { encoding, message } => {
+            format!("プラグインのペイロード（{encoding}）を変換できません: {message}")
+        }
     }
 }
 
@@ -410,6 +490,9 @@ fn run_capability_describe(
         OutputFormat::LspDerive => {
             return Err("--capability describe では lsp-derive 出力をサポートしていません".into())
         }
+        OutputFormat::Sarif => {
+            return Err("--capability describe では SARIF 出力をサポートしていません".into())
+        }
     }
     Ok(())
 }
@@ -639,6 +722,7 @@ fn run_frontend(args: &CliArgs) -> Result<CliRunResult, Box<dyn std::error::Erro
         let options = LexerOptions {
             identifier_profile: args.run_config.lex_identifier_profile,
             identifier_locale: args.run_config.lex_identifier_locale.clone(),
+            diagnostic_locale: args.diagnostic_locale,
         };
         let lex_output = lex_source_with_options(&source, options);
         write_json_file(path, &lex_output.tokens)?;
@@ -665,6 +749,7 @@ fn run_frontend(args: &CliArgs) -> Result<CliRunResult, Box<dyn std::error::Erro
     parser_options.streaming = args.streaming_state_config();
     parser_options.streaming_enabled = args.stream_config.enabled || run_config.trace;
     parser_options.stream_flow = Some(stream_flow_state.clone());
+    parser_options.diagnostic_locale = args.diagnostic_locale;
     let result = if args.stream_config.enabled {
         let runner = StreamingRunner::new(
             source.to_owned(),
@@ -821,7 +906,22 @@ fn run_frontend(args: &CliArgs) -> Result<CliRunResult, Box<dyn std::error::Erro
         write_json_file(path, &artifacts.typed_ast)?;
     }
     if let Some(path) = &args.emit_mir {
-        write_json_file(path, &artifacts.mir)?;
+        if args.debug_info || args.emit_mir_optimized {
+            let mut mir = artifacts.mir.clone();
+            if args.debug_info {
+                mir.debug_source = Some(mir::MirDebugSource::from_source(
+                    input_path.display().to_string(),
+                    &source,
+                ));
+            }
+            if args.emit_mir_optimized {
+                let options = mir_opt::MirOptOptions::new(args.mir_opt_level).with_dumps(true);
+                mir_opt::optimize_module(&mut mir, options);
+            }
+            write_json_file(path, &mir)?;
+        } else {
+            write_json_file(path, &artifacts.mir)?;
+        }
     }
     if let Some(path) = &args.emit_constraints {
         write_json_file(path, &artifacts.constraints)?;
@@ -1578,6 +1678,7 @@ struct CliArgs {
     input: PathBuf,
     parse_debug_output: Option<PathBuf>,
     output_format: OutputFormat,
+    diagnostic_locale: &'static str,
     command: CliCommandKind,
     phase: CliPhaseKind,
     run_id: Uuid,
@@ -1586,6 +1687,9 @@ struct CliArgs {
     emit_typed_ast: Option<PathBuf>,
     emit_ast: Option<PathBuf>,
     emit_mir: Option<PathBuf>,
+    emit_mir_optimized: bool,
+    mir_opt_level: OptimizationLevel,
+    debug_info: bool,
     emit_constraints: Option<PathBuf>,
     emit_typeck_debug: Option<PathBuf>,
     emit_effects_metrics: Option<PathBuf>,
@@ -2200,6 +2304,9 @@ fn parse_args() -> Result<CliArgs, Box<dyn std::error::Error>> {
     let mut emit_ast = None;
     let mut emit_typed_ast = None;
     let mut emit_mir = None;
+    let mut emit_mir_optimized = false;
+    let mut mir_opt_level = OptimizationLevel::O2;
+    let mut debug_info = false;
     let mut emit_constraints = None;
     let mut emit_typeck_debug = None;
     let mut emit_effects_metrics = None;
@@ -2219,6 +2326,7 @@ fn parse_args() -> Result<CliArgs, Box<dyn std::error::Error>> {
     let mut diagnostics_stream = false;
     let mut runtime_phase_enabled = true;
     let mut output_format = OutputFormat::default();
+    let mut requested_locale: Option<String> = None;
     let mut run_config = RunSettings::default();
     let mut stream_config = StreamSettings::default();
     let mut runtime_capabilities: Vec<RuntimeCapability> = Vec::new();
@@ -2365,6 +2473,22 @@ fn parse_args() -> Result<CliArgs, Box<dyn std::error::Error>> {
                     .ok_or_else(|| "--emit-mir は出力パスを伴う必要があります")?;
                 emit_mir = Some(PathBuf::from(path));
             }
+            "--emit-mir=raw" | "--emit-mir=optimized" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("{arg} は出力パスを伴う必要があります"))?;
+                emit_mir = Some(PathBuf::from(path));
+                emit_mir_optimized = arg == "--emit-mir=optimized";
+            }
+            "--opt-level" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--opt-level は 0/1/2/3/s のいずれかを伴う必要があります")?;
+                mir_opt_level = mir_opt::parse_optimization_level(&value).ok_or_else(|| {
+                    format!("--opt-level に未知の値 '{value}' が指定されました（0/1/2/3/s）")
+                })?;
+            }
+            "--debug-info" => debug_info = true,
             "--emit-constraints" => {
                 let path = args
                     .next()
@@ -2391,10 +2515,21 @@ fn parse_args() -> Result<CliArgs, Box<dyn std::error::Error>> {
             }
             "--output" | "--format" => {
                 let value = args.next().ok_or_else(|| {
-                    "--output は human|json|lsp|lsp-derive のいずれかを指定してください"
+                    "--output は human|json|lsp|lsp-derive|sarif のいずれかを指定してください"
                 })?;
                 output_format = OutputFormat::parse(&value)?;
             }
+            "--locale" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--locale はロケール ID を伴う必要があります")?;
+                if messages::catalog::negotiate_locale(&value).is_none() {
+                    eprintln!(
+                        "[CLI] --locale の値 `{value}` に対応する診断カタログがないため、環境変数または既定ロケールを使用します"
+                    );
+                }
+                requested_locale = Some(value);
+            }
             "--emit-tokens" => {
                 let path = args
                     .next()
@@ -2691,12 +2826,15 @@ fn parse_args() -> Result<CliArgs, Box<dyn std::error::Error>> {
         builder = builder.type_row_mode(mode);
     }
 
+    let diagnostic_locale = messages::resolve_locale(requested_locale.as_deref());
+
     Ok(CliArgs {
         program_name,
         raw_args: raw_cli_args,
         input,
         parse_debug_output: parse_debug,
         output_format,
+        diagnostic_locale,
         command: CliCommandKind::default(),
         phase: CliPhaseKind::default(),
         run_id,
@@ -2705,6 +2843,9 @@ fn parse_args() -> Result<CliArgs, Box<dyn std::error::Error>> {
         emit_ast,
         emit_typed_ast,
         emit_mir,
+        emit_mir_optimized,
+        mir_opt_level,
+        debug_info,
         emit_constraints,
         emit_typeck_debug,
         emit_effects_metrics,
@@ -2745,6 +2886,9 @@ fn print_help(program_name: &str) {
   --emit-ast <PATH>              解析結果 AST を JSON で保存
   --emit-typed-ast <PATH>        型付き AST を JSON で保存
   --emit-mir <PATH>              Match/Pattern MIR を JSON で保存（--debug-mir も利用可能）
+  --emit-mir=optimized <PATH>    最適化後の MIR をパス統計・パス前後のダンプ付きで保存
+  --opt-level <LEVEL>            MIR 最適化レベル（0/1/2/3/s、既定: 2）
+  --debug-info                   --emit-mir の出力に DWARF 生成用のソース対応表を含める
   --emit-constraints <PATH>      Typecheck 制約を JSON で保存
   --emit-typeck-debug <PATH>     型推論デバッグ情報を JSON で保存
   --config-compat <PROFILE>      設定ファイル互換プロファイルを指定 (strict-json / json-relaxed 等)
@@ -2763,6 +2907,7 @@ fn print_help(program_name: &str) {
   --trace-output <PATH>          Parser TraceEvent を Markdown で保存
   --lex-profile ascii|unicode    識別子プロファイルの切替
   --lex-locale <Bcp47>          識別子正規化で使用するロケール ID
+  --locale <Bcp47>               診断メッセージのロケール（未指定時は LC_ALL/LC_MESSAGES/LANG、既定: ja-JP）
   --runtime-phase on|off         パース/型検査後の簡易 runtime 実行フェーズを有効/無効化（既定: on）
   --no-runtime-phase             上記のショートカット（off）
   --packrat / --no-packrat       Packrat キャッシュを有効/無効化
@@ -3606,17 +3751,34 @@ fn build_type_diagnostics(
             let mut severity_label = messages::find_message(violation.code)
                 .map(|template| template.severity.as_str())
                 .unwrap_or("error");
+            let localized = localize_violation(violation, args.diagnostic_locale);
             if let Some(template) = messages::find_message(violation.code) {
+                let (title, message) = localized
+                    .as_ref()
+                    .map(|localized| (localized.title.as_str(), localized.message.as_str()))
+                    .unwrap_or((template.title, template.message));
                 extensions.insert(
                     "diagnostic.message".to_string(),
                     json!({
                         "code": template.code,
-                        "title": template.title,
-                        "message": template.message,
+                        "title": title,
+                        "message": message,
                         "severity": template.severity.as_str(),
+                        "locale": localized.as_ref().map(|localized| localized.locale.as_str()),
                     }),
                 );
             }
+            if let Some(explanation) = explain::find_explanation(violation.code) {
+                extensions.insert("diagnostic.explain".to_string(), explanation.link_value());
+            }
+            let message = localized
+                .as_ref()
+                .filter(|localized| localized.locale != messages::DEFAULT_LOCALE)
+                .map(|localized| match localized.detail.as_ref() {
+                    Some(detail) => format!("{} {detail}", localized.message),
+                    None => localized.message.clone(),
+                })
+                .unwrap_or_else(|| violation.message.clone());
             if should_downgrade_experimental(
                 args.run_config.ack_experimental_diagnostics,
                 &extensions,
@@ -3687,7 +3849,9 @@ fn build_type_diagnostics(
             json!({
                 "schema_version": SCHEMA_VERSION,
                 "timestamp": timestamp,
-                "message": violation.message,
+                "message": message,
+                "message_key": localized.as_ref().map(|_| violation.code),
+                "locale": localized.as_ref().map(|localized| localized.locale.clone()),
                 "severity": severity_label,
                 "severity_hint": Value::Null,
                 "domain": violation.domain(),
@@ -3709,6 +3873,21 @@ fn build_type_diagnostics(
         .collect()
 }
 
+/// 型検査違反をカタログで引く。既定ロケール以外では本文を置き換えるため、
+/// 補間引数（未処理バリアント数など）もここで組み立てる。
+fn localize_violation(
+    violation: &TypecheckViolation,
+    locale: &str,
+) -> Option<messages::LocalizedMessage> {
+    let mut args = messages::MessageArgs::new();
+    if let Some(variants) = violation.pattern_missing_variants.as_ref() {
+        args = args
+            .with_number("count", variants.len() as i64)
+            .with("variants", variants.join(", "));
+    }
+    messages::localize(violation.code, locale, &args)
+}
+
 fn has_recover_note(diag: &FrontendDiagnostic) -> bool {
     diag.notes
         .iter()
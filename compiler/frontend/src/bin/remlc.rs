@@ -1,4 +1,9 @@
+use reml_frontend::diagnostic::explain::{explanations, find_explanation, DiagnosticExplanation};
+use reml_frontend::diagnostic::json::LineIndex;
 use reml_frontend::ffi_executor::install_cli_ffi_executor;
+use reml_frontend::format::{format_source, FormatOptions};
+use reml_frontend::lexer::IdentifierProfile;
+use reml_frontend::lint::{apply_fixits, lint_source, LintOptions};
 use reml_runtime::collections::{
     audit_bridge::{AuditBridgeError, ChangeSet},
     persistent::btree::PersistentMap,
@@ -10,6 +15,12 @@ use reml_runtime::config::{
 use reml_runtime::config::{ChangeKind, ConfigChange};
 use reml_runtime::data::schema::Schema;
 use reml_runtime::prelude::ensure::{DiagnosticSeverity, GuardDiagnostic};
+use reml_llvm_backend::{
+    emit_llvm_module_from_mir_json_with_options, LlvmEmitOptions, TargetMachineBuilder,
+};
+use reml_wasm_backend::{
+    emit_wasm_module_from_mir_json, CodegenFallback, MirSnapshotError, WasmEmitOptions,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Map, Value};
 use std::env;
@@ -42,6 +53,9 @@ fn try_main() -> Result<i32, CliError> {
         "manifest" => handle_manifest(args),
         "config" => handle_config(args),
         "build" => handle_build(args),
+        "explain" => handle_explain(args),
+        "fmt" => handle_fmt(args),
+        "lint" => handle_lint(args),
         "--help" | "-h" => {
             print_help();
             Ok(0)
@@ -158,9 +172,16 @@ fn handle_build(args: Vec<String>) -> Result<i32, CliError> {
         None => (Vec::new(), Vec::new()),
     };
     diagnostics.append(&mut bindgen_diagnostics);
-    let report = BuildLintReport::new(
+    let mut report_diagnostics: Vec<LintDiagnostic> =
+        diagnostics.into_iter().map(guard_diag_to_report).collect();
+    let artifacts = if report_diagnostics.is_empty() {
+        emit_build_target(&opts, &mut report_diagnostics)?
+    } else {
+        Vec::new()
+    };
+    let mut report = BuildLintReport::new(
         &opts,
-        diagnostics.into_iter().map(guard_diag_to_report).collect(),
+        report_diagnostics,
         config.is_some(),
         config
             .as_ref()
@@ -168,10 +189,167 @@ fn handle_build(args: Vec<String>) -> Result<i32, CliError> {
             .is_some(),
         audit_entries,
     );
+    report.artifacts = artifacts;
     print_build_report(&report, opts.output_format)?;
     Ok(report.exit_code())
 }
 
+fn handle_explain(args: Vec<String>) -> Result<i32, CliError> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print_explain_help();
+        return Ok(0);
+    }
+    let opts = ExplainOptions::parse(args)?;
+    let Some(code) = opts.code else {
+        print_explain_index(opts.format)?;
+        return Ok(0);
+    };
+    let explanation = find_explanation(&code).ok_or_else(|| {
+        CliError::Usage(format!(
+            "診断コード `{code}` の詳細解説は登録されていません（`remlc explain` で一覧を表示できます）"
+        ))
+    })?;
+    print_explanation(explanation, opts.format)?;
+    Ok(0)
+}
+
+fn handle_fmt(args: Vec<String>) -> Result<i32, CliError> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print_fmt_help();
+        return Ok(0);
+    }
+    let opts = FmtOptions::parse(args)?;
+    let options = match opts.manifest_path.as_ref() {
+        Some(path) => FormatOptions::from_section(&read_manifest(path)?.format),
+        None => FormatOptions::default(),
+    };
+    let mut files = Vec::new();
+    for path in &opts.paths {
+        collect_reml_sources(path, &mut files)?;
+    }
+    files.sort();
+    files.dedup();
+    let mut report = FmtReport::new(opts.check);
+    for file in &files {
+        let display = file.display().to_string();
+        let source = fs::read_to_string(file)?;
+        match format_source(&source, &options) {
+            Ok(formatted) if formatted != source => {
+                if !opts.check {
+                    fs::write(file, formatted)?;
+                }
+                report.changed_files.push(display);
+            }
+            Ok(_) => {}
+            Err(err) => report.failures.push(FmtFailure {
+                path: display,
+                message: err.to_string(),
+            }),
+        }
+    }
+    report.stats.checked_files = files.len();
+    report.stats.changed_files = report.changed_files.len();
+    print_fmt_report(&report, opts.output_format)?;
+    Ok(report.exit_code())
+}
+
+fn handle_lint(args: Vec<String>) -> Result<i32, CliError> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print_lint_help();
+        return Ok(0);
+    }
+    let opts = SourceLintOptions::parse(args)?;
+    let mut options = match opts.manifest_path.as_ref() {
+        Some(path) => LintOptions::from_section(&read_manifest(path)?.lint),
+        None => LintOptions::default(),
+    };
+    options.identifier_profile = opts.identifier_profile;
+    let mut files = Vec::new();
+    for path in &opts.paths {
+        collect_reml_sources(path, &mut files)?;
+    }
+    files.sort();
+    files.dedup();
+    let mut report = SourceLintReport::new(opts.fix);
+    for file in &files {
+        let display = file.display().to_string();
+        let source = fs::read_to_string(file)?;
+        let findings = match lint_source(&source, &options) {
+            Ok(findings) => findings,
+            Err(err) => {
+                report.failures.push(FmtFailure {
+                    path: display,
+                    message: err.to_string(),
+                });
+                continue;
+            }
+        };
+        if opts.fix {
+            let fixits: Vec<_> = findings
+                .iter()
+                .flat_map(|finding| finding.fixits.iter().cloned())
+                .collect();
+            let fixed = apply_fixits(&source, &fixits);
+            if fixed != source {
+                fs::write(file, fixed)?;
+                report.fixed_files.push(display.clone());
+            }
+        }
+        let index = LineIndex::new(&source);
+        for finding in findings {
+            let (line, column) = index.line_col(finding.span.start as usize);
+            report.diagnostics.push(SourceLintDiagnostic {
+                path: display.clone(),
+                code: finding.rule.code,
+                level: finding.level.as_str(),
+                message: finding.message,
+                line,
+                column,
+                fixits: finding.fixits.len(),
+            });
+        }
+    }
+    report.stats.checked_files = files.len();
+    report.stats.warnings = report
+        .diagnostics
+        .iter()
+        .filter(|diag| diag.level == "warn")
+        .count();
+    report.stats.errors = report.diagnostics.len() - report.stats.warnings;
+    print_source_lint_report(&report, opts.output_format)?;
+    Ok(report.exit_code())
+}
+
+/// `fmt` / `lint` の対象となる `.reml` ファイルを収集する。ディレクトリは隠しディレクトリと
+/// `target/` を除いて再帰的に走査する。
+fn collect_reml_sources(path: &Path, out: &mut Vec<PathBuf>) -> Result<(), CliError> {
+    if !path.is_dir() {
+        if !path.exists() {
+            return Err(CliError::Usage(format!(
+                "対象 `{}` が見つかりません",
+                path.display()
+            )));
+        }
+        out.push(path.to_path_buf());
+        return Ok(());
+    }
+    for entry in fs::read_dir(path)? {
+        let entry_path = entry?.path();
+        let name = entry_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if entry_path.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                collect_reml_sources(&entry_path, out)?;
+            }
+        } else if entry_path.extension().is_some_and(|ext| ext == "reml") {
+            out.push(entry_path);
+        }
+    }
+    Ok(())
+}
+
 fn config_lint(args: Vec<String>) -> Result<i32, CliError> {
     if args.iter().any(|arg| arg == "--help" || arg == "-h") {
         print_config_lint_help();
@@ -287,6 +465,39 @@ struct BuildLintOptions {
     output_format: ReportFormat,
     emit_bindgen: bool,
     cache_dir: Option<PathBuf>,
+    target: Option<BuildTarget>,
+    mir_path: Option<PathBuf>,
+    out_path: Option<PathBuf>,
+    emit_wat: bool,
+    wasm_options: WasmEmitOptions,
+    llvm_options: LlvmEmitOptions,
+}
+
+/// `remlc build --target` で生成するターゲット。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BuildTarget {
+    Wasm32,
+    /// x86_64 Linux 向けの LLVM IR（`.ll`）。
+    Llvm,
+}
+
+impl BuildTarget {
+    fn parse(value: &str) -> Result<Self, CliError> {
+        match value {
+            "wasm32" | "wasm32-unknown-unknown" => Ok(BuildTarget::Wasm32),
+            "llvm" | "x86_64-unknown-linux-gnu" => Ok(BuildTarget::Llvm),
+            other => Err(CliError::Usage(format!(
+                "ターゲット `{other}` には対応していません（wasm32 / llvm のみサポート）"
+            ))),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            BuildTarget::Wasm32 => "wasm32",
+            BuildTarget::Llvm => "llvm",
+        }
+    }
 }
 
 impl Default for BuildLintOptions {
@@ -296,6 +507,12 @@ impl Default for BuildLintOptions {
             output_format: ReportFormat::Json,
             emit_bindgen: false,
             cache_dir: None,
+            target: None,
+            mir_path: None,
+            out_path: None,
+            emit_wat: false,
+            wasm_options: WasmEmitOptions::new(),
+            llvm_options: LlvmEmitOptions::new(),
         }
     }
 }
@@ -325,6 +542,26 @@ impl BuildLintOptions {
                     })?;
                     opts.cache_dir = Some(PathBuf::from(value));
                 }
+                "--target" => {
+                    let value = iter.next().ok_or_else(|| {
+                        CliError::Usage("--target はターゲット名を伴う必要があります".into())
+                    })?;
+                    opts.target = Some(BuildTarget::parse(&value)?);
+                }
+                "--mir" => {
+                    let value = iter.next().ok_or_else(|| {
+                        CliError::Usage("--mir はパスを伴う必要があります".into())
+                    })?;
+                    opts.mir_path = Some(PathBuf::from(value));
+                }
+                "--out" | "-o" => {
+                    let value = iter.next().ok_or_else(|| {
+                        CliError::Usage("--out はパスを伴う必要があります".into())
+                    })?;
+                    opts.out_path = Some(PathBuf::from(value));
+                }
+                "--emit-wat" => opts.emit_wat = true,
+                other if opts.apply_codegen_flag(other) => {}
                 other => {
                     return Err(CliError::Usage(format!(
                         "build コマンドの未知のオプション `{other}` が指定されました"
@@ -332,8 +569,33 @@ impl BuildLintOptions {
                 }
             }
         }
+        if opts.target.is_some() && opts.mir_path.is_none() {
+            return Err(CliError::Usage(
+                "--target を指定する場合は --mir <path> で入力 MIR を渡す必要があります".into(),
+            ));
+        }
+        match opts.target {
+            Some(BuildTarget::Llvm) if opts.wasm_options.plugin_abi || opts.emit_wat => {
+                return Err(CliError::Usage(
+                    "--plugin-abi / --emit-wat は --target wasm32 でのみ指定できます".into(),
+                ));
+            }
+            Some(BuildTarget::Wasm32) | None if opts.llvm_options.debug_info => {
+                return Err(CliError::Usage(
+                    "--debug-info は --target llvm でのみ指定できます".into(),
+                ));
+            }
+            _ => {}
+        }
         Ok(opts)
     }
+
+    /// コード生成フラグを両バックエンドのオプションへ適用する。どちらかが受理すれば `true`。
+    fn apply_codegen_flag(&mut self, flag: &str) -> bool {
+        let wasm = self.wasm_options.apply_flag(flag);
+        let llvm = self.llvm_options.apply_flag(flag);
+        wasm || llvm
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -343,6 +605,17 @@ struct BuildLintReport {
     diagnostics: Vec<LintDiagnostic>,
     audit: Vec<Value>,
     stats: BuildLintStats,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artifacts: Vec<BuildArtifact>,
+}
+
+/// `--target` 指定時に書き出した成果物。
+#[derive(Debug, Clone, Serialize)]
+struct BuildArtifact {
+    target: &'static str,
+    kind: &'static str,
+    path: String,
+    bytes: usize,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -360,7 +633,10 @@ impl BuildLintReport {
         ffi_present: bool,
         audit: Vec<Value>,
     ) -> Self {
-        let validated = diagnostics.is_empty() && config_loaded;
+        let validated = !diagnostics
+            .iter()
+            .any(|diag| diag.severity == severity_label(DiagnosticSeverity::Error))
+            && config_loaded;
         BuildLintReport {
             command: "build.lint",
             config: opts.config_path.display().to_string(),
@@ -371,6 +647,7 @@ impl BuildLintReport {
                 config_loaded,
                 ffi_present,
             },
+            artifacts: Vec::new(),
         }
     }
 
@@ -383,6 +660,101 @@ impl BuildLintReport {
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct FmtReport {
+    command: &'static str,
+    mode: &'static str,
+    changed_files: Vec<String>,
+    failures: Vec<FmtFailure>,
+    stats: FmtStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FmtFailure {
+    path: String,
+    message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct FmtStats {
+    checked_files: usize,
+    #[serde(rename = "formatting.changed_files")]
+    changed_files: usize,
+}
+
+impl FmtReport {
+    fn new(check: bool) -> Self {
+        FmtReport {
+            command: "fmt",
+            mode: if check { "check" } else { "write" },
+            changed_files: Vec::new(),
+            failures: Vec::new(),
+            stats: FmtStats::default(),
+        }
+    }
+
+    fn exit_code(&self) -> i32 {
+        let unformatted = self.mode == "check" && !self.changed_files.is_empty();
+        if unformatted || !self.failures.is_empty() {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// `remlc lint` の結果。`--fix` 指定時の診断は修正前のソースに対する位置を示す。
+#[derive(Debug, Clone, Serialize)]
+struct SourceLintReport {
+    command: &'static str,
+    mode: &'static str,
+    diagnostics: Vec<SourceLintDiagnostic>,
+    fixed_files: Vec<String>,
+    failures: Vec<FmtFailure>,
+    stats: SourceLintStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SourceLintDiagnostic {
+    path: String,
+    code: &'static str,
+    level: &'static str,
+    message: String,
+    line: u32,
+    column: u32,
+    fixits: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct SourceLintStats {
+    checked_files: usize,
+    #[serde(rename = "lint.warnings")]
+    warnings: usize,
+    #[serde(rename = "lint.errors")]
+    errors: usize,
+}
+
+impl SourceLintReport {
+    fn new(fix: bool) -> Self {
+        SourceLintReport {
+            command: "lint",
+            mode: if fix { "fix" } else { "check" },
+            diagnostics: Vec::new(),
+            fixed_files: Vec::new(),
+            failures: Vec::new(),
+            stats: SourceLintStats::default(),
+        }
+    }
+
+    fn exit_code(&self) -> i32 {
+        if self.stats.errors > 0 || !self.failures.is_empty() {
+            1
+        } else {
+            0
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct BuildConfig {
     #[serde(default)]
@@ -515,6 +887,124 @@ fn validate_build_config(config: &BuildConfig, path: &Path) -> Vec<GuardDiagnost
     diagnostics
 }
 
+/// `--target` 指定時に MIR JSON から成果物を書き出す。
+///
+/// `wasm32` は `.wasm`（`--emit-wat` なら `.wat` も）、`llvm` は `.ll` を生成する。
+/// 縮退箇所は警告として、`--strict-codegen` による中断や MIR の読み込み失敗はエラーとして報告する。
+fn emit_build_target(
+    opts: &BuildLintOptions,
+    diagnostics: &mut Vec<LintDiagnostic>,
+) -> Result<Vec<BuildArtifact>, CliError> {
+    let (Some(target), Some(mir_path)) = (opts.target, opts.mir_path.as_ref()) else {
+        return Ok(Vec::new());
+    };
+    let module_name = mir_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("main");
+    match target {
+        BuildTarget::Wasm32 => emit_wasm_target(opts, mir_path, module_name, diagnostics),
+        BuildTarget::Llvm => emit_llvm_target(opts, mir_path, module_name, diagnostics),
+    }
+}
+
+/// バックエンドのエラーを `build` の診断へ変換する。
+fn build_target_error(
+    target: BuildTarget,
+    mir_path: &Path,
+    err: MirSnapshotError,
+) -> LintDiagnostic {
+    if let MirSnapshotError::StrictCodegen(fallback) = &err {
+        return codegen_fallback_to_report(fallback, DiagnosticSeverity::Error);
+    }
+    let mut extensions = Map::new();
+    extensions.insert("path".into(), Value::String(mir_path.display().to_string()));
+    extensions.insert("target".into(), Value::String(target.label().into()));
+    LintDiagnostic {
+        code: "build.target.mir_invalid".into(),
+        domain: "build".into(),
+        severity: severity_label(DiagnosticSeverity::Error).to_string(),
+        message: format!("MIR の読み込みに失敗しました: {err}"),
+        extensions: Value::Object(extensions),
+        audit: Value::Object(Map::new()),
+    }
+}
+
+fn emit_llvm_target(
+    opts: &BuildLintOptions,
+    mir_path: &Path,
+    module_name: &str,
+    diagnostics: &mut Vec<LintDiagnostic>,
+) -> Result<Vec<BuildArtifact>, CliError> {
+    let ir = match emit_llvm_module_from_mir_json_with_options(
+        mir_path,
+        TargetMachineBuilder::new().build(),
+        Vec::new(),
+        opts.llvm_options.clone(),
+        module_name,
+    ) {
+        Ok(ir) => ir,
+        Err(err) => {
+            diagnostics.push(build_target_error(BuildTarget::Llvm, mir_path, err));
+            return Ok(Vec::new());
+        }
+    };
+    let ll_path = opts
+        .out_path
+        .clone()
+        .unwrap_or_else(|| mir_path.with_extension("ll"));
+    fs::write(&ll_path, &ir)?;
+    Ok(vec![BuildArtifact {
+        target: BuildTarget::Llvm.label(),
+        kind: "ll",
+        path: ll_path.display().to_string(),
+        bytes: ir.len(),
+    }])
+}
+
+fn emit_wasm_target(
+    opts: &BuildLintOptions,
+    mir_path: &Path,
+    module_name: &str,
+    diagnostics: &mut Vec<LintDiagnostic>,
+) -> Result<Vec<BuildArtifact>, CliError> {
+    let artifact = match emit_wasm_module_from_mir_json(mir_path, &opts.wasm_options, module_name) {
+        Ok(artifact) => artifact,
+        Err(err) => {
+            diagnostics.push(build_target_error(BuildTarget::Wasm32, mir_path, err));
+            return Ok(Vec::new());
+        }
+    };
+    diagnostics.extend(
+        artifact
+            .fallbacks
+            .iter()
+            .map(|fallback| codegen_fallback_to_report(fallback, DiagnosticSeverity::Warning)),
+    );
+    let wasm_path = opts
+        .out_path
+        .clone()
+        .unwrap_or_else(|| mir_path.with_extension("wasm"));
+    fs::write(&wasm_path, &artifact.binary)?;
+    let mut artifacts = vec![BuildArtifact {
+        target: "wasm32",
+        kind: "wasm",
+        path: wasm_path.display().to_string(),
+        bytes: artifact.binary.len(),
+    }];
+    if opts.emit_wat {
+        let wat_path = wasm_path.with_extension("wat");
+        fs::write(&wat_path, &artifact.wat)?;
+        artifacts.push(BuildArtifact {
+            target: "wasm32",
+            kind: "wat",
+            path: wat_path.display().to_string(),
+            bytes: artifact.wat.len(),
+        });
+    }
+    Ok(artifacts)
+}
+
 fn run_bindgen_if_enabled(
     config: &BuildConfig,
     opts: &BuildLintOptions,
@@ -1062,6 +1552,163 @@ impl ConfigLintOptions {
     }
 }
 
+#[derive(Debug)]
+struct ExplainOptions {
+    code: Option<String>,
+    format: ReportFormat,
+}
+
+impl ExplainOptions {
+    fn parse(args: Vec<String>) -> Result<Self, CliError> {
+        let mut opts = ExplainOptions {
+            code: None,
+            format: ReportFormat::Human,
+        };
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--format" => {
+                    let value = iter.next().ok_or_else(|| {
+                        CliError::Usage(
+                            "--format には human もしくは json を指定してください".to_string(),
+                        )
+                    })?;
+                    opts.format = ReportFormat::parse(&value)?;
+                }
+                other if other.starts_with('-') => {
+                    return Err(CliError::Usage(format!(
+                        "explain で未対応のオプション `{other}` が指定されました"
+                    )));
+                }
+                value => {
+                    if opts.code.is_some() {
+                        return Err(CliError::Usage(
+                            "explain に指定できる診断コードは 1 つのみです".to_string(),
+                        ));
+                    }
+                    opts.code = Some(value.to_string());
+                }
+            }
+        }
+        Ok(opts)
+    }
+}
+
+#[derive(Debug)]
+struct FmtOptions {
+    paths: Vec<PathBuf>,
+    check: bool,
+    manifest_path: Option<PathBuf>,
+    output_format: ReportFormat,
+}
+
+impl FmtOptions {
+    fn parse(args: Vec<String>) -> Result<Self, CliError> {
+        let mut opts = FmtOptions {
+            paths: Vec::new(),
+            check: false,
+            manifest_path: None,
+            output_format: ReportFormat::Human,
+        };
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--check" => opts.check = true,
+                "--manifest" => {
+                    let path = iter.next().ok_or_else(|| {
+                        CliError::Usage("--manifest にはパスを指定してください".to_string())
+                    })?;
+                    opts.manifest_path = Some(PathBuf::from(path));
+                }
+                "--format" => {
+                    let value = iter.next().ok_or_else(|| {
+                        CliError::Usage(
+                            "--format には human もしくは json を指定してください".to_string(),
+                        )
+                    })?;
+                    opts.output_format = ReportFormat::parse(&value)?;
+                }
+                other if other.starts_with('-') => {
+                    return Err(CliError::Usage(format!(
+                        "fmt で未対応のオプション `{other}` が指定されました"
+                    )));
+                }
+                path => opts.paths.push(PathBuf::from(path)),
+            }
+        }
+        if opts.paths.is_empty() {
+            opts.paths.push(PathBuf::from("."));
+        }
+        if opts.manifest_path.is_none() && Path::new("reml.toml").is_file() {
+            opts.manifest_path = Some(PathBuf::from("reml.toml"));
+        }
+        Ok(opts)
+    }
+}
+
+#[derive(Debug)]
+struct SourceLintOptions {
+    paths: Vec<PathBuf>,
+    fix: bool,
+    manifest_path: Option<PathBuf>,
+    identifier_profile: IdentifierProfile,
+    output_format: ReportFormat,
+}
+
+impl SourceLintOptions {
+    fn parse(args: Vec<String>) -> Result<Self, CliError> {
+        let mut opts = SourceLintOptions {
+            paths: Vec::new(),
+            fix: false,
+            manifest_path: None,
+            identifier_profile: IdentifierProfile::default(),
+            output_format: ReportFormat::Human,
+        };
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--fix" => opts.fix = true,
+                "--manifest" => {
+                    let path = iter.next().ok_or_else(|| {
+                        CliError::Usage("--manifest にはパスを指定してください".to_string())
+                    })?;
+                    opts.manifest_path = Some(PathBuf::from(path));
+                }
+                "--identifier-profile" => {
+                    let value = iter.next().unwrap_or_default();
+                    opts.identifier_profile = value.parse().map_err(|_| {
+                        CliError::Usage(
+                            "--identifier-profile には unicode もしくは ascii-compat を指定してください"
+                                .to_string(),
+                        )
+                    })?;
+                }
+                "--format" => {
+                    let value = iter.next().ok_or_else(|| {
+                        CliError::Usage(
+                            "--format には human もしくは json を指定してください".to_string(),
+                        )
+                    })?;
+                    opts.output_format = ReportFormat::parse(&value)?;
+                }
+                other if other.starts_with('-') => {
+                    return Err(CliError::Usage(format!(
+                        "lint で未対応のオプション `{other}` が指定されました"
+                    )));
+                }
+                path => opts.paths.push(PathBuf::from(path)),
+            }
+        }
+        if opts.paths.is_empty() {
+            opts.paths.push(PathBuf::from("."));
+        }
+        if opts.manifest_path.is_none() && Path::new("reml.toml").is_file() {
+            opts.manifest_path = Some(PathBuf::from("reml.toml"));
+        }
+        Ok(opts)
+    }
+}
+
 #[derive(Debug, Clone)]
 struct ConfigDiffOptions {
     base_path: PathBuf,
@@ -1284,6 +1931,26 @@ fn guard_diag_to_report(diag: GuardDiagnostic) -> LintDiagnostic {
     }
 }
 
+fn codegen_fallback_to_report(
+    fallback: &CodegenFallback,
+    severity: DiagnosticSeverity,
+) -> LintDiagnostic {
+    let diagnostic = fallback.to_diagnostic();
+    let extensions = diagnostic
+        .extensions
+        .into_iter()
+        .map(|(key, value)| (key, Value::String(value)))
+        .collect();
+    LintDiagnostic {
+        code: diagnostic.code,
+        domain: diagnostic.domain,
+        severity: severity_label(severity).to_string(),
+        message: diagnostic.message,
+        extensions: Value::Object(extensions),
+        audit: Value::Object(Map::new()),
+    }
+}
+
 fn severity_label(severity: DiagnosticSeverity) -> &'static str {
     match severity {
         DiagnosticSeverity::Error => "error",
@@ -1390,6 +2057,12 @@ fn print_build_report(report: &BuildLintReport, format: ReportFormat) -> Result<
                     report.audit.len()
                 );
             }
+            for artifact in &report.artifacts {
+                println!(
+                    "[build] {} {} を生成しました（{} bytes）",
+                    artifact.target, artifact.path, artifact.bytes
+                );
+            }
         }
     }
     Ok(())
@@ -1421,6 +2094,141 @@ fn print_diff_report(report: &ConfigDiffReport, format: ReportFormat) -> Result<
     Ok(())
 }
 
+fn print_fmt_report(report: &FmtReport, format: ReportFormat) -> Result<(), CliError> {
+    match format {
+        ReportFormat::Json => {
+            let body = serde_json::to_string_pretty(report)?;
+            println!("{body}");
+        }
+        ReportFormat::Human => {
+            let label = if report.mode == "check" {
+                "整形が必要"
+            } else {
+                "整形しました"
+            };
+            for path in &report.changed_files {
+                println!("[fmt] {label}: {path}");
+            }
+            for failure in &report.failures {
+                println!(
+                    "[fmt] {} をスキップしました: {}",
+                    failure.path, failure.message
+                );
+            }
+            println!(
+                "[fmt] {} ファイル中 {} ファイルが{}",
+                report.stats.checked_files,
+                report.stats.changed_files,
+                if report.mode == "check" {
+                    "未整形です"
+                } else {
+                    "変更されました"
+                }
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_source_lint_report(
+    report: &SourceLintReport,
+    format: ReportFormat,
+) -> Result<(), CliError> {
+    match format {
+        ReportFormat::Json => {
+            let body = serde_json::to_string_pretty(report)?;
+            println!("{body}");
+        }
+        ReportFormat::Human => {
+            for diag in &report.diagnostics {
+                println!(
+                    "{}:{}:{}: {}[{}] {}",
+                    diag.path, diag.line, diag.column, diag.level, diag.code, diag.message
+                );
+            }
+            for path in &report.fixed_files {
+                println!("[lint] 修正を適用しました: {path}");
+            }
+            for failure in &report.failures {
+                println!(
+                    "[lint] {} をスキップしました: {}",
+                    failure.path, failure.message
+                );
+            }
+            println!(
+                "[lint] {} ファイルを検査: 警告 {} 件 / エラー {} 件",
+                report.stats.checked_files, report.stats.warnings, report.stats.errors
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_explanation(
+    explanation: &DiagnosticExplanation,
+    format: ReportFormat,
+) -> Result<(), CliError> {
+    match format {
+        ReportFormat::Json => {
+            let body = serde_json::to_string_pretty(&explanation.to_value())?;
+            println!("{body}");
+        }
+        ReportFormat::Human => {
+            println!("[{}] {}", explanation.code, explanation.title);
+            println!();
+            println!("{}", explanation.description);
+            println!();
+            println!("失敗例:");
+            print_indented(explanation.failing_example);
+            println!();
+            println!("修正例:");
+            print_indented(explanation.fixed_example);
+            if !explanation.spec_sections.is_empty() {
+                println!();
+                println!("関連仕様:");
+                for section in explanation.spec_sections {
+                    println!("  - {} §{}", section.path, section.section);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_explain_index(format: ReportFormat) -> Result<(), CliError> {
+    match format {
+        ReportFormat::Json => {
+            let entries = explanations()
+                .iter()
+                .map(|entry| {
+                    serde_json::json!({
+                        "code": entry.code,
+                        "title": entry.title,
+                    })
+                })
+                .collect::<Vec<_>>();
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        }
+        ReportFormat::Human => {
+            println!("[explain] 詳細解説が登録された診断コード:");
+            for entry in explanations() {
+                println!("  {:<36} {}", entry.code, entry.title);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_indented(body: &str) {
+    for line in body.lines() {
+        if line.is_empty() {
+            println!();
+        } else {
+            println!("    {line}");
+        }
+    }
+}
+
 fn print_diff_change(change: &ConfigChange) {
     let key = value_to_string(&change.key);
     match change.kind {
@@ -1481,6 +2289,46 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    fn build_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn build_options_route_codegen_flags_to_llvm_target() {
+        let opts = BuildLintOptions::parse(build_args(&[
+            "--target",
+            "llvm",
+            "--mir",
+            "main.mir.json",
+            "--no-strict-codegen",
+            "--debug-info",
+        ]))
+        .expect("llvm target");
+        assert_eq!(opts.target, Some(BuildTarget::Llvm));
+        assert_eq!(opts.llvm_options.strict_codegen, Some(false));
+        assert!(opts.llvm_options.debug_info);
+
+        let opts = BuildLintOptions::parse(build_args(&[
+            "--target",
+            "wasm32",
+            "--mir",
+            "main.mir.json",
+            "--strict-codegen",
+        ]))
+        .expect("wasm target");
+        assert!(opts.wasm_options.strict_codegen);
+
+        for args in [
+            &["--target", "wasm32", "--mir", "m.json", "--debug-info"][..],
+            &["--target", "llvm", "--mir", "m.json", "--plugin-abi"][..],
+        ] {
+            assert!(matches!(
+                BuildLintOptions::parse(build_args(args)),
+                Err(CliError::Usage(_))
+            ));
+        }
+    }
+
     #[test]
     fn flatten_config_tree_creates_dotted_keys() {
         let source = json!({
@@ -1679,6 +2527,9 @@ fn print_help() {
   new <path>           テンプレートから新規プロジェクトを生成\n\
   manifest dump         reml.toml を JSON へダンプ\n\
   build                reml.json の FFI セクションを検証\n\
+  explain <code>       診断コードの詳細解説（失敗例・修正例・関連仕様）を表示\n\
+  fmt [paths]          .reml ソースを整形（--check で差分の有無のみ報告）\n\
+  lint [paths]         .reml ソースへ lint を適用（--fix で修正候補を適用）\n\
   config lint           マニフェスト/スキーマを検証して JSON レポートを表示\n\
   config diff <old> <new>  JSON 設定ファイル同士の差分を ChangeSet 形式で出力"
     );
@@ -1726,12 +2577,53 @@ fn print_config_diff_help() {
     );
 }
 
+fn print_explain_help() {
+    eprintln!(
+        "使い方: remlc explain [<code>] [--format human|json]\n\n\
+        <code>               解説を表示する診断コード（省略時は登録済みコードの一覧）\n\
+        --format human|json  出力形式を切替（既定: human）"
+    );
+}
+
+fn print_fmt_help() {
+    eprintln!(
+        "使い方: remlc fmt [<path>...] [--check] [--manifest <path>] [--format human|json]\n\n\
+        <path>               整形するファイルまたはディレクトリ（既定: カレントディレクトリ）\n\
+        --check              ファイルを書き換えず、未整形のファイルがあれば終了コード 1 を返す\n\
+        --manifest <path>    [format] セクションを読み込む reml.toml（既定: ./reml.toml があれば使用）\n\
+        --format human|json  出力形式を切替（既定: human）"
+    );
+}
+
+fn print_lint_help() {
+    eprintln!(
+        "使い方: remlc lint [<path>...] [--fix] [--manifest <path>] [--identifier-profile unicode|ascii-compat] [--format human|json]\n\n\
+        <path>               検査するファイルまたはディレクトリ（既定: カレントディレクトリ）\n\
+        --fix                重ならない修正候補（fix-it）をファイルへ適用する\n\
+        --manifest <path>    [lint.rules] を読み込む reml.toml（既定: ./reml.toml があれば使用）\n\
+        --identifier-profile 命名規約の判定に用いる識別子プロファイル（既定: unicode）\n\
+        --format human|json  出力形式を切替（既定: human）\n\
+        deny レベルの診断が 1 件でもあれば終了コード 1 を返す。"
+    );
+}
+
 fn print_build_help() {
     eprintln!(
-        "使い方: remlc build [--config <path>] [--emit-bindgen] [--cache-dir <path>] [--format human|json]\n\n\
+        "使い方: remlc build [--config <path>] [--emit-bindgen] [--cache-dir <path>] [--format human|json]\n\
+        \x20      [--target wasm32 --mir <path> [--out <path>] [--emit-wat] [--strict-codegen] [--plugin-abi]]\n\
+        \x20      [--target llvm --mir <path> [--out <path>] [--strict-codegen|--no-strict-codegen] [--debug-info]]\n\n\
         --config <path>  読み込む reml.json（既定: ./reml.json）\n\
         --emit-bindgen  reml-bindgen を起動して生成を行う\n\
         --cache-dir <path>  生成キャッシュを格納するルートディレクトリ\n\
-        --format human|json  出力形式を切替（既定: json）"
+        --format human|json  出力形式を切替（既定: json）\n\
+        --target wasm32  MIR から WebAssembly モジュールを生成する\n\
+        --target llvm  MIR から x86_64 Linux 向けの LLVM IR（.ll）を生成する\n\
+        --mir <path>  入力 MIR JSON（reml_frontend --emit-mir の出力）\n\
+        --out <path>  出力する .wasm / .ll（既定: MIR と同じ場所の <name>.wasm / <name>.ll）\n\
+        --emit-wat  .wasm と同じ場所へ WAT テキストも書き出す\n\
+        --strict-codegen  未対応の式があれば生成を中断する（llvm は -O2 相当のため既定で有効）\n\
+        --no-strict-codegen  縮退箇所があっても生成を続ける\n\
+        --debug-info  DWARF デバッグ情報を付与する（llvm のみ。--debug-info 付きで出力した MIR が必要）\n\
+        --plugin-abi  プラグイン呼び出し ABI（alloc/dealloc と Str -> Str のアダプタ）を生成する"
     );
 }
@@ -0,0 +1,313 @@
+//! 診断コードの詳細解説レジストリ。
+//!
+//! `remlc explain <code>` の情報源であり、JSON 出力の `diagnostic.explain`
+//! 拡張や SARIF の `rule.help` からも参照される。各エントリの失敗例と修正例は
+//! `tests/diagnostic_explain.rs` で実際にフロントエンドへ通して検証する。
+
+use serde_json::{json, Value};
+
+/// `docs/spec/` 配下の関連節。`path` はリポジトリルートからの相対パス。
+#[derive(Debug, Clone, Copy)]
+pub struct SpecSection {
+    pub path: &'static str,
+    pub section: &'static str,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DiagnosticExplanation {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub failing_example: &'static str,
+    pub fixed_example: &'static str,
+    pub spec_sections: &'static [SpecSection],
+}
+
+impl DiagnosticExplanation {
+    /// 詳細解説を表示する CLI コマンド。
+    pub fn command(&self) -> String {
+        format!("remlc explain {}", self.code)
+    }
+
+    /// 診断 JSON / SARIF に埋め込むリンク情報。
+    pub fn link_value(&self) -> Value {
+        json!({
+            "code": self.code,
+            "command": self.command(),
+            "spec": spec_sections_value(self.spec_sections),
+        })
+    }
+
+    /// `remlc explain --format json` 向けの完全な表現。
+    pub fn to_value(&self) -> Value {
+        json!({
+            "code": self.code,
+            "title": self.title,
+            "description": self.description,
+            "failing_example": self.failing_example,
+            "fixed_example": self.fixed_example,
+            "spec": spec_sections_value(self.spec_sections),
+        })
+    }
+
+    /// SARIF `help.markdown` などで使う Markdown 表現。
+    pub fn to_markdown(&self) -> String {
+        let mut body = format!(
+            "## {}: {}\n\n{}\n\n### 失敗例\n\n```reml\n{}```\n\n### 修正例\n\n```reml\n{}```\n",
+            self.code, self.title, self.description, self.failing_example, self.fixed_example
+        );
+        if !self.spec_sections.is_empty() {
+            body.push_str("\n### 関連仕様\n\n");
+            for section in self.spec_sections {
+                body.push_str(&format!(
+                    "- [{}]({}) {}\n",
+                    section.path, section.path, section.section
+                ));
+            }
+        }
+        body
+    }
+}
+
+fn spec_sections_value(sections: &[SpecSection]) -> Value {
+    Value::Array(
+        sections
+            .iter()
+            .map(|section| {
+                json!({
+                    "path": section.path,
+                    "section": section.section,
+                })
+            })
+            .collect(),
+    )
+}
+
+pub fn find_explanation(code: &str) -> Option<&'static DiagnosticExplanation> {
+    explanations().iter().find(|entry| entry.code == code)
+}
+
+pub fn explanations() -> &'static [DiagnosticExplanation] {
+    const SYNTAX_PATTERN: SpecSection = SpecSection {
+        path: "docs/spec/1-1-syntax.md",
+        section: "C.3 パターン（束縛・`match` で共通）",
+    };
+    const TYPES_PATTERN: SpecSection = SpecSection {
+        path: "docs/spec/1-2-types-Inference.md",
+        section: "D. パターンの型付け",
+    };
+    const TYPES_ALIAS: SpecSection = SpecSection {
+        path: "docs/spec/1-2-types-Inference.md",
+        section: "A.4 型エイリアス & ニュータイプ",
+    };
+    const SYNTAX_DECLS: SpecSection = SpecSection {
+        path: "docs/spec/1-1-syntax.md",
+        section: "B.4 宣言の種類",
+    };
+    const FFI_CORE: SpecSection = SpecSection {
+        path: "docs/spec/3-9-core-async-ffi-unsafe.md",
+        section: "2. Core.Ffi の枠組み",
+    };
+
+    static REGISTRY: &[DiagnosticExplanation] = &[
+        DiagnosticExplanation {
+            code: "pattern.exhaustiveness.missing",
+            title: "match の網羅性が不足しています",
+            description: "match 式は対象型のすべての値を受け止める必要があります。合成型のコンストラクタや範囲に取りこぼしがあると、実行時に一致するアームが存在しない入力が生まれます。診断の `pattern.missing_variants` に列挙されたケースを追加するか、最後に `_` アームを置いてください。",
+            failing_example: "type Shape = | Circle(Int) | Square(Int)\n\nfn area(shape: Shape) -> Int =\n  match shape with\n  | Circle(r) -> r * r * 3\n",
+            fixed_example: "type Shape = | Circle(Int) | Square(Int)\n\nfn area(shape: Shape) -> Int =\n  match shape with\n  | Circle(r) -> r * r * 3\n  | Square(side) -> side * side\n",
+            spec_sections: &[
+                TYPES_PATTERN,
+                SpecSection {
+                    path: "docs/spec/1-2-types-Inference.md",
+                    section: "C.6.1 アクティブパターンの型付けと網羅性（ドラフト）",
+                },
+            ],
+        },
+        DiagnosticExplanation {
+            code: "pattern.unreachable_arm",
+            title: "到達不能なパターンがあります",
+            description: "match のアームは上から順に評価されます。先行するアーム（`_` や完全 Active Pattern など）が既に同じ入力を受け止めている場合、後続のアームは決して選ばれません。より具体的なアームを先に置くか、冗長なアームを削除してください。",
+            failing_example: "fn classify(n: Int) -> Int =\n  match n with\n  | _ -> 0\n  | 1..=9 -> 1\n",
+            fixed_example: "fn classify(n: Int) -> Int =\n  match n with\n  | 1..=9 -> 1\n  | _ -> 0\n",
+            spec_sections: &[SYNTAX_PATTERN, TYPES_PATTERN],
+        },
+        DiagnosticExplanation {
+            code: "pattern.range.bound_inverted",
+            title: "範囲パターンの上下限が逆転しています",
+            description: "範囲パターン `a..b` / `a..=b` は開始境界が終了境界以下である必要があります。逆転した範囲はどの値にも一致しないため、境界の順序を入れ替えてください。",
+            failing_example: "fn classify(n: Int) -> Int =\n  match n with\n  | 10..1 -> 1\n  | _ -> 0\n",
+            fixed_example: "fn classify(n: Int) -> Int =\n  match n with\n  | 1..10 -> 1\n  | _ -> 0\n",
+            spec_sections: &[SYNTAX_PATTERN],
+        },
+        DiagnosticExplanation {
+            code: "pattern.slice.multiple_rest",
+            title: "スライスパターンで `..` が多重に指定されています",
+            description: "スライスパターンの残余 `..` は 1 つのパターンにつき 1 回だけ書けます。複数あると各要素をどちらへ割り当てるかが一意に決まりません。先頭・末尾の要素を固定し、残余は 1 箇所にまとめてください。",
+            failing_example: "fn summarize(xs: Array<Int>) -> Int =\n  match xs with\n  | [..before, 0, ..after] -> 0\n  | _ -> 1\n",
+            fixed_example: "fn summarize(xs: Array<Int>) -> Int =\n  match xs with\n  | [0, ..rest] -> 0\n  | _ -> 1\n",
+            spec_sections: &[SYNTAX_PATTERN],
+        },
+        DiagnosticExplanation {
+            code: "pattern.binding.duplicate_name",
+            title: "パターン束縛が重複しています",
+            description: "1 つのパターン内で同じ名前を `@` / `as` と内側の束縛の両方に使うと、どちらの値を指すかが曖昧になります。全体と部分を別々の名前で束縛してください。",
+            failing_example: "fn describe(value: Option<Int>) -> Int =\n  match value with\n  | num @ Some(num) -> num\n  | None -> 0\n",
+            fixed_example: "fn describe(value: Option<Int>) -> Int =\n  match value with\n  | whole @ Some(num) -> num\n  | None -> 0\n",
+            spec_sections: &[SYNTAX_PATTERN],
+        },
+        DiagnosticExplanation {
+            code: "language.shadowing.unicode",
+            title: "Unicode 識別子の再束縛",
+            description: "Unicode 識別子は見た目の紛らわしさを避けるため、同じスコープでの再束縛を警告ポリシーで拒否します。値を更新したい場合は別名で束縛してください。",
+            failing_example: "let αβ = 1\nlet αβ = αβ + 1\n",
+            fixed_example: "let αβ = 1\nlet αβ2 = αβ + 1\n",
+            spec_sections: &[
+                SpecSection {
+                    path: "docs/spec/1-1-syntax.md",
+                    section: "A.3 識別子とキーワード",
+                },
+                SpecSection {
+                    path: "docs/spec/1-4-test-unicode-model.md",
+                    section: "K. セキュリティ・混乱回避",
+                },
+            ],
+        },
+        DiagnosticExplanation {
+            code: "ffi.varargs.missing_fixed_param",
+            title: "可変長引数の前に固定引数が必要です",
+            description: "C の可変長引数関数は `va_start` の基準として少なくとも 1 つの固定引数を必要とします。`extern` 宣言で `...` だけを受け取る関数は呼び出し規約上表現できないため、書式文字列などの固定引数を先頭に置いてください。",
+            failing_example: "extern \"C\" {\n  fn log_all(...) -> i32;\n}\n",
+            fixed_example: "extern \"C\" {\n  fn log_all(fmt: Ptr<u8>, ...) -> i32;\n}\n",
+            spec_sections: &[SYNTAX_DECLS, FFI_CORE],
+        },
+        DiagnosticExplanation {
+            code: "ffi.varargs.invalid_abi",
+            title: "可変長引数は extern \"C\" でのみ使用できます",
+            description: "可変長引数の受け渡し規則は C ABI でのみ定義されています。`\"system\"` など別の ABI で `...` を宣言するとバックエンドが正しい呼び出しを生成できないため、`extern \"C\"` ブロックへ移してください。",
+            failing_example: "extern \"system\" {\n  fn printf(fmt: Ptr<u8>, ...) -> i32;\n}\n",
+            fixed_example: "extern \"C\" {\n  fn printf(fmt: Ptr<u8>, ...) -> i32;\n}\n",
+            spec_sections: &[SYNTAX_DECLS, FFI_CORE],
+        },
+        DiagnosticExplanation {
+            code: "conductor.dsl_id.duplicate",
+            title: "conductor 内で dsl_id が重複しています",
+            description: "conductor ブロックの DSL 定義 `<dsl_id>: <parser>` は、チャネル接続や監査ログで dsl_id をキーとして参照されます。同じ dsl_id を複数回宣言すると参照先が決まらないため、各定義に一意な名前を付けてください。",
+            failing_example: "conductor docs_pipeline {\n  markdown: markdown_parser\n  markdown: html_parser\n}\n",
+            fixed_example: "conductor docs_pipeline {\n  markdown: markdown_parser\n  html: html_parser\n}\n",
+            spec_sections: &[
+                SpecSection {
+                    path: "docs/spec/1-1-syntax.md",
+                    section: "B.8 DSL制御ブロック `conductor`",
+                },
+                SpecSection {
+                    path: "docs/spec/1-1-syntax.md",
+                    section: "B.8.1 文法概略",
+                },
+            ],
+        },
+        DiagnosticExplanation {
+            code: "effects.unsafe.pure_violation",
+            title: "`@pure` 関数内の unsafe ブロック",
+            description: "`@pure` は関数が一切の効果を持たないことを宣言します。`unsafe` ブロックは `unsafe` 効果を導入するため、純粋関数の中では使用できません。`unsafe` を取り除くか、`@pure` を外して効果を許可してください。",
+            failing_example: "@pure\nfn answer() -> Int = unsafe { 42 }\n",
+            fixed_example: "fn answer() -> Int = unsafe { 42 }\n",
+            spec_sections: &[
+                SpecSection {
+                    path: "docs/spec/1-3-effects-safety.md",
+                    section: "C. 効果の宣言と抑制（属性）",
+                },
+                SpecSection {
+                    path: "docs/spec/1-3-effects-safety.md",
+                    section: "F. FFI と unsafe",
+                },
+                SpecSection {
+                    path: "docs/spec/1-1-syntax.md",
+                    section: "C.7 `unsafe` ブロック",
+                },
+            ],
+        },
+        DiagnosticExplanation {
+            code: "type.unresolved_ident",
+            title: "型参照が未解決です",
+            description: "型注釈や型エイリアスで参照した名前に対応する `type` 宣言・組み込み型が見つかりません。綴りを確認するか、対象の型を宣言・`use` してください。",
+            failing_example: "type Score = Missing\n",
+            fixed_example: "type Score = Int\n",
+            spec_sections: &[
+                TYPES_ALIAS,
+                SpecSection {
+                    path: "docs/spec/1-1-syntax.md",
+                    section: "B.2 可視性と `use` が導入するシンボル",
+                },
+            ],
+        },
+        DiagnosticExplanation {
+            code: "type.alias.cycle",
+            title: "型エイリアスが循環参照しています",
+            description: "型エイリアスは展開時に別名を置き換えるだけなので、互いを参照し合うと展開が終わりません。再帰的な構造が必要な場合は合成型（ADT）として宣言し、エイリアスの連鎖はいずれかで具体的な型に到達させてください。",
+            failing_example: "type A = B\ntype B = A\n",
+            fixed_example: "type A = B\ntype B = Int\n",
+            spec_sections: &[
+                TYPES_ALIAS,
+                SpecSection {
+                    path: "docs/spec/1-1-syntax.md",
+                    section: "E.2 代数的データ型（ADT）",
+                },
+            ],
+        },
+    ];
+    REGISTRY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    #[test]
+    fn codes_are_unique() {
+        let mut seen = HashSet::new();
+        for entry in explanations() {
+            assert!(seen.insert(entry.code), "重複したコード: {}", entry.code);
+        }
+    }
+
+    #[test]
+    fn spec_sections_point_to_existing_documents() {
+        let repo_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        for entry in explanations() {
+            assert!(
+                !entry.spec_sections.is_empty(),
+                "{} に関連仕様がありません",
+                entry.code
+            );
+            for section in entry.spec_sections {
+                let path = repo_root.join(section.path);
+                assert!(
+                    path.is_file(),
+                    "{} の関連仕様 {} が存在しません",
+                    entry.code,
+                    section.path
+                );
+                let body = std::fs::read_to_string(&path).expect("仕様書を読み込めるはず");
+                assert!(
+                    body.lines()
+                        .any(|line| line.starts_with('#') && line.contains(section.section)),
+                    "{} の節 `{}` が {} に見つかりません",
+                    entry.code,
+                    section.section,
+                    section.path
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn link_value_carries_command_and_spec() {
+        let entry = find_explanation("ffi.varargs.invalid_abi").expect("登録済みのはず");
+        let link = entry.link_value();
+        assert_eq!(link["command"], "remlc explain ffi.varargs.invalid_abi");
+        assert_eq!(link["spec"][0]["path"], "docs/spec/1-1-syntax.md");
+    }
+}
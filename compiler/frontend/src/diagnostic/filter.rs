@@ -229,6 +229,133 @@ impl FilterPattern {
     fn matches(&self, candidate: &str) -> bool {
         wildcard_match(&self.normalized, &candidate.to_ascii_lowercase())
     }
+
+    /// 完全一致かどうかと、ワイルドカード以外の文字数。
+    fn specificity(&self) -> (bool, usize) {
+        let literal = self
+            .normalized
+            .bytes()
+            .filter(|byte| *byte != b'*' && *byte != b'?')
+            .count();
+        (literal == self.normalized.len(), literal)
+    }
+}
+
+/// lint 規則の適用レベル（仕様 4-3 §2.2 の `allow` / `warn` / `deny`）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl LintLevel {
+    /// `warning` / `error` も `warn` / `deny` の別名として受け付ける。
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "allow" => Some(Self::Allow),
+            "warn" | "warning" => Some(Self::Warn),
+            "deny" | "error" => Some(Self::Deny),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+        }
+    }
+
+    /// 診断として報告する際の Severity。`allow` は報告しない。
+    pub fn severity(&self) -> Option<DiagnosticSeverity> {
+        match self {
+            LintLevel::Allow => None,
+            LintLevel::Warn => Some(DiagnosticSeverity::Warning),
+            LintLevel::Deny => Some(DiagnosticSeverity::Error),
+        }
+    }
+}
+
+/// lint ID（またはワイルドカードパターン）ごとのレベル上書き。
+///
+/// パターンは `--diagnostic-filter` と同じく `*` / `?` を解釈し、lint ID（`unused_binding`）と
+/// 診断コード（`lint.unused_binding`）のどちらにも照合する。複数のパターンが一致した場合は
+/// ワイルドカードを含まない完全一致、次いでリテラル部分の長いパターンを優先し、
+/// 同順位なら後から追加したものを採用する。
+#[derive(Clone, Debug, Default)]
+pub struct LintLevelMap {
+    entries: Vec<(FilterPattern, LintLevel)>,
+}
+
+impl LintLevelMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn insert(&mut self, pattern: impl Into<String>, level: LintLevel) {
+        self.entries.push((FilterPattern::new(pattern), level));
+    }
+
+    /// 後から適用する上書きを連結する。
+    pub fn extend(&mut self, other: &LintLevelMap) {
+        self.entries.extend(other.entries.iter().cloned());
+    }
+
+    pub fn parse_assignment(previous: Option<Self>, assignment: &str) -> Result<Self, String> {
+        let mut map = previous.unwrap_or_default();
+        let (key, value) = split_assignment(assignment)?;
+        let level = LintLevel::from_label(value).ok_or_else(|| {
+            format!("lint `{key}` のレベルは allow|warn|deny のいずれかを指定してください (入力値: {value})")
+        })?;
+        map.insert(key, level);
+        Ok(map)
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, String> {
+        let obj = value
+            .as_object()
+            .ok_or_else(|| "lint レベル設定はオブジェクトである必要があります".to_string())?;
+        let mut map = Self::default();
+        for (key, level) in obj {
+            let label = level
+                .as_str()
+                .ok_or_else(|| format!("lint `{key}` のレベルは文字列で指定してください"))?;
+            let level = LintLevel::from_label(label)
+                .ok_or_else(|| format!("lint `{key}` のレベル `{label}` は解釈できません"))?;
+            map.insert(key.clone(), level);
+        }
+        Ok(map)
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut map = Map::new();
+        for (pattern, level) in &self.entries {
+            map.insert(pattern.raw.clone(), json!(level.as_str()));
+        }
+        Value::Object(map)
+    }
+
+    /// `id` に適用するレベルを返す。一致するパターンがなければ `None`。
+    pub fn level_for(&self, id: &str) -> Option<LintLevel> {
+        let code = format!("lint.{id}");
+        let mut best: Option<((bool, usize), LintLevel)> = None;
+        for (pattern, level) in &self.entries {
+            if !pattern.matches(id) && !pattern.matches(&code) {
+                continue;
+            }
+            let rank = pattern.specificity();
+            if best.is_none_or(|(current, _)| rank >= current) {
+                best = Some((rank, *level));
+            }
+        }
+        best.map(|(_, level)| level)
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -610,4 +737,19 @@ mod tests {
         assert!(result.dropped);
         assert_eq!(diag["audit"], Value::Null);
     }
+
+    #[test]
+    fn lint_level_map_prefers_specific_patterns() {
+        let mut map = LintLevelMap::new();
+        map.insert("unused_binding", LintLevel::Deny);
+        map.insert("unused_*", LintLevel::Allow);
+        map.insert("lint.*", LintLevel::Warn);
+        assert_eq!(map.level_for("unused_binding"), Some(LintLevel::Deny));
+        assert_eq!(map.level_for("unused_import"), Some(LintLevel::Allow));
+        assert_eq!(map.level_for("needless_var"), Some(LintLevel::Warn));
+
+        let map = LintLevelMap::parse_assignment(Some(map), "needless_var=error").unwrap();
+        assert_eq!(map.level_for("needless_var"), Some(LintLevel::Deny));
+        assert!(LintLevelMap::parse_assignment(None, "needless_var=loud").is_err());
+    }
 }
@@ -0,0 +1,536 @@
+//! 診断コードをキーとする多言語メッセージカタログ。
+//!
+//! カタログ本体は `compiler/frontend/locales/diagnostics/<locale>.toml` に置き、
+//! 同梱ロケールはビルド時に埋め込む。値は `{ $name }` による引数補間と
+//! CLDR 複数形カテゴリによる分岐をサポートする。
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use thiserror::Error;
+
+/// 既存テンプレートの記述言語であり、最終フォールバック先となるロケール。
+pub const DEFAULT_LOCALE: &str = "ja-JP";
+
+const SHIPPED_SOURCES: &[(&str, &str)] = &[
+    (
+        "ja-JP",
+        include_str!("../../../locales/diagnostics/ja-JP.toml"),
+    ),
+    (
+        "en-US",
+        include_str!("../../../locales/diagnostics/en-US.toml"),
+    ),
+];
+
+static SHIPPED_CATALOGS: Lazy<Vec<MessageCatalog>> = Lazy::new(|| {
+    SHIPPED_SOURCES
+        .iter()
+        .map(|(locale, source)| {
+            MessageCatalog::parse(source).unwrap_or_else(|err| {
+                panic!("同梱メッセージカタログ `{locale}` の読み込みに失敗しました: {err}")
+            })
+        })
+        .collect()
+});
+
+#[derive(Debug, Error)]
+pub enum CatalogError {
+    #[error("メッセージカタログの TOML 解析に失敗しました: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("メッセージカタログ `{locale}` の `{code}` に `other` 形式がありません")]
+    MissingOtherForm { locale: String, code: String },
+}
+
+/// 1 ロケール分のメッセージカタログ。
+#[derive(Debug, Clone)]
+pub struct MessageCatalog {
+    locale: String,
+    name: Option<String>,
+    entries: BTreeMap<String, CatalogEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogFile {
+    meta: CatalogMeta,
+    #[serde(default)]
+    messages: BTreeMap<String, CatalogEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogMeta {
+    locale: String,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// 診断コード 1 件分の翻訳。
+#[derive(Debug, Clone, Deserialize)]
+pub struct CatalogEntry {
+    pub title: CatalogText,
+    pub message: CatalogText,
+    #[serde(default)]
+    pub detail: Option<CatalogText>,
+}
+
+/// 単一文字列、または複数形カテゴリごとの文字列。
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum CatalogText {
+    Plain(String),
+    Plural(PluralText),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluralText {
+    #[serde(default = "default_plural_selector")]
+    pub selector: String,
+    #[serde(default)]
+    pub zero: Option<String>,
+    #[serde(default)]
+    pub one: Option<String>,
+    #[serde(default)]
+    pub two: Option<String>,
+    #[serde(default)]
+    pub few: Option<String>,
+    #[serde(default)]
+    pub many: Option<String>,
+    #[serde(default)]
+    pub other: Option<String>,
+}
+
+fn default_plural_selector() -> String {
+    "count".to_string()
+}
+
+impl PluralText {
+    fn form(&self, category: PluralCategory) -> Option<&str> {
+        let selected = match category {
+            PluralCategory::Zero => self.zero.as_deref(),
+            PluralCategory::One => self.one.as_deref(),
+            PluralCategory::Two => self.two.as_deref(),
+            PluralCategory::Few => self.few.as_deref(),
+            PluralCategory::Many => self.many.as_deref(),
+            PluralCategory::Other => None,
+        };
+        selected.or(self.other.as_deref())
+    }
+}
+
+impl CatalogText {
+    fn render(&self, locale: &str, args: &MessageArgs) -> String {
+        let pattern = match self {
+            CatalogText::Plain(text) => text.as_str(),
+            CatalogText::Plural(plural) => {
+                let category = args
+                    .number(&plural.selector)
+                    .map(|value| plural_category(locale, value))
+                    .unwrap_or(PluralCategory::Other);
+                plural.form(category).unwrap_or_default()
+            }
+        };
+        interpolate(pattern, args)
+    }
+
+    fn has_other_form(&self) -> bool {
+        match self {
+            CatalogText::Plain(_) => true,
+            CatalogText::Plural(plural) => plural.other.is_some(),
+        }
+    }
+}
+
+impl MessageCatalog {
+    pub fn parse(source: &str) -> Result<Self, CatalogError> {
+        let file: CatalogFile = toml::from_str(source)?;
+        for (code, entry) in &file.messages {
+            let texts = [
+                Some(&entry.title),
+                Some(&entry.message),
+                entry.detail.as_ref(),
+            ];
+            if texts
+                .into_iter()
+                .flatten()
+                .any(|text| !text.has_other_form())
+            {
+                return Err(CatalogError::MissingOtherForm {
+                    locale: file.meta.locale.clone(),
+                    code: code.clone(),
+                });
+            }
+        }
+        Ok(Self {
+            locale: file.meta.locale,
+            name: file.meta.name,
+            entries: file.messages,
+        })
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn contains(&self, code: &str) -> bool {
+        self.entries.contains_key(code)
+    }
+
+    pub fn codes(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|code| code.as_str())
+    }
+
+    pub fn entry(&self, code: &str) -> Option<&CatalogEntry> {
+        self.entries.get(code)
+    }
+
+    pub fn title(&self, code: &str, args: &MessageArgs) -> Option<String> {
+        self.entry(code)
+            .map(|entry| entry.title.render(&self.locale, args))
+    }
+
+    pub fn message(&self, code: &str, args: &MessageArgs) -> Option<String> {
+        self.entry(code)
+            .map(|entry| entry.message.render(&self.locale, args))
+    }
+
+    pub fn detail(&self, code: &str, args: &MessageArgs) -> Option<String> {
+        self.entry(code)
+            .and_then(|entry| entry.detail.as_ref())
+            .map(|detail| detail.render(&self.locale, args))
+    }
+}
+
+/// 補間引数。数値引数は複数形カテゴリの選択にも使う。
+#[derive(Debug, Clone, Default)]
+pub struct MessageArgs {
+    values: BTreeMap<String, MessageArg>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageArg {
+    Text(String),
+    Number(i64),
+}
+
+impl fmt::Display for MessageArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageArg::Text(text) => write!(f, "{text}"),
+            MessageArg::Number(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl MessageArgs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.values
+            .insert(name.into(), MessageArg::Text(value.into()));
+        self
+    }
+
+    pub fn with_number(mut self, name: impl Into<String>, value: i64) -> Self {
+        self.values.insert(name.into(), MessageArg::Number(value));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MessageArg> {
+        self.values.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn number(&self, name: &str) -> Option<i64> {
+        match self.values.get(name)? {
+            MessageArg::Number(value) => Some(*value),
+            MessageArg::Text(text) => text.trim().parse().ok(),
+        }
+    }
+}
+
+/// `{ $name }` を引数で置換する。未知の引数はプレースホルダのまま残す。
+fn interpolate(pattern: &str, args: &MessageArgs) -> String {
+    let mut output = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        let after_open = &rest[open + 1..];
+        let Some(close) = after_open.find('}') else {
+            output.push_str(&rest[open..]);
+            return output;
+        };
+        let placeholder = &rest[open..open + close + 2];
+        let name = after_open[..close]
+            .trim()
+            .strip_prefix('$')
+            .map(|name| name.trim());
+        match name.and_then(|name| args.get(name)) {
+            Some(value) => output.push_str(&value.to_string()),
+            None => output.push_str(placeholder),
+        }
+        rest = &after_open[close + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// CLDR の複数形カテゴリ。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+/// 整数値に対する複数形カテゴリを言語サブタグから判定する。
+pub fn plural_category(locale: &str, value: i64) -> PluralCategory {
+    let language = locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let n = value.unsigned_abs();
+    match language.as_str() {
+        "ja" | "zh" | "ko" | "th" | "vi" | "id" => PluralCategory::Other,
+        "fr" | "pt" if n <= 1 => PluralCategory::One,
+        "fr" | "pt" => PluralCategory::Other,
+        "ru" | "uk" => {
+            let (m10, m100) = (n % 10, n % 100);
+            if m10 == 1 && m100 != 11 {
+                PluralCategory::One
+            } else if (2..=4).contains(&m10) && !(12..=14).contains(&m100) {
+                PluralCategory::Few
+            } else {
+                PluralCategory::Many
+            }
+        }
+        _ if n == 1 => PluralCategory::One,
+        _ => PluralCategory::Other,
+    }
+}
+
+/// 同梱カタログのロケール一覧（先頭が既定ロケール）。
+pub fn shipped_locales() -> impl Iterator<Item = &'static str> {
+    SHIPPED_SOURCES.iter().map(|(locale, _)| *locale)
+}
+
+pub fn shipped_catalogs() -> &'static [MessageCatalog] {
+    SHIPPED_CATALOGS.as_slice()
+}
+
+pub fn catalog(locale: &str) -> Option<&'static MessageCatalog> {
+    shipped_catalogs()
+        .iter()
+        .find(|catalog| catalog.locale.eq_ignore_ascii_case(locale))
+}
+
+pub fn default_catalog() -> &'static MessageCatalog {
+    catalog(DEFAULT_LOCALE).expect("既定ロケールのカタログは常に同梱される")
+}
+
+/// `en_US.UTF-8` や `en` のような指定を同梱ロケールへ対応付ける。
+/// `C`/`POSIX` や未対応言語は `None` を返す。
+pub fn negotiate_locale(requested: &str) -> Option<&'static str> {
+    let trimmed = requested.trim();
+    let base = trimmed
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-");
+    if base.is_empty() || base.eq_ignore_ascii_case("C") || base.eq_ignore_ascii_case("POSIX") {
+        return None;
+    }
+    if let Some(exact) = shipped_locales().find(|locale| locale.eq_ignore_ascii_case(&base)) {
+        return Some(exact);
+    }
+    let language = base.split('-').next().unwrap_or_default();
+    shipped_locales().find(|locale| {
+        locale
+            .split('-')
+            .next()
+            .is_some_and(|candidate| candidate.eq_ignore_ascii_case(language))
+    })
+}
+
+/// `LC_ALL` → `LC_MESSAGES` → `LANG` の順で環境からロケールを決定する。
+pub fn locale_from_env() -> Option<&'static str> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| env::var(key).ok())
+        .find(|value| !value.trim().is_empty())
+        .and_then(|value| negotiate_locale(&value))
+}
+
+/// CLI 指定（`--locale`）を優先し、環境変数、既定ロケールの順に解決する。
+pub fn resolve_locale(requested: Option<&str>) -> &'static str {
+    requested
+        .and_then(negotiate_locale)
+        .or_else(locale_from_env)
+        .unwrap_or(DEFAULT_LOCALE)
+}
+
+/// カタログから解決したメッセージ。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizedMessage {
+    pub locale: String,
+    pub title: String,
+    pub message: String,
+    pub detail: Option<String>,
+}
+
+/// 指定ロケールでコードを引き、欠落時は既定ロケールへフォールバックする。
+pub fn localize(code: &str, locale: &str, args: &MessageArgs) -> Option<LocalizedMessage> {
+    let catalog = catalog(locale)
+        .filter(|catalog| catalog.contains(code))
+        .unwrap_or_else(default_catalog);
+    let entry = catalog.entry(code)?;
+    Some(LocalizedMessage {
+        locale: catalog.locale.clone(),
+        title: entry.title.render(&catalog.locale, args),
+        message: entry.message.render(&catalog.locale, args),
+        detail: entry
+            .detail
+            .as_ref()
+            .map(|detail| detail.render(&catalog.locale, args)),
+    })
+}
+
+/// 既定ロケール以外の翻訳だけを返す。
+///
+/// 既定ロケールの本文はフロントエンド内の既存文言を正とするため、呼び出し側は
+/// `None` のとき元のメッセージを使い続ける。
+pub fn translate(code: &str, locale: &str, args: &MessageArgs) -> Option<LocalizedMessage> {
+    localize(code, locale, args).filter(|localized| localized.locale != DEFAULT_LOCALE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::messages::message_templates;
+
+    const CONFIG_CODES: &[&str] = &[
+        "config.missing_manifest",
+        "config.schema_mismatch",
+        "config.compat.unsupported",
+    ];
+
+    fn registered_codes() -> Vec<&'static str> {
+        message_templates()
+            .into_iter()
+            .map(|template| template.code)
+            .chain(CONFIG_CODES.iter().copied())
+            .collect()
+    }
+
+    #[test]
+    fn every_code_is_present_in_every_shipped_locale() {
+        let mut missing = Vec::new();
+        for catalog in shipped_catalogs() {
+            for code in registered_codes() {
+                if !catalog.contains(code) {
+                    missing.push(format!("{}: {code}", catalog.locale()));
+                }
+            }
+            for code in default_catalog().codes() {
+                if !catalog.contains(code) {
+                    missing.push(format!("{}: {code}", catalog.locale()));
+                }
+            }
+        }
+        missing.sort();
+        missing.dedup();
+        assert!(missing.is_empty(), "カタログに欠落があります: {missing:?}");
+    }
+
+    #[test]
+    fn default_catalog_matches_static_templates() {
+        let catalog = default_catalog();
+        let args = MessageArgs::new();
+        for template in message_templates() {
+            assert_eq!(
+                catalog.title(template.code, &args).as_deref(),
+                Some(template.title),
+                "{} の title が一致しません",
+                template.code
+            );
+            assert_eq!(
+                catalog.message(template.code, &args).as_deref(),
+                Some(template.message),
+                "{} の message が一致しません",
+                template.code
+            );
+        }
+    }
+
+    #[test]
+    fn interpolation_and_plural_rules_follow_locale() {
+        let one = MessageArgs::new()
+            .with_number("count", 1)
+            .with("variants", "None");
+        let many = MessageArgs::new()
+            .with_number("count", 2)
+            .with("variants", "None, Some(_)");
+        let en = localize("pattern.exhaustiveness.missing", "en-US", &many).expect("en-US entry");
+        assert_eq!(en.detail.as_deref(), Some("2 missing cases: None, Some(_)"));
+        let en_one =
+            localize("pattern.exhaustiveness.missing", "en-US", &one).expect("en-US entry");
+        assert_eq!(en_one.detail.as_deref(), Some("Missing case: None"));
+        let ja = localize("pattern.exhaustiveness.missing", "ja-JP", &one).expect("ja-JP entry");
+        assert_eq!(ja.detail.as_deref(), Some("未処理のケース（1 件）: None"));
+    }
+
+    #[test]
+    fn unknown_placeholders_are_preserved() {
+        let rendered = interpolate("expected { $expected } got {$actual}", &MessageArgs::new());
+        assert_eq!(rendered, "expected { $expected } got {$actual}");
+        let args = MessageArgs::new().with("expected", "1.0");
+        assert_eq!(interpolate("v{ $expected }", &args), "v1.0");
+    }
+
+    #[test]
+    fn negotiates_posix_and_bcp47_locale_names() {
+        assert_eq!(negotiate_locale("en_US.UTF-8"), Some("en-US"));
+        assert_eq!(negotiate_locale("en-GB"), Some("en-US"));
+        assert_eq!(negotiate_locale("ja"), Some("ja-JP"));
+        assert_eq!(negotiate_locale("C.UTF-8"), None);
+        assert_eq!(negotiate_locale("de-DE"), None);
+        assert_eq!(resolve_locale(Some("en")), "en-US");
+    }
+
+    #[test]
+    fn missing_translation_falls_back_to_default_locale() {
+        let localized = localize("pattern.unreachable_arm", "fr-FR", &MessageArgs::new())
+            .expect("fallback entry");
+        assert_eq!(localized.locale, DEFAULT_LOCALE);
+    }
+
+    #[test]
+    fn plural_forms_require_other() {
+        let source = r#"
+[meta]
+locale = "en-US"
+
+[messages."sample.code"]
+title = "t"
+message = { one = "only one" }
+"#;
+        assert!(matches!(
+            MessageCatalog::parse(source),
+            Err(CatalogError::MissingOtherForm { .. })
+        ));
+    }
+}
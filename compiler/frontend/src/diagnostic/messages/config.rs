@@ -1,5 +1,6 @@
 //! Config/Data 系の診断テンプレートとメタデータ補助。
 
+use super::catalog::{localize, MessageArgs};
 use crate::{
     diagnostic::{DiagnosticDomain, DiagnosticSeverity, FrontendDiagnostic},
     error::Recoverability,
@@ -67,8 +68,9 @@ impl ConfigDiagnosticMetadata {
 }
 
 /// `reml.toml` が見つからない場合の共通診断。
-pub fn missing_manifest(metadata: ConfigDiagnosticMetadata) -> FrontendDiagnostic {
-    let mut diagnostic = FrontendDiagnostic::new("`reml.toml` を検出できませんでした")
+pub fn missing_manifest(metadata: ConfigDiagnosticMetadata, locale: &str) -> FrontendDiagnostic {
+    let message = config_message("config.missing_manifest", locale, &MessageArgs::new());
+    let mut diagnostic = FrontendDiagnostic::new(message)
         .with_code("config.missing_manifest")
         .with_severity(DiagnosticSeverity::Error)
         .with_domain(DiagnosticDomain::Config)
@@ -82,13 +84,16 @@ pub fn schema_mismatch(
     expected: impl Into<String>,
     actual: impl Into<String>,
     metadata: ConfigDiagnosticMetadata,
+    locale: &str,
 ) -> FrontendDiagnostic {
     let expected_value = expected.into();
     let actual_value = actual.into();
-    let message = format!(
-        "Schema バージョンが一致しません（期待値: {expected}, 入力: {actual}）",
-        expected = expected_value.as_str(),
-        actual = actual_value.as_str()
+    let message = config_message(
+        "config.schema_mismatch",
+        locale,
+        &MessageArgs::new()
+            .with("expected", expected_value.as_str())
+            .with("actual", actual_value.as_str()),
     );
     let mut diagnostic = FrontendDiagnostic::new(message)
         .with_code("config.schema_mismatch")
@@ -113,15 +118,18 @@ pub fn compatibility_unsupported(
     profile_label: impl Into<String>,
     reason: impl Into<String>,
     metadata: ConfigDiagnosticMetadata,
+    locale: &str,
 ) -> FrontendDiagnostic {
     let format_value = format_label.into();
     let profile_value = profile_label.into();
     let reason_value = reason.into();
-    let message = format!(
-        "{format} 互換プロファイル `{profile}` は未サポートです: {reason}",
-        format = format_value.as_str(),
-        profile = profile_value.as_str(),
-        reason = reason_value.as_str()
+    let message = config_message(
+        "config.compat.unsupported",
+        locale,
+        &MessageArgs::new()
+            .with("format", format_value.as_str())
+            .with("profile", profile_value.as_str())
+            .with("reason", reason_value.as_str()),
     );
     let mut diagnostic = FrontendDiagnostic::new(message)
         .with_code("config.compat.unsupported")
@@ -144,6 +152,13 @@ pub fn compatibility_unsupported(
     diagnostic
 }
 
+/// Config 診断は要求ロケールのカタログから本文を組み立てる（欠落時は既定ロケール）。
+fn config_message(code: &str, locale: &str, args: &MessageArgs) -> String {
+    localize(code, locale, args)
+        .map(|localized| localized.message)
+        .unwrap_or_else(|| code.to_string())
+}
+
 fn apply_config_metadata(diag: &mut FrontendDiagnostic, metadata: &ConfigDiagnosticMetadata) {
     let mut config_extension = take_config_extension(diag);
     if let Some(path) = metadata.manifest_path.as_ref() {
@@ -205,7 +220,10 @@ fn store_config_extension(diag: &mut FrontendDiagnostic, map: Map<String, Value>
 
 #[cfg(test)]
 mod tests {
-    use super::{missing_manifest, ConfigDiagnosticMetadata};
+    use super::{
+        compatibility_unsupported, missing_manifest, schema_mismatch, ConfigDiagnosticMetadata,
+    };
+    use crate::diagnostic::messages::DEFAULT_LOCALE;
     use serde_json::json;
     use std::path::PathBuf;
 
@@ -216,7 +234,7 @@ mod tests {
             .with_source("cli")
             .with_profile("strict")
             .with_compatibility(json!({ "format": "toml", "stage": "beta" }));
-        let diag = missing_manifest(metadata);
+        let diag = missing_manifest(metadata, DEFAULT_LOCALE);
         assert_eq!(diag.code.as_deref(), Some("config.missing_manifest"));
         let config_extension = diag
             .extensions
@@ -239,4 +257,35 @@ mod tests {
             .and_then(|v| v.get("format"))
             .is_some());
     }
+
+    #[test]
+    fn schema_mismatch_interpolates_versions_from_catalog() {
+        let diag = schema_mismatch(
+            "1.2",
+            "1.0",
+            ConfigDiagnosticMetadata::new(),
+            DEFAULT_LOCALE,
+        );
+        assert_eq!(
+            diag.message,
+            "Schema バージョンが一致しません（期待値: 1.2, 入力: 1.0）"
+        );
+    }
+
+    #[test]
+    fn config_diagnostics_follow_requested_locale() {
+        let diag = compatibility_unsupported(
+            "JSON",
+            "json5",
+            "trailing comma",
+            ConfigDiagnosticMetadata::new(),
+            "en-US",
+        );
+        assert_eq!(
+            diag.message,
+            "JSON compatibility profile `json5` is not supported: trailing comma"
+        );
+        let diag = missing_manifest(ConfigDiagnosticMetadata::new(), "en-US");
+        assert_eq!(diag.message, "Could not find `reml.toml`");
+    }
 }
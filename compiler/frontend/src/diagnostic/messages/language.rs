@@ -74,6 +74,12 @@ pub fn language_messages() -> &'static [LanguageDiagnosticMessage] {
             message: "合成型コンストラクタへ渡した引数の数が期待と一致しません。",
             severity: DiagnosticSeverity::Error,
         },
+        LanguageDiagnosticMessage {
+            code: "typeck.tailrec.non_tail_call",
+            title: "自己呼び出しが末尾位置にありません",
+            message: "@tailrec 関数の自己呼び出しは末尾位置に置く必要があります。",
+            severity: DiagnosticSeverity::Error,
+        },
     ];
     REGISTRY
 }
@@ -94,5 +100,6 @@ mod tests {
         assert!(find_language_message("type.alias.cycle").is_some());
         assert!(find_language_message("type.alias.expansion_limit").is_some());
         assert!(find_language_message("type.sum.constructor_arity_mismatch").is_some());
+        assert!(find_language_message("typeck.tailrec.non_tail_call").is_some());
     }
 }
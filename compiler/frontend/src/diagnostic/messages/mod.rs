@@ -1,9 +1,14 @@
 //! 診断メッセージのテンプレート郡。
 
+pub mod catalog;
 pub mod config;
 pub mod language;
 pub mod pattern;
 
+pub use catalog::{
+    localize, resolve_locale, translate, LocalizedMessage, MessageArgs, MessageCatalog,
+    DEFAULT_LOCALE,
+};
 pub use config::{
     compatibility_unsupported, missing_manifest, schema_mismatch, ConfigDiagnosticMetadata,
 };
@@ -45,3 +50,16 @@ pub fn find_message(code: &str) -> Option<DiagnosticMessageTemplate> {
         .map(DiagnosticMessageTemplate::from_pattern)
         .or_else(|| find_language_message(code).map(DiagnosticMessageTemplate::from_language))
 }
+
+/// 登録済みテンプレートを Pattern → Language の順で列挙する。
+pub fn message_templates() -> Vec<DiagnosticMessageTemplate> {
+    pattern_messages()
+        .iter()
+        .map(DiagnosticMessageTemplate::from_pattern)
+        .chain(
+            language_messages()
+                .iter()
+                .map(DiagnosticMessageTemplate::from_language),
+        )
+        .collect()
+}
@@ -1,6 +1,7 @@
 //! フロントエンド診断モジュールのエントリーポイント。
 
 pub mod effects;
+pub mod explain;
 pub mod filter;
 pub mod formatter;
 pub mod json;
@@ -5,6 +5,7 @@
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 
+use super::messages::{self, MessageArgs};
 use super::{EXPECTED_EMPTY_HUMANIZED, PARSE_EXPECTED_EMPTY_KEY, PARSE_EXPECTED_KEY};
 
 /// Recover で提示する期待トークンの分類。
@@ -98,6 +99,29 @@ impl ExpectedToken {
         }
     }
 
+    /// `quoted_label` の翻訳版。固有名（トークンや規則名）はそのまま残す。
+    fn localized_label(&self, locale: &str) -> String {
+        let (code, args) = match self {
+            ExpectedToken::Eof => ("parse.expected.eof", MessageArgs::new()),
+            ExpectedToken::Not(value) => (
+                "parse.expected.not",
+                MessageArgs::new().with("value", value.as_str()),
+            ),
+            ExpectedToken::TypeExpected(ty) => (
+                "parse.expected.type",
+                MessageArgs::new().with("type", ty.as_str()),
+            ),
+            ExpectedToken::TraitBound(trait_bound) => (
+                "parse.expected.trait_bound",
+                MessageArgs::new().with("trait", trait_bound.as_str()),
+            ),
+            _ => return self.quoted_label(),
+        };
+        messages::translate(code, locale, &args)
+            .map(|localized| localized.message)
+            .unwrap_or_else(|| self.quoted_label())
+    }
+
     fn cmp_for_sort(&self, other: &Self) -> Ordering {
         match self.priority().cmp(&other.priority()) {
             Ordering::Equal => self.raw_label().cmp(other.raw_label()),
@@ -253,6 +277,28 @@ impl ExpectedTokensSummary {
         !self.alternatives.is_empty()
     }
 
+    /// 既定ロケール以外では `humanized` をカタログの文言で組み直す。
+    pub fn localize_humanized(&mut self, locale: &str) {
+        let (code, args) = if self.alternatives.is_empty() {
+            (PARSE_EXPECTED_EMPTY_KEY, MessageArgs::new())
+        } else {
+            let labels = self
+                .alternatives
+                .iter()
+                .map(|token| token.localized_label(locale))
+                .collect::<Vec<_>>();
+            (
+                PARSE_EXPECTED_KEY,
+                MessageArgs::new()
+                    .with_number("count", labels.len() as i64)
+                    .with("tokens", labels.join(", ")),
+            )
+        };
+        if let Some(localized) = messages::translate(code, locale, &args) {
+            self.humanized = Some(localized.message);
+        }
+    }
+
     pub fn merge_with(&mut self, other: &ExpectedTokensSummary) {
         if self.alternatives.is_empty() {
             self.alternatives = other.alternatives.clone();
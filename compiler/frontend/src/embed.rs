@@ -0,0 +1,221 @@
+//! 埋め込み API（`reml_embed.h`）向けのモジュールコンパイラ。
+//!
+//! ランタイムの `reml_load_module` に渡されたソースを構文解析・型検査し、MIR を wasm
+//! バックエンドで `.wasm` へ変換する。エクスポートと extern のシグネチャは、MIR の型ラベルが
+//! レコードのフィールド名を失うため AST の型注釈から組み立てる。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use reml_llvm_backend::load_monomorphized_mir_from_json_str;
+use reml_runtime::embedding::{
+    set_embed_module_compiler, EmbedCompiledModule, EmbedDiagnostic, EmbedFunction,
+    EmbedModuleCompiler, RemlEmbedStatus, EMBED_COMPILER_ALREADY_SET_CODE,
+};
+use reml_wasm_backend::{emit_wasm_module, WasmEmitOptions};
+
+use crate::diagnostic::DiagnosticSeverity;
+use crate::parser::ast::{
+    DeclKind, FunctionSignature, Module, Param, TypeAnnot, TypeDeclBody, TypeKind,
+};
+use crate::parser::ParserDriver;
+use crate::span::Span;
+use crate::typeck::{TypecheckConfig, TypecheckDriver};
+
+const PARSE_FAILED_CODE: &str = "native.embed.parse_failed";
+const CODEGEN_FAILED_CODE: &str = "native.embed.codegen_failed";
+const DEFAULT_MODULE_NAME: &str = "embed";
+
+/// フロントエンドを埋め込みモジュールコンパイラとして登録する。登録済みなら何もしない。
+pub fn install_embed_module_compiler() -> Result<(), EmbedDiagnostic> {
+    match set_embed_module_compiler(Arc::new(FrontendEmbedCompiler)) {
+        Err(err) if err.code != EMBED_COMPILER_ALREADY_SET_CODE => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// C ホスト向けの登録関数。`reml_load_module` より前に 1 度呼び出す。
+#[no_mangle]
+pub extern "C" fn reml_embed_install_frontend() -> RemlEmbedStatus {
+    match install_embed_module_compiler() {
+        Ok(()) => RemlEmbedStatus::Ok,
+        Err(_) => RemlEmbedStatus::Error,
+    }
+}
+
+/// ソースから wasm モジュールを生成するコンパイラ。縮退箇所は許容せずエラーにする。
+pub struct FrontendEmbedCompiler;
+
+impl EmbedModuleCompiler for FrontendEmbedCompiler {
+    fn compile(&self, source: &str) -> Result<EmbedCompiledModule, Vec<EmbedDiagnostic>> {
+        let parsed = ParserDriver::parse(source);
+        let errors: Vec<EmbedDiagnostic> = parsed
+            .diagnostics
+            .iter()
+            .filter(|diag| diag.severity_or_default() == DiagnosticSeverity::Error)
+            .map(|diag| {
+                embed_diagnostic(
+                    diag.code.as_deref().unwrap_or(PARSE_FAILED_CODE),
+                    &diag.message,
+                    diag.primary_span(),
+                )
+            })
+            .collect();
+        let module = match parsed.value {
+            Some(module) if errors.is_empty() => module,
+            _ if errors.is_empty() => {
+                return Err(vec![EmbedDiagnostic::new(
+                    PARSE_FAILED_CODE,
+                    "モジュールを構築できませんでした",
+                )])
+            }
+            _ => return Err(errors),
+        };
+
+        let report = TypecheckDriver::infer_module(Some(&module), &TypecheckConfig::default());
+        if !report.violations.is_empty() {
+            return Err(report
+                .violations
+                .iter()
+                .map(|violation| {
+                    embed_diagnostic(violation.code, &violation.message, violation.span)
+                })
+                .collect());
+        }
+
+        let codegen_failed =
+            |message: String| vec![EmbedDiagnostic::new(CODEGEN_FAILED_CODE, message)];
+        let json = serde_json::to_string(&report.mir)
+            .map_err(|err| codegen_failed(format!("MIR を直列化できません: {err}")))?;
+        let loaded = load_monomorphized_mir_from_json_str(&json)
+            .map_err(|err| codegen_failed(err.to_string()))?;
+        let module_name = module
+            .header
+            .as_ref()
+            .map(|header| header.path.render())
+            .unwrap_or_else(|| DEFAULT_MODULE_NAME.to_string());
+        let artifact = emit_wasm_module(
+            &module_name,
+            &loaded.functions,
+            &WasmEmitOptions::new().with_strict_codegen(true),
+        )
+        .map_err(|err| codegen_failed(err.to_string()))?;
+
+        let (exports, imports) = embed_functions(&module);
+        Ok(EmbedCompiledModule {
+            wasm: artifact.binary,
+            exports,
+            imports,
+        })
+    }
+}
+
+fn embed_diagnostic(code: &str, message: &str, span: Option<Span>) -> EmbedDiagnostic {
+    let diagnostic = EmbedDiagnostic::new(code, message);
+    match span {
+        Some(span) => diagnostic.with_span(span.start as usize, span.end as usize),
+        None => diagnostic,
+    }
+}
+
+/// トップレベル関数と extern 宣言のうち、境界を越えられる型だけを持つものを列挙する。
+fn embed_functions(module: &Module) -> (Vec<EmbedFunction>, Vec<EmbedFunction>) {
+    let aliases: HashMap<&str, &TypeAnnot> = module
+        .decls
+        .iter()
+        .filter_map(|decl| match &decl.kind {
+            DeclKind::Type { decl } if decl.generics.is_empty() => match &decl.body {
+                Some(TypeDeclBody::Alias { ty }) => Some((decl.name.name.as_str(), ty)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let renderer = SignatureRenderer { aliases };
+
+    let exports = module
+        .functions
+        .iter()
+        .filter(|function| function.generics.is_empty())
+        .filter_map(|function| {
+            let signature = renderer.signature(&function.params, function.ret_type.as_ref())?;
+            Some(EmbedFunction::new(function.name.name.clone(), signature))
+        })
+        .collect();
+    let imports = module
+        .decls
+        .iter()
+        .filter_map(|decl| match &decl.kind {
+            DeclKind::Extern { functions, .. } => Some(functions),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|item| {
+            let FunctionSignature {
+                name,
+                params,
+                ret_type,
+                varargs: false,
+                ..
+            } = &item.signature
+            else {
+                return None;
+            };
+            let signature = renderer.signature(params, ret_type.as_ref())?;
+            Some(EmbedFunction::new(name.name.clone(), signature))
+        })
+        .collect();
+    (exports, imports)
+}
+
+/// 型注釈を `(Int, {x: Float}) -> [Str]` 形式へ変換する。
+struct SignatureRenderer<'a> {
+    aliases: HashMap<&'a str, &'a TypeAnnot>,
+}
+
+impl SignatureRenderer<'_> {
+    fn signature(&self, params: &[Param], ret: Option<&TypeAnnot>) -> Option<String> {
+        let params = params
+            .iter()
+            .map(|param| self.render(param.type_annotation.as_ref()?, 0))
+            .collect::<Option<Vec<_>>>()?;
+        let ret = match ret {
+            Some(ret) => self.render(ret, 0)?,
+            None => "Unit".to_string(),
+        };
+        Some(format!("({}) -> {ret}", params.join(", ")))
+    }
+
+    fn render(&self, ty: &TypeAnnot, depth: usize) -> Option<String> {
+        // 循環する別名を展開し続けないよう、深さで打ち切る。
+        if depth > 16 {
+            return None;
+        }
+        match &ty.kind {
+            TypeKind::Ident { name } => match name.name.as_str() {
+                "Int" | "i64" => Some("Int".to_string()),
+                "Float" | "f64" => Some("Float".to_string()),
+                "Bool" => Some("Bool".to_string()),
+                "Str" | "String" => Some("Str".to_string()),
+                "Unit" => Some("Unit".to_string()),
+                other => self.render(self.aliases.get(other)?, depth + 1),
+            },
+            TypeKind::Tuple { elements } if elements.is_empty() => Some("Unit".to_string()),
+            TypeKind::Slice { element } => Some(format!("[{}]", self.render(element, depth + 1)?)),
+            TypeKind::Record { fields } => {
+                // 線形メモリ上のレコードはフィールド名順に並ぶため、シグネチャもその順にする。
+                let mut fields: Vec<_> = fields.iter().collect();
+                fields.sort_by(|lhs, rhs| lhs.label.name.cmp(&rhs.label.name));
+                let fields = fields
+                    .into_iter()
+                    .map(|field| {
+                        let ty = self.render(&field.ty, depth + 1)?;
+                        Some(format!("{}: {ty}", field.label.name))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(format!("{{{}}}", fields.join(", ")))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! 字句トークン列とトリビアからロスレスな CST を構築する。
+//!
+//! ノード構成は `source_file` → `item`（トップレベルの宣言単位）→ 括弧ノード
+//! (`paren` / `bracket` / `brace`) → `token` の階層とし、各トークンノードに
+//! 空白・コメントをトリビアとして付与する。同じ行でトークンに続くコメントは
+//! 直前トークンの `trivia_trailing` に、独立した行のコメントは直後トークンの
+//! `trivia_leading` に付く。全トリビアを保持するため、`text::cst_printer` で
+//! 出力すると入力と同一の文字列に戻る。
+
+use reml_runtime::parse::{CstChild, CstNode, CstToken, InputPosition, Span, Trivia, TriviaKind};
+use reml_runtime::text::String as TextString;
+
+use crate::token::{Token, TokenKind};
+
+/// 行頭に置かれると、インデントにかかわらず直前の文の継続行とみなすトークン種別。
+pub(super) fn continues_from_line_start(kind: TokenKind) -> bool {
+    use TokenKind::*;
+    matches!(
+        kind,
+        Bar | PipeForward
+            | ChannelPipe
+            | Dot
+            | Arrow
+            | DoubleArrow
+            | Assign
+            | ColonAssign
+            | Plus
+            | Star
+            | Slash
+            | Percent
+            | Caret
+            | EqEq
+            | NotEqual
+            | Le
+            | Ge
+            | LogicalAnd
+            | LogicalOr
+            | Question
+            | KeywordWith
+            | KeywordWhen
+            | KeywordThen
+    )
+}
+
+/// 括弧の種類に対応する CST ノード名。
+fn delimiter_node_kind(open: TokenKind) -> Option<&'static str> {
+    match open {
+        TokenKind::LParen => Some("paren"),
+        TokenKind::LBracket => Some("bracket"),
+        TokenKind::LBrace => Some("brace"),
+        _ => None,
+    }
+}
+
+fn closing_delimiter(open: TokenKind) -> Option<TokenKind> {
+    match open {
+        TokenKind::LParen => Some(TokenKind::RParen),
+        TokenKind::LBracket => Some(TokenKind::RBracket),
+        TokenKind::LBrace => Some(TokenKind::RBrace),
+        _ => None,
+    }
+}
+
+/// バイトオフセットから行・桁を求めるための索引。
+struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(source: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            source
+                .bytes()
+                .enumerate()
+                .filter(|(_, byte)| *byte == b'\n')
+                .map(|(idx, _)| idx + 1),
+        );
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    fn position(&self, byte: usize) -> InputPosition {
+        let line = self.line_starts.partition_point(|start| *start <= byte);
+        let line_start = self.line_starts[line - 1];
+        let column = self.source[line_start..byte].chars().count() + 1;
+        InputPosition { byte, line, column }
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(self.position(start), self.position(end))
+    }
+}
+
+/// トークン間の空白・コメントをトリビア列へ分解する。
+fn split_trivia(index: &LineIndex<'_>, start: usize, end: usize) -> Vec<Trivia> {
+    let bytes = index.source.as_bytes();
+    let mut pieces = Vec::new();
+    let mut cursor = start;
+    while cursor < end {
+        let piece_start = cursor;
+        let kind = if bytes[cursor..end].starts_with(b"//") {
+            while cursor < end && bytes[cursor] != b'\n' {
+                cursor += 1;
+            }
+            TriviaKind::Comment
+        } else if bytes[cursor..end].starts_with(b"/*") {
+            let mut depth = 0usize;
+            while cursor < end {
+                if bytes[cursor..end].starts_with(b"/*") {
+                    depth += 1;
+                    cursor += 2;
+                } else if bytes[cursor..end].starts_with(b"*/") {
+                    depth -= 1;
+                    cursor += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    cursor += 1;
+                }
+            }
+            TriviaKind::Comment
+        } else {
+            while cursor < end
+                && !bytes[cursor..end].starts_with(b"//")
+                && !bytes[cursor..end].starts_with(b"/*")
+            {
+                cursor += 1;
+            }
+            TriviaKind::Whitespace
+        };
+        let cursor_end = cursor.min(end);
+        pieces.push(Trivia {
+            kind,
+            text: TextString::from(&index.source[piece_start..cursor_end]),
+            span: index.span(piece_start, cursor_end),
+        });
+        cursor = cursor_end;
+    }
+    pieces
+}
+
+/// 直前トークンと同じ行に続くコメントを末尾トリビアとして切り出す。
+///
+/// 改行を含まない区間では切り出さず、すべて直後トークンの先頭トリビアとする。
+fn split_trailing(pieces: Vec<Trivia>) -> (Vec<Trivia>, Vec<Trivia>) {
+    let newline_at = pieces.iter().position(|piece| {
+        piece.kind == TriviaKind::Whitespace && piece.text.as_str().contains('\n')
+    });
+    let Some(newline_at) = newline_at else {
+        return (Vec::new(), pieces);
+    };
+    let trailing_len = pieces[..newline_at]
+        .iter()
+        .rposition(|piece| piece.kind == TriviaKind::Comment)
+        .map_or(0, |idx| idx + 1);
+    let mut leading = pieces;
+    let trailing = leading.drain(..trailing_len).collect();
+    (trailing, leading)
+}
+
+fn token_node(source: &str, index: &LineIndex<'_>, token: &Token) -> CstNode {
+    let (start, end) = (token.span.start as usize, token.span.end as usize);
+    let span = index.span(start, end);
+    CstNode {
+        kind: TextString::from("token"),
+        children: vec![CstChild::Token(CstToken {
+            kind: TextString::from(format!("{:?}", token.kind)),
+            text: TextString::from(&source[start..end]),
+            span: span.clone(),
+        })],
+        trivia_leading: Vec::new(),
+        trivia_trailing: Vec::new(),
+        span,
+    }
+}
+
+fn node(kind: &str, children: Vec<CstChild>, span: Span) -> CstNode {
+    CstNode {
+        kind: TextString::from(kind),
+        children,
+        trivia_leading: Vec::new(),
+        trivia_trailing: Vec::new(),
+        span,
+    }
+}
+
+/// 構築途中の括弧ノード。
+struct OpenDelimiter {
+    kind: TokenKind,
+    start: usize,
+    children: Vec<CstChild>,
+}
+
+/// `lex_source` のトークン列（`EndOfFile` を含んでもよい）から CST を構築する。
+pub fn build_cst(source: &str, tokens: &[Token]) -> CstNode {
+    let index = LineIndex::new(source);
+    let tokens: Vec<&Token> = tokens
+        .iter()
+        .filter(|token| token.kind != TokenKind::EndOfFile)
+        .collect();
+
+    // トリビアを各トークンへ割り当てる。
+    let mut nodes: Vec<CstNode> = Vec::with_capacity(tokens.len());
+    let mut cursor = 0usize;
+    for token in tokens.iter() {
+        let pieces = split_trivia(&index, cursor, token.span.start as usize);
+        let mut node = token_node(source, &index, token);
+        node.trivia_leading = match nodes.last_mut() {
+            Some(previous) => {
+                let (trailing, leading) = split_trailing(pieces);
+                previous.trivia_trailing = trailing;
+                leading
+            }
+            None => pieces,
+        };
+        nodes.push(node);
+        cursor = token.span.end as usize;
+    }
+    let pieces = split_trivia(&index, cursor, source.len());
+    let rest = match nodes.last_mut() {
+        Some(last) => {
+            let (trailing, rest) = split_trailing(pieces);
+            last.trivia_trailing = trailing;
+            rest
+        }
+        None => pieces,
+    };
+
+    // 括弧の対応とトップレベルの宣言単位をまとめる。
+    let mut items: Vec<CstChild> = Vec::new();
+    let mut current_item: Vec<CstChild> = Vec::new();
+    let mut stack: Vec<OpenDelimiter> = Vec::new();
+    for (token, node_value) in tokens.iter().zip(nodes) {
+        // 行頭（1 桁目）から始まり、継続を示すトークンでなければ新しい宣言単位とする。
+        let starts_item = node_value.span.start.column == 1
+            && node_value.trivia_leading.iter().any(|trivia| {
+                trivia.kind == TriviaKind::Whitespace && trivia.text.as_str().contains('\n')
+            })
+            && !continues_from_line_start(token.kind);
+        if stack.is_empty() && starts_item && !current_item.is_empty() {
+            items.push(item_node(std::mem::take(&mut current_item)));
+        }
+        let child = CstChild::Node(Box::new(node_value));
+        if delimiter_node_kind(token.kind).is_some() {
+            stack.push(OpenDelimiter {
+                kind: token.kind,
+                start: token.span.start as usize,
+                children: vec![child],
+            });
+            continue;
+        }
+        let closes = stack
+            .last()
+            .is_some_and(|open| closing_delimiter(open.kind) == Some(token.kind));
+        if closes {
+            let mut open = stack.pop().expect("括弧スタックが空です");
+            open.children.push(child);
+            let kind = delimiter_node_kind(open.kind).expect("括弧ノード名");
+            let span = index.span(open.start, token.span.end as usize);
+            let delimited = CstChild::Node(Box::new(node(kind, open.children, span)));
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(delimited),
+                None => current_item.push(delimited),
+            }
+            continue;
+        }
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(child),
+            None => current_item.push(child),
+        }
+    }
+    // 閉じられていない括弧はノード化せずに展開する。
+    while let Some(open) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.extend(open.children),
+            None => current_item.extend(open.children),
+        }
+    }
+    if !current_item.is_empty() {
+        items.push(item_node(current_item));
+    }
+
+    let mut root = node("source_file", items, index.span(0, source.len()));
+    root.trivia_trailing = rest;
+    root
+}
+
+fn child_span(child: &CstChild) -> &Span {
+    match child {
+        CstChild::Node(node) => &node.span,
+        CstChild::Token(token) => &token.span,
+    }
+}
+
+fn item_node(children: Vec<CstChild>) -> CstChild {
+    let start = child_span(children.first().expect("空の宣言単位")).start;
+    let end = child_span(children.last().expect("空の宣言単位")).end;
+    CstChild::Node(Box::new(node("item", children, Span::new(start, end))))
+}
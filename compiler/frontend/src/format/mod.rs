@@ -0,0 +1,268 @@
+//! `remlc fmt` のソースフォーマッタ。
+//!
+//! `lexer::lex_source` のトークン列と、その間の空白・コメントからロスレスな
+//! CST を構築し（[`build_cst`]）、`reml_runtime::text` の Doc 代数で整形する。
+//! 整形はトークン間の空白・改行・コメント位置のみを変更するため、結果を
+//! `lex_source` に通したトークン列は入力と一致する。幅超過で折り返した行は
+//! 次の整形でも同じ段数に字下げされるため、1 回の整形で冪等な結果が得られる。
+//! 整形結果を再度整形して差分が出た場合は [`FormatError::NotIdempotent`] を返す。
+//!
+//! 設定は `reml.toml` の `[format]` セクション（仕様 4-3 §2.1）から読み込む。
+
+mod cst;
+mod printer;
+
+use std::fmt;
+
+use reml_runtime::config::{FormatSection, IndentStyle as ManifestIndentStyle};
+
+use crate::lexer::lex_source;
+use crate::token::TokenKind;
+
+pub use cst::build_cst;
+
+/// インデントに使用する文字。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndentStyle {
+    #[default]
+    Space,
+    Tab,
+}
+
+/// フォーマッタの設定。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// 折り返しの目安とする行幅。
+    pub line_width: usize,
+    /// 1 段あたりのインデント幅（タブ使用時は折り返し幅の計算にのみ使う）。
+    pub indent_width: usize,
+    pub indent_style: IndentStyle,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self::from_section(&FormatSection::default())
+    }
+}
+
+impl FormatOptions {
+    /// `reml.toml` の `[format]` セクションから設定を組み立てる。
+    ///
+    /// 未知の `indent_style` はマニフェスト検証で報告されるため、ここでは空白として扱う。
+    pub fn from_section(section: &FormatSection) -> Self {
+        Self {
+            line_width: section.line_width.max(1),
+            indent_width: section.indent_width.max(1),
+            indent_style: match section.indent_style {
+                ManifestIndentStyle::Tab => IndentStyle::Tab,
+                ManifestIndentStyle::Space | ManifestIndentStyle::Unknown(_) => IndentStyle::Space,
+            },
+        }
+    }
+}
+
+/// 整形できなかった理由。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// 字句解析エラーを含むソースは整形しない。
+    Lex {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// 整形結果を再度整形すると変化した（フォーマッタの不具合）。
+    NotIdempotent {
+        /// 最初に差分が出た行（1 始まり）。
+        line: usize,
+    },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Lex {
+                message,
+                line,
+                column,
+            } => write!(
+                f,
+                "{line}:{column}: 字句解析に失敗したため整形できません: {message}"
+            ),
+            FormatError::NotIdempotent { line } => write!(
+                f,
+                "{line}: 整形結果が安定しません（再整形で差分が生じました）"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// ソース全体を整形する。
+pub fn format_source(source: &str, options: &FormatOptions) -> Result<String, FormatError> {
+    let formatted = format_once(source, options)?;
+    let reformatted = format_once(&formatted, options)?;
+    if reformatted != formatted {
+        let line = formatted
+            .lines()
+            .zip(reformatted.lines())
+            .position(|(left, right)| left != right)
+            .unwrap_or_else(|| formatted.lines().count().min(reformatted.lines().count()))
+            + 1;
+        return Err(FormatError::NotIdempotent { line });
+    }
+    Ok(formatted)
+}
+
+/// 整形済みかどうかを判定する（`remlc fmt --check` 用）。
+pub fn is_formatted(source: &str, options: &FormatOptions) -> Result<bool, FormatError> {
+    Ok(format_source(source, options)? == source)
+}
+
+fn format_once(source: &str, options: &FormatOptions) -> Result<String, FormatError> {
+    let output = lex_source(source);
+    if let Some(error) = output.errors.first() {
+        let offset = error_offset(error).min(source.len());
+        let prefix = &source[..offset];
+        let line = prefix.matches('\n').count() + 1;
+        let column = prefix
+            .rsplit('\n')
+            .next()
+            .map_or(0, |rest| rest.chars().count())
+            + 1;
+        return Err(FormatError::Lex {
+            message: error.message(),
+            line,
+            column,
+        });
+    }
+    let root = build_cst(source, &output.tokens);
+    let kinds: Vec<TokenKind> = output
+        .tokens
+        .iter()
+        .map(|token| token.kind)
+        .filter(|kind| *kind != TokenKind::EndOfFile)
+        .collect();
+    Ok(printer::Printer::new(&root, &kinds, options).print())
+}
+
+fn error_offset(error: &crate::FrontendError) -> usize {
+    use crate::FrontendErrorKind;
+    match &error.kind {
+        FrontendErrorKind::UnknownToken { span } | FrontendErrorKind::MissingToken { span, .. } => {
+            span.start as usize
+        }
+        FrontendErrorKind::UnexpectedStructure { span, .. } => {
+            span.map_or(0, |span| span.start as usize)
+        }
+        FrontendErrorKind::InternalState { .. } => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reml_runtime::text::{cst_doc, cst_printer, render};
+
+    fn format(source: &str) -> String {
+        format_source(source, &FormatOptions::default()).expect("整形できるはず")
+    }
+
+    #[test]
+    fn cst_roundtrips_source_losslessly() {
+        let source = "// head\nfn  add(a,b) = a+b // tail\n\n/* block */ let x = [1 ,2]\n";
+        let tokens = lex_source(source).tokens;
+        let root = build_cst(source, &tokens);
+        assert_eq!(render(cst_doc(cst_printer(), &root), 100), source);
+    }
+
+    #[test]
+    fn comments_stay_attached_to_their_tokens() {
+        let source =
+            "fn main() -> Int {\n  // 先頭コメント\n  let x = 1 // 末尾コメント\n\n\n  x\n}\n";
+        assert_eq!(
+            format(source),
+            "fn main() -> Int {\n  // 先頭コメント\n  let x = 1 // 末尾コメント\n\n  x\n}\n"
+        );
+    }
+
+    #[test]
+    fn normalizes_spacing_and_indentation() {
+        let source = "fn f(x:Int)->Int=\n    match x with\n| 0->1\n| _ -> f( x ,2 )\n";
+        assert_eq!(
+            format(source),
+            "fn f(x:Int) -> Int =\n  match x with\n  | 0 -> 1\n  | _ -> f(x, 2)\n"
+        );
+    }
+
+    #[test]
+    fn breaks_long_argument_lists_within_width() {
+        let options = FormatOptions {
+            line_width: 24,
+            indent_width: 4,
+            indent_style: IndentStyle::Space,
+        };
+        let source = "let total = sum(alpha, beta, gamma)\n";
+        let formatted = format_source(source, &options).expect("整形できるはず");
+        assert_eq!(formatted, "let total = sum(alpha,\n    beta,\n    gamma)\n");
+        assert_eq!(
+            format_source(&formatted, &options).expect("整形できるはず"),
+            formatted
+        );
+    }
+
+    #[test]
+    fn nested_breaks_are_stable_after_one_pass() {
+        let options = FormatOptions {
+            line_width: 20,
+            indent_width: 2,
+            indent_style: IndentStyle::Space,
+        };
+        let source = "let v = outer(first, inner(alpha, beta, gamma), last)\n";
+        let formatted = format_once(source, &options).expect("整形できるはず");
+        assert_eq!(
+            formatted,
+            "let v = outer(first,\n  inner(alpha,\n    beta,\n    gamma),\n  last)\n"
+        );
+        assert_eq!(
+            format_once(&formatted, &options).expect("整形できるはず"),
+            formatted
+        );
+    }
+
+    #[test]
+    fn tab_indented_breaks_are_stable_after_one_pass() {
+        let options = FormatOptions {
+            line_width: 16,
+            indent_width: 4,
+            indent_style: IndentStyle::Tab,
+        };
+        let source = "fn main() {\n  call(alpha, beta)\n}\n";
+        let formatted = format_once(source, &options).expect("整形できるはず");
+        assert_eq!(formatted, "fn main() {\n\tcall(alpha,\n\t\tbeta)\n}\n");
+        assert_eq!(
+            format_once(&formatted, &options).expect("整形できるはず"),
+            formatted
+        );
+    }
+
+    #[test]
+    fn indents_with_tabs_when_requested() {
+        let options = FormatOptions {
+            indent_style: IndentStyle::Tab,
+            ..FormatOptions::default()
+        };
+        let source = "fn main() {\n    let x = 1\n}\n";
+        assert_eq!(
+            format_source(source, &options).expect("整形できるはず"),
+            "fn main() {\n\tlet x = 1\n}\n"
+        );
+    }
+
+    #[test]
+    fn rejects_sources_with_lex_errors() {
+        let err = format_source("let s = \"unterminated\n", &FormatOptions::default())
+            .expect_err("字句エラーは整形対象外");
+        assert!(matches!(err, FormatError::Lex { line: 1, .. }));
+    }
+}
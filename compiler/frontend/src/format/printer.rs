@@ -0,0 +1,498 @@
+//! CST を `reml_runtime::text` の Doc へ変換して整形済みテキストを得る。
+//!
+//! 改行位置は入力を尊重し（空行は 1 行まで）、各行のインデントを括弧の
+//! 入れ子と継続行から再計算する。行内のトークン間空白は演算子・区切り記号
+//! ごとの規則で正規化し、同じ行で開閉する括弧は `group` とカンマ位置の
+//! `softline` で表現して、幅を超える場合に折り返す。折り返した行は括弧の
+//! 入れ子から求めるインデントと同じ桁に置くため、整形結果を再度整形しても
+//! 変化しない。
+
+use reml_runtime::parse::{CstChild, CstNode, TriviaKind};
+use reml_runtime::text::{concat, group, nest, render, render_with_tabs, softline, text, Doc};
+
+use super::cst::continues_from_line_start;
+use super::{FormatOptions, IndentStyle};
+use crate::lexer::lex_source;
+use crate::token::TokenKind;
+
+/// トークン直前のコメント。
+struct GapComment<'a> {
+    text: &'a str,
+    /// 直前の要素からこのコメントまでの改行数。
+    newlines: usize,
+}
+
+/// トークン間（またはファイル末尾）のトリビアを整形向けに要約したもの。
+#[derive(Default)]
+struct Gap<'a> {
+    comments: Vec<GapComment<'a>>,
+    /// 最後のコメント（なければ直前のトークン）から次の要素までの改行数。
+    newlines: usize,
+    /// 空白が存在したかどうか。
+    whitespace: bool,
+    /// 入力上で次の要素が置かれていた桁（タブはインデント幅で換算する）。
+    indent: usize,
+}
+
+impl<'a> Gap<'a> {
+    fn extend(&mut self, trivia: &'a [reml_runtime::parse::Trivia], tab_width: usize) {
+        for entry in trivia {
+            let value = entry.text.as_str();
+            match entry.kind {
+                TriviaKind::Comment => {
+                    self.comments.push(GapComment {
+                        text: value,
+                        newlines: std::mem::take(&mut self.newlines),
+                    });
+                    self.indent = match value.rsplit_once('\n') {
+                        Some((_, rest)) => rest.chars().count(),
+                        None => self.indent + value.chars().count(),
+                    };
+                }
+                TriviaKind::Whitespace | TriviaKind::Layout => {
+                    self.whitespace |= !value.is_empty();
+                    for ch in value.chars() {
+                        match ch {
+                            '\n' => {
+                                self.newlines += 1;
+                                self.indent = 0;
+                            }
+                            '\t' => self.indent += tab_width,
+                            '\r' => {}
+                            _ => self.indent += 1,
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn breaks_line(&self) -> bool {
+        self.newlines > 0 || self.comments.iter().any(|comment| comment.newlines > 0)
+    }
+
+    /// 直前の行末に残すコメント数（改行より前にあるコメント）。
+    fn trailing_len(&self) -> usize {
+        self.comments
+            .iter()
+            .take_while(|comment| comment.newlines == 0)
+            .count()
+    }
+}
+
+struct Entry<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    /// 対応する括弧トークンの位置。
+    partner: Option<usize>,
+    /// 直前のトークンとの間にあるトリビア。
+    gap: Gap<'a>,
+}
+
+/// 括弧の入れ子ごとのインデント状態（単位はインデント段数）。
+///
+/// `statement` は直近の文の先頭行、`last` は直前の行の段数で、それぞれの
+/// 入力上の桁を `*_indent` に保持して継続行の判定に用いる。
+struct Frame {
+    open: Option<usize>,
+    base: usize,
+    inner: usize,
+    statement: usize,
+    statement_indent: Option<usize>,
+    last: usize,
+    last_indent: Option<usize>,
+}
+
+impl Frame {
+    fn new(open: Option<usize>, base: usize, inner: usize) -> Self {
+        Self {
+            open,
+            base,
+            inner,
+            statement: inner,
+            statement_indent: None,
+            last: inner,
+            last_indent: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Spacing {
+    Space,
+    None,
+    Keep,
+}
+
+fn spaced_operator(kind: TokenKind) -> bool {
+    use TokenKind::*;
+    matches!(
+        kind,
+        Assign
+            | ColonAssign
+            | Arrow
+            | DoubleArrow
+            | PipeForward
+            | ChannelPipe
+            | EqEq
+            | NotEqual
+            | LogicalAnd
+            | LogicalOr
+    )
+}
+
+fn spacing(left: TokenKind, right: TokenKind) -> Spacing {
+    use TokenKind::*;
+    match (left, right) {
+        (_, Comma | Semicolon) | (LParen | LBracket, _) | (_, RParen | RBracket) => Spacing::None,
+        (Comma, _) => Spacing::Space,
+        (left, right) if spaced_operator(left) || spaced_operator(right) => Spacing::Space,
+        _ => Spacing::Keep,
+    }
+}
+
+/// 整形結果を行単位で組み立てる。各行は Doc として幅付きでレンダリングする。
+struct Output {
+    text: String,
+    line: Vec<Doc>,
+    width: i64,
+    /// タブでインデントする場合のタブ幅。
+    tab_width: Option<usize>,
+}
+
+impl Output {
+    fn flush(&mut self) {
+        if self.line.is_empty() {
+            return;
+        }
+        let docs = std::mem::take(&mut self.line);
+        let doc = docs.into_iter().reduce(concat).unwrap_or_else(|| text(""));
+        let rendered = match self.tab_width {
+            Some(tab_width) => render_with_tabs(doc, self.width, tab_width),
+            None => render(doc, self.width),
+        };
+        self.text.push_str(&rendered);
+    }
+
+    /// 改行数に応じて新しい行を開始する（0 の場合は同じ行に空白を挟む）。
+    fn advance(&mut self, newlines: usize, indent: &str) {
+        let started = !self.text.is_empty() || !self.line.is_empty();
+        if started && newlines == 0 {
+            self.line.push(text(" "));
+            return;
+        }
+        self.flush();
+        if started {
+            self.text.push('\n');
+            if newlines >= 2 {
+                self.text.push('\n');
+            }
+        }
+        self.line.push(text(indent));
+    }
+
+    fn push(&mut self, doc: Doc) {
+        self.line.push(doc);
+    }
+}
+
+pub(super) struct Printer<'a> {
+    entries: Vec<Entry<'a>>,
+    final_gap: Gap<'a>,
+    options: &'a FormatOptions,
+}
+
+impl<'a> Printer<'a> {
+    pub(super) fn new(root: &'a CstNode, kinds: &[TokenKind], options: &'a FormatOptions) -> Self {
+        let mut printer = Self {
+            entries: Vec::new(),
+            final_gap: Gap::default(),
+            options,
+        };
+        let mut pending = Gap::default();
+        printer.collect(root, kinds, &mut pending);
+        pending.extend(&root.trivia_trailing, options.indent_width);
+        printer.final_gap = pending;
+        printer
+    }
+
+    fn collect(&mut self, node: &'a CstNode, kinds: &[TokenKind], pending: &mut Gap<'a>) {
+        if node.kind.as_str() == "token" {
+            pending.extend(&node.trivia_leading, self.options.indent_width);
+            let token_text = node
+                .children
+                .iter()
+                .find_map(|child| match child {
+                    CstChild::Token(token) => Some(token.text.as_str()),
+                    CstChild::Node(_) => None,
+                })
+                .unwrap_or_default();
+            let index = self.entries.len();
+            self.entries.push(Entry {
+                kind: kinds[index],
+                text: token_text,
+                partner: None,
+                gap: std::mem::take(pending),
+            });
+            pending.extend(&node.trivia_trailing, self.options.indent_width);
+            return;
+        }
+        let start = self.entries.len();
+        for child in node.children.iter() {
+            if let CstChild::Node(child) = child {
+                self.collect(child, kinds, pending);
+            }
+        }
+        if matches!(node.kind.as_str(), "paren" | "bracket" | "brace") {
+            let end = self.entries.len() - 1;
+            self.entries[start].partner = Some(end);
+            self.entries[end].partner = Some(start);
+        }
+    }
+
+    fn indent(&self, level: usize) -> String {
+        match self.options.indent_style {
+            IndentStyle::Tab => "\t".repeat(level),
+            IndentStyle::Space => " ".repeat(level * self.options.indent_width),
+        }
+    }
+
+    fn is_closer(&self, index: usize) -> bool {
+        self.entries[index]
+            .partner
+            .is_some_and(|partner| partner < index)
+    }
+
+    /// 行頭トークンごとのインデント段数と、その前に置くコメントの段数を求める。
+    ///
+    /// 継続を示すトークンで始まる行と、入力上で文の先頭行より深く字下げされた行は
+    /// 継続行として一段深くする（直前の行よりさらに深ければ直前の行から一段深くする）。
+    /// 判定は整形後のインデントでも同じ結果になるため、整形は冪等になる。
+    ///
+    /// あわせて開き括弧ごとに、その内側の行に置く段数を `inner_levels` に記録する。
+    /// 同じ行で続けて開いた括弧は一段ずつ深くなる。
+    fn line_levels(
+        &self,
+        lines: &[(usize, usize)],
+        inner_levels: &mut [usize],
+    ) -> Vec<(usize, usize)> {
+        let mut stack = vec![Frame::new(None, 0, 0)];
+        let mut levels = Vec::with_capacity(lines.len());
+        for &(start, end) in lines {
+            let frame = stack.last_mut().expect("ルートフレーム");
+            let entry = &self.entries[start];
+            let indent = entry.gap.indent;
+            let closes_frame = self.is_closer(start) && frame.open == entry.partner;
+            let (level, comment_level) = if closes_frame {
+                (frame.base, frame.inner)
+            } else {
+                let deeper_than_statement = frame
+                    .statement_indent
+                    .is_some_and(|statement| indent > statement);
+                let deeper_than_last = frame.last_indent.is_some_and(|last| indent > last);
+                let level = if start > 0
+                    && (continues_from_line_start(entry.kind) || deeper_than_statement)
+                {
+                    if deeper_than_last {
+                        frame.last + 1
+                    } else {
+                        frame.statement + 1
+                    }
+                } else {
+                    frame.statement = frame.inner;
+                    frame.statement_indent = Some(indent);
+                    frame.inner
+                };
+                frame.last = level;
+                frame.last_indent = Some(indent);
+                (level, level)
+            };
+            levels.push((level, comment_level));
+            // 行内で前の行から続く括弧を閉じた後は、その括弧を開いた行の段数を基準にする。
+            let mut current = level;
+            for (offset, entry) in self.entries[start..=end].iter().enumerate() {
+                let index = start + offset;
+                let Some(partner) = entry.partner else {
+                    continue;
+                };
+                if partner > index {
+                    stack.push(Frame::new(Some(index), current, current + 1));
+                    current += 1;
+                    inner_levels[index] = current;
+                } else if stack.len() > 1 {
+                    let closed = stack.pop().expect("括弧フレーム");
+                    current = closed.base;
+                    if index == start {
+                        let parent = stack.last_mut().expect("ルートフレーム");
+                        parent.last = level;
+                        parent.last_indent = Some(indent);
+                    }
+                }
+            }
+        }
+        levels
+    }
+
+    pub(super) fn print(&self) -> String {
+        let mut lines = Vec::new();
+        for index in 0..self.entries.len() {
+            if index == 0 || self.entries[index].gap.breaks_line() {
+                lines.push((index, index));
+            } else if let Some(line) = lines.last_mut() {
+                line.1 = index;
+            }
+        }
+        let mut inner_levels = vec![0; self.entries.len()];
+        let levels = self.line_levels(&lines, &mut inner_levels);
+        let mut output = Output {
+            text: String::new(),
+            line: Vec::new(),
+            width: self.options.line_width as i64,
+            tab_width: match self.options.indent_style {
+                IndentStyle::Tab => Some(self.options.indent_width),
+                IndentStyle::Space => None,
+            },
+        };
+        for (&(start, end), &(level, comment_level)) in lines.iter().zip(levels.iter()) {
+            let gap = &self.entries[start].gap;
+            let skip = if start == 0 { 0 } else { gap.trailing_len() };
+            let comment_indent = self.indent(comment_level);
+            for comment in gap.comments.iter().skip(skip) {
+                output.advance(comment.newlines, &comment_indent);
+                output.push(text(comment_text(comment.text)));
+            }
+            output.advance(gap.newlines, &self.indent(level));
+            output.push(self.range_doc(start, end, None, &inner_levels));
+            let next_gap = self
+                .entries
+                .get(end + 1)
+                .map_or(&self.final_gap, |next| &next.gap);
+            for comment in next_gap.comments.iter().take(next_gap.trailing_len()) {
+                output.push(text(" "));
+                output.push(text(comment_text(comment.text)));
+            }
+        }
+        let skip = if self.entries.is_empty() {
+            0
+        } else {
+            self.final_gap.trailing_len()
+        };
+        for comment in self.final_gap.comments.iter().skip(skip) {
+            output.advance(comment.newlines, "");
+            output.push(text(comment_text(comment.text)));
+        }
+        output.flush();
+        if !output.text.is_empty() {
+            output.text.push('\n');
+        }
+        output.text
+    }
+
+    /// 同じ行にある `start..=end` のトークン列を Doc にする。
+    ///
+    /// 範囲内で開閉する括弧は `group` とし、直下のカンマを `softline` にする。
+    /// `outer` は範囲を囲む括弧の内側の段数で、行の最上位では `None` になる。
+    /// 折り返した行は括弧ごとに記録した `inner_levels` の段数へ字下げする。
+    fn range_doc(
+        &self,
+        start: usize,
+        end: usize,
+        outer: Option<usize>,
+        inner_levels: &[usize],
+    ) -> Doc {
+        let breakable = outer.is_some();
+        let mut docs = Vec::new();
+        let mut index = start;
+        while index <= end {
+            if index > start {
+                docs.push(self.separator(index, breakable));
+            }
+            let entry = &self.entries[index];
+            match entry.partner {
+                Some(close) if close > index && close <= end => {
+                    let level = inner_levels[index];
+                    let mut parts = vec![text(entry.text)];
+                    if close > index + 1 {
+                        parts.push(self.separator(index + 1, true));
+                        parts.push(self.range_doc(index + 1, close - 1, Some(level), inner_levels));
+                    }
+                    // 閉じ括弧は開いた行の段数に戻るため、直前では折り返さない。
+                    parts.push(self.separator(close, false));
+                    parts.push(text(self.entries[close].text));
+                    let inner = parts.into_iter().reduce(concat).expect("括弧");
+                    let columns = (level - outer.unwrap_or(0)) * self.options.indent_width;
+                    docs.push(group(nest(columns as i64, inner)));
+                    index = close + 1;
+                }
+                _ => {
+                    docs.push(text(entry.text));
+                    index += 1;
+                }
+            }
+        }
+        docs.into_iter().reduce(concat).unwrap_or_else(|| text(""))
+    }
+
+    /// `index` 番目のトークンと直前トークンの間に置く区切りを求める。
+    fn separator(&self, index: usize, breakable: bool) -> Doc {
+        let previous = &self.entries[index - 1];
+        let entry = &self.entries[index];
+        // 継続を示すトークンを行頭に置くと次の整形で継続行として字下げされるため、
+        // その直前では折り返さない。
+        let space = || {
+            if breakable
+                && previous.kind == TokenKind::Comma
+                && !continues_from_line_start(entry.kind)
+            {
+                softline()
+            } else {
+                text(" ")
+            }
+        };
+        if !entry.gap.comments.is_empty() {
+            let comments = entry
+                .gap
+                .comments
+                .iter()
+                .map(|comment| text(format!(" {}", comment.text)))
+                .reduce(concat)
+                .expect("コメント");
+            return concat(comments, space());
+        }
+        let spaced = match spacing(previous.kind, entry.kind) {
+            Spacing::Space => true,
+            Spacing::None => entry.gap.whitespace && !glues(previous, entry),
+            Spacing::Keep => entry.gap.whitespace,
+        };
+        if spaced {
+            space()
+        } else {
+            text("")
+        }
+    }
+}
+
+/// 空白を取り除いて連結しても同じ 2 トークンとして字句解析されるか。
+fn glues(left: &Entry<'_>, right: &Entry<'_>) -> bool {
+    let joined = format!("{}{}", left.text, right.text);
+    let output = lex_source(&joined);
+    let tokens: Vec<_> = output
+        .tokens
+        .iter()
+        .filter(|token| token.kind != TokenKind::EndOfFile)
+        .collect();
+    output.errors.is_empty()
+        && tokens.len() == 2
+        && tokens[0].kind == left.kind
+        && tokens[1].kind == right.kind
+        && tokens[0].span.end as usize == left.text.len()
+}
+
+/// 行コメントの末尾空白を除く。ブロックコメントは内容をそのまま保持する。
+fn comment_text(comment: &str) -> &str {
+    if comment.starts_with("//") {
+        comment.trim_end()
+    } else {
+        comment
+    }
+}
@@ -8,6 +8,7 @@ use logos::{Lexer as LogosLexer, Logos};
 use reml_runtime::text::{self as unicode_text, LocaleId, Str as UnicodeStr, UnicodeErrorKind};
 use std::str::FromStr;
 
+use crate::diagnostic::messages::{self, MessageArgs};
 use crate::error::{FrontendError, FrontendErrorKind, Recoverability};
 use crate::span::Span;
 use crate::token::{IntBase, LiteralMetadata, StringKind, Token, TokenKind};
@@ -69,6 +70,8 @@ impl FromStr for IdentifierProfile {
 pub struct LexerOptions {
     pub identifier_profile: IdentifierProfile,
     pub identifier_locale: Option<LocaleId>,
+    /// 字句エラーのメッセージを組み立てる診断ロケール。
+    pub diagnostic_locale: &'static str,
 }
 
 impl Default for LexerOptions {
@@ -76,6 +79,7 @@ impl Default for LexerOptions {
         Self {
             identifier_profile: IdentifierProfile::Unicode,
             identifier_locale: None,
+            diagnostic_locale: messages::DEFAULT_LOCALE,
         }
     }
 }
@@ -649,7 +653,7 @@ pub fn lex_source_with_options(text: &str, options: LexerOptions) -> LexOutput {
                 let slice = lexer.slice();
                 if options.identifier_profile == IdentifierProfile::AsciiCompat && !slice.is_ascii()
                 {
-                    push_ascii_error(span, slice, &mut errors, &mut tokens);
+                    push_ascii_error(span, slice, options.diagnostic_locale, &mut errors, &mut tokens);
                     offset += consumed;
                     continue;
                 }
@@ -824,6 +828,7 @@ fn prepare_identifier_token(
             slice,
             options.identifier_locale.as_ref(),
             options.identifier_profile,
+            options.diagnostic_locale,
         )
     })
 }
@@ -834,6 +839,7 @@ fn unicode_error_to_frontend(
     raw: &str,
     locale: Option<&LocaleId>,
     profile: IdentifierProfile,
+    diagnostic_locale: &str,
 ) -> FrontendError {
     let mut detail = UnicodeDetail::from_error(&err)
         .with_phase("lex.identifier".to_string())
@@ -842,25 +848,38 @@ fn unicode_error_to_frontend(
     if let Some(locale) = locale {
         detail = detail.with_locale(locale.canonical().to_string());
     }
-    let mut message = match err.kind() {
-        UnicodeErrorKind::InvalidIdentifier => {
-            format!("Unicode 識別子の正規化に失敗しました: {}", err.message())
-        }
+    let reason = err.message();
+    let (code, args, fallback) = match err.kind() {
+        UnicodeErrorKind::InvalidIdentifier => (
+            "lexer.identifier.normalization_failed",
+            MessageArgs::new().with("reason", reason),
+            format!("Unicode 識別子の正規化に失敗しました: {reason}"),
+        ),
         UnicodeErrorKind::UnsupportedLocale => {
             let requested = locale
                 .map(|locale| locale.canonical().to_string())
                 .unwrap_or_else(|| "und".to_string());
-            format!(
-                "lex.identifier_locale `{}` は未サポートです: {}",
-                requested,
-                err.message()
+            let fallback =
+                format!("lex.identifier_locale `{requested}` は未サポートです: {reason}");
+            (
+                "lexer.identifier.unsupported_locale",
+                MessageArgs::new()
+                    .with("locale", requested)
+                    .with("reason", reason),
+                fallback,
             )
         }
-        other => format!(
-            "Unicode 識別子処理で予期しないエラー ({other:?}): {}",
-            err.message()
+        other => (
+            "lexer.identifier.unexpected_error",
+            MessageArgs::new()
+                .with("kind", format!("{other:?}"))
+                .with("reason", reason),
+            format!("Unicode 識別子処理で予期しないエラー ({other:?}): {reason}"),
         ),
     };
+    let mut message = messages::translate(code, diagnostic_locale, &args)
+        .map(|localized| localized.message)
+        .unwrap_or(fallback);
     if let Some(offset) = err.offset() {
         message.push_str(&format!(" (offset {offset})"));
     }
@@ -894,6 +913,7 @@ fn detect_int_base(lexeme: &str) -> IntBase {
 fn push_ascii_error(
     span: Span,
     lexeme: &str,
+    diagnostic_locale: &str,
     errors: &mut Vec<FrontendError>,
     tokens: &mut Vec<Token>,
 ) {
@@ -901,21 +921,30 @@ fn push_ascii_error(
         .chars()
         .find(|ch| !ch.is_ascii())
         .unwrap_or('\u{FFFD}');
-    let prefix = if lexeme
+    let (code, prefix) = if lexeme
         .chars()
         .next()
         .map(|ch| !ch.is_ascii())
         .unwrap_or(false)
     {
-        "識別子の先頭に使用できないコードポイント"
+        (
+            "lexer.identifier.invalid_start",
+            "識別子の先頭に使用できないコードポイント",
+        )
     } else {
-        "識別子に使用できないコードポイント"
+        (
+            "lexer.identifier.invalid_code_point",
+            "識別子に使用できないコードポイント",
+        )
     };
-    let message = format!(
-        "{prefix} U+{:04X} (profile={})",
-        invalid_code_point as u32,
-        IdentifierProfile::AsciiCompat.as_str()
-    );
+    let code_point = format!("{:04X}", invalid_code_point as u32);
+    let profile = IdentifierProfile::AsciiCompat.as_str();
+    let args = MessageArgs::new()
+        .with("code_point", code_point.as_str())
+        .with("profile", profile);
+    let message = messages::translate(code, diagnostic_locale, &args)
+        .map(|localized| localized.message)
+        .unwrap_or_else(|| format!("{prefix} U+{code_point} (profile={profile})"));
     errors.push(FrontendError::new(
         FrontendErrorKind::UnexpectedStructure {
             message,
@@ -5,9 +5,12 @@
 
 pub mod diagnostic;
 pub mod effects;
+pub mod embed;
 pub mod error;
 pub mod ffi_executor;
+pub mod format;
 pub mod lexer;
+pub mod lint;
 pub mod output;
 pub mod parser;
 pub mod pipeline;
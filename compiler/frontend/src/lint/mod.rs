@@ -0,0 +1,456 @@
+//! `remlc lint` の規約・正しさチェック。
+//!
+//! 型検査済みの `TypedModule` とその元になった AST を走査し、未使用の束縛や
+//! 冗長な `unsafe` などを [`LintFinding`] として報告する。各規則は ID・既定レベル・
+//! 修正候補（fix-it）を持ち、レベルは次の順に上書きされる（仕様 4-3 §2.2）。
+//!
+//! 1. 規則の既定レベル（[`LintRule::default_level`]）
+//! 2. `reml.toml` の `[lint.rules]`（[`LintOptions::from_section`]）
+//! 3. モジュールヘッダ前の `@allow` / `@warn` / `@deny` 属性（ファイル単位）
+//! 4. 関数に付けた同名の属性（関数本体のみ）
+//!
+//! レベルの照合には `diagnostic::filter` のワイルドカードパターンを用いる。
+//! 型付き AST はループ本体などを `Unknown` に畳み込むため、スコープ解析を要する
+//! 規則（未使用・シャドーイング・`var`・命名）は AST 側で解析する。
+
+mod naming;
+mod scope;
+mod typed;
+
+use std::fmt;
+
+use reml_runtime::config::{LintLevel as ManifestLintLevel, LintSection};
+
+use crate::diagnostic::filter::{LintLevel, LintLevelMap};
+use crate::diagnostic::json::LineIndex;
+use crate::diagnostic::{
+    DiagnosticDomain, DiagnosticFixIt, DiagnosticSeverity, FrontendDiagnostic,
+};
+use crate::lexer::IdentifierProfile;
+use crate::parser::ast::{Attribute, ExprKind, LiteralKind, Module};
+use crate::parser::ParserDriver;
+use crate::semantics::typed::TypedModule;
+use crate::span::Span;
+use crate::typeck::{TypecheckConfig, TypecheckDriver};
+
+/// lint 規則の定義。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LintRule {
+    pub id: &'static str,
+    pub code: &'static str,
+    pub default_level: LintLevel,
+    pub summary: &'static str,
+}
+
+pub const UNUSED_BINDING: LintRule = LintRule {
+    id: "unused_binding",
+    code: "lint.unused_binding",
+    default_level: LintLevel::Warn,
+    summary: "参照されない let/var 束縛・引数・パターン変数",
+};
+
+pub const UNUSED_IMPORT: LintRule = LintRule {
+    id: "unused_import",
+    code: "lint.unused_import",
+    default_level: LintLevel::Warn,
+    summary: "どこからも参照されない use 宣言",
+};
+
+pub const SHADOWED_BINDING: LintRule = LintRule {
+    id: "shadowed_binding",
+    code: "lint.shadowed_binding",
+    default_level: LintLevel::Allow,
+    summary: "外側のスコープの束縛を隠す内側の束縛",
+};
+
+pub const UNREACHABLE_ARM: LintRule = LintRule {
+    id: "unreachable_arm",
+    code: "lint.unreachable_arm",
+    default_level: LintLevel::Warn,
+    summary: "ガードのない全捕捉アームより後ろにある match アーム",
+};
+
+pub const REDUNDANT_PROPAGATE: LintRule = LintRule {
+    id: "redundant_propagate",
+    code: "lint.redundant_propagate",
+    default_level: LintLevel::Warn,
+    summary: "`Ok(expr?)` / `Some(expr?)` のように伝播直後に包み直す式",
+};
+
+pub const NEEDLESS_UNSAFE: LintRule = LintRule {
+    id: "needless_unsafe",
+    code: "lint.needless_unsafe",
+    default_level: LintLevel::Warn,
+    summary: "unsafe 操作を含まない unsafe ブロック",
+};
+
+pub const NEEDLESS_DEFER: LintRule = LintRule {
+    id: "needless_defer",
+    code: "lint.needless_defer",
+    default_level: LintLevel::Warn,
+    summary: "副作用のない式を登録する defer",
+};
+
+pub const NEEDLESS_VAR: LintRule = LintRule {
+    id: "needless_var",
+    code: "lint.needless_var",
+    default_level: LintLevel::Warn,
+    summary: "再代入されない var 束縛",
+};
+
+pub const NAMING_CONVENTION: LintRule = LintRule {
+    id: "naming_convention",
+    code: "lint.naming_convention",
+    default_level: LintLevel::Warn,
+    summary: "値は snake_case、型は UpperCamelCase という命名規約と識別子プロファイルへの違反",
+};
+
+const RULES: &[LintRule] = &[
+    UNUSED_BINDING,
+    UNUSED_IMPORT,
+    SHADOWED_BINDING,
+    UNREACHABLE_ARM,
+    REDUNDANT_PROPAGATE,
+    NEEDLESS_UNSAFE,
+    NEEDLESS_DEFER,
+    NEEDLESS_VAR,
+    NAMING_CONVENTION,
+];
+
+/// 登録済みの lint 規則一覧。
+pub fn rules() -> &'static [LintRule] {
+    RULES
+}
+
+/// lint ID または診断コードから規則を引く。
+pub fn find_rule(name: &str) -> Option<&'static LintRule> {
+    RULES
+        .iter()
+        .find(|rule| rule.id == name || rule.code == name)
+}
+
+/// lint 実行時の設定。
+#[derive(Debug, Clone, Default)]
+pub struct LintOptions {
+    /// プロジェクト単位のレベル上書き。
+    pub levels: LintLevelMap,
+    /// 命名規約の判定に用いる識別子プロファイル。
+    pub identifier_profile: IdentifierProfile,
+}
+
+impl LintOptions {
+    /// `reml.toml` の `[lint]` セクションから設定を組み立てる。
+    ///
+    /// 未知のレベルはマニフェスト検証で報告されるため、ここでは無視する。
+    pub fn from_section(section: &LintSection) -> Self {
+        let mut levels = LintLevelMap::new();
+        for (pattern, level) in &section.rules {
+            let level = match level {
+                ManifestLintLevel::Allow => LintLevel::Allow,
+                ManifestLintLevel::Warn => LintLevel::Warn,
+                ManifestLintLevel::Deny => LintLevel::Deny,
+                ManifestLintLevel::Unknown(_) => continue,
+            };
+            levels.insert(pattern.clone(), level);
+        }
+        Self {
+            levels,
+            identifier_profile: IdentifierProfile::default(),
+        }
+    }
+}
+
+/// lint が検出した 1 件の問題。
+#[derive(Debug, Clone)]
+pub struct LintFinding {
+    pub rule: &'static LintRule,
+    pub level: LintLevel,
+    pub message: String,
+    pub span: Span,
+    pub fixits: Vec<DiagnosticFixIt>,
+}
+
+impl LintFinding {
+    pub fn to_diagnostic(&self) -> FrontendDiagnostic {
+        let mut diagnostic = FrontendDiagnostic::new(self.message.clone())
+            .with_code(self.rule.code)
+            .with_span(self.span)
+            .with_domain(DiagnosticDomain::Other("lint".to_string()));
+        if let Some(severity) = self.level.severity() {
+            diagnostic.set_severity(severity);
+        }
+        for fixit in &self.fixits {
+            diagnostic.add_fixit(fixit.clone());
+        }
+        diagnostic
+    }
+}
+
+/// lint を実行できなかった理由。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintError {
+    /// 構文エラーを含むソースは検査しない。
+    Parse {
+        message: String,
+        line: u32,
+        column: u32,
+    },
+}
+
+impl fmt::Display for LintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintError::Parse {
+                message,
+                line,
+                column,
+            } => write!(
+                f,
+                "{line}:{column}: 構文解析に失敗したため lint を実行できません: {message}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LintError {}
+
+/// ソースを構文解析・型検査したうえで lint を適用する。
+///
+/// 型エラーは lint の対象外であり、型付き AST が得られる限り検査を続ける。
+pub fn lint_source(source: &str, options: &LintOptions) -> Result<Vec<LintFinding>, LintError> {
+    let parsed = ParserDriver::parse(source);
+    let error = parsed
+        .diagnostics
+        .iter()
+        .find(|diag| diag.severity_or_default() == DiagnosticSeverity::Error);
+    let module = match (parsed.value.as_ref(), error) {
+        (Some(module), None) => module,
+        (_, error) => {
+            let offset = error
+                .and_then(FrontendDiagnostic::primary_span)
+                .map_or(0, |span| span.start as usize);
+            let (line, column) = LineIndex::new(source).line_col(offset);
+            return Err(LintError::Parse {
+                message: error.map_or_else(
+                    || "モジュールを構築できませんでした".to_string(),
+                    |diag| diag.message.clone(),
+                ),
+                line,
+                column,
+            });
+        }
+    };
+    let report = TypecheckDriver::infer_module(Some(module), &TypecheckConfig::default());
+    Ok(lint_module(source, module, &report.typed_module, options))
+}
+
+/// モジュール全体に lint を適用し、ソース順に並べた結果を返す。
+pub fn lint_module(
+    source: &str,
+    module: &Module,
+    typed_module: &TypedModule,
+    options: &LintOptions,
+) -> Vec<LintFinding> {
+    let mut sink = LintSink::new(source, module, options);
+    scope::check_module(&mut sink, module);
+    typed::check_module(&mut sink, typed_module);
+    let mut findings = sink.findings;
+    findings.sort_by_key(|finding| (finding.span.start, finding.span.end));
+    findings
+}
+
+/// 重なりのない fix-it をソースへ適用する。重なる修正は先に現れたものを優先する。
+pub fn apply_fixits(source: &str, fixits: &[DiagnosticFixIt]) -> String {
+    let mut ordered: Vec<&DiagnosticFixIt> = fixits.iter().collect();
+    ordered.sort_by_key(|fixit| (fixit.span().start, fixit.span().end));
+    let mut output = String::with_capacity(source.len());
+    let mut cursor = 0usize;
+    for fixit in ordered {
+        let span = fixit.span();
+        let (start, end) = (span.start as usize, span.end as usize);
+        if start < cursor || end > source.len() {
+            continue;
+        }
+        output.push_str(&source[cursor..start]);
+        output.push_str(fixit.text().unwrap_or_default());
+        cursor = end;
+    }
+    output.push_str(&source[cursor..]);
+    output
+}
+
+/// 属性によるレベル上書きの適用範囲。
+struct ScopedLevels {
+    span: Span,
+    levels: LintLevelMap,
+}
+
+/// 各規則から報告を受け取り、レベルを解決して蓄積する。
+pub(crate) struct LintSink<'a> {
+    source: &'a str,
+    options: &'a LintOptions,
+    file_levels: LintLevelMap,
+    function_levels: Vec<ScopedLevels>,
+    findings: Vec<LintFinding>,
+}
+
+impl<'a> LintSink<'a> {
+    fn new(source: &'a str, module: &Module, options: &'a LintOptions) -> Self {
+        let file_levels = module
+            .header
+            .as_ref()
+            .map(|header| attribute_levels(&header.attrs))
+            .unwrap_or_default();
+        let function_levels = module
+            .functions
+            .iter()
+            .map(|function| ScopedLevels {
+                span: function.span,
+                levels: attribute_levels(&function.attrs),
+            })
+            .filter(|scoped| !scoped.levels.is_empty())
+            .collect();
+        Self {
+            source,
+            options,
+            file_levels,
+            function_levels,
+            findings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub(crate) fn identifier_profile(&self) -> IdentifierProfile {
+        self.options.identifier_profile
+    }
+
+    /// ソース上の `span` の文字列。範囲外なら空文字列を返す。
+    pub(crate) fn text(&self, span: Span) -> &'a str {
+        self.source
+            .get(span.start as usize..span.end as usize)
+            .unwrap_or_default()
+    }
+
+    fn level_for(&self, rule: &LintRule, span: Span) -> LintLevel {
+        let mut level = rule.default_level;
+        let scoped = self
+            .function_levels
+            .iter()
+            .filter(|scoped| scoped.span.start <= span.start && span.end <= scoped.span.end)
+            .map(|scoped| &scoped.levels);
+        let layers = [&self.options.levels, &self.file_levels]
+            .into_iter()
+            .chain(scoped);
+        for layer in layers {
+            if let Some(overridden) = layer.level_for(rule.id) {
+                level = overridden;
+            }
+        }
+        level
+    }
+
+    pub(crate) fn emit(
+        &mut self,
+        rule: &'static LintRule,
+        span: Span,
+        message: impl Into<String>,
+        fixits: Vec<DiagnosticFixIt>,
+    ) {
+        let level = self.level_for(rule, span);
+        if level == LintLevel::Allow {
+            return;
+        }
+        self.findings.push(LintFinding {
+            rule,
+            level,
+            message: message.into(),
+            span,
+            fixits,
+        });
+    }
+}
+
+/// `@allow(...)` / `@warn(...)` / `@deny(...)` 属性をレベル上書きへ変換する。
+///
+/// 引数は文字列リテラル（ワイルドカード可）または識別子で指定する。
+fn attribute_levels(attrs: &[Attribute]) -> LintLevelMap {
+    let mut levels = LintLevelMap::new();
+    for attr in attrs {
+        let level = match attr.name.name.as_str() {
+            "allow" => LintLevel::Allow,
+            "warn" => LintLevel::Warn,
+            "deny" => LintLevel::Deny,
+            _ => continue,
+        };
+        for arg in &attr.args {
+            match &arg.kind {
+                ExprKind::Literal(literal) => {
+                    if let LiteralKind::String { value, .. } = &literal.value {
+                        levels.insert(value.clone(), level);
+                    }
+                }
+                ExprKind::Identifier(ident) => levels.insert(ident.name.clone(), level),
+                _ => {}
+            }
+        }
+    }
+    levels
+}
+
+/// `span` が 1 行を占める場合は、その行全体（改行を含む）へ広げる。
+pub(crate) fn whole_line_span(source: &str, span: Span) -> Span {
+    let (start, end) = (span.start as usize, span.end as usize);
+    if end > source.len() {
+        return span;
+    }
+    let line_start = source[..start].rfind('\n').map_or(0, |idx| idx + 1);
+    let line_end = source[end..]
+        .find('\n')
+        .map_or(source.len(), |idx| end + idx + 1);
+    let before_blank = source[line_start..start].trim().is_empty();
+    let after_blank = source[end..line_end].trim().is_empty();
+    if before_blank && after_blank {
+        Span::new(line_start as u32, line_end as u32)
+    } else {
+        span
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rule_ids_and_codes_are_consistent() {
+        for rule in rules() {
+            assert_eq!(rule.code, format!("lint.{}", rule.id));
+            assert_eq!(find_rule(rule.id), Some(rule));
+            assert_eq!(find_rule(rule.code), Some(rule));
+        }
+    }
+
+    #[test]
+    fn apply_fixits_skips_overlapping_edits() {
+        let source = "let value = 1";
+        let fixits = vec![
+            DiagnosticFixIt::replace(Span::new(4, 9), "_value"),
+            DiagnosticFixIt::delete(Span::new(6, 11)),
+            DiagnosticFixIt::replace(Span::new(0, 3), "var"),
+        ];
+        assert_eq!(apply_fixits(source, &fixits), "var _value = 1");
+    }
+
+    #[test]
+    fn whole_line_span_covers_standalone_statements() {
+        let source = "{\n  defer 1\n  x\n}";
+        assert_eq!(whole_line_span(source, Span::new(4, 11)), Span::new(2, 12));
+        let inline = "let a = 1; defer 1";
+        assert_eq!(
+            whole_line_span(inline, Span::new(11, 18)),
+            Span::new(11, 18)
+        );
+    }
+}
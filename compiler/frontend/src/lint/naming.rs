@@ -0,0 +1,136 @@
+//! 命名規約の判定と変換。
+//!
+//! 大文字・小文字の区別は Unicode の文字種で判定し、漢字や仮名のように大小の
+//! 区別を持たない文字はどちらの規約にも適合するものとして扱う。
+
+use crate::lexer::IdentifierProfile;
+
+/// 識別子が満たすべき命名規約。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum NamingStyle {
+    /// 関数・引数・束縛などの値（`snake_case`）。
+    Value,
+    /// 型・列挙子などの型レベルの名前（`UpperCamelCase`）。
+    Type,
+}
+
+/// 規約違反の内容。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum NamingViolation {
+    /// 規約に合わない。`suggestion` は変換後の名前。
+    Style { suggestion: String },
+    /// `ascii-compat` プロファイルで ASCII 以外の文字を含む。
+    NonAscii,
+}
+
+pub(super) fn check_name(
+    name: &str,
+    style: NamingStyle,
+    profile: IdentifierProfile,
+) -> Option<NamingViolation> {
+    if profile == IdentifierProfile::AsciiCompat && !name.is_ascii() {
+        return Some(NamingViolation::NonAscii);
+    }
+    let conforms = match style {
+        NamingStyle::Value => is_snake_case(name),
+        NamingStyle::Type => is_upper_camel_case(name),
+    };
+    if conforms {
+        return None;
+    }
+    let suggestion = match style {
+        NamingStyle::Value => to_snake_case(name),
+        NamingStyle::Type => to_upper_camel_case(name),
+    };
+    (suggestion != name && !suggestion.trim_matches('_').is_empty())
+        .then_some(NamingViolation::Style { suggestion })
+}
+
+/// 先頭の `_` は未使用を示す慣習として許容する。
+fn is_snake_case(name: &str) -> bool {
+    let body = name.trim_start_matches('_');
+    !body.chars().any(char::is_uppercase) && !body.contains("__")
+}
+
+fn is_upper_camel_case(name: &str) -> bool {
+    let body = name.trim_start_matches('_');
+    let first_is_lower = body.chars().next().is_some_and(char::is_lowercase);
+    !first_is_lower && !body.contains('_')
+}
+
+fn to_snake_case(name: &str) -> String {
+    let leading = name.len() - name.trim_start_matches('_').len();
+    let mut output = String::from(&name[..leading]);
+    let chars: Vec<char> = name[leading..].chars().collect();
+    for (idx, ch) in chars.iter().enumerate() {
+        if *ch == '_' {
+            if !output.ends_with('_') {
+                output.push('_');
+            }
+            continue;
+        }
+        if ch.is_uppercase() && idx > 0 {
+            let prev = chars[idx - 1];
+            let next_is_lower = chars.get(idx + 1).is_some_and(|next| next.is_lowercase());
+            let boundary = prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower);
+            if boundary && !output.ends_with('_') {
+                output.push('_');
+            }
+        }
+        output.extend(ch.to_lowercase());
+    }
+    output
+}
+
+fn to_upper_camel_case(name: &str) -> String {
+    let leading = name.len() - name.trim_start_matches('_').len();
+    let mut output = String::from(&name[..leading]);
+    for part in name[leading..].split('_').filter(|part| !part.is_empty()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            output.extend(first.to_uppercase());
+            output.push_str(chars.as_str());
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_between_styles() {
+        assert_eq!(to_snake_case("parseHTTPRequest"), "parse_http_request");
+        assert_eq!(to_snake_case("_tmpValue"), "_tmp_value");
+        assert_eq!(to_snake_case("double__under"), "double_under");
+        assert_eq!(to_upper_camel_case("token_kind"), "TokenKind");
+    }
+
+    #[test]
+    fn caseless_scripts_conform_to_both_styles() {
+        let profile = IdentifierProfile::Unicode;
+        assert_eq!(check_name("合計", NamingStyle::Value, profile), None);
+        assert_eq!(check_name("合計", NamingStyle::Type, profile), None);
+        assert_eq!(
+            check_name("Größe", NamingStyle::Value, profile),
+            Some(NamingViolation::Style {
+                suggestion: "größe".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn ascii_profile_rejects_non_ascii_names() {
+        assert_eq!(
+            check_name("合計", NamingStyle::Value, IdentifierProfile::AsciiCompat),
+            Some(NamingViolation::NonAscii)
+        );
+        assert_eq!(
+            check_name("total", NamingStyle::Value, IdentifierProfile::AsciiCompat),
+            None
+        );
+    }
+}
@@ -0,0 +1,740 @@
+//! スコープ解析に基づく規則（`unused_binding` / `unused_import` / `shadowed_binding` /
+//! `needless_var` / `naming_convention`）。
+//!
+//! 束縛はスコープを抜ける時点で判定し、参照箇所を集めたうえで改名の fix-it を
+//! 組み立てる。`use` の参照判定は `use` 宣言以外に現れる識別子トークンとの
+//! 名前一致で行うため、同名のローカル束縛がある場合は使用済みとみなす。
+
+use std::collections::HashSet;
+
+use crate::diagnostic::DiagnosticFixIt;
+use crate::lexer::lex_source;
+use crate::parser::ast::{
+    DeclKind, Expr, ExprKind, Function, HandlerDecl, HandlerEntry, Ident, ImplItem, LiteralKind,
+    Module, ModulePath, Param, Pattern, PatternKind, RelativeHead, SlicePatternItem, Stmt,
+    StmtKind, UseDecl, UseItem, UseTree,
+};
+use crate::span::Span;
+use crate::token::TokenKind;
+
+use super::naming::{check_name, NamingStyle, NamingViolation};
+use super::{
+    whole_line_span, LintSink, NAMING_CONVENTION, NEEDLESS_VAR, SHADOWED_BINDING, UNUSED_BINDING,
+    UNUSED_IMPORT,
+};
+
+pub(super) fn check_module(sink: &mut LintSink<'_>, module: &Module) {
+    check_imports(sink, module);
+    for function in &module.functions {
+        check_function(sink, function);
+    }
+    for active in &module.active_patterns {
+        ScopeWalker::new(sink).walk_callable(&active.params, &active.body);
+    }
+    for decl in &module.decls {
+        check_top_level_decl(sink, &decl.kind);
+    }
+    for expr in &module.exprs {
+        ScopeWalker::new(sink).expr(expr);
+    }
+}
+
+fn check_function(sink: &mut LintSink<'_>, function: &Function) {
+    check_declared_name(sink, &function.name, NamingStyle::Value, "関数");
+    ScopeWalker::new(sink).walk_callable(&function.params, &function.body);
+}
+
+fn check_top_level_decl(sink: &mut LintSink<'_>, kind: &DeclKind) {
+    match kind {
+        DeclKind::Let { value, .. } | DeclKind::Var { value, .. } => {
+            ScopeWalker::new(sink).expr(value);
+        }
+        DeclKind::Const { value, .. } => ScopeWalker::new(sink).expr(value),
+        DeclKind::Type { decl } => {
+            check_declared_name(sink, &decl.name, NamingStyle::Type, "型");
+        }
+        DeclKind::Struct(decl) => {
+            check_declared_name(sink, &decl.name, NamingStyle::Type, "構造体");
+        }
+        DeclKind::Enum(decl) => {
+            check_declared_name(sink, &decl.name, NamingStyle::Type, "列挙型");
+            for variant in &decl.variants {
+                check_declared_name(sink, &variant.name, NamingStyle::Type, "列挙子");
+            }
+        }
+        DeclKind::Impl(impl_decl) => {
+            for item in &impl_decl.items {
+                match item {
+                    ImplItem::Function(function) => check_function(sink, function),
+                    ImplItem::Decl(decl) => check_top_level_decl(sink, &decl.kind),
+                }
+            }
+        }
+        DeclKind::Handler(handler) => ScopeWalker::new(sink).handler(handler),
+        _ => {}
+    }
+}
+
+/// 関数・型など、参照箇所を追跡しない宣言の命名を検査する。
+///
+/// 呼び出し側を書き換えられないため fix-it は付けず、候補名をメッセージで示す。
+fn check_declared_name(sink: &mut LintSink<'_>, ident: &Ident, style: NamingStyle, label: &str) {
+    match check_name(&ident.name, style, sink.identifier_profile()) {
+        Some(NamingViolation::Style { suggestion }) => {
+            let message = format!(
+                "{label} `{}` は {} で命名してください（候補: `{suggestion}`）",
+                ident.name,
+                style_label(style)
+            );
+            sink.emit(&NAMING_CONVENTION, ident.span, message, Vec::new());
+        }
+        Some(NamingViolation::NonAscii) => {
+            sink.emit(
+                &NAMING_CONVENTION,
+                ident.span,
+                non_ascii_message(label, &ident.name),
+                Vec::new(),
+            );
+        }
+        None => {}
+    }
+}
+
+fn style_label(style: NamingStyle) -> &'static str {
+    match style {
+        NamingStyle::Value => "snake_case",
+        NamingStyle::Type => "UpperCamelCase",
+    }
+}
+
+fn non_ascii_message(label: &str, name: &str) -> String {
+    format!("{label} `{name}` は ascii-compat プロファイルで使用できない文字を含んでいます")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindingKind {
+    Param,
+    Let,
+    Var,
+    Pattern,
+}
+
+impl BindingKind {
+    fn label(self) -> &'static str {
+        match self {
+            BindingKind::Param => "引数",
+            BindingKind::Let | BindingKind::Var => "束縛",
+            BindingKind::Pattern => "パターン変数",
+        }
+    }
+}
+
+struct Binding {
+    ident: Ident,
+    kind: BindingKind,
+    /// `var x = ...` の `var` キーワード（単一の変数を束縛する場合のみ）。
+    keyword: Option<Span>,
+    used: bool,
+    reassigned: bool,
+    /// 参照・代入箇所。改名の fix-it で宣言と一緒に置換する。
+    references: Vec<Span>,
+    /// or パターンやレコードの省略記法など、宣言を単独で改名できない束縛では false。
+    renamable: bool,
+    /// 隠している外側の束縛の位置。
+    shadows: Option<Span>,
+}
+
+impl Binding {
+    fn rename_fixits(&self, name: &str) -> Vec<DiagnosticFixIt> {
+        if !self.renamable {
+            return Vec::new();
+        }
+        std::iter::once(self.ident.span)
+            .chain(self.references.iter().copied())
+            .map(|span| DiagnosticFixIt::replace(span, name))
+            .collect()
+    }
+}
+
+/// 関数本体などを 1 つずつ走査するスコープ解析器。
+struct ScopeWalker<'s, 'a> {
+    sink: &'s mut LintSink<'a>,
+    scopes: Vec<Vec<Binding>>,
+}
+
+impl<'s, 'a> ScopeWalker<'s, 'a> {
+    fn new(sink: &'s mut LintSink<'a>) -> Self {
+        Self {
+            sink,
+            scopes: Vec::new(),
+        }
+    }
+
+    fn walk_callable(&mut self, params: &[Param], body: &Expr) {
+        self.push();
+        self.params(params);
+        self.expr(body);
+        self.pop();
+    }
+
+    fn params(&mut self, params: &[Param]) {
+        for param in params {
+            if let Some(default) = &param.default {
+                self.expr(default);
+            }
+            self.declare_pattern(&param.pattern, BindingKind::Param, None);
+        }
+    }
+
+    fn handler(&mut self, handler: &HandlerDecl) {
+        for entry in &handler.entries {
+            match entry {
+                HandlerEntry::Operation { params, body, .. } => self.walk_callable(params, body),
+                HandlerEntry::Return {
+                    value_ident, body, ..
+                } => {
+                    self.push();
+                    self.declare(value_ident, BindingKind::Param, None, true);
+                    self.expr(body);
+                    self.pop();
+                }
+            }
+        }
+    }
+
+    fn push(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn pop(&mut self) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        for binding in scope {
+            self.report(binding);
+        }
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern, kind: BindingKind, keyword: Option<Span>) {
+        let mut bindings = Vec::new();
+        let mut guards = Vec::new();
+        collect_pattern_bindings(pattern, true, &mut bindings, &mut guards);
+        let single = matches!(pattern.kind, PatternKind::Var(_));
+        let mut seen = HashSet::new();
+        for (ident, renamable) in bindings {
+            if seen.insert(ident.name.clone()) {
+                let keyword = if single { keyword } else { None };
+                self.declare(ident, kind, keyword, renamable);
+            }
+        }
+        for guard in guards {
+            self.expr(guard);
+        }
+    }
+
+    fn declare(
+        &mut self,
+        ident: &Ident,
+        kind: BindingKind,
+        keyword: Option<Span>,
+        renamable: bool,
+    ) {
+        let outer_len = self.scopes.len().saturating_sub(1);
+        let shadows = self.scopes[..outer_len]
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|binding| binding.ident.name == ident.name)
+            .map(|binding| binding.ident.span);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(Binding {
+                ident: ident.clone(),
+                kind,
+                keyword,
+                used: false,
+                reassigned: false,
+                references: Vec::new(),
+                renamable,
+                shadows,
+            });
+        }
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|binding| binding.ident.name == name)
+    }
+
+    fn use_ident(&mut self, ident: &Ident) {
+        if let Some(binding) = self.lookup(&ident.name) {
+            binding.used = true;
+            binding.references.push(ident.span);
+        }
+    }
+
+    fn report(&mut self, binding: Binding) {
+        let name = binding.ident.name.as_str();
+        if name.starts_with('_') || name == "self" {
+            return;
+        }
+        if !binding.used {
+            let message = if binding.reassigned {
+                format!(
+                    "{} `{name}` は代入されるだけで参照されていません",
+                    binding.kind.label()
+                )
+            } else {
+                format!("{} `{name}` は使用されていません", binding.kind.label())
+            };
+            let fixits = binding.rename_fixits(&format!("_{name}"));
+            self.sink
+                .emit(&UNUSED_BINDING, binding.ident.span, message, fixits);
+        } else if binding.kind == BindingKind::Var && !binding.reassigned {
+            if let Some(keyword) = binding.keyword {
+                self.sink.emit(
+                    &NEEDLESS_VAR,
+                    Span::new(keyword.start, binding.ident.span.end),
+                    format!("`{name}` は再代入されないため `let` で束縛できます"),
+                    vec![DiagnosticFixIt::replace(keyword, "let")],
+                );
+            }
+        }
+        if let Some(outer) = binding.shadows {
+            let fixits = binding.rename_fixits(&format!("{name}_inner"));
+            self.sink.emit(
+                &SHADOWED_BINDING,
+                binding.ident.span,
+                format!(
+                    "{} `{name}` が外側の束縛（{}..{}）を隠しています",
+                    binding.kind.label(),
+                    outer.start,
+                    outer.end
+                ),
+                fixits,
+            );
+        }
+        match check_name(name, NamingStyle::Value, self.sink.identifier_profile()) {
+            Some(NamingViolation::Style { suggestion }) => {
+                let fixits = binding.rename_fixits(&suggestion);
+                self.sink.emit(
+                    &NAMING_CONVENTION,
+                    binding.ident.span,
+                    format!(
+                        "{} `{name}` は snake_case で命名してください（候補: `{suggestion}`）",
+                        binding.kind.label()
+                    ),
+                    fixits,
+                );
+            }
+            Some(NamingViolation::NonAscii) => {
+                self.sink.emit(
+                    &NAMING_CONVENTION,
+                    binding.ident.span,
+                    non_ascii_message(binding.kind.label(), name),
+                    Vec::new(),
+                );
+            }
+            None => {}
+        }
+    }
+
+    fn block(&mut self, statements: &[Stmt]) {
+        self.push();
+        for stmt in statements {
+            self.stmt(stmt);
+        }
+        self.pop();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Decl { decl } => {
+                let keyword = Span::new(decl.span.start, decl.span.start + 3);
+                match &decl.kind {
+                    DeclKind::Let { pattern, value, .. } => {
+                        self.expr(value);
+                        self.declare_pattern(pattern, BindingKind::Let, None);
+                    }
+                    DeclKind::Var { pattern, value, .. } => {
+                        self.expr(value);
+                        let keyword = (self.sink.text(keyword) == "var").then_some(keyword);
+                        self.declare_pattern(pattern, BindingKind::Var, keyword);
+                    }
+                    DeclKind::Const { name, value, .. } => {
+                        self.expr(value);
+                        self.declare(name, BindingKind::Let, None, true);
+                    }
+                    DeclKind::Handler(handler) => self.handler(handler),
+                    _ => {}
+                }
+            }
+            StmtKind::Expr { expr } | StmtKind::Defer { expr } => self.expr(expr),
+            StmtKind::Assign { target, value } => {
+                self.expr(value);
+                self.assign_target(target);
+            }
+        }
+    }
+
+    /// 代入先の根にある束縛を再代入済みにする。フィールドや添字への代入は
+    /// 対象の読み出しも伴うため、参照としても数える。
+    fn assign_target(&mut self, target: &Expr) {
+        match &target.kind {
+            ExprKind::Identifier(ident) => {
+                if let Some(binding) = self.lookup(&ident.name) {
+                    binding.reassigned = true;
+                    binding.references.push(ident.span);
+                }
+            }
+            ExprKind::FieldAccess { target: inner, .. }
+            | ExprKind::TupleAccess { target: inner, .. } => {
+                self.assign_target(inner);
+                self.expr(inner);
+            }
+            ExprKind::Index {
+                target: inner,
+                index,
+            } => {
+                self.assign_target(inner);
+                self.expr(inner);
+                self.expr(index);
+            }
+            _ => self.expr(target),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Literal(literal) => match &literal.value {
+                LiteralKind::Tuple { elements }
+                | LiteralKind::Array { elements }
+                | LiteralKind::Set { elements } => {
+                    for element in elements {
+                        self.expr(element);
+                    }
+                }
+                LiteralKind::Record { fields, .. } => {
+                    for field in fields {
+                        match &field.value.kind {
+                            // `{ x }` の省略記法はフィールド名も兼ねるため改名できない。
+                            ExprKind::Identifier(ident) if ident.span == field.key.span => {
+                                self.use_ident(ident);
+                                if let Some(binding) = self.lookup(&ident.name) {
+                                    binding.renamable = false;
+                                }
+                            }
+                            _ => self.expr(&field.value),
+                        }
+                    }
+                }
+                _ => {}
+            },
+            ExprKind::Identifier(ident) => self.use_ident(ident),
+            ExprKind::FixityLiteral(_) | ExprKind::ModulePath(_) | ExprKind::Continue => {}
+            ExprKind::Call { callee, args } => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            ExprKind::PerformCall { call } => self.expr(&call.argument),
+            ExprKind::Lambda { params, body, .. } => self.walk_callable(params, body),
+            ExprKind::Pipe { left, right }
+            | ExprKind::Binary { left, right, .. }
+            | ExprKind::Index {
+                target: left,
+                index: right,
+            } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary { expr: inner, .. }
+            | ExprKind::Rec { expr: inner }
+            | ExprKind::Propagate { expr: inner }
+            | ExprKind::Await { expr: inner }
+            | ExprKind::FieldAccess { target: inner, .. }
+            | ExprKind::TupleAccess { target: inner, .. }
+            | ExprKind::Loop { body: inner }
+            | ExprKind::EffectBlock { body: inner }
+            | ExprKind::Async { body: inner, .. }
+            | ExprKind::Unsafe { body: inner }
+            | ExprKind::Defer { body: inner } => self.expr(inner),
+            ExprKind::IfElse {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.expr(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.expr(else_branch);
+                }
+            }
+            ExprKind::Match { target, arms } => {
+                self.expr(target);
+                for arm in arms {
+                    self.push();
+                    self.declare_pattern(&arm.pattern, BindingKind::Pattern, None);
+                    if let Some(alias) = &arm.alias {
+                        self.declare(alias, BindingKind::Pattern, None, true);
+                    }
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                    self.pop();
+                }
+            }
+            ExprKind::While { condition, body } => {
+                self.expr(condition);
+                self.expr(body);
+            }
+            ExprKind::For {
+                pattern,
+                start,
+                end,
+            } => {
+                self.expr(start);
+                self.push();
+                self.declare_pattern(pattern, BindingKind::Pattern, None);
+                self.expr(end);
+                self.pop();
+            }
+            ExprKind::Break { value } | ExprKind::Return { value } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            ExprKind::Handle { handle } => {
+                self.expr(&handle.target);
+                self.handler(&handle.handler);
+            }
+            ExprKind::Block { statements, .. } => self.block(statements),
+            ExprKind::Assign { target, value } => {
+                self.expr(value);
+                self.assign_target(target);
+            }
+            ExprKind::InlineAsm(asm) => {
+                for output in &asm.outputs {
+                    self.assign_target(&output.target);
+                }
+                for input in &asm.inputs {
+                    self.expr(&input.expr);
+                }
+            }
+            ExprKind::LlvmIr(ir) => {
+                for input in &ir.inputs {
+                    self.expr(input);
+                }
+            }
+        }
+    }
+}
+
+/// パターンが導入する束縛と、パターン内ガード式を集める。
+fn collect_pattern_bindings<'p>(
+    pattern: &'p Pattern,
+    renamable: bool,
+    bindings: &mut Vec<(&'p Ident, bool)>,
+    guards: &mut Vec<&'p Expr>,
+) {
+    match &pattern.kind {
+        // 大文字で始まる名前は引数なしのコンストラクタとして扱う。
+        PatternKind::Var(ident) => {
+            if !ident.name.chars().next().is_some_and(char::is_uppercase) {
+                bindings.push((ident, renamable));
+            }
+        }
+        PatternKind::Binding { name, pattern, .. } => {
+            bindings.push((name, renamable));
+            collect_pattern_bindings(pattern, renamable, bindings, guards);
+        }
+        PatternKind::Tuple { elements } => {
+            for element in elements {
+                collect_pattern_bindings(element, renamable, bindings, guards);
+            }
+        }
+        PatternKind::Constructor { args, .. } => {
+            for arg in args {
+                collect_pattern_bindings(arg, renamable, bindings, guards);
+            }
+        }
+        PatternKind::Record { fields, .. } => {
+            for field in fields {
+                match &field.value {
+                    Some(value) => collect_pattern_bindings(value, renamable, bindings, guards),
+                    None => bindings.push((&field.key, false)),
+                }
+            }
+        }
+        PatternKind::Guard { pattern, guard } => {
+            collect_pattern_bindings(pattern, renamable, bindings, guards);
+            guards.push(guard);
+        }
+        PatternKind::Or { variants } => {
+            for variant in variants {
+                collect_pattern_bindings(variant, false, bindings, guards);
+            }
+        }
+        PatternKind::Slice { elements } => {
+            for element in elements {
+                match element {
+                    SlicePatternItem::Element(pattern) => {
+                        collect_pattern_bindings(pattern, renamable, bindings, guards)
+                    }
+                    SlicePatternItem::Rest { ident: Some(ident) } => {
+                        bindings.push((ident, renamable))
+                    }
+                    SlicePatternItem::Rest { ident: None } => {}
+                }
+            }
+        }
+        PatternKind::ActivePattern {
+            argument: Some(argument),
+            ..
+        } => collect_pattern_bindings(argument, renamable, bindings, guards),
+        PatternKind::ActivePattern { argument: None, .. }
+        | PatternKind::Literal(_)
+        | PatternKind::Wildcard
+        | PatternKind::Range { .. }
+        | PatternKind::Regex { .. } => {}
+    }
+}
+
+/// `use` 宣言が導入する名前のうち、どこからも参照されないものを報告する。
+fn check_imports(sink: &mut LintSink<'_>, module: &Module) {
+    if module.uses.iter().all(|decl| decl.is_pub) {
+        return;
+    }
+    let use_spans: Vec<Span> = module.uses.iter().map(|decl| decl.span).collect();
+    let referenced: HashSet<String> = lex_source(sink.source())
+        .tokens
+        .into_iter()
+        .filter(|token| {
+            matches!(
+                token.kind,
+                TokenKind::Identifier | TokenKind::UpperIdentifier
+            )
+        })
+        .filter(|token| {
+            !use_spans
+                .iter()
+                .any(|span| span.start <= token.span.start && token.span.end <= span.end)
+        })
+        .filter_map(|token| token.lexeme)
+        .collect();
+    for decl in module.uses.iter().filter(|decl| !decl.is_pub) {
+        check_use_decl(sink, decl, &referenced);
+    }
+}
+
+fn check_use_decl(sink: &mut LintSink<'_>, decl: &UseDecl, referenced: &HashSet<String>) {
+    match &decl.tree {
+        UseTree::Path { path, alias } => {
+            // `use Core` のようなルートモジュールの取り込みは名前空間を開くため対象外。
+            if alias.is_none() && is_single_segment(path) {
+                return;
+            }
+            let Some(binding) = alias.as_ref().or_else(|| last_segment(path)) else {
+                return;
+            };
+            if !referenced.contains(&binding.name) {
+                let fixit = DiagnosticFixIt::delete(whole_line_span(sink.source(), decl.span));
+                sink.emit(
+                    &UNUSED_IMPORT,
+                    decl.span,
+                    format!("`{}` は使用されていません", binding.name),
+                    vec![fixit],
+                );
+            }
+        }
+        UseTree::Brace { path, items } => {
+            let mut unused = Vec::new();
+            let kept = retain_used_items(items, referenced, &mut unused);
+            if unused.is_empty() {
+                return;
+            }
+            let fixit = if kept.is_empty() {
+                DiagnosticFixIt::delete(whole_line_span(sink.source(), decl.span))
+            } else {
+                let rewritten = UseDecl {
+                    is_pub: decl.is_pub,
+                    tree: UseTree::Brace {
+                        path: path.clone(),
+                        items: kept,
+                    },
+                    span: decl.span,
+                };
+                DiagnosticFixIt::replace(decl.span, rewritten.render())
+            };
+            for item in unused {
+                sink.emit(
+                    &UNUSED_IMPORT,
+                    item.span,
+                    format!("`{}` は使用されていません", item.name),
+                    vec![fixit.clone()],
+                );
+            }
+        }
+    }
+}
+
+/// 参照される項目だけを残した `use` 項目列を返し、除いた名前を `unused` へ積む。
+fn retain_used_items(
+    items: &[UseItem],
+    referenced: &HashSet<String>,
+    unused: &mut Vec<Ident>,
+) -> Vec<UseItem> {
+    let mut kept = Vec::new();
+    for item in items {
+        if item.glob {
+            kept.push(item.clone());
+            continue;
+        }
+        if !item.nested.is_empty() {
+            let nested = retain_used_items(&item.nested, referenced, unused);
+            if !nested.is_empty() {
+                kept.push(UseItem {
+                    nested,
+                    ..item.clone()
+                });
+            }
+            continue;
+        }
+        let Some(binding) = item.alias.as_ref().or(item.name.as_ref()) else {
+            kept.push(item.clone());
+            continue;
+        };
+        if referenced.contains(&binding.name) {
+            kept.push(item.clone());
+        } else {
+            unused.push(Ident {
+                name: binding.name.clone(),
+                span: item.span,
+            });
+        }
+    }
+    kept
+}
+
+fn is_single_segment(path: &ModulePath) -> bool {
+    match path {
+        ModulePath::Root { segments } => segments.len() <= 1,
+        ModulePath::Relative { head, segments } => {
+            segments.is_empty() && matches!(head, RelativeHead::PlainIdent(_))
+        }
+    }
+}
+
+fn last_segment(path: &ModulePath) -> Option<&Ident> {
+    match path {
+        ModulePath::Root { segments } => segments.last(),
+        ModulePath::Relative { head, segments } => segments.last().or(match head {
+            RelativeHead::PlainIdent(ident) => Some(ident),
+            RelativeHead::Self_ | RelativeHead::Super(_) => None,
+        }),
+    }
+}
@@ -0,0 +1,363 @@
+//! 型付き AST に基づく規則（`unreachable_arm` / `redundant_propagate` /
+//! `needless_unsafe` / `needless_defer`）。
+
+use std::collections::HashSet;
+
+use crate::diagnostic::DiagnosticFixIt;
+use crate::semantics::typed::{
+    TypedExpr, TypedExprKind, TypedMatchArm, TypedModule, TypedPattern, TypedPatternKind,
+    TypedStmt, TypedStmtKind,
+};
+use crate::span::Span;
+
+use super::{
+    whole_line_span, LintSink, NEEDLESS_DEFER, NEEDLESS_UNSAFE, REDUNDANT_PROPAGATE,
+    UNREACHABLE_ARM,
+};
+
+pub(super) fn check_module(sink: &mut LintSink<'_>, typed_module: &TypedModule) {
+    let safe_functions: HashSet<&str> = typed_module
+        .functions
+        .iter()
+        .filter(|function| !function.is_unsafe)
+        .map(|function| function.name.as_str())
+        .collect();
+    let mut walker = TypedWalker {
+        sink,
+        safe_functions,
+    };
+    for function in &typed_module.functions {
+        walker.expr(&function.body);
+    }
+    for active in &typed_module.active_patterns {
+        walker.expr(&active.body);
+    }
+    for actor in &typed_module.actor_specs {
+        walker.expr(&actor.body);
+    }
+}
+
+struct TypedWalker<'s, 'a, 'm> {
+    sink: &'s mut LintSink<'a>,
+    /// `unsafe` 指定のないモジュール内関数。外部関数は含めない。
+    safe_functions: HashSet<&'m str>,
+}
+
+impl TypedWalker<'_, '_, '_> {
+    fn expr(&mut self, expr: &TypedExpr) {
+        match &expr.kind {
+            TypedExprKind::Call { callee, args, .. } => {
+                self.check_redundant_propagate(expr, callee, args);
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            TypedExprKind::Match { target, arms, .. } => {
+                self.check_unreachable_arms(arms);
+                self.expr(target);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                }
+            }
+            TypedExprKind::Unsafe { body } => {
+                if !self.requires_unsafe(body) {
+                    let text = self.sink.text(body.span).to_string();
+                    self.sink.emit(
+                        &NEEDLESS_UNSAFE,
+                        expr.span,
+                        "unsafe 操作を含まないため `unsafe` は不要です",
+                        vec![DiagnosticFixIt::replace(expr.span, text)],
+                    );
+                }
+                self.expr(body);
+            }
+            TypedExprKind::Block {
+                statements,
+                tail,
+                defers,
+            } => {
+                for stmt in statements {
+                    self.stmt(stmt);
+                }
+                if let Some(tail) = separate_tail(statements, tail.as_deref()) {
+                    self.expr(tail);
+                }
+                for defer in defers {
+                    self.expr(defer);
+                }
+            }
+            TypedExprKind::Lambda { body, .. }
+            | TypedExprKind::EffectBlock { body }
+            | TypedExprKind::Async { body, .. } => self.expr(body),
+            TypedExprKind::Rec { target: inner, .. }
+            | TypedExprKind::Propagate { expr: inner }
+            | TypedExprKind::Unary { operand: inner, .. }
+            | TypedExprKind::Await { expr: inner }
+            | TypedExprKind::FieldAccess { target: inner, .. }
+            | TypedExprKind::TupleAccess { target: inner, .. } => self.expr(inner),
+            TypedExprKind::Return { value } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            TypedExprKind::Binary { left, right, .. }
+            | TypedExprKind::Index {
+                target: left,
+                index: right,
+            } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            TypedExprKind::IfElse {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.expr(then_branch);
+                self.expr(else_branch);
+            }
+            TypedExprKind::PerformCall { call } => self.expr(&call.argument),
+            TypedExprKind::Handle { target, handler } => {
+                self.expr(target);
+                for operation in &handler.operations {
+                    self.expr(&operation.body);
+                }
+                if let Some(clause) = &handler.return_clause {
+                    self.expr(&clause.body);
+                }
+            }
+            TypedExprKind::InlineAsm {
+                outputs, inputs, ..
+            } => {
+                for output in outputs {
+                    self.expr(&output.target);
+                }
+                for input in inputs {
+                    self.expr(&input.expr);
+                }
+            }
+            TypedExprKind::LlvmIr { inputs, .. } => {
+                for input in inputs {
+                    self.expr(input);
+                }
+            }
+            TypedExprKind::Literal(_)
+            | TypedExprKind::Identifier { .. }
+            | TypedExprKind::Unknown => {}
+        }
+    }
+
+    fn stmt(&mut self, stmt: &TypedStmt) {
+        match &stmt.kind {
+            TypedStmtKind::Let { value, .. } | TypedStmtKind::Var { value, .. } => self.expr(value),
+            TypedStmtKind::Expr { expr } => self.expr(expr),
+            TypedStmtKind::Assign { target, value } => {
+                self.expr(target);
+                self.expr(value);
+            }
+            TypedStmtKind::Defer { expr } => {
+                if is_pure(expr) {
+                    let span = whole_line_span(self.sink.source(), stmt.span);
+                    self.sink.emit(
+                        &NEEDLESS_DEFER,
+                        stmt.span,
+                        "副作用のない式を `defer` で登録しても効果がありません",
+                        vec![DiagnosticFixIt::delete(span)],
+                    );
+                }
+                self.expr(expr);
+            }
+        }
+    }
+
+    /// ガードのない全捕捉アームより後ろのアームを報告する。
+    fn check_unreachable_arms(&mut self, arms: &[TypedMatchArm]) {
+        let Some(catch_all) = arms
+            .iter()
+            .position(|arm| arm.guard.is_none() && is_irrefutable(&arm.pattern))
+        else {
+            return;
+        };
+        for pair in arms[catch_all..].windows(2) {
+            let (previous, arm) = (&pair[0], &pair[1]);
+            let span = Span::new(arm.pattern.span.start, arm.body.span.end);
+            self.sink.emit(
+                &UNREACHABLE_ARM,
+                span,
+                "前のアームがすべての値に一致するため、このアームには到達しません",
+                vec![DiagnosticFixIt::delete(Span::new(
+                    previous.body.span.end,
+                    arm.body.span.end,
+                ))],
+            );
+        }
+    }
+
+    /// `Ok(expr?)` / `Some(expr?)` は `expr` と同じ値になる。
+    fn check_redundant_propagate(
+        &mut self,
+        call: &TypedExpr,
+        callee: &TypedExpr,
+        args: &[TypedExpr],
+    ) {
+        let TypedExprKind::Identifier { ident } = &callee.kind else {
+            return;
+        };
+        if !matches!(ident.name.as_str(), "Ok" | "Some") {
+            return;
+        }
+        let [arg] = args else {
+            return;
+        };
+        let TypedExprKind::Propagate { expr: inner } = &arg.kind else {
+            return;
+        };
+        let text = self.sink.text(inner.span).to_string();
+        self.sink.emit(
+            &REDUNDANT_PROPAGATE,
+            call.span,
+            format!("`{}(...?)` は伝播した値を包み直すだけです", ident.name),
+            vec![DiagnosticFixIt::replace(call.span, text)],
+        );
+    }
+
+    /// 式が unsafe 文脈を必要とする操作を含むかどうか。判定できない式は含むものとみなす。
+    fn requires_unsafe(&self, expr: &TypedExpr) -> bool {
+        match &expr.kind {
+            TypedExprKind::InlineAsm { .. }
+            | TypedExprKind::LlvmIr { .. }
+            | TypedExprKind::Unknown => true,
+            TypedExprKind::Call { callee, args, .. } => {
+                let callee_is_safe = match &callee.kind {
+                    TypedExprKind::Identifier { ident } => {
+                        self.safe_functions.contains(ident.name.as_str())
+                            || ident.name.chars().next().is_some_and(char::is_uppercase)
+                    }
+                    _ => false,
+                };
+                !callee_is_safe || args.iter().any(|arg| self.requires_unsafe(arg))
+            }
+            TypedExprKind::Literal(_) | TypedExprKind::Identifier { .. } => false,
+            TypedExprKind::Lambda { body, .. }
+            | TypedExprKind::EffectBlock { body }
+            | TypedExprKind::Async { body, .. }
+            | TypedExprKind::Unsafe { body } => self.requires_unsafe(body),
+            TypedExprKind::Rec { target: inner, .. }
+            | TypedExprKind::Propagate { expr: inner }
+            | TypedExprKind::Unary { operand: inner, .. }
+            | TypedExprKind::Await { expr: inner }
+            | TypedExprKind::FieldAccess { target: inner, .. }
+            | TypedExprKind::TupleAccess { target: inner, .. } => self.requires_unsafe(inner),
+            TypedExprKind::Return { value } => value
+                .as_ref()
+                .is_some_and(|value| self.requires_unsafe(value)),
+            TypedExprKind::Binary { left, right, .. }
+            | TypedExprKind::Index {
+                target: left,
+                index: right,
+            } => self.requires_unsafe(left) || self.requires_unsafe(right),
+            TypedExprKind::IfElse {
+                condition,
+                then_branch,
+                else_branch,
+            } => [condition, then_branch, else_branch]
+                .into_iter()
+                .any(|expr| self.requires_unsafe(expr)),
+            TypedExprKind::Match { target, arms, .. } => {
+                self.requires_unsafe(target)
+                    || arms.iter().any(|arm| {
+                        arm.guard
+                            .as_ref()
+                            .is_some_and(|guard| self.requires_unsafe(guard))
+                            || self.requires_unsafe(&arm.body)
+                    })
+            }
+            TypedExprKind::PerformCall { call } => self.requires_unsafe(&call.argument),
+            TypedExprKind::Handle { target, handler } => {
+                self.requires_unsafe(target)
+                    || handler
+                        .operations
+                        .iter()
+                        .any(|operation| self.requires_unsafe(&operation.body))
+                    || handler
+                        .return_clause
+                        .as_ref()
+                        .is_some_and(|clause| self.requires_unsafe(&clause.body))
+            }
+            TypedExprKind::Block {
+                statements,
+                tail,
+                defers,
+            } => {
+                statements.iter().any(|stmt| match &stmt.kind {
+                    TypedStmtKind::Let { value, .. }
+                    | TypedStmtKind::Var { value, .. }
+                    | TypedStmtKind::Expr { expr: value }
+                    | TypedStmtKind::Defer { expr: value } => self.requires_unsafe(value),
+                    TypedStmtKind::Assign { target, value } => {
+                        self.requires_unsafe(target) || self.requires_unsafe(value)
+                    }
+                }) || tail.as_ref().is_some_and(|tail| self.requires_unsafe(tail))
+                    || defers.iter().any(|defer| self.requires_unsafe(defer))
+            }
+        }
+    }
+}
+
+/// ブロック末尾の式は最後の式文としても保持されるため、重複しない場合のみ返す。
+fn separate_tail<'e>(
+    statements: &[TypedStmt],
+    tail: Option<&'e TypedExpr>,
+) -> Option<&'e TypedExpr> {
+    let tail = tail?;
+    match statements.last().map(|stmt| &stmt.kind) {
+        Some(TypedStmtKind::Expr { expr }) if expr.span == tail.span => None,
+        _ => Some(tail),
+    }
+}
+
+/// すべての値に一致するパターンかどうか。
+fn is_irrefutable(pattern: &TypedPattern) -> bool {
+    match &pattern.kind {
+        TypedPatternKind::Wildcard => true,
+        // 大文字で始まる名前は引数なしのコンストラクタとして扱う。
+        TypedPatternKind::Var { name } => !name.chars().next().is_some_and(char::is_uppercase),
+        TypedPatternKind::Binding { pattern, .. } => is_irrefutable(pattern),
+        TypedPatternKind::Tuple { elements } => elements.iter().all(is_irrefutable),
+        TypedPatternKind::Record { fields, .. } => fields
+            .iter()
+            .all(|field| field.value.as_deref().is_none_or(is_irrefutable)),
+        TypedPatternKind::Or { variants } => variants.iter().any(is_irrefutable),
+        _ => false,
+    }
+}
+
+/// 評価しても観測可能な効果を持たない式かどうか。
+fn is_pure(expr: &TypedExpr) -> bool {
+    match &expr.kind {
+        TypedExprKind::Literal(_) | TypedExprKind::Identifier { .. } => true,
+        TypedExprKind::FieldAccess { target, .. } | TypedExprKind::TupleAccess { target, .. } => {
+            is_pure(target)
+        }
+        TypedExprKind::Unary { operand, .. } => is_pure(operand),
+        TypedExprKind::Binary { left, right, .. } => is_pure(left) && is_pure(right),
+        TypedExprKind::Block {
+            statements,
+            tail,
+            defers,
+        } => {
+            defers.is_empty()
+                && tail.as_deref().is_none_or(is_pure)
+                && statements.iter().all(|stmt| match &stmt.kind {
+                    TypedStmtKind::Expr { expr } => is_pure(expr),
+                    _ => false,
+                })
+        }
+        _ => false,
+    }
+}
@@ -1,4 +1,5 @@
 use super::localization::LocalizationKey;
+use super::sarif::build_sarif_log;
 use reml_runtime::lsp::derive::{DeriveModel, LspDeriveEnvelope};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
@@ -12,6 +13,7 @@ pub enum OutputFormat {
     Json,
     Lsp,
     LspDerive,
+    Sarif,
 }
 
 impl OutputFormat {
@@ -21,8 +23,9 @@ impl OutputFormat {
             "json" => Ok(Self::Json),
             "lsp" => Ok(Self::Lsp),
             "lsp-derive" => Ok(Self::LspDerive),
+            "sarif" => Ok(Self::Sarif),
             other => Err(format!(
-                "--output に指定した値 `{other}` は human/json/lsp/lsp-derive のいずれかである必要があります"
+                "--output に指定した値 `{other}` は human/json/lsp/lsp-derive/sarif のいずれかである必要があります"
             )),
         }
     }
@@ -100,6 +103,30 @@ impl CliDiagnosticEnvelope {
             exit_code,
         }
     }
+
+    pub fn command(&self) -> &str {
+        self.command.as_str()
+    }
+
+    pub fn phase(&self) -> &str {
+        self.phase.as_str()
+    }
+
+    pub fn run_id(&self) -> &str {
+        self.run_id.as_str()
+    }
+
+    pub fn diagnostics(&self) -> &[Value] {
+        &self.diagnostics
+    }
+
+    pub fn summary(&self) -> &CliSummary {
+        &self.summary
+    }
+
+    pub fn exit_code(&self) -> &CliExitCode {
+        &self.exit_code
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -173,6 +200,10 @@ pub fn emit_cli_output(
         }
         OutputFormat::Lsp => emit_lsp_output(envelope, input_path)?,
         OutputFormat::LspDerive => emit_lsp_derive_output(lsp_derive, input_path)?,
+        OutputFormat::Sarif => {
+            let log = build_sarif_log(envelope, input_path);
+            println!("{}", serde_json::to_string_pretty(&log)?);
+        }
     }
     Ok(())
 }
@@ -360,7 +391,7 @@ fn diagnostic_location_label(diag: &Value) -> String {
     String::new()
 }
 
-fn path_to_uri(path: &Path) -> String {
+pub(crate) fn path_to_uri(path: &Path) -> String {
     if path.is_absolute() {
         format!("file://{}", path.display())
     } else if let Ok(absolute) = path.canonicalize() {
@@ -2,5 +2,6 @@
 
 pub mod cli;
 mod localization;
+pub mod sarif;
 
 pub use localization::LocalizationKey;
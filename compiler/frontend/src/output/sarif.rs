@@ -0,0 +1,560 @@
+//! `--output sarif` 向けに CLI 診断を SARIF 2.1.0 ログへ変換する。
+//!
+//! 入力は `CliDiagnosticEnvelope` に格納済みの JSON 診断
+//! （`diagnostic::json::build_frontend_diagnostic` の出力）であり、
+//! LSP 出力と同様に直列化後の値から各フィールドを写像する。
+
+use super::cli::{path_to_uri, CliDiagnosticEnvelope};
+use crate::diagnostic::explain::find_explanation;
+use crate::diagnostic::messages::{message_templates, DiagnosticMessageTemplate};
+use crate::diagnostic::DiagnosticSeverity;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA_URI: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const TOOL_NAME: &str = "reml_frontend";
+const SRCROOT_BASE_ID: &str = "%SRCROOT%";
+
+/// エンベロープ全体を 1 run の SARIF ログへ変換する。
+pub fn build_sarif_log(envelope: &CliDiagnosticEnvelope, input_path: &Path) -> Value {
+    let mut catalog = SarifRuleCatalog::from_templates();
+    let results = envelope
+        .diagnostics()
+        .iter()
+        .map(|diag| convert_to_sarif_result(diag, &mut catalog))
+        .collect::<Vec<_>>();
+    let summary = envelope.summary();
+    let artifacts = summary
+        .inputs
+        .iter()
+        .map(|input| json!({ "location": artifact_location(input) }))
+        .collect::<Vec<_>>();
+    let exit_code = envelope.exit_code();
+    let mut run_properties = Map::new();
+    run_properties.insert("run_id".to_string(), json!(envelope.run_id()));
+    run_properties.insert("command".to_string(), json!(envelope.command()));
+    run_properties.insert("phase".to_string(), json!(envelope.phase()));
+    run_properties.insert("input".to_string(), json!(path_to_uri(input_path)));
+    run_properties.insert("stats".to_string(), Value::Object(summary.stats.clone()));
+    if let Some(artifact) = summary.artifact.as_ref() {
+        run_properties.insert("artifact".to_string(), json!(artifact));
+    }
+    json!({
+        "$schema": SARIF_SCHEMA_URI,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": TOOL_NAME,
+                    "semanticVersion": env!("CARGO_PKG_VERSION"),
+                    "rules": catalog.into_rules(),
+                }
+            },
+            "automationDetails": {
+                "id": format!("{TOOL_NAME}/{}/", envelope.command()),
+                "guid": envelope.run_id(),
+            },
+            "invocations": [{
+                "executionSuccessful": exit_code.label() != "failure",
+                "exitCode": exit_code.value(),
+                "exitCodeDescription": exit_code.label(),
+                "startTimeUtc": summary.started_at,
+                "endTimeUtc": summary.finished_at,
+            }],
+            "artifacts": artifacts,
+            "results": results,
+            "properties": Value::Object(run_properties),
+        }]
+    })
+}
+
+/// `ruleId` と `ruleIndex` を対応付けるルールカタログ。
+/// テンプレート未登録のコードは結果に現れた時点で最小限の記述子を追加する。
+/// 詳細解説が登録されたコードには `help` と `remlc explain` への導線を付与する。
+struct SarifRuleCatalog {
+    rules: Vec<Value>,
+    index: BTreeMap<String, usize>,
+}
+
+impl SarifRuleCatalog {
+    fn from_templates() -> Self {
+        let mut catalog = Self {
+            rules: Vec::new(),
+            index: BTreeMap::new(),
+        };
+        for template in message_templates() {
+            if !catalog.index.contains_key(template.code) {
+                catalog.push(template.code.to_string(), rule_from_template(&template));
+            }
+        }
+        catalog
+    }
+
+    fn index_of(&mut self, code: &str) -> usize {
+        if let Some(&index) = self.index.get(code) {
+            return index;
+        }
+        let rule = json!({
+            "id": code,
+            "name": code,
+        });
+        self.push(code.to_string(), rule)
+    }
+
+    fn push(&mut self, code: String, mut rule: Value) -> usize {
+        attach_explanation(&mut rule, &code);
+        let index = self.rules.len();
+        self.rules.push(rule);
+        self.index.insert(code, index);
+        index
+    }
+
+    fn into_rules(self) -> Vec<Value> {
+        self.rules
+    }
+}
+
+fn attach_explanation(rule: &mut Value, code: &str) {
+    let (Some(explanation), Some(rule)) = (find_explanation(code), rule.as_object_mut()) else {
+        return;
+    };
+    rule.entry("shortDescription")
+        .or_insert_with(|| json!({ "text": explanation.title }));
+    rule.insert(
+        "help".to_string(),
+        json!({
+            "text": format!("{}（詳細: `{}`）", explanation.description, explanation.command()),
+            "markdown": explanation.to_markdown(),
+        }),
+    );
+    let properties = rule
+        .entry("properties")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(properties) = properties.as_object_mut() {
+        properties.insert("explain".to_string(), explanation.link_value());
+    }
+}
+
+fn rule_from_template(template: &DiagnosticMessageTemplate) -> Value {
+    json!({
+        "id": template.code,
+        "name": template.code,
+        "shortDescription": { "text": template.title },
+        "fullDescription": { "text": template.message },
+        "defaultConfiguration": { "level": severity_to_level(template.severity.as_str()) },
+        "properties": { "severity": template.severity.as_str() },
+    })
+}
+
+fn convert_to_sarif_result(diag: &Value, catalog: &mut SarifRuleCatalog) -> Value {
+    let severity = diag
+        .get("severity")
+        .and_then(|value| value.as_str())
+        .unwrap_or(DiagnosticSeverity::Error.as_str());
+    let mut result = Map::new();
+    if let Some(code) = diag
+        .get("code")
+        .and_then(|value| value.as_str())
+        .filter(|code| !code.trim().is_empty())
+    {
+        result.insert("ruleId".to_string(), json!(code));
+        result.insert("ruleIndex".to_string(), json!(catalog.index_of(code)));
+    }
+    result.insert("level".to_string(), json!(severity_to_level(severity)));
+    result.insert("message".to_string(), result_message(diag));
+    if let Some(id) = diag.get("id").and_then(|value| value.as_str()) {
+        result.insert("guid".to_string(), json!(id));
+    }
+    let locations = diag
+        .get("primary")
+        .and_then(physical_location_from_primary)
+        .map(|physical| vec![json!({ "physicalLocation": physical })])
+        .unwrap_or_default();
+    result.insert("locations".to_string(), Value::Array(locations));
+    let related = related_locations(diag);
+    if !related.is_empty() {
+        result.insert("relatedLocations".to_string(), Value::Array(related));
+    }
+    let fixes = fixes_from_diagnostic(diag);
+    if !fixes.is_empty() {
+        result.insert("fixes".to_string(), Value::Array(fixes));
+    }
+    if let Some(code_flow) = code_flow_from_span_trace(diag.get("span_trace")) {
+        result.insert("codeFlows".to_string(), json!([code_flow]));
+    }
+    result.insert("properties".to_string(), result_properties(diag));
+    Value::Object(result)
+}
+
+fn severity_to_level(severity: &str) -> &'static str {
+    match severity {
+        "error" => "error",
+        "warning" => "warning",
+        "info" | "hint" => "note",
+        _ => "none",
+    }
+}
+
+/// `notes` は Markdown の箇条書きとして `message.markdown` へ追記する。
+fn result_message(diag: &Value) -> Value {
+    let text = diag
+        .get("message")
+        .and_then(|value| value.as_str())
+        .unwrap_or("")
+        .to_string();
+    let notes = diag
+        .get("notes")
+        .and_then(|value| value.as_array())
+        .map(|notes| {
+            notes
+                .iter()
+                .filter_map(|note| {
+                    let message = note.get("message").and_then(|value| value.as_str())?;
+                    let label = note
+                        .get("label")
+                        .and_then(|value| value.as_str())
+                        .filter(|label| !label.trim().is_empty());
+                    Some(match label {
+                        Some(label) => format!("- **{label}**: {message}"),
+                        None => format!("- {message}"),
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if notes.is_empty() {
+        json!({ "text": text })
+    } else {
+        json!({
+            "text": text,
+            "markdown": format!("{text}\n\n{}", notes.join("\n")),
+        })
+    }
+}
+
+fn related_locations(diag: &Value) -> Vec<Value> {
+    let Some(secondary) = diag.get("secondary").and_then(|value| value.as_array()) else {
+        return Vec::new();
+    };
+    secondary
+        .iter()
+        .filter_map(|entry| {
+            let physical = physical_location_from_location(entry.get("span")?)?;
+            Some((
+                physical,
+                entry.get("message").and_then(|value| value.as_str()),
+            ))
+        })
+        .enumerate()
+        .map(|(index, (physical, message))| {
+            let mut location = Map::new();
+            location.insert("id".to_string(), json!(index));
+            location.insert("physicalLocation".to_string(), physical);
+            if let Some(message) = message {
+                location.insert("message".to_string(), json!({ "text": message }));
+            }
+            Value::Object(location)
+        })
+        .collect()
+}
+
+/// 診断直下の `fixits` と `hints[].actions` をそれぞれ 1 件の `fix` へまとめる。
+fn fixes_from_diagnostic(diag: &Value) -> Vec<Value> {
+    let mut fixes = Vec::new();
+    if let Some(fixits) = diag.get("fixits").and_then(|value| value.as_array()) {
+        if let Some(fix) = fix_from_actions(fixits, None) {
+            fixes.push(fix);
+        }
+    }
+    if let Some(hints) = diag.get("hints").and_then(|value| value.as_array()) {
+        for hint in hints {
+            let Some(actions) = hint.get("actions").and_then(|value| value.as_array()) else {
+                continue;
+            };
+            let description = hint.get("message").and_then(|value| value.as_str());
+            if let Some(fix) = fix_from_actions(actions, description) {
+                fixes.push(fix);
+            }
+        }
+    }
+    fixes
+}
+
+fn fix_from_actions(actions: &[Value], description: Option<&str>) -> Option<Value> {
+    let mut changes: BTreeMap<String, (Value, Vec<Value>)> = BTreeMap::new();
+    for action in actions {
+        let Some(span) = action.get("span") else {
+            continue;
+        };
+        let Some(file) = span.get("file").and_then(|value| value.as_str()) else {
+            continue;
+        };
+        let Some(region) = region_from_location(span) else {
+            continue;
+        };
+        let kind = action
+            .get("kind")
+            .and_then(|value| value.as_str())
+            .unwrap_or("replace");
+        let deleted_region = if kind == "insert" {
+            collapse_region(&region)
+        } else {
+            region
+        };
+        let mut replacement = Map::new();
+        replacement.insert("deletedRegion".to_string(), deleted_region);
+        if let Some(text) = action.get("text").and_then(|value| value.as_str()) {
+            replacement.insert("insertedContent".to_string(), json!({ "text": text }));
+        }
+        changes
+            .entry(file.to_string())
+            .or_insert_with(|| (artifact_location(file), Vec::new()))
+            .1
+            .push(Value::Object(replacement));
+    }
+    if changes.is_empty() {
+        return None;
+    }
+    let artifact_changes = changes
+        .into_values()
+        .map(|(location, replacements)| {
+            json!({
+                "artifactLocation": location,
+                "replacements": replacements,
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut fix = Map::new();
+    if let Some(description) = description {
+        fix.insert("description".to_string(), json!({ "text": description }));
+    }
+    fix.insert(
+        "artifactChanges".to_string(),
+        Value::Array(artifact_changes),
+    );
+    Some(Value::Object(fix))
+}
+
+fn code_flow_from_span_trace(trace: Option<&Value>) -> Option<Value> {
+    let frames = trace?.as_array()?;
+    let locations = frames
+        .iter()
+        .filter_map(|frame| {
+            let physical = physical_location_from_primary(frame.get("span")?)?;
+            let mut location = Map::new();
+            location.insert("physicalLocation".to_string(), physical);
+            if let Some(label) = frame.get("label").and_then(|value| value.as_str()) {
+                location.insert("message".to_string(), json!({ "text": label }));
+            }
+            Some(json!({ "location": Value::Object(location) }))
+        })
+        .collect::<Vec<_>>();
+    if locations.is_empty() {
+        return None;
+    }
+    Some(json!({
+        "threadFlows": [{ "locations": locations }],
+    }))
+}
+
+fn result_properties(diag: &Value) -> Value {
+    let mut properties = Map::new();
+    for key in [
+        "domain",
+        "severity_hint",
+        "recoverability",
+        "audit_metadata",
+        "audit",
+    ] {
+        if let Some(value) = diag.get(key).filter(|value| !value.is_null()) {
+            properties.insert(key.to_string(), value.clone());
+        }
+    }
+    if let Some(codes) = diag
+        .get("codes")
+        .and_then(|value| value.as_array())
+        .filter(|codes| codes.len() > 1)
+    {
+        properties.insert("codes".to_string(), Value::Array(codes.clone()));
+    }
+    Value::Object(properties)
+}
+
+/// `primary`/`span_trace[].span` 形式（`start_line` 等）を `physicalLocation` へ変換する。
+fn physical_location_from_primary(primary: &Value) -> Option<Value> {
+    let file = primary.get("file").and_then(|value| value.as_str())?;
+    let start_line = positive_field(primary, "start_line")?;
+    let start_col = positive_field(primary, "start_col").unwrap_or(1);
+    let end_line = positive_field(primary, "end_line").unwrap_or(start_line);
+    let end_col = positive_field(primary, "end_col").unwrap_or(start_col);
+    let mut region = Map::new();
+    region.insert("startLine".to_string(), json!(start_line));
+    region.insert("startColumn".to_string(), json!(start_col));
+    region.insert("endLine".to_string(), json!(end_line));
+    region.insert("endColumn".to_string(), json!(end_col));
+    if let Some(snippet) = primary.get("snippet").and_then(|value| value.as_str()) {
+        region.insert("snippet".to_string(), json!({ "text": snippet }));
+    }
+    Some(json!({
+        "artifactLocation": artifact_location(file),
+        "region": Value::Object(region),
+    }))
+}
+
+/// `secondary[].span`/`fixits[].span` 形式（`line`/`column` 等）を `physicalLocation` へ変換する。
+fn physical_location_from_location(location: &Value) -> Option<Value> {
+    let file = location.get("file").and_then(|value| value.as_str())?;
+    let region = region_from_location(location)?;
+    Some(json!({
+        "artifactLocation": artifact_location(file),
+        "region": region,
+    }))
+}
+
+fn region_from_location(location: &Value) -> Option<Value> {
+    let line = positive_field(location, "line")?;
+    let column = positive_field(location, "column").unwrap_or(1);
+    let end_line = positive_field(location, "endLine").unwrap_or(line);
+    let end_column = positive_field(location, "endColumn").unwrap_or(column);
+    Some(json!({
+        "startLine": line,
+        "startColumn": column,
+        "endLine": end_line,
+        "endColumn": end_column,
+    }))
+}
+
+fn collapse_region(region: &Value) -> Value {
+    json!({
+        "startLine": region["startLine"],
+        "startColumn": region["startColumn"],
+        "endLine": region["startLine"],
+        "endColumn": region["startColumn"],
+    })
+}
+
+/// SARIF の行・列は 1 始まりのため、0 以下は「位置なし」として扱う。
+fn positive_field(value: &Value, key: &str) -> Option<i64> {
+    value
+        .get(key)
+        .and_then(|value| value.as_i64())
+        .filter(|value| *value > 0)
+}
+
+fn artifact_location(file: &str) -> Value {
+    let path = Path::new(file);
+    if path.is_absolute() {
+        json!({ "uri": path_to_uri(path) })
+    } else {
+        json!({
+            "uri": file.replace('\\', "/"),
+            "uriBaseId": SRCROOT_BASE_ID,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_maps_locations_fixes_and_code_flows() {
+        let diag = json!({
+            "id": "11111111-1111-1111-1111-111111111111",
+            "message": "文の末尾にセミコロンが必要です",
+            "severity": "error",
+            "domain": "parser",
+            "code": "parser.rec.invalid_form",
+            "codes": ["parser.rec.invalid_form"],
+            "primary": {
+                "file": "src/main.reml",
+                "start_line": 2,
+                "start_col": 5,
+                "end_line": 2,
+                "end_col": 9
+            },
+            "secondary": [{
+                "span": { "file": "src/main.reml", "line": 1, "column": 1, "endLine": 1, "endColumn": 4 },
+                "message": "ここで開始"
+            }],
+            "fixits": [{
+                "span": { "file": "src/main.reml", "line": 2, "column": 9, "endLine": 2, "endColumn": 9 },
+                "kind": "insert",
+                "text": ";"
+            }],
+            "notes": [{ "label": "recover.expected_tokens", "message": "`;` が必要です" }],
+            "span_trace": [{
+                "label": "syntax:expr::block",
+                "span": { "file": "src/main.reml", "start_line": 2, "start_col": 1, "end_line": 2, "end_col": 9 }
+            }],
+            "audit_metadata": { "pipeline.stage": "diagnostics" }
+        });
+        let mut catalog = SarifRuleCatalog::from_templates();
+        let result = convert_to_sarif_result(&diag, &mut catalog);
+        assert_eq!(result["ruleId"], json!("parser.rec.invalid_form"));
+        assert_eq!(result["level"], json!("error"));
+        let region = &result["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startLine"], json!(2));
+        assert_eq!(region["endColumn"], json!(9));
+        assert_eq!(
+            result["relatedLocations"][0]["message"]["text"],
+            json!("ここで開始")
+        );
+        let replacement = &result["fixes"][0]["artifactChanges"][0]["replacements"][0];
+        assert_eq!(replacement["insertedContent"]["text"], json!(";"));
+        assert!(result["message"]["markdown"]
+            .as_str()
+            .expect("notes must produce markdown")
+            .contains("**recover.expected_tokens**"));
+        assert_eq!(
+            result["codeFlows"][0]["threadFlows"][0]["locations"][0]["location"]["message"]["text"],
+            json!("syntax:expr::block")
+        );
+        assert_eq!(
+            result["properties"]["audit_metadata"]["pipeline.stage"],
+            json!("diagnostics")
+        );
+    }
+
+    #[test]
+    fn unknown_codes_are_appended_to_rule_catalog() {
+        let mut catalog = SarifRuleCatalog::from_templates();
+        let known = catalog.rules.len();
+        let index = catalog.index_of("custom.unknown");
+        assert_eq!(index, known);
+        assert_eq!(catalog.index_of("custom.unknown"), known);
+        assert_eq!(catalog.into_rules().len(), known + 1);
+    }
+
+    #[test]
+    fn explained_codes_carry_help_and_explain_link() {
+        let mut catalog = SarifRuleCatalog::from_templates();
+        let index = catalog.index_of("ffi.varargs.invalid_abi");
+        let rules = catalog.into_rules();
+        let rule = &rules[index];
+        assert!(rule["help"]["markdown"]
+            .as_str()
+            .is_some_and(|markdown| markdown.contains("### 修正例")));
+        assert_eq!(
+            rule["properties"]["explain"]["command"],
+            "remlc explain ffi.varargs.invalid_abi"
+        );
+        assert!(rule["shortDescription"]["text"].is_string());
+    }
+
+    #[test]
+    fn missing_primary_produces_no_location() {
+        let diag = json!({
+            "message": "example",
+            "severity": "hint",
+            "primary": { "file": "x.reml", "start_line": 0, "start_col": 0, "end_line": 0, "end_col": 0 }
+        });
+        let mut catalog = SarifRuleCatalog::from_templates();
+        let result = convert_to_sarif_result(&diag, &mut catalog);
+        assert_eq!(result["level"], json!("note"));
+        assert_eq!(result["locations"], json!([]));
+        assert!(result.get("ruleId").is_none());
+    }
+}
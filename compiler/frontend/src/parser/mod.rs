@@ -32,8 +32,10 @@ const CODE_INTERNAL_STATE: &str = "parser.internal.state";
 const CODE_EXPECTED_TOKENS: &str = "parser.syntax.expected_tokens";
 
 use crate::diagnostic::{
-    recover::ExpectedTokensSummary, DiagnosticBuilder, DiagnosticDomain, DiagnosticNote,
-    DiagnosticSeverity, ExpectedToken, ExpectedTokenCollector, FrontendDiagnostic,
+    messages::{self, MessageArgs},
+    recover::ExpectedTokensSummary,
+    DiagnosticBuilder, DiagnosticDomain, DiagnosticNote, DiagnosticSeverity, ExpectedToken,
+    ExpectedTokenCollector, FrontendDiagnostic,
 };
 use crate::error::{FrontendError, Recoverability};
 use crate::lexer::{lex_source_with_options, IdentifierProfile, LexOutput, LexerOptions};
@@ -240,6 +242,8 @@ pub struct ParserOptions {
     pub lex_identifier_profile: IdentifierProfile,
     pub lex_identifier_locale: Option<LocaleId>,
     pub allow_top_level_expr: bool,
+    /// 字句・構文診断のメッセージに使うロケール。
+    pub diagnostic_locale: &'static str,
 }
 
 impl Default for ParserOptions {
@@ -252,6 +256,7 @@ impl Default for ParserOptions {
             lex_identifier_profile: IdentifierProfile::Unicode,
             lex_identifier_locale: None,
             allow_top_level_expr: false,
+            diagnostic_locale: messages::DEFAULT_LOCALE,
         }
     }
 }
@@ -269,6 +274,7 @@ impl ParserOptions {
             lex_identifier_profile: lex_identifier_profile_from_run_config(run_config),
             lex_identifier_locale: lex_identifier_locale_from_run_config(run_config),
             allow_top_level_expr: run_config.allow_top_level_expr,
+            diagnostic_locale: messages::DEFAULT_LOCALE,
         }
     }
 
@@ -291,6 +297,11 @@ impl ParserOptions {
         self.lex_identifier_locale = locale;
         self
     }
+
+    pub fn with_diagnostic_locale(mut self, locale: &'static str) -> Self {
+        self.diagnostic_locale = locale;
+        self
+    }
 }
 
 fn lex_identifier_profile_from_run_config(run_config: &RunConfig) -> IdentifierProfile {
@@ -336,9 +347,10 @@ impl ParserDriver {
         options: ParserOptions,
         run_config: RunConfig,
     ) -> ParseResult<Module> {
+        let diagnostic_locale = options.diagnostic_locale;
         let (parsed, legacy_error) = Self::parse_with_options(source, options);
         let _reply = build_parser_reply(source, &parsed, legacy_error.as_ref());
-        parse_result_from_module(parsed, run_config, legacy_error)
+        parse_result_from_module(parsed, run_config, legacy_error, diagnostic_locale)
     }
 
     pub fn parse_with_options(
@@ -348,6 +360,7 @@ impl ParserDriver {
         let lexer_options = LexerOptions {
             identifier_profile: options.lex_identifier_profile,
             identifier_locale: options.lex_identifier_locale.clone(),
+            diagnostic_locale: options.diagnostic_locale,
         };
         let LexOutput { tokens, errors } = lex_source_with_options(source, lexer_options);
         let streaming_state = StreamingState::new(options.streaming.clone());
@@ -364,14 +377,18 @@ impl ParserDriver {
             DiagnosticBuilder::with_merge_parse_expected(false)
         };
         diagnostics
-            .extend(errors.into_iter().map(Self::error_to_diagnostic))
+            .extend(
+                errors
+                    .into_iter()
+                    .map(|error| Self::error_to_diagnostic(error, options.diagnostic_locale)),
+            )
             .expect("lexer diagnostics must include severity/domain/code");
         diagnostics
             .extend(detect_handle_missing_with_tokens(&tokens).into_iter())
             .expect("token diagnostics must include severity/domain/code");
 
         let (ast, parse_errors, legacy_error, trace_events) =
-            parse_tokens(&tokens, source, &streaming_state);
+            parse_tokens(&tokens, source, &streaming_state, options.diagnostic_locale);
         let mut streaming_recover = StreamingRecoverController::new(streaming_enabled);
         streaming_recover.start_checkpoint();
         for (span, formatted) in parse_errors.into_iter() {
@@ -423,8 +440,28 @@ impl ParserDriver {
         )
     }
 
-    fn error_to_diagnostic(error: FrontendError) -> FrontendDiagnostic {
-        let mut diagnostic = FrontendDiagnostic::new(error.message())
+    fn error_to_diagnostic(error: FrontendError, locale: &str) -> FrontendDiagnostic {
+        let localized = match &error.kind {
+            crate::error::FrontendErrorKind::UnknownToken { .. } => {
+                messages::translate(CODE_UNKNOWN_TOKEN, locale, &MessageArgs::new())
+            }
+            crate::error::FrontendErrorKind::MissingToken { expected, .. } => messages::translate(
+                CODE_MISSING_TOKEN,
+                locale,
+                &MessageArgs::new().with("expected", expected.as_str()),
+            ),
+            crate::error::FrontendErrorKind::InternalState { message } => messages::translate(
+                CODE_INTERNAL_STATE,
+                locale,
+                &MessageArgs::new().with("message", message.as_str()),
+            ),
+            crate::error::FrontendErrorKind::UnexpectedStructure { .. } => None,
+        };
+        let message = localized
+            .as_ref()
+            .map(|localized| localized.message.clone())
+            .unwrap_or_else(|| error.message());
+        let mut diagnostic = FrontendDiagnostic::new(message)
             .with_severity(DiagnosticSeverity::Error)
             .with_domain(DiagnosticDomain::Parser);
 
@@ -438,10 +475,10 @@ impl ParserDriver {
         match error.kind {
             crate::error::FrontendErrorKind::UnknownToken { span } => {
                 diagnostic = diagnostic.with_span(span);
-                diagnostic.add_note(
-                    DiagnosticNote::new("lexer", "未定義のトークンをスキップします")
-                        .with_span(span),
-                );
+                let note = localized
+                    .and_then(|localized| localized.detail)
+                    .unwrap_or_else(|| "未定義のトークンをスキップします".to_string());
+                diagnostic.add_note(DiagnosticNote::new("lexer", note).with_span(span));
                 diagnostic.push_code(CODE_UNKNOWN_TOKEN);
             }
             crate::error::FrontendErrorKind::MissingToken { span, .. } => {
@@ -487,6 +524,7 @@ fn parse_tokens(
     tokens: &[Token],
     source: &str,
     streaming_state: &StreamingState,
+    diagnostic_locale: &str,
 ) -> (
     Option<Module>,
     Vec<(Option<Span>, FormattedSimpleError)>,
@@ -514,7 +552,7 @@ fn parse_tokens(
         .into_iter()
         .map(|err| {
             let span = Some(convert_range(err.span()));
-            let formatted = format_simple_error(&err);
+            let formatted = format_simple_error(&err, diagnostic_locale);
             record_streaming_error(streaming_state, &err, tokens, &formatted);
             if legacy_error.is_none() {
                 legacy_error = Some(build_parse_error(
@@ -561,6 +599,7 @@ fn parse_result_from_module(
     parsed: ParsedModule,
     run_config: RunConfig,
     legacy_error: Option<ParseError>,
+    diagnostic_locale: &str,
 ) -> ParseResult<Module> {
     let ParsedModule {
         tokens,
@@ -582,6 +621,7 @@ fn parse_result_from_module(
         collect_match_guard_diagnostics(module, &mut diagnostics);
         collect_rec_lambda_diagnostics(module, &mut diagnostics);
     }
+    localize_fixed_diagnostics(&mut diagnostics, diagnostic_locale);
 
     let farthest_error_offset = diagnostics
         .iter()
@@ -642,11 +682,13 @@ impl ParseResult<Module> {
     }
 }
 
-fn format_simple_error(err: &Simple<TokenKind>) -> FormattedSimpleError {
-    let summary = build_expected_summary(err);
+fn format_simple_error(err: &Simple<TokenKind>, locale: &str) -> FormattedSimpleError {
+    let summary = build_expected_summary(err, locale);
     let message = match err.reason() {
         SimpleReason::Unexpected | SimpleReason::Unclosed { .. } => {
-            "構文エラー: 入力を解釈できません".to_string()
+            localized_text(CODE_EXPECTED_TOKENS, locale, &MessageArgs::new(), || {
+                "構文エラー: 入力を解釈できません".to_string()
+            })
         }
         SimpleReason::Custom(msg) => msg.clone(),
     };
@@ -675,7 +717,19 @@ fn build_diagnostic_from_error(
     diagnostic.with_recoverability(Recoverability::Recoverable)
 }
 
-fn build_expected_summary(err: &Simple<TokenKind>) -> ExpectedTokensSummary {
+/// 既定ロケール以外ではカタログの文言を、既定ロケールでは `fallback` を返す。
+fn localized_text(
+    code: &str,
+    locale: &str,
+    args: &MessageArgs,
+    fallback: impl FnOnce() -> String,
+) -> String {
+    messages::translate(code, locale, args)
+        .map(|localized| localized.message)
+        .unwrap_or_else(fallback)
+}
+
+fn build_expected_summary(err: &Simple<TokenKind>, locale: &str) -> ExpectedTokensSummary {
     let mut collector = ExpectedTokenCollector::new();
     let label = err.label().and_then(|text| {
         let trimmed = text.trim();
@@ -698,21 +752,40 @@ fn build_expected_summary(err: &Simple<TokenKind>) -> ExpectedTokensSummary {
         }
     }
     let mut summary = collector.summarize();
+    summary.localize_humanized(locale);
     if summary.context_note.is_none() {
         if expectations
             .iter()
             .any(|expectation| matches!(expectation, Some(TokenKind::RParen)))
         {
-            summary.context_note = Some("`(` に対応する `)` が必要です".to_string());
+            summary.context_note = Some(localized_text(
+                "parse.context.unclosed_paren",
+                locale,
+                &MessageArgs::new(),
+                || "`(` に対応する `)` が必要です".to_string(),
+            ));
         } else if is_expr_context {
-            summary.context_note = Some(
-                label
-                    .as_ref()
-                    .map(|name| format!("演算子の後に {name} が必要です"))
-                    .unwrap_or_else(|| "演算子の後に式が必要です".to_string()),
-            );
+            summary.context_note = Some(match label.as_ref() {
+                Some(name) => localized_text(
+                    "parse.context.operand_missing",
+                    locale,
+                    &MessageArgs::new().with("name", name.as_str()),
+                    || format!("演算子の後に {name} が必要です"),
+                ),
+                None => localized_text(
+                    "parse.context.expression_missing",
+                    locale,
+                    &MessageArgs::new(),
+                    || "演算子の後に式が必要です".to_string(),
+                ),
+            });
         } else if let Some(label) = label {
-            summary.context_note = Some(format!("{label} が必要です"));
+            summary.context_note = Some(localized_text(
+                "parse.context.required",
+                locale,
+                &MessageArgs::new().with("name", label.as_str()),
+                || format!("{label} が必要です"),
+            ));
         }
     }
     summary
@@ -1223,6 +1296,46 @@ fn collect_intrinsic_attribute_diagnostics(
     }
 }
 
+/// 固定文言で生成する Parser 診断と、その補足ノート。
+const FIXED_MESSAGE_DIAGNOSTICS: &[(&str, Option<&str>)] = &[
+    (
+        "parser.top_level_expr.disallowed",
+        Some("parser.top_level_expr.hint"),
+    ),
+    ("parser.rec.invalid_form", None),
+    ("parser.rec.unsupported_position", None),
+    (
+        "pattern.guard.if_deprecated",
+        Some("pattern.guard.if_deprecated.note"),
+    ),
+];
+
+/// 固定文言の診断を要求ロケールのカタログで置き換える。カタログの `detail` は
+/// 対応するノートの本文になる。既定ロケールでは既存の文言を維持する。
+fn localize_fixed_diagnostics(diagnostics: &mut [FrontendDiagnostic], locale: &str) {
+    for diagnostic in diagnostics {
+        let Some((code, note_label)) = FIXED_MESSAGE_DIAGNOSTICS
+            .iter()
+            .find(|(code, _)| diagnostic.code.as_deref() == Some(*code))
+        else {
+            continue;
+        };
+        let Some(localized) = messages::translate(code, locale, &MessageArgs::new()) else {
+            continue;
+        };
+        diagnostic.message = localized.message;
+        if let (Some(label), Some(detail)) = (note_label, localized.detail) {
+            for note in diagnostic
+                .notes
+                .iter_mut()
+                .filter(|note| note.label == *label)
+            {
+                note.message = detail.clone();
+            }
+        }
+    }
+}
+
 fn collect_top_level_expr_diagnostics(module: &Module, diagnostics: &mut Vec<FrontendDiagnostic>) {
     if module.exprs.is_empty() {
         return;
@@ -6171,8 +6284,14 @@ fn parse_module_header_tokens(tokens: &[Token], start: usize) -> Option<(ModuleH
     if idx >= tokens.len() {
         return None;
     }
+    let mut attrs = Vec::new();
+    while let Some((attr, next_idx)) = parse_header_attribute_tokens(tokens, idx) {
+        attrs.push(attr);
+        idx = next_idx;
+    }
+    let visibility_idx = idx;
     let mut visibility = Visibility::Private;
-    if tokens[idx].kind == TokenKind::KeywordPub {
+    if tokens.get(idx)?.kind == TokenKind::KeywordPub {
         visibility = Visibility::Public;
         idx += 1;
     }
@@ -6181,7 +6300,7 @@ fn parse_module_header_tokens(tokens: &[Token], start: usize) -> Option<(ModuleH
         return None;
     }
     let span_start = if visibility == Visibility::Public {
-        tokens[start].span
+        tokens[visibility_idx].span
     } else {
         module_token.span
     };
@@ -6193,12 +6312,54 @@ fn parse_module_header_tokens(tokens: &[Token], start: usize) -> Option<(ModuleH
     let header = ModuleHeader {
         path,
         visibility,
-        attrs: Vec::new(),
+        attrs,
         span: span_union(span_start, path_span),
     };
     Some((header, next_idx))
 }
 
+/// モジュールヘッダ前の属性（`@allow("unused_binding")` など）を読み取る。
+///
+/// ファイル単位の設定に用いるため、引数は文字列リテラルと識別子のみを受け付ける。
+fn parse_header_attribute_tokens(tokens: &[Token], start: usize) -> Option<(Attribute, usize)> {
+    if tokens.get(start)?.kind != TokenKind::At {
+        return None;
+    }
+    let (name, mut idx) = parse_ident_with_index(tokens, start + 1)?;
+    let mut args = Vec::new();
+    let mut end_span = name.span;
+    if matches!(tokens.get(idx), Some(token) if token.kind == TokenKind::LParen) {
+        idx += 1;
+        loop {
+            let token = tokens.get(idx)?;
+            match token.kind {
+                TokenKind::RParen => {
+                    end_span = token.span;
+                    idx += 1;
+                    break;
+                }
+                TokenKind::Comma if !args.is_empty() => idx += 1,
+                TokenKind::StringLiteral => {
+                    let lexeme = token.lexeme.as_deref().unwrap_or_default();
+                    args.push(Expr::string(
+                        parse_string_literal_value(lexeme, 0..lexeme.len()),
+                        token.span,
+                    ));
+                    idx += 1;
+                }
+                TokenKind::Identifier | TokenKind::UpperIdentifier => {
+                    let (ident, next_idx) = parse_ident_with_index(tokens, idx)?;
+                    args.push(Expr::identifier(ident));
+                    idx = next_idx;
+                }
+                _ => return None,
+            }
+        }
+    }
+    let span = span_union(tokens[start].span, end_span);
+    Some((Attribute { name, args, span }, idx))
+}
+
 fn parse_use_decl_tokens(
     tokens: &[Token],
     start: usize,
@@ -7293,6 +7454,16 @@ mod parse_result_tests {
         );
     }
 
+    #[test]
+    fn module_header_accepts_leading_attributes() {
+        let result = ParserDriver::parse("@deny(\"unused_*\", needless_var)\nmodule app.main\n");
+        let module = result.value.expect("module missing");
+        let header = module.header.expect("header missing");
+        assert_eq!(header.attrs.len(), 1);
+        assert_eq!(header.attrs[0].name.name, "deny");
+        assert_eq!(header.attrs[0].args.len(), 2);
+    }
+
     #[test]
     fn lexer_error_generates_diagnostics() {
         let result = ParserDriver::parse("fn @@@");
@@ -1,7 +1,9 @@
 use serde::Serialize;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 
 use crate::parser::ast::{Ident, Literal};
+use crate::semantics::mir_opt::MirOptReport;
+use crate::semantics::mir_tail::{analyze_tail_calls, MirTailCall};
 use crate::semantics::typed;
 use crate::span::Span;
 
@@ -26,6 +28,12 @@ pub struct MirModule {
     pub qualified_calls: BTreeMap<String, MirQualifiedCall>,
     pub impl_registry_duplicates: Vec<String>,
     pub impl_registry_unresolved: Vec<String>,
+    /// `--debug-info` 指定時にバックエンドへ渡すソース対応表。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_source: Option<MirDebugSource>,
+    /// `--emit-mir=optimized` 指定時に記録する最適化パスの統計とダンプ。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub optimization: Option<MirOptReport>,
 }
 
 impl MirModule {
@@ -50,7 +58,7 @@ impl MirModule {
             })
             .collect();
         let conductors = module.conductors.iter().map(lower_conductor).collect();
-        Self {
+        let mut mir = Self {
             schema_version: MIR_SCHEMA_VERSION,
             functions,
             active_patterns,
@@ -63,6 +71,14 @@ impl MirModule {
                     span: extern_item.span,
                     abi: extern_item.abi.clone(),
                     symbol: extern_item.symbol.clone(),
+                    params: extern_item
+                        .params
+                        .iter()
+                        .map(|param| normalize_mir_type_label(param))
+                        .collect(),
+                    param_names: extern_item.param_names.clone(),
+                    return_type: normalize_mir_type_label(&extern_item.return_type),
+                    varargs: extern_item.varargs,
                 })
                 .collect(),
             dict_refs: module.dict_refs.clone(),
@@ -70,7 +86,11 @@ impl MirModule {
             qualified_calls,
             impl_registry_duplicates: Vec::new(),
             impl_registry_unresolved: Vec::new(),
-        }
+            debug_source: None,
+            optimization: None,
+        };
+        annotate_ffi_calls(&mut mir);
+        mir
     }
 }
 
@@ -87,6 +107,32 @@ impl Default for MirModule {
             qualified_calls: BTreeMap::new(),
             impl_registry_duplicates: Vec::new(),
             impl_registry_unresolved: Vec::new(),
+            debug_source: None,
+            optimization: None,
+        }
+    }
+}
+
+/// Span のバイトオフセットを行・列へ変換するためのソース情報。
+#[derive(Debug, Clone, Serialize)]
+pub struct MirDebugSource {
+    pub path: String,
+    /// 各行の先頭バイトオフセット。
+    pub line_starts: Vec<u32>,
+}
+
+impl MirDebugSource {
+    pub fn from_source(path: impl Into<String>, source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(
+                source
+                    .match_indices('\n')
+                    .map(|(offset, _)| offset as u32 + 1),
+            )
+            .collect();
+        Self {
+            path: path.into(),
+            line_starts,
         }
     }
 }
@@ -152,6 +198,12 @@ pub struct MirFunction {
     pub body: MirExprId,
     pub exprs: Vec<MirExpr>,
     pub dict_ref_ids: Vec<typed::DictRefId>,
+    /// 末尾位置にある呼び出し。最適化で式 ID が振り直されたら計算し直す。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tail_calls: Vec<MirTailCall>,
+    /// 本体から呼び出している extern 宣言。バックエンドはこれを基に外部関数を宣言する。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ffi_calls: Vec<MirFfiCall>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -160,6 +212,99 @@ pub struct MirExtern {
     pub span: Span,
     pub abi: String,
     pub symbol: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<String>,
+    /// 引数の名前（`params` と同じ順）。C ヘッダ生成で使う。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub param_names: Vec<String>,
+    pub return_type: String,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub varargs: bool,
+}
+
+/// extern 宣言の呼び出しシグネチャ（バックエンドの `ffi_calls` の要素）。
+///
+/// 型はバックエンドの型トークン（`i64`/`f64`/`bool`/`Str`/`unit` など）で表す。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MirFfiCall {
+    pub name: String,
+    pub calling_conv: String,
+    pub args: Vec<String>,
+    pub ret: String,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub variadic: bool,
+}
+
+impl MirFfiCall {
+    fn from_extern(extern_item: &MirExtern) -> Self {
+        Self {
+            name: extern_item.symbol.clone(),
+            calling_conv: ffi_calling_conv(&extern_item.abi).to_string(),
+            args: extern_item
+                .params
+                .iter()
+                .map(|param| ffi_type_token(param))
+                .collect(),
+            ret: ffi_type_token(&extern_item.return_type),
+            variadic: extern_item.varargs,
+        }
+    }
+}
+
+fn ffi_calling_conv(abi: &str) -> &'static str {
+    match abi {
+        "system" | "stdcall" => "x86_stdcallcc",
+        "fastcall" => "x86_fastcallcc",
+        _ => "ccc",
+    }
+}
+
+/// MIR の型ラベルをバックエンドの FFI 型トークンへ寄せる。
+fn ffi_type_token(label: &str) -> String {
+    match label.trim() {
+        "Float" => "f64".to_string(),
+        "Bool" => "bool".to_string(),
+        "()" => "unit".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// 各関数の `ffi_calls` を、本体で名前を直接呼び出している extern 宣言から計算し直す。
+///
+/// モジュール内の関数と同名の extern は関数側を優先し、対象にしない。
+pub fn annotate_ffi_calls(module: &mut MirModule) {
+    let defined: HashSet<String> = module
+        .functions
+        .iter()
+        .map(|function| function.name.clone())
+        .collect();
+    let externs: BTreeMap<&str, &MirExtern> = module
+        .externs
+        .iter()
+        .filter(|extern_item| !defined.contains(&extern_item.name))
+        .map(|extern_item| (extern_item.name.as_str(), extern_item))
+        .collect();
+    for function in &mut module.functions {
+        let mut calls: Vec<MirFfiCall> = Vec::new();
+        for expr in &function.exprs {
+            let MirExprKind::Call { callee, .. } = &expr.kind else {
+                continue;
+            };
+            let Some(MirExprKind::Identifier { ident }) =
+                function.exprs.get(*callee).map(|callee| &callee.kind)
+            else {
+                continue;
+            };
+            let Some(extern_item) = externs.get(ident.name.as_str()) else {
+                continue;
+            };
+            let call = MirFfiCall::from_extern(extern_item);
+            if !calls.contains(&call) {
+                calls.push(call);
+            }
+        }
+        function.ffi_calls = calls;
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -323,6 +468,10 @@ pub enum MirExprKind {
         #[serde(skip_serializing_if = "Option::is_none")]
         argument: Option<MirExprId>,
     },
+    Unary {
+        operator: String,
+        operand: MirExprId,
+    },
     Binary {
         operator: String,
         left: MirExprId,
@@ -353,6 +502,10 @@ pub enum MirExprKind {
     PerformCall {
         call: MirEffectCall,
     },
+    Handle {
+        target: MirExprId,
+        handler: MirHandler,
+    },
     EffectBlock {
         body: MirExprId,
     },
@@ -389,6 +542,28 @@ pub struct MirEffectCall {
     pub argument: MirExprId,
 }
 
+/// `handle ... with handler` のハンドラ。各節の本体はバックエンドでクロージャ化される。
+#[derive(Debug, Clone, Serialize)]
+pub struct MirHandler {
+    pub effect: Ident,
+    pub operations: Vec<MirHandlerOperation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_clause: Option<MirHandlerReturn>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MirHandlerOperation {
+    pub name: Ident,
+    pub params: Vec<MirParam>,
+    pub body: MirExprId,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MirHandlerReturn {
+    pub value: MirParam,
+    pub body: MirExprId,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct MirInlineAsmOutput {
     pub constraint: String,
@@ -504,6 +679,12 @@ pub struct MatchLoweringPlan {
     pub target_type: String,
     pub arm_count: usize,
     pub arms: Vec<MatchArmLowering>,
+    /// ガードのない腕で対象の値を覆い尽くすなら true。バックエンドは既定分岐を省ける。
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub exhaustive: bool,
+    /// 対象が和型なら宣言順のコンストラクタ名。バックエンドのタグ分岐に使う。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constructors: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -557,7 +738,7 @@ fn lower_function(
     let mut builder = MirExprBuilder::new(function.name.clone());
     let body = builder.lower_expr(&function.body);
     let (exprs, qualified_calls) = builder.finish();
-    let mir_function = MirFunction {
+    let mut mir_function = MirFunction {
         name: function.name.clone(),
         span: function.span,
         attributes: function.attributes.clone(),
@@ -577,7 +758,10 @@ fn lower_function(
         body,
         exprs,
         dict_ref_ids: function.dict_ref_ids.clone(),
+        tail_calls: Vec::new(),
+        ffi_calls: Vec::new(),
     };
+    mir_function.tail_calls = analyze_tail_calls(&mir_function).tail_calls;
     (mir_function, qualified_calls)
 }
 
@@ -744,7 +928,7 @@ impl MirExprBuilder {
                 tail,
                 defers,
             } => {
-                let statements = statements
+                let statements = statements_before_tail(statements, tail.as_deref())
                     .iter()
                     .map(|stmt| self.lower_stmt(stmt))
                     .collect::<Vec<_>>();
@@ -766,6 +950,10 @@ impl MirExprBuilder {
             typed::TypedExprKind::Propagate { expr } => MirExprKind::Propagate {
                 expr: self.lower_expr(expr),
             },
+            typed::TypedExprKind::Unary { operator, operand } => MirExprKind::Unary {
+                operator: operator.clone(),
+                operand: self.lower_expr(operand),
+            },
             typed::TypedExprKind::Binary {
                 operator,
                 left,
@@ -787,7 +975,11 @@ impl MirExprBuilder {
                 target: self.lower_expr(target),
                 index: self.lower_expr(index),
             },
-            typed::TypedExprKind::Match { target, arms } => MirExprKind::Match {
+            typed::TypedExprKind::Match {
+                target,
+                arms,
+                coverage,
+            } => MirExprKind::Match {
                 target: self.lower_expr(target),
                 arms: arms
                     .iter()
@@ -798,7 +990,7 @@ impl MirExprBuilder {
                         body: self.lower_expr(&arm.body),
                     })
                     .collect(),
-                lowering: build_match_lowering(expr.span, target, arms),
+                lowering: build_match_lowering(expr.span, target, arms, coverage),
             },
             typed::TypedExprKind::IfElse {
                 condition,
@@ -815,6 +1007,40 @@ impl MirExprBuilder {
                     argument: self.lower_expr(&call.argument),
                 },
             },
+            typed::TypedExprKind::Handle { target, handler } => MirExprKind::Handle {
+                target: self.lower_expr(target),
+                handler: MirHandler {
+                    effect: handler.effect.clone(),
+                    operations: handler
+                        .operations
+                        .iter()
+                        .map(|operation| MirHandlerOperation {
+                            name: operation.name.clone(),
+                            params: operation
+                                .params
+                                .iter()
+                                .map(|param| MirParam {
+                                    name: param.name.clone(),
+                                    span: param.span,
+                                    ty: normalize_mir_type_label(&param.ty),
+                                })
+                                .collect(),
+                            body: self.lower_expr(&operation.body),
+                        })
+                        .collect(),
+                    return_clause: handler
+                        .return_clause
+                        .as_ref()
+                        .map(|clause| MirHandlerReturn {
+                            value: MirParam {
+                                name: clause.value.name.clone(),
+                                span: clause.value.span,
+                                ty: normalize_mir_type_label(&clause.value.ty),
+                            },
+                            body: self.lower_expr(&clause.body),
+                        }),
+                },
+            },
             typed::TypedExprKind::EffectBlock { body } => MirExprKind::EffectBlock {
                 body: self.lower_expr(body),
             },
@@ -963,6 +1189,21 @@ fn map_qualified_call_kind(kind: &typed::QualifiedCallKind) -> MirQualifiedCallK
     }
 }
 
+/// 型付き AST は末尾式を最後の式文としても保持するため、MIR では末尾式としてだけ下ろす。
+/// 重複する最後の式文を除いた文を返す。
+fn statements_before_tail<'a>(
+    statements: &'a [typed::TypedStmt],
+    tail: Option<&typed::TypedExpr>,
+) -> &'a [typed::TypedStmt] {
+    match (statements.split_last(), tail) {
+        (Some((last, rest)), Some(tail)) => match &last.kind {
+            typed::TypedStmtKind::Expr { expr } if expr.span == tail.span => rest,
+            _ => statements,
+        },
+        _ => statements,
+    }
+}
+
 fn lower_pattern(pattern: &typed::TypedPattern) -> MirPattern {
     let kind = match &pattern.kind {
         typed::TypedPatternKind::Wildcard => MirPatternKind::Wildcard,
@@ -1069,6 +1310,7 @@ fn build_match_lowering(
     match_span: Span,
     target: &typed::TypedExpr,
     arms: &[typed::TypedMatchArm],
+    coverage: &typed::TypedMatchCoverage,
 ) -> MatchLoweringPlan {
     let arms_lowered = arms
         .iter()
@@ -1084,6 +1326,8 @@ fn build_match_lowering(
         target_type: target.ty.clone(),
         arm_count: arms.len(),
         arms: arms_lowered,
+        exhaustive: coverage.exhaustive,
+        constructors: coverage.constructors.clone(),
     }
 }
 
@@ -1263,8 +1507,12 @@ fn collect_match_lowerings_from_expr(
     plans: &mut Vec<MatchLoweringPlan>,
 ) {
     match &expr.kind {
-        typed::TypedExprKind::Match { target, arms } => {
-            let lowering = build_match_lowering(expr.span, target, arms);
+        typed::TypedExprKind::Match {
+            target,
+            arms,
+            coverage,
+        } => {
+            let lowering = build_match_lowering(expr.span, target, arms, coverage);
             plans.push(MatchLoweringPlan {
                 owner: owner.to_string(),
                 ..lowering
@@ -1295,6 +1543,9 @@ fn collect_match_lowerings_from_expr(
             collect_match_lowerings_from_expr(target, owner, plans);
             collect_match_lowerings_from_expr(index, owner, plans);
         }
+        typed::TypedExprKind::Unary { operand, .. } => {
+            collect_match_lowerings_from_expr(operand, owner, plans);
+        }
         typed::TypedExprKind::Binary { left, right, .. } => {
             collect_match_lowerings_from_expr(left, owner, plans);
             collect_match_lowerings_from_expr(right, owner, plans);
@@ -1340,6 +1591,15 @@ fn collect_match_lowerings_from_expr(
         typed::TypedExprKind::PerformCall { call } => {
             collect_match_lowerings_from_expr(&call.argument, owner, plans);
         }
+        typed::TypedExprKind::Handle { target, handler } => {
+            collect_match_lowerings_from_expr(target, owner, plans);
+            for operation in &handler.operations {
+                collect_match_lowerings_from_expr(&operation.body, owner, plans);
+            }
+            if let Some(clause) = &handler.return_clause {
+                collect_match_lowerings_from_expr(&clause.body, owner, plans);
+            }
+        }
         typed::TypedExprKind::InlineAsm {
             outputs, inputs, ..
         } => {
@@ -0,0 +1,2099 @@
+//! MIR 最適化パイプライン。
+//!
+//! 型検査が生成した `MirModule` をバックエンドへ渡す前に関数単位で書き換える。
+//! 各パスは式アリーナをその場で書き換え、参照されなくなった式は最後の圧縮で
+//! 取り除いて `MirExprId` を後行順に振り直す。パイプラインの構成は
+//! `reml_llvm_backend::OptimizationLevel` に対応させる。
+//!
+//! ブロックの末尾式は最後の式文としても保持される（同じ Span を持つ別ノード）ため、
+//! 各パスはこの重複を崩さないように書き換える。バックエンドは Span が一致する場合に
+//! 末尾式を一度だけ評価する。
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+
+use reml_llvm_backend::OptimizationLevel;
+use serde::Serialize;
+
+use crate::parser::ast::{Ident, IntBase, Literal, LiteralKind};
+use crate::semantics::mir::{
+    annotate_ffi_calls, MirExpr, MirExprId, MirExprKind, MirFunction, MirLambdaCapture, MirModule,
+    MirParam, MirPattern, MirPatternKind, MirStmt, MirStmtKind,
+};
+use crate::semantics::mir_tail::annotate_tail_calls;
+
+/// 最適化パス。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MirPass {
+    /// リテラル同士の単項・二項演算と定数条件の分岐を畳み込み、不変束縛の定数を伝播する。
+    ConstantFolding,
+    /// `let x = y` で導入された別名を元の変数へ置き換える。
+    CopyPropagation,
+    /// 効果のない式文、参照されない束縛、`return` 以降の文を取り除く。
+    DeadCodeElimination,
+    /// 小さな非再帰関数を呼び出し元へ展開する。
+    Inlining,
+    /// 検査対象の値が確定している `match` から到達しない腕を削る。
+    MatchArmPruning,
+    /// 入れ子のブロックを外側のブロックへ併合する。
+    BlockMerging,
+}
+
+impl MirPass {
+    pub fn name(self) -> &'static str {
+        match self {
+            MirPass::ConstantFolding => "constant_folding",
+            MirPass::CopyPropagation => "copy_propagation",
+            MirPass::DeadCodeElimination => "dead_code_elimination",
+            MirPass::Inlining => "inlining",
+            MirPass::MatchArmPruning => "match_arm_pruning",
+            MirPass::BlockMerging => "block_merging",
+        }
+    }
+}
+
+/// `-O` 系フラグの値を最適化レベルへ変換する。
+pub fn parse_optimization_level(value: &str) -> Option<OptimizationLevel> {
+    match value.trim_start_matches('O') {
+        "0" => Some(OptimizationLevel::O0),
+        "1" => Some(OptimizationLevel::O1),
+        "2" => Some(OptimizationLevel::O2),
+        "3" => Some(OptimizationLevel::O3),
+        "s" => Some(OptimizationLevel::Os),
+        _ => None,
+    }
+}
+
+/// パイプラインの実行設定。
+#[derive(Debug, Clone, Copy)]
+pub struct MirOptOptions {
+    pub level: OptimizationLevel,
+    /// 書き換えが発生した関数ごとにパス前後のダンプを記録する。
+    pub record_dumps: bool,
+}
+
+impl MirOptOptions {
+    pub fn new(level: OptimizationLevel) -> Self {
+        Self {
+            level,
+            record_dumps: false,
+        }
+    }
+
+    pub fn with_dumps(mut self, enabled: bool) -> Self {
+        self.record_dumps = enabled;
+        self
+    }
+}
+
+impl Default for MirOptOptions {
+    fn default() -> Self {
+        Self::new(OptimizationLevel::O2)
+    }
+}
+
+/// パスごとの統計。
+#[derive(Debug, Clone, Serialize)]
+pub struct MirPassStats {
+    pub pass: MirPass,
+    /// パスを実行したラウンド数。
+    pub runs: usize,
+    /// 書き換えが発生した関数の延べ数（ラウンドをまたいで数える）。
+    pub changed_functions: usize,
+    /// 書き換えた式・文の総数。
+    pub rewrites: usize,
+}
+
+/// 1 つの関数に対するパス前後のダンプ。
+#[derive(Debug, Clone, Serialize)]
+pub struct MirPassDump {
+    pub pass: MirPass,
+    pub round: usize,
+    pub function: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// パイプライン全体の実行結果。`--emit-mir=optimized` の出力に埋め込まれる。
+#[derive(Debug, Clone, Serialize)]
+pub struct MirOptReport {
+    pub level: String,
+    pub rounds: usize,
+    pub exprs_before: usize,
+    pub exprs_after: usize,
+    pub passes: Vec<MirPassStats>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dumps: Vec<MirPassDump>,
+}
+
+impl MirOptReport {
+    pub fn stats(&self, pass: MirPass) -> Option<&MirPassStats> {
+        self.passes.iter().find(|stats| stats.pass == pass)
+    }
+}
+
+/// 最適化レベルに応じたパス列を順に適用するパスマネージャ。
+#[derive(Debug, Clone)]
+pub struct MirPassManager {
+    options: MirOptOptions,
+    passes: Vec<MirPass>,
+    max_rounds: usize,
+    inline_budget: usize,
+}
+
+impl MirPassManager {
+    pub fn new(options: MirOptOptions) -> Self {
+        let scalar = [
+            MirPass::ConstantFolding,
+            MirPass::CopyPropagation,
+            MirPass::MatchArmPruning,
+            MirPass::DeadCodeElimination,
+            MirPass::BlockMerging,
+        ];
+        let with_inlining = || {
+            let mut passes = vec![MirPass::Inlining];
+            passes.extend(scalar);
+            passes
+        };
+        // インライン化の上限は呼び出し先本体の式数で数える。
+        let (passes, max_rounds, inline_budget) = match options.level {
+            OptimizationLevel::O0 => (Vec::new(), 0, 0),
+            OptimizationLevel::O1 => (scalar.to_vec(), 2, 0),
+            OptimizationLevel::O2 => (with_inlining(), 4, 24),
+            OptimizationLevel::O3 => (with_inlining(), 8, 64),
+            OptimizationLevel::Os => (with_inlining(), 4, 8),
+        };
+        Self {
+            options,
+            passes,
+            max_rounds,
+            inline_budget,
+        }
+    }
+
+    pub fn passes(&self) -> &[MirPass] {
+        &self.passes
+    }
+
+    /// モジュール内の全関数へパイプラインを適用する。書き換えがなくなるか
+    /// 上限ラウンドに達した時点で終了し、最後に式アリーナを圧縮する。
+    pub fn run(&self, module: &mut MirModule) -> MirOptReport {
+        let exprs_before = count_exprs(module);
+        let mut passes: Vec<MirPassStats> = self
+            .passes
+            .iter()
+            .map(|pass| MirPassStats {
+                pass: *pass,
+                runs: 0,
+                changed_functions: 0,
+                rewrites: 0,
+            })
+            .collect();
+        let mut dumps = Vec::new();
+        let mut rounds = 0;
+        for round in 1..=self.max_rounds {
+            rounds = round;
+            let mut changed = false;
+            for (pass, stats) in self.passes.iter().zip(passes.iter_mut()) {
+                let candidates = (*pass == MirPass::Inlining)
+                    .then(|| InlineCandidates::collect(module, self.inline_budget));
+                for function in module.functions.iter_mut() {
+                    let before = self
+                        .options
+                        .record_dumps
+                        .then(|| render_mir_function(function));
+                    let rewrites = match pass {
+                        MirPass::ConstantFolding => fold_constants(function),
+                        MirPass::CopyPropagation => propagate_copies(function),
+                        MirPass::DeadCodeElimination => eliminate_dead_code(function),
+                        MirPass::Inlining => match &candidates {
+                            Some(candidates) => inline_calls(function, candidates),
+                            None => 0,
+                        },
+                        MirPass::MatchArmPruning => prune_match_arms(function),
+                        MirPass::BlockMerging => merge_blocks(function),
+                    };
+                    if rewrites == 0 {
+                        continue;
+                    }
+                    changed = true;
+                    stats.changed_functions += 1;
+                    stats.rewrites += rewrites;
+                    if let Some(before) = before {
+                        dumps.push(MirPassDump {
+                            pass: *pass,
+                            round,
+                            function: function.name.clone(),
+                            before,
+                            after: render_mir_function(function),
+                        });
+                    }
+                }
+                stats.runs += 1;
+            }
+            if !changed {
+                break;
+            }
+        }
+        if rounds > 0 {
+            let remaps: Vec<(String, BTreeMap<MirExprId, MirExprId>)> = module
+                .functions
+                .iter_mut()
+                .map(|function| (function.name.clone(), compact_function(function)))
+                .collect();
+            for (owner, remap) in remaps {
+                remap_qualified_calls(module, &owner, &remap);
+            }
+            annotate_tail_calls(module);
+            annotate_ffi_calls(module);
+        }
+        MirOptReport {
+            level: format!("{:?}", self.options.level),
+            rounds,
+            exprs_before,
+            exprs_after: count_exprs(module),
+            passes,
+            dumps,
+        }
+    }
+}
+
+/// `MirModule` を指定レベルで最適化し、結果をモジュールへ記録する。
+pub fn optimize_module(module: &mut MirModule, options: MirOptOptions) -> MirOptReport {
+    let report = MirPassManager::new(options).run(module);
+    module.optimization = Some(report.clone());
+    report
+}
+
+fn count_exprs(module: &MirModule) -> usize {
+    module
+        .functions
+        .iter()
+        .map(|function| function.exprs.len())
+        .sum()
+}
+
+// ---------------------------------------------------------------------------
+// 式アリーナの走査
+
+pub(super) fn stmt_child_ids(stmt: &MirStmt) -> Vec<MirExprId> {
+    match &stmt.kind {
+        MirStmtKind::Let { value, .. } => vec![*value],
+        MirStmtKind::Expr { expr } | MirStmtKind::Defer { expr } => vec![*expr],
+        MirStmtKind::Assign { target, value } => vec![*target, *value],
+    }
+}
+
+pub(super) fn child_ids(kind: &MirExprKind) -> Vec<MirExprId> {
+    let mut ids = Vec::new();
+    match kind {
+        MirExprKind::Literal(_) | MirExprKind::Identifier { .. } | MirExprKind::Unknown => {}
+        MirExprKind::Call { callee, args } => {
+            ids.push(*callee);
+            ids.extend(args);
+        }
+        MirExprKind::Lambda { body, .. }
+        | MirExprKind::EffectBlock { body }
+        | MirExprKind::Async { body, .. }
+        | MirExprKind::Unsafe { body } => ids.push(*body),
+        MirExprKind::Rec { target, .. }
+        | MirExprKind::FieldAccess { target, .. }
+        | MirExprKind::TupleAccess { target, .. } => ids.push(*target),
+        MirExprKind::Block {
+            statements,
+            tail,
+            defers,
+            defer_lifo,
+        } => {
+            for stmt in statements {
+                ids.extend(stmt_child_ids(stmt));
+            }
+            ids.extend(tail);
+            ids.extend(defers);
+            ids.extend(defer_lifo);
+        }
+        MirExprKind::Return { value } => ids.extend(value),
+        MirExprKind::Panic { argument } => ids.extend(argument),
+        MirExprKind::Propagate { expr } | MirExprKind::Await { expr } => ids.push(*expr),
+        MirExprKind::Unary { operand, .. } => ids.push(*operand),
+        MirExprKind::Binary { left, right, .. } => ids.extend([*left, *right]),
+        MirExprKind::Index { target, index } => ids.extend([*target, *index]),
+        MirExprKind::Match { target, arms, .. } => {
+            ids.push(*target);
+            for arm in arms {
+                ids.extend(arm.guard);
+                ids.push(arm.body);
+            }
+        }
+        MirExprKind::IfElse {
+            condition,
+            then_branch,
+            else_branch,
+        } => ids.extend([*condition, *then_branch, *else_branch]),
+        MirExprKind::PerformCall { call } => ids.push(call.argument),
+        MirExprKind::Handle { target, handler } => {
+            ids.push(*target);
+            ids.extend(handler.operations.iter().map(|operation| operation.body));
+            ids.extend(handler.return_clause.as_ref().map(|clause| clause.body));
+        }
+        MirExprKind::InlineAsm {
+            outputs, inputs, ..
+        } => {
+            ids.extend(outputs.iter().map(|output| output.target));
+            ids.extend(inputs.iter().map(|input| input.expr));
+        }
+        MirExprKind::LlvmIr { inputs, .. } => ids.extend(inputs),
+    }
+    ids
+}
+
+fn remap_child_ids(kind: &mut MirExprKind, map: &mut impl FnMut(MirExprId) -> MirExprId) {
+    let mut apply = |id: &mut MirExprId| *id = map(*id);
+    match kind {
+        MirExprKind::Literal(_) | MirExprKind::Identifier { .. } | MirExprKind::Unknown => {}
+        MirExprKind::Call { callee, args } => {
+            apply(callee);
+            args.iter_mut().for_each(&mut apply);
+        }
+        MirExprKind::Lambda { body, .. }
+        | MirExprKind::EffectBlock { body }
+        | MirExprKind::Async { body, .. }
+        | MirExprKind::Unsafe { body } => apply(body),
+        MirExprKind::Rec { target, .. }
+        | MirExprKind::FieldAccess { target, .. }
+        | MirExprKind::TupleAccess { target, .. } => apply(target),
+        MirExprKind::Block {
+            statements,
+            tail,
+            defers,
+            defer_lifo,
+        } => {
+            for stmt in statements.iter_mut() {
+                match &mut stmt.kind {
+                    MirStmtKind::Let { value, .. } => apply(value),
+                    MirStmtKind::Expr { expr } | MirStmtKind::Defer { expr } => apply(expr),
+                    MirStmtKind::Assign { target, value } => {
+                        apply(target);
+                        apply(value);
+                    }
+                }
+            }
+            tail.iter_mut().for_each(&mut apply);
+            defers.iter_mut().for_each(&mut apply);
+            defer_lifo.iter_mut().for_each(&mut apply);
+        }
+        MirExprKind::Return { value } => value.iter_mut().for_each(&mut apply),
+        MirExprKind::Panic { argument } => argument.iter_mut().for_each(&mut apply),
+        MirExprKind::Propagate { expr } | MirExprKind::Await { expr } => apply(expr),
+        MirExprKind::Unary { operand, .. } => apply(operand),
+        MirExprKind::Binary { left, right, .. } => {
+            apply(left);
+            apply(right);
+        }
+        MirExprKind::Index { target, index } => {
+            apply(target);
+            apply(index);
+        }
+        MirExprKind::Match { target, arms, .. } => {
+            apply(target);
+            for arm in arms.iter_mut() {
+                arm.guard.iter_mut().for_each(&mut apply);
+                apply(&mut arm.body);
+            }
+        }
+        MirExprKind::IfElse {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            apply(condition);
+            apply(then_branch);
+            apply(else_branch);
+        }
+        MirExprKind::PerformCall { call } => apply(&mut call.argument),
+        MirExprKind::Handle { target, handler } => {
+            apply(target);
+            for operation in handler.operations.iter_mut() {
+                apply(&mut operation.body);
+            }
+            if let Some(clause) = handler.return_clause.as_mut() {
+                apply(&mut clause.body);
+            }
+        }
+        MirExprKind::InlineAsm {
+            outputs, inputs, ..
+        } => {
+            for output in outputs.iter_mut() {
+                apply(&mut output.target);
+            }
+            for input in inputs.iter_mut() {
+                apply(&mut input.expr);
+            }
+        }
+        MirExprKind::LlvmIr { inputs, .. } => inputs.iter_mut().for_each(&mut apply),
+    }
+}
+
+/// `root` から到達できる式を子を先にした後行順で返す。
+fn post_order(exprs: &[MirExpr], root: MirExprId) -> Vec<MirExprId> {
+    let mut order = Vec::new();
+    let mut visited = vec![false; exprs.len()];
+    let mut stack = vec![(root, false)];
+    while let Some((id, expanded)) = stack.pop() {
+        if expanded {
+            order.push(id);
+            continue;
+        }
+        if id >= exprs.len() || visited[id] {
+            continue;
+        }
+        visited[id] = true;
+        stack.push((id, true));
+        for child in child_ids(&exprs[id].kind).into_iter().rev() {
+            stack.push((child, false));
+        }
+    }
+    order
+}
+
+/// 到達可能な式だけを残して ID を後行順に振り直し、旧 ID から新 ID への対応を返す。
+fn compact_function(function: &mut MirFunction) -> BTreeMap<MirExprId, MirExprId> {
+    let order = post_order(&function.exprs, function.body);
+    let remap: BTreeMap<MirExprId, MirExprId> = order
+        .iter()
+        .enumerate()
+        .map(|(new_id, old_id)| (*old_id, new_id))
+        .collect();
+    let identity = order.iter().enumerate().all(|(new, old)| new == *old)
+        && order.len() == function.exprs.len();
+    if identity {
+        return remap;
+    }
+    let mut exprs = Vec::with_capacity(order.len());
+    for old_id in &order {
+        let mut expr = function.exprs[*old_id].clone();
+        expr.id = remap[old_id];
+        remap_child_ids(&mut expr.kind, &mut |id| remap[&id]);
+        exprs.push(expr);
+    }
+    function.body = remap[&function.body];
+    function.exprs = exprs;
+    remap
+}
+
+/// 修飾呼び出し表は `関数名#式ID` をキーにしているため、圧縮後の ID へ追従させる。
+fn remap_qualified_calls(
+    module: &mut MirModule,
+    owner: &str,
+    remap: &BTreeMap<MirExprId, MirExprId>,
+) {
+    let prefix = format!("{owner}#");
+    let keys: Vec<String> = module
+        .qualified_calls
+        .keys()
+        .filter(|key| key.starts_with(&prefix))
+        .cloned()
+        .collect();
+    let mut moved = Vec::new();
+    for key in keys {
+        let Some(call) = module.qualified_calls.remove(&key) else {
+            continue;
+        };
+        let Ok(old_id) = key[prefix.len()..].parse::<MirExprId>() else {
+            moved.push((key, call));
+            continue;
+        };
+        if let Some(new_id) = remap.get(&old_id) {
+            moved.push((format!("{prefix}{new_id}"), call));
+        }
+    }
+    module.qualified_calls.extend(moved);
+}
+
+// ---------------------------------------------------------------------------
+// 名前と束縛の解析
+
+fn pattern_binders(pattern: &MirPattern, out: &mut BTreeSet<String>) {
+    match &pattern.kind {
+        MirPatternKind::Wildcard | MirPatternKind::Literal(_) | MirPatternKind::Regex { .. } => {}
+        MirPatternKind::Var { name } => {
+            out.insert(name.clone());
+        }
+        MirPatternKind::Tuple { elements } => {
+            for element in elements {
+                pattern_binders(element, out);
+            }
+        }
+        MirPatternKind::Record { fields, .. } => {
+            for field in fields {
+                match &field.value {
+                    Some(value) => pattern_binders(value, out),
+                    None => {
+                        out.insert(field.key.clone());
+                    }
+                }
+            }
+        }
+        MirPatternKind::Constructor { args, .. } => {
+            for arg in args {
+                pattern_binders(arg, out);
+            }
+        }
+        MirPatternKind::Binding { name, pattern, .. } => {
+            out.insert(name.clone());
+            pattern_binders(pattern, out);
+        }
+        MirPatternKind::Or { variants } => {
+            for variant in variants {
+                pattern_binders(variant, out);
+            }
+        }
+        MirPatternKind::Slice(slice) => {
+            for element in slice.head.iter().chain(&slice.tail) {
+                pattern_binders(element, out);
+            }
+            if let Some(binding) = slice.rest.as_ref().and_then(|rest| rest.binding.as_ref()) {
+                out.insert(binding.clone());
+            }
+        }
+        MirPatternKind::Range { start, end, .. } => {
+            for bound in start.iter().chain(end) {
+                pattern_binders(bound, out);
+            }
+        }
+        MirPatternKind::Active(call) => {
+            if let Some(argument) = &call.argument {
+                pattern_binders(argument, out);
+            }
+            if let Some(binding) = &call.input_binding {
+                out.insert(binding.clone());
+            }
+        }
+    }
+}
+
+fn pattern_binds(pattern: &MirPattern, name: &str) -> bool {
+    let mut binders = BTreeSet::new();
+    pattern_binders(pattern, &mut binders);
+    binders.contains(name)
+}
+
+/// 式が導入する束縛名（let・ラムダ引数・match 腕・ハンドラ引数）を集める。
+pub(super) fn collect_binders(exprs: &[MirExpr], roots: &[MirExprId], out: &mut BTreeSet<String>) {
+    for root in roots {
+        for id in post_order(exprs, *root) {
+            match &exprs[id].kind {
+                MirExprKind::Block { statements, .. } => {
+                    for stmt in statements {
+                        if let MirStmtKind::Let { pattern, .. } = &stmt.kind {
+                            pattern_binders(pattern, out);
+                        }
+                    }
+                }
+                MirExprKind::Lambda { params, .. } => {
+                    out.extend(params.iter().map(|param| param.name.clone()));
+                }
+                MirExprKind::Match { arms, .. } => {
+                    for arm in arms {
+                        pattern_binders(&arm.pattern, out);
+                        out.extend(arm.alias.clone());
+                    }
+                }
+                MirExprKind::Handle { handler, .. } => {
+                    for operation in &handler.operations {
+                        out.extend(operation.params.iter().map(|param| param.name.clone()));
+                    }
+                    if let Some(clause) = &handler.return_clause {
+                        out.insert(clause.value.name.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// 式が参照する名前（識別子・キャプチャ・`rec` 対象）を集める。シャドーイングは考慮しない。
+fn collect_mentions(exprs: &[MirExpr], roots: &[MirExprId], out: &mut BTreeSet<String>) {
+    for root in roots {
+        for id in post_order(exprs, *root) {
+            match &exprs[id].kind {
+                MirExprKind::Identifier { ident } => {
+                    out.insert(ident.name.clone());
+                }
+                MirExprKind::Lambda { captures, .. } => {
+                    out.extend(captures.iter().map(|capture| capture.name.clone()));
+                }
+                MirExprKind::Rec {
+                    ident: Some(ident), ..
+                } => {
+                    out.insert(ident.name.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// 代入・インラインアセンブリの出力先になる変数名を集める。
+fn collect_assigned(exprs: &[MirExpr], roots: &[MirExprId], out: &mut BTreeSet<String>) {
+    for root in roots {
+        for id in post_order(exprs, *root) {
+            match &exprs[id].kind {
+                MirExprKind::Block { statements, .. } => {
+                    for stmt in statements {
+                        match &stmt.kind {
+                            MirStmtKind::Assign { target, .. } => {
+                                out.extend(place_root(exprs, *target));
+                            }
+                            MirStmtKind::Let {
+                                pattern,
+                                mutable: true,
+                                ..
+                            } => pattern_binders(pattern, out),
+                            _ => {}
+                        }
+                    }
+                }
+                MirExprKind::InlineAsm { outputs, .. } => {
+                    for output in outputs {
+                        out.extend(place_root(exprs, output.target));
+                    }
+                }
+                MirExprKind::Lambda { captures, .. } => out.extend(
+                    captures
+                        .iter()
+                        .filter(|capture| capture.mutable)
+                        .map(|capture| capture.name.clone()),
+                ),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// `a.b[0]` のような代入先の根となる変数名。
+fn place_root(exprs: &[MirExpr], id: MirExprId) -> Option<String> {
+    match &exprs.get(id)?.kind {
+        MirExprKind::Identifier { ident } => Some(ident.name.clone()),
+        MirExprKind::FieldAccess { target, .. }
+        | MirExprKind::TupleAccess { target, .. }
+        | MirExprKind::Index { target, .. } => place_root(exprs, *target),
+        _ => None,
+    }
+}
+
+/// 評価しても観測可能な効果を持たず、失敗もしない式かどうか。
+fn is_pure(exprs: &[MirExpr], id: MirExprId) -> bool {
+    let Some(expr) = exprs.get(id) else {
+        return false;
+    };
+    match &expr.kind {
+        MirExprKind::Literal(_) | MirExprKind::Identifier { .. } | MirExprKind::Lambda { .. } => {
+            true
+        }
+        MirExprKind::Unary { operand, .. } => is_pure(exprs, *operand),
+        MirExprKind::Binary {
+            operator,
+            left,
+            right,
+        } => {
+            // ゼロ除算と `MIN / -1` はトラップし得るため、除数が安全なリテラルの場合だけ純粋とみなす。
+            let divisor_safe = !matches!(operator.as_str(), "/" | "%")
+                || matches!(int_literal(exprs, *right), Some(value) if value != 0 && value != -1);
+            divisor_safe && is_pure(exprs, *left) && is_pure(exprs, *right)
+        }
+        MirExprKind::FieldAccess { target, .. } | MirExprKind::TupleAccess { target, .. } => {
+            is_pure(exprs, *target)
+        }
+        MirExprKind::IfElse {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            is_pure(exprs, *condition)
+                && is_pure(exprs, *then_branch)
+                && is_pure(exprs, *else_branch)
+        }
+        MirExprKind::Block {
+            statements,
+            tail,
+            defers,
+            ..
+        } => {
+            defers.is_empty()
+                && tail.is_none_or(|tail| is_pure(exprs, tail))
+                && statements.iter().all(|stmt| match &stmt.kind {
+                    MirStmtKind::Let { pattern, value, .. } => {
+                        is_simple_binding(pattern) && is_pure(exprs, *value)
+                    }
+                    MirStmtKind::Expr { expr } => is_pure(exprs, *expr),
+                    _ => false,
+                })
+        }
+        _ => false,
+    }
+}
+
+/// 失敗しない単純な束縛パターン（変数か `_`）。
+fn is_simple_binding(pattern: &MirPattern) -> bool {
+    match &pattern.kind {
+        MirPatternKind::Wildcard => true,
+        MirPatternKind::Var { name } => !starts_uppercase(name),
+        _ => false,
+    }
+}
+
+fn starts_uppercase(name: &str) -> bool {
+    name.chars().next().is_some_and(char::is_uppercase)
+}
+
+fn int_literal(exprs: &[MirExpr], id: MirExprId) -> Option<i64> {
+    match &exprs.get(id)?.kind {
+        MirExprKind::Literal(Literal {
+            value: LiteralKind::Int { value, .. },
+        }) => Some(*value),
+        _ => None,
+    }
+}
+
+fn bool_literal(exprs: &[MirExpr], id: MirExprId) -> Option<bool> {
+    match &exprs.get(id)?.kind {
+        MirExprKind::Literal(Literal {
+            value: LiteralKind::Bool { value },
+        }) => Some(*value),
+        _ => None,
+    }
+}
+
+/// 複製しても意味が変わらないスカラーのリテラル。
+fn scalar_literal(exprs: &[MirExpr], id: MirExprId) -> Option<&Literal> {
+    match &exprs.get(id)?.kind {
+        MirExprKind::Literal(literal) => match literal.value {
+            LiteralKind::Int { .. }
+            | LiteralKind::Float { .. }
+            | LiteralKind::Char { .. }
+            | LiteralKind::Bool { .. }
+            | LiteralKind::Unit => Some(literal),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn int_kind(value: i64) -> MirExprKind {
+    MirExprKind::Literal(Literal {
+        value: LiteralKind::Int {
+            value,
+            raw: value.to_string(),
+            base: IntBase::Base10,
+        },
+    })
+}
+
+fn bool_kind(value: bool) -> MirExprKind {
+    MirExprKind::Literal(Literal {
+        value: LiteralKind::Bool { value },
+    })
+}
+
+/// 式ノードの中身を別ノードの中身で置き換える。Span は置き換え前の位置を保持する。
+fn replace_with(exprs: &mut [MirExpr], id: MirExprId, from: MirExprId) {
+    let source = exprs[from].clone();
+    let target = &mut exprs[id];
+    target.kind = source.kind;
+    target.ty = source.ty;
+    target.dict_ref_ids = source.dict_ref_ids;
+}
+
+// ---------------------------------------------------------------------------
+// 束縛の伝播
+
+/// シャドーイングを考慮して `name` の参照を `replacement` へ置き換え、置換数を返す。
+fn substitute(
+    exprs: &mut [MirExpr],
+    id: MirExprId,
+    name: &str,
+    replacement: &MirExprKind,
+) -> usize {
+    if id >= exprs.len() {
+        return 0;
+    }
+    let kind = exprs[id].kind.clone();
+    match &kind {
+        MirExprKind::Identifier { ident } if ident.name == name => {
+            exprs[id].kind = replacement.clone();
+            1
+        }
+        MirExprKind::Lambda { params, body, .. } => {
+            if params.iter().any(|param| param.name == name) {
+                return 0;
+            }
+            let count = substitute(exprs, *body, name, replacement);
+            if count > 0 {
+                if let MirExprKind::Lambda { captures, .. } = &mut exprs[id].kind {
+                    retarget_captures(captures, name, replacement);
+                }
+            }
+            count
+        }
+        MirExprKind::Block {
+            statements,
+            tail,
+            defers,
+            ..
+        } => substitute_in_scope(exprs, statements, *tail, defers, name, replacement),
+        MirExprKind::Match { target, arms, .. } => {
+            let mut count = substitute(exprs, *target, name, replacement);
+            for arm in arms {
+                if pattern_binds(&arm.pattern, name) || arm.alias.as_deref() == Some(name) {
+                    continue;
+                }
+                if let Some(guard) = arm.guard {
+                    count += substitute(exprs, guard, name, replacement);
+                }
+                count += substitute(exprs, arm.body, name, replacement);
+            }
+            count
+        }
+        MirExprKind::Handle { target, handler } => {
+            let mut count = substitute(exprs, *target, name, replacement);
+            for operation in &handler.operations {
+                if operation.params.iter().all(|param| param.name != name) {
+                    count += substitute(exprs, operation.body, name, replacement);
+                }
+            }
+            if let Some(clause) = &handler.return_clause {
+                if clause.value.name != name {
+                    count += substitute(exprs, clause.body, name, replacement);
+                }
+            }
+            count
+        }
+        _ => child_ids(&kind)
+            .into_iter()
+            .map(|child| substitute(exprs, child, name, replacement))
+            .sum(),
+    }
+}
+
+/// 文の並びを順に置換し、`name` を再束縛する `let` に達したら以降を打ち切る。
+fn substitute_in_scope(
+    exprs: &mut [MirExpr],
+    statements: &[MirStmt],
+    tail: Option<MirExprId>,
+    defers: &[MirExprId],
+    name: &str,
+    replacement: &MirExprKind,
+) -> usize {
+    let mut count = 0;
+    for stmt in statements {
+        for child in stmt_child_ids(stmt) {
+            count += substitute(exprs, child, name, replacement);
+        }
+        if let MirStmtKind::Let { pattern, .. } = &stmt.kind {
+            if pattern_binds(pattern, name) {
+                return count;
+            }
+        }
+    }
+    for id in tail.iter().chain(defers) {
+        count += substitute(exprs, *id, name, replacement);
+    }
+    count
+}
+
+/// ラムダのキャプチャ一覧を置換後の参照に合わせる。
+fn retarget_captures(captures: &mut Vec<MirLambdaCapture>, name: &str, replacement: &MirExprKind) {
+    let Some(index) = captures.iter().position(|capture| capture.name == name) else {
+        return;
+    };
+    let capture = captures.remove(index);
+    if let MirExprKind::Identifier { ident } = replacement {
+        if captures.iter().all(|existing| existing.name != ident.name) {
+            captures.insert(
+                index,
+                MirLambdaCapture {
+                    name: ident.name.clone(),
+                    span: capture.span,
+                    mutable: capture.mutable,
+                },
+            );
+        }
+    }
+}
+
+/// ブロック内の不変束縛 `let name = value` を後続の文と末尾式へ伝播する。
+/// `replacement_for` が置換先を返した束縛だけを対象にする。
+fn propagate_bindings(
+    function: &mut MirFunction,
+    replacement_for: impl Fn(&[MirExpr], MirExprId, &BTreeSet<String>) -> Option<MirExprKind>,
+) -> usize {
+    let mut assigned = BTreeSet::new();
+    collect_assigned(&function.exprs, &[function.body], &mut assigned);
+    let mut rewrites = 0;
+    for id in post_order(&function.exprs, function.body) {
+        let MirExprKind::Block {
+            statements,
+            tail,
+            defers,
+            ..
+        } = function.exprs[id].kind.clone()
+        else {
+            continue;
+        };
+        for (index, stmt) in statements.iter().enumerate() {
+            let MirStmtKind::Let {
+                pattern,
+                value,
+                mutable: false,
+            } = &stmt.kind
+            else {
+                continue;
+            };
+            let MirPatternKind::Var { name } = &pattern.kind else {
+                continue;
+            };
+            if assigned.contains(name) {
+                continue;
+            }
+            let Some(replacement) = replacement_for(&function.exprs, *value, &assigned) else {
+                continue;
+            };
+            let rest = &statements[index + 1..];
+            if let MirExprKind::Identifier { ident } = &replacement {
+                // 別名の伝播先で元の変数が再束縛されていると参照先が変わってしまう。
+                let mut roots: Vec<MirExprId> = rest.iter().flat_map(stmt_child_ids).collect();
+                roots.extend(tail);
+                roots.extend(&defers);
+                let mut binders = BTreeSet::new();
+                collect_binders(&function.exprs, &roots, &mut binders);
+                for stmt in rest {
+                    if let MirStmtKind::Let { pattern, .. } = &stmt.kind {
+                        pattern_binders(pattern, &mut binders);
+                    }
+                }
+                if binders.contains(&ident.name) || ident.name == *name {
+                    continue;
+                }
+            }
+            rewrites +=
+                substitute_in_scope(&mut function.exprs, rest, tail, &defers, name, &replacement);
+        }
+    }
+    rewrites
+}
+
+// ---------------------------------------------------------------------------
+// 定数畳み込み
+
+fn fold_constants(function: &mut MirFunction) -> usize {
+    let mut rewrites = 0;
+    loop {
+        let mut changed = propagate_bindings(function, |exprs, value, _| {
+            scalar_literal(exprs, value).map(|literal| MirExprKind::Literal(literal.clone()))
+        });
+        for id in post_order(&function.exprs, function.body) {
+            if fold_expr(&mut function.exprs, id) {
+                changed += 1;
+            }
+        }
+        if changed == 0 {
+            return rewrites;
+        }
+        rewrites += changed;
+    }
+}
+
+fn fold_expr(exprs: &mut [MirExpr], id: MirExprId) -> bool {
+    let kind = exprs[id].kind.clone();
+    let folded = match &kind {
+        MirExprKind::Unary { operator, operand } => fold_unary(exprs, operator, *operand),
+        MirExprKind::Binary {
+            operator,
+            left,
+            right,
+        } => {
+            let (left, right) = (*left, *right);
+            if let Some(kind) = fold_binary(exprs, operator, left, right) {
+                Some(kind)
+            } else {
+                // `&&` / `||` は左辺が定数なら短絡結果か右辺そのものになる。
+                match (operator.as_str(), bool_literal(exprs, left)) {
+                    ("&&", Some(true)) | ("||", Some(false)) => {
+                        replace_with(exprs, id, right);
+                        return true;
+                    }
+                    ("&&", Some(false)) => Some(bool_kind(false)),
+                    ("||", Some(true)) => Some(bool_kind(true)),
+                    _ => None,
+                }
+            }
+        }
+        MirExprKind::IfElse {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            let chosen = match bool_literal(exprs, *condition) {
+                Some(true) => *then_branch,
+                Some(false) => *else_branch,
+                None => return false,
+            };
+            let ty = exprs[id].ty.clone();
+            replace_with(exprs, id, chosen);
+            exprs[id].ty = ty;
+            return true;
+        }
+        _ => None,
+    };
+    match folded {
+        Some(kind) => {
+            exprs[id].kind = kind;
+            true
+        }
+        None => false,
+    }
+}
+
+/// 既定の `Int`（i64）として型付けされた式か。
+///
+/// 畳み込みは i64 の検査付き演算で行うため、幅や符号の異なる整数型（`UInt` や
+/// 未解決の型変数を含む）の式は実行時の演算に任せる。
+fn is_default_int(exprs: &[MirExpr], id: MirExprId) -> bool {
+    exprs.get(id).is_some_and(|expr| expr.ty == "i64")
+}
+
+fn fold_unary(exprs: &[MirExpr], operator: &str, operand: MirExprId) -> Option<MirExprKind> {
+    let literal = scalar_literal(exprs, operand)?;
+    match (operator, &literal.value) {
+        ("-", LiteralKind::Int { value, .. }) if is_default_int(exprs, operand) => {
+            value.checked_neg().map(int_kind)
+        }
+        ("-", LiteralKind::Float { raw }) => {
+            let raw = match raw.strip_prefix('-') {
+                Some(positive) => positive.to_string(),
+                None => format!("-{raw}"),
+            };
+            Some(MirExprKind::Literal(Literal {
+                value: LiteralKind::Float { raw },
+            }))
+        }
+        ("!", LiteralKind::Bool { value }) => Some(bool_kind(!value)),
+        _ => None,
+    }
+}
+
+fn fold_binary(
+    exprs: &[MirExpr],
+    operator: &str,
+    left: MirExprId,
+    right: MirExprId,
+) -> Option<MirExprKind> {
+    let default_int = is_default_int(exprs, left) && is_default_int(exprs, right);
+    let left = scalar_literal(exprs, left)?;
+    let right = scalar_literal(exprs, right)?;
+    match (&left.value, &right.value) {
+        (LiteralKind::Int { value: lhs, .. }, LiteralKind::Int { value: rhs, .. })
+            if default_int =>
+        {
+            let (lhs, rhs) = (*lhs, *rhs);
+            match operator {
+                "+" => lhs.checked_add(rhs).map(int_kind),
+                "-" => lhs.checked_sub(rhs).map(int_kind),
+                "*" => lhs.checked_mul(rhs).map(int_kind),
+                "/" => lhs.checked_div(rhs).map(int_kind),
+                "%" => lhs.checked_rem(rhs).map(int_kind),
+                "==" => Some(bool_kind(lhs == rhs)),
+                "!=" => Some(bool_kind(lhs != rhs)),
+                "<" => Some(bool_kind(lhs < rhs)),
+                "<=" => Some(bool_kind(lhs <= rhs)),
+                ">" => Some(bool_kind(lhs > rhs)),
+                ">=" => Some(bool_kind(lhs >= rhs)),
+                _ => None,
+            }
+        }
+        (LiteralKind::Bool { value: lhs }, LiteralKind::Bool { value: rhs }) => match operator {
+            "&&" => Some(bool_kind(*lhs && *rhs)),
+            "||" => Some(bool_kind(*lhs || *rhs)),
+            "==" => Some(bool_kind(lhs == rhs)),
+            "!=" => Some(bool_kind(lhs != rhs)),
+            _ => None,
+        },
+        (LiteralKind::Char { value: lhs }, LiteralKind::Char { value: rhs }) => match operator {
+            "==" => Some(bool_kind(lhs == rhs)),
+            "!=" => Some(bool_kind(lhs != rhs)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// コピー伝播
+
+fn propagate_copies(function: &mut MirFunction) -> usize {
+    propagate_bindings(function, |exprs, value, assigned| {
+        match &exprs.get(value)?.kind {
+            // 元の変数が書き換えられ得る場合、別名は束縛時点の値を保持しなければならない。
+            MirExprKind::Identifier { ident } if !assigned.contains(&ident.name) => {
+                Some(MirExprKind::Identifier {
+                    ident: ident.clone(),
+                })
+            }
+            _ => None,
+        }
+    })
+}
+
+// ---------------------------------------------------------------------------
+// 不要コード除去
+
+fn eliminate_dead_code(function: &mut MirFunction) -> usize {
+    let mut rewrites = 0;
+    for id in post_order(&function.exprs, function.body) {
+        let MirExprKind::Block {
+            statements,
+            tail,
+            defers,
+            defer_lifo,
+        } = function.exprs[id].kind.clone()
+        else {
+            continue;
+        };
+        let exprs = &function.exprs;
+        let mut kept: Vec<MirStmt> = Vec::with_capacity(statements.len());
+        let mut removed = 0;
+        let mut diverged = false;
+        for (index, stmt) in statements.iter().enumerate() {
+            if diverged {
+                // `return` / `panic` 以降の文には到達しない。
+                removed += 1;
+                continue;
+            }
+            let is_last = index + 1 == statements.len();
+            match &stmt.kind {
+                MirStmtKind::Expr { expr } => {
+                    if matches!(
+                        exprs[*expr].kind,
+                        MirExprKind::Return { .. } | MirExprKind::Panic { .. }
+                    ) {
+                        diverged = true;
+                    } else if is_pure(exprs, *expr) && !(is_last && tail.is_none()) {
+                        removed += 1;
+                        continue;
+                    }
+                }
+                MirStmtKind::Let { pattern, value, .. } if is_simple_binding(pattern) => {
+                    let mut binders = BTreeSet::new();
+                    pattern_binders(pattern, &mut binders);
+                    let mut roots: Vec<MirExprId> = statements[index + 1..]
+                        .iter()
+                        .flat_map(stmt_child_ids)
+                        .collect();
+                    roots.extend(tail);
+                    roots.extend(&defers);
+                    let mut mentions = BTreeSet::new();
+                    collect_mentions(exprs, &roots, &mut mentions);
+                    if binders.iter().all(|name| !mentions.contains(name)) {
+                        removed += 1;
+                        if !is_pure(exprs, *value) {
+                            // 参照されない束縛でも右辺の効果は残す。
+                            kept.push(MirStmt {
+                                span: stmt.span,
+                                kind: MirStmtKind::Expr { expr: *value },
+                            });
+                        }
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            kept.push(stmt.clone());
+        }
+        if removed == 0 {
+            continue;
+        }
+        rewrites += removed;
+        function.exprs[id].kind = MirExprKind::Block {
+            statements: kept,
+            tail,
+            defers,
+            defer_lifo,
+        };
+    }
+    rewrites
+}
+
+// ---------------------------------------------------------------------------
+// インライン化
+
+/// インライン化できる関数の本体を呼び出し元へ複製できる形で保持する。
+struct InlineCandidate {
+    params: Vec<MirParam>,
+    body: MirExprId,
+    exprs: Vec<MirExpr>,
+    /// 本体が参照する自由な名前。呼び出し元の局所変数に捕捉されないことを確認する。
+    free_names: BTreeSet<String>,
+    /// 本体が導入する束縛名。展開後は呼び出し元の局所変数になる。
+    binders: BTreeSet<String>,
+}
+
+struct InlineCandidates {
+    functions: BTreeMap<String, InlineCandidate>,
+    /// 修飾呼び出し表に載っている呼び出し式（`関数名#式ID`）。展開すると対応が崩れる。
+    qualified: BTreeSet<String>,
+}
+
+impl InlineCandidates {
+    fn collect(module: &MirModule, budget: usize) -> Self {
+        let names: BTreeSet<&str> = module
+            .functions
+            .iter()
+            .map(|function| function.name.as_str())
+            .collect();
+        // 関数値として渡される参照も辺として扱い、相互再帰を保守的に検出する。
+        let mut graph: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+        for function in &module.functions {
+            let mut mentions = BTreeSet::new();
+            collect_mentions(&function.exprs, &[function.body], &mut mentions);
+            mentions.retain(|name| names.contains(name.as_str()));
+            graph.insert(function.name.as_str(), mentions);
+        }
+        let mut functions = BTreeMap::new();
+        for function in &module.functions {
+            if is_recursive(&graph, &function.name) || !is_inlinable(module, function, budget) {
+                continue;
+            }
+            let mut binders = BTreeSet::new();
+            collect_binders(&function.exprs, &[function.body], &mut binders);
+            // 局所変数が関数名を隠している本体は、どの参照が大域を指すかを区別できない。
+            if binders.iter().any(|name| names.contains(name.as_str())) {
+                continue;
+            }
+            let mut free_names = BTreeSet::new();
+            collect_mentions(&function.exprs, &[function.body], &mut free_names);
+            free_names.retain(|name| {
+                !binders.contains(name) && function.params.iter().all(|param| param.name != *name)
+            });
+            functions.insert(
+                function.name.clone(),
+                InlineCandidate {
+                    params: function.params.clone(),
+                    body: function.body,
+                    exprs: function.exprs.clone(),
+                    free_names,
+                    binders,
+                },
+            );
+        }
+        Self {
+            functions,
+            qualified: module.qualified_calls.keys().cloned().collect(),
+        }
+    }
+}
+
+fn is_recursive(graph: &BTreeMap<&str, BTreeSet<String>>, name: &str) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack: Vec<&str> = graph
+        .get(name)
+        .map(|edges| edges.iter().map(String::as_str).collect())
+        .unwrap_or_default();
+    while let Some(current) = stack.pop() {
+        if current == name {
+            return true;
+        }
+        if !visited.insert(current) {
+            continue;
+        }
+        if let Some(edges) = graph.get(current) {
+            stack.extend(edges.iter().map(String::as_str));
+        }
+    }
+    false
+}
+
+fn is_inlinable(module: &MirModule, function: &MirFunction, budget: usize) -> bool {
+    if function.varargs
+        || function.is_async
+        || function.is_unsafe
+        || !function.attributes.is_empty()
+        || !function.dict_ref_ids.is_empty()
+    {
+        return false;
+    }
+    // 修飾呼び出し表は `関数名#式ID` で引かれるため、複製すると対応が失われる。
+    let prefix = format!("{}#", function.name);
+    if module
+        .qualified_calls
+        .keys()
+        .any(|key| key.starts_with(&prefix))
+    {
+        return false;
+    }
+    let mut param_names = BTreeSet::new();
+    if !function
+        .params
+        .iter()
+        .all(|param| param_names.insert(param.name.as_str()))
+    {
+        return false;
+    }
+    let body = post_order(&function.exprs, function.body);
+    body.len() <= budget
+        && body.iter().all(|id| {
+            // 呼び出し元の制御を抜ける式は展開先で意味が変わる。
+            !matches!(
+                function.exprs[*id].kind,
+                MirExprKind::Return { .. }
+                    | MirExprKind::Propagate { .. }
+                    | MirExprKind::InlineAsm { .. }
+                    | MirExprKind::LlvmIr { .. }
+                    | MirExprKind::Unknown
+            )
+        })
+}
+
+fn inline_calls(function: &mut MirFunction, candidates: &InlineCandidates) -> usize {
+    let mut local_names: BTreeSet<String> = function
+        .params
+        .iter()
+        .map(|param| param.name.clone())
+        .collect();
+    collect_binders(&function.exprs, &[function.body], &mut local_names);
+    let mut next_suffix = next_inline_suffix(&local_names);
+    let mut rewrites = 0;
+    for id in post_order(&function.exprs, function.body) {
+        let MirExprKind::Call { callee, args } = &function.exprs[id].kind else {
+            continue;
+        };
+        if candidates
+            .qualified
+            .contains(&format!("{}#{id}", function.name))
+        {
+            continue;
+        }
+        let MirExprKind::Identifier { ident } = &function.exprs[*callee].kind else {
+            continue;
+        };
+        if ident.name == function.name || local_names.contains(&ident.name) {
+            continue;
+        }
+        let Some(candidate) = candidates.functions.get(&ident.name) else {
+            continue;
+        };
+        if candidate.params.len() != args.len()
+            || candidate
+                .free_names
+                .iter()
+                .any(|name| local_names.contains(name))
+        {
+            continue;
+        }
+        let args = args.clone();
+        let fresh = inline_call(function, id, candidate, &args, next_suffix);
+        local_names.extend(fresh);
+        local_names.extend(candidate.binders.iter().cloned());
+        next_suffix += 1;
+        rewrites += 1;
+    }
+    rewrites
+}
+
+const INLINE_SUFFIX: &str = "__inline";
+
+/// 既存の展開で使われた引数名と衝突しない接尾辞番号。
+fn next_inline_suffix(local_names: &BTreeSet<String>) -> usize {
+    local_names
+        .iter()
+        .filter_map(|name| {
+            let (_, suffix) = name.rsplit_once(INLINE_SUFFIX)?;
+            suffix.parse::<usize>().ok()
+        })
+        .max()
+        .map_or(0, |max| max + 1)
+}
+
+/// 呼び出し式を `{ let p1 = a1; ...; 本体 }` へ置き換える。引数名は衝突しない名前へ付け替え、
+/// 付け替えた名前を返す。
+fn inline_call(
+    function: &mut MirFunction,
+    call: MirExprId,
+    candidate: &InlineCandidate,
+    args: &[MirExprId],
+    suffix: usize,
+) -> Vec<String> {
+    let mut fresh_names = Vec::with_capacity(args.len());
+    let body = copy_subtree(&mut function.exprs, &candidate.exprs, candidate.body);
+    let mut statements = Vec::with_capacity(args.len());
+    for (param, arg) in candidate.params.iter().zip(args) {
+        let fresh = format!("{}{INLINE_SUFFIX}{suffix}", param.name);
+        fresh_names.push(fresh.clone());
+        let replacement = MirExprKind::Identifier {
+            ident: Ident {
+                name: fresh.clone(),
+                span: param.span,
+            },
+        };
+        substitute(&mut function.exprs, body, &param.name, &replacement);
+        statements.push(MirStmt {
+            span: param.span,
+            kind: MirStmtKind::Let {
+                pattern: MirPattern {
+                    span: param.span,
+                    ty: Some(param.ty.clone()),
+                    kind: MirPatternKind::Var { name: fresh },
+                },
+                value: *arg,
+                mutable: false,
+            },
+        });
+    }
+    function.exprs[call].kind = MirExprKind::Block {
+        statements,
+        tail: Some(body),
+        defers: Vec::new(),
+        defer_lifo: Vec::new(),
+    };
+    fresh_names
+}
+
+/// 別関数の式アリーナから部分木を複製し、新しい根の ID を返す。
+fn copy_subtree(target: &mut Vec<MirExpr>, source: &[MirExpr], root: MirExprId) -> MirExprId {
+    let mut remap = BTreeMap::new();
+    for old_id in post_order(source, root) {
+        let mut expr = source[old_id].clone();
+        let new_id = target.len();
+        expr.id = new_id;
+        remap_child_ids(&mut expr.kind, &mut |id| remap[&id]);
+        target.push(expr);
+        remap.insert(old_id, new_id);
+    }
+    remap[&root]
+}
+
+// ---------------------------------------------------------------------------
+// match 腕の刈り込み
+
+/// コンパイル時に確定している検査対象の値。
+enum KnownValue<'a> {
+    Literal(&'a LiteralKind),
+    /// 大文字で始まる識別子、またはその呼び出しをコンストラクタとみなす。
+    Constructor(&'a str),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ArmTest {
+    Always,
+    Never,
+    Unknown,
+}
+
+fn known_value(exprs: &[MirExpr], id: MirExprId) -> Option<KnownValue<'_>> {
+    match &exprs.get(id)?.kind {
+        MirExprKind::Literal(_) => {
+            scalar_literal(exprs, id).map(|literal| KnownValue::Literal(&literal.value))
+        }
+        MirExprKind::Identifier { ident } if starts_uppercase(&ident.name) => {
+            Some(KnownValue::Constructor(&ident.name))
+        }
+        MirExprKind::Call { callee, .. } => match &exprs.get(*callee)?.kind {
+            MirExprKind::Identifier { ident } if starts_uppercase(&ident.name) => {
+                Some(KnownValue::Constructor(&ident.name))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `Option.Some` と `Some` のような修飾の違いを無視してコンストラクタ名を比べる。
+fn constructor_name(name: &str) -> &str {
+    name.rsplit(['.', ':']).next().unwrap_or(name)
+}
+
+fn literal_equals(lhs: &LiteralKind, rhs: &LiteralKind) -> Option<bool> {
+    match (lhs, rhs) {
+        (LiteralKind::Int { value: a, .. }, LiteralKind::Int { value: b, .. }) => Some(a == b),
+        (LiteralKind::Bool { value: a }, LiteralKind::Bool { value: b }) => Some(a == b),
+        (LiteralKind::Char { value: a }, LiteralKind::Char { value: b }) => Some(a == b),
+        (LiteralKind::Unit, LiteralKind::Unit) => Some(true),
+        _ => None,
+    }
+}
+
+fn test_arm(pattern: &MirPattern, known: &KnownValue<'_>) -> ArmTest {
+    match (&pattern.kind, known) {
+        (MirPatternKind::Wildcard, _) => ArmTest::Always,
+        (MirPatternKind::Var { name }, KnownValue::Constructor(value))
+            if starts_uppercase(name) =>
+        {
+            if constructor_name(name) == constructor_name(value) {
+                ArmTest::Always
+            } else {
+                ArmTest::Never
+            }
+        }
+        (MirPatternKind::Var { name }, _) if !starts_uppercase(name) => ArmTest::Always,
+        (MirPatternKind::Literal(literal), KnownValue::Literal(value)) => {
+            match literal_equals(&literal.value, value) {
+                Some(true) => ArmTest::Always,
+                Some(false) => ArmTest::Never,
+                None => ArmTest::Unknown,
+            }
+        }
+        (MirPatternKind::Constructor { name, args }, KnownValue::Constructor(value)) => {
+            if constructor_name(name) != constructor_name(value) {
+                ArmTest::Never
+            } else if args
+                .iter()
+                .all(|arg| matches!(arg.kind, MirPatternKind::Wildcard))
+            {
+                ArmTest::Always
+            } else {
+                ArmTest::Unknown
+            }
+        }
+        (MirPatternKind::Binding { pattern, .. }, _) => test_arm(pattern, known),
+        (MirPatternKind::Or { variants }, _) => {
+            let tests: Vec<ArmTest> = variants
+                .iter()
+                .map(|variant| test_arm(variant, known))
+                .collect();
+            if tests.contains(&ArmTest::Always) {
+                ArmTest::Always
+            } else if tests.iter().all(|test| *test == ArmTest::Never) {
+                ArmTest::Never
+            } else {
+                ArmTest::Unknown
+            }
+        }
+        _ => ArmTest::Unknown,
+    }
+}
+
+fn prune_match_arms(function: &mut MirFunction) -> usize {
+    let mut rewrites = 0;
+    for id in post_order(&function.exprs, function.body) {
+        let MirExprKind::Match { target, arms, .. } = function.exprs[id].kind.clone() else {
+            continue;
+        };
+        let Some(known) = known_value(&function.exprs, target) else {
+            continue;
+        };
+        let tests: Vec<ArmTest> = arms
+            .iter()
+            .map(|arm| test_arm(&arm.pattern, &known))
+            .collect();
+        let mut kept = Vec::new();
+        for (index, test) in tests.iter().enumerate() {
+            if *test == ArmTest::Never {
+                continue;
+            }
+            kept.push(index);
+            if *test == ArmTest::Always && arms[index].guard.is_none() {
+                break;
+            }
+        }
+        if kept.is_empty() || kept.len() == arms.len() && tests[kept[0]] != ArmTest::Always {
+            continue;
+        }
+        let first = kept[0];
+        if tests[first] == ArmTest::Always && arms[first].guard.is_none() {
+            let arm = &arms[first];
+            let Some(binding) = single_binding(&arm.pattern, arm.alias.as_deref()) else {
+                // 構造を分解する束縛は match のまま残す。
+                rewrites += truncate_arms(&mut function.exprs[id], &kept);
+                continue;
+            };
+            let statements = match binding {
+                Some(name) => vec![MirStmt {
+                    span: arm.pattern.span,
+                    kind: MirStmtKind::Let {
+                        pattern: MirPattern {
+                            span: arm.pattern.span,
+                            ty: arm.pattern.ty.clone(),
+                            kind: MirPatternKind::Var { name },
+                        },
+                        value: target,
+                        mutable: false,
+                    },
+                }],
+                None if is_pure(&function.exprs, target) => Vec::new(),
+                None => vec![MirStmt {
+                    span: arm.pattern.span,
+                    kind: MirStmtKind::Expr { expr: target },
+                }],
+            };
+            let body = arm.body;
+            if statements.is_empty() {
+                let ty = function.exprs[id].ty.clone();
+                replace_with(&mut function.exprs, id, body);
+                function.exprs[id].ty = ty;
+            } else {
+                function.exprs[id].kind = MirExprKind::Block {
+                    statements,
+                    tail: Some(body),
+                    defers: Vec::new(),
+                    defer_lifo: Vec::new(),
+                };
+            }
+            rewrites += arms.len();
+            continue;
+        }
+        rewrites += truncate_arms(&mut function.exprs[id], &kept);
+    }
+    rewrites
+}
+
+/// 腕が検査対象全体へ束縛する名前。束縛しなければ `Some(None)`、構造を分解する場合は `None`。
+fn single_binding(pattern: &MirPattern, alias: Option<&str>) -> Option<Option<String>> {
+    let mut inner = BTreeSet::new();
+    let name = match &pattern.kind {
+        MirPatternKind::Var { name } if !starts_uppercase(name) => Some(name.clone()),
+        MirPatternKind::Binding { name, pattern, .. } => {
+            pattern_binders(pattern, &mut inner);
+            Some(name.clone())
+        }
+        _ => {
+            pattern_binders(pattern, &mut inner);
+            None
+        }
+    };
+    if !inner.is_empty() {
+        return None;
+    }
+    match (name, alias) {
+        (Some(_), Some(_)) => None,
+        (Some(name), None) => Some(Some(name)),
+        (None, alias) => Some(alias.map(str::to_string)),
+    }
+}
+
+/// 指定した腕だけを残し、lowering 計画も同じ並びに揃える。
+fn truncate_arms(expr: &mut MirExpr, kept: &[usize]) -> usize {
+    let MirExprKind::Match { arms, lowering, .. } = &mut expr.kind else {
+        return 0;
+    };
+    if kept.len() == arms.len() {
+        return 0;
+    }
+    let removed = arms.len() - kept.len();
+    *arms = kept.iter().map(|index| arms[*index].clone()).collect();
+    if lowering.arms.len() >= kept.iter().max().map_or(0, |max| max + 1) {
+        lowering.arms = kept
+            .iter()
+            .map(|index| lowering.arms[*index].clone())
+            .collect();
+    }
+    lowering.arm_count = arms.len();
+    removed
+}
+
+// ---------------------------------------------------------------------------
+// ブロック併合
+
+fn merge_blocks(function: &mut MirFunction) -> usize {
+    let mut rewrites = 0;
+    for id in post_order(&function.exprs, function.body) {
+        if collapse_trivial_block(&mut function.exprs, id) {
+            rewrites += 1;
+            continue;
+        }
+        rewrites += splice_nested_blocks(&mut function.exprs, id);
+    }
+    rewrites
+}
+
+/// 文を持たないブロック `{ e }` を `e` そのものへ置き換える。
+fn collapse_trivial_block(exprs: &mut [MirExpr], id: MirExprId) -> bool {
+    let MirExprKind::Block {
+        statements,
+        tail: Some(tail),
+        defers,
+        ..
+    } = &exprs[id].kind
+    else {
+        return false;
+    };
+    if !statements.is_empty() || !defers.is_empty() {
+        return false;
+    }
+    let tail = *tail;
+    let ty = exprs[id].ty.clone();
+    replace_with(exprs, id, tail);
+    exprs[id].ty = ty;
+    true
+}
+
+/// 入れ子ブロックの文を外側へ展開する。内側の束縛が外側の後続から参照される場合は
+/// 有効範囲が変わるため展開しない。
+fn splice_nested_blocks(exprs: &mut [MirExpr], id: MirExprId) -> usize {
+    let MirExprKind::Block {
+        statements,
+        tail,
+        defers,
+        defer_lifo,
+    } = exprs[id].kind.clone()
+    else {
+        return 0;
+    };
+    let mut merged = 0;
+    let mut result: Vec<MirStmt> = Vec::with_capacity(statements.len());
+    for (index, stmt) in statements.iter().enumerate() {
+        let MirStmtKind::Expr { expr } = &stmt.kind else {
+            result.push(stmt.clone());
+            continue;
+        };
+        let MirExprKind::Block {
+            statements: inner,
+            tail: inner_tail,
+            defers: inner_defers,
+            ..
+        } = &exprs[*expr].kind
+        else {
+            result.push(stmt.clone());
+            continue;
+        };
+        if !inner_defers.is_empty() {
+            result.push(stmt.clone());
+            continue;
+        }
+        let mut roots: Vec<MirExprId> = statements[index + 1..]
+            .iter()
+            .flat_map(stmt_child_ids)
+            .collect();
+        roots.extend(tail);
+        roots.extend(&defers);
+        if binders_escape(exprs, inner, &roots) {
+            result.push(stmt.clone());
+            continue;
+        }
+        result.extend(inner.iter().cloned());
+        if let Some(inner_tail) = inner_tail {
+            result.push(MirStmt {
+                span: exprs[*inner_tail].span,
+                kind: MirStmtKind::Expr { expr: *inner_tail },
+            });
+        }
+        merged += 1;
+    }
+
+    // 末尾式がブロックなら、その文を外側へ移して内側の末尾式を新しい末尾式にする。
+    let mut new_tail = tail;
+    if let Some(tail_id) = tail {
+        if let MirExprKind::Block {
+            statements: inner,
+            tail: inner_tail,
+            defers: inner_defers,
+            ..
+        } = &exprs[tail_id].kind
+        {
+            let tail_span = exprs[tail_id].span;
+            let duplicate_elsewhere = result
+                .iter()
+                .any(|stmt| matches!(&stmt.kind, MirStmtKind::Expr { expr } if exprs[*expr].span == tail_span));
+            if inner_defers.is_empty()
+                && !duplicate_elsewhere
+                && !binders_escape(exprs, inner, &defers)
+            {
+                result.extend(inner.iter().cloned());
+                new_tail = *inner_tail;
+                merged += 1;
+            }
+        }
+    }
+    if merged == 0 {
+        return 0;
+    }
+    exprs[id].kind = MirExprKind::Block {
+        statements: result,
+        tail: new_tail,
+        defers,
+        defer_lifo,
+    };
+    merged
+}
+
+fn binders_escape(exprs: &[MirExpr], inner: &[MirStmt], roots: &[MirExprId]) -> bool {
+    let mut binders = BTreeSet::new();
+    for stmt in inner {
+        if let MirStmtKind::Let { pattern, .. } = &stmt.kind {
+            pattern_binders(pattern, &mut binders);
+        }
+    }
+    if binders.is_empty() {
+        return false;
+    }
+    let mut mentions = BTreeSet::new();
+    collect_mentions(exprs, roots, &mut mentions);
+    binders.iter().any(|name| mentions.contains(name))
+}
+
+// ---------------------------------------------------------------------------
+// ダンプ
+
+/// 関数をソースに近い擬似コードで書き出す。パス前後のダンプに使う。
+pub fn render_mir_function(function: &MirFunction) -> String {
+    let params: Vec<String> = function
+        .params
+        .iter()
+        .map(|param| format!("{}: {}", param.name, param.ty))
+        .collect();
+    let mut out = format!(
+        "fn {}({}) -> {} = ",
+        function.name,
+        params.join(", "),
+        function.return_type
+    );
+    render_expr(&function.exprs, function.body, 0, &mut out);
+    out.push('\n');
+    out
+}
+
+fn render_expr(exprs: &[MirExpr], id: MirExprId, depth: usize, out: &mut String) {
+    let Some(expr) = exprs.get(id) else {
+        let _ = write!(out, "<missing #{id}>");
+        return;
+    };
+    match &expr.kind {
+        MirExprKind::Literal(literal) => out.push_str(&render_literal(literal)),
+        MirExprKind::Identifier { ident } => out.push_str(&ident.name),
+        MirExprKind::Call { callee, args } => {
+            render_expr(exprs, *callee, depth, out);
+            render_list(exprs, args, depth, out);
+        }
+        MirExprKind::Lambda { params, body, .. } => {
+            let names: Vec<&str> = params.iter().map(|param| param.name.as_str()).collect();
+            let _ = write!(out, "|{}| ", names.join(", "));
+            render_expr(exprs, *body, depth, out);
+        }
+        MirExprKind::Rec { target, .. } => {
+            out.push_str("rec ");
+            render_expr(exprs, *target, depth, out);
+        }
+        MirExprKind::Block {
+            statements,
+            tail,
+            defers,
+            ..
+        } => {
+            out.push_str("{\n");
+            let inner = depth + 1;
+            for stmt in statements {
+                indent(inner, out);
+                render_stmt(exprs, stmt, inner, out);
+                out.push('\n');
+            }
+            if let Some(tail) = tail {
+                indent(inner, out);
+                render_expr(exprs, *tail, inner, out);
+                out.push('\n');
+            }
+            if !defers.is_empty() {
+                indent(inner, out);
+                let _ = writeln!(out, "// defers: {}", defers.len());
+            }
+            indent(depth, out);
+            out.push('}');
+        }
+        MirExprKind::Return { value } => {
+            out.push_str("return");
+            if let Some(value) = value {
+                out.push(' ');
+                render_expr(exprs, *value, depth, out);
+            }
+        }
+        MirExprKind::Propagate { expr } => {
+            render_expr(exprs, *expr, depth, out);
+            out.push('?');
+        }
+        MirExprKind::Panic { argument } => {
+            out.push_str("panic");
+            render_list(exprs, argument.as_slice(), depth, out);
+        }
+        MirExprKind::Unary { operator, operand } => {
+            let _ = write!(out, "({operator}");
+            render_expr(exprs, *operand, depth, out);
+            out.push(')');
+        }
+        MirExprKind::Binary {
+            operator,
+            left,
+            right,
+        } => {
+            out.push('(');
+            render_expr(exprs, *left, depth, out);
+            let _ = write!(out, " {operator} ");
+            render_expr(exprs, *right, depth, out);
+            out.push(')');
+        }
+        MirExprKind::FieldAccess { target, field } => {
+            render_expr(exprs, *target, depth, out);
+            let _ = write!(out, ".{field}");
+        }
+        MirExprKind::TupleAccess { target, index } => {
+            render_expr(exprs, *target, depth, out);
+            let _ = write!(out, ".{index}");
+        }
+        MirExprKind::Index { target, index } => {
+            render_expr(exprs, *target, depth, out);
+            out.push('[');
+            render_expr(exprs, *index, depth, out);
+            out.push(']');
+        }
+        MirExprKind::Match { target, arms, .. } => {
+            out.push_str("match ");
+            render_expr(exprs, *target, depth, out);
+            out.push_str(" with\n");
+            for arm in arms {
+                indent(depth + 1, out);
+                let _ = write!(out, "| {}", render_pattern(&arm.pattern));
+                if let Some(guard) = arm.guard {
+                    out.push_str(" when ");
+                    render_expr(exprs, guard, depth + 1, out);
+                }
+                out.push_str(" -> ");
+                render_expr(exprs, arm.body, depth + 1, out);
+                out.push('\n');
+            }
+            indent(depth, out);
+            out.push_str("end");
+        }
+        MirExprKind::IfElse {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            out.push_str("if ");
+            render_expr(exprs, *condition, depth, out);
+            out.push_str(" then ");
+            render_expr(exprs, *then_branch, depth, out);
+            out.push_str(" else ");
+            render_expr(exprs, *else_branch, depth, out);
+        }
+        MirExprKind::PerformCall { call } => {
+            let _ = write!(out, "perform {}", call.effect.name);
+            render_list(exprs, &[call.argument], depth, out);
+        }
+        MirExprKind::Handle { target, handler } => {
+            out.push_str("handle ");
+            render_expr(exprs, *target, depth, out);
+            let _ = write!(out, " with {}", handler.effect.name);
+        }
+        MirExprKind::EffectBlock { body } => {
+            out.push_str("effect ");
+            render_expr(exprs, *body, depth, out);
+        }
+        MirExprKind::Async { body, .. } => {
+            out.push_str("async ");
+            render_expr(exprs, *body, depth, out);
+        }
+        MirExprKind::Await { expr } => {
+            out.push_str("await ");
+            render_expr(exprs, *expr, depth, out);
+        }
+        MirExprKind::Unsafe { body } => {
+            out.push_str("unsafe ");
+            render_expr(exprs, *body, depth, out);
+        }
+        MirExprKind::InlineAsm { template, .. } => {
+            let _ = write!(out, "asm({template:?})");
+        }
+        MirExprKind::LlvmIr {
+            template, inputs, ..
+        } => {
+            let _ = write!(out, "llvm_ir({template:?})");
+            render_list(exprs, inputs, depth, out);
+        }
+        MirExprKind::Unknown => out.push_str("<unknown>"),
+    }
+}
+
+fn render_stmt(exprs: &[MirExpr], stmt: &MirStmt, depth: usize, out: &mut String) {
+    match &stmt.kind {
+        MirStmtKind::Let {
+            pattern,
+            value,
+            mutable,
+        } => {
+            let keyword = if *mutable { "var" } else { "let" };
+            let _ = write!(out, "{keyword} {} = ", render_pattern(pattern));
+            render_expr(exprs, *value, depth, out);
+        }
+        MirStmtKind::Expr { expr } => render_expr(exprs, *expr, depth, out),
+        MirStmtKind::Assign { target, value } => {
+            render_expr(exprs, *target, depth, out);
+            out.push_str(" := ");
+            render_expr(exprs, *value, depth, out);
+        }
+        MirStmtKind::Defer { expr } => {
+            out.push_str("defer ");
+            render_expr(exprs, *expr, depth, out);
+        }
+    }
+}
+
+fn render_list(exprs: &[MirExpr], ids: &[MirExprId], depth: usize, out: &mut String) {
+    out.push('(');
+    for (index, id) in ids.iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        render_expr(exprs, *id, depth, out);
+    }
+    out.push(')');
+}
+
+fn render_literal(literal: &Literal) -> String {
+    match &literal.value {
+        LiteralKind::Int { value, .. } => value.to_string(),
+        LiteralKind::Float { raw } => raw.clone(),
+        LiteralKind::Char { value } => format!("'{value}'"),
+        LiteralKind::String { value, .. } => format!("{value:?}"),
+        LiteralKind::Bool { value } => value.to_string(),
+        LiteralKind::Unit => "()".to_string(),
+        _ => "<literal>".to_string(),
+    }
+}
+
+fn render_pattern(pattern: &MirPattern) -> String {
+    match &pattern.kind {
+        MirPatternKind::Wildcard => "_".to_string(),
+        MirPatternKind::Var { name } => name.clone(),
+        MirPatternKind::Literal(literal) => render_literal(literal),
+        MirPatternKind::Tuple { elements } => format!(
+            "({})",
+            elements
+                .iter()
+                .map(render_pattern)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        MirPatternKind::Constructor { name, args } if args.is_empty() => name.clone(),
+        MirPatternKind::Constructor { name, args } => format!(
+            "{name}({})",
+            args.iter()
+                .map(render_pattern)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        MirPatternKind::Binding { name, pattern, .. } => {
+            format!("{name} @ {}", render_pattern(pattern))
+        }
+        MirPatternKind::Or { variants } => variants
+            .iter()
+            .map(render_pattern)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ => "<pattern>".to_string(),
+    }
+}
+
+fn indent(depth: usize, out: &mut String) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
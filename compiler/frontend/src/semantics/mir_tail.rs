@@ -0,0 +1,191 @@
+//! MIR 上の末尾位置解析。
+//!
+//! 関数本体から末尾位置を辿り、末尾位置にある呼び出しを `MirFunction::tail_calls` に記録する。
+//! バックエンドは自己末尾呼び出しをループへ書き換え、それ以外の末尾呼び出しには
+//! `musttail`/`tail` を付ける。`@tailrec` 関数で末尾位置にない自己呼び出しは型検査が報告する。
+//!
+//! `defer` を持つブロックの中は末尾位置にならない（遅延式が呼び出しの後に走るため）。
+//! ラムダ本体は別の関数として持ち上げられるので辿らない。
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::semantics::mir::{MirExprId, MirExprKind, MirFunction, MirModule};
+use crate::semantics::mir_opt::{child_ids, collect_binders, stmt_child_ids};
+
+/// 末尾呼び出しの保証を要求する関数属性（`@tailrec`）。
+pub const TAILREC_ATTRIBUTE: &str = "tailrec";
+
+/// 末尾位置にある呼び出し。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MirTailCall {
+    /// `Call` 式の ID。
+    pub expr: MirExprId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callee: Option<String>,
+    pub kind: MirTailCallKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MirTailCallKind {
+    /// 引数の数が一致する自分自身への呼び出し。ループへ書き換えられる。
+    SelfRecursive,
+    /// モジュールの関数名を直接呼び出す。
+    Direct,
+    /// 局所変数やクロージャ経由の呼び出し。
+    Indirect,
+}
+
+/// 1 関数分の解析結果。
+#[derive(Debug, Clone, Default)]
+pub struct TailCallAnalysis {
+    pub tail_calls: Vec<MirTailCall>,
+    /// 末尾位置にない自己呼び出し（`Call` 式の ID）。
+    pub non_tail_self_calls: Vec<MirExprId>,
+}
+
+impl TailCallAnalysis {
+    pub fn has_self_tail_call(&self) -> bool {
+        self.tail_calls
+            .iter()
+            .any(|call| call.kind == MirTailCallKind::SelfRecursive)
+    }
+}
+
+/// 関数の末尾呼び出しを解析する。
+pub fn analyze_tail_calls(function: &MirFunction) -> TailCallAnalysis {
+    let mut binders = BTreeSet::new();
+    collect_binders(&function.exprs, &[function.body], &mut binders);
+    binders.extend(function.params.iter().map(|param| param.name.clone()));
+    let mut collector = TailCallCollector {
+        function,
+        binders,
+        defer_depth: 0,
+        analysis: TailCallAnalysis::default(),
+    };
+    if function.body < function.exprs.len() {
+        collector.visit(function.body, true);
+    }
+    collector.analysis
+}
+
+/// モジュール内の全関数の `tail_calls` を計算し直す。
+pub fn annotate_tail_calls(module: &mut MirModule) {
+    for function in &mut module.functions {
+        function.tail_calls = analyze_tail_calls(function).tail_calls;
+    }
+}
+
+struct TailCallCollector<'a> {
+    function: &'a MirFunction,
+    /// 関数名を隠しうる局所束縛と引数の名前。
+    binders: BTreeSet<String>,
+    /// 囲んでいる `defer` 付きブロックの数。`return` の値が末尾位置かどうかを決める。
+    defer_depth: usize,
+    analysis: TailCallAnalysis,
+}
+
+impl TailCallCollector<'_> {
+    fn visit(&mut self, id: MirExprId, tail: bool) {
+        let exprs = &self.function.exprs;
+        match &exprs[id].kind {
+            MirExprKind::Call { callee, args } => {
+                let (name, kind) = self.classify_callee(*callee, args.len());
+                if tail {
+                    self.analysis.tail_calls.push(MirTailCall {
+                        expr: id,
+                        callee: name,
+                        kind,
+                    });
+                } else if kind == MirTailCallKind::SelfRecursive {
+                    self.analysis.non_tail_self_calls.push(id);
+                }
+                self.visit(*callee, false);
+                for arg in args {
+                    self.visit(*arg, false);
+                }
+            }
+            MirExprKind::Block {
+                statements,
+                tail: block_tail,
+                defers,
+                defer_lifo,
+            } => {
+                let has_defer = !defers.is_empty() || !defer_lifo.is_empty();
+                if has_defer {
+                    self.defer_depth += 1;
+                }
+                for stmt in statements {
+                    for child in stmt_child_ids(stmt) {
+                        self.visit(child, false);
+                    }
+                }
+                if let Some(block_tail) = block_tail {
+                    self.visit(*block_tail, tail && !has_defer);
+                }
+                for deferred in defers.iter().chain(defer_lifo) {
+                    self.visit(*deferred, false);
+                }
+                if has_defer {
+                    self.defer_depth -= 1;
+                }
+            }
+            MirExprKind::IfElse {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.visit(*condition, false);
+                self.visit(*then_branch, tail);
+                self.visit(*else_branch, tail);
+            }
+            MirExprKind::Match { target, arms, .. } => {
+                self.visit(*target, false);
+                for arm in arms {
+                    if let Some(guard) = arm.guard {
+                        self.visit(guard, false);
+                    }
+                    self.visit(arm.body, tail);
+                }
+            }
+            MirExprKind::EffectBlock { body } | MirExprKind::Unsafe { body } => {
+                self.visit(*body, tail);
+            }
+            MirExprKind::Return { value } => {
+                if let Some(value) = value {
+                    self.visit(*value, self.defer_depth == 0);
+                }
+            }
+            MirExprKind::Lambda { .. } => {}
+            kind => {
+                for child in child_ids(kind) {
+                    self.visit(child, false);
+                }
+            }
+        }
+    }
+
+    fn classify_callee(
+        &self,
+        callee: MirExprId,
+        arg_count: usize,
+    ) -> (Option<String>, MirTailCallKind) {
+        let name = match &self.function.exprs[callee].kind {
+            MirExprKind::Identifier { ident } => ident.name.clone(),
+            MirExprKind::Rec {
+                ident: Some(ident), ..
+            } => ident.name.clone(),
+            _ => return (None, MirTailCallKind::Indirect),
+        };
+        let kind = if self.binders.contains(&name) {
+            MirTailCallKind::Indirect
+        } else if name == self.function.name && arg_count == self.function.params.len() {
+            MirTailCallKind::SelfRecursive
+        } else {
+            MirTailCallKind::Direct
+        };
+        (Some(name), kind)
+    }
+}
@@ -1,2 +1,4 @@
 pub mod mir;
+pub mod mir_opt;
+pub mod mir_tail;
 pub mod typed;
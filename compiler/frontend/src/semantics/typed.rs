@@ -162,6 +162,13 @@ pub struct TypedExtern {
     pub span: Span,
     pub abi: String,
     pub symbol: String,
+    pub params: Vec<String>,
+    /// 引数の名前（`params` と同じ順）。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub param_names: Vec<String>,
+    pub return_type: String,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub varargs: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -262,6 +269,10 @@ pub enum TypedExprKind {
     Propagate {
         expr: Box<TypedExpr>,
     },
+    Unary {
+        operator: String,
+        operand: Box<TypedExpr>,
+    },
     Binary {
         operator: String,
         left: Box<TypedExpr>,
@@ -282,6 +293,8 @@ pub enum TypedExprKind {
     Match {
         target: Box<TypedExpr>,
         arms: Vec<TypedMatchArm>,
+        #[serde(default, skip_serializing_if = "TypedMatchCoverage::is_unknown")]
+        coverage: TypedMatchCoverage,
     },
     IfElse {
         condition: Box<TypedExpr>,
@@ -291,6 +304,10 @@ pub enum TypedExprKind {
     PerformCall {
         call: TypedEffectCall,
     },
+    Handle {
+        target: Box<TypedExpr>,
+        handler: TypedHandler,
+    },
     EffectBlock {
         body: Box<TypedExpr>,
     },
@@ -327,6 +344,32 @@ pub struct TypedEffectCall {
     pub argument: Box<TypedExpr>,
 }
 
+/// `handle ... with handler` のハンドラ本体。
+#[derive(Debug, Clone, Serialize)]
+pub struct TypedHandler {
+    pub effect: Ident,
+    pub operations: Vec<TypedHandlerOperation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_clause: Option<TypedHandlerReturn>,
+}
+
+/// `operation name(arg, resume) { ... }` 節。2 番目の引数は継続 `resume` を束縛する。
+#[derive(Debug, Clone, Serialize)]
+pub struct TypedHandlerOperation {
+    pub name: Ident,
+    pub params: Vec<TypedParam>,
+    pub body: Box<TypedExpr>,
+    pub span: Span,
+}
+
+/// `return value { ... }` 節。
+#[derive(Debug, Clone, Serialize)]
+pub struct TypedHandlerReturn {
+    pub value: TypedParam,
+    pub body: Box<TypedExpr>,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct TypedInlineAsmOutput {
     pub constraint: String,
@@ -339,6 +382,23 @@ pub struct TypedInlineAsmInput {
     pub expr: Box<TypedExpr>,
 }
 
+/// match の網羅性検査から、コード生成で使える部分を取り出したもの。
+#[derive(Debug, Clone, Serialize, Default, PartialEq, Eq)]
+pub struct TypedMatchCoverage {
+    /// ガードなしで入れ子の判定を持たない腕だけで対象型の値を覆い尽くすなら true。
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub exhaustive: bool,
+    /// 対象が和型なら宣言順のコンストラクタ名。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constructors: Vec<String>,
+}
+
+impl TypedMatchCoverage {
+    fn is_unknown(&self) -> bool {
+        !self.exhaustive && self.constructors.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct TypedMatchArm {
     pub pattern: TypedPattern,
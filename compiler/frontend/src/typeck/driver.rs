@@ -25,6 +25,7 @@ use crate::parser::ast::{
     TypeDeclVariant, TypeDeclVariantPayload, TypeKind, TypeLiteral, TypeUnionVariant, UnaryOp,
     VariantPayload,
 };
+use crate::semantics::mir_tail::{analyze_tail_calls, TAILREC_ATTRIBUTE};
 use crate::semantics::{mir, typed};
 use crate::span::Span;
 
@@ -160,6 +161,7 @@ impl TypecheckDriver {
             &mut var_gen,
             &mut violations,
         );
+        register_effect_operations(module, &mut module_env, &mut var_gen);
         let effect_names = collect_effect_names(module);
         validate_handles_attrs(module, &effect_names, &mut violations);
         let (impls, impl_registry_duplicates, impl_registry_unresolved) =
@@ -635,7 +637,7 @@ impl TypecheckDriver {
             });
         });
         typed_module.actor_specs = actor_specs;
-        typed_module.externs = collect_externs(module);
+        typed_module.externs = collect_externs(module, &module_env);
 
         for expr in &module.exprs {
             let mut stats = FunctionStats::default();
@@ -672,7 +674,7 @@ impl TypecheckDriver {
         violations.extend(detect_varargs_violations(module));
         violations.extend(detect_spec_core_runtime_violations(module));
         violations.extend(detect_native_escape_hatch_violations(module));
-        let violations = compress_typecheck_violations(violations);
+        let mut violations = compress_typecheck_violations(violations);
 
         let used_impls = all_constraints
             .iter()
@@ -698,6 +700,7 @@ impl TypecheckDriver {
         mir_module.impl_registry_duplicates = impl_registry_duplicates;
         mir_module.impl_registry_unresolved = impl_registry_unresolved;
         populate_qualified_call_candidates(&mut mir_module);
+        detect_tailrec_violations(&mir_module, &mut violations);
         let qualified_call_table = mir_module.qualified_calls.clone();
 
         TypecheckReport {
@@ -850,6 +853,7 @@ pub enum TypecheckViolationKind {
     TypeAliasCycle,
     TypeAliasExpansionLimit,
     ConstructorArityMismatch,
+    TailrecNonTailCall,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -1703,6 +1707,27 @@ impl TypecheckViolation {
         }
     }
 
+    fn tailrec_non_tail_call(span: Span, function: &str) -> Self {
+        Self {
+            kind: TypecheckViolationKind::TailrecNonTailCall,
+            code: "typeck.tailrec.non_tail_call",
+            message: "@tailrec 関数の自己呼び出しが末尾位置にありません".to_string(),
+            span: Some(span),
+            notes: vec![ViolationNote::plain(format!(
+                "`{function}` の呼び出し結果を使う式や `defer` の内側ではループへ書き換えられません。累積引数を使って末尾位置へ移してください"
+            ))],
+            capability: None,
+            function: Some(function.to_string()),
+            expected: None,
+            recover: None,
+            iterator_stage: None,
+            capability_mismatch: None,
+            pattern_missing_variants: None,
+            pattern_missing_ranges: None,
+            pattern_range: None,
+        }
+    }
+
     fn active_pattern_return_contract(
         span: Span,
         name: &str,
@@ -2160,7 +2185,8 @@ impl TypecheckViolation {
             | TypecheckViolationKind::TypeUnresolvedIdent
             | TypecheckViolationKind::TypeAliasCycle
             | TypecheckViolationKind::TypeAliasExpansionLimit
-            | TypecheckViolationKind::ConstructorArityMismatch => "type",
+            | TypecheckViolationKind::ConstructorArityMismatch
+            | TypecheckViolationKind::TailrecNonTailCall => "type",
             TypecheckViolationKind::ResidualLeak
             | TypecheckViolationKind::StageMismatch
             | TypecheckViolationKind::IteratorStageMismatch
@@ -2368,35 +2394,91 @@ fn register_function_decls(
     violations: &mut Vec<TypecheckViolation>,
 ) {
     for decl in decls {
-        let DeclKind::Fn { signature } = &decl.kind else {
-            continue;
-        };
-        let generic_map = build_generic_map(&signature.generics, var_gen);
-        let generic_map_ref = if generic_map.is_empty() {
-            None
-        } else {
-            Some(&generic_map)
-        };
-        let mut param_types = Vec::new();
-        for param in &signature.params {
-            let ty = param
-                .type_annotation
-                .as_ref()
-                .and_then(|annot| type_from_annotation(annot, generic_map_ref, env, violations))
-                .unwrap_or_else(|| var_gen.fresh_type());
-            param_types.push(ty);
+        match &decl.kind {
+            DeclKind::Fn { signature } => {
+                register_function_signature(signature, env, var_gen, violations);
+            }
+            DeclKind::Extern { functions, .. } => {
+                // extern の型注釈（`Ptr<u8>` など）の検証はここでは行わないため、診断は破棄する。
+                let mut ignored_violations = Vec::new();
+                for item in functions {
+                    register_function_signature(
+                        &item.signature,
+                        env,
+                        var_gen,
+                        &mut ignored_violations,
+                    );
+                }
+            }
+            _ => {}
         }
-        let mut ret_type = signature
-            .ret_type
+    }
+}
+
+fn register_function_signature(
+    signature: &FunctionSignature,
+    env: &mut TypeEnv,
+    var_gen: &mut TypeVarGen,
+    violations: &mut Vec<TypecheckViolation>,
+) {
+    let generic_map = build_generic_map(&signature.generics, var_gen);
+    let generic_map_ref = if generic_map.is_empty() {
+        None
+    } else {
+        Some(&generic_map)
+    };
+    let mut param_types = Vec::new();
+    for param in &signature.params {
+        let ty = param
+            .type_annotation
             .as_ref()
             .and_then(|annot| type_from_annotation(annot, generic_map_ref, env, violations))
             .unwrap_or_else(|| var_gen.fresh_type());
-        if signature.is_async {
-            ret_type = future_type(ret_type);
+        param_types.push(ty);
+    }
+    let mut ret_type = signature
+        .ret_type
+        .as_ref()
+        .and_then(|annot| type_from_annotation(annot, generic_map_ref, env, violations))
+        .unwrap_or_else(|| var_gen.fresh_type());
+    if signature.is_async {
+        ret_type = future_type(ret_type);
+    }
+    let function_type = Type::arrow(param_types, ret_type);
+    let scheme = generalize_type(env, function_type);
+    env.insert(signature.binding_key(), scheme);
+}
+
+/// 効果宣言の操作シグネチャを `Effect::op` 名で登録し、`perform` の結果型推論に用いる。
+///
+/// 効果宣言のシグネチャ検証はここでは行わないため、解決できない型名の診断は破棄する。
+fn register_effect_operations(module: &Module, env: &mut TypeEnv, var_gen: &mut TypeVarGen) {
+    let mut ignored_violations = Vec::new();
+    let declared = module.decls.iter().filter_map(|decl| match &decl.kind {
+        DeclKind::Effect(effect) => Some(effect),
+        _ => None,
+    });
+    for effect in module.effects.iter().chain(declared) {
+        for operation in &effect.operations {
+            let Some(signature) = operation.signature.as_ref() else {
+                continue;
+            };
+            let Some(operation_ty) =
+                type_from_annotation(signature, None, env, &mut ignored_violations)
+            else {
+                continue;
+            };
+            let operation_ty = match operation_ty {
+                Type::Arrow { .. } => operation_ty,
+                // `operation tick : Int` のような非関数シグネチャは引数を問わない関数型とみなす
+                other => Type::arrow(vec![var_gen.fresh_type()], other),
+            };
+            let scheme = generalize_type(env, operation_ty);
+            env.insert_effect_operation(
+                format!("{}::{}", effect.name.name, operation.name.name),
+                scheme,
+            );
         }
-        let function_type = Type::arrow(param_types, ret_type);
-        let scheme = generalize_type(env, function_type);
-        env.insert(signature.binding_key(), scheme);
     }
 }
 
@@ -2659,41 +2741,76 @@ fn check_handles_attrs(
     }
 }
 
-fn collect_externs(module: &Module) -> Vec<typed::TypedExtern> {
+fn collect_externs(module: &Module, env: &TypeEnv) -> Vec<typed::TypedExtern> {
     let mut externs = Vec::new();
     for decl in &module.decls {
-        collect_externs_from_decl(decl, &mut externs);
+        collect_externs_from_decl(decl, env, &mut externs);
     }
     externs
 }
 
-fn collect_externs_from_body(body: &ModuleBody, externs: &mut Vec<typed::TypedExtern>) {
+fn collect_externs_from_body(
+    body: &ModuleBody,
+    env: &TypeEnv,
+    externs: &mut Vec<typed::TypedExtern>,
+) {
     for decl in &body.decls {
-        collect_externs_from_decl(decl, externs);
+        collect_externs_from_decl(decl, env, externs);
     }
 }
 
-fn collect_externs_from_decl(decl: &Decl, externs: &mut Vec<typed::TypedExtern>) {
+fn collect_externs_from_decl(decl: &Decl, env: &TypeEnv, externs: &mut Vec<typed::TypedExtern>) {
     match &decl.kind {
         DeclKind::Extern { abi, functions, .. } => {
             for item in functions {
                 let name = item.signature.binding_key();
                 let symbol = extract_extern_symbol(&item.attrs, &name);
+                let (params, return_type) = extern_signature_labels(&item.signature, env);
                 externs.push(typed::TypedExtern {
                     name,
                     span: item.span,
                     abi: abi.clone(),
                     symbol,
+                    params,
+                    param_names: item
+                        .signature
+                        .params
+                        .iter()
+                        .map(|param| param.pattern.render())
+                        .collect(),
+                    return_type,
+                    varargs: item.signature.varargs,
                 });
             }
         }
         DeclKind::Module(module_decl) => {
-            collect_externs_from_body(&module_decl.body, externs);
+            collect_externs_from_body(&module_decl.body, env, externs);
         }
         _ => {}
     }
 }
 
+/// extern の引数型と戻り値型のラベル。解決できない型注釈は `Unknown` とする。
+fn extern_signature_labels(signature: &FunctionSignature, env: &TypeEnv) -> (Vec<String>, String) {
+    let mut ignored_violations = Vec::new();
+    let mut label = |annot: Option<&TypeAnnot>| {
+        annot
+            .and_then(|annot| type_from_annotation(annot, None, env, &mut ignored_violations))
+            .map(|ty| ty.label())
+            .unwrap_or_else(|| BuiltinType::Unknown.as_str().to_string())
+    };
+    let params = signature
+        .params
+        .iter()
+        .map(|param| label(param.type_annotation.as_ref()))
+        .collect();
+    let return_type = match &signature.ret_type {
+        Some(annot) => label(Some(annot)),
+        None => BuiltinType::Unit.as_str().to_string(),
+    };
+    (params, return_type)
+}
+
 fn extract_extern_symbol(attrs: &[Attribute], fallback: &str) -> String {
     for attr in attrs {
         if attr.name.name != "link_name" && attr.name.name != "ffi_link_name" {
@@ -4073,6 +4190,27 @@ fn infer_expr(
             if context.is_pure {
                 violations.push(context.purity_violation(expr.span(), call.effect.name.clone()));
             }
+            // 効果宣言のシグネチャが分かる場合は引数と結果型を対応付ける
+            let result_type = match env.lookup_effect_operation(call.effect.name.as_str()) {
+                Some(scheme) => match scheme.instantiate(var_gen) {
+                    Type::Arrow { parameters, result } => {
+                        // 引数の不一致で結果型が失われないよう、結果型と引数を個別に単一化する
+                        if let Some(parameter) = parameters.into_iter().next() {
+                            stats.constraints += 1;
+                            metrics.record_constraint("perform.argument");
+                            constraints.push(Constraint::equal(
+                                argument_result.ty.clone(),
+                                parameter.clone(),
+                            ));
+                            metrics.record_unify_call();
+                            let _ = solver.unify(argument_result.ty.clone(), parameter);
+                        }
+                        solver.substitution().apply(&result)
+                    }
+                    _ => Type::builtin(BuiltinType::Unknown),
+                },
+                None => Type::builtin(BuiltinType::Unknown),
+            };
             make_typed(
                 expr,
                 TypedExprKindDraft::PerformCall {
@@ -4081,7 +4219,7 @@ fn infer_expr(
                         argument: Box::new(argument_result),
                     },
                 },
-                Type::builtin(BuiltinType::Unknown),
+                result_type,
                 vec![dict_ref_id],
             )
         }
@@ -4665,6 +4803,10 @@ fn infer_expr(
                 TypedExprKindDraft::Match {
                     target: Box::new(target_result),
                     arms: typed_arms,
+                    coverage: typed::TypedMatchCoverage {
+                        exhaustive: coverage.lowering_exhaustive,
+                        constructors: sum_constructor_order(env, &target_ty).unwrap_or_default(),
+                    },
                 },
                 arm_type.unwrap_or_else(|| Type::builtin(BuiltinType::Unknown)),
                 dicts,
@@ -4775,11 +4917,16 @@ fn infer_expr(
             constraints.push(Constraint::equal(result.ty.clone(), expected.clone()));
             metrics.record_unify_call();
             let _ = solver.unify(result.ty.clone(), expected.clone());
+            let ty = solver.substitution().apply(&expected);
+            let dict_ref_ids = result.dict_ref_ids.clone();
             make_typed(
                 expr,
-                TypedExprKindDraft::Unknown,
-                solver.substitution().apply(&expected),
-                result.dict_ref_ids.clone(),
+                TypedExprKindDraft::Unary {
+                    operator: operator.symbol().to_string(),
+                    operand: Box::new(result),
+                },
+                ty,
+                dict_ref_ids,
             )
         }
         ExprKind::Rec { expr: inner } => {
@@ -4897,6 +5044,164 @@ fn infer_expr(
                 dicts,
             )
         }
+        ExprKind::Handle { handle } => {
+            let target_result = infer_expr(
+                &handle.target,
+                env,
+                var_gen,
+                solver,
+                constraints,
+                stats,
+                metrics,
+                violations,
+                dict_refs,
+                loop_context,
+                context,
+            );
+            let effect_name = handle.handler.name.name.clone();
+            let handle_ty = var_gen.fresh_type();
+            let mut dicts = target_result.dict_ref_ids.clone();
+            let mut operations = Vec::new();
+            let mut return_clause = None;
+            for entry in &handle.handler.entries {
+                match entry {
+                    HandlerEntry::Operation {
+                        name,
+                        params,
+                        body,
+                        span,
+                        ..
+                    } => {
+                        let operation_key = format!("{effect_name}::{}", name.name);
+                        let (argument_ty, resume_ty) = match env
+                            .lookup_effect_operation(operation_key.as_str())
+                            .map(|scheme| scheme.instantiate(var_gen))
+                        {
+                            Some(Type::Arrow { parameters, result }) => (
+                                parameters
+                                    .into_iter()
+                                    .next()
+                                    .unwrap_or_else(|| var_gen.fresh_type()),
+                                *result,
+                            ),
+                            _ => (var_gen.fresh_type(), var_gen.fresh_type()),
+                        };
+                        // 第 1 引数は操作の引数、第 2 引数は継続 `resume: (R) -> handle の型`
+                        let mut clause_env = env.enter_scope();
+                        let mut param_bindings = Vec::new();
+                        for (index, param) in params.iter().enumerate() {
+                            let ty = param
+                                .type_annotation
+                                .as_ref()
+                                .and_then(|annot| {
+                                    type_from_annotation(annot, None, env, violations)
+                                })
+                                .unwrap_or_else(|| match index {
+                                    0 => argument_ty.clone(),
+                                    1 => Type::arrow(vec![resume_ty.clone()], handle_ty.clone()),
+                                    _ => var_gen.fresh_type(),
+                                });
+                            let scheme = Scheme::simple(ty.clone());
+                            bind_pattern_to_env(&param.pattern, &scheme, &mut clause_env, var_gen);
+                            param_bindings.push(ParamBinding {
+                                display: param.pattern.render(),
+                                span: param.span,
+                                ty,
+                                annotation: param
+                                    .type_annotation
+                                    .as_ref()
+                                    .map(|annot| annot.render()),
+                            });
+                        }
+                        let body_result = infer_expr(
+                            body,
+                            &mut clause_env,
+                            var_gen,
+                            solver,
+                            constraints,
+                            stats,
+                            metrics,
+                            violations,
+                            dict_refs,
+                            loop_context,
+                            context,
+                        );
+                        stats.constraints += 1;
+                        metrics.record_constraint("handle.operation");
+                        constraints
+                            .push(Constraint::equal(body_result.ty.clone(), handle_ty.clone()));
+                        metrics.record_unify_call();
+                        let _ = solver.unify(body_result.ty.clone(), handle_ty.clone());
+                        dicts.extend(body_result.dict_ref_ids.clone());
+                        operations.push(TypedHandlerOperationDraft {
+                            name: name.clone(),
+                            params: param_bindings,
+                            body: Box::new(body_result),
+                            span: *span,
+                        });
+                    }
+                    HandlerEntry::Return {
+                        value_ident,
+                        body,
+                        span,
+                    } => {
+                        let mut clause_env = env.enter_scope();
+                        clause_env.insert(
+                            value_ident.name.clone(),
+                            Scheme::simple(target_result.ty.clone()),
+                        );
+                        let body_result = infer_expr(
+                            body,
+                            &mut clause_env,
+                            var_gen,
+                            solver,
+                            constraints,
+                            stats,
+                            metrics,
+                            violations,
+                            dict_refs,
+                            loop_context,
+                            context,
+                        );
+                        stats.constraints += 1;
+                        metrics.record_constraint("handle.return");
+                        constraints
+                            .push(Constraint::equal(body_result.ty.clone(), handle_ty.clone()));
+                        metrics.record_unify_call();
+                        let _ = solver.unify(body_result.ty.clone(), handle_ty.clone());
+                        dicts.extend(body_result.dict_ref_ids.clone());
+                        return_clause = Some(TypedHandlerReturnDraft {
+                            value: ParamBinding {
+                                display: value_ident.name.clone(),
+                                span: value_ident.span,
+                                ty: target_result.ty.clone(),
+                                annotation: None,
+                            },
+                            body: Box::new(body_result),
+                            span: *span,
+                        });
+                    }
+                }
+            }
+            if return_clause.is_none() {
+                // return 節が無い場合は本体の値がそのまま handle の値になる
+                metrics.record_unify_call();
+                let _ = solver.unify(target_result.ty.clone(), handle_ty.clone());
+            }
+            make_typed(
+                expr,
+                TypedExprKindDraft::Handle {
+                    target: Box::new(target_result),
+                    handler: TypedHandlerDraft {
+                        effect: handle.handler.name.clone(),
+                        operations,
+                        return_clause,
+                    },
+                },
+                solver.substitution().apply(&handle_ty),
+                dicts,
+            )
+        }
         _ => make_typed(
             expr,
             TypedExprKindDraft::Unknown,
@@ -6925,6 +7230,8 @@ struct ExhaustivenessResult {
     unreachable_arm_indices: Vec<usize>,
     missing_variants: Option<Vec<String>>,
     missing_ranges: Option<Vec<PatternRangeInfo>>,
+    /// 入れ子の判定を持たない腕だけで網羅できるか。コード生成が既定分岐を省く根拠に使う。
+    lowering_exhaustive: bool,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -7065,6 +7372,11 @@ impl RangeCoverageTracker {
 }
 
 fn sum_constructors_for_type(env: &TypeEnv, target_ty: &Type) -> Option<HashSet<String>> {
+    sum_constructor_order(env, target_ty).map(|constructors| constructors.into_iter().collect())
+}
+
+/// 和型のコンストラクタ名を宣言順に返す。
+fn sum_constructor_order(env: &TypeEnv, target_ty: &Type) -> Option<Vec<String>> {
     let Type::App { constructor, .. } = target_ty else {
         return None;
     };
@@ -7078,7 +7390,7 @@ fn sum_constructors_for_type(env: &TypeEnv, target_ty: &Type) -> Option<HashSet<
     let constructors = variants
         .iter()
         .map(|variant| variant.name.name.clone())
-        .collect::<HashSet<_>>();
+        .collect::<Vec<_>>();
     Some(constructors)
 }
 
@@ -7162,12 +7474,74 @@ fn analyze_match_exhaustiveness(
     } else {
         None
     };
+    // 追跡器はコンストラクタの引数を見ないため、`Some(1)` も `Some` 全体の網羅として数える。
+    // コード生成では入れ子の判定を持たない腕だけで網羅を数え直す。
+    let mut lowering_tracker = ExhaustivenessTracker::new(domain, tracker.sum_constructors.clone());
+    let mut lowering_ranges = RangeCoverageTracker::new(target_ty);
+    for arm in arms
+        .iter()
+        .filter(|arm| arm.guard.is_none() && is_shallow_pattern(&arm.pattern))
+    {
+        lowering_tracker.observe_arm(arm);
+        lowering_ranges.observe_pattern(&arm.pattern);
+    }
+    let lowering_exhaustive =
+        lowering_tracker.coverage_reached() || lowering_ranges.result().coverage_reached;
     ExhaustivenessResult {
         coverage_reached,
         should_report_missing,
         unreachable_arm_indices,
         missing_variants,
         missing_ranges,
+        lowering_exhaustive,
+    }
+}
+
+/// 最上位の判定だけで成否が決まるパターンか（引数や要素はすべて無条件に一致する）。
+fn is_shallow_pattern(pattern: &Pattern) -> bool {
+    match &pattern.kind {
+        PatternKind::Binding { pattern: inner, .. } => is_shallow_pattern(inner),
+        PatternKind::Or { variants } => variants.iter().all(is_shallow_pattern),
+        PatternKind::Constructor { args, .. } => args.iter().all(is_irrefutable_pattern),
+        PatternKind::Slice { elements } => elements.iter().all(|element| match element {
+            SlicePatternItem::Element(inner) => is_irrefutable_pattern(inner),
+            SlicePatternItem::Rest { .. } => true,
+        }),
+        PatternKind::ActivePattern { argument, .. } => argument
+            .as_ref()
+            .map(|inner| is_irrefutable_pattern(inner))
+            .unwrap_or(true),
+        PatternKind::Guard { .. } => false,
+        PatternKind::Literal(_) | PatternKind::Range { .. } => true,
+        _ => is_irrefutable_pattern(pattern),
+    }
+}
+
+fn is_irrefutable_pattern(pattern: &Pattern) -> bool {
+    match &pattern.kind {
+        PatternKind::Wildcard | PatternKind::Var(_) => true,
+        PatternKind::Binding { pattern: inner, .. } => is_irrefutable_pattern(inner),
+        PatternKind::Tuple { elements } => elements.iter().all(is_irrefutable_pattern),
+        PatternKind::Record { fields, .. } => fields.iter().all(|field| {
+            field
+                .value
+                .as_ref()
+                .map(|value| is_irrefutable_pattern(value))
+                .unwrap_or(true)
+        }),
+        PatternKind::Range { start, end, .. } => start.is_none() && end.is_none(),
+        PatternKind::ActivePattern {
+            is_partial,
+            argument,
+            ..
+        } => {
+            !*is_partial
+                && argument
+                    .as_ref()
+                    .map(|inner| is_irrefutable_pattern(inner))
+                    .unwrap_or(true)
+        }
+        _ => false,
     }
 }
 
@@ -7877,6 +8251,7 @@ enum TypedExprKindDraft {
     Match {
         target: Box<TypedExprDraft>,
         arms: Vec<TypedMatchArmDraft>,
+        coverage: typed::TypedMatchCoverage,
     },
     Call {
         callee: Box<TypedExprDraft>,
@@ -7889,6 +8264,10 @@ enum TypedExprKindDraft {
         body: Box<TypedExprDraft>,
         captures: Vec<typed::TypedLambdaCapture>,
     },
+    Unary {
+        operator: String,
+        operand: Box<TypedExprDraft>,
+    },
     Binary {
         operator: String,
         left: Box<TypedExprDraft>,
@@ -7897,6 +8276,10 @@ enum TypedExprKindDraft {
     PerformCall {
         call: TypedEffectCallDraft,
     },
+    Handle {
+        target: Box<TypedExprDraft>,
+        handler: TypedHandlerDraft,
+    },
     EffectBlock {
         body: Box<TypedExprDraft>,
     },
@@ -7940,6 +8323,28 @@ struct TypedEffectCallDraft {
     argument: Box<TypedExprDraft>,
 }
 
+#[derive(Clone)]
+struct TypedHandlerDraft {
+    effect: Ident,
+    operations: Vec<TypedHandlerOperationDraft>,
+    return_clause: Option<TypedHandlerReturnDraft>,
+}
+
+#[derive(Clone)]
+struct TypedHandlerOperationDraft {
+    name: Ident,
+    params: Vec<ParamBinding>,
+    body: Box<TypedExprDraft>,
+    span: Span,
+}
+
+#[derive(Clone)]
+struct TypedHandlerReturnDraft {
+    value: ParamBinding,
+    body: Box<TypedExprDraft>,
+    span: Span,
+}
+
 #[derive(Clone)]
 struct InlineAsmOutputDraft {
     constraint: String,
@@ -8046,9 +8451,43 @@ fn unstable_attribute_strings(attrs: &[Attribute]) -> Vec<String> {
 fn function_attribute_strings(attrs: &[Attribute]) -> Vec<String> {
     let mut values = intrinsic_attribute_strings(attrs);
     values.extend(unstable_attribute_strings(attrs));
+    if attrs
+        .iter()
+        .any(|attr| attr.name.name == TAILREC_ATTRIBUTE && attr.args.is_empty())
+    {
+        values.push(TAILREC_ATTRIBUTE.to_string());
+    }
+    // `@export` は C ABI で公開する関数の目印。バックエンドの C ヘッダ生成が参照する。
+    if attrs
+        .iter()
+        .any(|attr| attr.name.name == "export" && attr.args.is_empty())
+    {
+        values.push("export".to_string());
+    }
     values
 }
 
+/// `@tailrec` 関数のうち、末尾位置にない自己呼び出しを報告する。
+fn detect_tailrec_violations(module: &mir::MirModule, violations: &mut Vec<TypecheckViolation>) {
+    for function in &module.functions {
+        if !function
+            .attributes
+            .iter()
+            .any(|attr| attr == TAILREC_ATTRIBUTE)
+        {
+            continue;
+        }
+        let analysis = analyze_tail_calls(function);
+        for expr_id in analysis.non_tail_self_calls {
+            let span = function.exprs[expr_id].span;
+            violations.push(TypecheckViolation::tailrec_non_tail_call(
+                span,
+                function.name.as_str(),
+            ));
+        }
+    }
+}
+
 fn effect_has_native(effect: &Option<EffectAnnotation>) -> bool {
     match effect {
         Some(annotation) => annotation.tags.iter().any(|tag| tag.name == "native"),
@@ -8127,6 +8566,15 @@ fn make_typed(
     }
 }
 
+fn finalize_param_binding(binding: ParamBinding, substitution: &Substitution) -> typed::TypedParam {
+    typed::TypedParam {
+        name: binding.display,
+        span: binding.span,
+        ty: substitution.apply(&binding.ty).label(),
+        annotation: binding.annotation,
+    }
+}
+
 fn finalize_typed_expr(expr: TypedExprDraft, substitution: &Substitution) -> typed::TypedExpr {
     let ty = substitution.apply(&expr.ty);
     let kind = match expr.kind {
@@ -8165,6 +8613,10 @@ fn finalize_typed_expr(expr: TypedExprDraft, substitution: &Substitution) -> typ
         TypedExprKindDraft::Propagate { expr } => typed::TypedExprKind::Propagate {
             expr: Box::new(finalize_typed_expr(*expr, substitution)),
         },
+        TypedExprKindDraft::Unary { operator, operand } => typed::TypedExprKind::Unary {
+            operator,
+            operand: Box::new(finalize_typed_expr(*operand, substitution)),
+        },
         TypedExprKindDraft::Binary {
             operator,
             left,
@@ -8174,7 +8626,11 @@ fn finalize_typed_expr(expr: TypedExprDraft, substitution: &Substitution) -> typ
             left: Box::new(finalize_typed_expr(*left, substitution)),
             right: Box::new(finalize_typed_expr(*right, substitution)),
         },
-        TypedExprKindDraft::Match { target, arms } => typed::TypedExprKind::Match {
+        TypedExprKindDraft::Match {
+            target,
+            arms,
+            coverage,
+        } => typed::TypedExprKind::Match {
             target: Box::new(finalize_typed_expr(*target, substitution)),
             arms: arms
                 .into_iter()
@@ -8187,6 +8643,7 @@ fn finalize_typed_expr(expr: TypedExprDraft, substitution: &Substitution) -> typ
                     body: finalize_typed_expr(arm.body, substitution),
                 })
                 .collect(),
+            coverage,
         },
         TypedExprKindDraft::Call {
             callee,
@@ -8225,6 +8682,33 @@ fn finalize_typed_expr(expr: TypedExprDraft, substitution: &Substitution) -> typ
                 argument: Box::new(finalize_typed_expr(*call.argument, substitution)),
             },
         },
+        TypedExprKindDraft::Handle { target, handler } => typed::TypedExprKind::Handle {
+            target: Box::new(finalize_typed_expr(*target, substitution)),
+            handler: typed::TypedHandler {
+                effect: handler.effect,
+                operations: handler
+                    .operations
+                    .into_iter()
+                    .map(|operation| typed::TypedHandlerOperation {
+                        name: operation.name,
+                        params: operation
+                            .params
+                            .into_iter()
+                            .map(|binding| finalize_param_binding(binding, substitution))
+                            .collect(),
+                        body: Box::new(finalize_typed_expr(*operation.body, substitution)),
+                        span: operation.span,
+                    })
+                    .collect(),
+                return_clause: handler
+                    .return_clause
+                    .map(|clause| typed::TypedHandlerReturn {
+                        value: finalize_param_binding(clause.value, substitution),
+                        body: Box::new(finalize_typed_expr(*clause.body, substitution)),
+                        span: clause.span,
+                    }),
+            },
+        },
         TypedExprKindDraft::EffectBlock { body } => typed::TypedExprKind::EffectBlock {
             body: Box::new(finalize_typed_expr(*body, substitution)),
         },
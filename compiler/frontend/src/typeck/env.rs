@@ -910,6 +910,7 @@ pub struct TypeEnv {
     bindings: IndexMap<String, Binding>,
     type_decls: IndexMap<String, TypeDeclBinding>,
     type_constructors: IndexMap<String, TypeConstructorBinding>,
+    effect_operations: IndexMap<String, Scheme>,
     parent: Option<Box<TypeEnv>>,
 }
 
@@ -925,6 +926,7 @@ impl TypeEnv {
             bindings: IndexMap::new(),
             type_decls: IndexMap::new(),
             type_constructors: IndexMap::new(),
+            effect_operations: IndexMap::new(),
             parent: None,
         }
     }
@@ -971,11 +973,27 @@ impl TypeEnv {
         }
     }
 
+    /// 効果操作 `Effect::op` のシグネチャを登録する。
+    pub fn insert_effect_operation(&mut self, name: impl Into<String>, scheme: Scheme) {
+        self.effect_operations.insert(name.into(), scheme);
+    }
+
+    pub fn lookup_effect_operation(&self, name: &str) -> Option<&Scheme> {
+        if let Some(scheme) = self.effect_operations.get(name) {
+            Some(scheme)
+        } else {
+            self.parent
+                .as_deref()
+                .and_then(|parent| parent.lookup_effect_operation(name))
+        }
+    }
+
     pub fn enter_scope(&self) -> TypeEnv {
         TypeEnv {
             bindings: IndexMap::new(),
             type_decls: IndexMap::new(),
             type_constructors: IndexMap::new(),
+            effect_operations: IndexMap::new(),
             parent: Some(Box::new(self.clone())),
         }
     }
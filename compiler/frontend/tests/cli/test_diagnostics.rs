@@ -3,10 +3,11 @@ use reml_frontend::output::cli::{
     render_human_output_to_string, CliCommandKind, CliDiagnosticEnvelope, CliExitCode,
     CliPhaseKind, CliSummary,
 };
+use reml_frontend::output::sarif::build_sarif_log;
 use reml_frontend::parser::ParserDriver;
 use serde_json::{json, Map, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 fn load_fixture_diagnostics() -> Vec<Value> {
@@ -88,6 +89,26 @@ fn cli_human_output_snapshot() {
     assert_snapshot!("cli_human_output", human);
 }
 
+#[test]
+fn cli_sarif_output_covers_every_diagnostic() {
+    let envelope = sample_envelope();
+    let log = build_sarif_log(&envelope, Path::new("tests/fixtures/sample.reml"));
+    assert_eq!(log["version"], json!("2.1.0"));
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], json!("reml_frontend"));
+    assert_eq!(run["invocations"][0]["exitCode"], json!(2));
+    let results = run["results"].as_array().expect("results must be an array");
+    assert_eq!(results.len(), envelope.diagnostics().len());
+    let rules = run["tool"]["driver"]["rules"]
+        .as_array()
+        .expect("rules must be an array");
+    for result in results {
+        if let Some(index) = result["ruleIndex"].as_u64() {
+            assert_eq!(rules[index as usize]["id"], result["ruleId"]);
+        }
+    }
+}
+
 #[test]
 fn streaming_examples_parse_without_diagnostics() {
     let root = workspace_root();
@@ -0,0 +1,52 @@
+use reml_frontend::diagnostic::explain::explanations;
+use reml_frontend::parser::ParserDriver;
+use reml_frontend::typeck::{TypecheckConfig, TypecheckDriver};
+
+/// パーサ診断と型検査違反のコードをまとめて収集する。
+fn emitted_codes(source: &str) -> Vec<String> {
+    let result = ParserDriver::parse(source);
+    let mut codes = result
+        .diagnostics
+        .iter()
+        .map(|diag| {
+            diag.code
+                .clone()
+                .unwrap_or_else(|| format!("<parser: {}>", diag.message))
+        })
+        .collect::<Vec<_>>();
+    let module = result.value.expect("AST を取得できるはず");
+    let report = TypecheckDriver::infer_module(Some(&module), &TypecheckConfig::default());
+    codes.extend(
+        report
+            .violations
+            .iter()
+            .map(|violation| violation.code.to_string()),
+    );
+    codes
+}
+
+#[test]
+fn failing_examples_emit_exactly_their_code() {
+    for entry in explanations() {
+        let codes = emitted_codes(entry.failing_example);
+        assert!(
+            !codes.is_empty() && codes.iter().all(|code| code == entry.code),
+            "{} の失敗例は当該コードのみを報告するはず: {:?}",
+            entry.code,
+            codes
+        );
+    }
+}
+
+#[test]
+fn fixed_examples_do_not_emit_their_code() {
+    for entry in explanations() {
+        let codes = emitted_codes(entry.fixed_example);
+        assert!(
+            codes.iter().all(|code| code != entry.code),
+            "{} の修正例で同じ診断が残っています: {:?}",
+            entry.code,
+            codes
+        );
+    }
+}
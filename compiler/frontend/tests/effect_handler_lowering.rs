@@ -0,0 +1,214 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use reml_frontend::parser::ParserDriver;
+use reml_frontend::typeck::{TypecheckConfig, TypecheckDriver};
+use reml_llvm_backend::{emit_llvm_module_from_mir_json, TargetMachineBuilder, Triple};
+
+fn mir_json(source: &str) -> String {
+    let result = ParserDriver::parse(source);
+    assert!(
+        result.diagnostics.is_empty(),
+        "parser diagnostics: {:?}",
+        result
+            .diagnostics
+            .iter()
+            .map(|diag| &diag.message)
+            .collect::<Vec<_>>()
+    );
+    let module = result.value.expect("AST");
+    let report = TypecheckDriver::infer_module(Some(&module), &TypecheckConfig::default());
+    serde_json::to_string(&report.mir).expect("MIR JSON")
+}
+
+/// `text` を保持するモジュール定数のシンボルを返す。
+fn string_constant(ir: &str, text: &str) -> String {
+    let initializer = format!(" x i8] c\"{text}\\00\"");
+    ir.lines()
+        .find(|line| line.ends_with(&initializer))
+        .and_then(|line| line.split_whitespace().next())
+        .unwrap_or_else(|| panic!("文字列定数 {text} が見つからない: {ir}"))
+        .to_string()
+}
+
+/// `runtime/native` の C ソース。効果ハンドラの実装（`effects.c`）と、それが使う
+/// メモリ管理・ボックス化をまとめてリンクする。
+fn native_runtime_sources() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../runtime/native/src");
+    let mut sources: Vec<PathBuf> = fs::read_dir(&dir)
+        .expect("runtime/native/src")
+        .map(|entry| entry.expect("ディレクトリ項目").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "c"))
+        .collect();
+    sources.sort();
+    sources
+}
+
+fn tool_available(program: &str) -> bool {
+    Command::new(program).arg("--version").output().is_ok()
+}
+
+fn llc_major_version() -> Option<u32> {
+    let output = Command::new("llc").arg("--version").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let version = text
+        .lines()
+        .find_map(|line| line.split_once("LLVM version ").map(|(_, rest)| rest))?;
+    version.split('.').next()?.trim().parse().ok()
+}
+
+/// MIR を LLVM IR へ下ろし、`llc` と C コンパイラでネイティブランタイムとリンクして実行する。
+/// 終了コードを返す。
+fn run_native(source: &str, name: &str) -> i32 {
+    let dir = env::temp_dir().join(format!("reml_{name}_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("作業ディレクトリの作成");
+    let mir_path = dir.join("app.mir.json");
+    let ir_path = dir.join("app.ll");
+    let obj_path = dir.join("app.o");
+    let exe_path = dir.join("app");
+    fs::write(&mir_path, mir_json(source)).expect("MIR JSON の書き出し");
+    let target = TargetMachineBuilder::new()
+        .with_triple(Triple::LinuxGNU)
+        .build();
+    let ir = emit_llvm_module_from_mir_json(&mir_path, target, vec![], false, name)
+        .expect("LLVM IR の生成");
+    fs::write(&ir_path, ir).expect("LLVM IR の書き出し");
+
+    let mut llc = Command::new("llc");
+    // LLVM 15 より前は `ptr` 表記に opaque pointer の明示が必要。
+    if llc_major_version().is_some_and(|major| major < 15) {
+        llc.arg("-opaque-pointers");
+    }
+    let output = llc
+        .arg("-filetype=obj")
+        .arg("-relocation-model=pic")
+        .arg(&ir_path)
+        .arg("-o")
+        .arg(&obj_path)
+        .output()
+        .expect("llc の起動");
+    assert!(
+        output.status.success(),
+        "llc が失敗しました: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let include = Path::new(env!("CARGO_MANIFEST_DIR")).join("../runtime/native/include");
+    let output = Command::new("cc")
+        .arg("-std=c11")
+        .arg("-I")
+        .arg(&include)
+        .arg(&obj_path)
+        .args(native_runtime_sources())
+        .arg("-o")
+        .arg(&exe_path)
+        .arg("-lm")
+        .output()
+        .expect("cc の起動");
+    assert!(
+        output.status.success(),
+        "リンクに失敗しました: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let status = Command::new(&exe_path).status().expect("実行");
+    let _ = fs::remove_dir_all(&dir);
+    status.code().expect("終了コード")
+}
+
+#[test]
+fn handler_example_lowers_to_runtime_effect_calls() {
+    let source = r#"
+effect Counter : state {
+  operation next : () -> Int
+  operation reset : () -> Int
+}
+
+fn tick() -> Int = do Counter.next(())
+
+fn main() -> Int {
+  let step = 2
+  handle tick() with
+    handler Counter {
+      operation next(_unit, resume) {
+        resume(step)
+      }
+      operation reset(_unit, resume) {
+        0
+      }
+      return value {
+        value
+      }
+    }
+}
+"#;
+    let path = env::temp_dir().join("reml_effect_handler_lowering.mir.json");
+    fs::write(&path, mir_json(source)).expect("MIR JSON の書き出し");
+    let target = TargetMachineBuilder::new()
+        .with_triple(Triple::LinuxGNU)
+        .build();
+    let ir = emit_llvm_module_from_mir_json(&path, target, vec![], false, "effects")
+        .expect("LLVM IR の生成");
+    fs::remove_file(&path).expect("一時ファイルの削除");
+
+    // 効果名・操作名は NUL 終端したモジュール定数として渡す。
+    let counter = string_constant(&ir, "Counter");
+    let next = string_constant(&ir, "next");
+
+    // perform は効果名・操作名・ボックス化した引数で Runtime を呼び、結果を取り出す。
+    assert!(ir.contains(&format!(
+        "call ptr @reml_perform(ptr {counter}, ptr {next}, ptr null)"
+    )));
+    assert!(ir.contains("call i64 @reml_unbox_i64(ptr %perform"));
+
+    // handle はハンドラフレームを組み立て、節を登録してから本体クロージャを渡す。
+    assert!(ir.contains(&format!(
+        "call ptr @reml_handler_new(ptr {counter}, ptr %closure"
+    )));
+    let add_op = |name: &str, tail: u8| {
+        let symbol = string_constant(&ir, name);
+        ir.lines().any(|line| {
+            line.contains("call void @reml_handler_add_op(ptr %handler")
+                && line.contains(&format!("ptr {symbol}, ptr %closure"))
+                && line.ends_with(&format!("i32 {tail})"))
+        })
+    };
+    // 末尾で一度だけ resume する節だけが継続を作らない経路で登録される。
+    assert!(add_op("next", 1), "{ir}");
+    assert!(add_op("reset", 0), "{ir}");
+    assert!(ir.contains("call ptr @reml_handle(ptr %handler"));
+    assert!(ir.contains("(ptr %__env, ptr %_unit__boxed, ptr %resume__boxed)"));
+}
+
+/// `llc` と C コンパイラが利用できる環境でのみ、ハンドラ付きのプログラムを実行して
+/// resume の値と return 節の変換を確かめる。
+#[test]
+fn handler_example_runs_against_native_runtime() {
+    if !tool_available("llc") || !tool_available("cc") {
+        eprintln!("llc または cc が見つからないため実行検証をスキップします");
+        return;
+    }
+    let source = r#"
+effect Counter : state {
+  operation next : () -> Int
+}
+
+fn tick() -> Int = do Counter.next(())
+
+fn main() -> Int {
+  let step = 2
+  handle tick() + tick() * 10 with
+    handler Counter {
+      operation next(_unit, resume) {
+        resume(step)
+      }
+      return value {
+        value + 3
+      }
+    }
+}
+"#;
+    // 各 perform は resume(2) で再開し、本体の値 2 + 2 * 10 に return 節が 3 を足す。
+    assert_eq!(run_native(source, "effect_handler_run"), 25);
+}
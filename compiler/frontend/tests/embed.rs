@@ -0,0 +1,280 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::c_void;
+use std::ptr;
+
+use serde_json::Value;
+
+use reml_frontend::embed::{install_embed_module_compiler, FrontendEmbedCompiler};
+use reml_runtime::embedding::{
+    reml_call, reml_create_context, reml_dispose_context, reml_embed_value_array,
+    reml_embed_value_array_get, reml_embed_value_as_float, reml_embed_value_as_int,
+    reml_embed_value_as_string, reml_embed_value_dec_ref, reml_embed_value_float,
+    reml_embed_value_int, reml_embed_value_length, reml_embed_value_record,
+    reml_embed_value_record_get, reml_last_error, reml_load_module, reml_register_function,
+    reml_run, EmbedFunction, EmbedModuleCompiler, EmbedValue, RemlEmbedContext, RemlEmbedStatus,
+    COMPILE_FAILED_CODE,
+};
+
+const BASIC_SOURCE: &str = include_str!("../../../examples/native/embedding/basic.reml");
+
+const HOST_SOURCE: &str = r#"module Embed.Host
+
+extern "C" {
+  fn host_add(a: Int, b: Int) -> Int;
+}
+
+type Point = { x: Int, y: Float }
+
+fn add_twice(a: Int) -> Int {
+  host_add(a, host_add(a, 1))
+}
+
+fn echo(p: Point) -> Point {
+  p
+}
+"#;
+
+const SHAPES_SOURCE: &str = r#"module Embed.Shapes
+
+type Point = { y: Float, x: Int }
+
+fn scale(p: Point, k: Int, f: Float) -> Point {
+  { x: p.x * k, y: p.y * f }
+}
+
+fn pair(p: Point) -> [Int] {
+  [p.x, p.x * 2]
+}
+
+fn twice(p: Point) -> [Point] {
+  [p, p]
+}
+
+fn echo(xs: [Int]) -> [Int] = xs
+"#;
+
+fn create_context() -> *mut RemlEmbedContext {
+    install_embed_module_compiler().expect("install frontend compiler");
+    let abi = CString::new("0.1.0").unwrap();
+    let mut context = ptr::null_mut();
+    assert_eq!(
+        reml_create_context(abi.as_ptr(), &mut context),
+        RemlEmbedStatus::Ok
+    );
+    context
+}
+
+fn load(context: *mut RemlEmbedContext, source: &str) -> RemlEmbedStatus {
+    reml_load_module(context, source.as_ptr(), source.len())
+}
+
+fn last_error(context: *const RemlEmbedContext) -> Option<Value> {
+    let message = reml_last_error(context);
+    if message.is_null() {
+        return None;
+    }
+    let text = unsafe { CStr::from_ptr(message) }.to_str().unwrap();
+    Some(serde_json::from_str(text).expect("last_error is JSON"))
+}
+
+fn call(
+    context: *mut RemlEmbedContext,
+    entrypoint: &str,
+    args: &[*const EmbedValue],
+) -> (RemlEmbedStatus, *mut EmbedValue) {
+    let entrypoint = CString::new(entrypoint).unwrap();
+    let mut result = ptr::null_mut();
+    let status = reml_call(
+        context,
+        entrypoint.as_ptr(),
+        args.as_ptr(),
+        args.len(),
+        &mut result,
+    );
+    (status, result)
+}
+
+fn point(x: i64, y: f64) -> *mut EmbedValue {
+    // 宣言順（y, x）のまま渡し、境界側でフィールド名から並べ替えさせる。
+    let names = [CString::new("y").unwrap(), CString::new("x").unwrap()];
+    let names: Vec<_> = names.iter().map(|name| name.as_ptr()).collect();
+    let values = [reml_embed_value_float(y), reml_embed_value_int(x)];
+    let fields: Vec<*const EmbedValue> = values.iter().map(|value| *value as *const _).collect();
+    let record = reml_embed_value_record(names.as_ptr(), fields.as_ptr(), fields.len());
+    for value in values {
+        reml_embed_value_dec_ref(value);
+    }
+    record
+}
+
+fn field(record: *const EmbedValue, name: &str) -> *const EmbedValue {
+    let name = CString::new(name).unwrap();
+    let value = reml_embed_value_record_get(record, name.as_ptr());
+    assert!(!value.is_null(), "field {name:?}");
+    value
+}
+
+fn ints(array: *const EmbedValue) -> Vec<i64> {
+    (0..reml_embed_value_length(array))
+        .map(|index| reml_embed_value_as_int(reml_embed_value_array_get(array, index)))
+        .collect()
+}
+
+unsafe extern "C" fn host_add(
+    _user_data: *mut c_void,
+    args: *const *const EmbedValue,
+    argc: usize,
+) -> *mut EmbedValue {
+    let args = std::slice::from_raw_parts(args, argc);
+    reml_embed_value_int(reml_embed_value_as_int(args[0]) + reml_embed_value_as_int(args[1]))
+}
+
+#[test]
+fn basic_example_runs_and_returns_its_string() {
+    let context = create_context();
+    assert_eq!(
+        load(context, BASIC_SOURCE),
+        RemlEmbedStatus::Ok,
+        "{:?}",
+        last_error(context)
+    );
+    let main = CString::new("main").unwrap();
+    assert_eq!(
+        reml_run(context, main.as_ptr()),
+        RemlEmbedStatus::Ok,
+        "{:?}",
+        last_error(context)
+    );
+
+    let (status, result) = call(context, "main", &[]);
+    assert_eq!(status, RemlEmbedStatus::Ok, "{:?}", last_error(context));
+    let mut length = 0;
+    let data = reml_embed_value_as_string(result, &mut length);
+    let text = unsafe { std::slice::from_raw_parts(data as *const u8, length) };
+    assert_eq!(text, b"embedded ok");
+
+    reml_embed_value_dec_ref(result);
+    assert_eq!(reml_dispose_context(context), RemlEmbedStatus::Ok);
+}
+
+#[test]
+fn extern_declarations_call_registered_host_functions() {
+    let context = create_context();
+    assert_eq!(
+        load(context, HOST_SOURCE),
+        RemlEmbedStatus::Ok,
+        "{:?}",
+        last_error(context)
+    );
+    let name = CString::new("host_add").unwrap();
+    let signature = CString::new("(Int, Int) -> Int").unwrap();
+    assert_eq!(
+        reml_register_function(
+            context,
+            name.as_ptr(),
+            signature.as_ptr(),
+            Some(host_add),
+            ptr::null_mut(),
+        ),
+        RemlEmbedStatus::Ok
+    );
+
+    let arg = reml_embed_value_int(20);
+    let (status, result) = call(context, "add_twice", &[arg]);
+    assert_eq!(status, RemlEmbedStatus::Ok, "{:?}", last_error(context));
+    assert_eq!(reml_embed_value_as_int(result), 41);
+
+    reml_embed_value_dec_ref(result);
+    reml_embed_value_dec_ref(arg);
+    assert_eq!(reml_dispose_context(context), RemlEmbedStatus::Ok);
+}
+
+#[test]
+fn records_and_arrays_round_trip_through_compiled_modules() {
+    let context = create_context();
+    assert_eq!(
+        load(context, SHAPES_SOURCE),
+        RemlEmbedStatus::Ok,
+        "{:?}",
+        last_error(context)
+    );
+    let p = point(2, 1.5);
+
+    let k = reml_embed_value_int(3);
+    let f = reml_embed_value_float(2.0);
+    let (status, scaled) = call(context, "scale", &[p, k, f]);
+    assert_eq!(status, RemlEmbedStatus::Ok, "{:?}", last_error(context));
+    assert_eq!(reml_embed_value_length(scaled), 2);
+    assert_eq!(reml_embed_value_as_int(field(scaled, "x")), 6);
+    assert_eq!(reml_embed_value_as_float(field(scaled, "y")), 3.0);
+
+    let (status, pair) = call(context, "pair", &[p]);
+    assert_eq!(status, RemlEmbedStatus::Ok, "{:?}", last_error(context));
+    assert_eq!(ints(pair), vec![2, 4]);
+
+    let (status, twice) = call(context, "twice", &[p]);
+    assert_eq!(status, RemlEmbedStatus::Ok, "{:?}", last_error(context));
+    assert_eq!(reml_embed_value_length(twice), 2);
+    for index in 0..2 {
+        let item = reml_embed_value_array_get(twice, index);
+        assert_eq!(reml_embed_value_as_int(field(item, "x")), 2);
+        assert_eq!(reml_embed_value_as_float(field(item, "y")), 1.5);
+    }
+
+    let items: Vec<_> = (1..=3).map(|value| reml_embed_value_int(value)).collect();
+    let borrowed: Vec<*const EmbedValue> = items.iter().map(|item| *item as *const _).collect();
+    let xs = reml_embed_value_array(borrowed.as_ptr(), borrowed.len());
+    let (status, echoed) = call(context, "echo", &[xs]);
+    assert_eq!(status, RemlEmbedStatus::Ok, "{:?}", last_error(context));
+    assert_eq!(ints(echoed), vec![1, 2, 3]);
+
+    for value in items {
+        reml_embed_value_dec_ref(value);
+    }
+    for value in [scaled, pair, twice, echoed, xs, p, k, f] {
+        reml_embed_value_dec_ref(value);
+    }
+    assert_eq!(reml_dispose_context(context), RemlEmbedStatus::Ok);
+}
+
+#[test]
+fn signatures_keep_record_field_names_from_annotations() {
+    let compiled = FrontendEmbedCompiler
+        .compile(HOST_SOURCE)
+        .expect("module compiles");
+    assert!(compiled.wasm.starts_with(b"\0asm"));
+    assert!(compiled.exports.contains(&EmbedFunction::new(
+        "echo",
+        "({x: Int, y: Float}) -> {x: Int, y: Float}"
+    )));
+    assert!(compiled
+        .exports
+        .contains(&EmbedFunction::new("add_twice", "(Int) -> Int")));
+    assert_eq!(
+        compiled.imports,
+        vec![EmbedFunction::new("host_add", "(Int, Int) -> Int")]
+    );
+
+    // フィールドは線形メモリ上の並び（名前順）で書く。
+    let compiled = FrontendEmbedCompiler
+        .compile(SHAPES_SOURCE)
+        .expect("module compiles");
+    assert!(compiled.exports.contains(&EmbedFunction::new(
+        "scale",
+        "({x: Int, y: Float}, Int, Float) -> {x: Int, y: Float}"
+    )));
+}
+
+#[test]
+fn type_errors_are_reported_with_source_spans() {
+    let context = create_context();
+    let source = "fn main() -> Int = if 42 then 1 else 0\n";
+    assert_eq!(load(context, source), RemlEmbedStatus::Error);
+    let error = last_error(context).expect("error recorded");
+    assert_eq!(error["code"], COMPILE_FAILED_CODE);
+    let cause = &error["diagnostics"][0];
+    assert!(cause["code"].as_str().is_some_and(|code| !code.is_empty()));
+    let start = cause["span"]["start"].as_u64().expect("span") as usize;
+    assert!(start < source.len());
+    assert_eq!(reml_dispose_context(context), RemlEmbedStatus::Ok);
+}
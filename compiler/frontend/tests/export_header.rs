@@ -0,0 +1,58 @@
+use reml_frontend::parser::ParserDriver;
+use reml_frontend::typeck::{TypecheckConfig, TypecheckDriver};
+use reml_llvm_backend::{emit_c_header_from_mir_json_str, CHeaderOptions, DataLayoutSpec};
+
+fn mir_json(source: &str) -> String {
+    let result = ParserDriver::parse(source);
+    assert!(
+        result.diagnostics.is_empty(),
+        "parser diagnostics: {:?}",
+        result
+            .diagnostics
+            .iter()
+            .map(|diag| &diag.message)
+            .collect::<Vec<_>>()
+    );
+    let module = result.value.expect("AST");
+    let report = TypecheckDriver::infer_module(Some(&module), &TypecheckConfig::default());
+    serde_json::to_string(&report.mir).expect("MIR JSON")
+}
+
+#[test]
+fn exported_functions_and_externs_become_c_declarations() {
+    let source = r#"
+extern "C" {
+  fn host_log(message: Str) -> Int;
+}
+
+@export
+fn add(a: Int, b: Int) -> Int = a + b
+
+@export
+fn greet(name: Str) -> Str = name
+
+fn hidden(x: Int) -> Int = x
+"#;
+    let header = emit_c_header_from_mir_json_str(
+        &mir_json(source),
+        DataLayoutSpec::system_v(),
+        &CHeaderOptions {
+            guard: Some("APP_EXPORTS_H".into()),
+            module: Some("app".into()),
+        },
+    )
+    .expect("ヘッダ生成");
+
+    assert_eq!(header.exports, vec!["add", "greet"]);
+    assert_eq!(header.imports, vec!["host_log"]);
+    assert!(header.diagnostics.is_empty(), "{:?}", header.diagnostics);
+    assert!(header.text.contains("#ifndef APP_EXPORTS_H"));
+    assert!(header.text.contains("int64_t add(int64_t a, int64_t b);"));
+    assert!(header
+        .text
+        .contains("reml_string_t greet(reml_string_t name);"));
+    assert!(header
+        .text
+        .contains("int64_t host_log(reml_string_t message);"));
+    assert!(!header.text.contains("hidden"));
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use reml_frontend::format::{format_source, FormatOptions, IndentStyle};
+use reml_frontend::lexer::lex_source;
+use reml_frontend::token::TokenKind;
+
+fn examples_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples")
+}
+
+fn collect_reml_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_reml_files(&path, out);
+        } else if path.extension().is_some_and(|ext| ext == "reml") {
+            out.push(path);
+        }
+    }
+}
+
+/// 字句エラーを含まないサンプルのみを対象にする。
+fn lexable_examples() -> Vec<(PathBuf, String)> {
+    let mut files = Vec::new();
+    collect_reml_files(&examples_root(), &mut files);
+    files.sort();
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let source = fs::read_to_string(&path).ok()?;
+            lex_source(&source)
+                .errors
+                .is_empty()
+                .then_some((path, source))
+        })
+        .collect()
+}
+
+fn token_stream(source: &str) -> Vec<(TokenKind, Option<String>)> {
+    lex_source(source)
+        .tokens
+        .into_iter()
+        .map(|token| (token.kind, token.lexeme))
+        .collect()
+}
+
+fn assert_roundtrip(options: &FormatOptions) {
+    let examples = lexable_examples();
+    assert!(!examples.is_empty(), "examples/ にサンプルが見つかりません");
+    for (path, source) in examples {
+        let formatted = format_source(&source, options)
+            .unwrap_or_else(|err| panic!("{} を整形できません: {err}", path.display()));
+        assert!(
+            token_stream(&source) == token_stream(&formatted),
+            "{} の整形でトークン列が変化しました",
+            path.display()
+        );
+        let again = format_source(&formatted, options).expect("整形済みソースは整形できるはず");
+        assert!(
+            again == formatted,
+            "{} の整形が冪等ではありません",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn examples_keep_token_stream_and_are_idempotent() {
+    assert_roundtrip(&FormatOptions::default());
+}
+
+#[test]
+fn narrow_width_and_tabs_keep_token_stream() {
+    assert_roundtrip(&FormatOptions {
+        line_width: 40,
+        indent_width: 4,
+        indent_style: IndentStyle::Tab,
+    });
+}
@@ -9,6 +9,7 @@ fn collect_tokens(source: &str, profile: IdentifierProfile) -> LexOutput {
     let options = LexerOptions {
         identifier_profile: profile,
         identifier_locale: None,
+        ..LexerOptions::default()
     };
     lex_source_with_options(source, options)
 }
@@ -28,6 +28,7 @@ fn lex_unicode(source: &str, locale: Option<&str>) -> LexOutput {
     let options = LexerOptions {
         identifier_profile: IdentifierProfile::Unicode,
         identifier_locale: locale,
+        ..LexerOptions::default()
     };
     lex_source_with_options(source, options)
 }
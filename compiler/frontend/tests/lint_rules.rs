@@ -0,0 +1,169 @@
+use reml_frontend::diagnostic::filter::{LintLevel, LintLevelMap};
+use reml_frontend::lexer::IdentifierProfile;
+use reml_frontend::lint::{apply_fixits, lint_source, LintFinding, LintOptions};
+
+fn lint(source: &str) -> Vec<LintFinding> {
+    lint_with(source, &LintOptions::default())
+}
+
+fn lint_with(source: &str, options: &LintOptions) -> Vec<LintFinding> {
+    lint_source(source, options).unwrap_or_else(|err| panic!("lint を実行できません: {err}"))
+}
+
+fn rule_ids(findings: &[LintFinding]) -> Vec<&'static str> {
+    findings.iter().map(|finding| finding.rule.id).collect()
+}
+
+fn fix(source: &str) -> String {
+    let fixits: Vec<_> = lint(source)
+        .into_iter()
+        .flat_map(|finding| finding.fixits)
+        .collect();
+    apply_fixits(source, &fixits)
+}
+
+#[test]
+fn reports_unused_bindings_and_parameters() {
+    let source = "fn add(a: Int, b: Int) -> Int {\n  let unused = 1\n  a\n}\n";
+    let findings = lint(source);
+    assert_eq!(
+        rule_ids(&findings),
+        vec!["unused_binding", "unused_binding"]
+    );
+    assert_eq!(
+        fix(source),
+        "fn add(a: Int, _b: Int) -> Int {\n  let _unused = 1\n  a\n}\n"
+    );
+}
+
+#[test]
+fn underscore_prefixed_bindings_are_ignored() {
+    let source = "fn main(_flag: Bool) -> Int {\n  let _tmp = 1\n  0\n}\n";
+    assert!(lint(source).is_empty());
+}
+
+#[test]
+fn suggests_let_for_var_without_reassignment() {
+    let source =
+        "fn main() -> Int {\n  var count = 0\n  var total = 0\n  total = count + 1\n  total\n}\n";
+    let findings = lint(source);
+    assert_eq!(rule_ids(&findings), vec!["needless_var"]);
+    assert_eq!(
+        fix(source),
+        "fn main() -> Int {\n  let count = 0\n  var total = 0\n  total = count + 1\n  total\n}\n"
+    );
+}
+
+#[test]
+fn renames_non_snake_case_bindings_with_references() {
+    let source = "fn main() -> Int {\n  let itemCount = 2\n  itemCount + itemCount\n}\n";
+    let findings = lint(source);
+    assert_eq!(rule_ids(&findings), vec!["naming_convention"]);
+    assert_eq!(
+        fix(source),
+        "fn main() -> Int {\n  let item_count = 2\n  item_count + item_count\n}\n"
+    );
+}
+
+#[test]
+fn ascii_profile_rejects_non_ascii_identifiers() {
+    let source = "fn main() -> Int {\n  let 合計 = 1\n  合計\n}\n";
+    assert!(lint(source).is_empty());
+    let options = LintOptions {
+        identifier_profile: IdentifierProfile::AsciiCompat,
+        ..LintOptions::default()
+    };
+    let findings = lint_with(source, &options);
+    assert_eq!(rule_ids(&findings), vec!["naming_convention"]);
+    assert!(findings[0].fixits.is_empty());
+}
+
+#[test]
+fn shadowing_is_reported_only_when_enabled() {
+    let source = "fn main(x: Int) -> Int {\n  let y = {\n    let x = x + 1\n    x\n  }\n  y\n}\n";
+    assert!(lint(source).is_empty());
+    let mut levels = LintLevelMap::new();
+    levels.insert("shadowed_binding", LintLevel::Warn);
+    let options = LintOptions {
+        levels,
+        ..LintOptions::default()
+    };
+    assert_eq!(
+        rule_ids(&lint_with(source, &options)),
+        vec!["shadowed_binding"]
+    );
+}
+
+#[test]
+fn removes_unused_imports() {
+    let source =
+        "use Core.Text.{trim, split}\nuse Core.Math\n\nfn main() -> Int {\n  trim(\"a\")\n  0\n}\n";
+    let findings = lint(source);
+    assert_eq!(rule_ids(&findings), vec!["unused_import", "unused_import"]);
+    assert_eq!(
+        fix(source),
+        "use Core.Text.{trim}\n\nfn main() -> Int {\n  trim(\"a\")\n  0\n}\n"
+    );
+}
+
+#[test]
+fn reports_arms_after_catch_all() {
+    let source = "fn pick(x: Int) -> Int =\n  match x with\n  | 0 -> 1\n  | _ -> 2\n  | 3 -> 4\n";
+    let findings = lint(source);
+    assert_eq!(rule_ids(&findings), vec!["unreachable_arm"]);
+    assert_eq!(
+        fix(source),
+        "fn pick(x: Int) -> Int =\n  match x with\n  | 0 -> 1\n  | _ -> 2\n"
+    );
+}
+
+#[test]
+fn unwraps_redundant_propagate() {
+    let source = "fn parse(x: Result<Int, Str>) -> Result<Int, Str> {\n  Ok(x?)\n}\n";
+    let findings = lint(source);
+    assert_eq!(rule_ids(&findings), vec!["redundant_propagate"]);
+    assert_eq!(
+        fix(source),
+        "fn parse(x: Result<Int, Str>) -> Result<Int, Str> {\n  x\n}\n"
+    );
+}
+
+#[test]
+fn reports_needless_unsafe_and_defer() {
+    let source = "fn main() -> Int {\n  defer 1\n  unsafe { 1 + 2 }\n}\n";
+    let findings = lint(source);
+    assert_eq!(
+        rule_ids(&findings),
+        vec!["needless_defer", "needless_unsafe"]
+    );
+    assert_eq!(fix(source), "fn main() -> Int {\n  { 1 + 2 }\n}\n");
+}
+
+#[test]
+fn attributes_override_levels_per_file_and_function() {
+    let source = "@deny(\"unused_*\")\nmodule app.main\n\n@allow(unused_binding)\nfn quiet(a: Int) -> Int = 0\n\nfn loud(b: Int) -> Int = 0\n";
+    let findings = lint(source);
+    assert_eq!(rule_ids(&findings), vec!["unused_binding"]);
+    assert_eq!(findings[0].level, LintLevel::Deny);
+    assert_eq!(
+        &source[findings[0].span.start as usize..findings[0].span.end as usize],
+        "b"
+    );
+}
+
+#[test]
+fn project_levels_use_filter_patterns() {
+    let source = "fn main(a: Int) -> Int {\n  var itemCount = 1\n  itemCount\n}\n";
+    let mut levels = LintLevelMap::new();
+    levels.insert("*", LintLevel::Deny);
+    levels.insert("naming_*", LintLevel::Allow);
+    let options = LintOptions {
+        levels,
+        ..LintOptions::default()
+    };
+    let findings = lint_with(source, &options);
+    assert_eq!(rule_ids(&findings), vec!["unused_binding", "needless_var"]);
+    assert!(findings
+        .iter()
+        .all(|finding| finding.level == LintLevel::Deny));
+}
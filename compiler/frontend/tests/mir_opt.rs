@@ -0,0 +1,271 @@
+use reml_frontend::parser::ast::LiteralKind;
+use reml_frontend::parser::ParserDriver;
+use reml_frontend::semantics::mir::{MirExprKind, MirFunction, MirModule, MirStmtKind};
+use reml_frontend::semantics::mir_opt::{
+    optimize_module, render_mir_function, MirOptOptions, MirOptReport, MirPass, MirPassManager,
+};
+use reml_frontend::typeck::{TypecheckConfig, TypecheckDriver};
+use reml_llvm_backend::OptimizationLevel;
+
+fn lower(source: &str) -> MirModule {
+    let result = ParserDriver::parse(source);
+    assert!(
+        result.diagnostics.is_empty(),
+        "parser diagnostics: {:?}",
+        result
+            .diagnostics
+            .iter()
+            .map(|diag| &diag.message)
+            .collect::<Vec<_>>()
+    );
+    let module = result.value.expect("AST");
+    TypecheckDriver::infer_module(Some(&module), &TypecheckConfig::default()).mir
+}
+
+fn optimize(source: &str, level: OptimizationLevel) -> (MirModule, MirOptReport) {
+    let mut module = lower(source);
+    let report = optimize_module(&mut module, MirOptOptions::new(level).with_dumps(true));
+    (module, report)
+}
+
+fn function<'a>(module: &'a MirModule, name: &str) -> &'a MirFunction {
+    module
+        .functions
+        .iter()
+        .find(|function| function.name == name)
+        .unwrap_or_else(|| panic!("関数 {name} が見つかりません"))
+}
+
+fn body_kind(function: &MirFunction) -> &MirExprKind {
+    &function.exprs[function.body].kind
+}
+
+fn body_int(function: &MirFunction) -> Option<i64> {
+    match body_kind(function) {
+        MirExprKind::Literal(literal) => match literal.value {
+            LiteralKind::Int { value, .. } => Some(value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn assert_ids_dense(module: &MirModule) {
+    for function in &module.functions {
+        for (index, expr) in function.exprs.iter().enumerate() {
+            assert_eq!(
+                expr.id, index,
+                "{} の式 ID が連番ではありません",
+                function.name
+            );
+        }
+        assert!(function.body < function.exprs.len());
+    }
+}
+
+#[test]
+fn block_tail_is_lowered_once() {
+    let module = lower("fn f(y: Int) -> Int {\n  let x = y\n  x + 1\n}\n");
+    let MirExprKind::Block {
+        statements,
+        tail: Some(_),
+        ..
+    } = body_kind(function(&module, "f"))
+    else {
+        panic!("本体は末尾式付きのブロックのはず");
+    };
+    assert_eq!(statements.len(), 1);
+    assert!(matches!(statements[0].kind, MirStmtKind::Let { .. }));
+}
+
+#[test]
+fn folds_constants_through_immutable_bindings() {
+    let source = "fn main() -> Int {\n  let x = 2 + 3\n  let y = -x\n  y * 4\n}\n";
+    let (module, report) = optimize(source, OptimizationLevel::O1);
+    let main = function(&module, "main");
+    assert_eq!(body_int(main), Some(-20), "{}", render_mir_function(main));
+    assert_eq!(main.exprs.len(), 1);
+    assert!(report.stats(MirPass::ConstantFolding).unwrap().rewrites > 0);
+    assert!(report.stats(MirPass::DeadCodeElimination).unwrap().rewrites > 0);
+    assert!(report.exprs_after < report.exprs_before);
+}
+
+#[test]
+fn does_not_fold_integers_of_other_widths() {
+    let mut module = lower("fn f() -> Int = 3000000000 + 3000000000\n");
+    let f = module
+        .functions
+        .iter_mut()
+        .find(|function| function.name == "f")
+        .expect("関数 f");
+    // i64 の検査付き演算で畳み込むと 32 ビット符号なし整数の折り返しが失われる。
+    for expr in &mut f.exprs {
+        expr.ty = "UInt".into();
+    }
+    optimize_module(&mut module, MirOptOptions::new(OptimizationLevel::O1));
+    let f = function(&module, "f");
+    assert!(
+        matches!(body_kind(f), MirExprKind::Binary { operator, .. } if operator == "+"),
+        "{}",
+        render_mir_function(f)
+    );
+}
+
+#[test]
+fn folds_constant_conditions_and_short_circuits() {
+    let source = "fn pick(a: Int) -> Int = if 1 < 2 && true then a else 0\n";
+    let (module, _) = optimize(source, OptimizationLevel::O1);
+    let pick = function(&module, "pick");
+    assert!(
+        matches!(body_kind(pick), MirExprKind::Identifier { ident } if ident.name == "a"),
+        "{}",
+        render_mir_function(pick)
+    );
+}
+
+#[test]
+fn keeps_division_by_zero_for_runtime() {
+    let source = "fn main() -> Int = 1 / 0\n";
+    let (module, _) = optimize(source, OptimizationLevel::O2);
+    let main = function(&module, "main");
+    assert!(matches!(body_kind(main), MirExprKind::Binary { operator, .. } if operator == "/"));
+}
+
+#[test]
+fn propagates_copies_to_the_original_binding() {
+    let source = "fn inc(a: Int) -> Int {\n  let b = a\n  b + 1\n}\n";
+    let (module, report) = optimize(source, OptimizationLevel::O1);
+    let inc = function(&module, "inc");
+    assert_eq!(
+        render_mir_function(inc),
+        "fn inc(a: i64) -> i64 = (a + 1)\n"
+    );
+    assert!(report.stats(MirPass::CopyPropagation).unwrap().rewrites > 0);
+}
+
+#[test]
+fn does_not_propagate_copies_of_mutable_variables() {
+    let source =
+        "fn snapshot(a: Int) -> Int {\n  var c: Int = a\n  let b = c\n  c := 5\n  b + c\n}\n";
+    let (module, _) = optimize(source, OptimizationLevel::O2);
+    let rendered = render_mir_function(function(&module, "snapshot"));
+    assert!(rendered.contains("let b = c"), "{rendered}");
+    assert!(rendered.contains("(b + c)"), "{rendered}");
+}
+
+#[test]
+fn inlines_small_non_recursive_functions() {
+    let source = "fn add(a: Int, b: Int) -> Int = a + b\nfn main() -> Int = add(2, 3)\n";
+    let (module, report) = optimize(source, OptimizationLevel::O2);
+    let main = function(&module, "main");
+    assert_eq!(body_int(main), Some(5), "{}", render_mir_function(main));
+    let inlining = report.stats(MirPass::Inlining).unwrap();
+    assert_eq!(inlining.rewrites, 1);
+    assert_ids_dense(&module);
+
+    let (module, report) = optimize(source, OptimizationLevel::O1);
+    assert!(matches!(
+        body_kind(function(&module, "main")),
+        MirExprKind::Call { .. }
+    ));
+    assert!(report.stats(MirPass::Inlining).is_none());
+}
+
+#[test]
+fn does_not_inline_recursive_functions() {
+    let source = "fn fact(n: Int) -> Int = if n <= 1 then 1 else n * fact(n - 1)\n\
+                  fn main() -> Int = fact(5)\n";
+    let (module, report) = optimize(source, OptimizationLevel::O3);
+    assert!(matches!(
+        body_kind(function(&module, "main")),
+        MirExprKind::Call { .. }
+    ));
+    assert_eq!(report.stats(MirPass::Inlining).unwrap().rewrites, 0);
+}
+
+#[test]
+fn inlined_parameters_do_not_capture_caller_bindings() {
+    let source = "fn sub(a: Int, b: Int) -> Int = a - b\n\
+                  fn main(b: Int, a: Int) -> Int = sub(b, a)\n";
+    let (module, _) = optimize(source, OptimizationLevel::O2);
+    let rendered = render_mir_function(function(&module, "main"));
+    assert_eq!(rendered, "fn main(b: i64, a: i64) -> i64 = (b - a)\n");
+}
+
+#[test]
+fn prunes_match_arms_for_known_scrutinee() {
+    let source = "fn main() -> Int =\n  match 2 with\n  | 1 -> 10\n  | 2 -> 20\n  | _ -> 30\n";
+    let (module, report) = optimize(source, OptimizationLevel::O1);
+    let main = function(&module, "main");
+    assert_eq!(body_int(main), Some(20), "{}", render_mir_function(main));
+    assert!(report.stats(MirPass::MatchArmPruning).unwrap().rewrites > 0);
+}
+
+#[test]
+fn keeps_guarded_arms_when_pruning() {
+    let source = "fn pick(flag: Bool) -> Int =\n  match 1 with\n  | 0 -> 5\n  | 1 when flag -> 10\n  | _ -> 30\n";
+    let (module, _) = optimize(source, OptimizationLevel::O1);
+    let pick = function(&module, "pick");
+    match body_kind(pick) {
+        MirExprKind::Match { arms, lowering, .. } => {
+            assert_eq!(arms.len(), 2);
+            assert_eq!(lowering.arm_count, 2);
+            assert_eq!(lowering.arms.len(), 2);
+        }
+        other => panic!("match が残っていません: {other:?}"),
+    }
+}
+
+#[test]
+fn merges_nested_blocks() {
+    let source = "fn main(a: Int) -> Int {\n  {\n    let b = a * 2\n    b + 1\n  }\n}\n";
+    let (module, report) = optimize(source, OptimizationLevel::O1);
+    let rendered = render_mir_function(function(&module, "main"));
+    assert_eq!(
+        rendered,
+        "fn main(a: i64) -> i64 = {\n  let b = (a * 2)\n  (b + 1)\n}\n"
+    );
+    assert!(report.stats(MirPass::BlockMerging).unwrap().rewrites > 0);
+}
+
+#[test]
+fn o0_leaves_module_untouched() {
+    let source = "fn main() -> Int = 1 + 2\n";
+    let before = lower(source);
+    let (module, report) = optimize(source, OptimizationLevel::O0);
+    assert!(
+        MirPassManager::new(MirOptOptions::new(OptimizationLevel::O0))
+            .passes()
+            .is_empty()
+    );
+    assert_eq!(report.rounds, 0);
+    assert!(report.passes.is_empty());
+    assert_eq!(
+        render_mir_function(function(&module, "main")),
+        render_mir_function(function(&before, "main"))
+    );
+}
+
+#[test]
+fn records_before_and_after_dumps_per_pass() {
+    let source = "fn main() -> Int = 1 + 2\n";
+    let (module, report) = optimize(source, OptimizationLevel::O2);
+    let dump = report
+        .dumps
+        .iter()
+        .find(|dump| dump.pass == MirPass::ConstantFolding)
+        .expect("定数畳み込みのダンプ");
+    assert_eq!(dump.function, "main");
+    assert_eq!(dump.round, 1);
+    assert!(dump.before.contains("(1 + 2)"));
+    assert!(dump.after.contains("= 3"));
+
+    let json = serde_json::to_value(&module).unwrap();
+    let optimization = &json["optimization"];
+    assert_eq!(optimization["level"], "O2");
+    assert!(optimization["passes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|pass| pass["pass"] == "constant_folding" && pass["rewrites"] == 1));
+}
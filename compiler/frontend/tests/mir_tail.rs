@@ -0,0 +1,98 @@
+use reml_frontend::parser::ParserDriver;
+use reml_frontend::semantics::mir::{MirFunction, MirModule};
+use reml_frontend::semantics::mir_tail::{analyze_tail_calls, MirTailCallKind};
+use reml_frontend::typeck::{TypecheckConfig, TypecheckDriver, TypecheckReport};
+
+fn typecheck(source: &str) -> TypecheckReport {
+    let result = ParserDriver::parse(source);
+    assert!(
+        result.diagnostics.is_empty(),
+        "parser diagnostics: {:?}",
+        result
+            .diagnostics
+            .iter()
+            .map(|diag| &diag.message)
+            .collect::<Vec<_>>()
+    );
+    let module = result.value.expect("AST");
+    TypecheckDriver::infer_module(Some(&module), &TypecheckConfig::default())
+}
+
+fn function<'a>(module: &'a MirModule, name: &str) -> &'a MirFunction {
+    module
+        .functions
+        .iter()
+        .find(|function| function.name == name)
+        .unwrap_or_else(|| panic!("関数 {name} が見つかりません"))
+}
+
+fn tail_kinds(function: &MirFunction) -> Vec<(Option<&str>, MirTailCallKind)> {
+    function
+        .tail_calls
+        .iter()
+        .map(|call| (call.callee.as_deref(), call.kind))
+        .collect()
+}
+
+#[test]
+fn tail_calls_are_classified_by_callee() {
+    let source = r#"
+fn sum(n: Int, acc: Int) -> Int =
+  if n == 0 then acc else sum(n - 1, acc + n)
+
+fn len(xs: [Int], acc: Int) -> Int =
+  match xs with
+  | [] -> acc
+  | [_, ..rest] -> len(rest, acc + 1)
+
+fn fact(n: Int) -> Int =
+  if n == 0 then 1 else n * fact(n - 1)
+
+fn apply(f: (Int) -> Int, x: Int) -> Int = f(x)
+
+fn main() -> Int = sum(10, 0)
+"#;
+    let report = typecheck(source);
+    let mir = &report.mir;
+    assert_eq!(
+        tail_kinds(function(mir, "sum")),
+        vec![(Some("sum"), MirTailCallKind::SelfRecursive)]
+    );
+    assert_eq!(
+        tail_kinds(function(mir, "len")),
+        vec![(Some("len"), MirTailCallKind::SelfRecursive)]
+    );
+    assert_eq!(
+        tail_kinds(function(mir, "apply")),
+        vec![(Some("f"), MirTailCallKind::Indirect)]
+    );
+    assert_eq!(
+        tail_kinds(function(mir, "main")),
+        vec![(Some("sum"), MirTailCallKind::Direct)]
+    );
+
+    let fact = analyze_tail_calls(function(mir, "fact"));
+    assert!(!fact.has_self_tail_call());
+    assert_eq!(fact.non_tail_self_calls.len(), 1);
+}
+
+#[test]
+fn tailrec_reports_non_tail_self_calls() {
+    let source = r#"
+@tailrec
+fn sum(n: Int, acc: Int) -> Int =
+  if n == 0 then acc else sum(n - 1, acc + n)
+
+@tailrec
+fn fact(n: Int) -> Int =
+  if n == 0 then 1 else n * fact(n - 1)
+"#;
+    let report = typecheck(source);
+    let violations: Vec<_> = report
+        .violations
+        .iter()
+        .filter(|violation| violation.code == "typeck.tailrec.non_tail_call")
+        .collect();
+    assert_eq!(violations.len(), 1, "fact の自己呼び出しだけを報告するはず");
+    assert_eq!(violations[0].function.as_deref(), Some("fact"));
+}
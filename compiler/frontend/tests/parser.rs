@@ -11,3 +11,6 @@ mod defer;
 
 #[path = "parser/qualified_fn_decl.rs"]
 mod qualified_fn_decl;
+
+#[path = "parser/localized_diagnostics.rs"]
+mod localized_diagnostics;
@@ -0,0 +1,59 @@
+use reml_frontend::parser::{ParserDriver, ParserOptions, RunConfig};
+
+fn parse_with_locale(source: &str, locale: &'static str) -> Vec<(String, String, Vec<String>)> {
+    let run_config = RunConfig::default();
+    let options = ParserOptions::from_run_config(&run_config).with_diagnostic_locale(locale);
+    ParserDriver::parse_with_options_and_run_config(source, options, run_config)
+        .diagnostics
+        .into_iter()
+        .map(|diag| {
+            let notes = diag.notes.iter().map(|note| note.message.clone()).collect();
+            (diag.code.unwrap_or_default(), diag.message, notes)
+        })
+        .collect()
+}
+
+fn find<'a>(
+    diagnostics: &'a [(String, String, Vec<String>)],
+    code: &str,
+) -> &'a (String, String, Vec<String>) {
+    diagnostics
+        .iter()
+        .find(|(candidate, _, _)| candidate == code)
+        .unwrap_or_else(|| panic!("{code} が見つかりません: {diagnostics:?}"))
+}
+
+#[test]
+fn lexer_and_parser_diagnostics_follow_requested_locale() {
+    let source = "fn broken() = ¤\n";
+    let en = parse_with_locale(source, "en-US");
+    let (_, message, notes) = find(&en, "parser.lexer.unknown_token");
+    assert_eq!(message, "Unknown token detected");
+    assert_eq!(notes, &vec!["Skipping the unknown token".to_string()]);
+    let (_, message, notes) = find(&en, "parser.syntax.expected_tokens");
+    assert_eq!(message, "Syntax error: unable to parse the input");
+    assert!(
+        notes.iter().all(|note| note.is_ascii()),
+        "英語ロケールのノートに未翻訳の文言があります: {notes:?}"
+    );
+
+    let ja = parse_with_locale(source, "ja-JP");
+    let (_, message, notes) = find(&ja, "parser.lexer.unknown_token");
+    assert_eq!(message, "未定義のトークンを検出しました");
+    assert_eq!(notes, &vec!["未定義のトークンをスキップします".to_string()]);
+    let (_, message, _) = find(&ja, "parser.syntax.expected_tokens");
+    assert_eq!(message, "構文エラー: 入力を解釈できません");
+}
+
+#[test]
+fn fixed_parser_diagnostics_follow_requested_locale() {
+    let source = "fn main() = 1\n1 + 2\n";
+    let en = parse_with_locale(source, "en-US");
+    let (_, message, notes) = find(&en, "parser.top_level_expr.disallowed");
+    assert_eq!(message, "Top-level expressions are not allowed.");
+    assert!(notes[0].starts_with("Wrap the expression in a `fn`"));
+
+    let ja = parse_with_locale(source, "ja-JP");
+    let (_, message, _) = find(&ja, "parser.top_level_expr.disallowed");
+    assert_eq!(message, "トップレベル式は許可されていません。");
+}
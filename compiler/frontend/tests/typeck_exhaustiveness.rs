@@ -107,3 +107,61 @@ fn classify(x: Foo) -> Int =
         "合成型の全分岐がある場合は網羅性診断が不要"
     );
 }
+
+fn match_lowering_plans(report: &TypecheckReport) -> Vec<serde_json::Value> {
+    let mir = serde_json::to_value(&report.mir).expect("MIR を JSON 化できるはず");
+    let mut plans = Vec::new();
+    let mut stack = vec![&mir];
+    while let Some(value) = stack.pop() {
+        match value {
+            serde_json::Value::Object(map) => {
+                if let Some(lowering) = map.get("lowering") {
+                    plans.push(lowering.clone());
+                }
+                stack.extend(map.values());
+            }
+            serde_json::Value::Array(items) => stack.extend(items),
+            _ => {}
+        }
+    }
+    plans
+}
+
+#[test]
+fn match_lowering_plan_records_constructor_order_and_coverage() {
+    let source = r#"
+type Foo = | Bar(Int) | Baz
+fn classify(x: Foo) -> Int =
+  match x with
+  | Baz -> 0
+  | Bar(_) -> 1
+"#;
+    let module = parse_module(source);
+    let report = TypecheckDriver::infer_module(Some(&module), &TypecheckConfig::default());
+    let plans = match_lowering_plans(&report);
+    assert_eq!(plans.len(), 1);
+    assert_eq!(plans[0]["exhaustive"], serde_json::json!(true));
+    assert_eq!(
+        plans[0]["constructors"],
+        serde_json::json!(["Bar", "Baz"]),
+        "コンストラクタは宣言順（タグ値の順）で記録されるはず"
+    );
+}
+
+#[test]
+fn refutable_payload_pattern_is_not_lowering_exhaustive() {
+    let source = r#"
+fn pick(x: Option<Int>) -> Int =
+  match x with
+  | Some(0) -> 0
+  | None -> 1
+"#;
+    let module = parse_module(source);
+    let report = TypecheckDriver::infer_module(Some(&module), &TypecheckConfig::default());
+    let plans = match_lowering_plans(&report);
+    assert_eq!(plans.len(), 1);
+    assert!(
+        plans[0].get("exhaustive").is_none(),
+        "引数に反駁可能なパターンがある腕は既定分岐を省く根拠にならないはず"
+    );
+}
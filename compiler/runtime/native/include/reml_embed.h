@@ -5,10 +5,22 @@
  * reml_embed.h — 埋め込み API (Phase 4)
  *
  * Rust 実装の `reml_create_context` / `reml_load_module` / `reml_run` /
- * `reml_dispose_context` を C から呼び出す最小 ABI を提供する。
+ * `reml_dispose_context` を C から呼び出す ABI を提供する。
+ *
+ * `reml_load_module` はソースをフロントエンドで wasm へコンパイルし、`reml_run` /
+ * `reml_call` はそれを wasmtime 上で実行する。コンパイラはフロントエンド
+ * （libreml_frontend）が提供するため、ロード前に `reml_embed_install_frontend` を呼ぶ。
+ *
+ * 値 (`reml_embed_value_t`) の所有権は reml_ffi_bridge.h と同じ規則に従う。
+ * - Borrowed: 受け取った側は参照を増やさずに使い、保持するなら `reml_embed_value_inc_ref` する。
+ * - Transferred: 受け取った側が `reml_embed_value_dec_ref` で手放す。
+ *
+ * 失敗時の `reml_last_error` は構造化診断の JSON 文字列
+ * （`{"severity", "code", "message", "span"?, "diagnostics"?}`）を返す。
  */
 
 #include <stddef.h>
+#include <stdint.h>
 
 #ifdef __cplusplus
 extern "C" {
@@ -24,6 +36,32 @@ typedef enum {
     REML_EMBED_STATUS_INVALID_ARGUMENT = 4
 } reml_embed_status_t;
 
+/** ホストと Reml の間で受け渡す参照カウント付きの不変値。 */
+typedef struct reml_embed_value_t reml_embed_value_t;
+
+typedef enum {
+    REML_EMBED_VALUE_UNIT = 0,
+    REML_EMBED_VALUE_INT = 1,
+    REML_EMBED_VALUE_FLOAT = 2,
+    REML_EMBED_VALUE_BOOL = 3,
+    REML_EMBED_VALUE_STRING = 4,
+    REML_EMBED_VALUE_ARRAY = 5,
+    REML_EMBED_VALUE_RECORD = 6
+} reml_embed_value_kind_t;
+
+/**
+ * ホスト関数。`args` は Borrowed、戻り値は Transferred（新しい参照）で返す。
+ * NULL を返すと呼び出しは `native.embed.host_call_failed` で失敗する。
+ */
+typedef reml_embed_value_t* (*reml_embed_host_function_t)(
+    void* user_data,
+    const reml_embed_value_t* const* args,
+    size_t argc
+);
+
+/** フロントエンドを埋め込みモジュールコンパイラとして登録する（libreml_frontend が提供）。 */
+reml_embed_status_t reml_embed_install_frontend(void);
+
 reml_embed_status_t reml_create_context(
     const char* abi_version,
     reml_embed_context_t** out_context
@@ -35,15 +73,88 @@ reml_embed_status_t reml_load_module(
     size_t length
 );
 
+/**
+ * ホスト関数を登録する。`signature` は `(Int, Str) -> [Int]` 形式で、
+ * Unit / Int / Float / Bool / Str / [T] / {name: T, ...} を使える。
+ * 同名の extern 宣言へ結び付けられ、宣言とシグネチャが合わなければ実行時に失敗する。
+ */
+reml_embed_status_t reml_register_function(
+    reml_embed_context_t* context,
+    const char* name,
+    const char* signature,
+    reml_embed_host_function_t function,
+    void* user_data
+);
+
 reml_embed_status_t reml_run(
     reml_embed_context_t* context,
     const char* entrypoint
 );
 
+/**
+ * エントリポイントを引数付きで呼び出す。`args` は Borrowed。
+ * `out_result` には Transferred の戻り値を書き込む（NULL なら戻り値を捨てる）。
+ */
+reml_embed_status_t reml_call(
+    reml_embed_context_t* context,
+    const char* entrypoint,
+    const reml_embed_value_t* const* args,
+    size_t argc,
+    reml_embed_value_t** out_result
+);
+
 reml_embed_status_t reml_dispose_context(reml_embed_context_t* context);
 
 const char* reml_last_error(const reml_embed_context_t* context);
 
+/* 値の構築（いずれも Transferred。失敗時は NULL） */
+reml_embed_value_t* reml_embed_value_unit(void);
+reml_embed_value_t* reml_embed_value_int(int64_t value);
+reml_embed_value_t* reml_embed_value_float(double value);
+reml_embed_value_t* reml_embed_value_bool(int value);
+/** UTF-8 のバイト列から文字列を作る。 */
+reml_embed_value_t* reml_embed_value_string(const char* data, size_t length);
+/** 要素は Borrowed で受け取り、配列が参照を保持する。 */
+reml_embed_value_t* reml_embed_value_array(
+    const reml_embed_value_t* const* items,
+    size_t length
+);
+/** フィールド値は Borrowed で受け取り、レコードが参照を保持する。 */
+reml_embed_value_t* reml_embed_value_record(
+    const char* const* names,
+    const reml_embed_value_t* const* values,
+    size_t length
+);
+
+void reml_embed_value_inc_ref(const reml_embed_value_t* value);
+void reml_embed_value_dec_ref(const reml_embed_value_t* value);
+
+/* 値の参照（返すポインタはいずれも Borrowed で、元の値が生きている間だけ有効） */
+reml_embed_value_kind_t reml_embed_value_kind(const reml_embed_value_t* value);
+int64_t reml_embed_value_as_int(const reml_embed_value_t* value);
+double reml_embed_value_as_float(const reml_embed_value_t* value);
+int reml_embed_value_as_bool(const reml_embed_value_t* value);
+/** NUL 終端ではない UTF-8 バイト列を返し、`out_length` にバイト数を書き込む。 */
+const char* reml_embed_value_as_string(
+    const reml_embed_value_t* value,
+    size_t* out_length
+);
+/** 配列の要素数・レコードのフィールド数・文字列のバイト数。 */
+size_t reml_embed_value_length(const reml_embed_value_t* value);
+const reml_embed_value_t* reml_embed_value_array_get(
+    const reml_embed_value_t* value,
+    size_t index
+);
+const reml_embed_value_t* reml_embed_value_record_get(
+    const reml_embed_value_t* value,
+    const char* name
+);
+const char* reml_embed_value_record_field_name(
+    const reml_embed_value_t* value,
+    size_t index,
+    size_t* out_length
+);
+
 #ifdef __cplusplus
 }
 #endif
@@ -151,6 +151,33 @@ reml_ffi_bridge_metrics_t reml_ffi_bridge_get_metrics(void);
  */
 double reml_ffi_bridge_pass_rate(void);
 
+/* ========== コールバック API（Rust ランタイム提供） ========== */
+
+/**
+ * コンパイル済みクロージャを C の関数ポインタとして登録する。
+ *
+ * env / code_ptr は `reml_closure_env` / `reml_closure_code_ptr` の値を渡す。
+ * 返した関数ポインタが呼ばれると、code_ptr を env を先頭引数として呼び出す。
+ * signature_json は `FfiFnSig` の JSON（例:
+ * `{"params":["I32","I32"],"returns":"I32","variadic":false}`）。
+ *
+ * 返した関数ポインタの参照は呼び出し側が 1 つ持ち、不要になったら
+ * `reml_ffi_callback_release` で手放す。env は登録中有効でなければならない。
+ * 可変長引数・スタック渡しの引数を持つシグネチャは登録できず NULL を返す。
+ */
+const void* reml_ffi_callback_new(void* env, const void* code_ptr, const char* signature_json);
+
+/**
+ * コールバックの参照を 1 つ増やす。登録されていない関数ポインタなら -1 を返す。
+ */
+int reml_ffi_callback_retain(const void* callback);
+
+/**
+ * コールバックの参照を 1 つ手放す。最後の参照が解放されるとスロットが空き、
+ * 以後の呼び出しは 0 を返す。登録されていない関数ポインタなら -1 を返す。
+ */
+int reml_ffi_callback_release(const void* callback);
+
 /**
  * 便宜用の成功記録ヘルパ。
  */
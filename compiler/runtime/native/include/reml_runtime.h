@@ -137,6 +137,17 @@ typedef struct {
  */
 void* reml_record_from(int64_t field_count, ...);
 
+/**
+ * Record の値スロットを取得する
+ *
+ * @param record_ptr Record オブジェクトへのポインタ
+ * @param index 正規化順のスロット番号
+ * @return スロットの値（借用参照。呼び出し側は dec_ref しない）
+ *
+ * @note クロージャ環境のキャプチャ読み出しにも用いる。
+ */
+void* reml_record_get(void* record_ptr, int64_t index);
+
 /* ========== Array API（Phase 3） ========== */
 
 /**
@@ -180,6 +191,61 @@ void* reml_closure_env(void* closure_ptr);
  */
 void* reml_closure_code_ptr(void* closure_ptr);
 
+/* ========== Effect Handler API（Phase 4） ========== */
+
+/**
+ * 効果ハンドラフレームを生成する
+ *
+ * @param effect 効果名（NULL 終端文字列、`handler` が対象とする効果）
+ * @param return_clause return 節のクロージャ（NULL の場合は値をそのまま返す）
+ * @return ハンドラフレーム（不透明ポインタ）
+ *
+ * @note 生成したフレームは `reml_handle` が所有権を引き取り、完了時に解放する。
+ */
+void* reml_handler_new(const char* effect, void* return_clause);
+
+/**
+ * ハンドラフレームに操作節を登録する
+ *
+ * @param handler `reml_handler_new` で生成したフレーム
+ * @param op 操作名（NULL 終端文字列）
+ * @param clause 操作節のクロージャ（`void*(env, arg, k)`）
+ * @param tail_resumptive 非 0 の場合、節を perform 側のスタックで直接呼び出す
+ */
+void reml_handler_add_op(void* handler, const char* op, void* clause, int32_t tail_resumptive);
+
+/**
+ * ハンドラフレームを導入して本体クロージャを実行する
+ *
+ * @param handler ハンドラフレーム
+ * @param body 本体クロージャ（`void*(env)`）
+ * @return return 節適用後の値、または操作節の戻り値（ボックス化済み）
+ */
+void* reml_handle(void* handler, void* body);
+
+/**
+ * 効果操作を発火する
+ *
+ * @param effect 効果名
+ * @param op 操作名
+ * @param arg 操作引数（ボックス化済み）
+ * @return resume で渡された値
+ *
+ * @note 対応するハンドラが存在しない場合は panic する。
+ */
+void* reml_perform(const char* effect, const char* op, void* arg);
+
+/**
+ * 中断した計算を再開する（one-shot）
+ *
+ * @param k 操作節に渡された継続（tail-resumptive 節では NULL）
+ * @param value perform の結果として渡す値（ボックス化済み）
+ * @return 再開した計算（ハンドラ適用後）の値
+ *
+ * @note 同一の継続を 2 回以上再開すると panic する。
+ */
+void* reml_resume(void* k, void* value);
+
 /* ========== 参照カウント対象の区分（Phase 3） ========== */
 
 /**
@@ -0,0 +1,368 @@
+/**
+ * effects.c - 効果ハンドラのハンドラフレームと one-shot 継続
+ *
+ * `handle ... with handler` で囲まれた計算を専用スタック（ファイバー）上で
+ * 実行し、`perform` 時にハンドラ側へ制御を戻す。継続は「中断したファイバー」
+ * そのものであり、`resume` で一度だけ再開できる（one-shot）。
+ *
+ * 値は全てボックス化済みの `void*` としてランタイムを横断する。
+ * クロージャの code_ptr は以下のシグネチャを想定する。
+ *   - 本体:        void* (*)(void* env)
+ *   - 操作節:      void* (*)(void* env, void* arg, void* k)
+ *   - return 節:   void* (*)(void* env, void* value)
+ *
+ * 末尾 resume の操作節（tail-resumptive）はファイバーを切り替えずに
+ * perform 側のスタック上で直接呼び出し、その戻り値を perform の結果とする。
+ *
+ * Windows では現時点でファイバー切り替えを提供せず、本体は呼び出し元の
+ * スタックで実行する。この場合 tail-resumptive 以外の操作は panic となる。
+ */
+
+#if !defined(_WIN32) && !defined(_WIN64)
+#ifndef _XOPEN_SOURCE
+#define _XOPEN_SOURCE 700
+#endif
+#endif
+
+#include "../include/reml_runtime.h"
+#include <stdlib.h>
+#include <string.h>
+
+#ifdef REML_PLATFORM_POSIX
+#include <ucontext.h>
+#endif
+
+/** ファイバー 1 本あたりのスタックサイズ（バイト） */
+#define REML_EFFECT_STACK_SIZE (256 * 1024)
+
+typedef void* (*reml_effect_body_fn)(void* env);
+typedef void* (*reml_effect_clause_fn)(void* env, void* arg, void* k);
+typedef void* (*reml_effect_return_fn)(void* env, void* value);
+
+typedef struct {
+    const char* name;
+    void* clause;
+    int tail_resumptive;
+} reml_handler_op_t;
+
+typedef enum {
+    REML_FIBER_READY,
+    REML_FIBER_SUSPENDED,
+    REML_FIBER_DONE,
+} reml_fiber_status_t;
+
+typedef struct reml_handler reml_handler_t;
+
+typedef struct {
+#ifdef REML_PLATFORM_POSIX
+    ucontext_t ctx;     ///< ファイバー側の再開位置
+    ucontext_t caller;  ///< run ループ側の再開位置
+    void* stack;
+#endif
+    reml_fiber_status_t status;
+    void* body;               ///< 本体クロージャ
+    void* value;              ///< 完了値 or resume で受け渡す値
+    size_t pending_op;        ///< perform された操作の添字
+    void* pending_arg;        ///< perform の引数
+    reml_handler_t* saved_top;  ///< 中断時点のハンドラチェーン先頭
+} reml_fiber_t;
+
+typedef enum {
+    REML_CONT_PENDING,
+    REML_CONT_RESUMED,
+    REML_CONT_DROPPED,
+} reml_cont_status_t;
+
+typedef struct reml_continuation {
+    reml_handler_t* handler;
+    reml_cont_status_t status;
+    struct reml_continuation* next;
+} reml_continuation_t;
+
+struct reml_handler {
+    const char* effect;
+    void* return_clause;
+    reml_handler_op_t* ops;
+    size_t op_count;
+    size_t op_capacity;
+    reml_handler_t* parent;
+    reml_fiber_t* fiber;
+    reml_continuation_t* continuations;
+};
+
+/** 現在のスレッドで有効なハンドラチェーンの先頭 */
+static REML_THREAD_LOCAL reml_handler_t* reml_handler_top = NULL;
+#ifdef REML_PLATFORM_POSIX
+/** makecontext へポインタを渡すための受け渡し領域 */
+static REML_THREAD_LOCAL reml_fiber_t* reml_fiber_bootstrap = NULL;
+#endif
+
+static void* reml_effect_call_body(void* closure) {
+    reml_effect_body_fn code = (reml_effect_body_fn)(uintptr_t)reml_closure_code_ptr(closure);
+    return code(reml_closure_env(closure));
+}
+
+static void* reml_effect_call_clause(void* closure, void* arg, void* k) {
+    reml_effect_clause_fn code = (reml_effect_clause_fn)(uintptr_t)reml_closure_code_ptr(closure);
+    return code(reml_closure_env(closure), arg, k);
+}
+
+static void* reml_effect_call_return(reml_handler_t* handler, void* value) {
+    if (handler->return_clause == NULL) {
+        return value;
+    }
+    reml_effect_return_fn code =
+        (reml_effect_return_fn)(uintptr_t)reml_closure_code_ptr(handler->return_clause);
+    return code(reml_closure_env(handler->return_clause), value);
+}
+
+static void reml_handler_free(reml_handler_t* handler) {
+    reml_continuation_t* cont = handler->continuations;
+    while (cont != NULL) {
+        reml_continuation_t* next = cont->next;
+        free(cont);
+        cont = next;
+    }
+    free(handler->ops);
+    free(handler);
+}
+
+void* reml_handler_new(const char* effect, void* return_clause) {
+    if (effect == NULL) {
+        panic("handler effect name is null");
+    }
+    reml_handler_t* handler = (reml_handler_t*)calloc(1, sizeof(reml_handler_t));
+    if (handler == NULL) {
+        panic("failed to allocate effect handler");
+    }
+    handler->effect = effect;
+    handler->return_clause = return_clause;
+    return handler;
+}
+
+void reml_handler_add_op(void* handler_ptr, const char* op, void* clause, int32_t tail_resumptive) {
+    reml_handler_t* handler = (reml_handler_t*)handler_ptr;
+    if (handler == NULL || op == NULL || clause == NULL) {
+        panic("handler operation registration received null");
+    }
+    if (handler->op_count == handler->op_capacity) {
+        size_t capacity = handler->op_capacity == 0 ? 4 : handler->op_capacity * 2;
+        reml_handler_op_t* ops =
+            (reml_handler_op_t*)realloc(handler->ops, capacity * sizeof(reml_handler_op_t));
+        if (ops == NULL) {
+            panic("failed to grow effect handler operations");
+        }
+        handler->ops = ops;
+        handler->op_capacity = capacity;
+    }
+    handler->ops[handler->op_count].name = op;
+    handler->ops[handler->op_count].clause = clause;
+    handler->ops[handler->op_count].tail_resumptive = tail_resumptive != 0;
+    handler->op_count++;
+}
+
+static int reml_handler_find_op(reml_handler_t* handler, const char* effect, const char* op,
+                                size_t* index) {
+    if (strcmp(handler->effect, effect) != 0) {
+        return 0;
+    }
+    for (size_t i = 0; i < handler->op_count; i++) {
+        if (strcmp(handler->ops[i].name, op) == 0) {
+            *index = i;
+            return 1;
+        }
+    }
+    return 0;
+}
+
+#ifdef REML_PLATFORM_POSIX
+
+static void reml_fiber_entry(void) {
+    reml_fiber_t* fiber = reml_fiber_bootstrap;
+    reml_fiber_bootstrap = NULL;
+    fiber->value = reml_effect_call_body(fiber->body);
+    fiber->status = REML_FIBER_DONE;
+    setcontext(&fiber->caller);
+    panic("effect fiber returned after completion");
+}
+
+static reml_fiber_t* reml_fiber_new(void* body) {
+    reml_fiber_t* fiber = (reml_fiber_t*)calloc(1, sizeof(reml_fiber_t));
+    if (fiber == NULL) {
+        panic("failed to allocate effect fiber");
+    }
+    fiber->stack = malloc(REML_EFFECT_STACK_SIZE);
+    if (fiber->stack == NULL) {
+        panic("failed to allocate effect fiber stack");
+    }
+    if (getcontext(&fiber->ctx) != 0) {
+        panic("getcontext failed for effect fiber");
+    }
+    fiber->ctx.uc_stack.ss_sp = fiber->stack;
+    fiber->ctx.uc_stack.ss_size = REML_EFFECT_STACK_SIZE;
+    fiber->ctx.uc_link = NULL;
+    makecontext(&fiber->ctx, reml_fiber_entry, 0);
+    fiber->status = REML_FIBER_READY;
+    fiber->body = body;
+    return fiber;
+}
+
+static void reml_fiber_free(reml_fiber_t* fiber) {
+    free(fiber->stack);
+    free(fiber);
+}
+
+/**
+ * ファイバーを再開し、完了するか次の perform が発生するまで実行する。
+ *
+ * 完了時は return 節を適用した値を返す。perform 時は継続を生成して
+ * 操作節を呼び出し、その戻り値を返す（ディープハンドラ）。
+ */
+static void* reml_handler_run(reml_handler_t* handler) {
+    reml_fiber_t* fiber = handler->fiber;
+    if (fiber->status == REML_FIBER_READY) {
+        reml_fiber_bootstrap = fiber;
+        reml_handler_top = handler;
+    } else {
+        reml_handler_top = fiber->saved_top;
+    }
+    if (swapcontext(&fiber->caller, &fiber->ctx) != 0) {
+        panic("swapcontext failed while entering effect fiber");
+    }
+    reml_handler_top = handler->parent;
+
+    if (fiber->status == REML_FIBER_DONE) {
+        void* value = fiber->value;
+        handler->fiber = NULL;
+        reml_fiber_free(fiber);
+        return reml_effect_call_return(handler, value);
+    }
+
+    reml_continuation_t* cont = (reml_continuation_t*)calloc(1, sizeof(reml_continuation_t));
+    if (cont == NULL) {
+        panic("failed to allocate continuation");
+    }
+    cont->handler = handler;
+    cont->status = REML_CONT_PENDING;
+    cont->next = handler->continuations;
+    handler->continuations = cont;
+
+    reml_handler_op_t* op = &handler->ops[fiber->pending_op];
+    void* result = reml_effect_call_clause(op->clause, fiber->pending_arg, cont);
+    if (cont->status == REML_CONT_PENDING) {
+        /* 継続が捨てられた場合は中断中のファイバーを破棄する */
+        cont->status = REML_CONT_DROPPED;
+        if (handler->fiber == fiber) {
+            handler->fiber = NULL;
+            reml_fiber_free(fiber);
+        }
+    }
+    return result;
+}
+
+void* reml_handle(void* handler_ptr, void* body) {
+    reml_handler_t* handler = (reml_handler_t*)handler_ptr;
+    if (handler == NULL || body == NULL) {
+        panic("handle received null handler or body");
+    }
+    handler->parent = reml_handler_top;
+    handler->fiber = reml_fiber_new(body);
+    void* result = reml_handler_run(handler);
+    reml_handler_top = handler->parent;
+    if (handler->fiber != NULL) {
+        reml_fiber_free(handler->fiber);
+    }
+    reml_handler_free(handler);
+    return result;
+}
+
+static void* reml_perform_suspend(reml_handler_t* handler, size_t op_index, void* arg) {
+    reml_fiber_t* fiber = handler->fiber;
+    if (fiber == NULL) {
+        panic("perform targets a handler without an active computation");
+    }
+    fiber->status = REML_FIBER_SUSPENDED;
+    fiber->pending_op = op_index;
+    fiber->pending_arg = arg;
+    fiber->saved_top = reml_handler_top;
+    if (swapcontext(&fiber->ctx, &fiber->caller) != 0) {
+        panic("swapcontext failed while suspending effect fiber");
+    }
+    return fiber->value;
+}
+
+void* reml_resume(void* k, void* value) {
+    if (k == NULL) {
+        /* tail-resumptive 節は継続を持たないため値をそのまま返す */
+        return value;
+    }
+    reml_continuation_t* cont = (reml_continuation_t*)k;
+    if (cont->status == REML_CONT_RESUMED) {
+        panic("continuation resumed more than once");
+    }
+    if (cont->status == REML_CONT_DROPPED) {
+        panic("continuation resumed after its handler clause returned");
+    }
+    cont->status = REML_CONT_RESUMED;
+    reml_handler_t* handler = cont->handler;
+    handler->parent = reml_handler_top;
+    handler->fiber->value = value;
+    void* result = reml_handler_run(handler);
+    reml_handler_top = handler->parent;
+    return result;
+}
+
+#else /* REML_PLATFORM_WINDOWS */
+
+void* reml_handle(void* handler_ptr, void* body) {
+    reml_handler_t* handler = (reml_handler_t*)handler_ptr;
+    if (handler == NULL || body == NULL) {
+        panic("handle received null handler or body");
+    }
+    handler->parent = reml_handler_top;
+    reml_handler_top = handler;
+    void* value = reml_effect_call_body(body);
+    reml_handler_top = handler->parent;
+    void* result = reml_effect_call_return(handler, value);
+    reml_handler_free(handler);
+    return result;
+}
+
+static void* reml_perform_suspend(reml_handler_t* handler, size_t op_index, void* arg) {
+    (void)handler;
+    (void)op_index;
+    (void)arg;
+    panic("non tail-resumptive effect operations are not supported on this platform");
+}
+
+void* reml_resume(void* k, void* value) {
+    if (k != NULL) {
+        panic("continuations are not supported on this platform");
+    }
+    return value;
+}
+
+#endif
+
+void* reml_perform(const char* effect, const char* op, void* arg) {
+    if (effect == NULL || op == NULL) {
+        panic("perform received null effect or operation");
+    }
+    for (reml_handler_t* handler = reml_handler_top; handler != NULL;
+         handler = handler->parent) {
+        size_t index = 0;
+        if (!reml_handler_find_op(handler, effect, op, &index)) {
+            continue;
+        }
+        if (handler->ops[index].tail_resumptive) {
+            /* 操作節はハンドラの外側の文脈で評価する */
+            reml_handler_t* saved_top = reml_handler_top;
+            reml_handler_top = handler->parent;
+            void* result = reml_effect_call_clause(handler->ops[index].clause, arg, NULL);
+            reml_handler_top = saved_top;
+            return result;
+        }
+        return reml_perform_suspend(handler, index, arg);
+    }
+    panic("unhandled effect operation");
+}
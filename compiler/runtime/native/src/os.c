@@ -1,3 +1,8 @@
+/* strnlen は POSIX.1-2008 の関数のため、-std=c11 でも宣言されるよう要求する。 */
+#if !defined(_WIN32) && !defined(_POSIX_C_SOURCE)
+#define _POSIX_C_SOURCE 200809L
+#endif
+
 #include "../include/reml_os.h"
 #include "../include/reml_platform.h"
 #include <errno.h>
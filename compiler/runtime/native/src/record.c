@@ -44,3 +44,17 @@ void* reml_record_from(int64_t field_count, ...) {
 
     return record;
 }
+
+void* reml_record_get(void* record_ptr, int64_t index) {
+    if (record_ptr == NULL) {
+        panic("record get target is null");
+    }
+    if (reml_get_type_tag(record_ptr) != REML_TAG_RECORD) {
+        panic("record get type tag mismatch");
+    }
+    reml_record_t* record = (reml_record_t*)record_ptr;
+    if (index < 0 || index >= record->field_count) {
+        panic("record get index out of bounds");
+    }
+    return record->values[index];
+}
@@ -0,0 +1,175 @@
+/**
+ * test_effects.c - 効果ハンドラランタイムのテストスイート
+ *
+ * reml_handle / reml_perform / reml_resume によるハンドラフレームの導入、
+ * one-shot 継続の再開、tail-resumptive 節の直接呼び出しを検証する。
+ */
+
+#include "../include/reml_runtime.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <stdint.h>
+
+/* ========== テストヘルパー ========== */
+
+static int test_count = 0;
+static int test_passed = 0;
+
+#define TEST(name) \
+    do { \
+        printf("  [%d] %s ... ", ++test_count, name); \
+        fflush(stdout); \
+    } while (0)
+
+#define PASS() \
+    do { \
+        printf("OK\n"); \
+        test_passed++; \
+    } while (0)
+
+#define FAIL(msg) \
+    do { \
+        printf("FAILED: %s\n", msg); \
+        exit(1); \
+    } while (0)
+
+#define ASSERT(cond, msg) \
+    do { \
+        if (!(cond)) { \
+            FAIL(msg); \
+        } \
+    } while (0)
+
+#define CLOSURE(fn) reml_closure_new(NULL, (void*)(uintptr_t)(fn))
+
+/* ========== テスト用クロージャ本体 ========== */
+
+static int clause_calls = 0;
+
+/* Counter.next を 2 回 perform して合計する */
+static void* body_sum_pair(void* env) {
+    (void)env;
+    int64_t a = reml_unbox_i64(reml_perform("Counter", "next", NULL));
+    int64_t b = reml_unbox_i64(reml_perform("Counter", "next", NULL));
+    return reml_box_i64(a + b);
+}
+
+/* operation next(_unit, resume) { resume(n) }（呼び出し毎に n を増やす） */
+static void* clause_next_resume(void* env, void* arg, void* k) {
+    (void)env;
+    (void)arg;
+    clause_calls++;
+    return reml_resume(k, reml_box_i64(clause_calls));
+}
+
+/* return value { value * 10 } */
+static void* return_times_ten(void* env, void* value) {
+    (void)env;
+    return reml_box_i64(reml_unbox_i64(value) * 10);
+}
+
+/* operation next(_unit, resume) { 42 }（継続を捨てて中断する） */
+static void* clause_next_abort(void* env, void* arg, void* k) {
+    (void)env;
+    (void)arg;
+    (void)k;
+    return reml_box_i64(42);
+}
+
+/* tail-resumptive 節: k は NULL で渡される */
+static void* clause_next_tail(void* env, void* arg, void* k) {
+    (void)env;
+    (void)arg;
+    ASSERT(k == NULL, "tail-resumptive clause should not receive a continuation");
+    clause_calls++;
+    return reml_resume(k, reml_box_i64(7));
+}
+
+/* Log.emit を perform してから外側の Counter.next を perform する */
+static void* body_nested_inner(void* env) {
+    (void)env;
+    int64_t logged = reml_unbox_i64(reml_perform("Log", "emit", reml_box_i64(5)));
+    int64_t next = reml_unbox_i64(reml_perform("Counter", "next", NULL));
+    return reml_box_i64(logged + next);
+}
+
+static void* clause_emit_double(void* env, void* arg, void* k) {
+    (void)env;
+    return reml_resume(k, reml_box_i64(reml_unbox_i64(arg) * 2));
+}
+
+static void* body_nested_outer(void* env) {
+    (void)env;
+    void* inner = reml_handler_new("Log", NULL);
+    reml_handler_add_op(inner, "emit", CLOSURE(clause_emit_double), 0);
+    return reml_handle(inner, CLOSURE(body_nested_inner));
+}
+
+/* ========== テストケース ========== */
+
+void test_handle_resume_with_return_clause(void) {
+    TEST("perform/resume と return 節の適用");
+
+    clause_calls = 0;
+    void* handler = reml_handler_new("Counter", CLOSURE(return_times_ten));
+    reml_handler_add_op(handler, "next", CLOSURE(clause_next_resume), 0);
+    void* result = reml_handle(handler, CLOSURE(body_sum_pair));
+    ASSERT(clause_calls == 2, "operation clause should run twice");
+    ASSERT(reml_unbox_i64(result) == 30, "(1 + 2) * 10 expected");
+
+    PASS();
+}
+
+void test_handle_abort_without_resume(void) {
+    TEST("resume しない操作節は handle 全体の値になる");
+
+    void* handler = reml_handler_new("Counter", CLOSURE(return_times_ten));
+    reml_handler_add_op(handler, "next", CLOSURE(clause_next_abort), 0);
+    void* result = reml_handle(handler, CLOSURE(body_sum_pair));
+    ASSERT(reml_unbox_i64(result) == 42, "aborted clause value expected");
+
+    PASS();
+}
+
+void test_tail_resumptive_clause(void) {
+    TEST("tail-resumptive 節は継続なしで直接呼び出される");
+
+    clause_calls = 0;
+    void* handler = reml_handler_new("Counter", NULL);
+    reml_handler_add_op(handler, "next", CLOSURE(clause_next_tail), 1);
+    void* result = reml_handle(handler, CLOSURE(body_sum_pair));
+    ASSERT(clause_calls == 2, "tail-resumptive clause should run twice");
+    ASSERT(reml_unbox_i64(result) == 14, "7 + 7 expected");
+
+    PASS();
+}
+
+void test_nested_handlers(void) {
+    TEST("内側ハンドラを越えて外側の操作を perform する");
+
+    clause_calls = 0;
+    void* outer = reml_handler_new("Counter", NULL);
+    reml_handler_add_op(outer, "next", CLOSURE(clause_next_resume), 0);
+    void* result = reml_handle(outer, CLOSURE(body_nested_outer));
+    ASSERT(clause_calls == 1, "outer clause should run once");
+    ASSERT(reml_unbox_i64(result) == 11, "5 * 2 + 1 expected");
+
+    PASS();
+}
+
+/* ========== メイン ========== */
+
+int main(void) {
+    printf("Running effect handler tests...\n\n");
+
+    test_handle_resume_with_return_clause();
+    test_handle_abort_without_resume();
+    test_tail_resumptive_clause();
+    test_nested_handlers();
+
+    printf("\n========================================\n");
+    printf("All %d tests passed!\n", test_passed);
+    printf("========================================\n");
+
+    return 0;
+}
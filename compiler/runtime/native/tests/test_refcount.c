@@ -184,6 +184,40 @@ void test_destructor_closure(void) {
     PASS();
 }
 
+/**
+ * Test 5b: クロージャ環境レコード
+ *
+ * Backend のクロージャ変換と同じ手順で環境を組み立て、
+ * reml_record_get で借用したキャプチャ値と env の寿命を確認。
+ */
+void test_closure_env_record(void) {
+    TEST("クロージャ環境レコード");
+
+    // キャプチャ値をボックス化し、環境レコードへ格納する
+    void* captured = reml_box_i64(42);
+    reml_object_header_t* captured_header = REML_GET_HEADER(captured);
+    void* env = reml_record_from(1, captured);
+    dec_ref(captured);
+    ASSERT(captured_header->refcount == 1, "captured refcount should be owned by env");
+
+    // クロージャ生成後は env の所有権をクロージャへ移す
+    void* closure = reml_closure_new(env, NULL);
+    reml_object_header_t* env_header = REML_GET_HEADER(env);
+    dec_ref(env);
+    ASSERT(env_header->refcount == 1, "env refcount should be 1 after ownership transfer");
+
+    // 持ち上げた関数のプロローグ相当の読み出し
+    void* slot = reml_record_get(reml_closure_env(closure), 0);
+    ASSERT(slot == captured, "record_get should return captured slot");
+    ASSERT(reml_unbox_i64(slot) == 42, "captured value should be 42");
+    ASSERT(captured_header->refcount == 1, "record_get should not inc_ref");
+
+    // クロージャの解放で env とキャプチャ値も解放される
+    dec_ref(closure);
+
+    PASS();
+}
+
 /**
  * Test 6: リークゼロ検証
  *
@@ -390,6 +424,7 @@ int main(void) {
     test_null_safety();
     test_destructor_primitive();
     test_destructor_closure();
+    test_closure_env_record();
     test_no_leaks();
     test_destructor_adt();
     test_destructor_tuple_record_array();
@@ -1,10 +1,15 @@
-use std::{env, error::Error, process};
+use std::{env, error::Error, fs, io::Write, path::PathBuf, process};
 
 use reml_runtime::{
     capability::{CapabilityDescriptor, CapabilityProvider, CapabilityTimestamp},
+    runtime::{
+        plugin::PluginLoader,
+        plugin_signing::{sign_bundle, write_bundle_signature, PluginSigningKey},
+    },
     CapabilityDescriptorList, CapabilityRegistry,
 };
 use serde_json::json;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
 const CAPABILITY_LIST_SCHEMA_VERSION: &str = "3.0.0-alpha";
 
@@ -26,6 +31,7 @@ fn run_cli() -> Result<(), Box<dyn Error>> {
     let command = iter.next().unwrap_or_else(|| "list".to_string());
     match command.as_str() {
         "list" => run_list(iter.collect()),
+        "plugin" => run_plugin(iter.collect()),
         "help" | "--help" | "-h" => {
             print_help(&program_name);
             Ok(())
@@ -69,6 +75,117 @@ fn run_list(args: Vec<String>) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+fn run_plugin(args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let mut args_iter = args.into_iter();
+    match args_iter.next().as_deref() {
+        Some("keygen") => run_plugin_keygen(args_iter.collect()),
+        Some("sign") => run_plugin_sign(args_iter.collect()),
+        Some("--help" | "-h") | None => {
+            print_plugin_help();
+            Ok(())
+        }
+        Some(other) => Err(format!("plugin の未知のサブコマンド `{other}` を指定しました").into()),
+    }
+}
+
+fn run_plugin_keygen(args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let mut out = None;
+    let mut key_id = None;
+    let mut args_iter = args.into_iter();
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--out" => out = args_iter.next().map(PathBuf::from),
+            "--key-id" => key_id = args_iter.next(),
+            "--help" | "-h" => {
+                print_plugin_help();
+                return Ok(());
+            }
+            other => {
+                return Err(
+                    format!("plugin keygen の未知のオプション `{other}` が指定されました").into(),
+                )
+            }
+        }
+    }
+    let out = out.ok_or("plugin keygen には --out <path> が必要です")?;
+    let key_id = key_id.ok_or("plugin keygen には --key-id <id> が必要です")?;
+    let key = PluginSigningKey::generate()?;
+    // 秘密鍵は作成時点から所有者だけが読める状態にする。
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&out).map_err(|err| -> Box<dyn Error> {
+        if err.kind() == std::io::ErrorKind::AlreadyExists {
+            format!("{} は既に存在します", out.display()).into()
+        } else {
+            err.into()
+        }
+    })?;
+    file.write_all(format!("{}\n", key.to_hex()).as_bytes())?;
+    // 信頼ストアの `signers` へそのまま追加できる形で公開鍵を出力する。
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&key.trusted_signer(key_id))?
+    );
+    Ok(())
+}
+
+fn run_plugin_sign(args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let mut bundle_path = None;
+    let mut key_path = None;
+    let mut key_id = None;
+    let mut issued_to = None;
+    let mut valid_until = None;
+    let mut args_iter = args.into_iter();
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--bundle" => bundle_path = args_iter.next().map(PathBuf::from),
+            "--key" => key_path = args_iter.next().map(PathBuf::from),
+            "--key-id" => key_id = args_iter.next(),
+            "--issued-to" => issued_to = args_iter.next(),
+            "--valid-until" => {
+                let value = args_iter
+                    .next()
+                    .ok_or("--valid-until には RFC 3339 の時刻を指定してください")?;
+                OffsetDateTime::parse(&value, &Rfc3339).map_err(|_| {
+                    format!("--valid-until の値 `{value}` は RFC 3339 の時刻ではありません")
+                })?;
+                valid_until = Some(value);
+            }
+            "--help" | "-h" => {
+                print_plugin_help();
+                return Ok(());
+            }
+            other => {
+                return Err(
+                    format!("plugin sign の未知のオプション `{other}` が指定されました").into(),
+                )
+            }
+        }
+    }
+    let bundle_path = bundle_path.ok_or("plugin sign には --bundle <path> が必要です")?;
+    let key_path = key_path.ok_or("plugin sign には --key <path> が必要です")?;
+    let key_id = key_id.ok_or("plugin sign には --key-id <id> が必要です")?;
+
+    let key = PluginSigningKey::load(&key_path)?;
+    let bundle = PluginLoader::new().load_bundle_manifest(&bundle_path)?;
+    let signature = sign_bundle(&bundle, &key, &key_id, issued_to, valid_until);
+    write_bundle_signature(&bundle_path, &signature)?;
+    println!(
+        "plugin.sign: {}@{} (key_id: {key_id})",
+        bundle.bundle_id, bundle.bundle_version
+    );
+    if let Some(bundle_hash) = &signature.bundle_hash {
+        println!("  bundle_hash: {bundle_hash}");
+    }
+    println!("  modules: {}", bundle.modules.len());
+    Ok(())
+}
+
 fn emit_json(list: &CapabilityDescriptorList) -> Result<(), Box<dyn Error>> {
     let payload = json!({
         "schema_version": CAPABILITY_LIST_SCHEMA_VERSION,
@@ -193,6 +310,7 @@ fn print_help(program: &str) {
 
 SUBCOMMAND:
   list      登録済み Capability を一覧表示します（既定）
+  plugin    プラグインバンドルの署名鍵を生成し、バンドルに署名します
   help      本メッセージを表示します
 
 `{prog} list --help` で list の詳細オプションを確認できます。
@@ -213,3 +331,24 @@ OPTIONS:
 "
     );
 }
+
+fn print_plugin_help() {
+    println!(
+        "\
+usage: reml_capability plugin keygen --out <path> --key-id <id>
+       reml_capability plugin sign --bundle <path> --key <path> --key-id <id> [OPTIONS]
+
+keygen:
+  Ed25519 の秘密鍵（32 バイトのシードを 16 進表記）を <path> に書き出し、
+  信頼ストアの signers に追加する項目を標準出力へ表示します。
+
+sign:
+  バンドルのマニフェストとモジュールのハッシュに署名し、bundle.json の
+  signature ブロックを書き換えます。ネットワークには接続しません。
+
+OPTIONS (sign):
+  --issued-to <name>         署名の発行先
+  --valid-until <rfc3339>    署名の有効期限（過ぎたバンドルは読み込まれません）
+"
+    );
+}
@@ -27,6 +27,9 @@ const CONFIG_MISSING_FIELD_CODE: &str = "config.missing_field";
 const CONFIG_INVALID_STAGE_CODE: &str = "config.invalid_stage";
 const CONFIG_PROJECT_KIND_UNKNOWN_CODE: &str = "config.project.kind_unknown";
 const CONFIG_BUILD_OPTIMIZE_UNKNOWN_CODE: &str = "config.build.optimize_unknown";
+const CONFIG_FORMAT_INDENT_STYLE_UNKNOWN_CODE: &str = "config.format.indent_style_unknown";
+const CONFIG_FORMAT_WIDTH_INVALID_CODE: &str = "config.format.width_invalid";
+const CONFIG_LINT_LEVEL_UNKNOWN_CODE: &str = "config.lint.level_unknown";
 const CONFIG_MANIFEST_IO_ERROR_CODE: &str = "config.manifest.io_error";
 const CONFIG_MANIFEST_PARSE_ERROR_CODE: &str = "config.manifest.parse_error";
 const CONFIG_MANIFEST_ENTRY_MISSING_CODE: &str = "manifest.entry.missing";
@@ -54,6 +57,12 @@ pub struct Manifest {
     pub config: ConfigRoot,
     #[serde(default)]
     pub run: RunSection,
+    #[serde(default)]
+    pub format: FormatSection,
+    #[serde(default)]
+    pub lint: LintSection,
+    #[serde(default, skip_serializing_if = "PluginSection::is_empty")]
+    pub plugin: PluginSection,
     #[serde(skip)]
     manifest_path: Option<PathBuf>,
 }
@@ -182,6 +191,43 @@ pub struct RunTargetSection {
     pub capabilities: Vec<RunCapabilityEntry>,
 }
 
+/// `plugin` ルートセクション。WASM プラグインがホストへ要求する import と設定を保持する。
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PluginSection {
+    /// 要求する import グループ（`host.log` / `wasi.fs` など）。
+    #[serde(default)]
+    pub imports: Vec<String>,
+    /// プラグインが利用するホスト側 Capability（`io.fs.read` など）。
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// `reml.v1::config_get` で参照できる設定値。
+    #[serde(default)]
+    pub config: BTreeMap<String, Value>,
+    /// `wasi.fs` で公開するディレクトリ。
+    #[serde(default)]
+    pub preopens: Vec<PluginPreopenEntry>,
+}
+
+impl PluginSection {
+    pub fn is_empty(&self) -> bool {
+        self.imports.is_empty()
+            && self.capabilities.is_empty()
+            && self.config.is_empty()
+            && self.preopens.is_empty()
+    }
+}
+
+/// `plugin.preopens[]` の 1 エントリ。
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PluginPreopenEntry {
+    /// ホスト側のパス。相対パスは `reml.toml` のあるディレクトリから解決する。
+    pub host: String,
+    /// プラグインから見えるディレクトリ名。
+    pub guest: String,
+    #[serde(default)]
+    pub writable: bool,
+}
+
 /// `run.target.capabilities[]` の 1 エントリ。
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RunCapabilityEntry {
@@ -379,6 +425,16 @@ impl ManifestBuilder {
         self
     }
 
+    pub fn format(mut self, format: FormatSection) -> Self {
+        self.manifest.format = format;
+        self
+    }
+
+    pub fn lint(mut self, lint: LintSection) -> Self {
+        self.manifest.lint = lint;
+        self
+    }
+
     pub fn manifest_path(mut self, path: impl Into<PathBuf>) -> Self {
         self.manifest.manifest_path = Some(path.into());
         self
@@ -708,6 +764,145 @@ pub struct AuthConfig {
     pub password: Option<String>,
 }
 
+/// `format` セクション。`remlc fmt` の整形幅とインデントを指定する。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatSection {
+    #[serde(default = "FormatSection::default_line_width")]
+    pub line_width: usize,
+    #[serde(default = "FormatSection::default_indent_width")]
+    pub indent_width: usize,
+    #[serde(default)]
+    pub indent_style: IndentStyle,
+}
+
+impl FormatSection {
+    pub const DEFAULT_LINE_WIDTH: usize = 100;
+    pub const DEFAULT_INDENT_WIDTH: usize = 2;
+
+    fn default_line_width() -> usize {
+        Self::DEFAULT_LINE_WIDTH
+    }
+
+    fn default_indent_width() -> usize {
+        Self::DEFAULT_INDENT_WIDTH
+    }
+}
+
+impl Default for FormatSection {
+    fn default() -> Self {
+        Self {
+            line_width: Self::DEFAULT_LINE_WIDTH,
+            indent_width: Self::DEFAULT_INDENT_WIDTH,
+            indent_style: IndentStyle::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum IndentStyle {
+    #[default]
+    Space,
+    Tab,
+    Unknown(String),
+}
+
+impl IndentStyle {
+    pub fn as_str(&self) -> &str {
+        match self {
+            IndentStyle::Space => "space",
+            IndentStyle::Tab => "tab",
+            IndentStyle::Unknown(value) => value.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for IndentStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Serialize for IndentStyle {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for IndentStyle {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        Ok(match raw.as_str() {
+            "space" => IndentStyle::Space,
+            "tab" => IndentStyle::Tab,
+            other => IndentStyle::Unknown(other.to_string()),
+        })
+    }
+}
+
+/// `lint` セクション。`remlc lint` の規則ごとのレベルを指定する。
+///
+/// `[lint.rules]` のキーは lint ID またはワイルドカードパターン（例: `"unused_*"`）。
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LintSection {
+    #[serde(default)]
+    pub rules: BTreeMap<String, LintLevel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+    Unknown(String),
+}
+
+impl LintLevel {
+    pub fn as_str(&self) -> &str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+            LintLevel::Unknown(value) => value.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Serialize for LintLevel {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for LintLevel {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        Ok(match raw.as_str() {
+            "allow" => LintLevel::Allow,
+            "warn" => LintLevel::Warn,
+            "deny" => LintLevel::Deny,
+            other => LintLevel::Unknown(other.to_string()),
+        })
+    }
+}
+
 /// `Manifest::parse_toml` から返す汎用エラー。
 #[derive(Debug)]
 pub struct ManifestParseError {
@@ -1031,6 +1226,8 @@ pub fn validate_manifest(manifest: &Manifest) -> Result<(), GuardDiagnostic> {
     validate_project_section(&manifest.project, manifest_path)?;
     validate_build_section(&manifest.build, manifest_path)?;
     validate_build_profiles(&manifest.build.profiles, manifest_path)?;
+    validate_format_section(&manifest.format, manifest_path)?;
+    validate_lint_section(&manifest.lint, manifest_path)?;
     validate_dsl_sections(manifest, manifest_path)?;
     Ok(())
 }
@@ -1184,6 +1381,54 @@ fn validate_build_profiles(
     Ok(())
 }
 
+fn validate_format_section(
+    format: &FormatSection,
+    manifest_path: Option<&Path>,
+) -> Result<(), GuardDiagnostic> {
+    if format.line_width == 0 {
+        return Err(format_width_diagnostic(
+            manifest_path,
+            &["format", "line_width"],
+        ));
+    }
+    if format.indent_width == 0 {
+        return Err(format_width_diagnostic(
+            manifest_path,
+            &["format", "indent_width"],
+        ));
+    }
+    if let IndentStyle::Unknown(value) = &format.indent_style {
+        return Err(manifest_diagnostic(
+            CONFIG_FORMAT_INDENT_STYLE_UNKNOWN_CODE,
+            format!(
+                "`format.indent_style` に未対応の値 `{value}` が指定されました（space / tab のいずれか）"
+            ),
+            manifest_path,
+            &["format", "indent_style"],
+        ));
+    }
+    Ok(())
+}
+
+fn validate_lint_section(
+    lint: &LintSection,
+    manifest_path: Option<&Path>,
+) -> Result<(), GuardDiagnostic> {
+    for (rule, level) in &lint.rules {
+        if let LintLevel::Unknown(value) = level {
+            return Err(manifest_diagnostic(
+                CONFIG_LINT_LEVEL_UNKNOWN_CODE,
+                format!(
+                    "`lint.rules.{rule}` に未対応のレベル `{value}` が指定されました（allow / warn / deny のいずれか）"
+                ),
+                manifest_path,
+                &["lint", "rules", rule],
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn validate_dsl_sections(
     manifest: &Manifest,
     manifest_path: Option<&Path>,
@@ -1396,6 +1641,16 @@ fn build_optimize_diagnostic(
     )
 }
 
+fn format_width_diagnostic(manifest_path: Option<&Path>, key_path: &[&str]) -> GuardDiagnostic {
+    let label = join_key_path(key_path);
+    manifest_diagnostic(
+        CONFIG_FORMAT_WIDTH_INVALID_CODE,
+        format!("`{label}` には 1 以上の値を指定してください"),
+        manifest_path,
+        key_path,
+    )
+}
+
 fn dsl_kind_diagnostic(manifest_path: Option<&Path>, dsl: &str, value: &str) -> GuardDiagnostic {
     manifest_diagnostic(
         CONFIG_DSL_UNKNOWN_KIND_CODE,
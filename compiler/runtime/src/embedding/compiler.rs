@@ -0,0 +1,117 @@
+//! 埋め込み実行のためのモジュールコンパイラ登録。
+//!
+//! ランタイムはフロントエンドに依存できないため、ソースから wasm モジュールを作る処理は
+//! `set_embed_module_compiler` で外から差し込む（フロントエンドは
+//! `reml_frontend::embed::install_embed_module_compiler` を提供する）。
+
+use std::sync::Arc;
+
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+
+static EMBED_MODULE_COMPILER: OnceCell<Arc<dyn EmbedModuleCompiler>> = OnceCell::new();
+
+pub const EMBED_COMPILER_ALREADY_SET_CODE: &str = "native.embed.compiler_already_set";
+
+/// ソースから wasm モジュールを生成するコンパイラ。
+pub trait EmbedModuleCompiler: Send + Sync {
+    /// 失敗した場合はフロントエンド/バックエンドの診断を返す。
+    fn compile(&self, source: &str) -> Result<EmbedCompiledModule, Vec<EmbedDiagnostic>>;
+}
+
+/// モジュールコンパイラを登録する。登録できるのはプロセスで 1 度だけ。
+pub fn set_embed_module_compiler(
+    compiler: Arc<dyn EmbedModuleCompiler>,
+) -> Result<(), EmbedDiagnostic> {
+    EMBED_MODULE_COMPILER.set(compiler).map_err(|_| {
+        EmbedDiagnostic::new(
+            EMBED_COMPILER_ALREADY_SET_CODE,
+            "埋め込みモジュールコンパイラは既に登録されています",
+        )
+    })
+}
+
+pub(crate) fn embed_module_compiler() -> Option<Arc<dyn EmbedModuleCompiler>> {
+    EMBED_MODULE_COMPILER.get().cloned()
+}
+
+/// コンパイル済みモジュール。
+#[derive(Debug, Clone, Default)]
+pub struct EmbedCompiledModule {
+    /// `.wasm` バイナリ。`memory`/`mem_alloc`/`dec_ref`/`reml_set_type_tag` をエクスポートしていること。
+    pub wasm: Vec<u8>,
+    /// 呼び出せる関数とそのシグネチャ。
+    pub exports: Vec<EmbedFunction>,
+    /// `env` から取り込む extern 宣言とそのシグネチャ。
+    pub imports: Vec<EmbedFunction>,
+}
+
+/// 関数名とシグネチャ（`(Int, Str) -> Int` 形式）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbedFunction {
+    pub name: String,
+    pub signature: String,
+}
+
+impl EmbedFunction {
+    pub fn new(name: impl Into<String>, signature: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            signature: signature.into(),
+        }
+    }
+}
+
+/// ソース上の位置（バイトオフセット）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbedSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// `reml_last_error` が JSON で返す構造化診断。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbedDiagnostic {
+    pub code: String,
+    pub message: String,
+    pub span: Option<EmbedSpan>,
+    /// 原因となった診断（コンパイルエラーの内訳など）。
+    pub diagnostics: Vec<EmbedDiagnostic>,
+}
+
+impl EmbedDiagnostic {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            span: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn with_span(mut self, start: usize, end: usize) -> Self {
+        self.span = Some(EmbedSpan { start, end });
+        self
+    }
+
+    pub fn with_diagnostics(mut self, diagnostics: Vec<EmbedDiagnostic>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            "severity": "error",
+            "code": self.code,
+            "message": self.message,
+        });
+        if let Some(span) = self.span {
+            value["span"] = json!({ "start": span.start, "end": span.end });
+        }
+        if !self.diagnostics.is_empty() {
+            value["diagnostics"] =
+                Value::Array(self.diagnostics.iter().map(Self::to_json).collect());
+        }
+        value
+    }
+}
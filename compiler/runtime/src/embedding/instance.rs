@@ -0,0 +1,708 @@
+//! コンパイル済みモジュールを wasmtime で実行し、値を線形メモリへ受け渡す。
+//!
+//! 線形メモリ上のオブジェクトは wasm バックエンドのランタイムと同じ配置を使う。
+//! 文字列は `{data i32, len i64 @8}`、配列/レコードは `{len i64, items i32 @8}` で、
+//! `items` はボックス化した要素へのポインタ列になる。確保は `mem_alloc`（refcount=1）で行い、
+//! 型タグは `reml_set_type_tag` で設定する。
+//!
+//! 参照カウントは `reml_ffi_bridge.h` の規則に従う。
+//! - エントリポイントへの引数は Borrowed で渡し、呼び出しが戻ったらランタイムが `dec_ref` する。
+//! - エントリポイントの戻り値は Transferred で受け取り、読み出した後に `dec_ref` する。
+//! - ホスト関数への引数は Borrowed（ランタイムは解放しない）、戻り値は refcount=1 の
+//!   新しいオブジェクトとして呼び出し元へ Transferred で渡す。
+
+use std::collections::HashMap;
+use std::os::raw::c_void;
+use std::sync::Arc;
+
+use wasmtime::{
+    AsContextMut, Caller, Engine, Extern, Instance, Linker, Memory, Module, Store, TypedFunc, Val,
+};
+
+use super::compiler::{EmbedCompiledModule, EmbedDiagnostic};
+use super::signature::{EmbedSignature, EmbedType};
+use super::value::{take_transferred, EmbedValue};
+use super::{
+    ARGUMENT_MISMATCH_CODE, ENTRYPOINT_MISSING_CODE, HOST_CALL_FAILED_CODE,
+    HOST_FUNCTION_MISSING_CODE, INVALID_MODULE_CODE, MARSHAL_FAILED_CODE, SIGNATURE_MISMATCH_CODE,
+    TRAP_CODE, UNSUPPORTED_SIGNATURE_CODE,
+};
+
+/// extern 宣言を取り込むモジュール名（wasm バックエンドの `IMPORT_MODULE`）。
+const IMPORT_MODULE: &str = "env";
+
+const TAG_INT: i32 = 1;
+const TAG_FLOAT: i32 = 2;
+const TAG_BOOL: i32 = 3;
+const TAG_STRING: i32 = 4;
+const TAG_RECORD: i32 = 6;
+const TAG_ARRAY: i32 = 11;
+
+/// ホストが登録する関数。引数は Borrowed、戻り値は Transferred。NULL を返すと呼び出しは失敗する。
+pub type RemlHostFunction = unsafe extern "C" fn(
+    user_data: *mut c_void,
+    args: *const *const EmbedValue,
+    argc: usize,
+) -> *mut EmbedValue;
+
+#[derive(Clone)]
+pub(crate) struct HostFunction {
+    pub(crate) signature: EmbedSignature,
+    pub(crate) callback: RemlHostFunction,
+    /// C 側のポインタ。スレッドをまたいで持ち回れるよう整数で保持する。
+    pub(crate) user_data: usize,
+}
+
+#[derive(Default)]
+pub(crate) struct EmbedStoreData {
+    /// ホスト関数の失敗。トラップとして呼び出し元へ戻った後に取り出す。
+    host_error: Option<EmbedDiagnostic>,
+}
+
+/// wasmtime へロード済みのモジュール。
+pub(crate) struct LoadedModule {
+    module: Module,
+    exports: HashMap<String, String>,
+    imports: HashMap<String, String>,
+}
+
+impl LoadedModule {
+    pub(crate) fn new(
+        engine: &Engine,
+        compiled: EmbedCompiledModule,
+    ) -> Result<Self, EmbedDiagnostic> {
+        let module = Module::new(engine, &compiled.wasm).map_err(|err| {
+            EmbedDiagnostic::new(
+                INVALID_MODULE_CODE,
+                format!("wasm モジュールを読み込めません: {err}"),
+            )
+        })?;
+        let into_map = |functions: Vec<super::compiler::EmbedFunction>| {
+            functions
+                .into_iter()
+                .map(|function| (function.name, function.signature))
+                .collect()
+        };
+        Ok(Self {
+            module,
+            exports: into_map(compiled.exports),
+            imports: into_map(compiled.imports),
+        })
+    }
+
+    pub(crate) fn export_signature(&self, name: &str) -> Result<EmbedSignature, EmbedDiagnostic> {
+        let Some(signature) = self.exports.get(name) else {
+            return Err(EmbedDiagnostic::new(
+                ENTRYPOINT_MISSING_CODE,
+                format!("エントリポイント {name} がモジュールにありません"),
+            ));
+        };
+        EmbedSignature::parse(signature).map_err(|reason| {
+            EmbedDiagnostic::new(
+                UNSUPPORTED_SIGNATURE_CODE,
+                format!("エントリポイント {name} のシグネチャを扱えません: {reason}"),
+            )
+        })
+    }
+}
+
+/// インスタンス化したモジュール。ホスト関数の登録やモジュールの差し替えで作り直す。
+pub(crate) struct EmbedInstance {
+    store: Store<EmbedStoreData>,
+    instance: Instance,
+    heap: WasmHeap,
+}
+
+impl EmbedInstance {
+    pub(crate) fn instantiate(
+        engine: &Engine,
+        loaded: &LoadedModule,
+        host_functions: &HashMap<String, HostFunction>,
+    ) -> Result<Self, EmbedDiagnostic> {
+        let mut linker = Linker::new(engine);
+        for import in loaded.module.imports() {
+            let name = import.name();
+            let Some(func_ty) = import.ty().func().cloned() else {
+                return Err(EmbedDiagnostic::new(
+                    INVALID_MODULE_CODE,
+                    format!(
+                        "関数以外の取り込み {}::{name} は扱えません",
+                        import.module()
+                    ),
+                ));
+            };
+            if import.module() != IMPORT_MODULE {
+                return Err(EmbedDiagnostic::new(
+                    INVALID_MODULE_CODE,
+                    format!("取り込みモジュール {} は扱えません", import.module()),
+                ));
+            }
+            let Some(host) = host_functions.get(name) else {
+                return Err(EmbedDiagnostic::new(
+                    HOST_FUNCTION_MISSING_CODE,
+                    format!("extern {name} に対応するホスト関数が登録されていません"),
+                ));
+            };
+            check_host_signature(name, host, loaded.imports.get(name))?;
+            if func_ty.params().len() != host.signature.params.len() {
+                return Err(EmbedDiagnostic::new(
+                    SIGNATURE_MISMATCH_CODE,
+                    format!(
+                        "extern {name} の引数は {} 個ですが、ホスト関数は {} 個です",
+                        func_ty.params().len(),
+                        host.signature.params.len()
+                    ),
+                ));
+            }
+            let host = host.clone();
+            let import_name = name.to_string();
+            linker
+                .func_new(
+                    IMPORT_MODULE,
+                    name,
+                    func_ty,
+                    move |caller, params, results| {
+                        call_host_function(caller, &import_name, &host, params, results)
+                    },
+                )
+                .map_err(|err| {
+                    EmbedDiagnostic::new(
+                        INVALID_MODULE_CODE,
+                        format!("ホスト関数 {name} を結び付けられません: {err}"),
+                    )
+                })?;
+        }
+        let mut store = Store::new(engine, EmbedStoreData::default());
+        let instance = linker
+            .instantiate(&mut store, &loaded.module)
+            .map_err(|err| {
+                EmbedDiagnostic::new(
+                    INVALID_MODULE_CODE,
+                    format!("モジュールをインスタンス化できません: {err}"),
+                )
+            })?;
+        let heap = WasmHeap::from_instance(&mut store, &instance)?;
+        Ok(Self {
+            store,
+            instance,
+            heap,
+        })
+    }
+
+    /// エクスポート関数を呼び出し、戻り値をホストの値へ変換する。
+    pub(crate) fn call(
+        &mut self,
+        name: &str,
+        signature: &EmbedSignature,
+        args: &[Arc<EmbedValue>],
+    ) -> Result<Arc<EmbedValue>, EmbedDiagnostic> {
+        let Some(func) = self.instance.get_func(&mut self.store, name) else {
+            return Err(EmbedDiagnostic::new(
+                ENTRYPOINT_MISSING_CODE,
+                format!("エントリポイント {name} がエクスポートされていません"),
+            ));
+        };
+        if args.len() != signature.params.len() {
+            return Err(EmbedDiagnostic::new(
+                ARGUMENT_MISMATCH_CODE,
+                format!(
+                    "{name} の引数は {} 個ですが、{} 個渡されました",
+                    signature.params.len(),
+                    args.len()
+                ),
+            ));
+        }
+        let mut params = Vec::with_capacity(args.len());
+        let mut borrowed = Vec::new();
+        for (index, (arg, ty)) in args.iter().zip(&signature.params).enumerate() {
+            if !arg.conforms_to(ty) {
+                self.release_all(&borrowed);
+                return Err(EmbedDiagnostic::new(
+                    ARGUMENT_MISMATCH_CODE,
+                    format!("{name} の {} 番目の引数は {ty} 型が必要です", index + 1),
+                ));
+            }
+            let lowered = self
+                .heap
+                .lower_value(&mut self.store, arg, ty)
+                .map_err(marshal_failed);
+            match lowered {
+                Ok(Some(Val::I32(pointer))) if is_object(ty) => {
+                    borrowed.push(pointer);
+                    params.push(Val::I32(pointer));
+                }
+                Ok(Some(value)) => params.push(value),
+                // Unit の引数は wasm では i32 の 0 で渡す。
+                Ok(None) => params.push(Val::I32(0)),
+                Err(diagnostic) => {
+                    self.release_all(&borrowed);
+                    return Err(diagnostic);
+                }
+            }
+        }
+        let result_count = func.ty(&self.store).results().len();
+        let mut results = vec![Val::I32(0); result_count];
+        let outcome = func.call(&mut self.store, &params, &mut results);
+        if let Err(err) = outcome {
+            self.release_all(&borrowed);
+            let host_error = self.store.data_mut().host_error.take();
+            return Err(host_error.unwrap_or_else(|| {
+                EmbedDiagnostic::new(
+                    TRAP_CODE,
+                    format!("{name} の実行中にトラップしました: {err}"),
+                )
+            }));
+        }
+        let lifted = self
+            .heap
+            .lift_value(&mut self.store, results.first(), &signature.ret)
+            .map_err(marshal_failed);
+        if let (Some(Val::I32(pointer)), true) = (results.first(), is_object(&signature.ret)) {
+            // wasm バックエンドは引数をそのまま返すときに inc_ref しないため、
+            // 引数と同じオブジェクトは引数側の参照として 1 度だけ解放する。
+            if !borrowed.contains(pointer) {
+                self.heap.release(&mut self.store, *pointer);
+            }
+        }
+        self.release_all(&borrowed);
+        lifted.map(Arc::new)
+    }
+
+    fn release_all(&mut self, pointers: &[i32]) {
+        for pointer in pointers {
+            self.heap.release(&mut self.store, *pointer);
+        }
+    }
+}
+
+fn check_host_signature(
+    name: &str,
+    host: &HostFunction,
+    declared: Option<&String>,
+) -> Result<(), EmbedDiagnostic> {
+    let Some(declared) = declared else {
+        return Ok(());
+    };
+    let declared = EmbedSignature::parse(declared).map_err(|reason| {
+        EmbedDiagnostic::new(
+            UNSUPPORTED_SIGNATURE_CODE,
+            format!("extern {name} のシグネチャを扱えません: {reason}"),
+        )
+    })?;
+    if !host.signature.is_compatible(&declared) {
+        return Err(EmbedDiagnostic::new(
+            SIGNATURE_MISMATCH_CODE,
+            format!(
+                "extern {name} は {declared} と宣言されていますが、ホスト関数は {} です",
+                host.signature
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn call_host_function(
+    mut caller: Caller<'_, EmbedStoreData>,
+    name: &str,
+    host: &HostFunction,
+    params: &[Val],
+    results: &mut [Val],
+) -> anyhow::Result<()> {
+    let outcome = invoke_host_function(&mut caller, name, host, params, results);
+    outcome.map_err(|diagnostic| {
+        let message = diagnostic.message.clone();
+        caller.data_mut().host_error = Some(diagnostic);
+        anyhow::Error::msg(message)
+    })
+}
+
+fn invoke_host_function(
+    caller: &mut Caller<'_, EmbedStoreData>,
+    name: &str,
+    host: &HostFunction,
+    params: &[Val],
+    results: &mut [Val],
+) -> Result<(), EmbedDiagnostic> {
+    let heap = WasmHeap::from_caller(caller)?;
+    let mut args = Vec::with_capacity(params.len());
+    for (param, ty) in params.iter().zip(&host.signature.params) {
+        let value = heap
+            .lift_value(&mut *caller, Some(param), ty)
+            .map_err(marshal_failed)?;
+        args.push(Arc::new(value));
+    }
+    let pointers: Vec<*const EmbedValue> = args.iter().map(Arc::as_ptr).collect();
+    let raw = unsafe {
+        (host.callback)(
+            host.user_data as *mut c_void,
+            pointers.as_ptr(),
+            pointers.len(),
+        )
+    };
+    drop(args);
+    if raw.is_null() {
+        return Err(EmbedDiagnostic::new(
+            HOST_CALL_FAILED_CODE,
+            format!("ホスト関数 {name} が失敗しました"),
+        ));
+    }
+    let result = unsafe { take_transferred(raw) };
+    if !result.conforms_to(&host.signature.ret) {
+        return Err(EmbedDiagnostic::new(
+            HOST_CALL_FAILED_CODE,
+            format!(
+                "ホスト関数 {name} の戻り値は {} 型が必要です",
+                host.signature.ret
+            ),
+        ));
+    }
+    let lowered = heap
+        .lower_value(&mut *caller, &result, &host.signature.ret)
+        .map_err(marshal_failed)?;
+    if let (Some(slot), Some(value)) = (results.first_mut(), lowered) {
+        *slot = value;
+    }
+    Ok(())
+}
+
+fn marshal_failed(reason: String) -> EmbedDiagnostic {
+    EmbedDiagnostic::new(MARSHAL_FAILED_CODE, format!("値を受け渡せません: {reason}"))
+}
+
+/// 線形メモリ上のオブジェクトとして受け渡す型か。
+fn is_object(ty: &EmbedType) -> bool {
+    matches!(
+        ty,
+        EmbedType::Str | EmbedType::Array(_) | EmbedType::Record(_)
+    )
+}
+
+/// モジュールがエクスポートするメモリとアロケータ。
+#[derive(Clone)]
+struct WasmHeap {
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    set_tag: TypedFunc<(i32, i32), ()>,
+    dec_ref: TypedFunc<i32, ()>,
+}
+
+impl WasmHeap {
+    fn from_instance(
+        store: &mut Store<EmbedStoreData>,
+        instance: &Instance,
+    ) -> Result<Self, EmbedDiagnostic> {
+        let missing = |name: &str| {
+            EmbedDiagnostic::new(
+                INVALID_MODULE_CODE,
+                format!("モジュールが {name} をエクスポートしていません"),
+            )
+        };
+        let memory = instance
+            .get_memory(&mut *store, "memory")
+            .ok_or_else(|| missing("memory"))?;
+        let alloc = instance
+            .get_typed_func(&mut *store, "mem_alloc")
+            .map_err(|_| missing("mem_alloc"))?;
+        let set_tag = instance
+            .get_typed_func(&mut *store, "reml_set_type_tag")
+            .map_err(|_| missing("reml_set_type_tag"))?;
+        let dec_ref = instance
+            .get_typed_func(&mut *store, "dec_ref")
+            .map_err(|_| missing("dec_ref"))?;
+        Ok(Self {
+            memory,
+            alloc,
+            set_tag,
+            dec_ref,
+        })
+    }
+
+    fn from_caller(caller: &mut Caller<'_, EmbedStoreData>) -> Result<Self, EmbedDiagnostic> {
+        let missing = |name: &str| {
+            EmbedDiagnostic::new(
+                INVALID_MODULE_CODE,
+                format!("モジュールが {name} をエクスポートしていません"),
+            )
+        };
+        let memory = caller
+            .get_export("memory")
+            .and_then(Extern::into_memory)
+            .ok_or_else(|| missing("memory"))?;
+        let mut typed = |name: &str| {
+            caller
+                .get_export(name)
+                .and_then(Extern::into_func)
+                .ok_or_else(|| missing(name))
+        };
+        let alloc = typed("mem_alloc")?;
+        let set_tag = typed("reml_set_type_tag")?;
+        let dec_ref = typed("dec_ref")?;
+        Ok(Self {
+            memory,
+            alloc: alloc.typed(&*caller).map_err(|_| missing("mem_alloc"))?,
+            set_tag: set_tag
+                .typed(&*caller)
+                .map_err(|_| missing("reml_set_type_tag"))?,
+            dec_ref: dec_ref.typed(&*caller).map_err(|_| missing("dec_ref"))?,
+        })
+    }
+
+    fn release<S: AsContextMut>(&self, store: &mut S, pointer: i32) {
+        // 解放中のトラップは値の受け渡しに影響しないため無視する。
+        let _ = self.dec_ref.call(store, pointer);
+    }
+
+    fn allocate<S: AsContextMut>(
+        &self,
+        store: &mut S,
+        size: usize,
+        tag: Option<i32>,
+    ) -> Result<i32, String> {
+        let size = i32::try_from(size).map_err(|_| format!("{size} バイトは確保できません"))?;
+        let pointer = self
+            .alloc
+            .call(&mut *store, size)
+            .map_err(|err| format!("mem_alloc に失敗しました: {err}"))?;
+        if let Some(tag) = tag {
+            self.set_tag
+                .call(&mut *store, (pointer, tag))
+                .map_err(|err| format!("型タグを設定できません: {err}"))?;
+        }
+        Ok(pointer)
+    }
+
+    fn write<S: AsContextMut>(
+        &self,
+        store: &mut S,
+        address: i32,
+        bytes: &[u8],
+    ) -> Result<(), String> {
+        self.memory
+            .write(store, address as u32 as usize, bytes)
+            .map_err(|_| format!("アドレス {address} へ書き込めません"))
+    }
+
+    fn read<S: AsContextMut>(
+        &self,
+        store: &mut S,
+        address: u32,
+        length: usize,
+    ) -> Result<Vec<u8>, String> {
+        if length > self.memory.data_size(&*store) {
+            return Err(format!("長さ {length} は線形メモリを超えています"));
+        }
+        let mut buffer = vec![0; length];
+        self.memory
+            .read(&*store, address as usize, &mut buffer)
+            .map_err(|_| format!("アドレス {address} から読み出せません"))?;
+        Ok(buffer)
+    }
+
+    fn read_u32<S: AsContextMut>(&self, store: &mut S, address: u32) -> Result<u32, String> {
+        let bytes = self.read(store, address, 4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().expect("4 バイト")))
+    }
+
+    fn read_u64<S: AsContextMut>(&self, store: &mut S, address: u32) -> Result<u64, String> {
+        let bytes = self.read(store, address, 8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().expect("8 バイト")))
+    }
+
+    /// ホストの値を wasm の値へ変換する。Unit は `None`。
+    fn lower_value<S: AsContextMut>(
+        &self,
+        store: &mut S,
+        value: &EmbedValue,
+        ty: &EmbedType,
+    ) -> Result<Option<Val>, String> {
+        Ok(match (value, ty) {
+            (_, EmbedType::Unit) => None,
+            (EmbedValue::Int(value), EmbedType::Int) => Some(Val::I64(*value)),
+            (EmbedValue::Float(value), EmbedType::Float) => Some(Val::F64(value.to_bits())),
+            (EmbedValue::Bool(value), EmbedType::Bool) => Some(Val::I32(i32::from(*value))),
+            _ => Some(Val::I32(self.lower_object(store, value, ty)?)),
+        })
+    }
+
+    fn lower_object<S: AsContextMut>(
+        &self,
+        store: &mut S,
+        value: &EmbedValue,
+        ty: &EmbedType,
+    ) -> Result<i32, String> {
+        match (value, ty) {
+            (EmbedValue::Str(text), EmbedType::Str) => {
+                let pointer = self.allocate(store, 16 + text.len(), Some(TAG_STRING))?;
+                let data = pointer + 16;
+                self.write(store, pointer, &data.to_le_bytes())?;
+                self.write(store, pointer + 8, &(text.len() as u64).to_le_bytes())?;
+                self.write(store, data, text.as_bytes())?;
+                Ok(pointer)
+            }
+            (EmbedValue::Array(items), EmbedType::Array(element)) => {
+                let items: Vec<_> = items
+                    .iter()
+                    .map(|item| (item.as_ref(), element.as_ref()))
+                    .collect();
+                self.lower_items(store, TAG_ARRAY, &items)
+            }
+            (EmbedValue::Record(_), EmbedType::Record(fields)) => {
+                let mut items = Vec::with_capacity(fields.len());
+                for (name, field_ty) in fields {
+                    let field = value
+                        .field(name)
+                        .ok_or_else(|| format!("フィールド {name} がありません"))?;
+                    items.push((field.as_ref(), field_ty));
+                }
+                self.lower_items(store, TAG_RECORD, &items)
+            }
+            _ => Err(format!("値は {ty} 型ではありません")),
+        }
+    }
+
+    fn lower_items<S: AsContextMut>(
+        &self,
+        store: &mut S,
+        tag: i32,
+        items: &[(&EmbedValue, &EmbedType)],
+    ) -> Result<i32, String> {
+        let pointer = self.allocate(store, 16, Some(tag))?;
+        let list = if items.is_empty() {
+            0
+        } else {
+            self.allocate(store, items.len() * 4, None)?
+        };
+        for (index, (item, ty)) in items.iter().enumerate() {
+            let boxed = self.box_element(store, item, ty)?;
+            self.write(store, list + index as i32 * 4, &boxed.to_le_bytes())?;
+        }
+        self.write(store, pointer, &(items.len() as u64).to_le_bytes())?;
+        self.write(store, pointer + 8, &list.to_le_bytes())?;
+        Ok(pointer)
+    }
+
+    /// 配列/レコードの要素をボックス化する。
+    fn box_element<S: AsContextMut>(
+        &self,
+        store: &mut S,
+        value: &EmbedValue,
+        ty: &EmbedType,
+    ) -> Result<i32, String> {
+        let (tag, bytes) = match (value, ty) {
+            (_, EmbedType::Unit) => return Ok(0),
+            (EmbedValue::Int(value), EmbedType::Int) => (TAG_INT, value.to_le_bytes()),
+            (EmbedValue::Float(value), EmbedType::Float) => {
+                (TAG_FLOAT, value.to_bits().to_le_bytes())
+            }
+            (EmbedValue::Bool(value), EmbedType::Bool) => {
+                (TAG_BOOL, u64::from(*value).to_le_bytes())
+            }
+            _ => return self.lower_object(store, value, ty),
+        };
+        let pointer = self.allocate(store, 8, Some(tag))?;
+        self.write(store, pointer, &bytes)?;
+        Ok(pointer)
+    }
+
+    /// wasm の値をホストの値へ変換する。
+    fn lift_value<S: AsContextMut>(
+        &self,
+        store: &mut S,
+        value: Option<&Val>,
+        ty: &EmbedType,
+    ) -> Result<EmbedValue, String> {
+        let missing = || format!("{ty} 型の値が返されていません");
+        Ok(match ty {
+            EmbedType::Unit => EmbedValue::Unit,
+            EmbedType::Int => EmbedValue::Int(value.and_then(Val::i64).ok_or_else(missing)?),
+            EmbedType::Float => EmbedValue::Float(value.and_then(Val::f64).ok_or_else(missing)?),
+            EmbedType::Bool => EmbedValue::Bool(value.and_then(Val::i32).ok_or_else(missing)? != 0),
+            _ => {
+                let pointer = value.and_then(Val::i32).ok_or_else(missing)?;
+                self.lift_object(store, pointer as u32, ty)?
+            }
+        })
+    }
+
+    fn lift_object<S: AsContextMut>(
+        &self,
+        store: &mut S,
+        pointer: u32,
+        ty: &EmbedType,
+    ) -> Result<EmbedValue, String> {
+        if pointer == 0 {
+            return Err(format!("{ty} 型の値が NULL です"));
+        }
+        match ty {
+            EmbedType::Str => {
+                let data = self.read_u32(store, pointer)?;
+                let length = self.read_u64(store, pointer + 8)?;
+                let length =
+                    usize::try_from(length).map_err(|_| "文字列が長すぎます".to_string())?;
+                let bytes = self.read(store, data, length)?;
+                String::from_utf8(bytes)
+                    .map(EmbedValue::Str)
+                    .map_err(|_| "文字列が UTF-8 ではありません".to_string())
+            }
+            EmbedType::Array(element) => {
+                let items = self.lift_items(store, pointer)?;
+                let mut values = Vec::with_capacity(items.len());
+                for item in items {
+                    values.push(Arc::new(self.unbox_element(store, item, element)?));
+                }
+                Ok(EmbedValue::Array(values))
+            }
+            EmbedType::Record(fields) => {
+                let items = self.lift_items(store, pointer)?;
+                if items.len() != fields.len() {
+                    return Err(format!(
+                        "レコードのフィールド数が {} ではなく {} です",
+                        fields.len(),
+                        items.len()
+                    ));
+                }
+                let mut values = Vec::with_capacity(fields.len());
+                for ((name, field_ty), item) in fields.iter().zip(items) {
+                    values.push((
+                        name.clone(),
+                        Arc::new(self.unbox_element(store, item, field_ty)?),
+                    ));
+                }
+                Ok(EmbedValue::Record(values))
+            }
+            _ => Err(format!("{ty} 型はオブジェクトではありません")),
+        }
+    }
+
+    /// `{len i64, items i32 @8}` の要素ポインタ列を読む。
+    fn lift_items<S: AsContextMut>(&self, store: &mut S, pointer: u32) -> Result<Vec<u32>, String> {
+        let length = self.read_u64(store, pointer)?;
+        let list = self.read_u32(store, pointer + 8)?;
+        let length = usize::try_from(length).map_err(|_| "要素数が大きすぎます".to_string())?;
+        let bytes = self.read(store, list, length.saturating_mul(4))?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().expect("4 バイト")))
+            .collect())
+    }
+
+    fn unbox_element<S: AsContextMut>(
+        &self,
+        store: &mut S,
+        pointer: u32,
+        ty: &EmbedType,
+    ) -> Result<EmbedValue, String> {
+        match ty {
+            EmbedType::Unit => Ok(EmbedValue::Unit),
+            EmbedType::Int | EmbedType::Float | EmbedType::Bool if pointer == 0 => {
+                Err(format!("{ty} 型の要素が NULL です"))
+            }
+            EmbedType::Int => Ok(EmbedValue::Int(self.read_u64(store, pointer)? as i64)),
+            EmbedType::Float => Ok(EmbedValue::Float(f64::from_bits(
+                self.read_u64(store, pointer)?,
+            ))),
+            EmbedType::Bool => Ok(EmbedValue::Bool(self.read_u32(store, pointer)? != 0)),
+            _ => self.lift_object(store, pointer, ty),
+        }
+    }
+}
@@ -0,0 +1,447 @@
+//! 埋め込み API (C ABI)。
+//! `native.embed.*` の監査メタデータを記録しつつ、ロードしたソースを登録済みの
+//! モジュールコンパイラで wasm へ変換し、wasmtime 上でエントリポイントを実行する。
+//! extern 宣言はホストが `reml_register_function` で登録した関数へ結び付ける。
+//! 失敗は `reml_last_error` から構造化診断（JSON）として取得できる。
+
+// C ABI の関数はポインタの有効性を呼び出し側（ホスト）の責務とする。
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+mod compiler;
+mod instance;
+mod signature;
+mod value;
+
+use crate::audit::{AuditEnvelope, AuditEvent};
+use crate::native::insert_embed_entrypoint_audit_metadata;
+use once_cell::sync::Lazy;
+use serde_json::{Map as JsonMap, Value};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use wasmtime::Engine;
+
+pub use compiler::{
+    set_embed_module_compiler, EmbedCompiledModule, EmbedDiagnostic, EmbedFunction,
+    EmbedModuleCompiler, EmbedSpan, EMBED_COMPILER_ALREADY_SET_CODE,
+};
+pub use instance::RemlHostFunction;
+pub use signature::{EmbedSignature, EmbedType};
+pub use value::{
+    reml_embed_value_array, reml_embed_value_array_get, reml_embed_value_as_bool,
+    reml_embed_value_as_float, reml_embed_value_as_int, reml_embed_value_as_string,
+    reml_embed_value_bool, reml_embed_value_dec_ref, reml_embed_value_float,
+    reml_embed_value_inc_ref, reml_embed_value_int, reml_embed_value_kind, reml_embed_value_length,
+    reml_embed_value_record, reml_embed_value_record_field_name, reml_embed_value_record_get,
+    reml_embed_value_string, reml_embed_value_unit, EmbedValue, RemlEmbedValueKind,
+};
+
+use instance::{EmbedInstance, HostFunction, LoadedModule};
+use value::{borrow_value, retain_borrowed, transfer_to_host};
+
+const EMBED_ABI_VERSION: &str = "0.1.0";
+const EMBED_CAPABILITY: &str = "native.embed";
+
+pub const INVALID_ARGUMENT_CODE: &str = "native.embed.invalid_argument";
+pub const COMPILER_UNAVAILABLE_CODE: &str = "native.embed.compiler_unavailable";
+pub const COMPILE_FAILED_CODE: &str = "native.embed.compile_failed";
+pub const MODULE_NOT_LOADED_CODE: &str = "native.embed.module_not_loaded";
+pub const INVALID_MODULE_CODE: &str = "native.embed.invalid_module";
+pub const ENTRYPOINT_MISSING_CODE: &str = "native.embed.entrypoint_missing";
+pub const ARGUMENT_MISMATCH_CODE: &str = "native.embed.argument_mismatch";
+pub const UNSUPPORTED_SIGNATURE_CODE: &str = "native.embed.unsupported_signature";
+pub const SIGNATURE_MISMATCH_CODE: &str = "native.embed.signature_mismatch";
+pub const HOST_FUNCTION_MISSING_CODE: &str = "native.embed.host_function_missing";
+pub const HOST_CALL_FAILED_CODE: &str = "native.embed.host_call_failed";
+pub const MARSHAL_FAILED_CODE: &str = "native.embed.marshal_failed";
+pub const TRAP_CODE: &str = "native.embed.trap";
+
+static EMBED_AUDIT_EVENTS: Lazy<Mutex<Vec<AuditEvent>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemlEmbedStatus {
+    Ok = 0,
+    Error = 1,
+    AbiMismatch = 2,
+    UnsupportedTarget = 3,
+    InvalidArgument = 4,
+}
+
+#[repr(C)]
+pub struct RemlEmbedContext {
+    abi_version: String,
+    last_error: Option<CString>,
+    engine: Engine,
+    module: Option<LoadedModule>,
+    instance: Option<EmbedInstance>,
+    host_functions: HashMap<String, HostFunction>,
+}
+
+impl RemlEmbedContext {
+    fn new(abi_version: String) -> Self {
+        Self {
+            abi_version,
+            last_error: None,
+            engine: Engine::default(),
+            module: None,
+            instance: None,
+            host_functions: HashMap::new(),
+        }
+    }
+
+    /// 失敗を構造化診断（JSON）として `last_error` に記録する。
+    fn set_error(&mut self, diagnostic: EmbedDiagnostic) {
+        let message = diagnostic.to_json().to_string();
+        self.last_error = CString::new(message)
+            .ok()
+            .or_else(|| Some(CString::new("unknown error").unwrap()));
+    }
+
+    /// 結果を `last_error` とステータスへ反映する。
+    fn finish(&mut self, result: Result<(), EmbedDiagnostic>) -> RemlEmbedStatus {
+        match result {
+            Ok(()) => {
+                self.last_error = None;
+                RemlEmbedStatus::Ok
+            }
+            Err(diagnostic) => {
+                self.set_error(diagnostic);
+                RemlEmbedStatus::Error
+            }
+        }
+    }
+
+    /// ソースをコンパイルし、実行できる状態にする。
+    fn load(&mut self, source: &str) -> Result<(), EmbedDiagnostic> {
+        let Some(compiler) = compiler::embed_module_compiler() else {
+            return Err(EmbedDiagnostic::new(
+                COMPILER_UNAVAILABLE_CODE,
+                "埋め込みモジュールコンパイラが登録されていません",
+            ));
+        };
+        let compiled = compiler.compile(source).map_err(|diagnostics| {
+            EmbedDiagnostic::new(COMPILE_FAILED_CODE, "module をコンパイルできません")
+                .with_diagnostics(diagnostics)
+        })?;
+        self.module = Some(LoadedModule::new(&self.engine, compiled)?);
+        self.instance = None;
+        Ok(())
+    }
+
+    fn register_function(
+        &mut self,
+        name: String,
+        signature: &str,
+        callback: RemlHostFunction,
+        user_data: *mut c_void,
+    ) -> Result<(), EmbedDiagnostic> {
+        let signature = EmbedSignature::parse(signature).map_err(|reason| {
+            EmbedDiagnostic::new(
+                UNSUPPORTED_SIGNATURE_CODE,
+                format!("ホスト関数 {name} のシグネチャを扱えません: {reason}"),
+            )
+        })?;
+        self.host_functions.insert(
+            name,
+            HostFunction {
+                signature,
+                callback,
+                user_data: user_data as usize,
+            },
+        );
+        self.instance = None;
+        Ok(())
+    }
+
+    fn call(
+        &mut self,
+        entrypoint: &str,
+        args: &[Arc<EmbedValue>],
+    ) -> Result<Arc<EmbedValue>, EmbedDiagnostic> {
+        let Some(module) = self.module.as_ref() else {
+            return Err(EmbedDiagnostic::new(
+                MODULE_NOT_LOADED_CODE,
+                "module がロードされていません",
+            ));
+        };
+        let signature = module.export_signature(entrypoint)?;
+        if self.instance.is_none() {
+            self.instance = Some(EmbedInstance::instantiate(
+                &self.engine,
+                module,
+                &self.host_functions,
+            )?);
+        }
+        let instance = self.instance.as_mut().expect("インスタンス化済み");
+        instance.call(entrypoint, &signature, args)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn reml_create_context(
+    abi_version: *const c_char,
+    out_context: *mut *mut RemlEmbedContext,
+) -> RemlEmbedStatus {
+    if out_context.is_null() {
+        record_embed_audit("reml_create_context", "unknown", None);
+        return RemlEmbedStatus::InvalidArgument;
+    }
+
+    let abi_version = match read_c_string(abi_version) {
+        Some(value) => value,
+        None => {
+            record_embed_audit("reml_create_context", "unknown", None);
+            return RemlEmbedStatus::InvalidArgument;
+        }
+    };
+
+    if abi_version != EMBED_ABI_VERSION {
+        record_embed_audit(
+            "reml_create_context",
+            &abi_version,
+            Some(("native.embed.abi_mismatch", true)),
+        );
+        return RemlEmbedStatus::AbiMismatch;
+    }
+
+    if !is_supported_target() {
+        record_embed_audit(
+            "reml_create_context",
+            &abi_version,
+            Some(("native.embed.unsupported_target", true)),
+        );
+        return RemlEmbedStatus::UnsupportedTarget;
+    }
+
+    let context = Box::new(RemlEmbedContext::new(abi_version.clone()));
+    unsafe {
+        *out_context = Box::into_raw(context);
+    }
+    record_embed_audit("reml_create_context", &abi_version, None);
+    RemlEmbedStatus::Ok
+}
+
+#[no_mangle]
+pub extern "C" fn reml_load_module(
+    context: *mut RemlEmbedContext,
+    source: *const u8,
+    length: usize,
+) -> RemlEmbedStatus {
+    let context = unsafe { context.as_mut() };
+    let Some(context) = context else {
+        record_embed_audit("reml_load_module", "unknown", None);
+        return RemlEmbedStatus::InvalidArgument;
+    };
+
+    if source.is_null() || length == 0 {
+        context.set_error(EmbedDiagnostic::new(
+            INVALID_ARGUMENT_CODE,
+            "source が空です",
+        ));
+        record_embed_audit("reml_load_module", &context.abi_version, None);
+        return RemlEmbedStatus::InvalidArgument;
+    }
+
+    let bytes = unsafe { std::slice::from_raw_parts(source, length) };
+    let source = match std::str::from_utf8(bytes) {
+        Ok(value) => value.to_string(),
+        Err(_) => {
+            context.set_error(EmbedDiagnostic::new(
+                INVALID_ARGUMENT_CODE,
+                "source が UTF-8 ではありません",
+            ));
+            record_embed_audit("reml_load_module", &context.abi_version, None);
+            return RemlEmbedStatus::Error;
+        }
+    };
+
+    let loaded = context.load(&source);
+    let status = context.finish(loaded);
+    record_embed_audit("reml_load_module", &context.abi_version, None);
+    status
+}
+
+/// ホスト関数を登録する。`signature` は `(Int, Str) -> Int` 形式で、
+/// 同名の extern 宣言を持つモジュールの実行時に結び付ける。
+#[no_mangle]
+pub extern "C" fn reml_register_function(
+    context: *mut RemlEmbedContext,
+    name: *const c_char,
+    signature: *const c_char,
+    function: Option<RemlHostFunction>,
+    user_data: *mut c_void,
+) -> RemlEmbedStatus {
+    let context = unsafe { context.as_mut() };
+    let Some(context) = context else {
+        record_embed_audit("reml_register_function", "unknown", None);
+        return RemlEmbedStatus::InvalidArgument;
+    };
+
+    let (Some(name), Some(signature), Some(function)) =
+        (read_c_string(name), read_c_string(signature), function)
+    else {
+        context.set_error(EmbedDiagnostic::new(
+            INVALID_ARGUMENT_CODE,
+            "name/signature/function は必須です",
+        ));
+        record_embed_audit("reml_register_function", &context.abi_version, None);
+        return RemlEmbedStatus::InvalidArgument;
+    };
+
+    let registered = context.register_function(name, &signature, function, user_data);
+    let status = context.finish(registered);
+    record_embed_audit("reml_register_function", &context.abi_version, None);
+    status
+}
+
+#[no_mangle]
+pub extern "C" fn reml_run(
+    context: *mut RemlEmbedContext,
+    entrypoint: *const c_char,
+) -> RemlEmbedStatus {
+    let context = unsafe { context.as_mut() };
+    let Some(context) = context else {
+        record_embed_audit("reml_run", "unknown", None);
+        return RemlEmbedStatus::InvalidArgument;
+    };
+
+    let entrypoint = read_c_string(entrypoint).unwrap_or_else(|| "main".to_string());
+    let result = context.call(&entrypoint, &[]).map(|_| ());
+    let status = context.finish(result);
+    record_embed_audit("reml_run", &context.abi_version, None);
+    status
+}
+
+/// エントリポイントを引数付きで呼び出す。
+///
+/// `args` は Borrowed で渡し、`out_result` には Transferred の戻り値を書き込む
+/// （不要なら NULL を渡してよい。受け取った場合は `reml_embed_value_dec_ref` で手放す）。
+#[no_mangle]
+pub extern "C" fn reml_call(
+    context: *mut RemlEmbedContext,
+    entrypoint: *const c_char,
+    args: *const *const EmbedValue,
+    argc: usize,
+    out_result: *mut *mut EmbedValue,
+) -> RemlEmbedStatus {
+    let context = unsafe { context.as_mut() };
+    let Some(context) = context else {
+        record_embed_audit("reml_call", "unknown", None);
+        return RemlEmbedStatus::InvalidArgument;
+    };
+
+    let arg_pointers = match (argc, args.is_null()) {
+        (0, _) => Some(&[][..]),
+        (_, true) => None,
+        (_, false) => Some(unsafe { std::slice::from_raw_parts(args, argc) }),
+    };
+    let arg_pointers = arg_pointers.filter(|pointers| {
+        pointers
+            .iter()
+            .all(|arg| unsafe { borrow_value(*arg) }.is_some())
+    });
+    let (Some(entrypoint), Some(arg_pointers)) = (read_c_string(entrypoint), arg_pointers) else {
+        context.set_error(EmbedDiagnostic::new(
+            INVALID_ARGUMENT_CODE,
+            "entrypoint と引数は NULL にできません",
+        ));
+        record_embed_audit("reml_call", &context.abi_version, None);
+        return RemlEmbedStatus::InvalidArgument;
+    };
+
+    let args: Vec<Arc<EmbedValue>> = arg_pointers
+        .iter()
+        .map(|arg| unsafe { retain_borrowed(*arg) })
+        .collect();
+    let result = context.call(&entrypoint, &args).map(|value| {
+        if let Some(out_result) = unsafe { out_result.as_mut() } {
+            *out_result = transfer_to_host(value);
+        }
+    });
+    let status = context.finish(result);
+    record_embed_audit("reml_call", &context.abi_version, None);
+    status
+}
+
+#[no_mangle]
+pub extern "C" fn reml_dispose_context(context: *mut RemlEmbedContext) -> RemlEmbedStatus {
+    if context.is_null() {
+        record_embed_audit("reml_dispose_context", "unknown", None);
+        return RemlEmbedStatus::InvalidArgument;
+    }
+
+    let abi_version = unsafe { &(*context).abi_version }.clone();
+    unsafe {
+        drop(Box::from_raw(context));
+    }
+    record_embed_audit("reml_dispose_context", &abi_version, None);
+    RemlEmbedStatus::Ok
+}
+
+#[no_mangle]
+pub extern "C" fn reml_last_error(context: *const RemlEmbedContext) -> *const c_char {
+    let context = unsafe { context.as_ref() };
+    let Some(context) = context else {
+        return ptr::null();
+    };
+    context
+        .last_error
+        .as_ref()
+        .map(|value| value.as_ptr())
+        .unwrap_or(ptr::null())
+}
+
+/// 記録済みの埋め込み監査イベントを取得してクリアする。
+pub fn take_embed_audit_events() -> Vec<AuditEvent> {
+    let mut events = EMBED_AUDIT_EVENTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let drained = events.clone();
+    events.clear();
+    drained
+}
+
+fn read_c_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    unsafe {
+        CStr::from_ptr(ptr)
+            .to_str()
+            .ok()
+            .map(|value| value.to_string())
+    }
+}
+
+fn is_supported_target() -> bool {
+    if let Ok(value) = std::env::var("REML_EMBED_FORCE_UNSUPPORTED") {
+        if value == "1" || value.eq_ignore_ascii_case("true") {
+            return false;
+        }
+    }
+    matches!(std::env::consts::OS, "macos" | "linux" | "windows")
+}
+
+fn record_embed_audit(entrypoint: &str, abi_version: &str, marker: Option<(&str, bool)>) {
+    let timestamp = OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".into());
+    let mut envelope =
+        AuditEnvelope::from_parts(JsonMap::new(), None, None, Some(EMBED_CAPABILITY.into()));
+    insert_embed_entrypoint_audit_metadata(&mut envelope, entrypoint, abi_version);
+    if let Some((key, value)) = marker {
+        envelope
+            .metadata
+            .insert(key.to_string(), Value::Bool(value));
+    }
+    let event = AuditEvent::new(timestamp, envelope);
+    let mut events = EMBED_AUDIT_EVENTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    events.push(event);
+}
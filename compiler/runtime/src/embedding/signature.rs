@@ -0,0 +1,278 @@
+//! 埋め込み境界で受け渡す値の型とシグネチャ。
+//!
+//! ホスト関数の登録やエントリポイントの呼び出しは `(Int, Str) -> [Int]` のような
+//! 文字列でシグネチャを表す。レコードは `{name: Str, age: Int}` と書き、フィールドの並びが
+//! 線形メモリ上の要素順になる。
+
+use std::fmt;
+
+/// 境界を越えられる値の型。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmbedType {
+    Unit,
+    Int,
+    Float,
+    Bool,
+    Str,
+    Array(Box<EmbedType>),
+    Record(Vec<(String, EmbedType)>),
+}
+
+impl EmbedType {
+    /// 型の表記を解釈する。
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = SignatureParser::new(text);
+        let ty = parser.parse_type()?;
+        parser.expect_end()?;
+        Ok(ty)
+    }
+
+    /// フィールド名を無視して、線形メモリ上の表現が一致するか。
+    pub fn is_compatible(&self, other: &EmbedType) -> bool {
+        match (self, other) {
+            (EmbedType::Array(left), EmbedType::Array(right)) => left.is_compatible(right),
+            (EmbedType::Record(left), EmbedType::Record(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right)
+                        .all(|((_, left), (_, right))| left.is_compatible(right))
+            }
+            (left, right) => left == right,
+        }
+    }
+}
+
+impl fmt::Display for EmbedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmbedType::Unit => write!(f, "Unit"),
+            EmbedType::Int => write!(f, "Int"),
+            EmbedType::Float => write!(f, "Float"),
+            EmbedType::Bool => write!(f, "Bool"),
+            EmbedType::Str => write!(f, "Str"),
+            EmbedType::Array(element) => write!(f, "[{element}]"),
+            EmbedType::Record(fields) => {
+                write!(f, "{{")?;
+                for (index, (name, ty)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: {ty}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// 関数シグネチャ。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbedSignature {
+    pub params: Vec<EmbedType>,
+    pub ret: EmbedType,
+}
+
+impl EmbedSignature {
+    /// `(T, ...) -> R` 形式のシグネチャを解釈する。
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = SignatureParser::new(text);
+        parser.expect('(')?;
+        let mut params = Vec::new();
+        if !parser.eat(')') {
+            loop {
+                params.push(parser.parse_type()?);
+                if parser.eat(')') {
+                    break;
+                }
+                parser.expect(',')?;
+            }
+        }
+        parser.expect('-')?;
+        parser.expect('>')?;
+        let ret = parser.parse_type()?;
+        parser.expect_end()?;
+        Ok(Self { params, ret })
+    }
+
+    /// 引数と戻り値の表現が一致するか（レコードのフィールド名は問わない）。
+    pub fn is_compatible(&self, other: &EmbedSignature) -> bool {
+        self.params.len() == other.params.len()
+            && self
+                .params
+                .iter()
+                .zip(&other.params)
+                .all(|(left, right)| left.is_compatible(right))
+            && self.ret.is_compatible(&other.ret)
+    }
+}
+
+impl fmt::Display for EmbedSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        for (index, param) in self.params.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{param}")?;
+        }
+        write!(f, ") -> {}", self.ret)
+    }
+}
+
+struct SignatureParser<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> SignatureParser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, offset: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.offset..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.offset += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(expected) {
+            self.offset += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("`{expected}` が必要です")))
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.rest().is_empty() {
+            Ok(())
+        } else {
+            Err(self.error("余分な入力があります"))
+        }
+    }
+
+    fn error(&self, reason: &str) -> String {
+        format!("型 `{}` の {} 文字目: {reason}", self.text, self.offset)
+    }
+
+    fn identifier(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let length = rest
+            .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return None;
+        }
+        self.offset += length;
+        Some(&rest[..length])
+    }
+
+    fn parse_type(&mut self) -> Result<EmbedType, String> {
+        if self.eat('[') {
+            let element = self.parse_type()?;
+            self.expect(']')?;
+            return Ok(EmbedType::Array(Box::new(element)));
+        }
+        if self.eat('{') {
+            let mut fields = Vec::new();
+            if !self.eat('}') {
+                loop {
+                    let Some(name) = self.identifier() else {
+                        return Err(self.error("フィールド名が必要です"));
+                    };
+                    self.expect(':')?;
+                    let ty = self.parse_type()?;
+                    if fields.iter().any(|(existing, _)| existing == name) {
+                        return Err(self.error(&format!("フィールド `{name}` が重複しています")));
+                    }
+                    fields.push((name.to_string(), ty));
+                    if self.eat('}') {
+                        break;
+                    }
+                    self.expect(',')?;
+                }
+            }
+            return Ok(EmbedType::Record(fields));
+        }
+        if self.eat('(') {
+            self.expect(')')?;
+            return Ok(EmbedType::Unit);
+        }
+        let Some(name) = self.identifier() else {
+            return Err(self.error("型名が必要です"));
+        };
+        match name {
+            "Unit" | "unit" => Ok(EmbedType::Unit),
+            "Int" | "Int64" | "i64" => Ok(EmbedType::Int),
+            "Float" | "f64" => Ok(EmbedType::Float),
+            "Bool" | "bool" => Ok(EmbedType::Bool),
+            "Str" | "String" => Ok(EmbedType::Str),
+            other => Err(self.error(&format!("型 `{other}` は埋め込み境界で扱えません"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_signatures() {
+        let signature =
+            EmbedSignature::parse("(Int, {name: Str, scores: [Float]}) -> [Bool]").unwrap();
+        assert_eq!(
+            signature.params,
+            vec![
+                EmbedType::Int,
+                EmbedType::Record(vec![
+                    ("name".into(), EmbedType::Str),
+                    (
+                        "scores".into(),
+                        EmbedType::Array(Box::new(EmbedType::Float))
+                    ),
+                ]),
+            ]
+        );
+        assert_eq!(signature.ret, EmbedType::Array(Box::new(EmbedType::Bool)));
+        assert_eq!(
+            signature.to_string(),
+            "(Int, {name: Str, scores: [Float]}) -> [Bool]"
+        );
+        assert_eq!(
+            EmbedSignature::parse("() -> ()").unwrap().ret,
+            EmbedType::Unit
+        );
+    }
+
+    #[test]
+    fn rejects_types_outside_the_boundary() {
+        assert!(EmbedType::parse("Ptr<u8>").is_err());
+        assert!(EmbedType::parse("{a: Int, a: Int}").is_err());
+        assert!(EmbedSignature::parse("(Int -> Int").is_err());
+    }
+
+    #[test]
+    fn record_field_names_do_not_affect_compatibility() {
+        let host = EmbedSignature::parse("({x: Int, y: Float}) -> Unit").unwrap();
+        let module = EmbedSignature::parse("({left: Int, right: Float}) -> ()").unwrap();
+        assert!(host.is_compatible(&module));
+        let swapped = EmbedSignature::parse("({y: Float, x: Int}) -> Unit").unwrap();
+        assert!(!host.is_compatible(&swapped));
+    }
+}
@@ -0,0 +1,333 @@
+//! ホストと Reml の間で受け渡す値（`reml_embed_value_t`）。
+//!
+//! 値は参照カウント付きの不変オブジェクトで、C には `Arc::into_raw` のポインタを渡す。
+//! 所有権の規則は `reml_ffi_bridge.h` と同じで、Borrowed で受け取った値を保持する場合は
+//! `reml_embed_value_inc_ref` で参照を増やし、Transferred で受け取った値は受け手が
+//! `reml_embed_value_dec_ref` で手放す。
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use std::sync::Arc;
+
+use super::signature::EmbedType;
+
+/// 境界を越える値。配列とレコードの要素も参照カウント付きで共有する。
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmbedValue {
+    Unit,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    Array(Vec<Arc<EmbedValue>>),
+    Record(Vec<(String, Arc<EmbedValue>)>),
+}
+
+/// `reml_embed_value_kind` が返す値の種別。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemlEmbedValueKind {
+    Unit = 0,
+    Int = 1,
+    Float = 2,
+    Bool = 3,
+    String = 4,
+    Array = 5,
+    Record = 6,
+}
+
+impl EmbedValue {
+    pub fn kind(&self) -> RemlEmbedValueKind {
+        match self {
+            EmbedValue::Unit => RemlEmbedValueKind::Unit,
+            EmbedValue::Int(_) => RemlEmbedValueKind::Int,
+            EmbedValue::Float(_) => RemlEmbedValueKind::Float,
+            EmbedValue::Bool(_) => RemlEmbedValueKind::Bool,
+            EmbedValue::Str(_) => RemlEmbedValueKind::String,
+            EmbedValue::Array(_) => RemlEmbedValueKind::Array,
+            EmbedValue::Record(_) => RemlEmbedValueKind::Record,
+        }
+    }
+
+    /// レコードのフィールドを名前で引く。
+    pub fn field(&self, name: &str) -> Option<&Arc<EmbedValue>> {
+        match self {
+            EmbedValue::Record(fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// 値が型に合うか。レコードは型の全フィールドを名前で持っていればよい。
+    pub fn conforms_to(&self, ty: &EmbedType) -> bool {
+        match (self, ty) {
+            (EmbedValue::Unit, EmbedType::Unit)
+            | (EmbedValue::Int(_), EmbedType::Int)
+            | (EmbedValue::Float(_), EmbedType::Float)
+            | (EmbedValue::Bool(_), EmbedType::Bool)
+            | (EmbedValue::Str(_), EmbedType::Str) => true,
+            (EmbedValue::Array(items), EmbedType::Array(element)) => {
+                items.iter().all(|item| item.conforms_to(element))
+            }
+            (EmbedValue::Record(_), EmbedType::Record(fields)) => {
+                fields.iter().all(|(name, field_ty)| {
+                    self.field(name)
+                        .is_some_and(|value| value.conforms_to(field_ty))
+                })
+            }
+            _ => false,
+        }
+    }
+
+    fn into_raw(self) -> *mut EmbedValue {
+        Arc::into_raw(Arc::new(self)) as *mut EmbedValue
+    }
+}
+
+/// C から渡されたポインタを借用する。
+///
+/// # Safety
+/// `value` は NULL か、このモジュールが返した生存中の値でなければならない。
+pub(crate) unsafe fn borrow_value<'a>(value: *const EmbedValue) -> Option<&'a EmbedValue> {
+    value.as_ref()
+}
+
+/// Borrowed で受け取ったポインタから、参照を 1 つ増やした `Arc` を作る。
+///
+/// # Safety
+/// `value` は NULL でない生存中の値でなければならない。
+pub(crate) unsafe fn retain_borrowed(value: *const EmbedValue) -> Arc<EmbedValue> {
+    Arc::increment_strong_count(value);
+    Arc::from_raw(value)
+}
+
+/// Transferred で受け取ったポインタの所有権を引き取る。
+///
+/// # Safety
+/// `value` は NULL でない生存中の値で、呼び出し側がその参照を手放していなければならない。
+pub(crate) unsafe fn take_transferred(value: *mut EmbedValue) -> Arc<EmbedValue> {
+    Arc::from_raw(value as *const EmbedValue)
+}
+
+/// 値の所有権を C へ渡す（Transferred）。
+pub(crate) fn transfer_to_host(value: Arc<EmbedValue>) -> *mut EmbedValue {
+    Arc::into_raw(value) as *mut EmbedValue
+}
+
+#[no_mangle]
+pub extern "C" fn reml_embed_value_unit() -> *mut EmbedValue {
+    EmbedValue::Unit.into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn reml_embed_value_int(value: i64) -> *mut EmbedValue {
+    EmbedValue::Int(value).into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn reml_embed_value_float(value: f64) -> *mut EmbedValue {
+    EmbedValue::Float(value).into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn reml_embed_value_bool(value: c_int) -> *mut EmbedValue {
+    EmbedValue::Bool(value != 0).into_raw()
+}
+
+/// UTF-8 のバイト列から文字列値を作る。UTF-8 でなければ NULL を返す。
+#[no_mangle]
+pub extern "C" fn reml_embed_value_string(data: *const c_char, length: usize) -> *mut EmbedValue {
+    if data.is_null() && length > 0 {
+        return ptr::null_mut();
+    }
+    let bytes = if length == 0 {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(data as *const u8, length) }
+    };
+    match std::str::from_utf8(bytes) {
+        Ok(text) => EmbedValue::Str(text.to_string()).into_raw(),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// 配列値を作る。要素は Borrowed で受け取り、配列が参照を保持する。
+#[no_mangle]
+pub extern "C" fn reml_embed_value_array(
+    items: *const *const EmbedValue,
+    length: usize,
+) -> *mut EmbedValue {
+    let Some(items) = (unsafe { borrowed_slice(items, length) }) else {
+        return ptr::null_mut();
+    };
+    if items.iter().any(|item| item.is_null()) {
+        return ptr::null_mut();
+    }
+    let items = items
+        .iter()
+        .map(|item| unsafe { retain_borrowed(*item) })
+        .collect();
+    EmbedValue::Array(items).into_raw()
+}
+
+/// レコード値を作る。フィールド値は Borrowed で受け取り、レコードが参照を保持する。
+#[no_mangle]
+pub extern "C" fn reml_embed_value_record(
+    names: *const *const c_char,
+    values: *const *const EmbedValue,
+    length: usize,
+) -> *mut EmbedValue {
+    let (Some(names), Some(values)) = (unsafe { borrowed_slice(names, length) }, unsafe {
+        borrowed_slice(values, length)
+    }) else {
+        return ptr::null_mut();
+    };
+    let mut fields: Vec<(String, Arc<EmbedValue>)> = Vec::with_capacity(length);
+    for (name, value) in names.iter().zip(values) {
+        if name.is_null() || value.is_null() {
+            return ptr::null_mut();
+        }
+        let Ok(name) = unsafe { CStr::from_ptr(*name) }.to_str() else {
+            return ptr::null_mut();
+        };
+        if fields.iter().any(|(existing, _)| existing == name) {
+            return ptr::null_mut();
+        }
+        fields.push((name.to_string(), unsafe { retain_borrowed(*value) }));
+    }
+    EmbedValue::Record(fields).into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn reml_embed_value_inc_ref(value: *const EmbedValue) {
+    if !value.is_null() {
+        unsafe { Arc::increment_strong_count(value) };
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn reml_embed_value_dec_ref(value: *const EmbedValue) {
+    if !value.is_null() {
+        unsafe { Arc::decrement_strong_count(value) };
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn reml_embed_value_kind(value: *const EmbedValue) -> RemlEmbedValueKind {
+    unsafe { borrow_value(value) }
+        .map(EmbedValue::kind)
+        .unwrap_or(RemlEmbedValueKind::Unit)
+}
+
+/// 整数値を読む。整数でなければ 0 を返すため、先に `reml_embed_value_kind` で確かめる。
+#[no_mangle]
+pub extern "C" fn reml_embed_value_as_int(value: *const EmbedValue) -> i64 {
+    match unsafe { borrow_value(value) } {
+        Some(EmbedValue::Int(value)) => *value,
+        _ => 0,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn reml_embed_value_as_float(value: *const EmbedValue) -> f64 {
+    match unsafe { borrow_value(value) } {
+        Some(EmbedValue::Float(value)) => *value,
+        _ => 0.0,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn reml_embed_value_as_bool(value: *const EmbedValue) -> c_int {
+    match unsafe { borrow_value(value) } {
+        Some(EmbedValue::Bool(value)) => c_int::from(*value),
+        _ => 0,
+    }
+}
+
+/// 文字列の UTF-8 バイト列を借用する（NUL 終端ではない）。文字列でなければ NULL を返す。
+#[no_mangle]
+pub extern "C" fn reml_embed_value_as_string(
+    value: *const EmbedValue,
+    out_length: *mut usize,
+) -> *const c_char {
+    let Some(EmbedValue::Str(text)) = (unsafe { borrow_value(value) }) else {
+        return ptr::null();
+    };
+    if let Some(out_length) = unsafe { out_length.as_mut() } {
+        *out_length = text.len();
+    }
+    text.as_ptr() as *const c_char
+}
+
+/// 配列の要素数・レコードのフィールド数・文字列のバイト数。その他の値は 0。
+#[no_mangle]
+pub extern "C" fn reml_embed_value_length(value: *const EmbedValue) -> usize {
+    match unsafe { borrow_value(value) } {
+        Some(EmbedValue::Array(items)) => items.len(),
+        Some(EmbedValue::Record(fields)) => fields.len(),
+        Some(EmbedValue::Str(text)) => text.len(),
+        _ => 0,
+    }
+}
+
+/// 配列の要素を借用する（Borrowed）。範囲外なら NULL を返す。
+#[no_mangle]
+pub extern "C" fn reml_embed_value_array_get(
+    value: *const EmbedValue,
+    index: usize,
+) -> *const EmbedValue {
+    match unsafe { borrow_value(value) } {
+        Some(EmbedValue::Array(items)) => items.get(index).map(Arc::as_ptr).unwrap_or(ptr::null()),
+        _ => ptr::null(),
+    }
+}
+
+/// レコードのフィールドを名前で借用する（Borrowed）。
+#[no_mangle]
+pub extern "C" fn reml_embed_value_record_get(
+    value: *const EmbedValue,
+    name: *const c_char,
+) -> *const EmbedValue {
+    if name.is_null() {
+        return ptr::null();
+    }
+    let Ok(name) = unsafe { CStr::from_ptr(name) }.to_str() else {
+        return ptr::null();
+    };
+    unsafe { borrow_value(value) }
+        .and_then(|value| value.field(name))
+        .map(Arc::as_ptr)
+        .unwrap_or(ptr::null())
+}
+
+/// `index` 番目のフィールド名を借用する（NUL 終端ではない）。範囲外なら NULL を返す。
+#[no_mangle]
+pub extern "C" fn reml_embed_value_record_field_name(
+    value: *const EmbedValue,
+    index: usize,
+    out_length: *mut usize,
+) -> *const c_char {
+    let Some(EmbedValue::Record(fields)) = (unsafe { borrow_value(value) }) else {
+        return ptr::null();
+    };
+    let Some((name, _)) = fields.get(index) else {
+        return ptr::null();
+    };
+    if let Some(out_length) = unsafe { out_length.as_mut() } {
+        *out_length = name.len();
+    }
+    name.as_ptr() as *const c_char
+}
+
+unsafe fn borrowed_slice<'a, T>(items: *const T, length: usize) -> Option<&'a [T]> {
+    if length == 0 {
+        return Some(&[]);
+    }
+    if items.is_null() {
+        return None;
+    }
+    Some(std::slice::from_raw_parts(items, length))
+}
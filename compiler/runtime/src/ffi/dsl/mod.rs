@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Map as JsonMap, Value};
 use std::{fmt, sync::Arc};
 
+pub use crate::ffi::dynamic::callback::{register_callback, FfiCallback};
+
 use crate::{
     audit::AuditEnvelope,
     prelude::ensure::{DiagnosticSeverity, GuardDiagnostic},
@@ -268,6 +270,7 @@ impl FfiLibrary {
             symbol: symbol.to_string(),
             signature: sig,
             library: self.handle.clone(),
+            return_ownership: None,
             call_handler: None,
         })
     }
@@ -299,6 +302,7 @@ pub struct FfiRawFn {
     symbol: String,
     signature: FfiFnSig,
     library: FfiLibraryHandle,
+    return_ownership: Option<Ownership>,
     call_handler: Option<Arc<dyn Fn(&[FfiValue]) -> Result<FfiValue, FfiError> + Send + Sync>>,
 }
 
@@ -308,6 +312,7 @@ impl fmt::Debug for FfiRawFn {
             .field("symbol", &self.symbol)
             .field("signature", &self.signature)
             .field("library", &self.library.audit_label())
+            .field("return_ownership", &self.return_ownership)
             .finish()
     }
 }
@@ -328,6 +333,17 @@ impl FfiRawFn {
         &self.signature
     }
 
+    /// 戻り値ポインタの所有権区分を返す。未指定なら実行エンジンは Borrowed として扱う。
+    pub fn return_ownership(&self) -> Option<Ownership> {
+        self.return_ownership
+    }
+
+    /// 戻り値ポインタの所有権区分を指定する。
+    pub fn with_return_ownership(mut self, ownership: Ownership) -> Self {
+        self.return_ownership = Some(ownership);
+        self
+    }
+
     /// 低レベル呼び出しを行う。
     pub fn call(&self, args: &[FfiValue]) -> Result<FfiValue, FfiError> {
         if let Some(handler) = &self.call_handler {
@@ -551,8 +567,8 @@ impl FfiWrappedFn {
     }
 }
 
-/// FFI ラッパを生成する。
-pub fn wrap(raw: FfiRawFn, spec: FfiWrapSpec) -> Result<FfiWrappedFn, FfiError> {
+/// FFI ラッパを生成する。`spec.ownership` は実行エンジンへ戻り値の所有権として伝える。
+pub fn wrap(mut raw: FfiRawFn, spec: FfiWrapSpec) -> Result<FfiWrappedFn, FfiError> {
     if spec.name.trim().is_empty() {
         return Err(FfiError::new(
             FfiErrorKind::InvalidArgument,
@@ -560,6 +576,9 @@ pub fn wrap(raw: FfiRawFn, spec: FfiWrapSpec) -> Result<FfiWrappedFn, FfiError>
         )
         .with_code(FFI_WRAP_INVALID_ARGUMENT_CODE));
     }
+    if spec.ownership.is_some() {
+        raw.return_ownership = spec.ownership;
+    }
     Ok(FfiWrappedFn { raw, spec })
 }
 
@@ -580,13 +599,20 @@ pub enum FfiValue {
     F64(f64),
     Ptr(Option<usize>),
     ConstPtr(Option<usize>),
-    Struct { name: String },
+    /// 値渡しの構造体。`fields` は `FfiStruct::fields` と同じ順に並べる。
+    Struct {
+        name: String,
+        fields: Vec<FfiValue>,
+    },
     Enum { name: String, value: i64 },
     FnPtr(usize),
+    /// Reml のクロージャを C の関数ポインタとして渡すコールバック。
+    Callback(FfiCallback),
 }
 
 impl FfiValue {
-    fn matches_type(&self, ty: &FfiType) -> bool {
+    /// 値が型に合うか。構造体はフィールドの値まで確かめる。
+    pub fn matches_type(&self, ty: &FfiType) -> bool {
         match (self, ty) {
             (FfiValue::Void, FfiType::Void) => true,
             (FfiValue::Bool(_), FfiType::Bool) => true,
@@ -603,13 +629,19 @@ impl FfiValue {
             // ポインタの内側型は最小実装では検証しない。
             (FfiValue::Ptr(_), FfiType::Ptr(_)) => true,
             (FfiValue::ConstPtr(_), FfiType::ConstPtr(_)) => true,
-            (FfiValue::Struct { name }, FfiType::Struct(def)) => {
-                name.is_empty() || name == &def.name
+            (FfiValue::Struct { name, fields }, FfiType::Struct(def)) => {
+                (name.is_empty() || name == &def.name)
+                    && fields.len() == def.fields.len()
+                    && fields
+                        .iter()
+                        .zip(&def.fields)
+                        .all(|(value, field)| value.matches_type(&field.ty))
             }
             (FfiValue::Enum { name, .. }, FfiType::Enum(def)) => {
                 name.is_empty() || name == &def.name
             }
             (FfiValue::FnPtr(_), FfiType::Fn(_)) => true,
+            (FfiValue::Callback(callback), FfiType::Fn(sig)) => callback.signature() == sig.as_ref(),
             _ => false,
         }
     }
@@ -0,0 +1,654 @@
+//! C から呼び戻される Reml クロージャ（コールバック）。
+//!
+//! C へ渡す関数ポインタには、事前に生成したサンク（スロット × 戻り値クラス）の空きスロットを
+//! 割り当てる。サンクは System V の引数レジスタをすべて受け取り、登録時の `FfiFnSig` に従って
+//! 値を復元してクロージャを呼び出す。クロージャのパニックや `Err` は境界で捕捉し、C には 0 で
+//! 埋めた戻り値を返して失敗を記録する。
+//!
+//! スロットは Rust 側のハンドル（`FfiCallback`）と C 側の `reml_ffi_callback_retain` が持つ
+//! 参照の数で管理し、参照がなくなった時点で解放する。サンクのアドレスはスロットごとに固定のため、
+//! 解放したスロットは未使用のスロットがなくなるまで再利用せず（古いものから順に再利用する）、
+//! C に残った古いポインタが新しい登録を呼び出さないようにする。解放済みのサンクが呼ばれた場合は
+//! 0 を返し、`orphan_callback_calls` に数える。
+
+use std::collections::VecDeque;
+use std::ffi::CStr;
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use once_cell::sync::Lazy;
+use serde_json::{Map as JsonMap, Value};
+
+use super::layout::{decode, layout_of};
+use super::trampoline::{self, classify, words_of, ArgClass, Pair, PassMode};
+use super::trampoline::{INT_REGISTERS, SSE_REGISTERS};
+use crate::audit::AuditEnvelope;
+use crate::ffi::dsl::{ptr, FfiError, FfiErrorKind, FfiFnSig, FfiType, FfiValue};
+
+/// 同時に登録できるコールバックの数。
+pub const FFI_CALLBACK_SLOTS: usize = 64;
+pub const FFI_CALLBACK_POOL_EXHAUSTED_CODE: &str = "ffi.callback.pool_exhausted";
+pub const FFI_CALLBACK_SIGNATURE_UNSUPPORTED_CODE: &str = "ffi.callback.signature_unsupported";
+pub const FFI_CALLBACK_FAILED_CODE: &str = "ffi.callback.failed";
+
+type Closure = dyn Fn(&[FfiValue]) -> Result<FfiValue, FfiError> + Send + Sync;
+
+static SLOTS: Lazy<Mutex<SlotTable>> = Lazy::new(|| {
+    Mutex::new(SlotTable {
+        entries: vec![None; FFI_CALLBACK_SLOTS],
+        retired: VecDeque::new(),
+    })
+});
+static ORPHAN_CALLS: AtomicU64 = AtomicU64::new(0);
+
+fn slots() -> MutexGuard<'static, SlotTable> {
+    SLOTS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+struct SlotTable {
+    entries: Vec<Option<Arc<Registration>>>,
+    /// 解放済みのスロット（解放順）。未使用のスロットが尽きたときだけ先頭から再利用する。
+    retired: VecDeque<usize>,
+}
+
+impl SlotTable {
+    /// 新しい登録に割り当てるスロットを選ぶ。
+    fn vacant(&mut self) -> Option<usize> {
+        let unused = self
+            .entries
+            .iter()
+            .enumerate()
+            .position(|(slot, entry)| entry.is_none() && !self.retired.contains(&slot));
+        unused.or_else(|| self.retired.pop_front())
+    }
+
+    fn find(&self, code_ptr: usize) -> Option<usize> {
+        self.entries.iter().position(|slot| {
+            slot.as_ref()
+                .is_some_and(|registration| registration.code_ptr == code_ptr)
+        })
+    }
+
+    /// 参照を 1 つ減らし、最後の参照ならスロットから外した登録を返す。
+    fn release_holder(&mut self, slot: usize) -> Option<Arc<Registration>> {
+        let registration = self.entries[slot].as_ref()?;
+        if registration.holders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.retired.push_back(slot);
+            return self.entries[slot].take();
+        }
+        None
+    }
+}
+
+enum CallbackTarget {
+    Closure(Arc<Closure>),
+    /// コンパイル済みクロージャ（`reml_closure_t` の env と code_ptr）。env を先頭引数に渡す。
+    Compiled {
+        env: usize,
+        code: usize,
+    },
+}
+
+struct Registration {
+    name: String,
+    signature: FfiFnSig,
+    slot: usize,
+    code_ptr: usize,
+    target: CallbackTarget,
+    /// 参照の数。`SLOTS` のロック中にだけ増減させる。
+    holders: AtomicUsize,
+    invocations: AtomicU64,
+    failures: AtomicU64,
+    last_failure: Mutex<Option<String>>,
+}
+
+/// コールバックの呼び出し状況。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FfiCallbackStats {
+    pub invocations: u64,
+    pub failures: u64,
+    pub last_failure: Option<String>,
+}
+
+/// C へ渡せるコールバックのハンドル。
+///
+/// すべてのハンドルを破棄し、C 側の参照も解放されるとスロットが空く。C がライブラリ内部に
+/// 関数ポインタを保持し続ける場合は `retain_for_foreign` で参照を渡しておく。
+pub struct FfiCallback {
+    registration: Arc<Registration>,
+}
+
+impl FfiCallback {
+    pub fn name(&self) -> &str {
+        &self.registration.name
+    }
+
+    pub fn signature(&self) -> &FfiFnSig {
+        &self.registration.signature
+    }
+
+    pub fn slot(&self) -> usize {
+        self.registration.slot
+    }
+
+    /// C へ渡す関数ポインタ。
+    pub fn code_ptr(&self) -> usize {
+        self.registration.code_ptr
+    }
+
+    /// `FfiType::Fn` の引数として渡す値。
+    pub fn as_value(&self) -> FfiValue {
+        FfiValue::Callback(self.clone())
+    }
+
+    /// C 側の参照を 1 つ増やして関数ポインタを返す。C は `reml_ffi_callback_release` で手放す。
+    pub fn retain_for_foreign(&self) -> usize {
+        let _slots = slots();
+        self.registration.holders.fetch_add(1, Ordering::AcqRel);
+        self.registration.code_ptr
+    }
+
+    pub fn stats(&self) -> FfiCallbackStats {
+        let registration = &self.registration;
+        FfiCallbackStats {
+            invocations: registration.invocations.load(Ordering::Acquire),
+            failures: registration.failures.load(Ordering::Acquire),
+            last_failure: registration.last_failure().clone(),
+        }
+    }
+}
+
+impl Clone for FfiCallback {
+    fn clone(&self) -> Self {
+        let _slots = slots();
+        self.registration.holders.fetch_add(1, Ordering::AcqRel);
+        Self {
+            registration: Arc::clone(&self.registration),
+        }
+    }
+}
+
+impl Drop for FfiCallback {
+    fn drop(&mut self) {
+        // 登録を手放すとクロージャが捕捉したハンドルも破棄されるため、ロックの外で落とす。
+        let released = slots().release_holder(self.registration.slot);
+        drop(released);
+    }
+}
+
+impl fmt::Debug for FfiCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FfiCallback")
+            .field("name", &self.registration.name)
+            .field("slot", &self.registration.slot)
+            .field("signature", &self.registration.signature)
+            .finish()
+    }
+}
+
+/// クロージャをコールバックとして登録する。
+pub fn register_callback<F>(
+    name: impl Into<String>,
+    signature: FfiFnSig,
+    closure: F,
+) -> Result<FfiCallback, FfiError>
+where
+    F: Fn(&[FfiValue]) -> Result<FfiValue, FfiError> + Send + Sync + 'static,
+{
+    register(
+        name.into(),
+        signature,
+        CallbackTarget::Closure(Arc::new(closure)),
+    )
+}
+
+/// 監査メタデータ `ffi.callback` を記録してコールバックを登録する。
+pub fn register_callback_with_audit<F>(
+    name: impl Into<String>,
+    signature: FfiFnSig,
+    closure: F,
+    envelope: &mut AuditEnvelope,
+) -> Result<FfiCallback, FfiError>
+where
+    F: Fn(&[FfiValue]) -> Result<FfiValue, FfiError> + Send + Sync + 'static,
+{
+    let name = name.into();
+    let result = register(
+        name.clone(),
+        signature.clone(),
+        CallbackTarget::Closure(Arc::new(closure)),
+    );
+    insert_callback_audit_metadata(envelope, &name, &signature, "closure", &result);
+    result
+}
+
+/// コンパイル済みクロージャ（`reml_closure_env` / `reml_closure_code_ptr`）を登録する。
+///
+/// `code` は env を先頭引数に取り、続けて `signature` の引数を取る C 関数として呼ぶ。
+///
+/// # Safety
+/// `code` はそのシグネチャの関数を指し、`env` はコールバックが登録されている間有効であること。
+pub unsafe fn register_compiled_callback(
+    name: impl Into<String>,
+    signature: FfiFnSig,
+    env: usize,
+    code: usize,
+) -> Result<FfiCallback, FfiError> {
+    register(
+        name.into(),
+        signature,
+        CallbackTarget::Compiled { env, code },
+    )
+}
+
+/// 登録中のコールバックの数。
+pub fn active_callbacks() -> usize {
+    slots().entries.iter().filter(|slot| slot.is_some()).count()
+}
+
+/// 解放済みのサンクが呼ばれた回数。
+pub fn orphan_callback_calls() -> u64 {
+    ORPHAN_CALLS.load(Ordering::Acquire)
+}
+
+/// `ffi.callback` 監査メタデータを挿入する。
+pub fn insert_callback_audit_metadata(
+    envelope: &mut AuditEnvelope,
+    name: &str,
+    signature: &FfiFnSig,
+    target: &str,
+    result: &Result<FfiCallback, FfiError>,
+) {
+    let mut obj = JsonMap::new();
+    obj.insert(
+        "event".into(),
+        Value::String("ffi.callback.register".into()),
+    );
+    obj.insert("name".into(), Value::String(name.to_string()));
+    obj.insert(
+        "signature".into(),
+        serde_json::to_value(signature).unwrap_or(Value::Null),
+    );
+    obj.insert("target".into(), Value::String(target.to_string()));
+    obj.insert("unwind".into(), Value::String("contained".into()));
+    match result {
+        Ok(callback) => {
+            obj.insert("status".into(), Value::String("success".into()));
+            obj.insert("slot".into(), Value::from(callback.slot()));
+        }
+        Err(err) => {
+            obj.insert("status".into(), Value::String("failed".into()));
+            if let Some(code) = err.diagnostic_code() {
+                obj.insert("code".into(), Value::String(code.to_string()));
+            }
+        }
+    }
+    envelope
+        .metadata
+        .insert("ffi.callback".into(), Value::Object(obj));
+}
+
+/// 戻り値のクラス。サンクの戻り値型を決める。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReturnKind {
+    Int,
+    Sse,
+    IntInt,
+    SseSse,
+    IntSse,
+    SseInt,
+}
+
+fn return_mode(returns: &FfiType) -> Result<PassMode, FfiError> {
+    match returns {
+        FfiType::Void => Ok(PassMode::Direct(Vec::new())),
+        ty => classify(ty),
+    }
+}
+
+/// サンクで受け取れるシグネチャか確かめ、戻り値のクラスを返す。
+fn plan(signature: &FfiFnSig) -> Result<ReturnKind, FfiError> {
+    if !cfg!(all(target_arch = "x86_64", unix)) {
+        return Err(unsupported(
+            "コールバックは x86_64 の System V 環境でのみ対応しています",
+        ));
+    }
+    if signature.variadic {
+        return Err(unsupported("可変長引数のコールバックは登録できません"));
+    }
+    use ArgClass::{Integer, Sse};
+    let (kind, mut ints) = match return_mode(&signature.returns)? {
+        // 隠しポインタを第 1 引数で受け取り、`rax` で返す。
+        PassMode::Memory => (ReturnKind::Int, 1),
+        PassMode::Direct(classes) => match classes.as_slice() {
+            [] | [Integer] => (ReturnKind::Int, 0),
+            [Sse] => (ReturnKind::Sse, 0),
+            [Integer, Integer] => (ReturnKind::IntInt, 0),
+            [Sse, Sse] => (ReturnKind::SseSse, 0),
+            [Integer, Sse] => (ReturnKind::IntSse, 0),
+            [Sse, Integer] => (ReturnKind::SseInt, 0),
+            _ => unreachable!("16 バイト以下の値は 2 語以内に収まる"),
+        },
+    };
+    let mut sses = 0;
+    for param in &signature.params {
+        let PassMode::Direct(classes) = classify(param)? else {
+            return Err(unsupported(
+                "16 バイトを超える構造体を値で受け取るコールバックは登録できません",
+            ));
+        };
+        ints += classes.iter().filter(|class| **class == Integer).count();
+        sses += classes.iter().filter(|class| **class == Sse).count();
+    }
+    if ints > INT_REGISTERS || sses > SSE_REGISTERS {
+        return Err(unsupported(
+            "引数がレジスタに収まらないコールバックは登録できません",
+        ));
+    }
+    Ok(kind)
+}
+
+fn register(
+    name: String,
+    signature: FfiFnSig,
+    target: CallbackTarget,
+) -> Result<FfiCallback, FfiError> {
+    let kind = plan(&signature)?;
+    let mut slots = slots();
+    let Some(slot) = slots.vacant() else {
+        return Err(FfiError::new(
+            FfiErrorKind::CallFailed,
+            format!("コールバックのスロット（{FFI_CALLBACK_SLOTS} 個）が不足しています"),
+        )
+        .with_code(FFI_CALLBACK_POOL_EXHAUSTED_CODE));
+    };
+    let registration = Arc::new(Registration {
+        name,
+        signature,
+        slot,
+        code_ptr: thunk_address(kind, slot),
+        target,
+        holders: AtomicUsize::new(1),
+        invocations: AtomicU64::new(0),
+        failures: AtomicU64::new(0),
+        last_failure: Mutex::new(None),
+    });
+    slots.entries[slot] = Some(Arc::clone(&registration));
+    Ok(FfiCallback { registration })
+}
+
+impl Registration {
+    fn last_failure(&self) -> MutexGuard<'_, Option<String>> {
+        self.last_failure
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn invoke(&self, ints: [u64; INT_REGISTERS], sses: [u64; SSE_REGISTERS]) -> [u64; 2] {
+        self.invocations.fetch_add(1, Ordering::AcqRel);
+        let failure = match catch_unwind(AssertUnwindSafe(|| self.invoke_inner(&ints, &sses))) {
+            Ok(Ok(words)) => return words,
+            Ok(Err(err)) => err.message,
+            Err(payload) => {
+                let reason = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "不明なパニック".to_string());
+                format!("コールバックがパニックしました: {reason}")
+            }
+        };
+        self.failures.fetch_add(1, Ordering::AcqRel);
+        *self.last_failure() = Some(failure);
+        // 失敗時も C へは 0 で埋めた値を返す。隠しポインタの領域も 0 で埋める。
+        if let Ok(PassMode::Memory) = return_mode(&self.signature.returns) {
+            if let Ok(layout) = layout_of(&self.signature.returns) {
+                unsafe { std::ptr::write_bytes(ints[0] as *mut u8, 0, layout.size) };
+            }
+            return [ints[0], 0];
+        }
+        [0, 0]
+    }
+
+    fn invoke_inner(
+        &self,
+        ints: &[u64; INT_REGISTERS],
+        sses: &[u64; SSE_REGISTERS],
+    ) -> Result<[u64; 2], FfiError> {
+        let returns = self.signature.returns.as_ref();
+        let sret = (return_mode(returns)? == PassMode::Memory).then_some(ints[0]);
+        let mut next_int = usize::from(sret.is_some());
+        let mut next_sse = 0;
+        let mut args = Vec::with_capacity(self.signature.params.len() + 1);
+        for ty in &self.signature.params {
+            let PassMode::Direct(classes) = classify(ty)? else {
+                return Err(unsupported("スタック渡しの引数は受け取れません"));
+            };
+            let bytes: Vec<u8> = classes
+                .iter()
+                .map(|class| match class {
+                    ArgClass::Integer => {
+                        next_int += 1;
+                        ints[next_int - 1]
+                    }
+                    ArgClass::Sse => {
+                        next_sse += 1;
+                        sses[next_sse - 1]
+                    }
+                })
+                .flat_map(u64::to_ne_bytes)
+                .collect();
+            args.push(decode(ty, &bytes)?);
+        }
+
+        let value = match &self.target {
+            CallbackTarget::Closure(closure) => closure(&args)?,
+            #[cfg(all(target_arch = "x86_64", unix))]
+            CallbackTarget::Compiled { env, code } => {
+                let mut signature = self.signature.clone();
+                signature.params.insert(0, ptr(FfiType::Void));
+                args.insert(0, FfiValue::Ptr(Some(*env)));
+                unsafe { trampoline::call(*code as *const c_void, &signature, &args)? }
+            }
+            // `plan` が登録を拒否するため、他のターゲットでは到達しない。
+            #[cfg(not(all(target_arch = "x86_64", unix)))]
+            CallbackTarget::Compiled { .. } => unreachable!("callbacks are x86_64 System V only"),
+        };
+        if !value.matches_type(returns) {
+            return Err(FfiError::new(
+                FfiErrorKind::InvalidArgument,
+                format!("コールバックの戻り値 {value:?} が {returns:?} と一致しません"),
+            )
+            .with_code(FFI_CALLBACK_FAILED_CODE));
+        }
+        if *returns == FfiType::Void {
+            return Ok([0, 0]);
+        }
+        let layout = layout_of(returns)?;
+        let words = words_of(&value, returns, layout)?;
+        if let Some(sret) = sret {
+            let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_ne_bytes()).collect();
+            unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), sret as *mut u8, layout.size) };
+            return Ok([sret, 0]);
+        }
+        let mut out = [0u64; 2];
+        out[..words.len()].copy_from_slice(&words);
+        Ok(out)
+    }
+}
+
+fn unsupported(message: &str) -> FfiError {
+    FfiError::new(FfiErrorKind::InvalidSignature, message)
+        .with_code(FFI_CALLBACK_SIGNATURE_UNSUPPORTED_CODE)
+}
+
+fn dispatch(slot: usize, ints: [u64; INT_REGISTERS], sses: [u64; SSE_REGISTERS]) -> [u64; 2] {
+    let registration = slots().entries[slot].clone();
+    match registration {
+        Some(registration) => registration.invoke(ints, sses),
+        None => {
+            ORPHAN_CALLS.fetch_add(1, Ordering::AcqRel);
+            [0, 0]
+        }
+    }
+}
+
+/// サンクの戻り値型。`dispatch` の 2 語を `rax`/`rdx`・`xmm0`/`xmm1` へ載せる。
+trait ThunkReturn {
+    fn from_words(words: [u64; 2]) -> Self;
+}
+
+impl ThunkReturn for u64 {
+    fn from_words(words: [u64; 2]) -> Self {
+        words[0]
+    }
+}
+
+impl ThunkReturn for f64 {
+    fn from_words(words: [u64; 2]) -> Self {
+        f64::from_bits(words[0])
+    }
+}
+
+impl ThunkReturn for Pair<u64, u64> {
+    fn from_words(words: [u64; 2]) -> Self {
+        Pair(words[0], words[1])
+    }
+}
+
+impl ThunkReturn for Pair<f64, f64> {
+    fn from_words(words: [u64; 2]) -> Self {
+        Pair(f64::from_bits(words[0]), f64::from_bits(words[1]))
+    }
+}
+
+impl ThunkReturn for Pair<u64, f64> {
+    fn from_words(words: [u64; 2]) -> Self {
+        Pair(words[0], f64::from_bits(words[1]))
+    }
+}
+
+impl ThunkReturn for Pair<f64, u64> {
+    fn from_words(words: [u64; 2]) -> Self {
+        Pair(f64::from_bits(words[0]), words[1])
+    }
+}
+
+type Thunk<R> =
+    extern "C" fn(u64, u64, u64, u64, u64, u64, f64, f64, f64, f64, f64, f64, f64, f64) -> R;
+
+/// 整数レジスタ 6 本と SSE レジスタ 8 本をそのまま受け取り、スロットの登録へ振り分ける。
+#[allow(clippy::too_many_arguments)]
+extern "C" fn thunk<R: ThunkReturn, const SLOT: usize>(
+    i0: u64,
+    i1: u64,
+    i2: u64,
+    i3: u64,
+    i4: u64,
+    i5: u64,
+    x0: f64,
+    x1: f64,
+    x2: f64,
+    x3: f64,
+    x4: f64,
+    x5: f64,
+    x6: f64,
+    x7: f64,
+) -> R {
+    let sses = [x0, x1, x2, x3, x4, x5, x6, x7].map(f64::to_bits);
+    R::from_words(dispatch(SLOT, [i0, i1, i2, i3, i4, i5], sses))
+}
+
+macro_rules! thunk_table {
+    ($ret:ty) => {
+        thunk_table!(@ $ret;
+            0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+            32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59
+            60 61 62 63)
+    };
+    (@ $ret:ty; $($slot:literal)*) => {
+        [$(thunk::<$ret, $slot> as Thunk<$ret>),*]
+    };
+}
+
+static INT_THUNKS: [Thunk<u64>; FFI_CALLBACK_SLOTS] = thunk_table!(u64);
+static SSE_THUNKS: [Thunk<f64>; FFI_CALLBACK_SLOTS] = thunk_table!(f64);
+static INT_INT_THUNKS: [Thunk<Pair<u64, u64>>; FFI_CALLBACK_SLOTS] = thunk_table!(Pair<u64, u64>);
+static SSE_SSE_THUNKS: [Thunk<Pair<f64, f64>>; FFI_CALLBACK_SLOTS] = thunk_table!(Pair<f64, f64>);
+static INT_SSE_THUNKS: [Thunk<Pair<u64, f64>>; FFI_CALLBACK_SLOTS] = thunk_table!(Pair<u64, f64>);
+static SSE_INT_THUNKS: [Thunk<Pair<f64, u64>>; FFI_CALLBACK_SLOTS] = thunk_table!(Pair<f64, u64>);
+
+fn thunk_address(kind: ReturnKind, slot: usize) -> usize {
+    match kind {
+        ReturnKind::Int => INT_THUNKS[slot] as *const () as usize,
+        ReturnKind::Sse => SSE_THUNKS[slot] as *const () as usize,
+        ReturnKind::IntInt => INT_INT_THUNKS[slot] as *const () as usize,
+        ReturnKind::SseSse => SSE_SSE_THUNKS[slot] as *const () as usize,
+        ReturnKind::IntSse => INT_SSE_THUNKS[slot] as *const () as usize,
+        ReturnKind::SseInt => SSE_INT_THUNKS[slot] as *const () as usize,
+    }
+}
+
+/// コンパイル済みクロージャをコールバックとして登録し、C へ渡す関数ポインタを返す。
+///
+/// `signature_json` は `FfiFnSig` の JSON。返したポインタの参照は呼び出し側（C）が持ち、
+/// 不要になったら `reml_ffi_callback_release` で手放す。失敗時は NULL を返す。
+// C ABI の関数はポインタの有効性を呼び出し側（ホスト）の責務とする。
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[no_mangle]
+pub extern "C" fn reml_ffi_callback_new(
+    env: *mut c_void,
+    code_ptr: *const c_void,
+    signature_json: *const c_char,
+) -> *const c_void {
+    if code_ptr.is_null() || signature_json.is_null() {
+        return std::ptr::null();
+    }
+    let Ok(text) = unsafe { CStr::from_ptr(signature_json) }.to_str() else {
+        return std::ptr::null();
+    };
+    let Ok(signature) = serde_json::from_str::<FfiFnSig>(text) else {
+        return std::ptr::null();
+    };
+    let name = format!("closure@{:#x}", code_ptr as usize);
+    match unsafe { register_compiled_callback(name, signature, env as usize, code_ptr as usize) } {
+        Ok(callback) => {
+            let code = callback.code_ptr();
+            // ハンドルの参照をそのまま C へ移す。
+            std::mem::forget(callback);
+            code as *const c_void
+        }
+        Err(_) => std::ptr::null(),
+    }
+}
+
+/// コールバックの参照を 1 つ増やす。登録されていないポインタなら -1 を返す。
+#[no_mangle]
+pub extern "C" fn reml_ffi_callback_retain(code_ptr: *const c_void) -> c_int {
+    let slots = slots();
+    match slots.find(code_ptr as usize) {
+        Some(slot) => {
+            if let Some(registration) = &slots.entries[slot] {
+                registration.holders.fetch_add(1, Ordering::AcqRel);
+            }
+            0
+        }
+        None => -1,
+    }
+}
+
+/// コールバックの参照を 1 つ手放す。登録されていないポインタなら -1 を返す。
+#[no_mangle]
+pub extern "C" fn reml_ffi_callback_release(code_ptr: *const c_void) -> c_int {
+    let mut slots = slots();
+    let Some(slot) = slots.find(code_ptr as usize) else {
+        return -1;
+    };
+    let released = slots.release_holder(slot);
+    drop(slots);
+    drop(released);
+    0
+}
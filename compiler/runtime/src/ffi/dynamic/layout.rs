@@ -0,0 +1,285 @@
+//! `FfiType` のメモリレイアウトと値のバイト列変換。
+//!
+//! 構造体は `FfiRepr` に従って配置する。`C` はフィールドを自然境界へ揃え、`Packed` は
+//! 詰めて並べ、`Transparent` は唯一のフィールドと同じ表現になる。
+
+use super::invalid_argument;
+use crate::ffi::dsl::{FfiError, FfiErrorKind, FfiIntRepr, FfiRepr, FfiStruct, FfiType, FfiValue};
+
+/// 型のサイズとアラインメント（バイト）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FfiLayout {
+    pub size: usize,
+    pub align: usize,
+}
+
+impl FfiLayout {
+    fn scalar(size: usize) -> Self {
+        Self { size, align: size }
+    }
+
+    /// 8 バイト単位に切り上げた語数。
+    pub fn words(&self) -> usize {
+        self.size.div_ceil(8)
+    }
+}
+
+/// 型のレイアウトを求める。`Void` は引数や構造体のフィールドに使えない。
+pub fn layout_of(ty: &FfiType) -> Result<FfiLayout, FfiError> {
+    Ok(match ty {
+        FfiType::Void => {
+            return Err(invalid_signature("Void は値として配置できません"));
+        }
+        FfiType::Bool | FfiType::I8 | FfiType::U8 => FfiLayout::scalar(1),
+        FfiType::I16 | FfiType::U16 => FfiLayout::scalar(2),
+        FfiType::I32 | FfiType::U32 | FfiType::F32 => FfiLayout::scalar(4),
+        FfiType::I64 | FfiType::U64 | FfiType::F64 => FfiLayout::scalar(8),
+        FfiType::Ptr(_) | FfiType::ConstPtr(_) | FfiType::Fn(_) => {
+            FfiLayout::scalar(std::mem::size_of::<usize>())
+        }
+        FfiType::Enum(def) => FfiLayout::scalar(int_repr_size(def.repr)),
+        FfiType::Struct(def) => struct_layout(def)?.0,
+    })
+}
+
+/// 構造体のレイアウトと各フィールドのオフセット。
+pub fn struct_layout(def: &FfiStruct) -> Result<(FfiLayout, Vec<usize>), FfiError> {
+    if def.repr == FfiRepr::Transparent {
+        let [field] = def.fields.as_slice() else {
+            return Err(invalid_signature(format!(
+                "repr(transparent) の構造体 `{}` はフィールドを 1 つだけ持てます",
+                def.name
+            )));
+        };
+        return Ok((layout_of(&field.ty)?, vec![0]));
+    }
+    let packed = def.repr == FfiRepr::Packed;
+    let mut offsets = Vec::with_capacity(def.fields.len());
+    let mut offset = 0usize;
+    let mut align = 1usize;
+    for field in &def.fields {
+        let layout = layout_of(&field.ty)?;
+        if !packed {
+            offset = offset.next_multiple_of(layout.align);
+            align = align.max(layout.align);
+        }
+        offsets.push(offset);
+        offset += layout.size;
+    }
+    Ok((
+        FfiLayout {
+            size: offset.next_multiple_of(align),
+            align,
+        },
+        offsets,
+    ))
+}
+
+fn int_repr_size(repr: FfiIntRepr) -> usize {
+    match repr {
+        FfiIntRepr::I8 | FfiIntRepr::U8 => 1,
+        FfiIntRepr::I16 | FfiIntRepr::U16 => 2,
+        FfiIntRepr::I32 | FfiIntRepr::U32 => 4,
+        FfiIntRepr::I64 | FfiIntRepr::U64 => 8,
+    }
+}
+
+/// 構造体を平坦化したスカラー（オフセット・サイズ・浮動小数点か）。
+pub(super) fn scalars(
+    ty: &FfiType,
+    base: usize,
+    out: &mut Vec<(usize, usize, bool)>,
+) -> Result<(), FfiError> {
+    match ty {
+        FfiType::Struct(def) => {
+            let (_, offsets) = struct_layout(def)?;
+            for (field, offset) in def.fields.iter().zip(offsets) {
+                scalars(&field.ty, base + offset, out)?;
+            }
+        }
+        other => {
+            let layout = layout_of(other)?;
+            let is_float = matches!(other, FfiType::F32 | FfiType::F64);
+            out.push((base, layout.size, is_float));
+        }
+    }
+    Ok(())
+}
+
+/// 値を型の表現でバイト列へ書き込む。`out` は型のサイズ以上の長さを持つこと。
+pub fn encode(value: &FfiValue, ty: &FfiType, out: &mut [u8]) -> Result<(), FfiError> {
+    macro_rules! put {
+        ($value:expr) => {{
+            let bytes = $value.to_ne_bytes();
+            out[..bytes.len()].copy_from_slice(&bytes);
+        }};
+    }
+    match (value, ty) {
+        (FfiValue::Bool(value), FfiType::Bool) => put!(u8::from(*value)),
+        (FfiValue::I8(value), FfiType::I8) => put!(value),
+        (FfiValue::U8(value), FfiType::U8) => put!(value),
+        (FfiValue::I16(value), FfiType::I16) => put!(value),
+        (FfiValue::U16(value), FfiType::U16) => put!(value),
+        (FfiValue::I32(value), FfiType::I32) => put!(value),
+        (FfiValue::U32(value), FfiType::U32) => put!(value),
+        (FfiValue::I64(value), FfiType::I64) => put!(value),
+        (FfiValue::U64(value), FfiType::U64) => put!(value),
+        (FfiValue::F32(value), FfiType::F32) => put!(value),
+        (FfiValue::F64(value), FfiType::F64) => put!(value),
+        (FfiValue::Ptr(addr), FfiType::Ptr(_))
+        | (FfiValue::ConstPtr(addr), FfiType::ConstPtr(_))
+        // 可変ポインタは const ポインタの引数へ渡してよい。
+        | (FfiValue::Ptr(addr), FfiType::ConstPtr(_)) => put!(addr.unwrap_or(0)),
+        (FfiValue::FnPtr(addr), FfiType::Fn(_)) => put!(addr),
+        (FfiValue::Callback(callback), FfiType::Fn(_)) if value.matches_type(ty) => {
+            put!(callback.code_ptr())
+        }
+        (FfiValue::Enum { value, .. }, FfiType::Enum(def)) => {
+            let bytes = value.to_ne_bytes();
+            let size = int_repr_size(def.repr);
+            out[..size].copy_from_slice(&bytes[..size]);
+        }
+        (FfiValue::Struct { fields, .. }, FfiType::Struct(def))
+            if value.matches_type(ty) =>
+        {
+            let (_, offsets) = struct_layout(def)?;
+            for ((field, value), offset) in def.fields.iter().zip(fields).zip(offsets) {
+                encode(value, &field.ty, &mut out[offset..])?;
+            }
+        }
+        _ => {
+            return Err(invalid_argument(format!(
+                "値 {value:?} は FFI 型 {ty:?} として渡せません"
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// 型の表現で書かれたバイト列から値を読み出す。
+pub fn decode(ty: &FfiType, bytes: &[u8]) -> Result<FfiValue, FfiError> {
+    macro_rules! get {
+        ($ty:ty) => {{
+            const SIZE: usize = std::mem::size_of::<$ty>();
+            let mut buf = [0u8; SIZE];
+            buf.copy_from_slice(&bytes[..SIZE]);
+            <$ty>::from_ne_bytes(buf)
+        }};
+    }
+    let pointer = |addr: usize| (addr != 0).then_some(addr);
+    Ok(match ty {
+        FfiType::Void => FfiValue::Void,
+        FfiType::Bool => FfiValue::Bool(get!(u8) != 0),
+        FfiType::I8 => FfiValue::I8(get!(i8)),
+        FfiType::U8 => FfiValue::U8(get!(u8)),
+        FfiType::I16 => FfiValue::I16(get!(i16)),
+        FfiType::U16 => FfiValue::U16(get!(u16)),
+        FfiType::I32 => FfiValue::I32(get!(i32)),
+        FfiType::U32 => FfiValue::U32(get!(u32)),
+        FfiType::I64 => FfiValue::I64(get!(i64)),
+        FfiType::U64 => FfiValue::U64(get!(u64)),
+        FfiType::F32 => FfiValue::F32(get!(f32)),
+        FfiType::F64 => FfiValue::F64(get!(f64)),
+        FfiType::Ptr(_) => FfiValue::Ptr(pointer(get!(usize))),
+        FfiType::ConstPtr(_) => FfiValue::ConstPtr(pointer(get!(usize))),
+        FfiType::Fn(_) => FfiValue::FnPtr(get!(usize)),
+        FfiType::Enum(def) => FfiValue::Enum {
+            name: def.name.clone(),
+            value: match def.repr {
+                FfiIntRepr::I8 => i64::from(get!(i8)),
+                FfiIntRepr::U8 => i64::from(get!(u8)),
+                FfiIntRepr::I16 => i64::from(get!(i16)),
+                FfiIntRepr::U16 => i64::from(get!(u16)),
+                FfiIntRepr::I32 => i64::from(get!(i32)),
+                FfiIntRepr::U32 => i64::from(get!(u32)),
+                FfiIntRepr::I64 | FfiIntRepr::U64 => get!(i64),
+            },
+        },
+        FfiType::Struct(def) => {
+            let (_, offsets) = struct_layout(def)?;
+            let fields = def
+                .fields
+                .iter()
+                .zip(offsets)
+                .map(|(field, offset)| decode(&field.ty, &bytes[offset..]))
+                .collect::<Result<Vec<_>, _>>()?;
+            FfiValue::Struct {
+                name: def.name.clone(),
+                fields,
+            }
+        }
+    })
+}
+
+fn invalid_signature(message: impl Into<String>) -> FfiError {
+    FfiError::new(FfiErrorKind::InvalidSignature, message).with_code(super::SIGNATURE_INVALID_CODE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::dsl::FfiField;
+
+    fn record(repr: FfiRepr, fields: Vec<FfiType>) -> FfiStruct {
+        FfiStruct {
+            name: "S".into(),
+            fields: fields
+                .into_iter()
+                .enumerate()
+                .map(|(index, ty)| FfiField {
+                    name: format!("f{index}"),
+                    ty,
+                })
+                .collect(),
+            repr,
+        }
+    }
+
+    #[test]
+    fn repr_controls_padding() {
+        let fields = vec![FfiType::U8, FfiType::I32, FfiType::I16];
+        let (layout, offsets) = struct_layout(&record(FfiRepr::C, fields.clone())).unwrap();
+        assert_eq!((layout.size, layout.align), (12, 4));
+        assert_eq!(offsets, vec![0, 4, 8]);
+        let (layout, offsets) = struct_layout(&record(FfiRepr::Packed, fields)).unwrap();
+        assert_eq!((layout.size, layout.align), (7, 1));
+        assert_eq!(offsets, vec![0, 1, 5]);
+        let transparent = record(FfiRepr::Transparent, vec![FfiType::F64]);
+        assert_eq!(layout_of(&FfiType::Struct(transparent)).unwrap().size, 8);
+        assert!(struct_layout(&record(FfiRepr::Transparent, vec![])).is_err());
+    }
+
+    #[test]
+    fn structs_round_trip_through_bytes() {
+        let ty = FfiType::Struct(record(
+            FfiRepr::C,
+            vec![
+                FfiType::I8,
+                FfiType::F64,
+                FfiType::Struct(record(FfiRepr::Packed, vec![FfiType::U16, FfiType::I32])),
+            ],
+        ));
+        let value = FfiValue::Struct {
+            name: "S".into(),
+            fields: vec![
+                FfiValue::I8(-3),
+                FfiValue::F64(1.5),
+                FfiValue::Struct {
+                    name: "S".into(),
+                    fields: vec![FfiValue::U16(7), FfiValue::I32(-9)],
+                },
+            ],
+        };
+        let mut bytes = vec![0u8; layout_of(&ty).unwrap().size];
+        encode(&value, &ty, &mut bytes).unwrap();
+        assert_eq!(
+            format!("{:?}", decode(&ty, &bytes).unwrap()),
+            format!("{value:?}")
+        );
+        let wrong = FfiValue::Struct {
+            name: "S".into(),
+            fields: vec![FfiValue::I8(0)],
+        };
+        assert!(encode(&wrong, &ty, &mut bytes).is_err());
+    }
+}
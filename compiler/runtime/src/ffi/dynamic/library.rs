@@ -0,0 +1,119 @@
+//! `dlopen`/`dlsym` による共有ライブラリとシンボルの解決。
+//!
+//! `bind_library` に渡したラベルを共有ライブラリ名へ対応付けて開き、ハンドルとシンボルの
+//! アドレスをキャッシュする。開いたライブラリはシンボルを保持し続けるため閉じない。
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_void;
+use std::sync::Mutex;
+
+use super::{FFI_LIBRARY_NOT_FOUND_CODE, FFI_SYMBOL_NOT_FOUND_CODE};
+use crate::ffi::dsl::{FfiError, FfiErrorKind};
+
+/// 開いたライブラリと解決済みシンボルのキャッシュ。
+#[derive(Debug, Default)]
+pub struct LibraryCache {
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    handles: HashMap<String, usize>,
+    symbols: HashMap<(String, String), usize>,
+}
+
+impl LibraryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ライブラリ `label` のシンボル `symbol` のアドレスを返す。
+    pub fn resolve(&self, label: &str, symbol: &str) -> Result<*const c_void, FfiError> {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        let key = (label.to_string(), symbol.to_string());
+        if let Some(addr) = state.symbols.get(&key) {
+            return Ok(*addr as *const c_void);
+        }
+        let handle = match state.handles.get(label) {
+            Some(handle) => *handle,
+            None => {
+                let handle = open_library(label)?;
+                state.handles.insert(label.to_string(), handle);
+                handle
+            }
+        };
+        let name = CString::new(symbol).map_err(|_| {
+            symbol_not_found(format!("シンボル名に NUL が含まれています: {symbol}"))
+        })?;
+        unsafe {
+            libc::dlerror();
+            let addr = libc::dlsym(handle as *mut c_void, name.as_ptr());
+            if addr.is_null() {
+                let reason = last_dl_error().unwrap_or_else(|| "NULL シンボル".to_string());
+                return Err(symbol_not_found(format!(
+                    "シンボル `{symbol}` が `{label}` に見つかりません: {reason}"
+                )));
+            }
+            state.symbols.insert(key, addr as usize);
+            Ok(addr)
+        }
+    }
+}
+
+/// ラベルから `dlopen` に渡す候補名を作る。
+///
+/// `c`/`libc` と `m`/`libm` はシステムのライブラリへ、パスや拡張子付きの名前はそのまま、
+/// それ以外は `lib<name>.so`（macOS では `.dylib`）として探す。
+pub fn library_candidates(label: &str) -> Vec<String> {
+    let system = |linux: &str| {
+        if cfg!(target_os = "macos") {
+            "/usr/lib/libSystem.B.dylib".to_string()
+        } else {
+            linux.to_string()
+        }
+    };
+    match label {
+        "c" | "libc" => return vec![system("libc.so.6")],
+        "m" | "libm" => return vec![system("libm.so.6")],
+        _ => {}
+    }
+    if label.contains('/') || label.contains(".so") || label.ends_with(".dylib") {
+        return vec![label.to_string()];
+    }
+    let base = label.strip_prefix("lib").unwrap_or(label);
+    let extension = if cfg!(target_os = "macos") {
+        "dylib"
+    } else {
+        "so"
+    };
+    vec![format!("lib{base}.{extension}"), label.to_string()]
+}
+
+fn open_library(label: &str) -> Result<usize, FfiError> {
+    let mut reasons = Vec::new();
+    for candidate in library_candidates(label) {
+        let Ok(path) = CString::new(candidate.as_str()) else {
+            continue;
+        };
+        let handle = unsafe { libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if !handle.is_null() {
+            return Ok(handle as usize);
+        }
+        reasons.push(unsafe { last_dl_error() }.unwrap_or(candidate));
+    }
+    Err(FfiError::new(
+        FfiErrorKind::LibraryNotFound,
+        format!("ライブラリ `{label}` を開けません: {}", reasons.join("; ")),
+    )
+    .with_code(FFI_LIBRARY_NOT_FOUND_CODE))
+}
+
+unsafe fn last_dl_error() -> Option<String> {
+    let message = libc::dlerror();
+    (!message.is_null()).then(|| CStr::from_ptr(message).to_string_lossy().into_owned())
+}
+
+fn symbol_not_found(message: String) -> FfiError {
+    FfiError::new(FfiErrorKind::SymbolNotFound, message).with_code(FFI_SYMBOL_NOT_FOUND_CODE)
+}
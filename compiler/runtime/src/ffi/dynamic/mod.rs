@@ -0,0 +1,149 @@
+//! `dlopen` と汎用トランポリンによる FFI 呼び出しエンジン。
+//!
+//! `FfiRawFn` のライブラリラベルとシンボルを実行時に解決し、`FfiFnSig` に従って値を
+//! マーシャリングして呼び出す。戻り値のポインタは `FfiRawFn::return_ownership` に従って
+//! 追跡し、Owned/Transferred のものは `release_foreign_ptr` で解放する。
+//!
+//! 呼び出しは x86_64 System V 専用のトランポリンで行うため、実行エンジンの登録と呼び出しは
+//! そのターゲットでだけビルドする。
+#![cfg_attr(not(all(target_arch = "x86_64", unix)), allow(dead_code, unused_imports))]
+
+pub mod callback;
+pub mod layout;
+pub mod library;
+pub mod trampoline;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::ffi::dsl::{
+    set_ffi_call_executor, FfiCallExecutor, FfiError, FfiErrorKind, FfiRawFn, FfiValue, Ownership,
+};
+
+pub use callback::{
+    active_callbacks, orphan_callback_calls, register_callback_with_audit,
+    register_compiled_callback, FfiCallbackStats, FFI_CALLBACK_FAILED_CODE,
+    FFI_CALLBACK_POOL_EXHAUSTED_CODE, FFI_CALLBACK_SIGNATURE_UNSUPPORTED_CODE, FFI_CALLBACK_SLOTS,
+};
+pub use layout::{layout_of, struct_layout, FfiLayout};
+pub use library::{library_candidates, LibraryCache};
+pub use trampoline::{classify, ArgClass, PassMode, MAX_STACK_WORDS};
+
+pub const FFI_LIBRARY_NOT_FOUND_CODE: &str = "ffi.library.not_found";
+pub const FFI_SYMBOL_NOT_FOUND_CODE: &str = "ffi.symbol.not_found";
+pub const FFI_CALL_FAILED_CODE: &str = "ffi.call.failed";
+pub const FFI_CALL_INVALID_ARGUMENT_CODE: &str = "ffi.call.invalid_argument";
+pub const FFI_RELEASE_OWNERSHIP_VIOLATION_CODE: &str = "ffi.release.ownership_violation";
+const SIGNATURE_INVALID_CODE: &str = "ffi.signature.invalid";
+
+#[cfg(all(target_arch = "x86_64", unix))]
+static DYNAMIC_EXECUTOR: Lazy<Arc<DynamicFfiExecutor>> =
+    Lazy::new(|| Arc::new(DynamicFfiExecutor::new()));
+
+/// プロセス共有の動的 FFI 呼び出しエンジン。
+#[cfg(all(target_arch = "x86_64", unix))]
+pub fn dynamic_ffi_executor() -> Arc<DynamicFfiExecutor> {
+    Arc::clone(&DYNAMIC_EXECUTOR)
+}
+
+/// 共有の動的 FFI 呼び出しエンジンを `set_ffi_call_executor` へ登録する。
+#[cfg(all(target_arch = "x86_64", unix))]
+pub fn install_dynamic_ffi_executor() -> Result<(), FfiError> {
+    set_ffi_call_executor(dynamic_ffi_executor())
+}
+
+/// 戻り値ポインタの所有権区分ごとの件数。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FfiOwnershipStats {
+    pub borrowed_results: u64,
+    pub owned_results: u64,
+    pub transferred_results: u64,
+    pub released: u64,
+}
+
+/// `dlopen`/`dlsym` でシンボルを解決して呼び出す実行エンジン。
+#[derive(Debug, Default)]
+pub struct DynamicFfiExecutor {
+    libraries: LibraryCache,
+    foreign: Mutex<ForeignPointers>,
+}
+
+#[derive(Debug, Default)]
+struct ForeignPointers {
+    live: HashMap<usize, Ownership>,
+    stats: FfiOwnershipStats,
+}
+
+impl DynamicFfiExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 呼び出しから受け取ったポインタの所有権区分。解放済みや Borrowed なら `None`。
+    pub fn ownership_of(&self, addr: usize) -> Option<Ownership> {
+        self.foreign().live.get(&addr).copied()
+    }
+
+    pub fn ownership_stats(&self) -> FfiOwnershipStats {
+        self.foreign().stats
+    }
+
+    /// Owned/Transferred で受け取ったポインタを `free` で解放する。
+    ///
+    /// Borrowed のポインタや解放済みのポインタは解放せず、所有権違反として報告する。
+    pub fn release_foreign_ptr(&self, addr: usize) -> Result<(), FfiError> {
+        let mut foreign = self.foreign();
+        if foreign.live.remove(&addr).is_none() {
+            return Err(FfiError::new(
+                FfiErrorKind::OwnershipViolation,
+                format!("ポインタ {addr:#x} は解放できる所有権を持っていません"),
+            )
+            .with_code(FFI_RELEASE_OWNERSHIP_VIOLATION_CODE));
+        }
+        foreign.stats.released += 1;
+        unsafe { libc::free(addr as *mut libc::c_void) };
+        Ok(())
+    }
+
+    fn foreign(&self) -> std::sync::MutexGuard<'_, ForeignPointers> {
+        self.foreign.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn track_return(&self, raw: &FfiRawFn, value: &FfiValue) {
+        let (FfiValue::Ptr(Some(addr)) | FfiValue::ConstPtr(Some(addr))) = value else {
+            return;
+        };
+        let mut foreign = self.foreign();
+        match raw.return_ownership().unwrap_or(Ownership::Borrowed) {
+            Ownership::Borrowed => foreign.stats.borrowed_results += 1,
+            ownership @ Ownership::Owned => {
+                foreign.stats.owned_results += 1;
+                foreign.live.insert(*addr, ownership);
+            }
+            ownership @ Ownership::Transferred => {
+                foreign.stats.transferred_results += 1;
+                foreign.live.insert(*addr, ownership);
+            }
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", unix))]
+impl FfiCallExecutor for DynamicFfiExecutor {
+    fn call(&self, raw: &FfiRawFn, args: &[FfiValue]) -> Result<FfiValue, FfiError> {
+        let code = self.libraries.resolve(raw.library_label(), raw.symbol())?;
+        let value = unsafe { trampoline::call(code, raw.signature(), args) }?;
+        self.track_return(raw, &value);
+        Ok(value)
+    }
+}
+
+fn call_failed(message: impl Into<String>) -> FfiError {
+    FfiError::new(FfiErrorKind::CallFailed, message).with_code(FFI_CALL_FAILED_CODE)
+}
+
+fn invalid_argument(message: impl Into<String>) -> FfiError {
+    FfiError::new(FfiErrorKind::InvalidArgument, message).with_code(FFI_CALL_INVALID_ARGUMENT_CODE)
+}
@@ -0,0 +1,330 @@
+//! x86_64 System V 呼出規約に従って任意のシグネチャの関数を呼び出す汎用トランポリン。
+//!
+//! 引数は型ごとに整数レジスタ（6 本）・SSE レジスタ（8 本）・スタックへ振り分け、
+//! シンボルを C の可変長引数関数として全レジスタとスタック語を渡して呼び出す。可変長引数の
+//! 呼び出しでは `%al` に SSE レジスタ数が入るため、`printf` 系の関数もそのまま呼べる。
+//! 戻り値はクラス分類に応じた型で受け取り、16 バイトを超える構造体は隠しポインタで受け取る。
+//!
+//! この呼び出し方は x86_64 System V でしか成り立たないため、`call` はそのターゲットでだけ
+//! ビルドする。他のターゲットで動的 FFI を使おうとするとコンパイルエラーになる。
+#![cfg_attr(
+    not(all(target_arch = "x86_64", unix)),
+    allow(dead_code, unused_imports)
+)]
+
+use std::os::raw::c_void;
+
+use super::invalid_argument;
+use super::layout::{decode, encode, layout_of, scalars, FfiLayout};
+use crate::ffi::dsl::{FfiError, FfiFnSig, FfiIntRepr, FfiType, FfiValue};
+
+pub(super) const INT_REGISTERS: usize = 6;
+pub(super) const SSE_REGISTERS: usize = 8;
+/// スタックで渡せる語数の上限。超える呼び出しは `ffi.call.invalid_argument` で拒否する。
+pub const MAX_STACK_WORDS: usize = 16;
+
+/// 8 バイト単位の引数クラス。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgClass {
+    Integer,
+    Sse,
+}
+
+/// 値の受け渡し方法。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PassMode {
+    /// 8 バイトごとのクラスに従ってレジスタで渡す。
+    Direct(Vec<ArgClass>),
+    /// メモリ（引数ならスタック、戻り値なら隠しポインタ）で渡す。
+    Memory,
+}
+
+/// 型を System V のクラスへ分類する。
+pub fn classify(ty: &FfiType) -> Result<PassMode, FfiError> {
+    let layout = layout_of(ty)?;
+    if layout.size > 16 {
+        return Ok(PassMode::Memory);
+    }
+    let mut fields = Vec::new();
+    scalars(ty, 0, &mut fields)?;
+    // 境界に揃っていないフィールドを含む packed 構造体はメモリで渡す。
+    if fields.iter().any(|(offset, size, _)| offset % size != 0) {
+        return Ok(PassMode::Memory);
+    }
+    let classes = (0..layout.words())
+        .map(|word| {
+            let all_float = fields
+                .iter()
+                .filter(|(offset, _, _)| offset / 8 == word)
+                .all(|(_, _, is_float)| *is_float);
+            if all_float {
+                ArgClass::Sse
+            } else {
+                ArgClass::Integer
+            }
+        })
+        .collect();
+    Ok(PassMode::Direct(classes))
+}
+
+/// 2 語の値を `rax`/`rdx`・`xmm0`/`xmm1` の組で受け渡すための型。
+#[repr(C)]
+pub(super) struct Pair<A, B>(pub A, pub B);
+
+/// レジスタとスタックへ振り分けた引数。
+#[derive(Debug, Default)]
+struct Frame {
+    ints: Vec<u64>,
+    sses: Vec<u64>,
+    stack: Vec<u64>,
+}
+
+impl Frame {
+    fn push(&mut self, words: &[u64], mode: &PassMode) {
+        if let PassMode::Direct(classes) = mode {
+            let ints = classes.iter().filter(|c| **c == ArgClass::Integer).count();
+            let sses = classes.len() - ints;
+            // 一部だけレジスタに載せることはせず、足りなければ全体をスタックへ置く。
+            if self.ints.len() + ints <= INT_REGISTERS && self.sses.len() + sses <= SSE_REGISTERS {
+                for (word, class) in words.iter().zip(classes) {
+                    match class {
+                        ArgClass::Integer => self.ints.push(*word),
+                        ArgClass::Sse => self.sses.push(*word),
+                    }
+                }
+                return;
+            }
+        }
+        self.stack.extend_from_slice(words);
+    }
+}
+
+/// 値を型の表現で 8 バイト語の列へ変換する。
+pub(super) fn words_of(
+    value: &FfiValue,
+    ty: &FfiType,
+    layout: FfiLayout,
+) -> Result<Vec<u64>, FfiError> {
+    let mut bytes = vec![0u8; layout.words() * 8];
+    encode(value, ty, &mut bytes)?;
+    // 32 ビット未満の整数は呼び出し先が拡張済みの値を前提とするため、レジスタ幅へ拡張する。
+    let extended = match value {
+        FfiValue::I8(v) => Some(i64::from(*v) as u64),
+        FfiValue::I16(v) => Some(i64::from(*v) as u64),
+        FfiValue::I32(v) => Some(i64::from(*v) as u64),
+        FfiValue::Enum { value, .. } if !matches!(ty, FfiType::Enum(def) if is_unsigned(def.repr)) => {
+            Some(*value as u64)
+        }
+        _ => None,
+    };
+    let mut words: Vec<u64> = bytes
+        .chunks_exact(8)
+        .map(|chunk| u64::from_ne_bytes(chunk.try_into().expect("8 バイト")))
+        .collect();
+    if let Some(extended) = extended {
+        words[0] = extended;
+    }
+    Ok(words)
+}
+
+fn is_unsigned(repr: FfiIntRepr) -> bool {
+    matches!(
+        repr,
+        FfiIntRepr::U8 | FfiIntRepr::U16 | FfiIntRepr::U32 | FfiIntRepr::U64
+    )
+}
+
+/// 可変長引数部の値を C の既定の実引数昇格に従って 1 語へ変換する。
+fn promote_variadic(value: &FfiValue) -> Result<(u64, ArgClass), FfiError> {
+    Ok(match value {
+        FfiValue::Bool(v) => (u64::from(*v), ArgClass::Integer),
+        FfiValue::I8(v) => (i64::from(*v) as u64, ArgClass::Integer),
+        FfiValue::U8(v) => (u64::from(*v), ArgClass::Integer),
+        FfiValue::I16(v) => (i64::from(*v) as u64, ArgClass::Integer),
+        FfiValue::U16(v) => (u64::from(*v), ArgClass::Integer),
+        FfiValue::I32(v) => (i64::from(*v) as u64, ArgClass::Integer),
+        FfiValue::U32(v) => (u64::from(*v), ArgClass::Integer),
+        FfiValue::I64(v) => (*v as u64, ArgClass::Integer),
+        FfiValue::U64(v) => (*v, ArgClass::Integer),
+        FfiValue::F32(v) => (f64::from(*v).to_bits(), ArgClass::Sse),
+        FfiValue::F64(v) => (v.to_bits(), ArgClass::Sse),
+        FfiValue::Ptr(addr) | FfiValue::ConstPtr(addr) => {
+            (addr.unwrap_or(0) as u64, ArgClass::Integer)
+        }
+        FfiValue::FnPtr(addr) => (*addr as u64, ArgClass::Integer),
+        FfiValue::Callback(callback) => (callback.code_ptr() as u64, ArgClass::Integer),
+        FfiValue::Enum { value, .. } => (*value as u64, ArgClass::Integer),
+        FfiValue::Void | FfiValue::Struct { .. } => {
+            return Err(invalid_argument(format!(
+                "可変長引数として {value:?} は渡せません"
+            )))
+        }
+    })
+}
+
+/// シグネチャに従って `code` を呼び出す。
+///
+/// # Safety
+/// `code` はシグネチャどおりの C 関数を指し、ポインタ引数は呼び出し先の前提を満たすこと。
+#[cfg(all(target_arch = "x86_64", unix))]
+pub unsafe fn call(
+    code: *const c_void,
+    sig: &FfiFnSig,
+    args: &[FfiValue],
+) -> Result<FfiValue, FfiError> {
+    if args.len() < sig.params.len() || (!sig.variadic && args.len() != sig.params.len()) {
+        return Err(invalid_argument(format!(
+            "引数の数がシグネチャと一致しません（期待 {}、実際 {}）",
+            sig.params.len(),
+            args.len()
+        )));
+    }
+    let mut frame = Frame::default();
+    let returns = sig.returns.as_ref();
+    let (ret_mode, ret_layout) = match returns {
+        FfiType::Void => (
+            PassMode::Direct(Vec::new()),
+            FfiLayout { size: 0, align: 1 },
+        ),
+        ty => (classify(ty)?, layout_of(ty)?),
+    };
+    let mut ret_buffer = vec![0u64; ret_layout.words().max(1)];
+    if ret_mode == PassMode::Memory {
+        frame.ints.push(ret_buffer.as_mut_ptr() as u64);
+    }
+
+    for (value, ty) in args.iter().zip(&sig.params) {
+        let words = words_of(value, ty, layout_of(ty)?)?;
+        frame.push(&words, &classify(ty)?);
+    }
+    for value in &args[sig.params.len()..] {
+        let (word, class) = promote_variadic(value)?;
+        frame.push(&[word], &PassMode::Direct(vec![class]));
+    }
+    if frame.stack.len() > MAX_STACK_WORDS {
+        return Err(invalid_argument(format!(
+            "レジスタに載らない引数が {} 語あり、スタックで渡せる上限（{MAX_STACK_WORDS} 語）を超えています",
+            frame.stack.len()
+        )));
+    }
+
+    let words = invoke(code, &frame, &ret_mode);
+    if ret_mode != PassMode::Memory {
+        ret_buffer[..words.len()].copy_from_slice(&words);
+    }
+    let bytes: Vec<u8> = ret_buffer
+        .iter()
+        .flat_map(|word| word.to_ne_bytes())
+        .collect();
+    decode(returns, &bytes)
+}
+
+#[cfg(all(target_arch = "x86_64", unix))]
+unsafe fn invoke(code: *const c_void, frame: &Frame, ret: &PassMode) -> Vec<u64> {
+    let mut i = [0u64; INT_REGISTERS];
+    i[..frame.ints.len()].copy_from_slice(&frame.ints);
+    let mut x = [0f64; SSE_REGISTERS];
+    for (slot, bits) in x.iter_mut().zip(&frame.sses) {
+        *slot = f64::from_bits(*bits);
+    }
+    let mut s = [0u64; MAX_STACK_WORDS];
+    s[..frame.stack.len()].copy_from_slice(&frame.stack);
+
+    macro_rules! call_as {
+        ($ret:ty) => {{
+            let function: unsafe extern "C" fn(u64, ...) -> $ret = std::mem::transmute(code);
+            function(
+                i[0], i[1], i[2], i[3], i[4], i[5], x[0], x[1], x[2], x[3], x[4], x[5], x[6], x[7],
+                s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7], s[8], s[9], s[10], s[11], s[12],
+                s[13], s[14], s[15],
+            )
+        }};
+    }
+
+    use ArgClass::{Integer, Sse};
+    match ret {
+        PassMode::Memory => {
+            call_as!(u64);
+            Vec::new()
+        }
+        PassMode::Direct(classes) => match classes.as_slice() {
+            [] => {
+                call_as!(u64);
+                Vec::new()
+            }
+            [Integer] => vec![call_as!(u64)],
+            [Sse] => vec![call_as!(f64).to_bits()],
+            [Integer, Integer] => {
+                let Pair(a, b) = call_as!(Pair<u64, u64>);
+                vec![a, b]
+            }
+            [Sse, Sse] => {
+                let Pair(a, b) = call_as!(Pair<f64, f64>);
+                vec![a.to_bits(), b.to_bits()]
+            }
+            [Integer, Sse] => {
+                let Pair(a, b) = call_as!(Pair<u64, f64>);
+                vec![a, b.to_bits()]
+            }
+            [Sse, Integer] => {
+                let Pair(a, b) = call_as!(Pair<f64, u64>);
+                vec![a.to_bits(), b]
+            }
+            _ => unreachable!("16 バイト以下の値は 2 語以内に収まる"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::dsl::{FfiField, FfiRepr, FfiStruct};
+
+    fn record(repr: FfiRepr, fields: Vec<FfiType>) -> FfiType {
+        FfiType::Struct(FfiStruct {
+            name: "S".into(),
+            fields: fields
+                .into_iter()
+                .enumerate()
+                .map(|(index, ty)| FfiField {
+                    name: format!("f{index}"),
+                    ty,
+                })
+                .collect(),
+            repr,
+        })
+    }
+
+    #[test]
+    fn classifies_eightbytes_like_system_v() {
+        use ArgClass::{Integer, Sse};
+        assert_eq!(
+            classify(&FfiType::F32).unwrap(),
+            PassMode::Direct(vec![Sse])
+        );
+        let mixed = record(FfiRepr::C, vec![FfiType::F32, FfiType::F32, FfiType::I64]);
+        assert_eq!(
+            classify(&mixed).unwrap(),
+            PassMode::Direct(vec![Sse, Integer])
+        );
+        let shared = record(FfiRepr::C, vec![FfiType::F32, FfiType::I32]);
+        assert_eq!(classify(&shared).unwrap(), PassMode::Direct(vec![Integer]));
+        let large = record(FfiRepr::C, vec![FfiType::F64, FfiType::F64, FfiType::F64]);
+        assert_eq!(classify(&large).unwrap(), PassMode::Memory);
+        let unaligned = record(FfiRepr::Packed, vec![FfiType::U8, FfiType::I32]);
+        assert_eq!(classify(&unaligned).unwrap(), PassMode::Memory);
+    }
+
+    #[test]
+    fn structs_spill_to_the_stack_as_a_whole() {
+        let mut frame = Frame::default();
+        let pair = PassMode::Direct(vec![ArgClass::Integer, ArgClass::Integer]);
+        for word in 0..5 {
+            frame.push(&[word], &PassMode::Direct(vec![ArgClass::Integer]));
+        }
+        frame.push(&[10, 11], &pair);
+        frame.push(&[12], &PassMode::Direct(vec![ArgClass::Integer]));
+        assert_eq!(frame.ints, vec![0, 1, 2, 3, 4, 12]);
+        assert_eq!(frame.stack, vec![10, 11]);
+    }
+}
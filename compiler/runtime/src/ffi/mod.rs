@@ -1,3 +1,4 @@
 //! Core.Ffi 関連のランタイム API。
 
 pub mod dsl;
+pub mod dynamic;
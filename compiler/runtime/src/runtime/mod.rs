@@ -2,8 +2,12 @@ pub mod api;
 pub mod async_bridge;
 pub mod bridge;
 pub mod plugin;
+pub mod plugin_abi;
 pub mod plugin_bridge;
+pub mod plugin_host;
+pub(crate) mod plugin_limits;
 pub mod plugin_manager;
+pub mod plugin_signing;
 pub mod signal;
 
 pub use signal::{Signal, SignalError, SignalErrorKind, SignalInfo};
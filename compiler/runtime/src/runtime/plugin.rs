@@ -1,7 +1,7 @@
 use std::{
     fs,
     path::{Path, PathBuf},
-    sync::Mutex,
+    sync::{Arc, Mutex},
 };
 
 use once_cell::sync::Lazy;
@@ -18,7 +18,10 @@ use crate::{
         load_manifest, Manifest, ManifestCapabilities, ManifestCapabilityError, ProjectKind,
     },
     prelude::ensure::{DiagnosticSeverity, GuardDiagnostic, IntoDiagnostic},
-    runtime::bridge::attach_bridge_stage_metadata,
+    runtime::{
+        bridge::attach_bridge_stage_metadata,
+        plugin_signing::{verify_signature_value, PluginTrustStore},
+    },
     stage::{StageId, StageRequirement},
 };
 
@@ -28,6 +31,8 @@ const PLUGIN_EVENT_INSTALL: &str = "plugin.install";
 const PLUGIN_EVENT_REVOKE: &str = "plugin.revoke";
 const PLUGIN_EVENT_VERIFY_SIGNATURE: &str = "plugin.verify_signature";
 const PLUGIN_EVENT_SIGNATURE_FAILURE: &str = "plugin.signature.failure";
+const PLUGIN_EVENT_LOG: &str = "plugin.log";
+const PLUGIN_EVENT_LIMIT_EXCEEDED: &str = "plugin.limit_exceeded";
 
 /// 署名検証の方針。
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -52,7 +57,10 @@ impl SignatureAlgorithm {
     }
 }
 
-/// プラグイン署名情報（最小）。
+/// プラグイン署名情報。
+///
+/// `value` は [`crate::runtime::plugin_signing::signing_payload`] に対する detached
+/// 署名で、`key_id` で信頼ストアの鍵を選ぶ。
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PluginSignature {
     pub algorithm: SignatureAlgorithm,
@@ -60,6 +68,9 @@ pub struct PluginSignature {
     pub issued_to: Option<String>,
     pub valid_until: Option<String>,
     pub bundle_hash: Option<String>,
+    pub key_id: Option<String>,
+    /// 署名値（64 バイトの 16 進表記）。
+    pub value: Option<String>,
 }
 
 /// 署名検証の結果。
@@ -113,6 +124,86 @@ pub struct PluginModuleInfo {
     pub plugin_id: String,
     pub module_path: PathBuf,
     pub module_hash: String,
+    pub execution: PluginExecutionPolicy,
+    pub abi: PluginAbi,
+}
+
+/// バンドルの `plugins[].execution` で指定する WASM 実行ポリシー。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginExecutionPolicy {
+    /// 1 回の呼び出しで消費できる fuel（命令数の目安）。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuel: Option<u64>,
+    /// 1 回の呼び出しの実時間上限（epoch 割り込みで打ち切る）。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// 線形メモリの上限（バイト）。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_bytes: Option<u64>,
+    /// テーブル要素数の上限。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_table_elements: Option<u32>,
+    #[serde(default)]
+    pub instance_mode: PluginInstanceMode,
+    /// `long_lived` で保持するインスタンス数（既定 1）。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_size: Option<u32>,
+}
+
+/// 呼び出しごとのインスタンス生成方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginInstanceMode {
+    /// 呼び出しごとに `Store` とインスタンスを作り直す。
+    #[default]
+    PerCall,
+    /// プール割り当てのインスタンスを保持し、呼び出し間で状態を引き継ぐ。
+    LongLived,
+}
+
+impl PluginInstanceMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PluginInstanceMode::PerCall => "per_call",
+            PluginInstanceMode::LongLived => "long_lived",
+        }
+    }
+}
+
+/// バンドルの `plugins[].abi` で宣言する呼び出し ABI。
+///
+/// `version` 0 は入出力をオフセット 0 に置く旧方式、1 はゲストの `alloc`/`dealloc` を
+/// 使う方式（[`crate::runtime::plugin_abi`]）。ゲストが名乗る版数と一致しなければ読み込まない。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginAbi {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub encoding: PluginPayloadEncoding,
+}
+
+/// エントリポイントが受け渡すペイロードの符号化。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginPayloadEncoding {
+    /// バイト列をそのまま渡す。
+    #[default]
+    Raw,
+    Json,
+    /// MessagePack（フィールド名付きのマップ表現）。
+    Msgpack,
+}
+
+impl PluginPayloadEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PluginPayloadEncoding::Raw => "raw",
+            PluginPayloadEncoding::Json => "json",
+            PluginPayloadEncoding::Msgpack => "msgpack",
+        }
+    }
 }
 
 impl PluginModuleInfo {
@@ -127,6 +218,16 @@ impl PluginModuleInfo {
             "plugin.module_hash".into(),
             Value::String(self.module_hash.clone()),
         );
+        if self.execution != PluginExecutionPolicy::default() {
+            if let Ok(execution) = serde_json::to_value(&self.execution) {
+                value.insert("plugin.execution".into(), execution);
+            }
+        }
+        if self.abi != PluginAbi::default() {
+            if let Ok(abi) = serde_json::to_value(self.abi) {
+                value.insert("plugin.abi".into(), abi);
+            }
+        }
         Value::Object(value)
     }
 }
@@ -156,6 +257,10 @@ pub enum PluginLoadError {
     SignatureMissing,
     #[error("plugin signature の検証に失敗しました: {reason}")]
     SignatureInvalid { reason: String },
+    #[error("信頼ストアの読み込みに失敗しました: {message}")]
+    TrustStore { message: String },
+    #[error("署名鍵の読み込みに失敗しました: {message}")]
+    SigningKey { message: String },
 }
 
 /// プラグイン実行時のエラー。
@@ -180,6 +285,34 @@ pub enum PluginError {
         message: String,
         capability_error: Option<CapabilityError>,
     },
+    #[error("plugin import denied: {plugin_id} called {import} without requesting it")]
+    ImportDenied { plugin_id: String, import: String },
+    #[error("plugin import unresolved: {plugin_id} imports unknown {import}")]
+    ImportUnresolved { plugin_id: String, import: String },
+    #[error("plugin fuel exhausted: {plugin_id} used up {fuel} units")]
+    FuelExhausted { plugin_id: String, fuel: u64 },
+    #[error("plugin call timed out: {plugin_id} exceeded {timeout_ms} ms")]
+    Timeout { plugin_id: String, timeout_ms: u64 },
+    #[error(
+        "plugin memory limit exceeded: {plugin_id} requested {requested_bytes} bytes (limit {limit_bytes})"
+    )]
+    MemoryLimitExceeded {
+        plugin_id: String,
+        limit_bytes: u64,
+        requested_bytes: u64,
+    },
+    #[error(
+        "plugin table limit exceeded: {plugin_id} requested {requested_elements} elements (limit {limit_elements})"
+    )]
+    TableLimitExceeded {
+        plugin_id: String,
+        limit_elements: u32,
+        requested_elements: u32,
+    },
+    #[error("plugin abi mismatch: {plugin_id}: {detail}")]
+    AbiMismatch { plugin_id: String, detail: String },
+    #[error("plugin payload {encoding} conversion failed: {message}")]
+    PayloadInvalid { encoding: String, message: String },
 }
 
 impl PluginError {
@@ -235,6 +368,49 @@ impl PluginError {
                 message.clone(),
                 capability_error.as_ref(),
             ),
+            PluginError::ImportDenied { plugin_id, import } => (
+                "runtime.plugin.import_denied",
+                "import_denied",
+                format!("plugin {plugin_id} called {import} without requesting it"),
+                None,
+            ),
+            PluginError::ImportUnresolved { plugin_id, import } => (
+                "runtime.plugin.import_unresolved",
+                "import_unresolved",
+                format!("plugin {plugin_id} imports unknown {import}"),
+                None,
+            ),
+            PluginError::FuelExhausted { .. } => (
+                "runtime.plugin.fuel_exhausted",
+                "fuel_exhausted",
+                self.to_string(),
+                None,
+            ),
+            PluginError::Timeout { .. } => ("runtime.plugin.timeout", "timeout", self.to_string(), None),
+            PluginError::MemoryLimitExceeded { .. } => (
+                "runtime.plugin.memory_limit_exceeded",
+                "memory_limit_exceeded",
+                self.to_string(),
+                None,
+            ),
+            PluginError::TableLimitExceeded { .. } => (
+                "runtime.plugin.table_limit_exceeded",
+                "table_limit_exceeded",
+                self.to_string(),
+                None,
+            ),
+            PluginError::AbiMismatch { .. } => (
+                "runtime.plugin.abi_mismatch",
+                "abi_mismatch",
+                self.to_string(),
+                None,
+            ),
+            PluginError::PayloadInvalid { .. } => (
+                "runtime.plugin.payload_invalid",
+                "payload_invalid",
+                self.to_string(),
+                None,
+            ),
         };
 
         let stage_snapshot = capability_error.and_then(stage_mismatch_snapshot);
@@ -379,21 +555,38 @@ impl IntoDiagnostic for PluginError {
 #[derive(Debug, Clone)]
 pub struct PluginLoader {
     registry: &'static CapabilityRegistry,
+    trust_store: Option<Arc<PluginTrustStore>>,
 }
 
 impl PluginLoader {
     pub fn new() -> Self {
         Self {
             registry: CapabilityRegistry::registry(),
+            trust_store: None,
         }
     }
 
+    /// 署名検証に使う信頼ストアを設定する。未設定の場合 Strict モードの署名検証は失敗する。
+    pub fn with_trust_store(mut self, trust_store: PluginTrustStore) -> Self {
+        self.trust_store = Some(Arc::new(trust_store));
+        self
+    }
+
+    pub fn trust_store(&self) -> Option<&PluginTrustStore> {
+        self.trust_store.as_deref()
+    }
+
     pub(crate) fn verify_bundle_signature(
         &self,
         bundle: &PluginBundleManifest,
         policy: VerificationPolicy,
     ) -> Result<SignatureStatus, PluginLoadError> {
-        let signature_status = verify_plugin_signature(bundle, policy)?;
+        let signature_status = verify_plugin_signature(
+            bundle,
+            policy,
+            self.trust_store(),
+            OffsetDateTime::now_utc(),
+        )?;
         record_signature_audit(bundle, &signature_status);
         Ok(signature_status)
     }
@@ -547,6 +740,8 @@ fn stage_requirement_label(requirement: StageRequirement) -> String {
 fn verify_plugin_signature(
     bundle: &PluginBundleManifest,
     policy: VerificationPolicy,
+    trust_store: Option<&PluginTrustStore>,
+    now: OffsetDateTime,
 ) -> Result<SignatureStatus, PluginLoadError> {
     let signature = match bundle.signature.as_ref() {
         Some(signature) => signature,
@@ -600,6 +795,37 @@ fn verify_plugin_signature(
         });
     }
 
+    // Permissive では検証できない署名（署名値・信頼ストア・対応アルゴリズムのいずれかが
+    // ない）を Skipped とし、検証できる署名が不正な場合だけ拒否する。
+    let trust_store = match (trust_store, signature.value.as_ref()) {
+        (Some(trust_store), Some(_)) => trust_store,
+        (trust_store, _) => {
+            return match policy {
+                VerificationPolicy::Strict => {
+                    let reason = if trust_store.is_none() {
+                        "信頼ストアが設定されていません"
+                    } else {
+                        "署名値がありません"
+                    };
+                    record_signature_failure_audit(bundle, reason);
+                    Err(PluginLoadError::SignatureInvalid {
+                        reason: reason.to_string(),
+                    })
+                }
+                VerificationPolicy::Permissive => Ok(SignatureStatus::Skipped),
+            };
+        }
+    };
+    if matches!(policy, VerificationPolicy::Permissive)
+        && matches!(signature.algorithm, SignatureAlgorithm::Unknown(_))
+    {
+        return Ok(SignatureStatus::Skipped);
+    }
+    if let Err(reason) = verify_signature_value(bundle, signature, trust_store, now) {
+        record_signature_failure_audit(bundle, &reason);
+        return Err(PluginLoadError::SignatureInvalid { reason });
+    }
+
     Ok(SignatureStatus::Verified)
 }
 
@@ -663,6 +889,12 @@ fn record_signature_audit(bundle: &PluginBundleManifest, status: &SignatureStatu
                 Value::String(bundle_hash.clone()),
             );
         }
+        if let Some(key_id) = &signature.key_id {
+            metadata.insert(
+                "plugin.signature.key_id".into(),
+                Value::String(key_id.clone()),
+            );
+        }
         if let Some(issued_to) = &signature.issued_to {
             metadata.insert(
                 "plugin.signature.issued_to".into(),
@@ -730,6 +962,12 @@ fn record_signature_failure_audit(bundle: &PluginBundleManifest, reason: &str) {
                 Value::String(bundle_hash.clone()),
             );
         }
+        if let Some(key_id) = &signature.key_id {
+            metadata.insert(
+                "plugin.signature.key_id".into(),
+                Value::String(key_id.clone()),
+            );
+        }
         if let Some(issued_to) = &signature.issued_to {
             metadata.insert(
                 "plugin.signature.issued_to".into(),
@@ -867,6 +1105,79 @@ pub(crate) fn record_revoke_audit(
         .push(event);
 }
 
+pub(crate) fn record_log_audit(plugin_id: &str, level: &str, source: &str, message: &str) {
+    let timestamp = OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".into());
+    let mut metadata = JsonMap::new();
+    metadata.insert(
+        "event.kind".into(),
+        Value::String(PLUGIN_EVENT_LOG.to_string()),
+    );
+    metadata.insert(
+        "event.domain".into(),
+        Value::String(PLUGIN_DOMAIN.to_string()),
+    );
+    metadata.insert("plugin.id".into(), Value::String(plugin_id.to_string()));
+    metadata.insert("plugin.log.level".into(), Value::String(level.to_string()));
+    metadata.insert(
+        "plugin.log.source".into(),
+        Value::String(source.to_string()),
+    );
+    metadata.insert(
+        "plugin.log.message".into(),
+        Value::String(message.to_string()),
+    );
+    let envelope = AuditEnvelope::from_parts(metadata, None, None, Some("plugin.log".into()));
+    let event = AuditEvent::new(timestamp, envelope);
+    PLUGIN_AUDIT_EVENTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(event);
+}
+
+pub(crate) fn record_limit_audit(
+    plugin_id: &str,
+    entrypoint: &str,
+    kind: &str,
+    limit: u64,
+    requested: Option<u64>,
+) {
+    let timestamp = OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".into());
+    let mut metadata = JsonMap::new();
+    metadata.insert(
+        "event.kind".into(),
+        Value::String(PLUGIN_EVENT_LIMIT_EXCEEDED.to_string()),
+    );
+    metadata.insert(
+        "event.domain".into(),
+        Value::String(PLUGIN_DOMAIN.to_string()),
+    );
+    metadata.insert("plugin.id".into(), Value::String(plugin_id.to_string()));
+    metadata.insert(
+        "plugin.entrypoint".into(),
+        Value::String(entrypoint.to_string()),
+    );
+    metadata.insert("plugin.limit.kind".into(), Value::String(kind.to_string()));
+    metadata.insert("plugin.limit.value".into(), Value::from(limit));
+    if let Some(requested) = requested {
+        metadata.insert("plugin.limit.requested".into(), Value::from(requested));
+    }
+    let envelope = AuditEnvelope::from_parts(
+        metadata,
+        None,
+        None,
+        Some("plugin.limit_exceeded".into()),
+    );
+    let event = AuditEvent::new(timestamp, envelope);
+    PLUGIN_AUDIT_EVENTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(event);
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct BundleContext {
     bundle_id: String,
@@ -972,6 +1283,10 @@ struct PluginBundleEntry {
     manifest_path: PathBuf,
     #[serde(default)]
     module_path: Option<PathBuf>,
+    #[serde(default)]
+    execution: PluginExecutionPolicy,
+    #[serde(default)]
+    abi: PluginAbi,
 }
 
 #[derive(Debug, Deserialize)]
@@ -981,6 +1296,10 @@ struct PluginSignatureFile {
     issued_to: Option<String>,
     valid_until: Option<String>,
     bundle_hash: Option<String>,
+    #[serde(default)]
+    key_id: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
 }
 
 fn load_bundle_from_path(path: impl AsRef<Path>) -> Result<PluginBundleManifest, PluginLoadError> {
@@ -1019,10 +1338,13 @@ fn load_bundle_from_path(path: impl AsRef<Path>) -> Result<PluginBundleManifest,
                 plugin_id: manifest.project.name.0.clone(),
                 module_path: resolved_path,
                 module_hash: compute_module_hash(&module_bytes),
+                execution: entry.execution.clone(),
+                abi: entry.abi,
             });
         }
         manifests.push(manifest);
-        hash_sources.push((manifest_path, manifest_body));
+        // 署名をバンドルの置き場所に依存させないよう、相対パスでハッシュする。
+        hash_sources.push((entry.manifest_path.clone(), manifest_body));
     }
 
     let bundle_hash = Some(compute_bundle_hash(
@@ -1037,6 +1359,8 @@ fn load_bundle_from_path(path: impl AsRef<Path>) -> Result<PluginBundleManifest,
         issued_to: sig.issued_to,
         valid_until: sig.valid_until,
         bundle_hash: sig.bundle_hash,
+        key_id: sig.key_id,
+        value: sig.value,
     });
 
     Ok(PluginBundleManifest {
@@ -1085,7 +1409,7 @@ fn compute_module_hash(bytes: &[u8]) -> String {
     format!("sha256:{}", bytes_to_hex(digest.as_slice()))
 }
 
-fn bytes_to_hex(bytes: &[u8]) -> String {
+pub(crate) fn bytes_to_hex(bytes: &[u8]) -> String {
     let mut out = String::with_capacity(bytes.len() * 2);
     for value in bytes {
         out.push(hex_nibble(value >> 4));
@@ -0,0 +1,151 @@
+//! WASM プラグインの呼び出し ABI とペイロード符号化。
+//!
+//! ABI v1 ではゲストが次の関数をエクスポートする。
+//!
+//! ```text
+//! reml_abi_version() -> i32            ゲストが名乗る ABI の版数
+//! alloc(len i32) -> ptr i32            ホストが入力を書き込むバッファの確保
+//! dealloc(ptr i32, len i32)            出力バッファの返却
+//! <entry>(ptr i32, len i32) -> i64     (out_ptr << 32) | out_len
+//! ```
+//!
+//! ホストは `alloc` で得たバッファへ入力を書いてエントリポイントを呼び、返された
+//! 範囲を読み取ってから `dealloc` で返却する。入力バッファの所有権は呼び出しで
+//! ゲストへ移る（入力をそのまま出力として返してもよい）。
+//!
+//! `reml_abi_version` を持たないモジュールは版数 0（入出力をオフセット 0 に置く
+//! 旧方式）とみなす。Reml の wasm バックエンドは `--plugin-abi` で v1 の
+//! エクスポートを生成する。
+
+use crate::runtime::plugin::{PluginAbi, PluginError, PluginPayloadEncoding};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasmtime::{ExternType, Module};
+
+/// 現行の ABI 版数。
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+/// ホストが受け付ける ABI 版数。
+pub const SUPPORTED_ABI_VERSIONS: [u32; 2] = [0, PLUGIN_ABI_VERSION];
+
+pub const ABI_VERSION_EXPORT: &str = "reml_abi_version";
+pub const ALLOC_EXPORT: &str = "alloc";
+pub const DEALLOC_EXPORT: &str = "dealloc";
+
+/// エントリポイントの戻り値 `(ptr << 32) | len` を分解する。
+pub fn unpack_result(packed: i64) -> (u32, u32) {
+    let packed = packed as u64;
+    ((packed >> 32) as u32, packed as u32)
+}
+
+/// 値を `encoding` でペイロードへ符号化する。`raw` は型付きの値を運べない。
+pub fn encode_payload<T: Serialize + ?Sized>(
+    encoding: PluginPayloadEncoding,
+    value: &T,
+) -> Result<Vec<u8>, PluginError> {
+    match encoding {
+        PluginPayloadEncoding::Raw => Err(payload_error(
+            encoding,
+            "raw encoding carries bytes only".to_string(),
+        )),
+        PluginPayloadEncoding::Json => {
+            serde_json::to_vec(value).map_err(|err| payload_error(encoding, err.to_string()))
+        }
+        PluginPayloadEncoding::Msgpack => {
+            rmp_serde::to_vec_named(value).map_err(|err| payload_error(encoding, err.to_string()))
+        }
+    }
+}
+
+/// ペイロードを `encoding` で復号する。
+pub fn decode_payload<T: DeserializeOwned>(
+    encoding: PluginPayloadEncoding,
+    bytes: &[u8],
+) -> Result<T, PluginError> {
+    match encoding {
+        PluginPayloadEncoding::Raw => Err(payload_error(
+            encoding,
+            "raw encoding carries bytes only".to_string(),
+        )),
+        PluginPayloadEncoding::Json => {
+            serde_json::from_slice(bytes).map_err(|err| payload_error(encoding, err.to_string()))
+        }
+        PluginPayloadEncoding::Msgpack => {
+            rmp_serde::from_slice(bytes).map_err(|err| payload_error(encoding, err.to_string()))
+        }
+    }
+}
+
+fn payload_error(encoding: PluginPayloadEncoding, message: String) -> PluginError {
+    PluginError::PayloadInvalid {
+        encoding: encoding.as_str().to_string(),
+        message,
+    }
+}
+
+/// 宣言された ABI をホストが扱えるか、v1 に必要なエクスポートがあるかを読み込み時に検査する。
+///
+/// ゲストが名乗る版数そのものはインスタンス化時に `reml_abi_version` で照合する。
+pub(crate) fn check_module_abi(
+    plugin_id: &str,
+    module: &Module,
+    abi: &PluginAbi,
+) -> Result<(), PluginError> {
+    if !SUPPORTED_ABI_VERSIONS.contains(&abi.version) {
+        return Err(abi_mismatch(
+            plugin_id,
+            format!(
+                "abi.version {} is not supported (supported: {:?})",
+                abi.version, SUPPORTED_ABI_VERSIONS
+            ),
+        ));
+    }
+    if abi.version == 0 {
+        return Ok(());
+    }
+    for name in [ABI_VERSION_EXPORT, ALLOC_EXPORT, DEALLOC_EXPORT] {
+        if !matches!(module.get_export(name), Some(ExternType::Func(_))) {
+            return Err(abi_mismatch(
+                plugin_id,
+                format!("abi.version {} requires export `{name}`", abi.version),
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn abi_mismatch(plugin_id: &str, detail: String) -> PluginError {
+    PluginError::AbiMismatch {
+        plugin_id: plugin_id.to_string(),
+        detail,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Request {
+        name: String,
+        count: u32,
+    }
+
+    #[test]
+    fn payload_round_trips_through_json_and_msgpack() {
+        let request = Request {
+            name: "reml".into(),
+            count: 3,
+        };
+        for encoding in [PluginPayloadEncoding::Json, PluginPayloadEncoding::Msgpack] {
+            let bytes = encode_payload(encoding, &request).expect("encode");
+            let decoded: Request = decode_payload(encoding, &bytes).expect("decode");
+            assert_eq!(decoded, request);
+        }
+        assert!(matches!(
+            encode_payload(PluginPayloadEncoding::Raw, &request),
+            Err(PluginError::PayloadInvalid { .. })
+        ));
+        assert_eq!(unpack_result((2048_i64 << 32) | 5), (2048, 5));
+    }
+}
@@ -1,13 +1,24 @@
 use crate::config::manifest::{Manifest, ManifestCapabilities};
 use crate::runtime::bridge::{BridgeMetadata, RuntimeBridgeRegistry};
-use crate::runtime::plugin::PluginError;
+use crate::runtime::plugin::{
+    PluginAbi, PluginError, PluginExecutionPolicy, PluginInstanceMode, PluginPayloadEncoding,
+};
+use crate::runtime::plugin_abi::{self, ABI_VERSION_EXPORT, ALLOC_EXPORT, DEALLOC_EXPORT};
+use crate::runtime::plugin_host::{self, PluginHostPolicy, PluginHostState};
+use crate::runtime::plugin_limits::{self, EpochTicker, PluginLimiter};
 use crate::stage::{StageId, StageRequirement};
+use once_cell::sync::OnceCell;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
-use wasmtime::{Engine, Instance, Module, Store};
+use std::sync::{Arc, Mutex};
+use wasmtime::{
+    Config, Engine, Instance, InstanceAllocationStrategy, Linker, Memory, Module,
+    PoolingAllocationConfig, Store, TypedFunc, WasmParams, WasmResults,
+};
 
 #[derive(Debug, Clone)]
 pub struct PluginInstance {
@@ -20,15 +31,46 @@ pub struct PluginInvokeRequest {
     pub payload: Vec<u8>,
 }
 
+impl PluginInvokeRequest {
+    /// `value` を `encoding` で符号化したリクエストを作る。
+    pub fn encoded<T: Serialize + ?Sized>(
+        entrypoint: impl Into<String>,
+        encoding: PluginPayloadEncoding,
+        value: &T,
+    ) -> Result<Self, PluginError> {
+        Ok(Self {
+            entrypoint: entrypoint.into(),
+            payload: plugin_abi::encode_payload(encoding, value)?,
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PluginInvokeResponse {
     pub payload: Vec<u8>,
 }
 
+impl PluginInvokeResponse {
+    /// ペイロードを `encoding` で復号する。
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        encoding: PluginPayloadEncoding,
+    ) -> Result<T, PluginError> {
+        plugin_abi::decode_payload(encoding, &self.payload)
+    }
+}
+
 pub struct PluginLoadRequest<'a> {
     pub manifest: &'a Manifest,
     pub bundle_hash: Option<&'a str>,
     pub module_path: Option<&'a Path>,
+    /// バンドル検証時に計算したモジュールのハッシュ。指定時は読み込み直したモジュールが
+    /// 一致しなければ拒否する（検証後の差し替えを防ぐ）。
+    pub module_hash: Option<&'a str>,
+    /// バンドルの `plugins[].execution`。`None` は制限なしの `per_call`。
+    pub execution: Option<&'a PluginExecutionPolicy>,
+    /// バンドルの `plugins[].abi`。`None` は版数 0 の旧方式。
+    pub abi: Option<&'a PluginAbi>,
 }
 
 pub trait PluginExecutionBridge: Send + Sync {
@@ -108,9 +150,15 @@ impl PluginExecutionBridge for NativePluginExecutionBridge {
     }
 }
 
+/// 長寿命インスタンス用プールアロケータのスロット数（ブリッジ全体）。
+const POOLED_INSTANCE_SLOTS: u32 = 16;
+/// プールの 1 スロットあたりの線形メモリ上限（64 KiB ページ単位、64 MiB）。
+const POOLED_MEMORY_PAGES: u64 = 1024;
+
 pub struct PluginWasmBridge {
     registry: &'static RuntimeBridgeRegistry,
-    engine: Engine,
+    per_call: Arc<WasmRuntime>,
+    long_lived: OnceCell<Arc<WasmRuntime>>,
     modules: Mutex<HashMap<String, WasmModuleRecord>>,
 }
 
@@ -118,10 +166,29 @@ impl PluginWasmBridge {
     pub fn new() -> Self {
         Self {
             registry: RuntimeBridgeRegistry::global(),
-            engine: Engine::default(),
+            per_call: Arc::new(WasmRuntime::new(InstanceAllocationStrategy::OnDemand)),
+            long_lived: OnceCell::new(),
             modules: Mutex::new(HashMap::new()),
         }
     }
+
+    fn runtime_for(&self, mode: PluginInstanceMode) -> Arc<WasmRuntime> {
+        match mode {
+            PluginInstanceMode::PerCall => self.per_call.clone(),
+            PluginInstanceMode::LongLived => self
+                .long_lived
+                .get_or_init(|| {
+                    let mut pooling = PoolingAllocationConfig::default();
+                    pooling
+                        .instance_count(POOLED_INSTANCE_SLOTS)
+                        .instance_memory_pages(POOLED_MEMORY_PAGES);
+                    Arc::new(WasmRuntime::new(InstanceAllocationStrategy::Pooling(
+                        pooling,
+                    )))
+                })
+                .clone(),
+        }
+    }
 }
 
 impl Default for PluginWasmBridge {
@@ -138,16 +205,38 @@ impl PluginExecutionBridge for PluginWasmBridge {
                 message: "wasm module path is missing".to_string(),
             })?;
 
+        let execution = request.execution.cloned().unwrap_or_default();
+        let runtime = self.runtime_for(execution.instance_mode);
+        if execution.timeout_ms.is_some() {
+            runtime.ensure_epoch_ticker();
+        }
+
         let module_bytes = fs::read(module_path).map_err(|err| PluginError::Io {
             message: err.to_string(),
         })?;
         let module_hash = compute_module_hash(&module_bytes);
-        let module = Module::new(&self.engine, &module_bytes).map_err(|err| {
+        if let Some(expected) = request.module_hash {
+            if expected != module_hash {
+                return Err(PluginError::VerificationFailed {
+                    message: format!(
+                        "wasm module {} changed after verification (expected {expected}, found {module_hash})",
+                        module_path.display()
+                    ),
+                });
+            }
+        }
+        let module = Module::new(&runtime.engine, &module_bytes).map_err(|err| {
             PluginError::VerificationFailed {
                 message: err.to_string(),
             }
         })?;
 
+        let plugin_id = request.manifest.project.name.0.clone();
+        let abi = request.abi.copied().unwrap_or_default();
+        plugin_host::check_module_imports(&plugin_id, &module)?;
+        plugin_abi::check_module_abi(&plugin_id, &module, &abi)?;
+        let host_policy = PluginHostPolicy::from_manifest(request.manifest)?;
+
         let capabilities =
             ManifestCapabilities::from_manifest(request.manifest).map_err(|err| {
                 PluginError::VerificationFailed {
@@ -165,7 +254,6 @@ impl PluginExecutionBridge for PluginWasmBridge {
             );
         }
 
-        let plugin_id = request.manifest.project.name.0.clone();
         let mut guard = self
             .modules
             .lock()
@@ -174,6 +262,11 @@ impl PluginExecutionBridge for PluginWasmBridge {
             plugin_id.clone(),
             WasmModuleRecord {
                 module,
+                runtime,
+                host_policy: Arc::new(host_policy),
+                execution,
+                abi,
+                idle: Arc::new(Mutex::new(Vec::new())),
                 module_path: module_path.to_path_buf(),
                 module_hash,
                 bundle_hash,
@@ -201,67 +294,315 @@ impl PluginExecutionBridge for PluginWasmBridge {
                 })?
         };
 
-        let mut store = Store::new(&self.engine, ());
-        let instance =
-            Instance::new(&mut store, &module.module, &[]).map_err(|err| PluginError::Bridge {
+        let long_lived = module.execution.instance_mode == PluginInstanceMode::LongLived;
+        let idle = if long_lived {
+            module
+                .idle
+                .lock()
+                .expect("WasmModuleRecord.idle poisoned")
+                .pop()
+        } else {
+            None
+        };
+        let mut slot = match idle {
+            Some(slot) => slot,
+            None => module.instantiate(&instance.plugin_id, &request.entrypoint)?,
+        };
+
+        // トラップしたインスタンスは状態が不定なのでプールへ戻さない。
+        let response = slot.call(&module, &instance.plugin_id, &request)?;
+        if long_lived {
+            let mut idle = module.idle.lock().expect("WasmModuleRecord.idle poisoned");
+            if idle.len() < module.execution.pool_size.unwrap_or(1) as usize {
+                idle.push(slot);
+            }
+        }
+        Ok(response)
+    }
+
+    fn unload(&self, instance: PluginInstance) -> Result<(), PluginError> {
+        let mut guard = self
+            .modules
+            .lock()
+            .expect("PluginWasmBridge.modules poisoned");
+        guard.remove(&instance.plugin_id);
+        Ok(())
+    }
+}
+
+/// `Engine` と、それに結び付いた `Linker` / epoch スレッド。
+struct WasmRuntime {
+    engine: Engine,
+    linker: Linker<PluginHostState>,
+    epoch_ticker: OnceCell<EpochTicker>,
+}
+
+impl WasmRuntime {
+    fn new(strategy: InstanceAllocationStrategy) -> Self {
+        let mut config = Config::new();
+        config
+            .consume_fuel(true)
+            .epoch_interruption(true)
+            .allocation_strategy(strategy);
+        let engine = Engine::new(&config).expect("plugin engine config should be valid");
+        let linker =
+            plugin_host::build_linker(&engine).expect("plugin host imports should be defined once");
+        Self {
+            engine,
+            linker,
+            epoch_ticker: OnceCell::new(),
+        }
+    }
+
+    fn ensure_epoch_ticker(&self) {
+        self.epoch_ticker
+            .get_or_init(|| EpochTicker::start(self.engine.clone()));
+    }
+}
+
+#[derive(Clone)]
+struct WasmModuleRecord {
+    module: Module,
+    runtime: Arc<WasmRuntime>,
+    host_policy: Arc<PluginHostPolicy>,
+    execution: PluginExecutionPolicy,
+    abi: PluginAbi,
+    idle: Arc<Mutex<Vec<WasmInstanceSlot>>>,
+    module_path: PathBuf,
+    module_hash: String,
+    bundle_hash: Option<String>,
+}
+
+impl WasmModuleRecord {
+    fn instantiate(
+        &self,
+        plugin_id: &str,
+        entrypoint: &str,
+    ) -> Result<WasmInstanceSlot, PluginError> {
+        let mut store = Store::new(
+            &self.runtime.engine,
+            PluginHostState::new(
+                self.host_policy.clone(),
+                PluginLimiter::new(&self.execution),
+            ),
+        );
+        store.limiter(|state| &mut state.limiter);
+        plugin_limits::arm_call_limits(&mut store, &self.execution).map_err(|err| {
+            PluginError::Bridge {
                 message: err.to_string(),
-            })?;
-        let memory =
-            instance
-                .get_memory(&mut store, "memory")
-                .ok_or_else(|| PluginError::Bridge {
-                    message: "wasm memory export not found".to_string(),
+            }
+        })?;
+        match self.runtime.linker.instantiate(&mut store, &self.module) {
+            Ok(instance) => {
+                let mut slot = WasmInstanceSlot { store, instance };
+                slot.negotiate_abi(self, plugin_id)?;
+                Ok(slot)
+            }
+            Err(err) => {
+                let violation = store.data_mut().limiter.take_violation();
+                Err(plugin_limits::limit_error(
+                    plugin_id,
+                    entrypoint,
+                    &self.execution,
+                    violation,
+                    Some(&err),
+                )
+                .unwrap_or_else(|| plugin_host::call_error(plugin_id, err)))
+            }
+        }
+    }
+}
+
+/// インスタンス化済みの `Store`。`long_lived` ではプールに保持して再利用する。
+struct WasmInstanceSlot {
+    store: Store<PluginHostState>,
+    instance: Instance,
+}
+
+impl WasmInstanceSlot {
+    /// ゲストが名乗る ABI 版数（`reml_abi_version`、なければ 0）をバンドルの宣言と照合する。
+    fn negotiate_abi(
+        &mut self,
+        module: &WasmModuleRecord,
+        plugin_id: &str,
+    ) -> Result<(), PluginError> {
+        let reported = match self.instance.get_func(&mut self.store, ABI_VERSION_EXPORT) {
+            None => 0,
+            Some(func) => {
+                let typed = func.typed::<(), i32>(&self.store).map_err(|_| {
+                    plugin_abi::abi_mismatch(
+                        plugin_id,
+                        format!("export `{ABI_VERSION_EXPORT}` must be () -> i32"),
+                    )
                 })?;
+                let outcome = typed.call(&mut self.store, ());
+                self.settle(module, plugin_id, ABI_VERSION_EXPORT, outcome)? as u32
+            }
+        };
+        if reported != module.abi.version {
+            return Err(plugin_abi::abi_mismatch(
+                plugin_id,
+                format!(
+                    "bundle declares abi.version {} but module reports {reported}",
+                    module.abi.version
+                ),
+            ));
+        }
+        Ok(())
+    }
 
-        let payload_len = request.payload.len();
-        memory
-            .write(&mut store, 0, &request.payload)
-            .map_err(|err| PluginError::Bridge {
+    fn call(
+        &mut self,
+        module: &WasmModuleRecord,
+        plugin_id: &str,
+        request: &PluginInvokeRequest,
+    ) -> Result<PluginInvokeResponse, PluginError> {
+        plugin_limits::arm_call_limits(&mut self.store, &module.execution).map_err(|err| {
+            PluginError::Bridge {
                 message: err.to_string(),
-            })?;
+            }
+        })?;
+        self.store.data_mut().limiter.take_violation();
 
-        let func = instance
-            .get_func(&mut store, request.entrypoint.as_str())
-            .ok_or_else(|| PluginError::VerificationFailed {
-                message: format!("unknown entrypoint: {}", request.entrypoint),
+        let memory = self
+            .instance
+            .get_memory(&mut self.store, "memory")
+            .ok_or_else(|| PluginError::Bridge {
+                message: "wasm memory export not found".to_string(),
             })?;
-        let typed = func
-            .typed::<(i32, i32), i32>(&store)
+        let payload = if module.abi.version == 0 {
+            self.call_legacy(module, plugin_id, memory, request)?
+        } else {
+            self.call_v1(module, plugin_id, memory, request)?
+        };
+        Ok(PluginInvokeResponse { payload })
+    }
+
+    /// 版数 0: 入力をオフセット 0 へ書き、戻り値の長さだけオフセット 0 から読む。
+    fn call_legacy(
+        &mut self,
+        module: &WasmModuleRecord,
+        plugin_id: &str,
+        memory: Memory,
+        request: &PluginInvokeRequest,
+    ) -> Result<Vec<u8>, PluginError> {
+        memory
+            .write(&mut self.store, 0, &request.payload)
             .map_err(|err| PluginError::Bridge {
                 message: err.to_string(),
             })?;
-        let response_len = typed
-            .call(&mut store, (0, payload_len as i32))
+        let entry = self
+            .entrypoint::<(i32, i32), i32>(&request.entrypoint)?
             .map_err(|err| PluginError::Bridge {
                 message: err.to_string(),
             })?;
+        let outcome = entry.call(&mut self.store, (0, request.payload.len() as i32));
+        let response_len = self.settle(module, plugin_id, &request.entrypoint, outcome)?;
 
         let mut response = vec![0u8; response_len as usize];
         memory
-            .read(&mut store, 0, &mut response)
+            .read(&self.store, 0, &mut response)
             .map_err(|err| PluginError::Bridge {
                 message: err.to_string(),
             })?;
+        Ok(response)
+    }
+
+    /// 版数 1: ゲストの `alloc` で確保したバッファを介して受け渡す。
+    fn call_v1(
+        &mut self,
+        module: &WasmModuleRecord,
+        plugin_id: &str,
+        memory: Memory,
+        request: &PluginInvokeRequest,
+    ) -> Result<Vec<u8>, PluginError> {
+        let entry = self
+            .entrypoint::<(i32, i32), i64>(&request.entrypoint)?
+            .map_err(|_| {
+                plugin_abi::abi_mismatch(
+                    plugin_id,
+                    format!(
+                        "entrypoint `{}` must be (i32, i32) -> i64",
+                        request.entrypoint
+                    ),
+                )
+            })?;
+        let alloc = self.abi_export::<i32, i32>(plugin_id, ALLOC_EXPORT)?;
+        let dealloc = self.abi_export::<(i32, i32), ()>(plugin_id, DEALLOC_EXPORT)?;
+        let input_len = i32::try_from(request.payload.len()).map_err(|_| PluginError::Bridge {
+            message: format!("payload too large: {} bytes", request.payload.len()),
+        })?;
+
+        let outcome = alloc.call(&mut self.store, input_len);
+        let input = self.settle(module, plugin_id, &request.entrypoint, outcome)?;
+        memory
+            .write(&mut self.store, input as u32 as usize, &request.payload)
+            .map_err(|err| PluginError::Bridge {
+                message: format!("input buffer from `{ALLOC_EXPORT}` is out of bounds: {err}"),
+            })?;
 
-        Ok(PluginInvokeResponse { payload: response })
+        let outcome = entry.call(&mut self.store, (input, input_len));
+        let packed = self.settle(module, plugin_id, &request.entrypoint, outcome)?;
+        let (output, output_len) = plugin_abi::unpack_result(packed);
+        let mut response = vec![0u8; output_len as usize];
+        memory
+            .read(&self.store, output as usize, &mut response)
+            .map_err(|err| PluginError::Bridge {
+                message: format!(
+                    "output range returned by `{}` is out of bounds: {err}",
+                    request.entrypoint
+                ),
+            })?;
+
+        let outcome = dealloc.call(&mut self.store, (output as i32, output_len as i32));
+        self.settle(module, plugin_id, &request.entrypoint, outcome)?;
+        Ok(response)
     }
 
-    fn unload(&self, instance: PluginInstance) -> Result<(), PluginError> {
-        let mut guard = self
-            .modules
-            .lock()
-            .expect("PluginWasmBridge.modules poisoned");
-        guard.remove(&instance.plugin_id);
-        Ok(())
+    /// エントリポイントを引く。外側の `Err` は未定義、内側はシグネチャ不一致。
+    fn entrypoint<Params: WasmParams, Results: WasmResults>(
+        &mut self,
+        name: &str,
+    ) -> Result<anyhow::Result<TypedFunc<Params, Results>>, PluginError> {
+        let func = self
+            .instance
+            .get_func(&mut self.store, name)
+            .ok_or_else(|| PluginError::VerificationFailed {
+                message: format!("unknown entrypoint: {name}"),
+            })?;
+        Ok(func.typed::<Params, Results>(&self.store))
     }
-}
 
-#[derive(Clone)]
-struct WasmModuleRecord {
-    module: Module,
-    module_path: PathBuf,
-    module_hash: String,
-    bundle_hash: Option<String>,
+    fn abi_export<Params: WasmParams, Results: WasmResults>(
+        &mut self,
+        plugin_id: &str,
+        name: &str,
+    ) -> Result<TypedFunc<Params, Results>, PluginError> {
+        self.instance
+            .get_typed_func::<Params, Results>(&mut self.store, name)
+            .map_err(|err| plugin_abi::abi_mismatch(plugin_id, format!("export `{name}`: {err}")))
+    }
+
+    /// ゲスト呼び出しの結果を、資源制限の違反・トラップを含めて `PluginError` へ写す。
+    fn settle<R>(
+        &mut self,
+        module: &WasmModuleRecord,
+        plugin_id: &str,
+        entrypoint: &str,
+        outcome: anyhow::Result<R>,
+    ) -> Result<R, PluginError> {
+        let violation = self.store.data_mut().limiter.take_violation();
+        if let Some(error) = plugin_limits::limit_error(
+            plugin_id,
+            entrypoint,
+            &module.execution,
+            violation,
+            outcome.as_ref().err(),
+        ) {
+            return Err(error);
+        }
+        outcome.map_err(|err| plugin_host::call_error(plugin_id, err))
+    }
 }
 
 fn stage_from_requirement(requirement: StageRequirement) -> StageId {
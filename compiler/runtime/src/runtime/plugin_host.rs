@@ -0,0 +1,1072 @@
+//! WASM プラグインへ公開するホスト import。
+//!
+//! `reml.v1` 名前空間（ログ・Capability 照会・設定参照）と
+//! `wasi_snapshot_preview1` の一部（時計・preopen ディレクトリ配下のファイル）を
+//! `Linker` に常に定義し、呼び出し時に `reml.toml` の `[plugin].imports` で
+//! 要求されたグループかどうかを検査する。要求していない import の呼び出しは
+//! トラップとなり、`PluginError::ImportDenied` として呼び出し元へ返る。
+
+use crate::capability::CapabilityRegistry;
+use crate::config::manifest::Manifest;
+use crate::runtime::plugin::{record_log_audit, PluginError};
+use crate::runtime::plugin_limits::PluginLimiter;
+use crate::stage::StageId;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use wasmtime::{Caller, Engine, Extern, Linker, Memory, Module};
+
+/// Reml ホスト関数の import モジュール名。
+pub const REML_IMPORT_MODULE: &str = "reml.v1";
+/// WASI preview1 の import モジュール名。
+pub const WASI_IMPORT_MODULE: &str = "wasi_snapshot_preview1";
+
+const CAPABILITY_FS_READ: &str = "io.fs.read";
+const CAPABILITY_FS_WRITE: &str = "io.fs.write";
+
+/// `[plugin].imports` で要求できる import グループ。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HostImportGroup {
+    HostLog,
+    HostCapability,
+    HostConfig,
+    WasiClock,
+    WasiFs,
+}
+
+impl HostImportGroup {
+    pub const ALL: [HostImportGroup; 5] = [
+        HostImportGroup::HostLog,
+        HostImportGroup::HostCapability,
+        HostImportGroup::HostConfig,
+        HostImportGroup::WasiClock,
+        HostImportGroup::WasiFs,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HostImportGroup::HostLog => "host.log",
+            HostImportGroup::HostCapability => "host.capability",
+            HostImportGroup::HostConfig => "host.config",
+            HostImportGroup::WasiClock => "wasi.clock",
+            HostImportGroup::WasiFs => "wasi.fs",
+        }
+    }
+
+    pub fn parse(label: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|group| group.as_str() == label.trim())
+    }
+}
+
+impl fmt::Display for HostImportGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+const WASI_ANY: &[HostImportGroup] = &[HostImportGroup::WasiClock, HostImportGroup::WasiFs];
+const WASI_FS: &[HostImportGroup] = &[HostImportGroup::WasiFs];
+const WASI_CLOCK: &[HostImportGroup] = &[HostImportGroup::WasiClock];
+
+/// 定義済み import と、呼び出しに必要なグループ（いずれか 1 つ）の一覧。
+const HOST_IMPORTS: &[(&str, &str, &[HostImportGroup])] = &[
+    (REML_IMPORT_MODULE, "log", &[HostImportGroup::HostLog]),
+    (
+        REML_IMPORT_MODULE,
+        "capability_stage",
+        &[HostImportGroup::HostCapability],
+    ),
+    (
+        REML_IMPORT_MODULE,
+        "config_get",
+        &[HostImportGroup::HostConfig],
+    ),
+    (WASI_IMPORT_MODULE, "args_sizes_get", WASI_ANY),
+    (WASI_IMPORT_MODULE, "args_get", WASI_ANY),
+    (WASI_IMPORT_MODULE, "environ_sizes_get", WASI_ANY),
+    (WASI_IMPORT_MODULE, "environ_get", WASI_ANY),
+    (WASI_IMPORT_MODULE, "proc_exit", WASI_ANY),
+    (WASI_IMPORT_MODULE, "clock_time_get", WASI_CLOCK),
+    (WASI_IMPORT_MODULE, "clock_res_get", WASI_CLOCK),
+    (WASI_IMPORT_MODULE, "fd_prestat_get", WASI_FS),
+    (WASI_IMPORT_MODULE, "fd_prestat_dir_name", WASI_FS),
+    (WASI_IMPORT_MODULE, "fd_fdstat_get", WASI_FS),
+    (WASI_IMPORT_MODULE, "path_open", WASI_FS),
+    (WASI_IMPORT_MODULE, "fd_read", WASI_FS),
+    (WASI_IMPORT_MODULE, "fd_write", WASI_FS),
+    (WASI_IMPORT_MODULE, "fd_seek", WASI_FS),
+    (WASI_IMPORT_MODULE, "fd_close", WASI_FS),
+];
+
+fn import_groups(module: &str, name: &str) -> Option<&'static [HostImportGroup]> {
+    HOST_IMPORTS
+        .iter()
+        .find(|(entry_module, entry_name, _)| *entry_module == module && *entry_name == name)
+        .map(|(_, _, groups)| *groups)
+}
+
+/// 要求されていない import を呼び出したときのトラップ。
+#[derive(Debug)]
+pub(crate) struct ImportDenied {
+    pub(crate) import: String,
+}
+
+impl fmt::Display for ImportDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "plugin import denied: {}", self.import)
+    }
+}
+
+impl std::error::Error for ImportDenied {}
+
+/// `proc_exit` によるトラップ。
+#[derive(Debug)]
+pub(crate) struct PluginExit(pub(crate) i32);
+
+impl fmt::Display for PluginExit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "plugin exited with code {}", self.0)
+    }
+}
+
+impl std::error::Error for PluginExit {}
+
+#[derive(Debug, Clone)]
+struct Preopen {
+    host: PathBuf,
+    guest: String,
+    writable: bool,
+}
+
+/// マニフェストから導出したホスト import の許可範囲。
+#[derive(Debug, Clone)]
+pub(crate) struct PluginHostPolicy {
+    plugin_id: String,
+    groups: BTreeSet<HostImportGroup>,
+    config: BTreeMap<String, Value>,
+    preopens: Vec<Preopen>,
+}
+
+impl PluginHostPolicy {
+    pub(crate) fn from_manifest(manifest: &Manifest) -> Result<Self, PluginError> {
+        let section = &manifest.plugin;
+        let mut groups = BTreeSet::new();
+        for label in &section.imports {
+            let group =
+                HostImportGroup::parse(label).ok_or_else(|| PluginError::VerificationFailed {
+                    message: format!("unknown plugin import group: {label}"),
+                })?;
+            groups.insert(group);
+        }
+
+        let registry = CapabilityRegistry::registry();
+        let mut capabilities = BTreeSet::new();
+        for capability in &section.capabilities {
+            registry.describe(capability)?;
+            capabilities.insert(capability.clone());
+        }
+
+        if groups.contains(&HostImportGroup::WasiFs) && !capabilities.contains(CAPABILITY_FS_READ) {
+            return Err(PluginError::VerificationFailed {
+                message: format!("wasi.fs import requires capability {CAPABILITY_FS_READ}"),
+            });
+        }
+
+        let base_dir = manifest
+            .manifest_path()
+            .and_then(|path| path.parent())
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let mut preopens = Vec::new();
+        for entry in &section.preopens {
+            if entry.writable && !capabilities.contains(CAPABILITY_FS_WRITE) {
+                return Err(PluginError::VerificationFailed {
+                    message: format!(
+                        "writable preopen {} requires capability {CAPABILITY_FS_WRITE}",
+                        entry.guest
+                    ),
+                });
+            }
+            preopens.push(Preopen {
+                host: base_dir.join(&entry.host),
+                guest: entry.guest.clone(),
+                writable: entry.writable,
+            });
+        }
+
+        Ok(Self {
+            plugin_id: manifest.project.name.0.clone(),
+            groups,
+            config: section.config.clone(),
+            preopens,
+        })
+    }
+
+    fn allows(&self, groups: &[HostImportGroup]) -> bool {
+        groups.iter().any(|group| self.groups.contains(group))
+    }
+}
+
+/// import の解決前検査。定義されていない import を持つモジュールは拒否する。
+pub(crate) fn check_module_imports(plugin_id: &str, module: &Module) -> Result<(), PluginError> {
+    for import in module.imports() {
+        let is_func = matches!(import.ty(), wasmtime::ExternType::Func(_));
+        if !is_func || import_groups(import.module(), import.name()).is_none() {
+            return Err(PluginError::ImportUnresolved {
+                plugin_id: plugin_id.to_string(),
+                import: format!("{}::{}", import.module(), import.name()),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// トラップを `PluginError` へ変換する。
+pub(crate) fn call_error(plugin_id: &str, err: anyhow::Error) -> PluginError {
+    if let Some(denied) = err.downcast_ref::<ImportDenied>() {
+        return PluginError::ImportDenied {
+            plugin_id: plugin_id.to_string(),
+            import: denied.import.clone(),
+        };
+    }
+    if let Some(exit) = err.downcast_ref::<PluginExit>() {
+        return PluginError::Bridge {
+            message: exit.to_string(),
+        };
+    }
+    PluginError::Bridge {
+        message: err.to_string(),
+    }
+}
+
+enum HostFd {
+    Preopen(usize),
+    File { file: File, writable: bool },
+}
+
+/// 1 回の呼び出しに対応する `Store` のデータ。
+pub(crate) struct PluginHostState {
+    policy: Arc<PluginHostPolicy>,
+    pub(crate) limiter: PluginLimiter,
+    fds: HashMap<u32, HostFd>,
+    next_fd: u32,
+}
+
+impl PluginHostState {
+    pub(crate) fn new(policy: Arc<PluginHostPolicy>, limiter: PluginLimiter) -> Self {
+        let mut fds = HashMap::new();
+        let mut next_fd = 3;
+        if policy.groups.contains(&HostImportGroup::WasiFs) {
+            for index in 0..policy.preopens.len() {
+                fds.insert(next_fd, HostFd::Preopen(index));
+                next_fd += 1;
+            }
+        }
+        Self {
+            policy,
+            limiter,
+            fds,
+            next_fd,
+        }
+    }
+}
+
+type HostCaller<'a> = Caller<'a, PluginHostState>;
+type Errno = i32;
+
+const ERRNO_SUCCESS: Errno = 0;
+const ERRNO_BADF: Errno = 8;
+const ERRNO_FAULT: Errno = 21;
+const ERRNO_INVAL: Errno = 28;
+const ERRNO_IO: Errno = 29;
+const ERRNO_NOENT: Errno = 44;
+const ERRNO_NOTCAPABLE: Errno = 76;
+
+/// `fd_read` が 1 回に読むバイト数の上限。
+const FD_READ_CHUNK: usize = 64 * 1024;
+
+const FILETYPE_CHARACTER_DEVICE: u8 = 2;
+const FILETYPE_DIRECTORY: u8 = 3;
+const FILETYPE_REGULAR_FILE: u8 = 4;
+
+const OFLAGS_CREAT: i32 = 1;
+const OFLAGS_DIRECTORY: i32 = 2;
+const OFLAGS_EXCL: i32 = 4;
+const OFLAGS_TRUNC: i32 = 8;
+const FDFLAGS_APPEND: i32 = 1;
+const RIGHTS_FD_WRITE: i64 = 1 << 6;
+
+static MONOTONIC_ORIGIN: Lazy<Instant> = Lazy::new(Instant::now);
+
+fn ensure_import(caller: &HostCaller<'_>, module: &str, name: &str) -> anyhow::Result<()> {
+    let allowed = import_groups(module, name)
+        .map(|groups| caller.data().policy.allows(groups))
+        .unwrap_or(false);
+    if allowed {
+        Ok(())
+    } else {
+        Err(anyhow::Error::new(ImportDenied {
+            import: format!("{module}::{name}"),
+        }))
+    }
+}
+
+fn errno(result: Result<(), Errno>) -> i32 {
+    match result {
+        Ok(()) => ERRNO_SUCCESS,
+        Err(code) => code,
+    }
+}
+
+fn guest_memory(caller: &mut HostCaller<'_>) -> anyhow::Result<Memory> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| anyhow::Error::msg("wasm memory export not found"))
+}
+
+/// ゲストが渡した範囲が線形メモリに収まるか確かめる。ホスト側のバッファを確保する前に呼ぶ。
+fn check_guest_range(caller: &mut HostCaller<'_>, ptr: i32, len: i32) -> anyhow::Result<()> {
+    let memory = guest_memory(caller)?;
+    let end = (ptr as u32 as usize).checked_add(len as u32 as usize);
+    match end {
+        Some(end) if end <= memory.data_size(&*caller) => Ok(()),
+        _ => Err(anyhow::Error::msg("guest range is out of bounds")),
+    }
+}
+
+fn read_guest(caller: &mut HostCaller<'_>, ptr: i32, len: i32) -> anyhow::Result<Vec<u8>> {
+    check_guest_range(caller, ptr, len)?;
+    let memory = guest_memory(caller)?;
+    let mut buffer = vec![0u8; len as u32 as usize];
+    memory.read(&*caller, ptr as u32 as usize, &mut buffer)?;
+    Ok(buffer)
+}
+
+fn write_guest(caller: &mut HostCaller<'_>, ptr: i32, bytes: &[u8]) -> anyhow::Result<()> {
+    let memory = guest_memory(caller)?;
+    memory.write(&mut *caller, ptr as u32 as usize, bytes)?;
+    Ok(())
+}
+
+fn read_guest_str(caller: &mut HostCaller<'_>, ptr: i32, len: i32) -> anyhow::Result<String> {
+    let bytes = read_guest(caller, ptr, len)?;
+    String::from_utf8(bytes).map_err(anyhow::Error::new)
+}
+
+fn write_u32(caller: &mut HostCaller<'_>, ptr: i32, value: u32) -> Result<(), Errno> {
+    write_guest(caller, ptr, &value.to_le_bytes()).map_err(|_| ERRNO_FAULT)
+}
+
+fn write_u64(caller: &mut HostCaller<'_>, ptr: i32, value: u64) -> Result<(), Errno> {
+    write_guest(caller, ptr, &value.to_le_bytes()).map_err(|_| ERRNO_FAULT)
+}
+
+/// `iovec` / `ciovec` 配列を `(ptr, len)` の組として読み出す。
+fn read_iovecs(
+    caller: &mut HostCaller<'_>,
+    iovs: i32,
+    iovs_len: i32,
+) -> Result<Vec<(i32, i32)>, Errno> {
+    let raw = read_guest(caller, iovs, iovs_len.wrapping_mul(8)).map_err(|_| ERRNO_FAULT)?;
+    Ok(raw
+        .chunks_exact(8)
+        .map(|chunk| {
+            let ptr = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            let len = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+            (ptr as i32, len as i32)
+        })
+        .collect())
+}
+
+fn log_level_label(level: i32) -> &'static str {
+    match level {
+        0 => "trace",
+        1 => "debug",
+        3 => "warn",
+        4 => "error",
+        _ => "info",
+    }
+}
+
+fn stage_ordinal(stage: StageId) -> i32 {
+    match stage {
+        StageId::Experimental => 0,
+        StageId::Alpha => 1,
+        StageId::Beta => 2,
+        StageId::Stable => 3,
+    }
+}
+
+/// `config_get` が返す表現。文字列はそのまま、それ以外は JSON テキストにする。
+fn config_value_bytes(value: &Value) -> Vec<u8> {
+    match value {
+        Value::String(text) => text.clone().into_bytes(),
+        other => other.to_string().into_bytes(),
+    }
+}
+
+/// preopen 配下の相対パスを解決する。親ディレクトリや絶対パスへは出られない。
+fn resolve_guest_path(root: &Path, path: &str) -> Result<PathBuf, Errno> {
+    let mut resolved = root.to_path_buf();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(ERRNO_NOTCAPABLE)
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// `path_open` が要求する開き方。
+#[derive(Debug, Clone, Copy, Default)]
+struct OpenMode {
+    write: bool,
+    append: bool,
+    truncate: bool,
+    create: bool,
+    create_new: bool,
+}
+
+/// preopen 配下のファイルを開く。preopen のディレクトリから `openat` で 1 要素ずつ
+/// `O_NOFOLLOW` を付けて辿るため、途中や末尾がシンボリックリンクなら（判定後に差し替え
+/// られても）外へは出られない。ディレクトリは開かない。
+#[cfg(unix)]
+fn open_in_preopen(root: &Path, resolved: &Path, mode: OpenMode) -> Result<File, Errno> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+
+    let relative = resolved.strip_prefix(root).map_err(|_| ERRNO_NOTCAPABLE)?;
+    let parts = relative
+        .components()
+        .map(|component| CString::new(component.as_os_str().as_bytes()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ERRNO_INVAL)?;
+    let Some((name, dirs)) = parts.split_last() else {
+        return Err(ERRNO_NOTCAPABLE);
+    };
+    let openat = |dir: &File, name: &CString, flags: libc::c_int| {
+        // SAFETY: dir は開いたままの記述子、name は NUL 終端済み。成功時の記述子は File が所有する。
+        let fd = unsafe {
+            libc::openat(
+                dir.as_raw_fd(),
+                name.as_ptr(),
+                flags | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+                0o666 as libc::c_uint,
+            )
+        };
+        if fd < 0 {
+            let err = std::io::Error::last_os_error();
+            Err(match err.raw_os_error() {
+                Some(libc::ELOOP | libc::ENOTDIR | libc::EISDIR) => ERRNO_NOTCAPABLE,
+                _ => io_errno(&err),
+            })
+        } else {
+            Ok(unsafe { File::from_raw_fd(fd) })
+        }
+    };
+
+    let mut dir = File::open(root).map_err(|err| io_errno(&err))?;
+    for part in dirs {
+        dir = openat(&dir, part, libc::O_RDONLY | libc::O_DIRECTORY)?;
+    }
+    let mut flags = if mode.write {
+        libc::O_RDWR
+    } else {
+        libc::O_RDONLY
+    };
+    for (enabled, flag) in [
+        (mode.append, libc::O_APPEND),
+        (mode.truncate, libc::O_TRUNC),
+        (mode.create || mode.create_new, libc::O_CREAT),
+        (mode.create_new, libc::O_EXCL),
+    ] {
+        if enabled {
+            flags |= flag;
+        }
+    }
+    let file = openat(&dir, name, flags)?;
+    match file.metadata() {
+        Ok(meta) if meta.is_dir() => Err(ERRNO_NOTCAPABLE),
+        Ok(_) => Ok(file),
+        Err(err) => Err(io_errno(&err)),
+    }
+}
+
+/// `openat` を使えない環境では、リンクを解決した実体が preopen の実体配下に留まることを
+/// 確かめてから開く。未作成のファイルは親ディレクトリで判定する。
+#[cfg(not(unix))]
+fn open_in_preopen(root: &Path, resolved: &Path, mode: OpenMode) -> Result<File, Errno> {
+    use std::fs::{self, OpenOptions};
+
+    let real_root = fs::canonicalize(root).map_err(|err| io_errno(&err))?;
+    let real = match fs::symlink_metadata(resolved) {
+        Ok(_) => fs::canonicalize(resolved).map_err(|_| ERRNO_NOTCAPABLE)?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let (Some(parent), Some(name)) = (resolved.parent(), resolved.file_name()) else {
+                return Err(ERRNO_NOTCAPABLE);
+            };
+            fs::canonicalize(parent)
+                .map_err(|err| io_errno(&err))?
+                .join(name)
+        }
+        Err(err) => return Err(io_errno(&err)),
+    };
+    if !real.starts_with(&real_root) || fs::metadata(&real).is_ok_and(|meta| meta.is_dir()) {
+        return Err(ERRNO_NOTCAPABLE);
+    }
+    OpenOptions::new()
+        .read(true)
+        .write(mode.write)
+        .append(mode.append)
+        .truncate(mode.truncate)
+        .create(mode.create)
+        .create_new(mode.create_new)
+        .open(&real)
+        .map_err(|err| io_errno(&err))
+}
+
+fn io_errno(err: &std::io::Error) -> Errno {
+    match err.kind() {
+        std::io::ErrorKind::NotFound => ERRNO_NOENT,
+        std::io::ErrorKind::PermissionDenied => ERRNO_NOTCAPABLE,
+        std::io::ErrorKind::AlreadyExists => 20,
+        _ => ERRNO_IO,
+    }
+}
+
+/// ホスト import を定義した `Linker` を構築する。
+pub(crate) fn build_linker(engine: &Engine) -> anyhow::Result<Linker<PluginHostState>> {
+    let mut linker = Linker::new(engine);
+    define_reml_imports(&mut linker)?;
+    define_wasi_imports(&mut linker)?;
+    Ok(linker)
+}
+
+fn define_reml_imports(linker: &mut Linker<PluginHostState>) -> anyhow::Result<()> {
+    linker.func_wrap(
+        REML_IMPORT_MODULE,
+        "log",
+        |mut caller: HostCaller<'_>, level: i32, ptr: i32, len: i32| -> anyhow::Result<()> {
+            ensure_import(&caller, REML_IMPORT_MODULE, "log")?;
+            let message = read_guest(&mut caller, ptr, len)?;
+            record_log_audit(
+                &caller.data().policy.plugin_id,
+                log_level_label(level),
+                REML_IMPORT_MODULE,
+                &String::from_utf8_lossy(&message),
+            );
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        REML_IMPORT_MODULE,
+        "capability_stage",
+        |mut caller: HostCaller<'_>, ptr: i32, len: i32| -> anyhow::Result<i32> {
+            ensure_import(&caller, REML_IMPORT_MODULE, "capability_stage")?;
+            let capability = read_guest_str(&mut caller, ptr, len)?;
+            Ok(CapabilityRegistry::registry()
+                .describe(&capability)
+                .map(|descriptor| stage_ordinal(descriptor.stage()))
+                .unwrap_or(-1))
+        },
+    )?;
+    linker.func_wrap(
+        REML_IMPORT_MODULE,
+        "config_get",
+        |mut caller: HostCaller<'_>,
+         key_ptr: i32,
+         key_len: i32,
+         out_ptr: i32,
+         out_cap: i32|
+         -> anyhow::Result<i32> {
+            ensure_import(&caller, REML_IMPORT_MODULE, "config_get")?;
+            let key = read_guest_str(&mut caller, key_ptr, key_len)?;
+            let Some(value) = caller
+                .data()
+                .policy
+                .config
+                .get(&key)
+                .map(config_value_bytes)
+            else {
+                return Ok(-1);
+            };
+            let copy_len = value.len().min(out_cap.max(0) as usize);
+            write_guest(&mut caller, out_ptr, &value[..copy_len])?;
+            Ok(value.len() as i32)
+        },
+    )?;
+    Ok(())
+}
+
+fn define_wasi_imports(linker: &mut Linker<PluginHostState>) -> anyhow::Result<()> {
+    const WASI: &str = WASI_IMPORT_MODULE;
+
+    for name in ["args_sizes_get", "environ_sizes_get"] {
+        linker.func_wrap(
+            WASI,
+            name,
+            move |mut caller: HostCaller<'_>,
+                  count_ptr: i32,
+                  size_ptr: i32|
+                  -> anyhow::Result<i32> {
+                ensure_import(&caller, WASI, name)?;
+                Ok(errno(
+                    write_u32(&mut caller, count_ptr, 0)
+                        .and_then(|()| write_u32(&mut caller, size_ptr, 0)),
+                ))
+            },
+        )?;
+    }
+    for name in ["args_get", "environ_get"] {
+        linker.func_wrap(
+            WASI,
+            name,
+            move |caller: HostCaller<'_>, _ptrs: i32, _buf: i32| -> anyhow::Result<i32> {
+                ensure_import(&caller, WASI, name)?;
+                Ok(ERRNO_SUCCESS)
+            },
+        )?;
+    }
+    linker.func_wrap(
+        WASI,
+        "proc_exit",
+        |caller: HostCaller<'_>, code: i32| -> anyhow::Result<()> {
+            ensure_import(&caller, WASI, "proc_exit")?;
+            Err(anyhow::Error::new(PluginExit(code)))
+        },
+    )?;
+
+    linker.func_wrap(
+        WASI,
+        "clock_time_get",
+        |mut caller: HostCaller<'_>, id: i32, _precision: i64, out: i32| -> anyhow::Result<i32> {
+            ensure_import(&caller, WASI, "clock_time_get")?;
+            let now = match id {
+                0 => SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_nanos() as u64)
+                    .unwrap_or(0),
+                1 => MONOTONIC_ORIGIN.elapsed().as_nanos() as u64,
+                _ => return Ok(ERRNO_INVAL),
+            };
+            Ok(errno(write_u64(&mut caller, out, now)))
+        },
+    )?;
+    linker.func_wrap(
+        WASI,
+        "clock_res_get",
+        |mut caller: HostCaller<'_>, id: i32, out: i32| -> anyhow::Result<i32> {
+            ensure_import(&caller, WASI, "clock_res_get")?;
+            if !(0..=1).contains(&id) {
+                return Ok(ERRNO_INVAL);
+            }
+            Ok(errno(write_u64(&mut caller, out, 1)))
+        },
+    )?;
+
+    linker.func_wrap(
+        WASI,
+        "fd_prestat_get",
+        |mut caller: HostCaller<'_>, fd: i32, out: i32| -> anyhow::Result<i32> {
+            ensure_import(&caller, WASI, "fd_prestat_get")?;
+            Ok(errno(fd_prestat_get(&mut caller, fd, out)))
+        },
+    )?;
+    linker.func_wrap(
+        WASI,
+        "fd_prestat_dir_name",
+        |mut caller: HostCaller<'_>, fd: i32, path: i32, len: i32| -> anyhow::Result<i32> {
+            ensure_import(&caller, WASI, "fd_prestat_dir_name")?;
+            Ok(errno(fd_prestat_dir_name(&mut caller, fd, path, len)))
+        },
+    )?;
+    linker.func_wrap(
+        WASI,
+        "fd_fdstat_get",
+        |mut caller: HostCaller<'_>, fd: i32, out: i32| -> anyhow::Result<i32> {
+            ensure_import(&caller, WASI, "fd_fdstat_get")?;
+            Ok(errno(fd_fdstat_get(&mut caller, fd, out)))
+        },
+    )?;
+    linker.func_wrap(
+        WASI,
+        "path_open",
+        |mut caller: HostCaller<'_>,
+         dir_fd: i32,
+         _lookup_flags: i32,
+         path: i32,
+         path_len: i32,
+         oflags: i32,
+         rights_base: i64,
+         _rights_inheriting: i64,
+         fdflags: i32,
+         out_fd: i32|
+         -> anyhow::Result<i32> {
+            ensure_import(&caller, WASI, "path_open")?;
+            Ok(errno(path_open(
+                &mut caller,
+                PathOpen {
+                    dir_fd,
+                    path,
+                    path_len,
+                    oflags,
+                    rights_base,
+                    fdflags,
+                    out_fd,
+                },
+            )))
+        },
+    )?;
+    linker.func_wrap(
+        WASI,
+        "fd_read",
+        |mut caller: HostCaller<'_>,
+         fd: i32,
+         iovs: i32,
+         iovs_len: i32,
+         out: i32|
+         -> anyhow::Result<i32> {
+            ensure_import(&caller, WASI, "fd_read")?;
+            Ok(errno(fd_read(&mut caller, fd, iovs, iovs_len, out)))
+        },
+    )?;
+    linker.func_wrap(
+        WASI,
+        "fd_write",
+        |mut caller: HostCaller<'_>,
+         fd: i32,
+         iovs: i32,
+         iovs_len: i32,
+         out: i32|
+         -> anyhow::Result<i32> {
+            ensure_import(&caller, WASI, "fd_write")?;
+            Ok(errno(fd_write(&mut caller, fd, iovs, iovs_len, out)))
+        },
+    )?;
+    linker.func_wrap(
+        WASI,
+        "fd_seek",
+        |mut caller: HostCaller<'_>,
+         fd: i32,
+         offset: i64,
+         whence: i32,
+         out: i32|
+         -> anyhow::Result<i32> {
+            ensure_import(&caller, WASI, "fd_seek")?;
+            Ok(errno(fd_seek(&mut caller, fd, offset, whence, out)))
+        },
+    )?;
+    linker.func_wrap(
+        WASI,
+        "fd_close",
+        |mut caller: HostCaller<'_>, fd: i32| -> anyhow::Result<i32> {
+            ensure_import(&caller, WASI, "fd_close")?;
+            let fd = fd as u32;
+            if fd > 2 && caller.data_mut().fds.remove(&fd).is_none() {
+                return Ok(ERRNO_BADF);
+            }
+            Ok(ERRNO_SUCCESS)
+        },
+    )?;
+    Ok(())
+}
+
+fn preopen_for(caller: &HostCaller<'_>, fd: i32) -> Result<Preopen, Errno> {
+    let state = caller.data();
+    match state.fds.get(&(fd as u32)) {
+        Some(HostFd::Preopen(index)) => Ok(state.policy.preopens[*index].clone()),
+        _ => Err(ERRNO_BADF),
+    }
+}
+
+fn fd_prestat_get(caller: &mut HostCaller<'_>, fd: i32, out: i32) -> Result<(), Errno> {
+    let preopen = preopen_for(caller, fd)?;
+    // prestat: tag (u8, 0 = dir) + パディング + name_len (u32)
+    let mut prestat = [0u8; 8];
+    prestat[4..].copy_from_slice(&(preopen.guest.len() as u32).to_le_bytes());
+    write_guest(caller, out, &prestat).map_err(|_| ERRNO_FAULT)
+}
+
+fn fd_prestat_dir_name(
+    caller: &mut HostCaller<'_>,
+    fd: i32,
+    path: i32,
+    len: i32,
+) -> Result<(), Errno> {
+    let preopen = preopen_for(caller, fd)?;
+    let name = preopen.guest.as_bytes();
+    if (len as u32 as usize) < name.len() {
+        return Err(ERRNO_INVAL);
+    }
+    write_guest(caller, path, name).map_err(|_| ERRNO_FAULT)
+}
+
+fn fd_fdstat_get(caller: &mut HostCaller<'_>, fd: i32, out: i32) -> Result<(), Errno> {
+    let filetype = match fd {
+        0..=2 => FILETYPE_CHARACTER_DEVICE,
+        _ => match caller.data().fds.get(&(fd as u32)) {
+            Some(HostFd::Preopen(_)) => FILETYPE_DIRECTORY,
+            Some(HostFd::File { .. }) => FILETYPE_REGULAR_FILE,
+            None => return Err(ERRNO_BADF),
+        },
+    };
+    // fdstat: filetype (u8) + flags (u16) + rights_base (u64) + rights_inheriting (u64)
+    let mut fdstat = [0u8; 24];
+    fdstat[0] = filetype;
+    fdstat[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+    fdstat[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+    write_guest(caller, out, &fdstat).map_err(|_| ERRNO_FAULT)
+}
+
+struct PathOpen {
+    dir_fd: i32,
+    path: i32,
+    path_len: i32,
+    oflags: i32,
+    rights_base: i64,
+    fdflags: i32,
+    out_fd: i32,
+}
+
+fn path_open(caller: &mut HostCaller<'_>, request: PathOpen) -> Result<(), Errno> {
+    let preopen = preopen_for(caller, request.dir_fd)?;
+    let path = read_guest_str(caller, request.path, request.path_len).map_err(|_| ERRNO_FAULT)?;
+    let host_path = resolve_guest_path(&preopen.host, &path)?;
+    if request.oflags & OFLAGS_DIRECTORY != 0 {
+        return Err(ERRNO_NOTCAPABLE);
+    }
+
+    let wants_write = request.rights_base & RIGHTS_FD_WRITE != 0
+        || request.oflags & (OFLAGS_CREAT | OFLAGS_TRUNC) != 0
+        || request.fdflags & FDFLAGS_APPEND != 0;
+    if wants_write && !preopen.writable {
+        return Err(ERRNO_NOTCAPABLE);
+    }
+
+    let mode = if wants_write {
+        OpenMode {
+            write: true,
+            append: request.fdflags & FDFLAGS_APPEND != 0,
+            truncate: request.oflags & OFLAGS_TRUNC != 0,
+            create: request.oflags & OFLAGS_CREAT != 0,
+            create_new: request.oflags & OFLAGS_EXCL != 0,
+        }
+    } else {
+        OpenMode::default()
+    };
+    let file = open_in_preopen(&preopen.host, &host_path, mode)?;
+
+    let state = caller.data_mut();
+    let fd = state.next_fd;
+    state.next_fd += 1;
+    state.fds.insert(
+        fd,
+        HostFd::File {
+            file,
+            writable: wants_write,
+        },
+    );
+    write_u32(caller, request.out_fd, fd)
+}
+
+fn fd_read(
+    caller: &mut HostCaller<'_>,
+    fd: i32,
+    iovs: i32,
+    iovs_len: i32,
+    out: i32,
+) -> Result<(), Errno> {
+    let iovecs = read_iovecs(caller, iovs, iovs_len)?;
+    if fd == 0 {
+        return write_u32(caller, out, 0);
+    }
+    // iovec の長さはゲストが決めるため、ホスト側は固定長のバッファで区切って読む。
+    let mut buffer = vec![0u8; FD_READ_CHUNK];
+    let mut total = 0u32;
+    'iovecs: for (ptr, len) in iovecs {
+        check_guest_range(caller, ptr, len).map_err(|_| ERRNO_FAULT)?;
+        let len = len as u32 as usize;
+        let mut offset = 0;
+        while offset < len {
+            let want = (len - offset).min(FD_READ_CHUNK);
+            let read = match caller.data_mut().fds.get_mut(&(fd as u32)) {
+                Some(HostFd::File { file, .. }) => file
+                    .read(&mut buffer[..want])
+                    .map_err(|err| io_errno(&err))?,
+                _ => return Err(ERRNO_BADF),
+            };
+            write_guest(caller, ptr.wrapping_add(offset as i32), &buffer[..read])
+                .map_err(|_| ERRNO_FAULT)?;
+            total += read as u32;
+            offset += read;
+            if read < want {
+                break 'iovecs;
+            }
+        }
+    }
+    write_u32(caller, out, total)
+}
+
+fn fd_write(
+    caller: &mut HostCaller<'_>,
+    fd: i32,
+    iovs: i32,
+    iovs_len: i32,
+    out: i32,
+) -> Result<(), Errno> {
+    let mut bytes = Vec::new();
+    for (ptr, len) in read_iovecs(caller, iovs, iovs_len)? {
+        bytes.extend(read_guest(caller, ptr, len).map_err(|_| ERRNO_FAULT)?);
+    }
+    match fd {
+        1 | 2 => {
+            let (level, source) = if fd == 1 {
+                ("info", "stdout")
+            } else {
+                ("warn", "stderr")
+            };
+            let message = String::from_utf8_lossy(&bytes);
+            record_log_audit(
+                &caller.data().policy.plugin_id,
+                level,
+                source,
+                message.trim_end_matches('\n'),
+            );
+        }
+        _ => match caller.data_mut().fds.get_mut(&(fd as u32)) {
+            Some(HostFd::File {
+                file,
+                writable: true,
+            }) => file.write_all(&bytes).map_err(|err| io_errno(&err))?,
+            Some(HostFd::File { .. }) => return Err(ERRNO_NOTCAPABLE),
+            _ => return Err(ERRNO_BADF),
+        },
+    }
+    write_u32(caller, out, bytes.len() as u32)
+}
+
+fn fd_seek(
+    caller: &mut HostCaller<'_>,
+    fd: i32,
+    offset: i64,
+    whence: i32,
+    out: i32,
+) -> Result<(), Errno> {
+    let position = match whence {
+        0 => SeekFrom::Start(offset as u64),
+        1 => SeekFrom::Current(offset),
+        2 => SeekFrom::End(offset),
+        _ => return Err(ERRNO_INVAL),
+    };
+    let new_offset = match caller.data_mut().fds.get_mut(&(fd as u32)) {
+        Some(HostFd::File { file, .. }) => file.seek(position).map_err(|err| io_errno(&err))?,
+        _ => return Err(ERRNO_BADF),
+    };
+    write_u64(caller, out, new_offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn every_linker_import_is_listed() {
+        let engine = Engine::default();
+        let linker = build_linker(&engine).expect("linker");
+        let mut store = wasmtime::Store::new(
+            &engine,
+            PluginHostState::new(
+                Arc::new(PluginHostPolicy {
+                    plugin_id: "plugin.test".into(),
+                    groups: BTreeSet::new(),
+                    config: BTreeMap::new(),
+                    preopens: Vec::new(),
+                }),
+                PluginLimiter::default(),
+            ),
+        );
+        let defined: Vec<(String, String)> = linker
+            .iter(&mut store)
+            .map(|(module, name, _)| (module.to_string(), name.to_string()))
+            .collect();
+        assert_eq!(defined.len(), HOST_IMPORTS.len());
+        for (module, name) in defined {
+            assert!(
+                import_groups(&module, &name).is_some(),
+                "{module}::{name} は HOST_IMPORTS に登録されていない"
+            );
+        }
+    }
+
+    #[test]
+    fn guest_paths_stay_inside_preopen() {
+        let root = Path::new("/srv/plugin");
+        assert_eq!(
+            resolve_guest_path(root, "./data/input.txt"),
+            Ok(root.join("data/input.txt"))
+        );
+        assert_eq!(resolve_guest_path(root, "../secret"), Err(ERRNO_NOTCAPABLE));
+        assert_eq!(
+            resolve_guest_path(root, "/etc/passwd"),
+            Err(ERRNO_NOTCAPABLE)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_escape_preopen() {
+        use std::os::unix::fs::symlink;
+
+        let root = tempfile::tempdir().expect("preopen");
+        let outside = tempfile::tempdir().expect("outside");
+        fs::write(outside.path().join("secret"), b"secret").expect("secret");
+        fs::write(root.path().join("input.txt"), b"input").expect("input");
+        symlink(outside.path().join("secret"), root.path().join("leak")).expect("leak");
+        symlink(outside.path(), root.path().join("escape")).expect("escape");
+        symlink(outside.path().join("missing"), root.path().join("dangling")).expect("dangling");
+        symlink(root.path().join("input.txt"), root.path().join("alias")).expect("alias");
+        fs::create_dir(root.path().join("sub")).expect("sub");
+        fs::write(root.path().join("sub/inner.txt"), b"inner").expect("inner");
+
+        let open = |path: &str, mode: OpenMode| {
+            let resolved = resolve_guest_path(root.path(), path)?;
+            open_in_preopen(root.path(), &resolved, mode).map(|_| ())
+        };
+        let create = OpenMode {
+            write: true,
+            create: true,
+            ..OpenMode::default()
+        };
+        assert_eq!(open("leak", OpenMode::default()), Err(ERRNO_NOTCAPABLE));
+        assert_eq!(
+            open("escape/secret", OpenMode::default()),
+            Err(ERRNO_NOTCAPABLE)
+        );
+        assert_eq!(open("escape/new.txt", create), Err(ERRNO_NOTCAPABLE));
+        assert_eq!(open("dangling", create), Err(ERRNO_NOTCAPABLE));
+        assert_eq!(open("alias", OpenMode::default()), Err(ERRNO_NOTCAPABLE));
+        assert_eq!(open("sub", OpenMode::default()), Err(ERRNO_NOTCAPABLE));
+        assert_eq!(open("input.txt", OpenMode::default()), Ok(()));
+        assert_eq!(open("sub/inner.txt", OpenMode::default()), Ok(()));
+        assert_eq!(open("missing.txt", OpenMode::default()), Err(ERRNO_NOENT));
+        assert_eq!(open("new.txt", create), Ok(()));
+        assert!(root.path().join("new.txt").exists());
+        assert!(!outside.path().join("new.txt").exists());
+        assert!(!outside.path().join("missing").exists());
+    }
+}
@@ -0,0 +1,219 @@
+//! WASM プラグイン呼び出しの資源制限。
+//!
+//! バンドルの `plugins[].execution`（[`PluginExecutionPolicy`]）を `Store` へ適用する。
+//! fuel と実時間（epoch 割り込み）は呼び出しごとに再設定し、線形メモリと
+//! テーブルの拡張は [`PluginLimiter`] で拒否したうえで違反内容を記録する。
+
+use crate::runtime::plugin::{record_limit_audit, PluginError, PluginExecutionPolicy};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use wasmtime::{Engine, ResourceLimiter, Store, Trap};
+
+/// epoch を進める間隔。`timeout_ms` はこの粒度で切り上げる。
+pub(crate) const EPOCH_TICK_MS: u64 = 10;
+/// 実時間制限を持たない呼び出しの epoch 期限。
+const NO_EPOCH_DEADLINE: u64 = u64::MAX / 2;
+/// fuel 制限を持たない呼び出しに与える量。`add_fuel` は累積値が i64 を
+/// 超えると破綻するため、最大値ではなく消費分だけを補充する。
+const UNLIMITED_FUEL: u64 = 1 << 62;
+
+/// 拡張を拒否した線形メモリ / テーブルの要求。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LimitViolation {
+    Memory { limit: u64, requested: u64 },
+    Table { limit: u32, requested: u32 },
+}
+
+/// `Store` に登録する `ResourceLimiter`。最初の違反だけを保持する。
+#[derive(Debug, Default)]
+pub(crate) struct PluginLimiter {
+    max_memory_bytes: Option<u64>,
+    max_table_elements: Option<u32>,
+    violation: Option<LimitViolation>,
+}
+
+impl PluginLimiter {
+    pub(crate) fn new(policy: &PluginExecutionPolicy) -> Self {
+        Self {
+            max_memory_bytes: policy.max_memory_bytes,
+            max_table_elements: policy.max_table_elements,
+            violation: None,
+        }
+    }
+
+    pub(crate) fn take_violation(&mut self) -> Option<LimitViolation> {
+        self.violation.take()
+    }
+}
+
+impl ResourceLimiter for PluginLimiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> bool {
+        match self.max_memory_bytes {
+            Some(limit) if desired as u64 > limit => {
+                self.violation.get_or_insert(LimitViolation::Memory {
+                    limit,
+                    requested: desired as u64,
+                });
+                false
+            }
+            _ => true,
+        }
+    }
+
+    fn table_growing(&mut self, _current: u32, desired: u32, _maximum: Option<u32>) -> bool {
+        match self.max_table_elements {
+            Some(limit) if desired > limit => {
+                self.violation.get_or_insert(LimitViolation::Table {
+                    limit,
+                    requested: desired,
+                });
+                false
+            }
+            _ => true,
+        }
+    }
+}
+
+/// 次の呼び出し（またはインスタンス化）に fuel と epoch 期限を設定する。
+pub(crate) fn arm_call_limits<T>(
+    store: &mut Store<T>,
+    policy: &PluginExecutionPolicy,
+) -> anyhow::Result<()> {
+    let remaining = store.consume_fuel(0)?;
+    match policy.fuel {
+        Some(fuel) => {
+            store.consume_fuel(remaining)?;
+            store.add_fuel(fuel)?;
+        }
+        None if remaining < UNLIMITED_FUEL / 2 => store.add_fuel(UNLIMITED_FUEL - remaining)?,
+        None => {}
+    }
+    let deadline = policy
+        .timeout_ms
+        .map(|timeout_ms| (timeout_ms.saturating_add(EPOCH_TICK_MS - 1) / EPOCH_TICK_MS).max(1))
+        .unwrap_or(NO_EPOCH_DEADLINE);
+    store.set_epoch_deadline(deadline);
+    Ok(())
+}
+
+/// 呼び出し結果を制限違反として解釈できる場合に `PluginError` を返し、監査へ記録する。
+///
+/// `violation` は `ResourceLimiter` が拒否した拡張で、ゲストが失敗を握りつぶした
+/// 場合でも違反として扱う。
+pub(crate) fn limit_error(
+    plugin_id: &str,
+    entrypoint: &str,
+    policy: &PluginExecutionPolicy,
+    violation: Option<LimitViolation>,
+    error: Option<&anyhow::Error>,
+) -> Option<PluginError> {
+    let trap = error.and_then(|err| err.downcast_ref::<Trap>().copied());
+    let (kind, limit, requested, plugin_error) = match (violation, trap) {
+        (Some(LimitViolation::Memory { limit, requested }), _) => (
+            "memory",
+            limit,
+            Some(requested),
+            PluginError::MemoryLimitExceeded {
+                plugin_id: plugin_id.to_string(),
+                limit_bytes: limit,
+                requested_bytes: requested,
+            },
+        ),
+        (Some(LimitViolation::Table { limit, requested }), _) => (
+            "table",
+            u64::from(limit),
+            Some(u64::from(requested)),
+            PluginError::TableLimitExceeded {
+                plugin_id: plugin_id.to_string(),
+                limit_elements: limit,
+                requested_elements: requested,
+            },
+        ),
+        (None, Some(Trap::OutOfFuel)) => {
+            let fuel = policy.fuel.unwrap_or(UNLIMITED_FUEL);
+            (
+                "fuel",
+                fuel,
+                None,
+                PluginError::FuelExhausted {
+                    plugin_id: plugin_id.to_string(),
+                    fuel,
+                },
+            )
+        }
+        (None, Some(Trap::Interrupt)) => {
+            let timeout_ms = policy.timeout_ms.unwrap_or_default();
+            (
+                "timeout",
+                timeout_ms,
+                None,
+                PluginError::Timeout {
+                    plugin_id: plugin_id.to_string(),
+                    timeout_ms,
+                },
+            )
+        }
+        _ => return None,
+    };
+    record_limit_audit(plugin_id, entrypoint, kind, limit, requested);
+    Some(plugin_error)
+}
+
+/// `Engine` の epoch を一定間隔で進めるバックグラウンドスレッド。
+pub(crate) struct EpochTicker {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl EpochTicker {
+    pub(crate) fn start(engine: Engine) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let handle = thread::Builder::new()
+            .name("reml-plugin-epoch".into())
+            .spawn(move || {
+                while !flag.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(EPOCH_TICK_MS));
+                    engine.increment_epoch();
+                }
+            })
+            .ok();
+        Self { stop, handle }
+    }
+}
+
+impl Drop for EpochTicker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limiter_records_first_violation() {
+        let mut limiter = PluginLimiter::new(&PluginExecutionPolicy {
+            max_memory_bytes: Some(65_536),
+            max_table_elements: Some(4),
+            ..PluginExecutionPolicy::default()
+        });
+        assert!(limiter.memory_growing(0, 65_536, None));
+        assert!(!limiter.memory_growing(65_536, 131_072, None));
+        assert!(!limiter.table_growing(0, 8, None));
+        assert_eq!(
+            limiter.take_violation(),
+            Some(LimitViolation::Memory {
+                limit: 65_536,
+                requested: 131_072
+            })
+        );
+        assert_eq!(limiter.take_violation(), None);
+    }
+}
@@ -146,6 +146,9 @@ impl PluginRuntimeManager {
                 manifest,
                 bundle_hash: bundle.bundle_hash.as_deref(),
                 module_path: module_info.map(|info| info.module_path.as_path()),
+                module_hash: module_info.map(|info| info.module_hash.as_str()),
+                execution: module_info.map(|info| &info.execution),
+                abi: module_info.map(|info| &info.abi),
             };
             match self.bridge.load(request) {
                 Ok(instance) => {
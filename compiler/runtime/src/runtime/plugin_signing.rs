@@ -0,0 +1,337 @@
+//! プラグインバンドルの Ed25519 署名と信頼ストア。
+//!
+//! 署名は bundle.json の `signature` ブロックに置く detached 署名で、対象は
+//! [`signing_payload`] が組み立てる正規化ペイロード（バンドル ID / 版、マニフェスト
+//! 群の `bundle_hash`、各モジュールのハッシュと実行ポリシー、署名メタデータ）である。
+//! 検証には信頼ストア（[`PluginTrustStore`]）に登録された公開鍵を使い、鍵の失効と
+//! 有効期限、署名の `valid_until` も確認する。
+//!
+//! 信頼ストアは次の形の JSON ファイルで、鍵は 32 バイトの 16 進表記で持つ。
+//!
+//! ```json
+//! {
+//!   "signers": [
+//!     { "key_id": "release-2026", "public_key": "3b6a…", "expires_at": "2027-01-01T00:00:00Z" },
+//!     { "key_id": "release-2025", "public_key": "9f0c…", "revoked": true }
+//!   ]
+//! }
+//! ```
+
+use std::{fs, io, path::Path};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::runtime::plugin::{
+    bytes_to_hex, PluginAbi, PluginBundleManifest, PluginExecutionPolicy, PluginLoadError,
+    PluginSignature, SignatureAlgorithm,
+};
+
+/// 署名ペイロードの形式名。ペイロードの構成を変える場合は版を上げる。
+pub const SIGNATURE_FORMAT: &str = "reml.plugin.signature/1";
+
+/// 署名を受け付ける鍵の一覧。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginTrustStore {
+    #[serde(default)]
+    pub signers: Vec<TrustedSigner>,
+}
+
+/// 信頼ストアに登録された署名者の鍵。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrustedSigner {
+    pub key_id: String,
+    /// Ed25519 公開鍵（32 バイトの 16 進表記）。
+    pub public_key: String,
+    /// 鍵の有効期限（RFC 3339）。過ぎた鍵による署名は受け付けない。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    /// 失効済みの鍵。署名の時期に関わらず受け付けない。
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub revoked: bool,
+}
+
+impl PluginTrustStore {
+    /// JSON ファイルから読み込む。
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PluginLoadError> {
+        let path = path.as_ref();
+        let body = fs::read_to_string(path).map_err(|err| PluginLoadError::TrustStore {
+            message: format!("{}: {err}", path.display()),
+        })?;
+        Self::from_json_str(&body)
+    }
+
+    pub fn from_json_str(body: &str) -> Result<Self, PluginLoadError> {
+        serde_json::from_str(body).map_err(|err| PluginLoadError::TrustStore {
+            message: err.to_string(),
+        })
+    }
+
+    pub fn signer(&self, key_id: &str) -> Option<&TrustedSigner> {
+        self.signers.iter().find(|signer| signer.key_id == key_id)
+    }
+
+    /// 鍵を登録する。同じ `key_id` の鍵は置き換える。
+    pub fn trust(&mut self, signer: TrustedSigner) {
+        self.signers
+            .retain(|existing| existing.key_id != signer.key_id);
+        self.signers.push(signer);
+    }
+}
+
+/// オフライン署名に使う Ed25519 秘密鍵。ファイルでは 32 バイトのシードを 16 進で持つ。
+pub struct PluginSigningKey {
+    key: SigningKey,
+}
+
+impl PluginSigningKey {
+    /// OS の乱数源から新しい鍵を生成する。
+    pub fn generate() -> io::Result<Self> {
+        let mut seed = [0u8; 32];
+        getrandom::getrandom(&mut seed).map_err(|err| io::Error::other(err.to_string()))?;
+        Ok(Self::from_seed(seed))
+    }
+
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(&seed),
+        }
+    }
+
+    pub fn from_hex(text: &str) -> Result<Self, PluginLoadError> {
+        let seed = decode_hex::<32>(text.trim()).ok_or_else(|| PluginLoadError::SigningKey {
+            message: "秘密鍵は 32 バイトの 16 進表記で指定してください".to_string(),
+        })?;
+        Ok(Self::from_seed(seed))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PluginLoadError> {
+        let path = path.as_ref();
+        let body = fs::read_to_string(path).map_err(|err| PluginLoadError::SigningKey {
+            message: format!("{}: {err}", path.display()),
+        })?;
+        Self::from_hex(&body)
+    }
+
+    pub fn to_hex(&self) -> String {
+        bytes_to_hex(&self.key.to_bytes())
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        bytes_to_hex(self.key.verifying_key().as_bytes())
+    }
+
+    /// この鍵を信頼ストアへ登録するための項目。
+    pub fn trusted_signer(&self, key_id: impl Into<String>) -> TrustedSigner {
+        TrustedSigner {
+            key_id: key_id.into(),
+            public_key: self.public_key_hex(),
+            expires_at: None,
+            revoked: false,
+        }
+    }
+}
+
+impl std::fmt::Debug for PluginSigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginSigningKey")
+            .field("public_key", &self.public_key_hex())
+            .finish()
+    }
+}
+
+#[derive(Serialize)]
+struct SigningPayload<'a> {
+    format: &'static str,
+    bundle_id: &'a str,
+    bundle_version: &'a str,
+    bundle_hash: Option<&'a str>,
+    manifests: Vec<String>,
+    modules: Vec<SignedModule<'a>>,
+    algorithm: &'a str,
+    key_id: Option<&'a str>,
+    issued_to: Option<&'a str>,
+    valid_until: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct SignedModule<'a> {
+    plugin_id: &'a str,
+    module_hash: &'a str,
+    execution: &'a PluginExecutionPolicy,
+    abi: &'a PluginAbi,
+}
+
+/// `signature` が署名するバイト列。署名値そのものと `certificate` は含まない。
+pub fn signing_payload(bundle: &PluginBundleManifest, signature: &PluginSignature) -> Vec<u8> {
+    let payload = SigningPayload {
+        format: SIGNATURE_FORMAT,
+        bundle_id: &bundle.bundle_id,
+        bundle_version: &bundle.bundle_version,
+        bundle_hash: bundle.bundle_hash.as_deref(),
+        manifests: bundle
+            .manifest_paths
+            .iter()
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .collect(),
+        modules: bundle
+            .modules
+            .iter()
+            .map(|module| SignedModule {
+                plugin_id: &module.plugin_id,
+                module_hash: &module.module_hash,
+                execution: &module.execution,
+                abi: &module.abi,
+            })
+            .collect(),
+        algorithm: signature.algorithm.as_str(),
+        key_id: signature.key_id.as_deref(),
+        issued_to: signature.issued_to.as_deref(),
+        valid_until: signature.valid_until.as_deref(),
+    };
+    serde_json::to_vec(&payload).expect("signing payload is always serializable")
+}
+
+/// バンドルに対する Ed25519 署名を作る。
+pub fn sign_bundle(
+    bundle: &PluginBundleManifest,
+    key: &PluginSigningKey,
+    key_id: &str,
+    issued_to: Option<String>,
+    valid_until: Option<String>,
+) -> PluginSignature {
+    let mut signature = PluginSignature {
+        algorithm: SignatureAlgorithm::Ed25519,
+        certificate: None,
+        issued_to,
+        valid_until,
+        bundle_hash: bundle.bundle_hash.clone(),
+        key_id: Some(key_id.to_string()),
+        value: None,
+    };
+    let value = key.key.sign(&signing_payload(bundle, &signature));
+    signature.value = Some(bytes_to_hex(&value.to_bytes()));
+    signature
+}
+
+/// bundle.json の `signature` ブロックを `signature` で置き換える。
+pub fn write_bundle_signature(
+    bundle_path: impl AsRef<Path>,
+    signature: &PluginSignature,
+) -> Result<(), PluginLoadError> {
+    let bundle_path = bundle_path.as_ref();
+    let load_error = |message: String| PluginLoadError::BundleLoad { message };
+    let body = fs::read_to_string(bundle_path).map_err(|err| load_error(err.to_string()))?;
+    let mut bundle: Value =
+        serde_json::from_str(&body).map_err(|err| load_error(err.to_string()))?;
+    let object = bundle
+        .as_object_mut()
+        .ok_or_else(|| load_error("bundle はオブジェクトである必要があります".to_string()))?;
+    let mut block = Map::new();
+    block.insert("algorithm".into(), json!(signature.algorithm.as_str()));
+    for (key, value) in [
+        ("key_id", &signature.key_id),
+        ("issued_to", &signature.issued_to),
+        ("valid_until", &signature.valid_until),
+        ("bundle_hash", &signature.bundle_hash),
+        ("certificate", &signature.certificate),
+        ("value", &signature.value),
+    ] {
+        if let Some(value) = value {
+            block.insert(key.into(), json!(value));
+        }
+    }
+    object.insert("signature".into(), Value::Object(block));
+    let mut body =
+        serde_json::to_string_pretty(&bundle).map_err(|err| load_error(err.to_string()))?;
+    body.push('\n');
+    fs::write(bundle_path, body).map_err(|err| load_error(err.to_string()))
+}
+
+/// 署名値を信頼ストアの鍵で検証する。失敗時は理由を返す。
+pub(crate) fn verify_signature_value(
+    bundle: &PluginBundleManifest,
+    signature: &PluginSignature,
+    trust_store: &PluginTrustStore,
+    now: OffsetDateTime,
+) -> Result<(), String> {
+    if !matches!(signature.algorithm, SignatureAlgorithm::Ed25519) {
+        return Err(format!(
+            "署名アルゴリズム {} は検証できません",
+            signature.algorithm.as_str()
+        ));
+    }
+    let key_id = signature
+        .key_id
+        .as_deref()
+        .ok_or_else(|| "署名に key_id がありません".to_string())?;
+    let value = signature
+        .value
+        .as_deref()
+        .ok_or_else(|| "署名値がありません".to_string())?;
+    let signer = trust_store
+        .signer(key_id)
+        .ok_or_else(|| format!("鍵 {key_id} は信頼ストアに登録されていません"))?;
+    if signer.revoked {
+        return Err(format!("鍵 {key_id} は失効しています"));
+    }
+    if let Some(expires_at) = signer.expires_at.as_deref() {
+        if now > parse_timestamp("expires_at", expires_at)? {
+            return Err(format!(
+                "鍵 {key_id} の有効期限 {expires_at} を過ぎています"
+            ));
+        }
+    }
+    if let Some(valid_until) = signature.valid_until.as_deref() {
+        if now > parse_timestamp("valid_until", valid_until)? {
+            return Err(format!("署名の有効期限 {valid_until} を過ぎています"));
+        }
+    }
+    let public_key = decode_hex::<32>(&signer.public_key)
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| format!("鍵 {key_id} の公開鍵が不正です"))?;
+    let value = decode_hex::<64>(value)
+        .map(|bytes| Signature::from_bytes(&bytes))
+        .ok_or_else(|| "署名値が 64 バイトの 16 進表記ではありません".to_string())?;
+    public_key
+        .verify_strict(&signing_payload(bundle, signature), &value)
+        .map_err(|_| format!("鍵 {key_id} による署名が一致しません"))
+}
+
+fn parse_timestamp(field: &str, value: &str) -> Result<OffsetDateTime, String> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|_| format!("{field} を RFC 3339 の時刻として解釈できません: {value}"))
+}
+
+fn decode_hex<const N: usize>(text: &str) -> Option<[u8; N]> {
+    let digits = text.as_bytes();
+    if digits.len() != N * 2 {
+        return None;
+    }
+    let mut out = [0u8; N];
+    for (index, pair) in digits.chunks(2).enumerate() {
+        let high = (pair[0] as char).to_digit(16)?;
+        let low = (pair[1] as char).to_digit(16)?;
+        out[index] = (high * 16 + low) as u8;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signing_key_round_trips_through_hex() {
+        let key = PluginSigningKey::from_seed([7; 32]);
+        let restored = PluginSigningKey::from_hex(&key.to_hex()).expect("hex seed");
+        assert_eq!(restored.public_key_hex(), key.public_key_hex());
+        assert!(PluginSigningKey::from_hex("zz").is_err());
+        assert_eq!(decode_hex::<2>("0aff"), Some([0x0a, 0xff]));
+        assert_eq!(decode_hex::<2>("0afg"), None);
+    }
+}
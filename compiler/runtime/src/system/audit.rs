@@ -1,6 +1,10 @@
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
 use serde_json::{Map as JsonMap, Number, Value};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
-use crate::audit::AuditEnvelope;
+use crate::audit::{AuditEnvelope, AuditEvent};
 use crate::runtime::Signal;
 
 use super::process::{Command, ExitStatus, ProcessId};
@@ -9,6 +13,8 @@ use super::signal::SignalDetail;
 const SYSTEM_EVENT_DOMAIN: &str = "core.system";
 const RAW_CODE_MASKED: &str = "masked";
 
+static SYSTEM_AUDIT_EVENTS: Lazy<Mutex<Vec<AuditEvent>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProcessAuditEvent {
     Spawn,
@@ -62,6 +68,32 @@ pub fn insert_process_audit_metadata(envelope: &mut AuditEnvelope, info: &Proces
     }
 }
 
+/// プロセス操作の監査イベントを記録する。
+pub(crate) fn record_process_audit(info: &ProcessAuditInfo<'_>) {
+    let mut envelope = AuditEnvelope::new();
+    envelope.capability = Some("core.process".into());
+    insert_process_audit_metadata(&mut envelope, info);
+    push_system_audit_event(envelope);
+}
+
+/// 記録済みの Core.System 監査イベントを取得してクリアする。
+pub fn take_system_audit_events() -> Vec<AuditEvent> {
+    let mut events = SYSTEM_AUDIT_EVENTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    std::mem::take(&mut *events)
+}
+
+fn push_system_audit_event(envelope: AuditEnvelope) {
+    let timestamp = OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string());
+    SYSTEM_AUDIT_EVENTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(AuditEvent::new(timestamp, envelope));
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SignalAuditEvent {
     Send,
@@ -135,6 +167,14 @@ pub fn insert_signal_audit_metadata(envelope: &mut AuditEnvelope, info: &SignalA
     }
 }
 
+/// シグナル操作の監査イベントを記録する。
+pub(crate) fn record_signal_audit(info: &SignalAuditInfo<'_>) {
+    let mut envelope = AuditEnvelope::new();
+    envelope.capability = Some("core.signal".into());
+    insert_signal_audit_metadata(&mut envelope, info);
+    push_system_audit_event(envelope);
+}
+
 fn format_command(command: &Command) -> String {
     let mut parts = Vec::with_capacity(1 + command.args.len());
     parts.push(command.program.to_string_lossy().to_string());
@@ -1,5 +1,17 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path as StdPath, PathBuf as StdPathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::process::{
+    ensure_process_capability, io_error, ProcessError, ProcessErrorKind, ProcessId, ProcessResult,
+};
 use crate::path::PathBuf;
-use super::process::{ProcessError, ProcessErrorKind, ProcessId, ProcessResult};
+
+const EFFECTS_DAEMON: &[&str] = &["process", "security"];
+
+/// 同じプロセス内で並行に書き込んでも一時ファイルが衝突しないようにする連番。
+static TEMP_SEQUENCE: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DaemonConfig {
@@ -7,22 +19,451 @@ pub struct DaemonConfig {
     pub pid_file: Option<PathBuf>,
     pub user: Option<String>,
     pub group: Option<String>,
+    /// デーモンプロセスに設定する umask。
+    pub umask: u32,
+    /// デーモンプロセスの作業ディレクトリ。
+    pub cwd: PathBuf,
+    /// stdout / stderr の出力先（追記）。`None` は `/dev/null`。stdin は常に `/dev/null`。
+    pub stdout: Option<PathBuf>,
+    pub stderr: Option<PathBuf>,
 }
 
-pub fn daemonize(_config: DaemonConfig) -> ProcessResult<()> {
-    Err(ProcessError::new(
-        ProcessErrorKind::Unsupported,
-        "daemonize is not wired in this runtime",
-    )
-    .with_context("core.system.daemon.daemonize"))
+impl DaemonConfig {
+    /// 既定値は umask `0o022`、作業ディレクトリ `/`、標準入出力はすべて `/dev/null`。
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            pid_file: None,
+            user: None,
+            group: None,
+            umask: 0o022,
+            cwd: PathBuf::from_std(StdPathBuf::from("/")),
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    pub fn with_pid_file(mut self, path: PathBuf) -> Self {
+        self.pid_file = Some(path);
+        self
+    }
+
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    pub fn with_umask(mut self, umask: u32) -> Self {
+        self.umask = umask;
+        self
+    }
+
+    pub fn with_cwd(mut self, cwd: PathBuf) -> Self {
+        self.cwd = cwd;
+        self
+    }
+
+    pub fn with_stdout(mut self, path: PathBuf) -> Self {
+        self.stdout = Some(path);
+        self
+    }
+
+    pub fn with_stderr(mut self, path: PathBuf) -> Self {
+        self.stderr = Some(path);
+        self
+    }
+}
+
+/// 二重 fork でデーモン化する。戻るのはデーモンプロセスだけで、呼び出し元のプロセスは
+/// デーモンの準備完了を待って終了コード 0 で終了する。
+///
+/// デーモン側の準備（セッション作成、umask、作業ディレクトリ、PID ファイル、権限の降格、
+/// 標準入出力の付け替え）に失敗した場合は、呼び出し元のプロセスが `Err` を受け取る。
+pub fn daemonize(config: DaemonConfig) -> ProcessResult<()> {
+    ensure_process_capability(EFFECTS_DAEMON)?;
+    #[cfg(unix)]
+    {
+        unix::daemonize(&config)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = config;
+        Err(ProcessError::new(
+            ProcessErrorKind::Unsupported,
+            "daemonize is only supported on Unix",
+        )
+        .with_context("core.system.daemon.daemonize"))
+    }
+}
+
+/// PID ファイルを原子的に作成する。
+///
+/// 既存のファイルが稼働中の別プロセスを指す場合は `AlreadyRunning`。終了済みのプロセスや
+/// 読み取れない内容を指す古いファイルは置き換える。判定と置き換えは同じディレクトリの
+/// `.<ファイル名>.lock` への排他ロック下で行う（ロックファイルは削除しない）。
+pub fn write_pid_file(path: PathBuf, pid: ProcessId) -> ProcessResult<()> {
+    let context = "core.system.daemon.write_pid_file";
+    let path = path.into_std();
+    let file_name = path.file_name().ok_or_else(|| {
+        ProcessError::new(
+            ProcessErrorKind::SpawnFailed,
+            format!("pid file path {} has no file name", path.display()),
+        )
+        .with_context(context)
+    })?;
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => StdPath::new("."),
+    };
+    let file_name = file_name.to_string_lossy();
+    let temp = dir.join(format!(
+        ".{file_name}.{}.{}.tmp",
+        std::process::id(),
+        TEMP_SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    ));
+    let lock = dir.join(format!(".{file_name}.lock"));
+    let result = write_temp_pid_file(&temp, pid)
+        .and_then(|()| lock_pid_file(&lock))
+        .map_err(|err| io_error(err, context))
+        .and_then(|_lock| claim_pid_file(&temp, &path, pid, context));
+    let _ = fs::remove_file(&temp);
+    result
+}
+
+fn write_temp_pid_file(temp: &StdPath, pid: ProcessId) -> io::Result<()> {
+    let mut file = fs::File::create(temp)?;
+    writeln!(file, "{pid}")?;
+    file.sync_all()
+}
+
+/// PID ファイルの判定と置き換えを直列化するロックを取る。返したファイルを閉じると解放される。
+#[cfg(unix)]
+fn lock_pid_file(lock: &StdPath) -> io::Result<fs::File> {
+    use std::os::unix::io::AsRawFd;
+
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(lock)?;
+    loop {
+        // SAFETY: file は開いたままのファイル記述子を持つ。
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+            return Ok(file);
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/// ファイルロックの手段がない環境ではロックファイルを作るだけにする。既存の PID ファイルは
+/// 稼働中として扱われ、新規作成はハードリンクで行うため、競合しても二重には取得されない。
+#[cfg(not(unix))]
+fn lock_pid_file(lock: &StdPath) -> io::Result<fs::File> {
+    fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(lock)
 }
 
-pub fn write_pid_file(_path: PathBuf, _pid: ProcessId) -> ProcessResult<()> {
-    Err(ProcessError::new(
-        ProcessErrorKind::Unsupported,
-        "write_pid_file is not wired in this runtime",
+/// ロック下で呼ぶ。書き込み済みの一時ファイルを配置し、中身が揃った PID ファイルだけが見えるようにする。
+/// 新規作成はハードリンク、古いファイルの置き換えは rename で行う。
+fn claim_pid_file(
+    temp: &StdPath,
+    path: &StdPath,
+    pid: ProcessId,
+    context: &str,
+) -> ProcessResult<()> {
+    if !path.exists() {
+        match fs::hard_link(temp, path) {
+            Ok(()) => return Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(io_error(err, context)),
+        }
+    }
+    if let Some(holder) = read_pid_file(path) {
+        if holder != pid && process_alive(holder) {
+            return Err(already_running(path, holder, context));
+        }
+    }
+    fs::rename(temp, path).map_err(|err| io_error(err, context))
+}
+
+fn read_pid_file(path: &StdPath) -> Option<ProcessId> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn already_running(path: &StdPath, holder: ProcessId, context: &str) -> ProcessError {
+    ProcessError::new(
+        ProcessErrorKind::AlreadyRunning,
+        format!(
+            "pid file {} is held by running process {holder}",
+            path.display()
+        ),
     )
-    .with_context("core.system.daemon.write_pid_file"))
+    .with_context(context)
+}
+
+#[cfg(unix)]
+fn process_alive(pid: ProcessId) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // SAFETY: シグナル 0 は存在確認だけを行い、対象には何も届かない。
+    let alive = unsafe { libc::kill(pid, 0) } == 0;
+    alive || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
 }
 
-// TODO: Core.System.Daemon の拡張は Phase 5 で追加する。
+/// 生存確認の手段がない環境では、既存の PID ファイルを稼働中として扱う。
+#[cfg(not(unix))]
+fn process_alive(_pid: ProcessId) -> bool {
+    true
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::ffi::{CStr, CString};
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Read, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+
+    use libc::{c_char, c_int, gid_t, uid_t};
+
+    use super::{write_pid_file, DaemonConfig};
+    use crate::system::process::{
+        io_error, ProcessError, ProcessErrorKind, ProcessId, ProcessResult,
+    };
+
+    const CONTEXT: &str = "core.system.daemon.daemonize";
+    /// 準備完了を呼び出し元へ伝える 1 バイト。失敗時は `READY_FAILED` に続けてメッセージを送る。
+    const READY_OK: u8 = 0;
+    const READY_FAILED: u8 = 1;
+
+    /// fork 前に解決した降格先の uid / gid。
+    struct Credentials {
+        uid: Option<uid_t>,
+        gid: Option<gid_t>,
+    }
+
+    pub(super) fn daemonize(config: &DaemonConfig) -> ProcessResult<()> {
+        // 失敗を呼び出し元へそのまま返せるよう、開けるものは fork 前に開いておく。
+        let devnull = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/null")
+            .map_err(|err| io_error(err, CONTEXT))?;
+        let stdout = open_output(config.stdout.as_ref(), &devnull)?;
+        let stderr = open_output(config.stderr.as_ref(), &devnull)?;
+        let cwd = CString::new(config.cwd.as_std_path().as_os_str().as_bytes())
+            .map_err(|err| invalid(format!("daemon cwd is not a valid path: {err}")))?;
+        let credentials = resolve_credentials(config)?;
+        let (mut ready_reader, ready_writer) = ready_pipe()?;
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+
+        // SAFETY: fork 後の子プロセスはこの関数の残りを実行するだけで、親は待機して終了する。
+        match unsafe { libc::fork() } {
+            -1 => return Err(io_error(io::Error::last_os_error(), CONTEXT)),
+            0 => {}
+            child => {
+                drop(ready_writer);
+                let mut status = Vec::new();
+                let _ = ready_reader.read_to_end(&mut status);
+                // SAFETY: 直前に fork した子プロセスを回収する。
+                unsafe { libc::waitpid(child, std::ptr::null_mut(), 0) };
+                return match status.split_first() {
+                    Some((&READY_OK, _)) => {
+                        // SAFETY: デーモンの準備が完了したので呼び出し元のプロセスを終える。
+                        unsafe { libc::_exit(0) }
+                    }
+                    Some((_, message)) => Err(ProcessError::new(
+                        ProcessErrorKind::SpawnFailed,
+                        format!(
+                            "daemon {} failed to start: {}",
+                            config.name,
+                            String::from_utf8_lossy(message)
+                        ),
+                    )
+                    .with_context(CONTEXT)),
+                    None => Err(ProcessError::new(
+                        ProcessErrorKind::SpawnFailed,
+                        format!("daemon {} exited before it was ready", config.name),
+                    )
+                    .with_context(CONTEXT)),
+                };
+            }
+        }
+        drop(ready_reader);
+        let mut ready_writer = ready_writer;
+        match prepare_daemon(config, &cwd, &credentials, &devnull, &stdout, &stderr) {
+            Ok(()) => {
+                let _ = ready_writer.write_all(&[READY_OK]);
+                Ok(())
+            }
+            Err(message) => {
+                let mut report = vec![READY_FAILED];
+                report.extend_from_slice(message.as_bytes());
+                let _ = ready_writer.write_all(&report);
+                drop(ready_writer);
+                // SAFETY: 呼び出し元へ失敗を伝えたので、中間・デーモン側のプロセスを終える。
+                unsafe { libc::_exit(1) }
+            }
+        }
+    }
+
+    /// 1 回目の fork 後に実行する。成功時はデーモンとなる孫プロセスでだけ `Ok` を返す。
+    fn prepare_daemon(
+        config: &DaemonConfig,
+        cwd: &CStr,
+        credentials: &Credentials,
+        devnull: &File,
+        stdout: &File,
+        stderr: &File,
+    ) -> Result<(), String> {
+        // SAFETY: 以下の libc 呼び出しは検証済みの引数だけを受け取る。
+        unsafe {
+            if libc::setsid() == -1 {
+                return Err(format!("setsid: {}", io::Error::last_os_error()));
+            }
+            match libc::fork() {
+                -1 => return Err(format!("fork: {}", io::Error::last_os_error())),
+                0 => {}
+                _ => libc::_exit(0),
+            }
+            libc::umask(config.umask as libc::mode_t);
+            if libc::chdir(cwd.as_ptr()) == -1 {
+                return Err(format!(
+                    "chdir {}: {}",
+                    cwd.to_string_lossy(),
+                    io::Error::last_os_error()
+                ));
+            }
+        }
+        if let Some(pid_file) = config.pid_file.clone() {
+            let pid = ProcessId::from(std::process::id());
+            write_pid_file(pid_file, pid).map_err(|err| err.message)?;
+        }
+        // SAFETY: 同上。gid を先に変更し、uid の降格後に権限が戻らないようにする。
+        unsafe {
+            if let Some(gid) = credentials.gid {
+                if libc::setgroups(1, &gid) == -1 || libc::setgid(gid) == -1 {
+                    return Err(format!("setgid {gid}: {}", io::Error::last_os_error()));
+                }
+            }
+            if let Some(uid) = credentials.uid {
+                if libc::setuid(uid) == -1 {
+                    return Err(format!("setuid {uid}: {}", io::Error::last_os_error()));
+                }
+            }
+        }
+        for (file, target) in [(devnull, 0), (stdout, 1), (stderr, 2)] {
+            // SAFETY: file は有効なファイル記述子を持ち、target は標準入出力の番号。
+            if unsafe { libc::dup2(file.as_raw_fd(), target) } == -1 {
+                return Err(format!("dup2: {}", io::Error::last_os_error()));
+            }
+        }
+        Ok(())
+    }
+
+    fn open_output(path: Option<&crate::path::PathBuf>, devnull: &File) -> ProcessResult<File> {
+        match path {
+            Some(path) => OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path.as_std_path()),
+            None => devnull.try_clone(),
+        }
+        .map_err(|err| io_error(err, CONTEXT))
+    }
+
+    fn ready_pipe() -> ProcessResult<(File, File)> {
+        let mut fds = [0 as c_int; 2];
+        // SAFETY: fds は 2 要素の配列で、pipe が両端を書き込む。
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(io_error(io::Error::last_os_error(), CONTEXT));
+        }
+        for fd in fds {
+            // SAFETY: fd は直前に作成したパイプの端。
+            unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+        }
+        // SAFETY: 作成したばかりの記述子の所有権を File へ移す。
+        Ok(unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) })
+    }
+
+    fn resolve_credentials(config: &DaemonConfig) -> ProcessResult<Credentials> {
+        let user = config.user.as_deref().map(lookup_user).transpose()?;
+        let group = config.group.as_deref().map(lookup_group).transpose()?;
+        Ok(Credentials {
+            uid: user.map(|(uid, _)| uid),
+            gid: group.or(user.map(|(_, gid)| gid)),
+        })
+    }
+
+    fn lookup_user(name: &str) -> ProcessResult<(uid_t, gid_t)> {
+        let c_name =
+            CString::new(name).map_err(|_| invalid(format!("invalid user name {name:?}")))?;
+        let mut buffer = vec![0 as c_char; 1024];
+        loop {
+            // SAFETY: passwd と buffer は getpwnam_r の呼び出し中だけ参照される。
+            let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+            let mut found = std::ptr::null_mut();
+            let code = unsafe {
+                libc::getpwnam_r(
+                    c_name.as_ptr(),
+                    &mut passwd,
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                    &mut found,
+                )
+            };
+            match code {
+                0 if found.is_null() => return Err(invalid(format!("unknown user {name}"))),
+                0 => return Ok((passwd.pw_uid, passwd.pw_gid)),
+                libc::ERANGE => buffer.resize(buffer.len() * 2, 0),
+                code => return Err(io_error(io::Error::from_raw_os_error(code), CONTEXT)),
+            }
+        }
+    }
+
+    fn lookup_group(name: &str) -> ProcessResult<gid_t> {
+        let c_name =
+            CString::new(name).map_err(|_| invalid(format!("invalid group name {name:?}")))?;
+        let mut buffer = vec![0 as c_char; 1024];
+        loop {
+            // SAFETY: group と buffer は getgrnam_r の呼び出し中だけ参照される。
+            let mut group: libc::group = unsafe { std::mem::zeroed() };
+            let mut found = std::ptr::null_mut();
+            let code = unsafe {
+                libc::getgrnam_r(
+                    c_name.as_ptr(),
+                    &mut group,
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                    &mut found,
+                )
+            };
+            match code {
+                0 if found.is_null() => return Err(invalid(format!("unknown group {name}"))),
+                0 => return Ok(group.gr_gid),
+                libc::ERANGE => buffer.resize(buffer.len() * 2, 0),
+                code => return Err(io_error(io::Error::from_raw_os_error(code), CONTEXT)),
+            }
+        }
+    }
+
+    fn invalid(message: String) -> ProcessError {
+        ProcessError::new(ProcessErrorKind::SpawnFailed, message).with_context(CONTEXT)
+    }
+}
@@ -1,14 +1,23 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::{self, Child, ChildStderr, ChildStdin, ChildStdout, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration as StdDuration, Instant};
 
 use crate::path::PathBuf;
 use crate::prelude::ensure::{DiagnosticSeverity, GuardDiagnostic, IntoDiagnostic};
 use crate::runtime::api::guard_capability;
 use crate::runtime::Signal;
 use crate::stage::{StageId, StageRequirement};
+use once_cell::sync::Lazy;
 use serde_json::{Map as JsonMap, Value};
 
+use super::audit::{record_process_audit, ProcessAuditEvent, ProcessAuditInfo};
+
 #[cfg(any(feature = "core_time", feature = "metrics"))]
 use crate::time::{Duration, Timestamp};
 #[cfg(not(any(feature = "core_time", feature = "metrics")))]
@@ -19,6 +28,16 @@ const EFFECTS_PROCESS: &[&str] = &["process"];
 const EFFECTS_PROCESS_BLOCKING: &[&str] = &["process", "io.blocking"];
 const EFFECTS_PROCESS_SIGNAL: &[&str] = &["process", "signal"];
 
+/// `wait` が終了を確認する間隔の下限と上限。
+const WAIT_POLL_MIN: StdDuration = StdDuration::from_millis(1);
+const WAIT_POLL_MAX: StdDuration = StdDuration::from_millis(50);
+
+/// `spawn` した子プロセス。`wait` で終了を回収するか `detach` で手放すまで保持し、
+/// 回収前の pid だけを `kill` の対象にする（回収後に再利用された pid へ送らないため）。
+/// どちらも呼ばずに終了した子プロセスはゾンビとして残る。
+static CHILDREN: Lazy<Mutex<HashMap<ProcessId, SpawnedChild>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 pub type ProcessId = i64;
 pub type ExitStatus = i64;
 pub type ProcessResult<T> = Result<T, ProcessError>;
@@ -40,25 +59,55 @@ impl Command {
             env: None,
         }
     }
+
+    pub fn with_arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_cwd(mut self, cwd: PathBuf) -> Self {
+        self.cwd = Some(cwd);
+        self
+    }
+
+    /// 親プロセスの環境変数に重ねる値を追加する。
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env
+            .get_or_insert_with(BTreeMap::new)
+            .insert(key.into(), value.into());
+        self
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct SpawnOptions {
-    pub stdin: Option<PathBuf>,
-    pub stdout: Option<PathBuf>,
-    pub stderr: Option<PathBuf>,
-    pub detach: bool,
+/// 子プロセスの標準入出力の接続先。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StdioMode {
+    /// 親プロセスのものを引き継ぐ。
+    #[default]
+    Inherit,
+    Null,
+    /// パイプで接続し、`take_stdin` / `take_stdout` / `take_stderr` で取り出す。
+    Pipe,
+    /// ファイルへ接続する。stdin は読み込み、stdout / stderr は作成（既存なら切り詰め）する。
+    File(PathBuf),
 }
 
-impl Default for SpawnOptions {
-    fn default() -> Self {
-        Self {
-            stdin: None,
-            stdout: None,
-            stderr: None,
-            detach: false,
-        }
-    }
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpawnOptions {
+    pub stdin: StdioMode,
+    pub stdout: StdioMode,
+    pub stderr: StdioMode,
+    /// 子プロセスを新しいプロセスグループで起動し、端末からのシグナルを受けないようにする。
+    pub detach: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -73,6 +122,10 @@ pub enum ProcessErrorKind {
     PermissionDenied,
     TimedOut,
     TerminatedBySignal,
+    /// このランタイムが起動していない、回収済み、またはパイプを持たないプロセス。
+    InvalidHandle,
+    /// PID ファイルが稼働中の別プロセスに保持されている。
+    AlreadyRunning,
     Unsupported,
 }
 
@@ -148,34 +201,330 @@ impl IntoDiagnostic for ProcessError {
     }
 }
 
-pub fn spawn(_command: Command, _options: SpawnOptions) -> ProcessResult<ProcessHandle> {
+/// 子プロセスを起動する。`command.env` は親プロセスの環境変数に重ねて適用する。
+/// 終了を待たない場合は `detach` で手放し、ランタイムに回収させること。
+pub fn spawn(command: Command, options: SpawnOptions) -> ProcessResult<ProcessHandle> {
     ensure_process_capability(EFFECTS_PROCESS)?;
-    Err(ProcessError::new(
-        ProcessErrorKind::Unsupported,
-        "process spawn is not wired in this runtime",
-    )
-    .with_context("core.system.process.spawn"))
+    let context = "core.system.process.spawn";
+    let mut builder = process::Command::new(command.program.as_std_path());
+    builder.args(&command.args);
+    if let Some(cwd) = command.cwd.as_ref() {
+        builder.current_dir(cwd.as_std_path());
+    }
+    if let Some(env) = command.env.as_ref() {
+        builder.envs(env);
+    }
+    builder
+        .stdin(open_stdio(&options.stdin, false).map_err(|err| io_error(err, context))?)
+        .stdout(open_stdio(&options.stdout, true).map_err(|err| io_error(err, context))?)
+        .stderr(open_stdio(&options.stderr, true).map_err(|err| io_error(err, context))?);
+    #[cfg(unix)]
+    if options.detach {
+        use std::os::unix::process::CommandExt;
+        builder.process_group(0);
+    }
+
+    let child = builder.spawn().map_err(|err| io_error(err, context))?;
+    let handle = ProcessHandle {
+        pid: ProcessId::from(child.id()),
+        started_at: now_timestamp(),
+    };
+    record_process_audit(&ProcessAuditInfo {
+        event: ProcessAuditEvent::Spawn,
+        pid: Some(handle.pid),
+        command: Some(&command),
+        exit_status: None,
+        signal: None,
+    });
+    lock_children().insert(handle.pid, SpawnedChild { child, command });
+    Ok(handle)
 }
 
-pub fn wait(_handle: ProcessHandle, _timeout: Option<Duration>) -> ProcessResult<ExitStatus> {
+/// 子プロセスの終了を待つ。`timeout` を過ぎた場合は `TimedOut` を返し、プロセスは
+/// 引き続き `wait` / `kill` できる。シグナルで終了した場合は `TerminatedBySignal`。
+///
+/// 取り出していない stdin のパイプは待機前に閉じる。stdout / stderr をパイプにした
+/// 場合は、子プロセスがパイプの詰まりで止まらないよう先に読み出すこと。
+pub fn wait(handle: ProcessHandle, timeout: Option<Duration>) -> ProcessResult<ExitStatus> {
     ensure_process_capability(EFFECTS_PROCESS_BLOCKING)?;
-    Err(ProcessError::new(
-        ProcessErrorKind::Unsupported,
-        "process wait is not wired in this runtime",
-    )
-    .with_context("core.system.process.wait"))
+    let context = "core.system.process.wait";
+    let deadline = timeout.map(|timeout| Instant::now() + std_duration(timeout));
+    let mut interval = WAIT_POLL_MIN;
+    loop {
+        {
+            let mut children = lock_children();
+            let spawned = children
+                .get_mut(&handle.pid)
+                .ok_or_else(|| unknown_process(handle.pid, context))?;
+            drop(spawned.child.stdin.take());
+            if let Some(status) = spawned
+                .child
+                .try_wait()
+                .map_err(|err| io_error(err, context))?
+            {
+                let spawned = children.remove(&handle.pid).expect("child is present");
+                return exit_status(handle.pid, &spawned.command, status);
+            }
+        }
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(ProcessError::new(
+                    ProcessErrorKind::TimedOut,
+                    format!("process {} did not exit before the timeout", handle.pid),
+                )
+                .with_context(context));
+            }
+            interval = interval.min(remaining);
+        }
+        thread::sleep(interval);
+        interval = (interval * 2).min(WAIT_POLL_MAX);
+    }
 }
 
-pub fn kill(_handle: ProcessHandle, _signal: Signal) -> ProcessResult<()> {
+/// 子プロセスへシグナルを送る。対象は `spawn` で起動し、まだ回収していないプロセスに限る。
+pub fn kill(handle: ProcessHandle, signal: Signal) -> ProcessResult<()> {
     ensure_process_capability(EFFECTS_PROCESS_SIGNAL)?;
-    Err(ProcessError::new(
-        ProcessErrorKind::Unsupported,
-        "process kill is not wired in this runtime",
+    let context = "core.system.process.kill";
+    let mut children = lock_children();
+    let spawned = children
+        .get_mut(&handle.pid)
+        .ok_or_else(|| unknown_process(handle.pid, context))?;
+    send_signal(&mut spawned.child, signal).map_err(|err| match err.raw_os_error() {
+        #[cfg(unix)]
+        Some(libc::EINVAL) => ProcessError::new(
+            ProcessErrorKind::Unsupported,
+            format!("signal {signal} is not supported"),
+        )
+        .with_context(context),
+        _ => io_error(err, context),
+    })?;
+    record_process_audit(&ProcessAuditInfo {
+        event: ProcessAuditEvent::Kill,
+        pid: Some(handle.pid),
+        command: Some(&spawned.command),
+        exit_status: None,
+        signal: Some(signal),
+    });
+    Ok(())
+}
+
+/// 子プロセスを手放し、終了したらバックグラウンドで回収する。以後このハンドルは
+/// `wait` / `kill` できない。`SpawnOptions::detach` と異なりプロセスグループは変えない。
+pub fn detach(handle: ProcessHandle) -> ProcessResult<()> {
+    ensure_process_capability(EFFECTS_PROCESS)?;
+    let context = "core.system.process.detach";
+    let mut spawned = lock_children()
+        .remove(&handle.pid)
+        .ok_or_else(|| unknown_process(handle.pid, context))?;
+    drop(spawned.child.stdin.take());
+    thread::Builder::new()
+        .name(format!("reml-reap-{}", handle.pid))
+        .spawn(move || {
+            let _ = spawned.child.wait();
+        })
+        .map(|_| ())
+        .map_err(|err| io_error(err, context))
+}
+
+/// `StdioMode::Pipe` で起動した子プロセスの stdin を取り出す。閉じると子プロセスに EOF が届く。
+pub fn take_stdin(handle: &ProcessHandle) -> ProcessResult<PipeWriter> {
+    take_pipe(handle, "stdin", |child| child.stdin.take()).map(|inner| PipeWriter { inner })
+}
+
+/// `StdioMode::Pipe` で起動した子プロセスの stdout を取り出す。
+pub fn take_stdout(handle: &ProcessHandle) -> ProcessResult<PipeReader> {
+    take_pipe(handle, "stdout", |child| child.stdout.take()).map(|stdout| PipeReader {
+        inner: PipeSource::Stdout(stdout),
+    })
+}
+
+/// `StdioMode::Pipe` で起動した子プロセスの stderr を取り出す。
+pub fn take_stderr(handle: &ProcessHandle) -> ProcessResult<PipeReader> {
+    take_pipe(handle, "stderr", |child| child.stderr.take()).map(|stderr| PipeReader {
+        inner: PipeSource::Stderr(stderr),
+    })
+}
+
+/// 子プロセスの stdout / stderr を読むパイプ。`std::io::Read` を実装するため
+/// [`crate::io::Reader`] としてそのまま使える。
+#[derive(Debug)]
+pub struct PipeReader {
+    inner: PipeSource,
+}
+
+#[derive(Debug)]
+enum PipeSource {
+    Stdout(ChildStdout),
+    Stderr(ChildStderr),
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            PipeSource::Stdout(stdout) => stdout.read(buf),
+            PipeSource::Stderr(stderr) => stderr.read(buf),
+        }
+    }
+}
+
+/// 子プロセスの stdin へ書くパイプ。[`crate::io::Writer`] としても使える。
+#[derive(Debug)]
+pub struct PipeWriter {
+    inner: ChildStdin,
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+struct SpawnedChild {
+    child: Child,
+    command: Command,
+}
+
+fn lock_children() -> std::sync::MutexGuard<'static, HashMap<ProcessId, SpawnedChild>> {
+    CHILDREN
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn take_pipe<T>(
+    handle: &ProcessHandle,
+    stream: &str,
+    take: impl FnOnce(&mut Child) -> Option<T>,
+) -> ProcessResult<T> {
+    ensure_process_capability(EFFECTS_PROCESS)?;
+    let context = "core.system.process.pipe";
+    let mut children = lock_children();
+    let spawned = children
+        .get_mut(&handle.pid)
+        .ok_or_else(|| unknown_process(handle.pid, context))?;
+    take(&mut spawned.child).ok_or_else(|| {
+        ProcessError::new(
+            ProcessErrorKind::InvalidHandle,
+            format!(
+                "{stream} of process {} is not piped or was already taken",
+                handle.pid
+            ),
+        )
+        .with_context(context)
+    })
+}
+
+fn open_stdio(mode: &StdioMode, write: bool) -> io::Result<Stdio> {
+    Ok(match mode {
+        StdioMode::Inherit => Stdio::inherit(),
+        StdioMode::Null => Stdio::null(),
+        StdioMode::Pipe => Stdio::piped(),
+        StdioMode::File(path) if write => Stdio::from(fs::File::create(path.as_std_path())?),
+        StdioMode::File(path) => Stdio::from(fs::File::open(path.as_std_path())?),
+    })
+}
+
+fn exit_status(
+    pid: ProcessId,
+    command: &Command,
+    status: process::ExitStatus,
+) -> ProcessResult<ExitStatus> {
+    #[cfg(unix)]
+    let signal = {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal().map(Signal::from)
+    };
+    #[cfg(not(unix))]
+    let signal: Option<Signal> = None;
+    let code = status.code().map(ExitStatus::from);
+    record_process_audit(&ProcessAuditInfo {
+        event: ProcessAuditEvent::Wait,
+        pid: Some(pid),
+        command: Some(command),
+        exit_status: code,
+        signal,
+    });
+    match (code, signal) {
+        (Some(code), _) => Ok(code),
+        (None, Some(signal)) => Err(ProcessError::new(
+            ProcessErrorKind::TerminatedBySignal,
+            format!("process {pid} was terminated by signal {signal}"),
+        )
+        .with_context("core.system.process.wait")),
+        (None, None) => Err(ProcessError::new(
+            ProcessErrorKind::Unsupported,
+            format!("process {pid} exited without a status code"),
+        )
+        .with_context("core.system.process.wait")),
+    }
+}
+
+#[cfg(unix)]
+fn send_signal(child: &mut Child, signal: Signal) -> io::Result<()> {
+    let signal =
+        libc::c_int::try_from(signal).map_err(|_| io::Error::from_raw_os_error(libc::EINVAL))?;
+    // SAFETY: pid は回収前の子プロセスのもので、CHILDREN のロック中は再利用されない。
+    if unsafe { libc::kill(child.id() as libc::pid_t, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Unix 以外では強制終了（SIGKILL / SIGTERM 相当）だけを扱う。
+#[cfg(not(unix))]
+fn send_signal(child: &mut Child, signal: Signal) -> io::Result<()> {
+    match signal {
+        9 | 15 => child.kill(),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("signal {signal} is not supported on this platform"),
+        )),
+    }
+}
+
+pub(super) fn io_error(err: io::Error, context: &str) -> ProcessError {
+    let kind = match err.kind() {
+        io::ErrorKind::PermissionDenied => ProcessErrorKind::PermissionDenied,
+        io::ErrorKind::Unsupported => ProcessErrorKind::Unsupported,
+        _ => ProcessErrorKind::SpawnFailed,
+    };
+    ProcessError::new(kind, err.to_string()).with_context(context)
+}
+
+fn unknown_process(pid: ProcessId, context: &str) -> ProcessError {
+    ProcessError::new(
+        ProcessErrorKind::InvalidHandle,
+        format!("process {pid} was not spawned by this runtime or has already been waited for"),
     )
-    .with_context("core.system.process.kill"))
+    .with_context(context)
+}
+
+#[cfg(any(feature = "core_time", feature = "metrics"))]
+pub(super) fn std_duration(duration: Duration) -> StdDuration {
+    duration.to_std().unwrap_or_default()
+}
+
+#[cfg(not(any(feature = "core_time", feature = "metrics")))]
+pub(super) fn std_duration(duration: Duration) -> StdDuration {
+    duration
+}
+
+#[cfg(any(feature = "core_time", feature = "metrics"))]
+pub(super) fn now_timestamp() -> Option<Timestamp> {
+    crate::time::now().ok()
+}
+
+#[cfg(not(any(feature = "core_time", feature = "metrics")))]
+pub(super) fn now_timestamp() -> Option<Timestamp> {
+    Some(Timestamp::now())
 }
 
-fn ensure_process_capability(required_effects: &[&str]) -> ProcessResult<()> {
+pub(super) fn ensure_process_capability(required_effects: &[&str]) -> ProcessResult<()> {
     let requirement = StageRequirement::AtLeast(StageId::Experimental);
     guard_capability(CAP_PROCESS, requirement, required_effects)
         .map(|_| ())
@@ -203,6 +552,8 @@ impl ProcessErrorKind {
             ProcessErrorKind::PermissionDenied => "permission_denied",
             ProcessErrorKind::TimedOut => "timed_out",
             ProcessErrorKind::TerminatedBySignal => "terminated_by_signal",
+            ProcessErrorKind::InvalidHandle => "invalid_handle",
+            ProcessErrorKind::AlreadyRunning => "already_running",
             ProcessErrorKind::Unsupported => "unsupported",
         }
     }
@@ -213,6 +564,8 @@ impl ProcessErrorKind {
             ProcessErrorKind::PermissionDenied => "core.system.process.permission_denied",
             ProcessErrorKind::TimedOut => "core.system.process.timed_out",
             ProcessErrorKind::TerminatedBySignal => "core.system.process.terminated_by_signal",
+            ProcessErrorKind::InvalidHandle => "core.system.process.invalid_handle",
+            ProcessErrorKind::AlreadyRunning => "core.system.process.already_running",
             ProcessErrorKind::Unsupported => "core.system.process.unsupported",
         }
     }
@@ -1,11 +1,12 @@
 use std::collections::BTreeMap;
 
-use crate::runtime::{Signal, SignalError, SignalErrorKind, SignalInfo};
 use crate::runtime::api::guard_capability;
+use crate::runtime::{Signal, SignalError, SignalErrorKind, SignalInfo};
 use crate::stage::{StageId, StageRequirement};
 use serde::{Deserialize, Serialize};
 
-use super::process::ProcessId;
+use super::audit::{record_signal_audit, SignalAuditEvent, SignalAuditInfo};
+use super::process::{now_timestamp, std_duration, ProcessId};
 
 #[cfg(any(feature = "core_time", feature = "metrics"))]
 use crate::time::{Duration, Timestamp};
@@ -43,28 +44,84 @@ pub fn from_runtime_info(info: SignalInfo) -> SignalDetail {
     }
 }
 
-pub fn send(_pid: ProcessId, _signal: Signal) -> Result<(), SignalError> {
+/// 単一のプロセスへシグナルを送る。プロセスグループ宛て（`pid <= 0`）は扱わない。
+pub fn send(pid: ProcessId, signal: Signal) -> Result<(), SignalError> {
     ensure_signal_capability(EFFECTS_SIGNAL_PROCESS)?;
-    Err(SignalError::new(
-        SignalErrorKind::RuntimeFailure,
-        "signal send is not wired in this runtime",
-    ))
+    if pid <= 0 {
+        return Err(SignalError::new(
+            SignalErrorKind::InvalidSignal,
+            format!("signals can only be sent to a single process, got pid {pid}"),
+        ));
+    }
+    platform::send(pid, signal)?;
+    record_signal_audit(&SignalAuditInfo {
+        event: SignalAuditEvent::Send,
+        signal,
+        target_pid: Some(pid),
+        detail: None,
+        raw_code_policy: None,
+    });
+    Ok(())
 }
 
-pub fn wait(_signals: &[Signal], _timeout: Option<Duration>) -> Result<SignalDetail, SignalError> {
+/// `signals` のいずれかが届くまで待つ。`timeout` を過ぎると `TimedOut`。
+///
+/// 待機中だけハンドラを登録し、既定の動作（終了など）の代わりに受信を記録する。
+/// 待っていない間に届くシグナルも受け取るには、先に `subscribe` しておく。
+pub fn wait(signals: &[Signal], timeout: Option<Duration>) -> Result<SignalDetail, SignalError> {
     ensure_signal_capability(EFFECTS_SIGNAL_BLOCKING)?;
-    Err(SignalError::new(
-        SignalErrorKind::RuntimeFailure,
-        "signal wait is not wired in this runtime",
-    ))
+    if signals.is_empty() {
+        return Err(SignalError::new(
+            SignalErrorKind::InvalidSignal,
+            "signal wait needs at least one signal",
+        ));
+    }
+    let detail = platform::wait(signals, timeout.map(std_duration))?;
+    record_signal_audit(&SignalAuditInfo {
+        event: SignalAuditEvent::Wait,
+        signal: detail.info.signal,
+        target_pid: Some(ProcessId::from(std::process::id())),
+        detail: Some(&detail),
+        raw_code_policy: None,
+    });
+    Ok(detail)
 }
 
-pub fn raise(_signal: Signal) -> Result<(), SignalError> {
+/// `signals` にハンドラを登録し、返したガードを破棄するまで受信を記録し続ける。
+/// 記録は次の `wait` で返す。最後の利用者が離れたシグナルは登録前の動作に戻り、
+/// 取り出されていない記録も捨てる。
+pub fn subscribe(signals: &[Signal]) -> Result<SignalSubscription, SignalError> {
     ensure_signal_capability(EFFECTS_SIGNAL)?;
-    Err(SignalError::new(
-        SignalErrorKind::RuntimeFailure,
-        "signal raise is not wired in this runtime",
-    ))
+    platform::acquire(signals)?;
+    Ok(SignalSubscription {
+        signals: signals.to_vec(),
+    })
+}
+
+/// `subscribe` が返すガード。
+#[derive(Debug)]
+pub struct SignalSubscription {
+    signals: Vec<Signal>,
+}
+
+impl Drop for SignalSubscription {
+    fn drop(&mut self) {
+        platform::release(&self.signals);
+    }
+}
+
+/// 自プロセスへシグナルを送る。
+pub fn raise(signal: Signal) -> Result<(), SignalError> {
+    ensure_signal_capability(EFFECTS_SIGNAL)?;
+    platform::raise(signal)?;
+    record_signal_audit(&SignalAuditInfo {
+        event: SignalAuditEvent::Raise,
+        signal,
+        target_pid: Some(ProcessId::from(std::process::id())),
+        detail: None,
+        raw_code_policy: None,
+    });
+    Ok(())
 }
 
 fn ensure_signal_capability(required_effects: &[&str]) -> Result<(), SignalError> {
@@ -73,3 +130,406 @@ fn ensure_signal_capability(required_effects: &[&str]) -> Result<(), SignalError
         .map(|_| ())
         .map_err(|err| SignalError::new(SignalErrorKind::Unsupported, err.detail().to_string()))
 }
+
+/// Unix ではシグナルハンドラから自己パイプへ受信記録を書き込み、`wait` がそれを読み出す。
+#[cfg(unix)]
+mod platform {
+    use std::collections::{HashMap, VecDeque};
+    use std::io;
+    use std::mem;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::{Duration as StdDuration, Instant};
+
+    use libc::{c_int, c_void, siginfo_t};
+    use once_cell::sync::Lazy;
+
+    use super::{now_timestamp, ProcessId, SignalDetail, SignalPayload};
+    use crate::runtime::{Signal, SignalError, SignalErrorKind, SignalInfo};
+
+    /// ハンドラが 1 回の受信で書き込む記録（signal, 送信元 pid, si_code, si_value）。
+    /// `PIPE_BUF` 未満なので書き込みは分割されない。
+    type Record = [i64; 4];
+
+    /// 受信済みで `wait` に取り出されていない記録の上限。超えた分は古いものから捨てる。
+    const PENDING_LIMIT: usize = 64;
+    /// 他スレッドが読み出した記録を見落とさないよう、`poll` はこの間隔で区切る。
+    const POLL_SLICE: StdDuration = StdDuration::from_millis(50);
+
+    /// ハンドラから参照する自己パイプの書き込み側。ハンドラ内ではロックを取れないため原子変数で渡す。
+    static WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+    static STATE: Lazy<Mutex<State>> = Lazy::new(|| Mutex::new(State::default()));
+
+    #[derive(Default)]
+    struct State {
+        read_fd: Option<c_int>,
+        installed: HashMap<c_int, Installed>,
+        pending: VecDeque<SignalDetail>,
+    }
+
+    /// 登録済みのハンドラ。利用者（待機中の `wait` と購読）がいなくなったら `previous` に戻す。
+    struct Installed {
+        previous: libc::sigaction,
+        users: usize,
+    }
+
+    /// `wait` の間だけ登録を保持する。
+    struct Acquired<'a>(&'a [Signal]);
+
+    impl Drop for Acquired<'_> {
+        fn drop(&mut self) {
+            release(self.0);
+        }
+    }
+
+    pub(super) fn acquire(signals: &[Signal]) -> Result<(), SignalError> {
+        let wanted = signals
+            .iter()
+            .map(|&signal| signal_number(signal))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut state = lock_state();
+        state.ensure_pipe().map_err(runtime_failure)?;
+        for (index, &signo) in wanted.iter().enumerate() {
+            if let Err(err) = state.install(signo) {
+                for &installed in &wanted[..index] {
+                    state.uninstall(installed);
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn release(signals: &[Signal]) {
+        let mut state = lock_state();
+        for &signal in signals {
+            if let Ok(signo) = signal_number(signal) {
+                state.uninstall(signo);
+            }
+        }
+    }
+
+    pub(super) fn send(pid: ProcessId, signal: Signal) -> Result<(), SignalError> {
+        let pid = libc::pid_t::try_from(pid).map_err(|_| {
+            SignalError::new(
+                SignalErrorKind::InvalidSignal,
+                format!("pid {pid} is out of range"),
+            )
+        })?;
+        let signo = signal_number(signal)?;
+        // SAFETY: 引数は検証済みの整数で、kill はメモリに触れない。
+        if unsafe { libc::kill(pid, signo) } == 0 {
+            Ok(())
+        } else {
+            Err(os_error(io::Error::last_os_error(), signal))
+        }
+    }
+
+    pub(super) fn raise(signal: Signal) -> Result<(), SignalError> {
+        let signo = signal_number(signal)?;
+        // SAFETY: raise は呼び出しスレッドへシグナルを送るだけでメモリに触れない。
+        if unsafe { libc::raise(signo) } == 0 {
+            Ok(())
+        } else {
+            Err(os_error(io::Error::last_os_error(), signal))
+        }
+    }
+
+    pub(super) fn wait(
+        signals: &[Signal],
+        timeout: Option<StdDuration>,
+    ) -> Result<SignalDetail, SignalError> {
+        let wanted = signals
+            .iter()
+            .map(|&signal| signal_number(signal))
+            .collect::<Result<Vec<_>, _>>()?;
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        acquire(signals)?;
+        let _acquired = Acquired(signals);
+        let read_fd = lock_state().read_fd.expect("pipe is created by acquire");
+        loop {
+            {
+                let mut state = lock_state();
+                state.drain(read_fd);
+                let position = state.pending.iter().position(|detail| {
+                    wanted
+                        .iter()
+                        .any(|&signo| detail.info.signal == signo as Signal)
+                });
+                if let Some(detail) = position.and_then(|index| state.pending.remove(index)) {
+                    return Ok(detail);
+                }
+            }
+            let slice = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(SignalError::new(
+                            SignalErrorKind::TimedOut,
+                            format!("none of the signals {signals:?} arrived before the timeout"),
+                        ));
+                    }
+                    remaining.min(POLL_SLICE)
+                }
+                None => POLL_SLICE,
+            };
+            let mut pollfd = libc::pollfd {
+                fd: read_fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let millis = slice.as_millis().max(1) as c_int;
+            // SAFETY: pollfd は有効な 1 要素の配列として渡す。
+            if unsafe { libc::poll(&mut pollfd, 1, millis) } < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(runtime_failure(err));
+                }
+            }
+        }
+    }
+
+    impl State {
+        fn ensure_pipe(&mut self) -> io::Result<c_int> {
+            if let Some(read_fd) = self.read_fd {
+                return Ok(read_fd);
+            }
+            let mut fds = [0 as c_int; 2];
+            // SAFETY: fds は 2 要素の配列で、pipe が両端を書き込む。
+            if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            for fd in fds {
+                // SAFETY: fd は直前に作成したパイプの端。
+                unsafe {
+                    let flags = libc::fcntl(fd, libc::F_GETFL);
+                    libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+                    libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+                }
+            }
+            WRITE_FD.store(fds[1], Ordering::SeqCst);
+            self.read_fd = Some(fds[0]);
+            Ok(fds[0])
+        }
+
+        fn install(&mut self, signo: c_int) -> Result<(), SignalError> {
+            if let Some(installed) = self.installed.get_mut(&signo) {
+                installed.users += 1;
+                return Ok(());
+            }
+            // SAFETY: sigaction 構造体はゼロ初期化した上で必要なフィールドを設定する。
+            // ハンドラは非同期シグナル安全な操作（write と errno の退避）だけを行う。
+            let mut previous: libc::sigaction = unsafe { mem::zeroed() };
+            let result = unsafe {
+                let mut action: libc::sigaction = mem::zeroed();
+                let handler: extern "C" fn(c_int, *mut siginfo_t, *mut c_void) = on_signal;
+                action.sa_sigaction = handler as usize;
+                action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+                libc::sigemptyset(&mut action.sa_mask);
+                libc::sigaction(signo, &action, &mut previous)
+            };
+            if result != 0 {
+                return Err(os_error(io::Error::last_os_error(), Signal::from(signo)));
+            }
+            self.installed
+                .insert(signo, Installed { previous, users: 1 });
+            Ok(())
+        }
+
+        fn uninstall(&mut self, signo: c_int) {
+            let Some(installed) = self.installed.get_mut(&signo) else {
+                return;
+            };
+            installed.users -= 1;
+            if installed.users > 0 {
+                return;
+            }
+            let installed = self.installed.remove(&signo).expect("entry is present");
+            // SAFETY: previous は install 時に sigaction が書き込んだ登録前の動作。
+            unsafe { libc::sigaction(signo, &installed.previous, std::ptr::null_mut()) };
+            // 戻す前に届いた記録を読み切ってから、このシグナルの分を捨てる。
+            if let Some(read_fd) = self.read_fd {
+                self.drain(read_fd);
+            }
+            self.pending
+                .retain(|detail| detail.info.signal != signo as Signal);
+        }
+
+        fn drain(&mut self, read_fd: c_int) {
+            let mut record: Record = [0; 4];
+            loop {
+                // SAFETY: record は Record 全体を受け取れる大きさのバッファ。
+                let read = unsafe {
+                    libc::read(
+                        read_fd,
+                        record.as_mut_ptr().cast::<c_void>(),
+                        mem::size_of::<Record>(),
+                    )
+                };
+                if read != mem::size_of::<Record>() as isize {
+                    break;
+                }
+                if self.pending.len() == PENDING_LIMIT {
+                    self.pending.pop_front();
+                }
+                self.pending.push_back(detail_from_record(record));
+            }
+        }
+    }
+
+    extern "C" fn on_signal(signo: c_int, info: *mut siginfo_t, _context: *mut c_void) {
+        let fd = WRITE_FD.load(Ordering::Relaxed);
+        if fd < 0 {
+            return;
+        }
+        let saved_errno = io::Error::last_os_error().raw_os_error();
+        // SAFETY: info はカーネルが渡す siginfo_t で、ハンドラの実行中は有効。
+        let (sender, code, value) = unsafe { siginfo_fields(info) };
+        let record: Record = [i64::from(signo), sender, code, value];
+        // SAFETY: write は非同期シグナル安全。パイプが満杯なら記録を捨てる。
+        unsafe {
+            libc::write(
+                fd,
+                record.as_ptr().cast::<c_void>(),
+                mem::size_of::<Record>(),
+            );
+        }
+        if let Some(errno) = saved_errno {
+            restore_errno(errno);
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe fn siginfo_fields(info: *const siginfo_t) -> (i64, i64, i64) {
+        if info.is_null() {
+            return (0, 0, 0);
+        }
+        let info = &*info;
+        let value = if info.si_code == libc::SI_QUEUE {
+            info.si_value().sival_ptr as i64
+        } else {
+            0
+        };
+        (i64::from(info.si_pid()), i64::from(info.si_code), value)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    unsafe fn siginfo_fields(info: *const siginfo_t) -> (i64, i64, i64) {
+        if info.is_null() {
+            return (0, 0, 0);
+        }
+        (0, i64::from((*info).si_code), 0)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn restore_errno(errno: c_int) {
+        // SAFETY: __errno_location は呼び出しスレッドの errno を指す。
+        unsafe { *libc::__errno_location() = errno };
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    fn restore_errno(errno: c_int) {
+        // SAFETY: __error は呼び出しスレッドの errno を指す。
+        unsafe { *libc::__error() = errno };
+    }
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd"
+    )))]
+    fn restore_errno(_errno: c_int) {}
+
+    fn detail_from_record([signo, sender, code, value]: Record) -> SignalDetail {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let payload = (code == i64::from(libc::SI_QUEUE)).then(|| {
+            if signo >= i64::from(libc::SIGRTMIN()) {
+                SignalPayload::RealTime(value)
+            } else {
+                SignalPayload::UserData(value)
+            }
+        });
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let payload = {
+            let _ = (code, value);
+            None
+        };
+        SignalDetail {
+            info: SignalInfo::new(signo, sender),
+            timestamp: now_timestamp(),
+            payload,
+            source_pid: (sender > 0).then_some(sender),
+            raw_code: Some(code),
+        }
+    }
+
+    fn signal_number(signal: Signal) -> Result<c_int, SignalError> {
+        c_int::try_from(signal).map_err(|_| {
+            SignalError::new(
+                SignalErrorKind::InvalidSignal,
+                format!("signal {signal} is out of range"),
+            )
+        })
+    }
+
+    fn os_error(err: io::Error, signal: Signal) -> SignalError {
+        match err.raw_os_error() {
+            Some(libc::EINVAL) => SignalError::new(
+                SignalErrorKind::InvalidSignal,
+                format!("signal {signal} cannot be used here: {err}"),
+            ),
+            Some(libc::EPERM) => {
+                SignalError::new(SignalErrorKind::PermissionDenied, err.to_string())
+            }
+            _ => runtime_failure(err),
+        }
+    }
+
+    fn runtime_failure(err: io::Error) -> SignalError {
+        SignalError::new(SignalErrorKind::RuntimeFailure, err.to_string())
+    }
+
+    fn lock_state() -> MutexGuard<'static, State> {
+        STATE
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(not(unix))]
+mod platform {
+    use std::time::Duration as StdDuration;
+
+    use super::{ProcessId, SignalDetail};
+    use crate::runtime::{Signal, SignalError, SignalErrorKind};
+
+    pub(super) fn send(_pid: ProcessId, _signal: Signal) -> Result<(), SignalError> {
+        Err(unsupported("signal send"))
+    }
+
+    pub(super) fn raise(_signal: Signal) -> Result<(), SignalError> {
+        Err(unsupported("signal raise"))
+    }
+
+    pub(super) fn acquire(_signals: &[Signal]) -> Result<(), SignalError> {
+        Err(unsupported("signal subscribe"))
+    }
+
+    pub(super) fn release(_signals: &[Signal]) {}
+
+    pub(super) fn wait(
+        _signals: &[Signal],
+        _timeout: Option<StdDuration>,
+    ) -> Result<SignalDetail, SignalError> {
+        Err(unsupported("signal wait"))
+    }
+
+    fn unsupported(operation: &str) -> SignalError {
+        SignalError::new(
+            SignalErrorKind::Unsupported,
+            format!("{operation} is only supported on Unix"),
+        )
+    }
+}
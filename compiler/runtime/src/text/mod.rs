@@ -39,7 +39,8 @@ pub use identifier::{prepare_identifier, prepare_identifier_with_locale};
 pub use locale::LocaleId;
 pub use normalize::{is_normalized, normalize, NormalizationForm};
 pub use pretty::{
-    concat, cst_doc, cst_printer, group, line, nest, render, softline, text, CstPrinter, Doc,
+    concat, cst_doc, cst_printer, group, line, nest, render, render_with_tabs, softline, text,
+    CstPrinter, Doc,
 };
 pub use span_highlight::{span_highlight, SpanHighlight};
 pub use str_ref::Str;
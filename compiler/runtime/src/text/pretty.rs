@@ -127,6 +127,18 @@ fn concat_all(mut docs: Vec<Doc>) -> Doc {
 
 /// 指定幅でドキュメントをレンダリングする。
 pub fn render(doc: Doc, width: i64) -> String {
+    render_indented(doc, width, None)
+}
+
+/// 改行後のインデントをタブで出力してレンダリングする。
+///
+/// タブ 1 文字は `tab_width` 桁として幅を計算し、インデントのうちタブで
+/// 埋めきれない端数だけを空白で出力する。
+pub fn render_with_tabs(doc: Doc, width: i64, tab_width: usize) -> String {
+    render_indented(doc, width, Some(tab_width.max(1)))
+}
+
+fn render_indented(doc: Doc, width: i64, tab_width: Option<usize>) -> String {
     let width = width.max(0) as usize;
     let mut output = String::new();
     let mut stack = vec![DocFrame {
@@ -139,12 +151,12 @@ pub fn render(doc: Doc, width: i64) -> String {
     while let Some(frame) = stack.pop() {
         match frame.doc {
             Doc::Text(value) => {
-                column += text_width(&value);
+                column += text_width(&value, tab_width);
                 output.push_str(&value);
             }
             Doc::Line => {
                 output.push('\n');
-                push_indent(&mut output, frame.indent);
+                push_indent(&mut output, frame.indent, tab_width);
                 column = frame.indent;
             }
             Doc::Softline => match frame.mode {
@@ -154,7 +166,7 @@ pub fn render(doc: Doc, width: i64) -> String {
                 }
                 Mode::Break => {
                     output.push('\n');
-                    push_indent(&mut output, frame.indent);
+                    push_indent(&mut output, frame.indent, tab_width);
                     column = frame.indent;
                 }
             },
@@ -187,7 +199,7 @@ pub fn render(doc: Doc, width: i64) -> String {
                     doc: doc.clone(),
                 });
                 let remaining = width as isize - column as isize;
-                let flat = fits(remaining, &trial);
+                let flat = fits(remaining, &trial, tab_width);
                 stack.push(DocFrame {
                     indent: frame.indent,
                     mode: if flat { Mode::Flat } else { Mode::Break },
@@ -200,7 +212,7 @@ pub fn render(doc: Doc, width: i64) -> String {
     output
 }
 
-fn fits(remaining: isize, stack: &[DocFrame]) -> bool {
+fn fits(remaining: isize, stack: &[DocFrame], tab_width: Option<usize>) -> bool {
     let mut remaining = remaining;
     let mut stack = stack.to_vec();
 
@@ -210,7 +222,7 @@ fn fits(remaining: isize, stack: &[DocFrame]) -> bool {
         }
         match frame.doc {
             Doc::Text(value) => {
-                remaining -= text_width(&value) as isize;
+                remaining -= text_width(&value, tab_width) as isize;
             }
             Doc::Line => return true,
             Doc::Softline => match frame.mode {
@@ -253,19 +265,27 @@ fn fits(remaining: isize, stack: &[DocFrame]) -> bool {
     true
 }
 
-fn text_width(value: &str) -> usize {
+fn text_width(value: &str, tab_width: Option<usize>) -> usize {
     let str_ref = Str::from(value);
     str_ref
         .iter_graphemes()
-        .map(|grapheme| UnicodeWidthStr::width(grapheme).max(1))
+        .map(|grapheme| match (grapheme, tab_width) {
+            ("\t", Some(tab_width)) => tab_width,
+            _ => UnicodeWidthStr::width(grapheme).max(1),
+        })
         .sum()
 }
 
-fn push_indent(output: &mut String, indent: usize) {
+fn push_indent(output: &mut String, indent: usize, tab_width: Option<usize>) {
     if indent == 0 {
         return;
     }
-    output.push_str(&" ".repeat(indent));
+    let (tabs, spaces) = match tab_width {
+        Some(tab_width) => (indent / tab_width, indent % tab_width),
+        None => (0, indent),
+    };
+    output.push_str(&"\t".repeat(tabs));
+    output.push_str(&" ".repeat(spaces));
 }
 
 #[cfg(test)]
@@ -281,4 +301,17 @@ mod tests {
         assert_eq!(render(doc.clone(), 10), "let x = 1");
         assert_eq!(render(doc, 5), "let\n  x = 1");
     }
+
+    #[test]
+    fn render_with_tabs_indents_with_tabs_and_counts_tab_width() {
+        let doc = concat(
+            text("\t"),
+            group(concat(
+                text("f(a,"),
+                nest(8, concat(softline(), text("b)"))),
+            )),
+        );
+        assert_eq!(render(doc.clone(), 10), "\tf(a, b)");
+        assert_eq!(render_with_tabs(doc, 10, 4), "\tf(a,\n\t\tb)");
+    }
 }
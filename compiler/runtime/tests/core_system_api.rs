@@ -3,14 +3,40 @@ use std::convert::TryFrom;
 use reml_runtime::{
     capability::registry::{reset_for_tests, CapabilityRegistry},
     env as core_env,
+    io::{Reader, Writer},
     path::PathBuf,
     prelude::ensure::IntoDiagnostic,
     runtime::{SignalErrorKind, SignalInfo},
-    system::{env as system_env, process, signal},
+    system::{audit::take_system_audit_events, daemon, env as system_env, process, signal},
 };
 
+#[cfg(any(feature = "core_time", feature = "metrics"))]
+fn millis(value: u64) -> reml_runtime::time::Duration {
+    reml_runtime::time::Duration::from_millis(value as i64)
+}
+
+#[cfg(not(any(feature = "core_time", feature = "metrics")))]
+fn millis(value: u64) -> std::time::Duration {
+    std::time::Duration::from_millis(value)
+}
+
+fn shell(script: &str) -> process::Command {
+    process::Command::new(PathBuf::try_from("/bin/sh").expect("path should be valid"))
+        .with_args(["-c", script])
+}
+
+fn piped() -> process::SpawnOptions {
+    process::SpawnOptions {
+        stdin: process::StdioMode::Pipe,
+        stdout: process::StdioMode::Pipe,
+        stderr: process::StdioMode::Pipe,
+        detach: false,
+    }
+}
+
 #[test]
 fn process_returns_unsupported_when_capability_missing() {
+    let _guard = reml_runtime::test_support::lock();
     reset_for_tests();
     let registry = CapabilityRegistry::registry();
     registry
@@ -28,6 +54,7 @@ fn process_returns_unsupported_when_capability_missing() {
 
 #[test]
 fn signal_returns_unsupported_when_capability_missing() {
+    let _guard = reml_runtime::test_support::lock();
     reset_for_tests();
     let registry = CapabilityRegistry::registry();
     registry
@@ -65,3 +92,438 @@ fn core_env_alias_returns_same_values() {
 
     system_env::remove_env(key).expect("env cleanup should succeed");
 }
+
+#[cfg(unix)]
+#[test]
+fn process_spawn_streams_pipes_and_reports_exit_status() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let _ = take_system_audit_events();
+    let dir = tempfile::tempdir().expect("tempdir");
+    let command = shell("read line; echo \"$GREETING $line from $(pwd)\"; echo oops >&2; exit 3")
+        .with_env("GREETING", "hello")
+        .with_cwd(PathBuf::from_std(
+            dir.path().canonicalize().expect("canonical"),
+        ));
+    let handle = process::spawn(command, piped()).expect("spawn should succeed");
+
+    let mut stdin = process::take_stdin(&handle).expect("stdin pipe");
+    Writer::write_all(&mut stdin, b"reml\n").expect("write stdin");
+    drop(stdin);
+    let stdout = Reader::read_to_end(&mut process::take_stdout(&handle).expect("stdout pipe"))
+        .expect("read stdout");
+    let stderr = Reader::read_to_end(&mut process::take_stderr(&handle).expect("stderr pipe"))
+        .expect("read stderr");
+    assert_eq!(
+        String::from_utf8_lossy(stdout.as_slice()),
+        format!(
+            "hello reml from {}\n",
+            dir.path().canonicalize().unwrap().display()
+        )
+    );
+    assert_eq!(stderr.as_slice(), b"oops\n");
+    let err = process::take_stdout(&handle).expect_err("stdout was already taken");
+    assert_eq!(err.kind, process::ProcessErrorKind::InvalidHandle);
+
+    assert_eq!(process::wait(handle.clone(), None), Ok(3));
+    let err = process::wait(handle, None).expect_err("process was already waited for");
+    assert_eq!(err.kind, process::ProcessErrorKind::InvalidHandle);
+
+    let events = take_system_audit_events();
+    let kinds = events
+        .iter()
+        .filter_map(|event| event.envelope.event_kind())
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, ["process.spawn", "process.wait"]);
+    assert_eq!(
+        events[1].envelope.metadata.get("process.exit_status"),
+        Some(&serde_json::json!(3))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn process_wait_times_out_and_kill_delivers_signal() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let _ = take_system_audit_events();
+    let handle = process::spawn(shell("sleep 30"), process::SpawnOptions::default())
+        .expect("spawn should succeed");
+
+    let err = process::wait(handle.clone(), Some(millis(50))).expect_err("sleep should time out");
+    assert_eq!(err.kind, process::ProcessErrorKind::TimedOut);
+
+    process::kill(handle.clone(), 15).expect("kill should succeed");
+    let err = process::wait(handle.clone(), Some(millis(5_000)))
+        .expect_err("terminated process reports the signal");
+    assert_eq!(err.kind, process::ProcessErrorKind::TerminatedBySignal);
+    assert!(err.message.contains("signal 15"), "{}", err.message);
+
+    let err = process::kill(handle, 15).expect_err("reaped process cannot be signalled");
+    assert_eq!(err.kind, process::ProcessErrorKind::InvalidHandle);
+
+    let events = take_system_audit_events();
+    let kill = events
+        .iter()
+        .find(|event| event.envelope.event_kind() == Some("process.kill"))
+        .expect("process.kill should be recorded");
+    assert_eq!(
+        kill.envelope.metadata.get("process.signal"),
+        Some(&serde_json::json!(15))
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn process_detach_reaps_exited_children() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let handle = process::spawn(shell("exit 0"), process::SpawnOptions::default())
+        .expect("spawn should succeed");
+    let proc_entry = std::path::PathBuf::from(format!("/proc/{}", handle.pid));
+
+    process::detach(handle.clone()).expect("detach should succeed");
+    let err = process::wait(handle.clone(), None).expect_err("detached process cannot be waited");
+    assert_eq!(err.kind, process::ProcessErrorKind::InvalidHandle);
+    let err = process::detach(handle).expect_err("process was already detached");
+    assert_eq!(err.kind, process::ProcessErrorKind::InvalidHandle);
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    while proc_entry.exists() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "detached child was not reaped"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn process_spawn_redirects_stdio_to_files() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let dir = tempfile::tempdir().expect("tempdir");
+    let input = dir.path().join("input.txt");
+    let output = dir.path().join("output.txt");
+    std::fs::write(&input, "from file\n").expect("write input");
+    let options = process::SpawnOptions {
+        stdin: process::StdioMode::File(PathBuf::from_std(input)),
+        stdout: process::StdioMode::File(PathBuf::from_std(output.clone())),
+        stderr: process::StdioMode::Null,
+        detach: true,
+    };
+    let handle = process::spawn(shell("cat; echo ignored >&2"), options).expect("spawn");
+    assert_eq!(process::wait(handle, None), Ok(0));
+    assert_eq!(
+        std::fs::read_to_string(output).expect("read output"),
+        "from file\n"
+    );
+
+    let missing = process::Command::new(
+        PathBuf::try_from("/nonexistent/reml-tool").expect("path should be valid"),
+    );
+    let err = process::spawn(missing, process::SpawnOptions::default())
+        .expect_err("missing program should fail");
+    assert_eq!(err.kind, process::ProcessErrorKind::SpawnFailed);
+    let _ = take_system_audit_events();
+}
+
+#[cfg(unix)]
+#[test]
+fn signal_raise_and_send_are_delivered_to_wait() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let _ = take_system_audit_events();
+    let usr1 = i64::from(libc::SIGUSR1);
+    let usr2 = i64::from(libc::SIGUSR2);
+    let own_pid = i64::from(std::process::id());
+
+    // 購読中のシグナルはプロセスを終了させずに保持される。
+    let subscription = signal::subscribe(&[usr1, usr2]).expect("subscribe should succeed");
+    let err = signal::wait(&[usr1, usr2], Some(millis(0))).expect_err("nothing was sent yet");
+    assert_eq!(err.kind, SignalErrorKind::TimedOut);
+
+    signal::raise(usr1).expect("raise should succeed");
+    signal::send(own_pid, usr2).expect("send should succeed");
+    let detail = signal::wait(&[usr2], Some(millis(5_000))).expect("SIGUSR2 should arrive");
+    assert_eq!(detail.info.signal, usr2);
+    assert_eq!(detail.source_pid, Some(own_pid));
+    assert_eq!(detail.raw_code, Some(i64::from(libc::SI_USER)));
+    assert!(detail.timestamp.is_some());
+    let detail = signal::wait(&[usr1], Some(millis(5_000))).expect("SIGUSR1 was kept");
+    assert_eq!(detail.info.signal, usr1);
+    drop(subscription);
+
+    let events = take_system_audit_events();
+    let kinds = events
+        .iter()
+        .filter_map(|event| event.envelope.event_kind())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        ["signal.raise", "signal.send", "signal.wait", "signal.wait"]
+    );
+    assert_eq!(
+        events[1].envelope.metadata.get("signal.target_pid"),
+        Some(&serde_json::json!(own_pid))
+    );
+    assert_eq!(
+        events[2].envelope.metadata.get("signal.raw_code"),
+        Some(&serde_json::json!("masked"))
+    );
+}
+
+/// 現在の SIGUSR1 の動作が `SIG_IGN` かどうか。
+#[cfg(unix)]
+fn usr1_is_ignored() -> bool {
+    // SAFETY: 新しい動作は渡さず、現在の動作を読み出すだけ。
+    unsafe {
+        let mut current: libc::sigaction = std::mem::zeroed();
+        assert_eq!(
+            libc::sigaction(libc::SIGUSR1, std::ptr::null(), &mut current),
+            0
+        );
+        current.sa_sigaction == libc::SIG_IGN
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn signal_handlers_are_restored_when_the_last_user_leaves() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let usr1 = i64::from(libc::SIGUSR1);
+    // SAFETY: テスト中だけ SIGUSR1 を無視させ、最後に既定の動作へ戻す。
+    unsafe { libc::signal(libc::SIGUSR1, libc::SIG_IGN) };
+
+    let err = signal::wait(&[usr1], Some(millis(0))).expect_err("nothing was sent");
+    assert_eq!(err.kind, SignalErrorKind::TimedOut);
+    assert!(usr1_is_ignored(), "wait restores the previous action");
+
+    let first = signal::subscribe(&[usr1]).expect("subscribe should succeed");
+    let second = signal::subscribe(&[usr1]).expect("subscribe should succeed");
+    assert!(!usr1_is_ignored());
+    drop(first);
+    assert!(!usr1_is_ignored(), "the second subscription still holds it");
+    signal::raise(usr1).expect("raise should succeed");
+    drop(second);
+    assert!(usr1_is_ignored(), "the last subscription restores it");
+
+    // 購読を外すと保持していた記録も捨てる。
+    let err = signal::wait(&[usr1], Some(millis(0))).expect_err("the record was dropped");
+    assert_eq!(err.kind, SignalErrorKind::TimedOut);
+
+    // SAFETY: 既定の動作へ戻す。
+    unsafe { libc::signal(libc::SIGUSR1, libc::SIG_DFL) };
+    let _ = take_system_audit_events();
+}
+
+#[cfg(unix)]
+#[test]
+fn signal_rejects_invalid_targets_and_uncatchable_signals() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let own_pid = i64::from(std::process::id());
+
+    let err = signal::wait(&[i64::from(libc::SIGKILL)], Some(millis(0)))
+        .expect_err("SIGKILL cannot be caught");
+    assert_eq!(err.kind, SignalErrorKind::InvalidSignal);
+    let err = signal::send(0, 15).expect_err("process groups are not targeted");
+    assert_eq!(err.kind, SignalErrorKind::InvalidSignal);
+    let err = signal::send(own_pid, 100_000).expect_err("unknown signal number");
+    assert_eq!(err.kind, SignalErrorKind::InvalidSignal);
+
+    let handle = process::spawn(shell("sleep 30"), process::SpawnOptions::default())
+        .expect("spawn should succeed");
+    signal::send(handle.pid, 15).expect("send to child should succeed");
+    let err = process::wait(handle, Some(millis(5_000))).expect_err("child was terminated");
+    assert_eq!(err.kind, process::ProcessErrorKind::TerminatedBySignal);
+    let _ = take_system_audit_events();
+}
+
+#[cfg(unix)]
+#[test]
+fn write_pid_file_replaces_stale_files_and_rejects_running_holders() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("service.pid");
+    let pid_file = || PathBuf::from_std(path.clone());
+    let own_pid = i64::from(std::process::id());
+
+    daemon::write_pid_file(pid_file(), own_pid).expect("fresh pid file");
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        format!("{own_pid}\n")
+    );
+    daemon::write_pid_file(pid_file(), own_pid).expect("rewriting our own pid file");
+    let err = daemon::write_pid_file(pid_file(), own_pid + 1)
+        .expect_err("a running process holds the pid file");
+    assert_eq!(err.kind, process::ProcessErrorKind::AlreadyRunning);
+
+    let exited = process::spawn(shell("exit 0"), process::SpawnOptions::default())
+        .expect("spawn should succeed");
+    let stale_pid = exited.pid;
+    assert_eq!(process::wait(exited, None), Ok(0));
+    std::fs::write(&path, format!("{stale_pid}\n")).unwrap();
+    daemon::write_pid_file(pid_file(), own_pid).expect("stale pid file is replaced");
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        format!("{own_pid}\n")
+    );
+
+    std::fs::write(&path, "not a pid").unwrap();
+    daemon::write_pid_file(pid_file(), own_pid).expect("unreadable pid file is replaced");
+    let mut leftovers = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    leftovers.sort();
+    assert_eq!(
+        leftovers,
+        [".service.pid.lock", "service.pid"],
+        "temporary files should be removed"
+    );
+    let _ = take_system_audit_events();
+}
+
+#[cfg(unix)]
+#[test]
+fn write_pid_file_grants_a_stale_file_to_one_racing_writer() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("service.pid");
+    let exited = process::spawn(shell("exit 0"), process::SpawnOptions::default())
+        .expect("spawn should succeed");
+    let stale_pid = exited.pid;
+    assert_eq!(process::wait(exited, None), Ok(0));
+
+    for _ in 0..10 {
+        std::fs::write(&path, format!("{stale_pid}\n")).unwrap();
+        let writers = (0..4)
+            .map(|_| {
+                process::spawn(shell("sleep 30"), process::SpawnOptions::default())
+                    .expect("spawn should succeed")
+            })
+            .collect::<Vec<_>>();
+        let barrier = std::sync::Barrier::new(writers.len());
+        let results = std::thread::scope(|scope| {
+            let tasks = writers
+                .iter()
+                .map(|writer| {
+                    let (barrier, path) = (&barrier, &path);
+                    scope.spawn(move || {
+                        barrier.wait();
+                        daemon::write_pid_file(PathBuf::from_std(path.clone()), writer.pid)
+                    })
+                })
+                .collect::<Vec<_>>();
+            tasks
+                .into_iter()
+                .map(|task| task.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        let winners = writers
+            .iter()
+            .zip(&results)
+            .filter(|(_, result)| result.is_ok())
+            .map(|(writer, _)| writer.pid)
+            .collect::<Vec<_>>();
+        assert_eq!(winners.len(), 1, "{results:?}");
+        assert!(results
+            .iter()
+            .flat_map(|result| result.as_ref().err())
+            .all(|err| err.kind == process::ProcessErrorKind::AlreadyRunning));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n", winners[0])
+        );
+        for writer in writers {
+            process::kill(writer.clone(), 9).expect("kill should succeed");
+            let _ = process::wait(writer, None);
+        }
+    }
+    let _ = take_system_audit_events();
+}
+
+/// デーモン化は呼び出し元のプロセスを終了させるため、テストバイナリ自身を再実行して確かめる。
+#[cfg(unix)]
+#[test]
+fn daemonize_detaches_and_writes_pid_file() {
+    const DAEMON_DIR_ENV: &str = "REML_TEST_DAEMONIZE_DIR";
+    if let Ok(dir) = std::env::var(DAEMON_DIR_ENV) {
+        let dir = std::path::PathBuf::from(dir);
+        if dir.ends_with("missing") {
+            // 準備に失敗したデーモンのエラーは呼び出し元のプロセスへ返る。
+            let config =
+                daemon::DaemonConfig::new("reml-test-daemon").with_cwd(PathBuf::from_std(dir));
+            let err = daemon::daemonize(config).expect_err("missing cwd should fail");
+            assert_eq!(err.kind, process::ProcessErrorKind::SpawnFailed);
+            assert!(err.message.contains("chdir"), "{}", err.message);
+            std::process::exit(3);
+        }
+        let config = daemon::DaemonConfig::new("reml-test-daemon")
+            .with_pid_file(PathBuf::from_std(dir.join("daemon.pid")))
+            .with_cwd(PathBuf::from_std(dir.clone()))
+            .with_stdout(PathBuf::from_std(dir.join("daemon.log")))
+            .with_umask(0o077);
+        daemon::daemonize(config).expect("daemonize should succeed");
+        println!("daemon running");
+        // SAFETY: getsid(0) は自プロセスのセッション ID を返すだけ。
+        let sid = unsafe { libc::getsid(0) };
+        std::fs::write("report.tmp", format!("{} {sid}", std::process::id())).unwrap();
+        std::fs::rename("report.tmp", "report").unwrap();
+        // SAFETY: デーモン側ではテストハーネスへ戻らずに終了する。
+        unsafe { libc::_exit(0) };
+    }
+
+    let run_daemon = |dir: &std::path::Path| {
+        std::process::Command::new(std::env::current_exe().expect("test binary"))
+            .args([
+                "--exact",
+                "daemonize_detaches_and_writes_pid_file",
+                "--test-threads=1",
+                "--nocapture",
+            ])
+            .env(DAEMON_DIR_ENV, dir)
+            .stdout(std::process::Stdio::null())
+            .status()
+            .expect("re-run test binary")
+    };
+    let dir = tempfile::tempdir().expect("tempdir");
+    let dir_path = dir.path().canonicalize().expect("canonical");
+    let status = run_daemon(&dir_path.join("missing"));
+    assert_eq!(status.code(), Some(3), "daemonize reports the failure");
+    let status = run_daemon(&dir_path);
+    assert!(status.success(), "the launching process exits with 0");
+
+    let report = dir_path.join("report");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !report.exists() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "daemon did not report"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    let content = std::fs::read_to_string(&report).unwrap();
+    let (pid, sid) = content.split_once(' ').expect("pid and sid");
+    // SAFETY: getsid(0) は自プロセスのセッション ID を返すだけ。
+    let own_sid = unsafe { libc::getsid(0) };
+    assert_ne!(sid, own_sid.to_string(), "daemon runs in its own session");
+    assert_ne!(sid, pid, "daemon is not the session leader");
+    assert_eq!(
+        std::fs::read_to_string(dir_path.join("daemon.pid")).unwrap(),
+        format!("{pid}\n")
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir_path.join("daemon.log")).unwrap(),
+        "daemon running\n"
+    );
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(&report).unwrap().permissions().mode();
+    assert_eq!(mode & 0o077, 0, "umask is applied");
+}
@@ -0,0 +1,435 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::{Arc, Once};
+
+use serde_json::Value;
+
+use reml_runtime::embedding::{
+    reml_call, reml_create_context, reml_dispose_context, reml_embed_value_array,
+    reml_embed_value_array_get, reml_embed_value_as_float, reml_embed_value_as_int,
+    reml_embed_value_as_string, reml_embed_value_bool, reml_embed_value_dec_ref,
+    reml_embed_value_float, reml_embed_value_int, reml_embed_value_kind, reml_embed_value_length,
+    reml_embed_value_record, reml_embed_value_record_get, reml_embed_value_string, reml_last_error,
+    reml_load_module, reml_register_function, reml_run, set_embed_module_compiler,
+    EmbedCompiledModule, EmbedDiagnostic, EmbedFunction, EmbedModuleCompiler, EmbedValue,
+    RemlEmbedContext, RemlEmbedStatus, RemlEmbedValueKind, COMPILE_FAILED_CODE,
+    HOST_CALL_FAILED_CODE, HOST_FUNCTION_MISSING_CODE, SIGNATURE_MISMATCH_CODE,
+};
+
+/// wasm バックエンドと同じ配置（16 バイトのヘッダ、payload 直前に refcount/tag）を持つ
+/// 最小のアロケータ。`dec_ref` は呼び出し回数だけを数える。
+const RUNTIME_WAT: &str = r#"
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (global $released (mut i64) (i64.const 0))
+  (func (export "mem_alloc") (param $size i32) (result i32)
+    (local $ptr i32)
+    global.get $heap
+    i32.const 16
+    i32.add
+    local.set $ptr
+    local.get $ptr
+    i32.const 16
+    i32.sub
+    local.get $size
+    i32.store
+    local.get $ptr
+    i32.const 8
+    i32.sub
+    i32.const 1
+    i32.store
+    local.get $ptr
+    local.get $size
+    i32.const 7
+    i32.add
+    i32.const -8
+    i32.and
+    i32.add
+    global.set $heap
+    local.get $ptr)
+  (func (export "reml_set_type_tag") (param $ptr i32) (param $tag i32)
+    local.get $ptr
+    i32.const 4
+    i32.sub
+    local.get $tag
+    i32.store)
+  (func (export "dec_ref") (param $ptr i32)
+    global.get $released
+    i64.const 1
+    i64.add
+    global.set $released)
+  (func (export "released") (result i64)
+    global.get $released)
+"#;
+
+/// ソースを次の形式で解釈するモックコンパイラ。
+/// - `add`: `add(Int, Int) -> Int` が `env.host_add` を呼ぶ。
+/// - `relay <signature>`: `relay` がオブジェクトを `env.transform` へ渡して結果を返す。
+/// - それ以外: 位置付きの診断でコンパイルに失敗する。
+struct MockCompiler;
+
+impl EmbedModuleCompiler for MockCompiler {
+    fn compile(&self, source: &str) -> Result<EmbedCompiledModule, Vec<EmbedDiagnostic>> {
+        if source == "add" {
+            let wat = format!(
+                r#"(module
+  (import "env" "host_add" (func $host_add (param i64 i64) (result i64)))
+  {RUNTIME_WAT}
+  (func (export "add") (param i64 i64) (result i64)
+    local.get 0
+    local.get 1
+    call $host_add))"#
+            );
+            return Ok(compiled(
+                &wat,
+                vec![
+                    EmbedFunction::new("add", "(Int, Int) -> Int"),
+                    EmbedFunction::new("released", "() -> Int"),
+                ],
+                vec![EmbedFunction::new("host_add", "(Int, Int) -> Int")],
+            ));
+        }
+        if let Some(signature) = source.strip_prefix("relay ") {
+            let wat = format!(
+                r#"(module
+  (import "env" "transform" (func $transform (param i32) (result i32)))
+  {RUNTIME_WAT}
+  (func (export "relay") (param i32) (result i32)
+    local.get 0
+    call $transform))"#
+            );
+            return Ok(compiled(
+                &wat,
+                vec![
+                    EmbedFunction::new("relay", signature),
+                    EmbedFunction::new("released", "() -> Int"),
+                ],
+                vec![EmbedFunction::new("transform", signature)],
+            ));
+        }
+        Err(vec![EmbedDiagnostic::new(
+            "parser.syntax.expected_tokens",
+            "`fn` が必要です",
+        )
+        .with_span(0, source.len())])
+    }
+}
+
+fn compiled(
+    wat: &str,
+    exports: Vec<EmbedFunction>,
+    imports: Vec<EmbedFunction>,
+) -> EmbedCompiledModule {
+    EmbedCompiledModule {
+        wasm: wat::parse_str(wat).expect("valid wat"),
+        exports,
+        imports,
+    }
+}
+
+fn install_mock_compiler() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_embed_module_compiler(Arc::new(MockCompiler)).expect("compiler installed once");
+    });
+}
+
+fn create_loaded_context(source: &str) -> *mut RemlEmbedContext {
+    install_mock_compiler();
+    let abi = CString::new("0.1.0").unwrap();
+    let mut context = ptr::null_mut();
+    assert_eq!(
+        reml_create_context(abi.as_ptr(), &mut context),
+        RemlEmbedStatus::Ok
+    );
+    assert_eq!(
+        reml_load_module(context, source.as_ptr(), source.len()),
+        RemlEmbedStatus::Ok,
+        "{:?}",
+        last_error(context)
+    );
+    context
+}
+
+fn last_error(context: *const RemlEmbedContext) -> Option<Value> {
+    let message = reml_last_error(context);
+    if message.is_null() {
+        return None;
+    }
+    let text = unsafe { CStr::from_ptr(message) }.to_str().unwrap();
+    Some(serde_json::from_str(text).expect("last_error is JSON"))
+}
+
+fn register(
+    context: *mut RemlEmbedContext,
+    name: &str,
+    signature: &str,
+    callback: reml_runtime::embedding::RemlHostFunction,
+    user_data: *mut c_void,
+) -> RemlEmbedStatus {
+    let name = CString::new(name).unwrap();
+    let signature = CString::new(signature).unwrap();
+    reml_register_function(
+        context,
+        name.as_ptr(),
+        signature.as_ptr(),
+        Some(callback),
+        user_data,
+    )
+}
+
+fn call(
+    context: *mut RemlEmbedContext,
+    entrypoint: &str,
+    args: &[*const EmbedValue],
+) -> (RemlEmbedStatus, *mut EmbedValue) {
+    let entrypoint = CString::new(entrypoint).unwrap();
+    let mut result = ptr::null_mut();
+    let status = reml_call(
+        context,
+        entrypoint.as_ptr(),
+        args.as_ptr(),
+        args.len(),
+        &mut result,
+    );
+    (status, result)
+}
+
+fn string_of(value: *const EmbedValue) -> String {
+    let mut length = 0;
+    let data = reml_embed_value_as_string(value, &mut length);
+    assert!(!data.is_null());
+    let bytes = unsafe { std::slice::from_raw_parts(data as *const u8, length) };
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+unsafe extern "C" fn host_add(
+    user_data: *mut c_void,
+    args: *const *const EmbedValue,
+    argc: usize,
+) -> *mut EmbedValue {
+    let calls = &mut *(user_data as *mut usize);
+    *calls += 1;
+    let args = std::slice::from_raw_parts(args, argc);
+    reml_embed_value_int(reml_embed_value_as_int(args[0]) + reml_embed_value_as_int(args[1]))
+}
+
+/// レコード `{name: Str, scores: [Float]}` を受け取り、名前に印を付けて点数を 2 倍にする。
+unsafe extern "C" fn host_transform(
+    _user_data: *mut c_void,
+    args: *const *const EmbedValue,
+    argc: usize,
+) -> *mut EmbedValue {
+    assert_eq!(argc, 1);
+    let record = *args;
+    let name_key = CString::new("name").unwrap();
+    let scores_key = CString::new("scores").unwrap();
+    let name = string_of(reml_embed_value_record_get(record, name_key.as_ptr()));
+    let scores = reml_embed_value_record_get(record, scores_key.as_ptr());
+
+    let name = format!("{name}!");
+    let name = reml_embed_value_string(name.as_ptr() as *const _, name.len());
+    let doubled: Vec<*const EmbedValue> = (0..reml_embed_value_length(scores))
+        .map(|index| {
+            reml_embed_value_float(
+                reml_embed_value_as_float(reml_embed_value_array_get(scores, index)) * 2.0,
+            ) as *const EmbedValue
+        })
+        .collect();
+    let scores = reml_embed_value_array(doubled.as_ptr(), doubled.len());
+    for item in doubled {
+        reml_embed_value_dec_ref(item);
+    }
+    let names = [name_key.as_ptr(), scores_key.as_ptr()];
+    let values = [name as *const EmbedValue, scores as *const EmbedValue];
+    let result = reml_embed_value_record(names.as_ptr(), values.as_ptr(), 2);
+    reml_embed_value_dec_ref(name);
+    reml_embed_value_dec_ref(scores);
+    result
+}
+
+unsafe extern "C" fn host_fail(
+    _user_data: *mut c_void,
+    _args: *const *const EmbedValue,
+    _argc: usize,
+) -> *mut EmbedValue {
+    ptr::null_mut()
+}
+
+#[test]
+fn entrypoint_calls_registered_host_function() {
+    let context = create_loaded_context("add");
+    let mut calls = 0usize;
+    assert_eq!(
+        register(
+            context,
+            "host_add",
+            "(Int, Int) -> Int",
+            host_add,
+            &mut calls as *mut usize as *mut c_void,
+        ),
+        RemlEmbedStatus::Ok
+    );
+
+    let left = reml_embed_value_int(40);
+    let right = reml_embed_value_int(2);
+    let (status, result) = call(context, "add", &[left, right]);
+    assert_eq!(status, RemlEmbedStatus::Ok, "{:?}", last_error(context));
+    assert_eq!(reml_embed_value_kind(result), RemlEmbedValueKind::Int);
+    assert_eq!(reml_embed_value_as_int(result), 42);
+    assert_eq!(calls, 1);
+
+    reml_embed_value_dec_ref(result);
+    reml_embed_value_dec_ref(left);
+    reml_embed_value_dec_ref(right);
+    assert_eq!(reml_dispose_context(context), RemlEmbedStatus::Ok);
+}
+
+#[test]
+fn records_strings_and_arrays_round_trip_through_linear_memory() {
+    let signature = "({name: Str, scores: [Float]}) -> {name: Str, scores: [Float]}";
+    let context = create_loaded_context(&format!("relay {signature}"));
+    assert_eq!(
+        register(
+            context,
+            "transform",
+            signature,
+            host_transform,
+            ptr::null_mut()
+        ),
+        RemlEmbedStatus::Ok
+    );
+
+    let name = reml_embed_value_string("reml".as_ptr() as *const _, 4);
+    let scores: Vec<*const EmbedValue> = [1.5, 2.0]
+        .iter()
+        .map(|score| reml_embed_value_float(*score) as *const EmbedValue)
+        .collect();
+    let array = reml_embed_value_array(scores.as_ptr(), scores.len());
+    let names = [
+        CString::new("name").unwrap(),
+        CString::new("scores").unwrap(),
+    ];
+    let name_ptrs = [names[0].as_ptr(), names[1].as_ptr()];
+    let values = [name as *const EmbedValue, array as *const EmbedValue];
+    let record = reml_embed_value_record(name_ptrs.as_ptr(), values.as_ptr(), 2);
+
+    let (status, result) = call(context, "relay", &[record]);
+    assert_eq!(status, RemlEmbedStatus::Ok, "{:?}", last_error(context));
+    assert_eq!(reml_embed_value_kind(result), RemlEmbedValueKind::Record);
+    assert_eq!(
+        string_of(reml_embed_value_record_get(result, name_ptrs[0])),
+        "reml!"
+    );
+    let doubled = reml_embed_value_record_get(result, name_ptrs[1]);
+    assert_eq!(reml_embed_value_length(doubled), 2);
+    assert_eq!(
+        reml_embed_value_as_float(reml_embed_value_array_get(doubled, 0)),
+        3.0
+    );
+    assert_eq!(
+        reml_embed_value_as_float(reml_embed_value_array_get(doubled, 1)),
+        4.0
+    );
+
+    // 引数（Borrowed）と戻り値（Transferred）はどちらも呼び出し後に wasm 側で解放される。
+    let (status, released) = call(context, "released", &[]);
+    assert_eq!(status, RemlEmbedStatus::Ok);
+    assert_eq!(reml_embed_value_as_int(released), 2);
+
+    for value in [result, released, record, name, array] {
+        reml_embed_value_dec_ref(value);
+    }
+    for score in scores {
+        reml_embed_value_dec_ref(score);
+    }
+    assert_eq!(reml_dispose_context(context), RemlEmbedStatus::Ok);
+}
+
+#[test]
+fn missing_or_mismatched_host_functions_are_structured_errors() {
+    let context = create_loaded_context("add");
+    let left = reml_embed_value_int(1);
+
+    let (status, _) = call(context, "add", &[left, left]);
+    assert_eq!(status, RemlEmbedStatus::Error);
+    let error = last_error(context).expect("error recorded");
+    assert_eq!(error["code"], HOST_FUNCTION_MISSING_CODE);
+    assert_eq!(error["severity"], "error");
+
+    let mut calls = 0usize;
+    let user_data = &mut calls as *mut usize as *mut c_void;
+    assert_eq!(
+        register(
+            context,
+            "host_add",
+            "(Int, Str) -> Int",
+            host_add,
+            user_data
+        ),
+        RemlEmbedStatus::Ok
+    );
+    let (status, _) = call(context, "add", &[left, left]);
+    assert_eq!(status, RemlEmbedStatus::Error);
+    assert_eq!(
+        last_error(context).unwrap()["code"],
+        SIGNATURE_MISMATCH_CODE
+    );
+
+    assert_eq!(
+        register(
+            context,
+            "host_add",
+            "(Int, Int) -> Int",
+            host_fail,
+            user_data
+        ),
+        RemlEmbedStatus::Ok
+    );
+    let (status, _) = call(context, "add", &[left, left]);
+    assert_eq!(status, RemlEmbedStatus::Error);
+    assert_eq!(last_error(context).unwrap()["code"], HOST_CALL_FAILED_CODE);
+
+    let flag = reml_embed_value_bool(1);
+    let (status, _) = call(context, "add", &[left, flag]);
+    assert_eq!(status, RemlEmbedStatus::Error);
+    assert_eq!(
+        last_error(context).unwrap()["code"],
+        "native.embed.argument_mismatch"
+    );
+
+    reml_embed_value_dec_ref(left);
+    reml_embed_value_dec_ref(flag);
+    assert_eq!(reml_dispose_context(context), RemlEmbedStatus::Ok);
+}
+
+#[test]
+fn compile_failures_report_diagnostics_with_spans() {
+    install_mock_compiler();
+    let abi = CString::new("0.1.0").unwrap();
+    let mut context = ptr::null_mut();
+    assert_eq!(
+        reml_create_context(abi.as_ptr(), &mut context),
+        RemlEmbedStatus::Ok
+    );
+    let source = "let x = 1";
+    assert_eq!(
+        reml_load_module(context, source.as_ptr(), source.len()),
+        RemlEmbedStatus::Error
+    );
+    let error = last_error(context).expect("error recorded");
+    assert_eq!(error["code"], COMPILE_FAILED_CODE);
+    let cause = &error["diagnostics"][0];
+    assert_eq!(cause["code"], "parser.syntax.expected_tokens");
+    assert_eq!(cause["span"]["start"], 0);
+    assert_eq!(cause["span"]["end"], source.len());
+
+    let main = CString::new("main").unwrap();
+    assert_eq!(reml_run(context, main.as_ptr()), RemlEmbedStatus::Error);
+    assert_eq!(
+        last_error(context).unwrap()["code"],
+        "native.embed.module_not_loaded"
+    );
+    assert_eq!(reml_dispose_context(context), RemlEmbedStatus::Ok);
+}
@@ -0,0 +1,337 @@
+#![cfg(all(target_arch = "x86_64", target_os = "linux"))]
+
+use std::ffi::CString;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use reml_runtime::audit::AuditEnvelope;
+use reml_runtime::ffi::dsl::{
+    bind_library, fn_sig, ptr, register_callback, FfiCallExecutor, FfiErrorKind, FfiField, FfiRepr,
+    FfiStruct, FfiType, FfiValue, DOUBLE, INT,
+};
+use reml_runtime::ffi::dynamic::callback::{
+    reml_ffi_callback_new, reml_ffi_callback_release, reml_ffi_callback_retain,
+};
+use reml_runtime::ffi::dynamic::{
+    orphan_callback_calls, register_callback_with_audit, DynamicFfiExecutor,
+    FFI_CALLBACK_SIGNATURE_UNSUPPORTED_CODE,
+};
+
+/// スロットはプロセス共有のため、解放済みスロットの再利用が他のテストと干渉しないよう直列化する。
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn serial() -> MutexGuard<'static, ()> {
+    SERIAL.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn record(name: &str, fields: &[(&str, FfiType)]) -> FfiType {
+    FfiType::Struct(FfiStruct {
+        name: name.into(),
+        fields: fields
+            .iter()
+            .map(|(name, ty)| FfiField {
+                name: (*name).into(),
+                ty: ty.clone(),
+            })
+            .collect(),
+        repr: FfiRepr::C,
+    })
+}
+
+fn read_i32(value: &FfiValue) -> i32 {
+    let FfiValue::ConstPtr(Some(addr)) = value else {
+        panic!("ポインタ引数であること: {value:?}");
+    };
+    unsafe { *(*addr as *const i32) }
+}
+
+#[test]
+fn qsort_calls_back_into_a_closure() {
+    let _serial = serial();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let element = ptr(FfiType::Void);
+    let compare_sig = fn_sig(
+        vec![
+            FfiType::ConstPtr(Box::new(FfiType::Void)),
+            FfiType::ConstPtr(Box::new(FfiType::Void)),
+        ],
+        INT,
+        false,
+    );
+    // 降順に並べる比較関数。
+    let compare = register_callback("descending", compare_sig.clone(), move |args| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Ok(FfiValue::I32(
+            read_i32(&args[1]).cmp(&read_i32(&args[0])) as i32
+        ))
+    })
+    .unwrap();
+
+    let qsort = bind_library("c")
+        .unwrap()
+        .bind_fn(
+            "qsort",
+            fn_sig(
+                vec![
+                    element,
+                    FfiType::U64,
+                    FfiType::U64,
+                    FfiType::Fn(Box::new(compare_sig)),
+                ],
+                FfiType::Void,
+                false,
+            ),
+        )
+        .unwrap();
+    let mut values = [3i32, 9, -4, 0, 7, 7];
+    DynamicFfiExecutor::new()
+        .call(
+            &qsort,
+            &[
+                FfiValue::Ptr(Some(values.as_mut_ptr() as usize)),
+                FfiValue::U64(values.len() as u64),
+                FfiValue::U64(4),
+                compare.as_value(),
+            ],
+        )
+        .unwrap();
+    assert_eq!(values, [9, 7, 7, 3, 0, -4]);
+    let invocations = calls.load(Ordering::Relaxed) as u64;
+    assert!(invocations > 0);
+    assert_eq!(compare.stats().invocations, invocations);
+    assert_eq!(compare.stats().failures, 0);
+}
+
+#[test]
+fn mismatched_callback_signatures_are_rejected_as_arguments() {
+    let _serial = serial();
+    let callback = register_callback("unit", fn_sig(vec![], FfiType::Void, false), |_| {
+        Ok(FfiValue::Void)
+    })
+    .unwrap();
+    let expects = FfiType::Fn(Box::new(fn_sig(vec![INT], INT, false)));
+    assert!(!callback.as_value().matches_type(&expects));
+}
+
+#[test]
+fn panics_and_errors_are_contained_at_the_boundary() {
+    let _serial = serial();
+    let callback = register_callback("halve", fn_sig(vec![INT], INT, false), |args| {
+        match args[0] {
+            FfiValue::I32(0) => panic!("ゼロは扱えない"),
+            FfiValue::I32(value) if value < 0 => Err(reml_runtime::ffi::dsl::FfiError::new(
+                FfiErrorKind::InvalidArgument,
+                "負の値",
+            )),
+            FfiValue::I32(value) => Ok(FfiValue::I32(value / 2)),
+            _ => unreachable!(),
+        }
+    })
+    .unwrap();
+    let function: extern "C" fn(i32) -> i32 = unsafe { std::mem::transmute(callback.code_ptr()) };
+    assert_eq!(function(42), 21);
+    assert_eq!(function(0), 0);
+    let stats = callback.stats();
+    assert_eq!(stats.failures, 1);
+    assert!(stats.last_failure.unwrap().contains("ゼロは扱えない"));
+    assert_eq!(function(-8), 0);
+    let stats = callback.stats();
+    assert_eq!((stats.invocations, stats.failures), (3, 2));
+    assert_eq!(stats.last_failure.as_deref(), Some("負の値"));
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+struct Triple {
+    a: f64,
+    b: i64,
+    c: f64,
+}
+
+#[test]
+fn struct_and_float_values_cross_in_both_directions() {
+    let _serial = serial();
+    let complex = record("complex", &[("re", DOUBLE), ("im", DOUBLE)]);
+    let polar = register_callback(
+        "polar",
+        fn_sig(vec![DOUBLE, INT, complex.clone()], complex, false),
+        |args| {
+            let (FfiValue::F64(scale), FfiValue::I32(offset), FfiValue::Struct { fields, .. }) =
+                (&args[0], &args[1], &args[2])
+            else {
+                unreachable!();
+            };
+            let (FfiValue::F64(re), FfiValue::F64(im)) = (&fields[0], &fields[1]) else {
+                unreachable!();
+            };
+            Ok(FfiValue::Struct {
+                name: "complex".into(),
+                fields: vec![
+                    FfiValue::F64(re * scale + f64::from(*offset)),
+                    FfiValue::F64(im * scale),
+                ],
+            })
+        },
+    )
+    .unwrap();
+    let function: extern "C" fn(f64, i32, Complex) -> Complex =
+        unsafe { std::mem::transmute(polar.code_ptr()) };
+    assert_eq!(
+        function(2.0, 1, Complex { re: 1.5, im: -3.0 }),
+        Complex { re: 4.0, im: -6.0 }
+    );
+
+    let triple = record(
+        "Triple",
+        &[("a", DOUBLE), ("b", FfiType::I64), ("c", DOUBLE)],
+    );
+    let spread = register_callback(
+        "spread",
+        fn_sig(vec![FfiType::I64, FfiType::F32], triple, false),
+        |args| {
+            let (FfiValue::I64(b), FfiValue::F32(x)) = (&args[0], &args[1]) else {
+                unreachable!();
+            };
+            Ok(FfiValue::Struct {
+                name: "Triple".into(),
+                fields: vec![
+                    FfiValue::F64(f64::from(*x)),
+                    FfiValue::I64(*b),
+                    FfiValue::F64(-f64::from(*x)),
+                ],
+            })
+        },
+    )
+    .unwrap();
+    let function: extern "C" fn(i64, f32) -> Triple =
+        unsafe { std::mem::transmute(spread.code_ptr()) };
+    assert_eq!(
+        function(-5, 0.25),
+        Triple {
+            a: 0.25,
+            b: -5,
+            c: -0.25
+        }
+    );
+}
+
+#[test]
+fn foreign_references_keep_callbacks_alive() {
+    let _serial = serial();
+    let callback = register_callback("twice", fn_sig(vec![INT], INT, false), |args| {
+        let FfiValue::I32(value) = args[0] else {
+            unreachable!();
+        };
+        Ok(FfiValue::I32(value * 2))
+    })
+    .unwrap();
+    let code = callback.retain_for_foreign() as *const c_void;
+    drop(callback);
+    let function: extern "C" fn(i32) -> i32 = unsafe { std::mem::transmute(code) };
+    assert_eq!(function(21), 42);
+
+    assert_eq!(reml_ffi_callback_release(code), 0);
+    assert_eq!(reml_ffi_callback_retain(code), -1);
+    assert_eq!(reml_ffi_callback_release(code), -1);
+    let orphans = orphan_callback_calls();
+    assert_eq!(function(21), 0);
+    assert_eq!(orphan_callback_calls(), orphans + 1);
+}
+
+#[test]
+fn stale_pointers_do_not_reach_later_registrations() {
+    let _serial = serial();
+    let signature = fn_sig(vec![INT], INT, false);
+    let first = register_callback("first", signature.clone(), |_| Ok(FfiValue::I32(1))).unwrap();
+    let stale = first.code_ptr();
+    let slot = first.slot();
+    drop(first);
+
+    // 解放したスロットは未使用のスロットが残っている間は再利用されない。
+    let second = register_callback("second", signature, |_| Ok(FfiValue::I32(2))).unwrap();
+    assert_ne!(second.slot(), slot);
+    assert_ne!(second.code_ptr(), stale);
+
+    let function: extern "C" fn(i32) -> i32 = unsafe { std::mem::transmute(stale) };
+    let orphans = orphan_callback_calls();
+    assert_eq!(function(0), 0);
+    assert_eq!(orphan_callback_calls(), orphans + 1);
+    assert_eq!(second.stats().invocations, 0);
+}
+
+unsafe extern "C" fn add_env(env: *mut c_void, value: i32) -> i32 {
+    *(env as *const i32) + value
+}
+
+#[test]
+fn compiled_closures_receive_their_environment() {
+    let _serial = serial();
+    let mut env = 100i32;
+    let signature =
+        CString::new(serde_json::to_string(&fn_sig(vec![INT], INT, false)).unwrap()).unwrap();
+    let code = reml_ffi_callback_new(
+        &mut env as *mut i32 as *mut c_void,
+        add_env as *const c_void,
+        signature.as_ptr(),
+    );
+    assert!(!code.is_null());
+    let function: extern "C" fn(i32) -> i32 = unsafe { std::mem::transmute(code) };
+    assert_eq!(function(23), 123);
+    assert_eq!(reml_ffi_callback_release(code), 0);
+
+    let invalid = CString::new("{").unwrap();
+    assert!(reml_ffi_callback_new(
+        std::ptr::null_mut(),
+        add_env as *const c_void,
+        invalid.as_ptr()
+    )
+    .is_null());
+}
+
+#[test]
+fn registrations_are_audited_and_unsupported_signatures_fail() {
+    let _serial = serial();
+    let mut envelope = AuditEnvelope::new();
+    let callback = register_callback_with_audit(
+        "on_event",
+        fn_sig(vec![ptr(FfiType::Void)], FfiType::Void, false),
+        |_| Ok(FfiValue::Void),
+        &mut envelope,
+    )
+    .unwrap();
+    let audit = &envelope.metadata["ffi.callback"];
+    assert_eq!(audit["event"], "ffi.callback.register");
+    assert_eq!(audit["name"], "on_event");
+    assert_eq!(audit["status"], "success");
+    assert_eq!(audit["slot"], callback.slot());
+    assert_eq!(audit["unwind"], "contained");
+
+    let mut envelope = AuditEnvelope::new();
+    let err = register_callback_with_audit(
+        "printf_like",
+        fn_sig(vec![ptr(FfiType::U8)], INT, true),
+        |_| Ok(FfiValue::I32(0)),
+        &mut envelope,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.diagnostic_code(),
+        Some(FFI_CALLBACK_SIGNATURE_UNSUPPORTED_CODE)
+    );
+    let audit = &envelope.metadata["ffi.callback"];
+    assert_eq!(audit["status"], "failed");
+    assert_eq!(audit["code"], FFI_CALLBACK_SIGNATURE_UNSUPPORTED_CODE);
+
+    let too_many = fn_sig(vec![FfiType::I64; 7], FfiType::Void, false);
+    let err = register_callback("stack", too_many, |_| Ok(FfiValue::Void)).unwrap_err();
+    assert_eq!(err.kind, FfiErrorKind::InvalidSignature);
+}
@@ -0,0 +1,420 @@
+#![cfg(all(target_arch = "x86_64", target_os = "linux"))]
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+
+use reml_runtime::ffi::dsl::{
+    bind_library, const_ptr, fn_sig, ptr, wrap, FfiCallExecutor, FfiEnum, FfiErrorKind, FfiField,
+    FfiFnSig, FfiIntRepr, FfiRawFn, FfiRepr, FfiStruct, FfiType, FfiValue, FfiVariant, FfiWrapSpec,
+    Ownership, DOUBLE, INT,
+};
+use reml_runtime::ffi::dynamic::{
+    install_dynamic_ffi_executor, trampoline, DynamicFfiExecutor, FFI_LIBRARY_NOT_FOUND_CODE,
+    FFI_RELEASE_OWNERSHIP_VIOLATION_CODE, FFI_SYMBOL_NOT_FOUND_CODE, MAX_STACK_WORDS,
+};
+
+fn bind(library: &str, symbol: &str, sig: FfiFnSig) -> FfiRawFn {
+    bind_library(library)
+        .expect("ライブラリ名が有効であること")
+        .bind_fn(symbol, sig)
+        .expect("シンボルを束縛できること")
+}
+
+fn record(name: &str, fields: &[(&str, FfiType)]) -> FfiType {
+    FfiType::Struct(FfiStruct {
+        name: name.into(),
+        fields: fields
+            .iter()
+            .map(|(name, ty)| FfiField {
+                name: (*name).into(),
+                ty: ty.clone(),
+            })
+            .collect(),
+        repr: FfiRepr::C,
+    })
+}
+
+fn fields(value: FfiValue) -> Vec<FfiValue> {
+    match value {
+        FfiValue::Struct { fields, .. } => fields,
+        other => panic!("構造体が返ること: {other:?}"),
+    }
+}
+
+fn c_string(addr: Option<usize>) -> String {
+    let addr = addr.expect("NULL でないこと");
+    unsafe { CStr::from_ptr(addr as *const c_char) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn calls_libm_with_floating_point_arguments() {
+    let executor = DynamicFfiExecutor::new();
+    let cos = bind("m", "cos", fn_sig(vec![DOUBLE], DOUBLE, false));
+    let FfiValue::F64(value) = executor.call(&cos, &[FfiValue::F64(0.0)]).unwrap() else {
+        panic!("F64 が返ること");
+    };
+    assert_eq!(value, 1.0);
+
+    let pow = bind("libm", "pow", fn_sig(vec![DOUBLE, DOUBLE], DOUBLE, false));
+    let result = executor
+        .call(&pow, &[FfiValue::F64(2.0), FfiValue::F64(10.0)])
+        .unwrap();
+    assert!(matches!(result, FfiValue::F64(value) if value == 1024.0));
+
+    let sqrtf = bind(
+        "m",
+        "sqrtf",
+        fn_sig(vec![FfiType::F32], FfiType::F32, false),
+    );
+    let result = executor.call(&sqrtf, &[FfiValue::F32(6.25)]).unwrap();
+    assert!(matches!(result, FfiValue::F32(value) if value == 2.5));
+}
+
+#[test]
+fn passes_pointers_and_variadic_arguments_to_libc() {
+    let executor = DynamicFfiExecutor::new();
+    let text = CString::new("reml").unwrap();
+    let strlen = bind(
+        "c",
+        "strlen",
+        fn_sig(vec![const_ptr(FfiType::U8)], FfiType::U64, false),
+    );
+    let result = executor
+        .call(&strlen, &[FfiValue::ConstPtr(Some(text.as_ptr() as usize))])
+        .unwrap();
+    assert!(matches!(result, FfiValue::U64(4)));
+
+    let mut buffer = [0u8; 64];
+    let format = CString::new("%d/%s/%.2f/%ld").unwrap();
+    let snprintf = bind(
+        "c",
+        "snprintf",
+        fn_sig(
+            vec![ptr(FfiType::U8), FfiType::U64, const_ptr(FfiType::U8)],
+            INT,
+            true,
+        ),
+    );
+    let result = executor
+        .call(
+            &snprintf,
+            &[
+                FfiValue::Ptr(Some(buffer.as_mut_ptr() as usize)),
+                FfiValue::U64(buffer.len() as u64),
+                FfiValue::ConstPtr(Some(format.as_ptr() as usize)),
+                FfiValue::I8(-7),
+                FfiValue::ConstPtr(Some(text.as_ptr() as usize)),
+                FfiValue::F32(1.25),
+                FfiValue::I64(1 << 40),
+            ],
+        )
+        .unwrap();
+    let written = CStr::from_bytes_until_nul(&buffer)
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert_eq!(written, "-7/reml/1.25/1099511627776");
+    assert!(matches!(result, FfiValue::I32(length) if length as usize == written.len()));
+}
+
+#[test]
+fn returns_and_passes_structs_by_value() {
+    let executor = DynamicFfiExecutor::new();
+    let div_t = record("div_t", &[("quot", INT), ("rem", INT)]);
+    let div = bind("c", "div", fn_sig(vec![INT, INT], div_t, false));
+    let result = executor
+        .call(&div, &[FfiValue::I32(17), FfiValue::I32(5)])
+        .unwrap();
+    assert_eq!(
+        format!("{:?}", fields(result)),
+        format!("{:?}", vec![FfiValue::I32(3), FfiValue::I32(2)])
+    );
+
+    let ldiv_t = record("ldiv_t", &[("quot", FfiType::I64), ("rem", FfiType::I64)]);
+    let ldiv = bind(
+        "c",
+        "ldiv",
+        fn_sig(vec![FfiType::I64, FfiType::I64], ldiv_t, false),
+    );
+    let result = executor
+        .call(
+            &ldiv,
+            &[FfiValue::I64(-(1 << 40) - 3), FfiValue::I64(1 << 20)],
+        )
+        .unwrap();
+    assert_eq!(
+        format!("{:?}", fields(result)),
+        format!("{:?}", vec![FfiValue::I64(-(1 << 20)), FfiValue::I64(-3)])
+    );
+
+    // `double complex` は {double, double} と同じく SSE レジスタ 2 本で受け渡される。
+    let complex = record("complex", &[("re", DOUBLE), ("im", DOUBLE)]);
+    let conj = bind("m", "conj", fn_sig(vec![complex.clone()], complex, false));
+    let value = FfiValue::Struct {
+        name: "complex".into(),
+        fields: vec![FfiValue::F64(3.0), FfiValue::F64(4.0)],
+    };
+    let result = executor.call(&conj, &[value]).unwrap();
+    assert_eq!(
+        format!("{:?}", fields(result)),
+        format!("{:?}", vec![FfiValue::F64(3.0), FfiValue::F64(-4.0)])
+    );
+
+    let in_addr = record("in_addr", &[("s_addr", FfiType::U32)]);
+    let inet_ntoa = bind(
+        "c",
+        "inet_ntoa",
+        fn_sig(vec![in_addr], const_ptr(FfiType::U8), false),
+    );
+    let address = FfiValue::Struct {
+        name: "in_addr".into(),
+        fields: vec![FfiValue::U32(u32::from_ne_bytes([127, 0, 0, 1]))],
+    };
+    let FfiValue::ConstPtr(addr) = executor.call(&inet_ntoa, &[address]).unwrap() else {
+        panic!("ポインタが返ること");
+    };
+    assert_eq!(c_string(addr), "127.0.0.1");
+}
+
+#[test]
+fn enums_use_their_integer_repr() {
+    let executor = DynamicFfiExecutor::new();
+    let sign = FfiType::Enum(FfiEnum {
+        name: "Level".into(),
+        repr: FfiIntRepr::I32,
+        variants: vec![FfiVariant {
+            name: "Low".into(),
+            value: Some(-3),
+        }],
+    });
+    let abs = bind("c", "abs", fn_sig(vec![sign.clone()], sign, false));
+    let result = executor
+        .call(
+            &abs,
+            &[FfiValue::Enum {
+                name: "Level".into(),
+                value: -3,
+            }],
+        )
+        .unwrap();
+    assert!(matches!(result, FfiValue::Enum { ref name, value: 3 } if name == "Level"));
+}
+
+extern "C" fn compare_i32(left: *const c_void, right: *const c_void) -> c_int {
+    let (left, right) = unsafe { (*(left as *const i32), *(right as *const i32)) };
+    left.cmp(&right) as c_int
+}
+
+#[test]
+fn function_pointers_are_passed_as_callbacks() {
+    let executor = DynamicFfiExecutor::new();
+    let comparator = FfiType::Fn(Box::new(fn_sig(
+        vec![const_ptr(FfiType::Void), const_ptr(FfiType::Void)],
+        INT,
+        false,
+    )));
+    let qsort = bind(
+        "c",
+        "qsort",
+        fn_sig(
+            vec![ptr(FfiType::Void), FfiType::U64, FfiType::U64, comparator],
+            FfiType::Void,
+            false,
+        ),
+    );
+    let mut values = [5i32, -1, 9, 0, 3];
+    let result = executor
+        .call(
+            &qsort,
+            &[
+                FfiValue::Ptr(Some(values.as_mut_ptr() as usize)),
+                FfiValue::U64(values.len() as u64),
+                FfiValue::U64(4),
+                FfiValue::FnPtr(compare_i32 as *const () as usize),
+            ],
+        )
+        .unwrap();
+    assert!(matches!(result, FfiValue::Void));
+    assert_eq!(values, [-1, 0, 3, 5, 9]);
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Triple {
+    a: f64,
+    b: i64,
+    c: f64,
+}
+
+extern "C" fn scale_triple(
+    triple: Triple,
+    a: i64,
+    b: i64,
+    c: i64,
+    d: i64,
+    e: i64,
+    f: i64,
+    g: i8,
+) -> Triple {
+    let factor = (a + b + c + d + e + f + i64::from(g)) as f64;
+    Triple {
+        a: triple.a * factor,
+        b: triple.b * factor as i64,
+        c: triple.c * factor,
+    }
+}
+
+#[test]
+fn large_structs_use_memory_and_the_stack() {
+    let triple = record(
+        "Triple",
+        &[("a", DOUBLE), ("b", FfiType::I64), ("c", DOUBLE)],
+    );
+    let mut params = vec![triple.clone()];
+    params.extend(std::iter::repeat_n(FfiType::I64, 6));
+    params.push(FfiType::I8);
+    let sig = fn_sig(params, triple, false);
+    let mut args = vec![FfiValue::Struct {
+        name: "Triple".into(),
+        fields: vec![FfiValue::F64(0.5), FfiValue::I64(3), FfiValue::F64(-1.0)],
+    }];
+    args.extend((1..=6).map(FfiValue::I64));
+    args.push(FfiValue::I8(-1));
+    let code = scale_triple as *const c_void;
+    let result = unsafe { trampoline::call(code, &sig, &args) }.unwrap();
+    assert_eq!(
+        format!("{:?}", fields(result)),
+        format!(
+            "{:?}",
+            vec![FfiValue::F64(10.0), FfiValue::I64(60), FfiValue::F64(-20.0)]
+        )
+    );
+}
+
+#[test]
+fn too_many_stack_arguments_are_rejected_before_the_call() {
+    // 整数レジスタ 6 本を超えた分はスタックに載る。
+    let count = 6 + MAX_STACK_WORDS + 1;
+    let sig = fn_sig(
+        std::iter::repeat_n(FfiType::I64, count).collect(),
+        FfiType::I64,
+        false,
+    );
+    let args: Vec<FfiValue> = (0..count as i64).map(FfiValue::I64).collect();
+    // 上限の判定は呼び出し前に行うため、呼び出し先は実在しなくてよい。
+    let err = unsafe { trampoline::call(std::ptr::null(), &sig, &args) }.unwrap_err();
+    assert_eq!(err.kind, FfiErrorKind::InvalidArgument);
+    assert_eq!(err.diagnostic_code(), Some("ffi.call.invalid_argument"));
+    assert!(err.message.contains("17 語"), "{}", err.message);
+}
+
+#[test]
+fn ownership_controls_how_returned_pointers_are_released() {
+    let executor = DynamicFfiExecutor::new();
+    let source = CString::new("owned text").unwrap();
+    let strdup = bind(
+        "c",
+        "strdup",
+        fn_sig(vec![const_ptr(FfiType::U8)], ptr(FfiType::U8), false),
+    )
+    .with_return_ownership(Ownership::Transferred);
+    let FfiValue::Ptr(addr) = executor
+        .call(
+            &strdup,
+            &[FfiValue::ConstPtr(Some(source.as_ptr() as usize))],
+        )
+        .unwrap()
+    else {
+        panic!("ポインタが返ること");
+    };
+    assert_eq!(c_string(addr), "owned text");
+    let addr = addr.unwrap();
+    assert_eq!(executor.ownership_of(addr), Some(Ownership::Transferred));
+    executor.release_foreign_ptr(addr).unwrap();
+    let err = executor.release_foreign_ptr(addr).unwrap_err();
+    assert_eq!(err.kind, FfiErrorKind::OwnershipViolation);
+    assert_eq!(
+        err.diagnostic_code(),
+        Some(FFI_RELEASE_OWNERSHIP_VIOLATION_CODE)
+    );
+
+    let strchr = bind(
+        "c",
+        "strchr",
+        fn_sig(
+            vec![const_ptr(FfiType::U8), INT],
+            const_ptr(FfiType::U8),
+            false,
+        ),
+    );
+    let FfiValue::ConstPtr(Some(found)) = executor
+        .call(
+            &strchr,
+            &[
+                FfiValue::ConstPtr(Some(source.as_ptr() as usize)),
+                FfiValue::I32(i32::from(b't')),
+            ],
+        )
+        .unwrap()
+    else {
+        panic!("ポインタが返ること");
+    };
+    assert_eq!(executor.ownership_of(found), None);
+    assert!(executor.release_foreign_ptr(found).is_err());
+
+    let stats = executor.ownership_stats();
+    assert_eq!(stats.transferred_results, 1);
+    assert_eq!(stats.borrowed_results, 1);
+    assert_eq!(stats.released, 1);
+}
+
+#[test]
+fn wrapped_functions_run_through_the_installed_executor() {
+    install_dynamic_ffi_executor().expect("このテストバイナリで最初の登録であること");
+    let source = CString::new("wrapped").unwrap();
+    let raw = bind(
+        "c",
+        "strdup",
+        fn_sig(vec![const_ptr(FfiType::U8)], ptr(FfiType::U8), false),
+    );
+    let wrapped = wrap(
+        raw,
+        FfiWrapSpec {
+            name: "dup".into(),
+            null_check: true,
+            ownership: Some(Ownership::Owned),
+            error_map: None,
+        },
+    )
+    .unwrap();
+    let FfiValue::Ptr(addr) = wrapped
+        .call(vec![FfiValue::ConstPtr(Some(source.as_ptr() as usize))])
+        .unwrap()
+    else {
+        panic!("ポインタが返ること");
+    };
+    assert_eq!(c_string(addr), "wrapped");
+    let executor = reml_runtime::ffi::dynamic::dynamic_ffi_executor();
+    assert_eq!(executor.ownership_of(addr.unwrap()), Some(Ownership::Owned));
+    executor.release_foreign_ptr(addr.unwrap()).unwrap();
+}
+
+#[test]
+fn missing_libraries_and_symbols_are_reported() {
+    let executor = DynamicFfiExecutor::new();
+    let missing = bind("reml_no_such_library", "f", fn_sig(vec![], INT, false));
+    let err = executor.call(&missing, &[]).unwrap_err();
+    assert_eq!(err.kind, FfiErrorKind::LibraryNotFound);
+    assert_eq!(err.diagnostic_code(), Some(FFI_LIBRARY_NOT_FOUND_CODE));
+
+    let missing = bind("c", "reml_no_such_symbol", fn_sig(vec![], INT, false));
+    let err = executor.call(&missing, &[]).unwrap_err();
+    assert_eq!(err.kind, FfiErrorKind::SymbolNotFound);
+    assert_eq!(err.diagnostic_code(), Some(FFI_SYMBOL_NOT_FOUND_CODE));
+
+    let abs = bind("c", "abs", fn_sig(vec![INT], INT, false));
+    let err = executor.call(&abs, &[FfiValue::F64(1.0)]).unwrap_err();
+    assert_eq!(err.kind, FfiErrorKind::InvalidArgument);
+}
@@ -1,6 +1,5 @@
 use insta::assert_yaml_snapshot;
 use reml_runtime::capability::contract::CapabilityContractSpan;
-use reml_runtime::config::OptimizeLevel;
 use reml_runtime::config::{
     manifest::{
         BuildProfile, CapabilityId, DslCategory, DslEntry, DslExportRef, DslExportSignature,
@@ -10,6 +9,7 @@ use reml_runtime::config::{
     },
     update_dsl_signature, ProjectStage, SemanticVersion,
 };
+use reml_runtime::config::{IndentStyle, LintLevel, OptimizeLevel};
 use reml_runtime::prelude::ensure::GuardDiagnostic;
 use reml_runtime::stage::{StageId, StageRequirement};
 use serde_json::Value;
@@ -113,6 +113,54 @@ fn manifest_rejects_unknown_profile_optimize() {
     expect_manifest_error("manifest_unknown_profile_optimize", manifest);
 }
 
+#[test]
+fn manifest_rejects_unknown_format_indent_style() {
+    let mut manifest = base_manifest();
+    manifest.format.indent_style = IndentStyle::Unknown("mixed".into());
+    expect_manifest_error("manifest_unknown_format_indent_style", manifest);
+}
+
+#[test]
+fn manifest_rejects_zero_format_line_width() {
+    let mut manifest = base_manifest();
+    manifest.format.line_width = 0;
+    expect_manifest_error("manifest_zero_format_line_width", manifest);
+}
+
+#[test]
+fn manifest_parses_format_section_with_defaults() {
+    let manifest = Manifest::parse_toml(
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[format]\nline_width = 80\nindent_style = \"tab\"\n",
+    )
+    .expect("format セクションを解析できるはず");
+    assert_eq!(manifest.format.line_width, 80);
+    assert_eq!(manifest.format.indent_width, 2);
+    assert_eq!(manifest.format.indent_style, IndentStyle::Tab);
+}
+
+#[test]
+fn manifest_rejects_unknown_lint_level() {
+    let mut manifest = base_manifest();
+    manifest
+        .lint
+        .rules
+        .insert("unused_binding".into(), LintLevel::Unknown("forbid".into()));
+    expect_manifest_error("manifest_unknown_lint_level", manifest);
+}
+
+#[test]
+fn manifest_parses_lint_rules() {
+    let manifest = Manifest::parse_toml(
+        "[project]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[lint.rules]\nunused_binding = \"deny\"\n\"naming_*\" = \"allow\"\n",
+    )
+    .expect("lint セクションを解析できるはず");
+    assert_eq!(
+        manifest.lint.rules.get("unused_binding"),
+        Some(&LintLevel::Deny)
+    );
+    assert_eq!(manifest.lint.rules.get("naming_*"), Some(&LintLevel::Allow));
+}
+
 #[test]
 fn manifest_requires_dsl_entry_path() {
     let mut manifest = base_manifest();
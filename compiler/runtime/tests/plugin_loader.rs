@@ -1,15 +1,24 @@
+use std::fs;
+
+use tempfile::tempdir;
+
 use reml_runtime::{
     capability::{registry::reset_for_tests, CapabilityProvider, CapabilityRegistry},
     config::manifest::{
         CapabilityId, Manifest, PackageName, ProjectKind, ProjectSection, RunCapabilityEntry,
         RunSection, RunTargetSection, SemanticVersion,
     },
-    runtime::plugin::{
-        take_plugin_audit_events, PluginBundleManifest, PluginLoader, PluginSignature,
-        SignatureAlgorithm, VerificationPolicy,
+    runtime::{
+        plugin::{
+            take_plugin_audit_events, PluginBundleManifest, PluginLoadError, PluginLoader,
+            PluginModuleInfo, SignatureStatus, VerificationPolicy,
+        },
+        plugin_signing::{sign_bundle, write_bundle_signature, PluginSigningKey, PluginTrustStore},
     },
 };
 
+const SIGNER_KEY_ID: &str = "release-2026";
+
 fn sample_manifest() -> Manifest {
     let mut manifest = Manifest::default();
     manifest.project = ProjectSection {
@@ -32,6 +41,54 @@ fn sample_manifest() -> Manifest {
     manifest
 }
 
+fn sample_bundle() -> PluginBundleManifest {
+    PluginBundleManifest {
+        bundle_id: "bundle.demo".to_string(),
+        bundle_version: "0.1.0".to_string(),
+        plugins: vec![sample_manifest()],
+        signature: None,
+        bundle_hash: Some("sha256:demo".to_string()),
+        modules: vec![PluginModuleInfo {
+            plugin_id: "plugin.demo".to_string(),
+            module_path: "plugin.wasm".into(),
+            module_hash: "sha256:module".to_string(),
+            execution: Default::default(),
+            abi: Default::default(),
+        }],
+        manifest_paths: vec!["plugin/reml.toml".into()],
+    }
+}
+
+fn signer_key() -> PluginSigningKey {
+    PluginSigningKey::from_seed([42; 32])
+}
+
+fn trusting_loader(configure: impl FnOnce(&mut PluginTrustStore)) -> PluginLoader {
+    let mut trust_store = PluginTrustStore::default();
+    trust_store.trust(signer_key().trusted_signer(SIGNER_KEY_ID));
+    configure(&mut trust_store);
+    PluginLoader::new().with_trust_store(trust_store)
+}
+
+fn signed_bundle(valid_until: &str) -> PluginBundleManifest {
+    let mut bundle = sample_bundle();
+    bundle.signature = Some(sign_bundle(
+        &bundle,
+        &signer_key(),
+        SIGNER_KEY_ID,
+        Some("plugin.demo".to_string()),
+        Some(valid_until.to_string()),
+    ));
+    bundle
+}
+
+fn signature_rejection(loader: &PluginLoader, bundle: PluginBundleManifest) -> String {
+    match loader.register_bundle(bundle, VerificationPolicy::Strict) {
+        Err(PluginLoadError::SignatureInvalid { reason }) => reason,
+        other => panic!("unexpected verification result: {other:?}"),
+    }
+}
+
 #[test]
 fn plugin_loader_registers_manifest_capabilities() {
     let _guard = reml_runtime::test_support::lock();
@@ -75,10 +132,7 @@ fn plugin_bundle_requires_signature_in_strict_mode() {
     let err = loader
         .register_bundle(bundle, VerificationPolicy::Strict)
         .expect_err("strict policy should reject missing signature");
-    assert!(matches!(
-        err,
-        reml_runtime::runtime::plugin::PluginLoadError::SignatureMissing
-    ));
+    assert!(matches!(err, PluginLoadError::SignatureMissing));
 }
 
 #[test]
@@ -86,27 +140,14 @@ fn plugin_bundle_accepts_signature_in_strict_mode() {
     let _guard = reml_runtime::test_support::lock();
     reset_for_tests();
     let _ = take_plugin_audit_events();
-    let loader = PluginLoader::new();
-    let bundle = PluginBundleManifest {
-        bundle_id: "bundle.demo".to_string(),
-        bundle_version: "0.1.0".to_string(),
-        plugins: vec![sample_manifest()],
-        signature: Some(PluginSignature {
-            algorithm: SignatureAlgorithm::Ed25519,
-            certificate: Some("demo-cert".to_string()),
-            issued_to: Some("plugin.demo".to_string()),
-            valid_until: Some("2027-01-01T00:00:00Z".to_string()),
-            bundle_hash: Some("sha256:demo".to_string()),
-        }),
-        bundle_hash: Some("sha256:demo".to_string()),
-        modules: Vec::new(),
-        manifest_paths: Vec::new(),
-    };
+    let loader = trusting_loader(|_| {});
+    let bundle = signed_bundle("2099-01-01T00:00:00Z");
     let registration = loader
         .register_bundle(bundle, VerificationPolicy::Strict)
         .expect("bundle registration should succeed");
     assert_eq!(registration.bundle_id, "bundle.demo");
     assert_eq!(registration.plugins.len(), 1);
+    assert_eq!(registration.signature_status, SignatureStatus::Verified);
 
     let events = take_plugin_audit_events();
     assert_eq!(events.len(), 2);
@@ -130,3 +171,113 @@ fn plugin_bundle_accepts_signature_in_strict_mode() {
         Some("bundle.demo")
     );
 }
+
+#[test]
+fn plugin_bundle_rejects_tampered_or_untrusted_signatures() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let loader = trusting_loader(|_| {});
+
+    let mut tampered = signed_bundle("2099-01-01T00:00:00Z");
+    tampered.modules[0].module_hash = "sha256:other".to_string();
+    assert!(signature_rejection(&loader, tampered).contains("一致しません"));
+
+    let mut relaxed = signed_bundle("2099-01-01T00:00:00Z");
+    relaxed.modules[0].execution.fuel = Some(1);
+    assert!(signature_rejection(&loader, relaxed).contains("一致しません"));
+
+    let untrusted = PluginLoader::new().with_trust_store(PluginTrustStore::default());
+    let reason = signature_rejection(&untrusted, signed_bundle("2099-01-01T00:00:00Z"));
+    assert!(reason.contains("登録されていません"), "{reason}");
+
+    let reason = signature_rejection(&PluginLoader::new(), signed_bundle("2099-01-01T00:00:00Z"));
+    assert!(reason.contains("信頼ストア"), "{reason}");
+    let _ = take_plugin_audit_events();
+}
+
+#[test]
+fn plugin_bundle_enforces_key_revocation_and_expiry() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+
+    let expired_signature = signature_rejection(
+        &trusting_loader(|_| {}),
+        signed_bundle("2020-01-01T00:00:00Z"),
+    );
+    assert!(expired_signature.contains("2020-01-01T00:00:00Z"));
+
+    let revoked = trusting_loader(|store| store.signers[0].revoked = true);
+    let reason = signature_rejection(&revoked, signed_bundle("2099-01-01T00:00:00Z"));
+    assert!(reason.contains("失効"), "{reason}");
+
+    let expired_key = trusting_loader(|store| {
+        store.signers[0].expires_at = Some("2021-06-30T00:00:00Z".to_string())
+    });
+    let reason = signature_rejection(&expired_key, signed_bundle("2099-01-01T00:00:00Z"));
+    assert!(reason.contains("2021-06-30T00:00:00Z"), "{reason}");
+
+    let events = take_plugin_audit_events();
+    assert!(events.iter().all(|event| {
+        event
+            .envelope
+            .metadata
+            .get("plugin.signature.key_id")
+            .and_then(|value| value.as_str())
+            == Some(SIGNER_KEY_ID)
+    }));
+}
+
+#[test]
+fn signed_bundle_file_verifies_after_relocation() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let original = tempdir().expect("tempdir");
+    fs::create_dir_all(original.path().join("plugin")).expect("plugin dir");
+    fs::write(
+        original.path().join("plugin/reml.toml"),
+        "[project]\nname = \"plugin.demo\"\nversion = \"0.1.0\"\nkind = \"plugin\"\n",
+    )
+    .expect("write manifest");
+    fs::write(original.path().join("plugin/plugin.wasm"), b"wasm-demo").expect("write module");
+    let bundle_path = original.path().join("bundle.json");
+    fs::write(
+        &bundle_path,
+        r#"{
+  "bundle_id": "bundle.demo",
+  "bundle_version": "0.1.0",
+  "plugins": [
+    { "manifest_path": "plugin/reml.toml", "module_path": "plugin/plugin.wasm" }
+  ]
+}"#,
+    )
+    .expect("write bundle");
+
+    let loader = trusting_loader(|_| {});
+    let bundle = loader.load_bundle_manifest(&bundle_path).expect("bundle");
+    let signature = sign_bundle(&bundle, &signer_key(), SIGNER_KEY_ID, None, None);
+    write_bundle_signature(&bundle_path, &signature).expect("write signature");
+
+    // 署名はバンドルの置き場所に依存しない。
+    let relocated = tempdir().expect("tempdir");
+    fs::create_dir_all(relocated.path().join("plugin")).expect("plugin dir");
+    for file in ["bundle.json", "plugin/reml.toml", "plugin/plugin.wasm"] {
+        fs::copy(original.path().join(file), relocated.path().join(file)).expect("copy");
+    }
+    let verification = loader
+        .verify_bundle_path(
+            relocated.path().join("bundle.json"),
+            VerificationPolicy::Strict,
+        )
+        .expect("relocated bundle should verify");
+    assert_eq!(verification.signature_status, SignatureStatus::Verified);
+
+    fs::write(relocated.path().join("plugin/plugin.wasm"), b"wasm-evil").expect("tamper");
+    let err = loader
+        .verify_bundle_path(
+            relocated.path().join("bundle.json"),
+            VerificationPolicy::Strict,
+        )
+        .expect_err("tampered module should be rejected");
+    assert!(matches!(err, PluginLoadError::SignatureInvalid { .. }));
+    let _ = take_plugin_audit_events();
+}
@@ -6,11 +6,16 @@ use tempfile::tempdir;
 use reml_runtime::{
     capability::registry::reset_for_tests,
     capability::CapabilityRegistry,
+    config::manifest::load_manifest,
     runtime::{
         bridge::RuntimeBridgeRegistry,
-        plugin::PluginLoader,
+        plugin::{
+            take_plugin_audit_events, PluginAbi, PluginError, PluginExecutionPolicy,
+            PluginInstanceMode, PluginLoader, PluginPayloadEncoding,
+        },
         plugin_bridge::{
-            PluginExecutionBridge, PluginInvokeRequest, PluginLoadRequest, PluginWasmBridge,
+            PluginExecutionBridge, PluginInstance, PluginInvokeRequest, PluginLoadRequest,
+            PluginWasmBridge,
         },
     },
     stage::StageRequirement,
@@ -120,6 +125,9 @@ fn wasm_bridge_loads_bundle_and_invokes() {
             manifest,
             bundle_hash: bundle.bundle_hash.as_deref(),
             module_path: Some(module.module_path.as_path()),
+            module_hash: Some(module.module_hash.as_str()),
+            execution: Some(&module.execution),
+            abi: Some(&module.abi),
         })
         .expect("wasm bridge load should succeed");
 
@@ -159,4 +167,662 @@ fn wasm_bridge_loads_bundle_and_invokes() {
         descriptor.stage(),
         "bridge stage record should match capability stage"
     );
+
+    // 検証後にモジュールを差し替えると、検証時のハッシュと合わず読み込まない。
+    fs::write(
+        &module_path,
+        wat::parse_str(r#"(module (memory (export "memory") 1))"#).expect("valid wat"),
+    )
+    .expect("swap module");
+    let swapped = bridge.load(PluginLoadRequest {
+        manifest,
+        bundle_hash: bundle.bundle_hash.as_deref(),
+        module_path: Some(module.module_path.as_path()),
+        module_hash: Some(module.module_hash.as_str()),
+        execution: Some(&module.execution),
+        abi: Some(&module.abi),
+    });
+    match swapped {
+        Err(PluginError::VerificationFailed { message }) => {
+            assert!(message.contains("changed after verification"), "{message}")
+        }
+        Err(other) => panic!("unexpected error: {other:?}"),
+        Ok(_) => panic!("swapped module should be rejected"),
+    }
+}
+
+fn load_host_plugin(
+    dir: &Path,
+    plugin_section: &str,
+    wat: &str,
+) -> Result<(PluginWasmBridge, PluginInstance), PluginError> {
+    load_limited_plugin(dir, plugin_section, wat, None)
+}
+
+fn load_limited_plugin(
+    dir: &Path,
+    plugin_section: &str,
+    wat: &str,
+    execution: Option<&PluginExecutionPolicy>,
+) -> Result<(PluginWasmBridge, PluginInstance), PluginError> {
+    let wasm = wat::parse_str(wat).expect("valid wat");
+    load_plugin_module(dir, plugin_section, &wasm, execution, None)
+}
+
+fn load_plugin_module(
+    dir: &Path,
+    plugin_section: &str,
+    wasm: &[u8],
+    execution: Option<&PluginExecutionPolicy>,
+    abi: Option<&PluginAbi>,
+) -> Result<(PluginWasmBridge, PluginInstance), PluginError> {
+    let plugin_dir = dir.join("plugin");
+    fs::create_dir_all(&plugin_dir).expect("plugin dir");
+    let manifest_path = plugin_dir.join("reml.toml");
+    fs::write(
+        &manifest_path,
+        format!(
+            r#"
+[project]
+name = "plugin.host"
+version = "0.1.0"
+kind = "plugin"
+
+{plugin_section}
+"#
+        ),
+    )
+    .expect("write manifest");
+    let module_path = plugin_dir.join("plugin.wasm");
+    fs::write(&module_path, wasm).expect("write wasm");
+
+    let manifest = load_manifest(&manifest_path).expect("manifest should load");
+    let bridge = PluginWasmBridge::new();
+    let instance = bridge.load(PluginLoadRequest {
+        manifest: &manifest,
+        bundle_hash: None,
+        module_path: Some(module_path.as_path()),
+        module_hash: None,
+        execution,
+        abi,
+    })?;
+    Ok((bridge, instance))
+}
+
+fn invoke(
+    bridge: &PluginWasmBridge,
+    instance: &PluginInstance,
+    entrypoint: &str,
+) -> Result<Vec<u8>, PluginError> {
+    bridge
+        .invoke(
+            instance,
+            PluginInvokeRequest {
+                entrypoint: entrypoint.to_string(),
+                payload: Vec::new(),
+            },
+        )
+        .map(|response| response.payload)
+}
+
+const HOST_IMPORTS_WAT: &str = r#"(module
+  (import "reml.v1" "log" (func $log (param i32 i32 i32)))
+  (import "reml.v1" "capability_stage" (func $stage (param i32 i32) (result i32)))
+  (import "reml.v1" "config_get" (func $config (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 1024) "started")
+  (data (i32.const 1040) "greeting")
+  (data (i32.const 1056) "io.fs.read")
+  (func (export "plugin.greet") (param i32 i32) (result i32)
+    (call $log (i32.const 2) (i32.const 1024) (i32.const 7))
+    (call $config (i32.const 1040) (i32.const 8) (i32.const 0) (i32.const 64)))
+  (func (export "plugin.stage") (param i32 i32) (result i32)
+    (i32.store8 (i32.const 0) (call $stage (i32.const 1056) (i32.const 10)))
+    (i32.const 1))
+)"#;
+
+#[test]
+fn wasm_bridge_exposes_requested_host_imports() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let _ = take_plugin_audit_events();
+
+    let dir = tempdir().expect("tempdir");
+    let (bridge, instance) = load_host_plugin(
+        dir.path(),
+        r#"
+[plugin]
+imports = ["host.log", "host.capability", "host.config"]
+
+[plugin.config]
+greeting = "hello"
+"#,
+        HOST_IMPORTS_WAT,
+    )
+    .expect("plugin should load");
+
+    assert_eq!(
+        invoke(&bridge, &instance, "plugin.greet").expect("greet"),
+        b"hello"
+    );
+    assert_eq!(
+        invoke(&bridge, &instance, "plugin.stage").expect("stage"),
+        vec![3],
+        "io.fs.read は stable として報告される"
+    );
+
+    let events = take_plugin_audit_events();
+    let log = events
+        .iter()
+        .find(|event| event.envelope.metadata.get("event.kind") == Some(&"plugin.log".into()))
+        .expect("plugin.log event should be recorded");
+    assert_eq!(
+        log.envelope.metadata.get("plugin.id"),
+        Some(&"plugin.host".into())
+    );
+    assert_eq!(
+        log.envelope.metadata.get("plugin.log.level"),
+        Some(&"info".into())
+    );
+    assert_eq!(
+        log.envelope.metadata.get("plugin.log.message"),
+        Some(&"started".into())
+    );
+}
+
+#[test]
+fn wasm_bridge_traps_unrequested_host_imports() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+
+    let dir = tempdir().expect("tempdir");
+    let (bridge, instance) = load_host_plugin(
+        dir.path(),
+        r#"
+[plugin]
+imports = ["host.config"]
+"#,
+        HOST_IMPORTS_WAT,
+    )
+    .expect("plugin should load");
+
+    let err = invoke(&bridge, &instance, "plugin.greet").expect_err("log is not requested");
+    match err {
+        PluginError::ImportDenied { plugin_id, import } => {
+            assert_eq!(plugin_id, "plugin.host");
+            assert_eq!(import, "reml.v1::log");
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn wasm_bridge_rejects_unknown_imports_on_load() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+
+    let dir = tempdir().expect("tempdir");
+    let result = load_host_plugin(
+        dir.path(),
+        "",
+        r#"(module
+  (import "reml.v1" "spawn" (func $spawn (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+)"#,
+    );
+    match result {
+        Err(PluginError::ImportUnresolved { import, .. }) => {
+            assert_eq!(import, "reml.v1::spawn")
+        }
+        Err(other) => panic!("unexpected error: {other:?}"),
+        Ok(_) => panic!("unknown import should be rejected"),
+    }
+}
+
+const WASI_FS_WAT: &str = r#"(module
+  (import "wasi_snapshot_preview1" "path_open"
+    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 1024) "input.txt")
+  (data (i32.const 1040) "output.txt")
+  (func (export "plugin.read") (param i32 i32) (result i32)
+    (if (i32.ne
+          (call $path_open (i32.const 3) (i32.const 0) (i32.const 1024) (i32.const 9)
+            (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 1100))
+          (i32.const 0))
+      (then (return (i32.const 0))))
+    (i32.store (i32.const 1200) (i32.const 0))
+    (i32.store (i32.const 1204) (i32.const 64))
+    (drop (call $fd_read (i32.load (i32.const 1100)) (i32.const 1200) (i32.const 1) (i32.const 1300)))
+    (i32.load (i32.const 1300)))
+  (func (export "plugin.read_huge") (param i32 i32) (result i32)
+    (drop (call $path_open (i32.const 3) (i32.const 0) (i32.const 1024) (i32.const 9)
+      (i32.const 0) (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 1100)))
+    (i32.store (i32.const 1200) (i32.const 2048))
+    (i32.store (i32.const 1204) (i32.const -16))
+    (i32.store8 (i32.const 0)
+      (call $fd_read (i32.load (i32.const 1100)) (i32.const 1200) (i32.const 1) (i32.const 1300)))
+    (i32.const 1))
+  (func (export "plugin.create") (param i32 i32) (result i32)
+    (i32.store8 (i32.const 0)
+      (call $path_open (i32.const 3) (i32.const 0) (i32.const 1040) (i32.const 10)
+        (i32.const 1) (i64.const 64) (i64.const 0) (i32.const 0) (i32.const 1100)))
+    (i32.const 1))
+)"#;
+
+#[test]
+fn wasm_bridge_limits_wasi_fs_to_declared_capabilities() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+
+    let dir = tempdir().expect("tempdir");
+    let data_dir = dir.path().join("plugin").join("data");
+    fs::create_dir_all(&data_dir).expect("data dir");
+    fs::write(data_dir.join("input.txt"), "from host").expect("write input");
+
+    let missing = load_host_plugin(
+        dir.path(),
+        r#"
+[plugin]
+imports = ["wasi.fs"]
+"#,
+        WASI_FS_WAT,
+    );
+    assert!(
+        matches!(missing, Err(PluginError::VerificationFailed { .. })),
+        "wasi.fs は io.fs.read の宣言が必要"
+    );
+
+    let (bridge, instance) = load_host_plugin(
+        dir.path(),
+        r#"
+[plugin]
+imports = ["wasi.fs"]
+capabilities = ["io.fs.read"]
+
+[[plugin.preopens]]
+host = "data"
+guest = "/data"
+"#,
+        WASI_FS_WAT,
+    )
+    .expect("plugin should load");
+
+    assert_eq!(
+        invoke(&bridge, &instance, "plugin.read").expect("read"),
+        b"from host"
+    );
+    assert_eq!(
+        invoke(&bridge, &instance, "plugin.read_huge").expect("read_huge"),
+        vec![21],
+        "線形メモリに収まらない iovec はホストで確保せず EFAULT を返す"
+    );
+    assert_eq!(
+        invoke(&bridge, &instance, "plugin.create").expect("create"),
+        vec![76],
+        "読み取り専用の preopen には ENOTCAPABLE を返す"
+    );
+    assert!(!data_dir.join("output.txt").exists());
+}
+
+const LIMITS_WAT: &str = r#"(module
+  (memory (export "memory") 1)
+  (table 1 funcref)
+  (global $counter (mut i32) (i32.const 0))
+  (func (export "plugin.spin") (param i32 i32) (result i32)
+    (loop $forever (br $forever))
+    (i32.const 0))
+  (func (export "plugin.grow_memory") (param i32 i32) (result i32)
+    (drop (memory.grow (i32.const 4)))
+    (i32.const 0))
+  (func (export "plugin.grow_table") (param i32 i32) (result i32)
+    (drop (table.grow (ref.null func) (i32.const 16)))
+    (i32.const 0))
+  (func (export "plugin.count") (param i32 i32) (result i32)
+    (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+    (i32.store8 (i32.const 0) (global.get $counter))
+    (i32.const 1))
+)"#;
+
+#[test]
+fn wasm_bridge_stops_runaway_plugins_with_fuel_and_timeout() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+    let _ = take_plugin_audit_events();
+
+    let dir = tempdir().expect("tempdir");
+    let fuel = PluginExecutionPolicy {
+        fuel: Some(10_000),
+        ..PluginExecutionPolicy::default()
+    };
+    let (bridge, instance) =
+        load_limited_plugin(dir.path(), "", LIMITS_WAT, Some(&fuel)).expect("plugin should load");
+    let err = invoke(&bridge, &instance, "plugin.spin").expect_err("fuel should run out");
+    assert!(
+        matches!(err, PluginError::FuelExhausted { fuel: 10_000, .. }),
+        "{err:?}"
+    );
+    assert_eq!(
+        invoke(&bridge, &instance, "plugin.count").expect("fuel is re-armed per call"),
+        vec![1]
+    );
+
+    let timeout = PluginExecutionPolicy {
+        timeout_ms: Some(50),
+        ..PluginExecutionPolicy::default()
+    };
+    let (bridge, instance) = load_limited_plugin(dir.path(), "", LIMITS_WAT, Some(&timeout))
+        .expect("plugin should load");
+    let err = invoke(&bridge, &instance, "plugin.spin").expect_err("call should time out");
+    assert!(
+        matches!(err, PluginError::Timeout { timeout_ms: 50, .. }),
+        "{err:?}"
+    );
+
+    let kinds: Vec<_> = take_plugin_audit_events()
+        .into_iter()
+        .filter(|event| {
+            event.envelope.metadata.get("event.kind") == Some(&"plugin.limit_exceeded".into())
+        })
+        .filter_map(|event| {
+            event
+                .envelope
+                .metadata
+                .get("plugin.limit.kind")
+                .and_then(|value| value.as_str().map(str::to_string))
+        })
+        .collect();
+    assert_eq!(kinds, vec!["fuel", "timeout"]);
+}
+
+#[test]
+fn wasm_bridge_reports_memory_and_table_limits() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+
+    let dir = tempdir().expect("tempdir");
+    let policy = PluginExecutionPolicy {
+        max_memory_bytes: Some(2 * 65_536),
+        max_table_elements: Some(8),
+        ..PluginExecutionPolicy::default()
+    };
+    let (bridge, instance) =
+        load_limited_plugin(dir.path(), "", LIMITS_WAT, Some(&policy)).expect("plugin should load");
+
+    match invoke(&bridge, &instance, "plugin.grow_memory") {
+        Err(PluginError::MemoryLimitExceeded {
+            limit_bytes,
+            requested_bytes,
+            ..
+        }) => {
+            assert_eq!(limit_bytes, 2 * 65_536);
+            assert_eq!(requested_bytes, 5 * 65_536);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match invoke(&bridge, &instance, "plugin.grow_table") {
+        Err(PluginError::TableLimitExceeded {
+            limit_elements,
+            requested_elements,
+            ..
+        }) => {
+            assert_eq!(limit_elements, 8);
+            assert_eq!(requested_elements, 17);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn wasm_bridge_keeps_long_lived_instances_between_calls() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+
+    let dir = tempdir().expect("tempdir");
+    let (bridge, instance) =
+        load_limited_plugin(dir.path(), "", LIMITS_WAT, None).expect("plugin should load");
+    assert_eq!(invoke(&bridge, &instance, "plugin.count").unwrap(), vec![1]);
+    assert_eq!(invoke(&bridge, &instance, "plugin.count").unwrap(), vec![1]);
+
+    let long_lived = PluginExecutionPolicy {
+        instance_mode: PluginInstanceMode::LongLived,
+        fuel: Some(10_000),
+        ..PluginExecutionPolicy::default()
+    };
+    let (bridge, instance) = load_limited_plugin(dir.path(), "", LIMITS_WAT, Some(&long_lived))
+        .expect("plugin should load");
+    assert_eq!(invoke(&bridge, &instance, "plugin.count").unwrap(), vec![1]);
+    assert_eq!(invoke(&bridge, &instance, "plugin.count").unwrap(), vec![2]);
+
+    // トラップしたインスタンスは破棄され、次の呼び出しは新しいインスタンスで始まる。
+    assert!(invoke(&bridge, &instance, "plugin.spin").is_err());
+    assert_eq!(invoke(&bridge, &instance, "plugin.count").unwrap(), vec![1]);
+}
+
+#[test]
+fn bundle_execution_and_abi_reach_module_info() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+
+    let dir = tempdir().expect("tempdir");
+    write_plugin_manifest(dir.path());
+    write_wasm_module(dir.path());
+    let bundle_path = dir.path().join("bundle.json");
+    fs::write(
+        &bundle_path,
+        r#"
+{
+  "bundle_id": "bundle.demo",
+  "bundle_version": "0.1.0",
+  "plugins": [
+    {
+      "manifest_path": "plugin/reml.toml",
+      "module_path": "plugin/plugin.wasm",
+      "execution": {
+        "fuel": 1000000,
+        "timeout_ms": 250,
+        "max_memory_bytes": 1048576,
+        "instance_mode": "long_lived",
+        "pool_size": 2
+      },
+      "abi": { "version": 1, "encoding": "msgpack" }
+    }
+  ]
+}
+"#,
+    )
+    .expect("write bundle");
+
+    let bundle = PluginLoader::new()
+        .load_bundle_manifest(&bundle_path)
+        .expect("bundle should load");
+    let module = bundle
+        .module_info_for("plugin.demo")
+        .expect("module info should exist");
+    assert_eq!(
+        module.execution,
+        PluginExecutionPolicy {
+            fuel: Some(1_000_000),
+            timeout_ms: Some(250),
+            max_memory_bytes: Some(1_048_576),
+            max_table_elements: None,
+            instance_mode: PluginInstanceMode::LongLived,
+            pool_size: Some(2),
+        }
+    );
+    assert_eq!(
+        module.abi,
+        PluginAbi {
+            version: 1,
+            encoding: PluginPayloadEncoding::Msgpack,
+        }
+    );
+}
+
+const ABI_V1_WAT: &str = r#"(module
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 4096))
+  (data (i32.const 0) "keep")
+  (func (export "reml_abi_version") (result i32) (i32.const 1))
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+    (local.get $ptr))
+  (func (export "dealloc") (param i32 i32))
+  (func $pack (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len))))
+  (func (export "plugin.echo") (param i32 i32) (result i64)
+    (call $pack (local.get 0) (local.get 1)))
+  (func (export "plugin.sentinel") (param i32 i32) (result i64)
+    (call $pack (i32.const 0) (i32.const 4)))
+  (func (export "plugin.out_of_bounds") (param i32 i32) (result i64)
+    (call $pack (i32.const 65530) (i32.const 16)))
+)"#;
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Greeting {
+    name: String,
+    count: u32,
+}
+
+#[test]
+fn wasm_bridge_passes_payloads_through_guest_allocator() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+
+    let dir = tempdir().expect("tempdir");
+    let wasm = wat::parse_str(ABI_V1_WAT).expect("valid wat");
+    let execution = PluginExecutionPolicy {
+        instance_mode: PluginInstanceMode::LongLived,
+        ..PluginExecutionPolicy::default()
+    };
+    let abi = PluginAbi {
+        version: 1,
+        encoding: PluginPayloadEncoding::Json,
+    };
+    let (bridge, instance) =
+        load_plugin_module(dir.path(), "", &wasm, Some(&execution), Some(&abi))
+            .expect("plugin should load");
+
+    let greeting = Greeting {
+        name: "reml".into(),
+        count: 2,
+    };
+    for encoding in [PluginPayloadEncoding::Json, PluginPayloadEncoding::Msgpack] {
+        let request = PluginInvokeRequest::encoded("plugin.echo", encoding, &greeting)
+            .expect("payload should encode");
+        let response = bridge.invoke(&instance, request).expect("echo");
+        assert_eq!(
+            response.decode::<Greeting>(encoding).expect("decode"),
+            greeting
+        );
+    }
+    // 入力はゲストの確保したバッファへ書かれ、オフセット 0 のゲストデータは残る。
+    assert_eq!(
+        invoke(&bridge, &instance, "plugin.sentinel").expect("sentinel"),
+        b"keep"
+    );
+    assert!(matches!(
+        invoke(&bridge, &instance, "plugin.out_of_bounds"),
+        Err(PluginError::Bridge { .. })
+    ));
+}
+
+#[test]
+fn wasm_bridge_rejects_abi_version_mismatch() {
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+
+    let dir = tempdir().expect("tempdir");
+    let v1 = PluginAbi {
+        version: 1,
+        ..PluginAbi::default()
+    };
+    let legacy = wat::parse_str(LIMITS_WAT).expect("valid wat");
+    let missing_exports = load_plugin_module(dir.path(), "", &legacy, None, Some(&v1));
+    assert!(
+        matches!(missing_exports, Err(PluginError::AbiMismatch { .. })),
+        "v1 には alloc/dealloc/reml_abi_version が必要"
+    );
+
+    let unsupported = PluginAbi {
+        version: 7,
+        ..PluginAbi::default()
+    };
+    let wasm = wat::parse_str(ABI_V1_WAT).expect("valid wat");
+    assert!(matches!(
+        load_plugin_module(dir.path(), "", &wasm, None, Some(&unsupported)),
+        Err(PluginError::AbiMismatch { .. })
+    ));
+
+    // 宣言のないバンドルは版数 0 とみなし、v1 を名乗るモジュールは呼び出し時に拒否する。
+    let (bridge, instance) =
+        load_plugin_module(dir.path(), "", &wasm, None, None).expect("plugin should load");
+    match invoke(&bridge, &instance, "plugin.echo") {
+        Err(PluginError::AbiMismatch { detail, .. }) => {
+            assert!(detail.contains("module reports 1"), "{detail}");
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn wasm_bridge_invokes_reml_wasm_backend_output() {
+    use reml_wasm_backend::{emit_wasm_module_from_mir_json, WasmEmitOptions};
+
+    let _guard = reml_runtime::test_support::lock();
+    reset_for_tests();
+
+    // @export fn greet(input: Str) -> Str = input
+    let dir = tempdir().expect("tempdir");
+    let mir_path = dir.path().join("greet.mir.json");
+    fs::write(
+        &mir_path,
+        r#"{
+  "module": "greet",
+  "functions": [
+    {
+      "name": "greet",
+      "attributes": ["export"],
+      "params": [{"name": "input", "ty": "Str"}],
+      "return_type": "Str",
+      "body": 0,
+      "exprs": [
+        {"id": 0, "ty": "Str", "kind": {"kind": "identifier", "ident": {"name": "input"}}}
+      ]
+    }
+  ]
+}"#,
+    )
+    .expect("write mir");
+    let artifact = emit_wasm_module_from_mir_json(
+        &mir_path,
+        &WasmEmitOptions::new()
+            .with_strict_codegen(true)
+            .with_plugin_abi(true),
+        "greet",
+    )
+    .expect("wasm backend should emit");
+
+    let abi = PluginAbi {
+        version: 1,
+        encoding: PluginPayloadEncoding::Msgpack,
+    };
+    let (bridge, instance) = load_plugin_module(dir.path(), "", &artifact.binary, None, Some(&abi))
+        .expect("backend output should load as a plugin");
+    let greeting = Greeting {
+        name: "from reml".into(),
+        count: 1,
+    };
+    let request = PluginInvokeRequest::encoded("greet", abi.encoding, &greeting)
+        .expect("payload should encode");
+    let response = bridge.invoke(&instance, request).expect("greet");
+    assert_eq!(
+        response.decode::<Greeting>(abi.encoding).expect("decode"),
+        greeting
+    );
 }
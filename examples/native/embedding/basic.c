@@ -29,6 +29,11 @@ int main(void) {
     const char* source = "module Examples.Native.Embedding.Basic\n\nfn main() -> Str { \"embedded ok\" }\n";
     reml_embed_context_t* context = NULL;
 
+    if (reml_embed_install_frontend() != REML_EMBED_STATUS_OK) {
+        fprintf(stderr, "install frontend failed\n");
+        return 1;
+    }
+
     reml_embed_status_t status = reml_create_context(abi_version, &context);
     printf("create=%s\n", status_label(status));
     if (status != REML_EMBED_STATUS_OK) {